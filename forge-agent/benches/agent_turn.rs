@@ -0,0 +1,96 @@
+//! Benchmarks of the agent turn pipeline: the phases `forge-cli --profile`
+//! reports, on a generated workspace.
+//!
+//!   cargo bench -p forge-agent --bench agent_turn
+//!   cargo flamegraph -p forge-agent --bench agent_turn -- --bench repo_map
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use forge_agent::prompt_template::{self, TURN_TEMPLATE};
+use forge_agent::tools::selection::{self, ToolSelection};
+use forge_agent::tools::{self, project_tree, schema, treesitter, Tool, ToolCall};
+
+const MODULES: usize = 40;
+const FUNCTIONS: usize = 25;
+
+fn rust_source(module: usize) -> String {
+    let mut source = format!("//! Module {module}.\n\nuse std::collections::HashMap;\n\n");
+    source.push_str(&format!("pub struct State{module} {{\n    items: HashMap<String, usize>,\n}}\n\n"));
+    source.push_str(&format!("impl State{module} {{\n"));
+    for f in 0..FUNCTIONS {
+        source.push_str(&format!(
+            "    /// Function {f}.\n    pub fn item_{f}(&mut self, key: &str) -> usize {{\n        \
+             let n = self.items.entry(key.to_string()).or_insert({f});\n        *n += 1;\n        *n\n    }}\n\n"
+        ));
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// A workspace of `MODULES` Rust files in a few directories.
+fn workspace() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"bench\"\nedition = \"2021\"\n").unwrap();
+    for module in 0..MODULES {
+        let path = dir.path().join(format!("src/area_{}/module_{module}.rs", module % 5));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, rust_source(module)).unwrap();
+    }
+    dir
+}
+
+fn repo_map(c: &mut Criterion) {
+    let dir = workspace();
+    c.bench_function("repo_map", |b| b.iter(|| project_tree::build(black_box(dir.path()), 2000)));
+}
+
+fn tags(c: &mut Criterion) {
+    let source = rust_source(0);
+    c.bench_function("tags/definitions", |b| b.iter(|| treesitter::parse_definitions(black_box(&source), "rs").unwrap()));
+    c.bench_function("tags/references", |b| b.iter(|| treesitter::parse_references(black_box(&source), "rs").unwrap()));
+}
+
+fn prompt_assembly(c: &mut Criterion) {
+    let dir = workspace();
+    let template = prompt_template::template(TURN_TEMPLATE);
+    let selection = ToolSelection::default();
+    c.bench_function("prompt_assembly", |b| {
+        b.iter(|| {
+            let active = selection.select(tools::definitions(false));
+            let mut vars = HashMap::new();
+            vars.insert("question", "Why does item_3 return 4 on the first call?".to_string());
+            vars.insert("workspace", project_tree::build(dir.path(), 500));
+            vars.insert("rules", prompt_template::workspace_fragments(dir.path()));
+            vars.insert("tools", selection::prompt_section(&selection, &active));
+            let mut body = serde_json::json!({ "question": prompt_template::render(&template, &vars) });
+            selection::apply(&mut body, &selection, &active);
+            black_box(body)
+        })
+    });
+}
+
+fn call(name: &str, arguments: serde_json::Value) -> ToolCall {
+    ToolCall { name: name.to_string(), arguments, thought_signature: None }
+}
+
+fn tool_dispatch(c: &mut Criterion) {
+    let dir = workspace();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let read = call("read_file", serde_json::json!({ "path": "src/area_0/module_0.rs" }));
+    let grep = call("grep", serde_json::json!({ "pattern": "fn item_1\\d", "path": "." }));
+    c.bench_function("tool_dispatch/validate", |b| {
+        b.iter(|| {
+            let tool = Tool::from_name(black_box(&read.name)).unwrap();
+            black_box(tool.is_mutating());
+            schema::validate_call(&read.name, &read.arguments).unwrap();
+        })
+    });
+    let execute = |tool: &ToolCall, root: &Path| runtime.block_on(tools::execute(tool, root, false));
+    c.bench_function("tool_dispatch/read_file", |b| b.iter(|| execute(&read, dir.path())));
+    c.bench_function("tool_dispatch/grep", |b| b.iter(|| execute(&grep, dir.path())));
+}
+
+criterion_group!(benches, repo_map, tags, prompt_assembly, tool_dispatch);
+criterion_main!(benches);
@@ -0,0 +1,238 @@
+//! The standalone agent loop (terminal UI, integration tests): streams chat
+//! turns from a [`ChatApi`], runs the tools they ask for locally and asks the
+//! caller before mutating anything.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::api::ChatApi;
+use crate::forge_search::SseEvent;
+use crate::loop_detection::LoopDetector;
+use crate::profiling;
+use crate::self_correction::{Check, SelfCorrection};
+use crate::session::Session;
+use crate::staging::{self, StagedEdits};
+use crate::tools::selection::{self, ToolSelection};
+use crate::tools::{self, Tool, ToolCall};
+
+/// Something for the UI to show.
+#[derive(Debug)]
+pub enum AgentEvent {
+    Text(String),
+    Thinking(String),
+    ToolStart { name: String, summary: String },
+    ToolEnd { name: String, success: bool },
+    /// The UI answers on the sender (`true` = approved).
+    Approval { summary: String, reply: oneshot::Sender<bool> },
+    Done,
+    Error(String),
+}
+
+/// One-line description of a tool call for transcripts and approvals.
+pub fn describe(name: &str, args: &Value) -> String {
+    let arg = ["command", "path", "pattern", "query", "symbol"]
+        .iter()
+        .find_map(|k| args.get(*k).and_then(|v| v.as_str()));
+    match arg {
+        Some(arg) => format!("{name} {}", arg.chars().take(120).collect::<String>()),
+        None => name.to_string(),
+    }
+}
+
+/// Run one user message to completion, including tool round-trips.
+pub async fn run_turn(
+    api: Arc<dyn ChatApi>,
+    question: String,
+    workspace: PathBuf,
+    conversation_id: String,
+    events: mpsc::UnboundedSender<AgentEvent>,
+) {
+    run(api, question, workspace, conversation_id, events, None).await;
+}
+
+/// Like [`run_turn`], but file edits run without asking and are returned
+/// for review at the end instead.
+pub async fn run_staged_turn(
+    api: Arc<dyn ChatApi>,
+    question: String,
+    workspace: PathBuf,
+    conversation_id: String,
+    events: mpsc::UnboundedSender<AgentEvent>,
+) -> StagedEdits {
+    let mut staged = StagedEdits::new(workspace.clone());
+    run(api, question, workspace, conversation_id, events, Some(&mut staged)).await;
+    staged
+}
+
+async fn run(
+    api: Arc<dyn ChatApi>,
+    question: String,
+    workspace: PathBuf,
+    conversation_id: String,
+    events: mpsc::UnboundedSender<AgentEvent>,
+    mut staged: Option<&mut StagedEdits>,
+) {
+    let workspace_id = crate::forge_search::workspace_id(&workspace);
+    let mut question = Some(question);
+    let mut tool_results: Vec<Value> = Vec::new();
+    let mut self_correction = SelfCorrection::from_config();
+    let mut loop_detector = LoopDetector::new();
+    let assembly = std::time::Instant::now();
+    let tool_selection = ToolSelection::configured();
+    let active_tools = tool_selection.select(crate::tools::definitions(false));
+    profiling::record("prompt assembly", assembly.elapsed());
+    // Files edited in this message, formatted on completion
+    let mut touched: Vec<String> = Vec::new();
+
+    loop {
+        let assembly = std::time::Instant::now();
+        let mut body = json!({
+            "workspace_id": workspace_id,
+            "conversation_id": conversation_id,
+        });
+        if let Some(q) = question.take() {
+            let section = selection::prompt_section(&tool_selection, &active_tools);
+            body["question"] = Value::String(if section.is_empty() { q } else { format!("{q}\n\n[{section}]") });
+        }
+        selection::apply(&mut body, &tool_selection, &active_tools);
+        if !tool_results.is_empty() {
+            body["tool_results"] = Value::Array(std::mem::take(&mut tool_results));
+        }
+        let role = crate::model_routing::ModelRole::for_turn(body.get("question").is_some(), false);
+        crate::model_routing::apply(&mut body, role);
+        profiling::record("prompt assembly", assembly.elapsed());
+
+        let request = std::time::Instant::now();
+        let mut first_event = true;
+        let mut stream = match api.chat_stream(&body).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = events.send(AgentEvent::Error(e.to_string()));
+                return;
+            }
+        };
+        let mut calls = Vec::new();
+        let mut streamed = false;
+        while let Some(event) = stream.next().await {
+            if std::mem::take(&mut first_event) {
+                profiling::record("model: first event", request.elapsed());
+            }
+            match event {
+                SseEvent::TextDelta { text } => {
+                    streamed = true;
+                    let _ = events.send(AgentEvent::Text(text));
+                }
+                SseEvent::Thinking { message, .. } => {
+                    let _ = events.send(AgentEvent::Thinking(message));
+                }
+                SseEvent::ToolStart { tool_name, arguments, .. } => {
+                    let summary = describe(&tool_name, &arguments);
+                    let _ = events.send(AgentEvent::ToolStart { name: tool_name, summary });
+                }
+                SseEvent::ToolEnd { tool_name, success, .. } => {
+                    let _ = events.send(AgentEvent::ToolEnd { name: tool_name, success });
+                }
+                SseEvent::RequiresAction { tool_calls } => calls = tool_calls,
+                SseEvent::Done { answer } => {
+                    if let Some(answer) = answer.filter(|_| !streamed) {
+                        let _ = events.send(AgentEvent::Text(answer));
+                    }
+                    break;
+                }
+                SseEvent::Error { error } => {
+                    let _ = events.send(AgentEvent::Error(error));
+                    return;
+                }
+                SseEvent::Plan { .. } | SseEvent::Usage { .. } => {}
+            }
+        }
+        profiling::record("model: stream", request.elapsed());
+        if calls.is_empty() {
+            let _ = events.send(AgentEvent::Done);
+            return;
+        }
+
+        let mut edited: Vec<(String, String)> = Vec::new();
+        for call in calls {
+            let summary = describe(&call.name, &call.args);
+            let repeated = loop_detector.check_tool_call(&call.name, &call.args.to_string());
+            if repeated.is_loop() {
+                let _ = events.send(AgentEvent::ToolEnd { name: call.name.clone(), success: false });
+                tool_results.push(json!({ "call_id": call.id, "output": repeated.message(), "success": false }));
+                continue;
+            }
+            if call.name == "focus_chain" {
+                let mut session = Session::load(&conversation_id);
+                let result = tools::focus_chain::apply(&mut session, &call.args);
+                if result.success {
+                    session.save();
+                }
+                let _ = events.send(AgentEvent::ToolEnd { name: call.name, success: result.success });
+                tool_results.push(json!({ "call_id": call.id, "output": result.output, "success": result.success }));
+                continue;
+            }
+            let mutating = Tool::from_name(&call.name).map_or(true, |t| t.is_mutating());
+            let staged_edit = staged.is_some() && staging::is_file_edit(&call.name);
+            if mutating && !staged_edit {
+                let (reply, answer) = oneshot::channel();
+                let _ = events.send(AgentEvent::Approval { summary: summary.clone(), reply });
+                if !profiling::timed("approval wait", answer).await.unwrap_or(false) {
+                    tool_results.push(json!({
+                        "call_id": call.id,
+                        "output": "User rejected this command. It was not executed.",
+                        "success": false,
+                    }));
+                    continue;
+                }
+            }
+            let edit_path = matches!(call.name.as_str(), "write_file" | "write_file_range" | "edit_file" | "apply_patch")
+                .then(|| call.args.get("path").and_then(|p| p.as_str()).map(String::from))
+                .flatten();
+            if let Some(path) = &edit_path {
+                profiling::timed("self-correction", self_correction.before_edit(path, &workspace)).await;
+            }
+            if call.name == "attempt_completion" && crate::formatting::enabled() && !touched.is_empty() {
+                let (workspace, paths) = (workspace.clone(), touched.clone());
+                let format = tokio::task::spawn_blocking(move || crate::formatting::format_files(&workspace, &paths));
+                let _ = profiling::timed("format", format).await;
+            }
+            if let Some(staged) = staged.as_deref_mut().filter(|_| staged_edit) {
+                staged.before(&call.name, &call.args);
+            }
+            let _ = events.send(AgentEvent::ToolStart { name: call.name.clone(), summary });
+            let tool_call = ToolCall { name: call.name.clone(), arguments: call.args, thought_signature: None };
+            let result = tools::execute(&tool_call, &workspace, false).await;
+            let (output, success) = if result.output == "PENDING_IDE_EXECUTION" {
+                (format!("{} needs the IDE and is not available in the terminal.", call.name), false)
+            } else {
+                let output = profiling::timed("output masking", crate::output_masking::postprocess(&call.name, &result.output, &workspace)).await;
+                (output, result.success)
+            };
+            let _ = events.send(AgentEvent::ToolEnd { name: call.name, success });
+            if let Some(path) = edit_path.filter(|_| success) {
+                touched.push(path.clone());
+                edited.push((call.id.clone(), path));
+            }
+            tool_results.push(json!({ "call_id": call.id, "output": output, "success": success }));
+        }
+
+        let paths: Vec<String> = edited.iter().map(|(_, p)| p.clone()).collect();
+        match profiling::timed("self-correction", self_correction.after_edits(&paths, &workspace)).await {
+            Check::Clean => {}
+            Check::Retry(message) => {
+                let last_edit = edited.last().map(|(id, _)| id.as_str());
+                if let Some(result) = tool_results.iter_mut().find(|r| r["call_id"].as_str() == last_edit) {
+                    let output = result["output"].as_str().unwrap_or_default().to_string();
+                    result["output"] = Value::String(format!("{output}\n\n{message}"));
+                }
+            }
+            Check::GiveUp(message) => {
+                let _ = events.send(AgentEvent::Text(format!("\n\n{message}")));
+            }
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! Chat turns sent straight to the user's LLM provider, without forge-search.
+//!
+//! The IDE agent uses this backend when `agent_backend = "local"`
+//! (`FORGE_AGENT_BACKEND=local`) or local-only mode is on (see
+//! [`crate::egress`]). It speaks the OpenAI chat-completions protocol, which
+//! OpenAI, Ollama, OpenRouter and Groq serve natively and Gemini and
+//! Anthropic through their OpenAI-compatible endpoints, and turns the
+//! streamed text and tool calls into the same [`SseEvent`]s the cloud sends.
+//!
+//! forge-search keeps conversations on the server; here they are kept in
+//! memory per `conversation_id`, so they last as long as the proxy, or
+//! until the least recently used are dropped to stay within the caps of
+//! [`crate::lru_cache`].
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::{anyhow, Result};
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+
+use super::ChatApi;
+use crate::forge_search::{SseEvent, ToolCallInfo};
+use crate::lru_cache::LruCache;
+
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const OLLAMA_URL: &str = "http://localhost:11434";
+
+const SYSTEM_PROMPT: &str = "You are Forge, a coding agent working in the user's workspace. \
+Use the tools to explore the code before answering, make changes with the editing tools rather \
+than printing whole files (for existing files over 300 lines, `write_file_range` or `edit_file`, \
+never a full `write_file`), and run commands to check your work when that is possible. \
+Keep answers short and refer to code by path and line.";
+
+/// Whether the IDE agent should run against the provider directly.
+pub fn selected() -> bool {
+    crate::egress::local_only()
+        || crate::config::var("FORGE_AGENT_BACKEND").is_some_and(|v| v.trim().eq_ignore_ascii_case("local"))
+}
+
+/// The OpenAI-compatible endpoint of `provider`.
+fn base_url(provider: &str) -> Result<String> {
+    let config = crate::config::active();
+    let url = match provider {
+        "openai" => config.get("openai_base_url").unwrap_or(OPENAI_BASE_URL).to_string(),
+        "ollama" => format!("{}/v1", config.get("ollama_url").unwrap_or(OLLAMA_URL).trim_end_matches('/')),
+        "openrouter" => "https://openrouter.ai/api/v1".to_string(),
+        "groq" => "https://api.groq.com/openai/v1".to_string(),
+        "gemini" => "https://generativelanguage.googleapis.com/v1beta/openai".to_string(),
+        "anthropic" => "https://api.anthropic.com/v1".to_string(),
+        other => return Err(anyhow!("the local agent does not support provider {other:?}")),
+    };
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+/// Histories by conversation; the least recently used are dropped first.
+fn conversations() -> &'static Mutex<LruCache<String, Vec<Value>>> {
+    static CONVERSATIONS: OnceLock<Mutex<LruCache<String, Vec<Value>>>> = OnceLock::new();
+    CONVERSATIONS.get_or_init(|| {
+        Mutex::new(LruCache::new("conversations", 64, 64 << 20, |id, history| {
+            id.len() + history.iter().map(|m| m.to_string().len()).sum::<usize>()
+        }))
+    })
+}
+
+pub struct DirectProvider {
+    provider: String,
+    model: String,
+    api_key: Option<String>,
+    base_url: String,
+}
+
+impl DirectProvider {
+    /// `api_key` from the IDE's settings, else the configured key.
+    pub fn new(provider: &str, model: &str, api_key: Option<String>) -> Result<Self> {
+        if !crate::egress::allows_llm(provider) {
+            return Err(anyhow!("{provider} is blocked in local-only mode (local_only_llm = \"ollama\")"));
+        }
+        let api_key = api_key.filter(|k| !k.trim().is_empty()).or_else(|| crate::secrets::api_key(provider));
+        if api_key.is_none() && provider != "ollama" {
+            return Err(anyhow!("no API key for {provider}: set {}", crate::secrets::env_var(provider)));
+        }
+        Ok(Self { provider: provider.to_string(), model: model.to_string(), api_key, base_url: base_url(provider)? })
+    }
+}
+
+/// The user message for a request: the question, attached files and images.
+fn user_message(body: &Value) -> Option<Value> {
+    let question = body.get("question").and_then(Value::as_str)?;
+    let mut text = question.to_string();
+    for file in body.get("attached_files").and_then(Value::as_array).into_iter().flatten() {
+        let path = file.get("path").and_then(Value::as_str).unwrap_or_default();
+        let content = file.get("content").and_then(Value::as_str).unwrap_or_default();
+        text.push_str(&format!("\n\n[File: {path}]\n```\n{content}\n```"));
+    }
+    let images = body.get("attached_images").and_then(Value::as_array).filter(|i| !i.is_empty());
+    let Some(images) = images else {
+        return Some(json!({"role": "user", "content": text}));
+    };
+    let mut parts = vec![json!({"type": "text", "text": text})];
+    for image in images {
+        let mime = image.get("mime_type").and_then(Value::as_str).unwrap_or("image/png");
+        let data = image.get("data").and_then(Value::as_str).unwrap_or_default();
+        parts.push(json!({"type": "image_url", "image_url": {"url": format!("data:{mime};base64,{data}")}}));
+    }
+    Some(json!({"role": "user", "content": parts}))
+}
+
+/// The conversation with this request's messages appended. `rewind_to_turn`
+/// first drops the user messages from that (0-based) one on.
+fn extend_history(history: &mut Vec<Value>, body: &Value) {
+    if let Some(turn) = body.get("rewind_to_turn").and_then(Value::as_u64) {
+        let cut = history
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m["role"] == "user")
+            .nth(turn as usize)
+            .map_or(history.len(), |(i, _)| i);
+        history.truncate(cut);
+    }
+    for result in body.get("tool_results").and_then(Value::as_array).into_iter().flatten() {
+        history.push(json!({
+            "role": "tool",
+            "tool_call_id": result.get("call_id").cloned().unwrap_or_default(),
+            "content": result.get("output").and_then(Value::as_str).unwrap_or_default(),
+        }));
+    }
+    history.extend(user_message(body));
+}
+
+/// The tools offered: every built-in one (or the `allowed_tools`), plus
+/// `extra_tools`, in the function-calling format.
+fn tools(body: &Value) -> Vec<Value> {
+    let allowed: Option<Vec<&str>> =
+        body.get("allowed_tools").and_then(Value::as_array).map(|a| a.iter().filter_map(Value::as_str).collect());
+    let mut definitions: Vec<Value> = crate::tools::definitions(false)
+        .into_iter()
+        .filter(|d| allowed.as_ref().is_none_or(|a| d["name"].as_str().is_some_and(|n| a.contains(&n))))
+        .collect();
+    for extra in body.get("extra_tools").and_then(Value::as_array).into_iter().flatten() {
+        if !definitions.iter().any(|d| d["name"] == extra["name"]) {
+            definitions.push(extra.clone());
+        }
+    }
+    definitions
+        .into_iter()
+        .map(|d| {
+            json!({"type": "function", "function": {
+                "name": d["name"],
+                "description": d["description"],
+                "parameters": d["parameters"],
+            }})
+        })
+        .collect()
+}
+
+/// A streamed assistant message being put together from chunks.
+#[derive(Default)]
+struct Reply {
+    text: String,
+    /// Tool calls by stream index: id, name, argument JSON so far.
+    calls: BTreeMap<u64, (String, String, String)>,
+}
+
+impl Reply {
+    /// Take in one `chat.completion.chunk`; returns its text, if any.
+    fn feed(&mut self, chunk: &Value) -> Option<String> {
+        let delta = chunk.pointer("/choices/0/delta")?;
+        for call in delta.get("tool_calls").and_then(Value::as_array).into_iter().flatten() {
+            let index = call.get("index").and_then(Value::as_u64).unwrap_or(self.calls.len() as u64);
+            let entry = self.calls.entry(index).or_default();
+            if let Some(id) = call.get("id").and_then(Value::as_str) {
+                entry.0 = id.to_string();
+            }
+            if let Some(name) = call.pointer("/function/name").and_then(Value::as_str) {
+                entry.1.push_str(name);
+            }
+            if let Some(args) = call.pointer("/function/arguments").and_then(Value::as_str) {
+                entry.2.push_str(args);
+            }
+        }
+        let text = delta.get("content").and_then(Value::as_str).filter(|t| !t.is_empty())?;
+        self.text.push_str(text);
+        Some(text.to_string())
+    }
+
+    /// The assistant message for the history, and the tool calls to run.
+    fn finish(self) -> (Value, Vec<ToolCallInfo>) {
+        let calls: Vec<ToolCallInfo> = self
+            .calls
+            .into_iter()
+            .map(|(index, (id, name, args))| ToolCallInfo {
+                id: if id.is_empty() { format!("call_{index}") } else { id },
+                name,
+                args: serde_json::from_str(&args).unwrap_or_else(|_| json!({})),
+            })
+            .collect();
+        let mut message = json!({"role": "assistant", "content": self.text});
+        if !calls.is_empty() {
+            message["tool_calls"] = calls
+                .iter()
+                .map(|c| json!({"id": c.id, "type": "function", "function": {"name": c.name, "arguments": c.args.to_string()}}))
+                .collect();
+        }
+        (message, calls)
+    }
+}
+
+#[async_trait::async_trait]
+impl ChatApi for DirectProvider {
+    async fn chat_stream(&self, body: &Value) -> Result<BoxStream<'static, SseEvent>> {
+        let conversation_id = body.get("conversation_id").and_then(Value::as_str).unwrap_or_default().to_string();
+        let messages = {
+            let mut conversations = conversations().lock().map_err(|_| anyhow!("conversation store poisoned"))?;
+            let history = conversations.get_or_insert_with(conversation_id.clone(), Vec::new);
+            extend_history(history, body);
+            let history = history.clone();
+            conversations.reweigh(&conversation_id);
+            history
+        };
+        let model = body.get("model").and_then(Value::as_str).unwrap_or(&self.model);
+        let mut request = json!({
+            "model": model,
+            "messages": std::iter::once(json!({"role": "system", "content": SYSTEM_PROMPT})).chain(messages).collect::<Vec<_>>(),
+            "stream": true,
+            "stream_options": {"include_usage": true},
+        });
+        let tools = tools(body);
+        if !tools.is_empty() {
+            request["tools"] = Value::Array(tools);
+        }
+
+        let mut req = crate::http::client().post(format!("{}/chat/completions", self.base_url)).json(&request);
+        if let Some(key) = &self.api_key {
+            req = req.bearer_auth(key);
+        }
+        let resp = req.send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(anyhow!("{} error {status}: {}", self.provider, &text[..text.len().min(300)]));
+        }
+
+        let (events, stream) = futures::channel::mpsc::unbounded();
+        tokio::spawn(async move {
+            let mut bytes = resp.bytes_stream();
+            let mut buffer = String::new();
+            let mut reply = Reply::default();
+            while let Some(chunk) = bytes.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        let _ = events.unbounded_send(SseEvent::Error { error: e.to_string() });
+                        return;
+                    }
+                };
+                buffer.push_str(&String::from_utf8_lossy(&chunk));
+                while let Some(end) = buffer.find('\n') {
+                    let line: String = buffer.drain(..=end).collect();
+                    let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else { continue };
+                    if data == "[DONE]" {
+                        continue;
+                    }
+                    let Ok(chunk) = serde_json::from_str::<Value>(data) else { continue };
+                    if let Some(error) = chunk.get("error") {
+                        let _ = events.unbounded_send(SseEvent::Error { error: error.to_string() });
+                        return;
+                    }
+                    if let Some(usage) = chunk.get("usage").filter(|u| u.is_object()) {
+                        let count = |key| usage.get(key).and_then(Value::as_u64).unwrap_or(0);
+                        let _ = events.unbounded_send(SseEvent::Usage {
+                            input_tokens: count("prompt_tokens"),
+                            output_tokens: count("completion_tokens"),
+                        });
+                    }
+                    if let Some(text) = reply.feed(&chunk) {
+                        let _ = events.unbounded_send(SseEvent::TextDelta { text });
+                    }
+                }
+            }
+            let (message, calls) = reply.finish();
+            if let Ok(mut conversations) = conversations().lock() {
+                conversations.get_or_insert_with(conversation_id.clone(), Vec::new).push(message);
+                conversations.reweigh(&conversation_id);
+            }
+            if !calls.is_empty() {
+                let _ = events.unbounded_send(SseEvent::RequiresAction { tool_calls: calls });
+            }
+            let _ = events.unbounded_send(SseEvent::Done { answer: None });
+        });
+        Ok(stream.boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reply_collects_text_and_tool_calls() {
+        let mut reply = Reply::default();
+        let chunks = [
+            json!({"choices": [{"delta": {"content": "Let me look."}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [{"index": 0, "id": "c1", "function": {"name": "read_file", "arguments": "{\"pa"}}]}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [{"index": 0, "function": {"arguments": "th\": \"a.rs\"}"}}]}}]}),
+        ];
+        let text: Vec<String> = chunks.iter().filter_map(|c| reply.feed(c)).collect();
+        assert_eq!(text, ["Let me look."]);
+        let (message, calls) = reply.finish();
+        assert_eq!(calls.len(), 1);
+        assert_eq!((calls[0].id.as_str(), calls[0].name.as_str()), ("c1", "read_file"));
+        assert_eq!(calls[0].args["path"], "a.rs");
+        assert_eq!(message["tool_calls"][0]["function"]["arguments"], "{\"path\":\"a.rs\"}");
+    }
+
+    #[test]
+    fn test_history_tool_results_and_rewind() {
+        let mut history = Vec::new();
+        extend_history(&mut history, &json!({"question": "first"}));
+        history.push(json!({"role": "assistant", "content": "", "tool_calls": []}));
+        extend_history(&mut history, &json!({"tool_results": [{"call_id": "c1", "output": "ok", "success": true}]}));
+        extend_history(&mut history, &json!({"question": "second"}));
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[2]["role"], "tool");
+
+        extend_history(&mut history, &json!({"question": "again", "rewind_to_turn": 1}));
+        let users: Vec<&Value> = history.iter().filter(|m| m["role"] == "user").map(|m| &m["content"]).collect();
+        assert_eq!(users, ["first", "again"]);
+    }
+}
@@ -0,0 +1,56 @@
+//! Scripted chat backend for tests and offline runs.
+//!
+//! Fixtures use the session recording format (see [`crate::replay`]): each
+//! `{"request": ...}` line starts a turn and the `{"event": ...}` lines after
+//! it are what that turn streams. Recorded requests are ignored, so a hand
+//! written fixture can use `{"request": {}}`. Turns are served in order, one
+//! per chat request; the requests received are kept for assertions.
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use serde_json::Value;
+
+use super::ChatApi;
+use crate::forge_search::SseEvent;
+
+pub struct MockProvider {
+    turns: Mutex<VecDeque<Vec<SseEvent>>>,
+    requests: Mutex<Vec<Value>>,
+}
+
+impl MockProvider {
+    pub fn new(turns: Vec<Vec<SseEvent>>) -> Self {
+        Self { turns: Mutex::new(turns.into()), requests: Mutex::new(Vec::new()) }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let turns = crate::replay::load(path)?;
+        Ok(Self::new(turns.into_iter().map(|t| t.events).collect()))
+    }
+
+    /// The request bodies received so far.
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Scripted turns not served yet.
+    pub fn remaining(&self) -> usize {
+        self.turns.lock().unwrap().len()
+    }
+}
+
+#[async_trait::async_trait]
+impl ChatApi for MockProvider {
+    async fn chat_stream(&self, body: &Value) -> Result<BoxStream<'static, SseEvent>> {
+        self.requests.lock().unwrap().push(body.clone());
+        let events = self.turns.lock().unwrap().pop_front().unwrap_or_else(|| {
+            vec![SseEvent::Error { error: "mock provider: no more scripted responses".to_string() }]
+        });
+        Ok(futures_util::stream::iter(events).boxed())
+    }
+}
@@ -0,0 +1,60 @@
+//! The chat backend behind the agent loop.
+//!
+//! Normally forge-search; with `provider = "mock"` in the config the built-in
+//! [`mock::MockProvider`] serves scripted responses from `mock_fixture`
+//! instead, so the agent loop runs without network access.
+//! [`direct::DirectProvider`] talks to the user's LLM provider itself, for
+//! running without the cloud backend.
+
+pub mod direct;
+pub mod mock;
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use serde_json::Value;
+
+use crate::forge_search::{ForgeSearchClient, SseEvent};
+
+/// Streams one chat turn.
+#[async_trait::async_trait]
+pub trait ChatApi: Send + Sync {
+    async fn chat_stream(&self, body: &Value) -> Result<BoxStream<'static, SseEvent>>;
+}
+
+#[async_trait::async_trait]
+impl ChatApi for ForgeSearchClient {
+    async fn chat_stream(&self, body: &Value) -> Result<BoxStream<'static, SseEvent>> {
+        Ok(ForgeSearchClient::chat_stream(self, body).await?.boxed())
+    }
+}
+
+/// The configured chat backend.
+pub fn chat_api() -> Result<Arc<dyn ChatApi>> {
+    let config = crate::config::active();
+    if config.get("provider") == Some("mock") {
+        let fixture = config
+            .get("mock_fixture")
+            .ok_or_else(|| anyhow::anyhow!("provider = \"mock\" needs mock_fixture (a recording or fixture file)"))?;
+        let fixture = shellexpand::tilde(fixture).into_owned();
+        return Ok(Arc::new(mock::MockProvider::from_file(std::path::Path::new(&fixture))?));
+    }
+    Ok(cloud())
+}
+
+/// forge-search, as a [`ChatApi`].
+pub fn cloud() -> Arc<dyn ChatApi> {
+    Arc::new(StaticClient)
+}
+
+/// The shared forge-search client, as an owned [`ChatApi`].
+struct StaticClient;
+
+#[async_trait::async_trait]
+impl ChatApi for StaticClient {
+    async fn chat_stream(&self, body: &Value) -> Result<BoxStream<'static, SseEvent>> {
+        ChatApi::chat_stream(crate::forge_search::client(), body).await
+    }
+}
@@ -0,0 +1,131 @@
+//! How long the agent waits for the user to approve a tool call, and what
+//! it does when nobody answers, per kind of call.
+//!
+//! ```toml
+//! [approvals]
+//! timeout = 300          # seconds; 0 waits until answered
+//! on_timeout = "reject"  # or "approve"
+//!
+//! [approvals.command]    # commands asked about before they run
+//! timeout = 120
+//!
+//! [approvals.edit]       # file edits reviewed after they're made
+//! on_timeout = "approve"
+//! ```
+//!
+//! `delete` (`delete_file`) takes a timeout too but always rejects when it
+//! runs out.
+
+use std::time::Duration;
+
+/// Default wait for an answer.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+/// Most lines of an approval preview.
+pub const MAX_PREVIEW_LINES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalKind {
+    /// A file edit already made, kept or reverted on review.
+    Edit,
+    /// A command or other risky call, run once approved.
+    Command,
+    /// A file deletion.
+    Delete,
+}
+
+impl ApprovalKind {
+    pub fn of(tool_name: &str, is_file_edit: bool) -> Self {
+        match tool_name {
+            "delete_file" => Self::Delete,
+            _ if is_file_edit => Self::Edit,
+            _ => Self::Command,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Edit => "edit",
+            Self::Command => "command",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApprovalPolicy {
+    /// `None` waits until answered.
+    pub timeout: Option<Duration>,
+    /// Whether a call nobody answered is approved.
+    pub approve_on_timeout: bool,
+}
+
+impl ApprovalPolicy {
+    pub fn configured(kind: ApprovalKind) -> Self {
+        let config = crate::config::active();
+        Self::from_settings(kind, |key| config.get(key).map(String::from))
+    }
+
+    fn from_settings(kind: ApprovalKind, get: impl Fn(&str) -> Option<String>) -> Self {
+        let setting = |name: &str| {
+            get(&format!("approvals.{}.{name}", kind.as_str())).or_else(|| get(&format!("approvals.{name}")))
+        };
+        let timeout = match setting("timeout").and_then(|v| v.trim().parse::<u64>().ok()) {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => Some(DEFAULT_TIMEOUT),
+        };
+        let approve_on_timeout = kind != ApprovalKind::Delete
+            && setting("on_timeout").is_some_and(|v| v.trim().eq_ignore_ascii_case("approve"));
+        Self { timeout, approve_on_timeout }
+    }
+}
+
+/// A unified diff of an edit to `path` for reviewing it, capped at
+/// [`MAX_PREVIEW_LINES`].
+pub fn edit_preview(path: &str, before: &str, after: &str) -> String {
+    let diff = similar::TextDiff::from_lines(before, after);
+    let text = diff
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{path}"), &format!("b/{path}"))
+        .to_string();
+    let total = text.lines().count();
+    if total <= MAX_PREVIEW_LINES {
+        return text;
+    }
+    let mut out: String = text.lines().take(MAX_PREVIEW_LINES).flat_map(|l| [l, "\n"]).collect();
+    out.push_str(&format!("… {} more lines\n", total - MAX_PREVIEW_LINES));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_policy_per_kind() {
+        let settings: HashMap<&str, &str> = [
+            ("approvals.timeout", "60"),
+            ("approvals.on_timeout", "approve"),
+            ("approvals.command.timeout", "0"),
+            ("approvals.command.on_timeout", "reject"),
+        ]
+        .into();
+        let get = |key: &str| settings.get(key).map(|v| v.to_string());
+
+        let edit = ApprovalPolicy::from_settings(ApprovalKind::Edit, get);
+        assert_eq!(edit, ApprovalPolicy { timeout: Some(Duration::from_secs(60)), approve_on_timeout: true });
+        let command = ApprovalPolicy::from_settings(ApprovalKind::Command, get);
+        assert_eq!(command, ApprovalPolicy { timeout: None, approve_on_timeout: false });
+        // Deletions never go through on a timeout
+        assert!(!ApprovalPolicy::from_settings(ApprovalKind::Delete, get).approve_on_timeout);
+
+        let default = ApprovalPolicy::from_settings(ApprovalKind::Edit, |_| None);
+        assert_eq!(default.timeout, Some(DEFAULT_TIMEOUT));
+
+        let preview = edit_preview("src/a.rs", "one\ntwo\n", "one\n2\n");
+        assert!(preview.starts_with("--- a/src/a.rs\n+++ b/src/a.rs\n"));
+        assert!(preview.contains("-two\n+2\n"));
+    }
+}
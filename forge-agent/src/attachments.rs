@@ -0,0 +1,139 @@
+//! Files the user drops or pastes into the chat, turned into prompt text.
+//!
+//! PDFs go through `pdftotext` (poppler), CSVs are previewed with their
+//! header and first rows, logs keep their tail (where the error usually is)
+//! and other text is truncated. Binary files are named but not included.
+
+use std::path::Path;
+use std::process::Command;
+
+use base64::Engine;
+
+/// Most characters of one attachment to include.
+pub const MAX_TEXT_CHARS: usize = 40_000;
+/// Data rows of a CSV/TSV shown after its header.
+pub const CSV_PREVIEW_ROWS: usize = 20;
+/// Lines kept from the end of a log file.
+pub const LOG_TAIL_LINES: usize = 300;
+
+/// A `### name` section per attachment; `files` are (filename, base64 data).
+pub fn prompt_section<'a>(files: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    files
+        .into_iter()
+        .map(|(name, data)| {
+            match base64::engine::general_purpose::STANDARD.decode(data) {
+                Ok(bytes) => render(name, &bytes),
+                Err(_) => format!("### {name}\n(could not decode the attachment)"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// `bytes` of the file `name` as a prompt section.
+pub fn render(name: &str, bytes: &[u8]) -> String {
+    let ext = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    if ext == "pdf" {
+        return match pdf_text(bytes) {
+            Ok(text) => format!("### {name} (PDF text)\n{}", truncate(text.trim())),
+            Err(e) => format!("### {name}\n(PDF text could not be extracted: {e})"),
+        };
+    }
+    if bytes.contains(&0) {
+        return format!("### {name}\n(binary file, {} bytes, not included)", bytes.len());
+    }
+    let text = String::from_utf8_lossy(bytes);
+    match ext.as_str() {
+        "csv" | "tsv" => format!("### {name}\n{}", csv_preview(&text)),
+        "log" | "out" | "err" => format!("### {name}\n{}", log_tail(&text)),
+        _ => format!("### {name}\n```\n{}\n```", truncate(text.trim_end())),
+    }
+}
+
+fn pdf_text(bytes: &[u8]) -> Result<String, String> {
+    let path = crate::tools::resources::TempPath::new("attachment", "pdf");
+    std::fs::write(path.path(), bytes).map_err(|e| e.to_string())?;
+    let output = Command::new("pdftotext").arg("-layout").arg(path.path()).arg("-").output();
+    drop(path);
+    match output {
+        Ok(out) if out.status.success() => Ok(String::from_utf8_lossy(&out.stdout).into_owned()),
+        Ok(out) => Err(String::from_utf8_lossy(&out.stderr).trim().to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err("pdftotext is not installed (poppler-utils)".to_string())
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn csv_preview(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    let Some((header, rows)) = lines.split_first() else {
+        return "(empty)".to_string();
+    };
+    let shown = rows.len().min(CSV_PREVIEW_ROWS);
+    let mut out = format!("```csv\n{header}\n");
+    for row in &rows[..shown] {
+        out.push_str(row);
+        out.push('\n');
+    }
+    out.push_str("```");
+    if shown < rows.len() {
+        out.push_str(&format!("\n(first {shown} of {} rows)", rows.len()));
+    }
+    truncate(&out)
+}
+
+fn log_tail(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let skip = lines.len().saturating_sub(LOG_TAIL_LINES);
+    let mut out = String::new();
+    if skip > 0 {
+        out.push_str(&format!("(last {} of {} lines)\n", lines.len() - skip, lines.len()));
+    }
+    out.push_str("```\n");
+    let tail = lines[skip..].join("\n");
+    // Long lines can still make the tail too big: keep its end.
+    let start = tail.len().saturating_sub(MAX_TEXT_CHARS);
+    let start = (start..=tail.len()).find(|&i| tail.is_char_boundary(i)).unwrap_or(0);
+    out.push_str(&tail[start..]);
+    out.push_str("\n```");
+    out
+}
+
+fn truncate(text: &str) -> String {
+    if text.len() <= MAX_TEXT_CHARS {
+        return text.to_string();
+    }
+    let end = (0..=MAX_TEXT_CHARS).rev().find(|&i| text.is_char_boundary(i)).unwrap_or(0);
+    format!("{}\n… (truncated, {} of {} bytes)", &text[..end], end, text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_attachments() {
+        let csv: String = std::iter::once("id,name\n".to_string())
+            .chain((0..30).map(|i| format!("{i},row{i}\n")))
+            .collect();
+        let out = render("data.CSV", csv.as_bytes());
+        assert!(out.contains("id,name\n0,row0"));
+        assert!(out.contains("(first 20 of 30 rows)"));
+        assert!(!out.contains("row25"));
+
+        let log: String = (0..1000).map(|i| format!("line {i}\n")).collect();
+        let out = render("app.log", log.as_bytes());
+        assert!(out.contains("(last 300 of 1000 lines)"));
+        assert!(out.contains("line 999") && !out.contains("line 699\n"));
+
+        assert!(render("blob.bin", &[1, 0, 2]).contains("binary file, 3 bytes"));
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode("hello");
+        assert_eq!(prompt_section([("a.txt", encoded.as_str())]), "### a.txt\n```\nhello\n```");
+    }
+}
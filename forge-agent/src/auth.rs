@@ -0,0 +1,301 @@
+//! OAuth device-code login (RFC 8628) for providers and gateways that offer
+//! it, so users don't have to mint long-lived API keys.
+//!
+//! A provider is configured under `auth.<name>` (see [`crate::config`]):
+//!
+//! ```toml
+//! [auth.gateway]
+//! device_url = "https://login.example.com/oauth/device/code"
+//! token_url = "https://login.example.com/oauth/token"
+//! client_id = "forge-ide"
+//! scope = "openid offline_access"
+//! ```
+//!
+//! [`login`] shows a code for the user to enter in the browser and polls
+//! until they approve. Tokens are kept in the keychain (account
+//! `oauth:<name>`) and [`access_token`] refreshes them shortly before they
+//! expire. [`crate::secrets::api_key`] returns a still-valid access token
+//! for providers without an API key.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Refresh tokens this long before they expire.
+const REFRESH_MARGIN_SECS: i64 = 60;
+
+/// Endpoints of one device-flow provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceFlowProvider {
+    pub name: String,
+    pub device_url: String,
+    pub token_url: String,
+    pub client_id: String,
+    pub scope: Option<String>,
+}
+
+impl DeviceFlowProvider {
+    /// The provider `name` from the active config.
+    pub fn from_config(name: &str) -> Result<Self> {
+        let section = crate::config::active().section(&format!("auth.{name}"));
+        let field = |key: &str| {
+            section
+                .get(key)
+                .cloned()
+                .ok_or_else(|| anyhow!("auth.{name}.{key} is not configured"))
+        };
+        Ok(Self {
+            name: name.to_string(),
+            device_url: field("device_url")?,
+            token_url: field("token_url")?,
+            client_id: field("client_id")?,
+            scope: section.get("scope").cloned(),
+        })
+    }
+}
+
+/// What the user needs to finish logging in.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    pub expires_in: u64,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+/// Tokens as stored in the keychain.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TokenSet {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Unix seconds; `None` when the server gave no lifetime.
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+impl TokenSet {
+    fn is_fresh(&self, now: i64) -> bool {
+        self.expires_at.map_or(true, |at| at - REFRESH_MARGIN_SECS > now)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Outcome of one poll of the token endpoint.
+#[derive(Debug, PartialEq, Eq)]
+enum Poll {
+    Done(TokenSet),
+    Pending,
+    SlowDown,
+    Failed(String),
+}
+
+fn classify(response: TokenResponse, previous_refresh: Option<&str>, now: i64) -> Poll {
+    if let Some(access_token) = response.access_token {
+        return Poll::Done(TokenSet {
+            access_token,
+            // Servers may omit the refresh token on refresh; keep the old one
+            refresh_token: response.refresh_token.or(previous_refresh.map(String::from)),
+            expires_at: response.expires_in.map(|secs| now + secs),
+        });
+    }
+    match response.error.as_deref() {
+        Some("authorization_pending") => Poll::Pending,
+        Some("slow_down") => Poll::SlowDown,
+        Some("expired_token") => Poll::Failed("the code expired, log in again".into()),
+        Some("access_denied") => Poll::Failed("login was denied".into()),
+        Some(other) => Poll::Failed(
+            response.error_description.unwrap_or_else(|| other.to_string()),
+        ),
+        None => Poll::Failed("no token in the response".into()),
+    }
+}
+
+fn keychain_account(provider: &str) -> String {
+    format!("oauth:{provider}")
+}
+
+/// The stored tokens for `provider`.
+pub fn stored_tokens(provider: &str) -> Option<TokenSet> {
+    let json = crate::secrets::stored(&keychain_account(provider))?;
+    serde_json::from_str(&json).ok()
+}
+
+/// The stored access token if it is still valid (no network).
+pub fn cached_access_token(provider: &str) -> Option<String> {
+    stored_tokens(provider)
+        .filter(|t| t.is_fresh(chrono::Utc::now().timestamp()))
+        .map(|t| t.access_token)
+}
+
+fn save_tokens(provider: &str, tokens: &TokenSet) -> Result<()> {
+    crate::secrets::store(&keychain_account(provider), &serde_json::to_string(tokens)?)
+}
+
+/// Ask for a device code.
+pub async fn start(provider: &DeviceFlowProvider) -> Result<DeviceCode> {
+    let mut form = vec![("client_id", provider.client_id.as_str())];
+    if let Some(scope) = &provider.scope {
+        form.push(("scope", scope));
+    }
+    let resp = crate::http::client()
+        .post(&provider.device_url)
+        .form(&form)
+        .send()
+        .await
+        .context("requesting a device code")?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        return Err(anyhow!("device code request failed ({status}): {}", resp.text().await.unwrap_or_default()));
+    }
+    Ok(resp.json().await?)
+}
+
+/// Poll until the user approves `code`, then store the tokens.
+pub async fn finish(provider: &DeviceFlowProvider, code: &DeviceCode) -> Result<TokenSet> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(code.expires_in);
+    let mut interval = code.interval.max(1);
+    while tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        let form = [
+            ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ("device_code", code.device_code.as_str()),
+            ("client_id", provider.client_id.as_str()),
+        ];
+        match token_request(provider, &form, None).await? {
+            Poll::Done(tokens) => {
+                save_tokens(&provider.name, &tokens)?;
+                return Ok(tokens);
+            }
+            Poll::Pending => {}
+            Poll::SlowDown => interval += 5,
+            Poll::Failed(reason) => return Err(anyhow!("{}: {reason}", provider.name)),
+        }
+    }
+    Err(anyhow!("{}: the code expired, log in again", provider.name))
+}
+
+/// Full device flow: `show` gets the code to present to the user.
+pub async fn login(name: &str, show: impl FnOnce(&DeviceCode)) -> Result<TokenSet> {
+    let provider = DeviceFlowProvider::from_config(name)?;
+    let code = start(&provider).await?;
+    show(&code);
+    finish(&provider, &code).await
+}
+
+/// Names of the providers configured under `auth.*`.
+pub fn configured_providers() -> Vec<String> {
+    let mut names: Vec<String> = crate::config::active()
+        .section("auth")
+        .keys()
+        .filter_map(|key| key.split_once('.').map(|(name, _)| name.to_string()))
+        .collect();
+    names.dedup();
+    names
+}
+
+/// Refresh the tokens of every logged-in provider that are about to expire,
+/// so synchronous lookups ([`cached_access_token`]) keep finding them.
+pub async fn refresh_all() {
+    for name in configured_providers() {
+        if stored_tokens(&name).is_none() {
+            continue;
+        }
+        if let Err(e) = access_token(&name).await {
+            tracing::warn!("OAuth refresh failed: {e:#}");
+        }
+    }
+}
+
+/// Forget the tokens for `provider`.
+pub fn logout(provider: &str) -> Result<()> {
+    crate::secrets::delete(&keychain_account(provider))
+}
+
+/// A valid access token for `provider`, refreshing it when it is about to
+/// expire.
+pub async fn access_token(name: &str) -> Result<String> {
+    let tokens = stored_tokens(name).ok_or_else(|| anyhow!("not logged in to {name}"))?;
+    if tokens.is_fresh(chrono::Utc::now().timestamp()) {
+        return Ok(tokens.access_token);
+    }
+    let refresh = tokens
+        .refresh_token
+        .as_deref()
+        .ok_or_else(|| anyhow!("{name} session expired, log in again"))?;
+    let provider = DeviceFlowProvider::from_config(name)?;
+    let form = [
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh),
+        ("client_id", provider.client_id.as_str()),
+    ];
+    match token_request(&provider, &form, Some(refresh)).await? {
+        Poll::Done(fresh) => {
+            save_tokens(name, &fresh)?;
+            Ok(fresh.access_token)
+        }
+        _ => Err(anyhow!("{name} session expired, log in again")),
+    }
+}
+
+async fn token_request(
+    provider: &DeviceFlowProvider,
+    form: &[(&str, &str)],
+    previous_refresh: Option<&str>,
+) -> Result<Poll> {
+    let resp = crate::http::client()
+        .post(&provider.token_url)
+        .header("Accept", "application/json")
+        .form(form)
+        .send()
+        .await
+        .context("contacting the token endpoint")?;
+    let body: TokenResponse = resp.json().await.context("invalid token response")?;
+    Ok(classify(body, previous_refresh, chrono::Utc::now().timestamp()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(json: &str) -> TokenResponse {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_classify_poll() {
+        assert_eq!(classify(response(r#"{"error":"authorization_pending"}"#), None, 0), Poll::Pending);
+        assert_eq!(classify(response(r#"{"error":"slow_down"}"#), None, 0), Poll::SlowDown);
+        assert!(matches!(
+            classify(response(r#"{"error":"invalid_grant","error_description":"bad code"}"#), None, 0),
+            Poll::Failed(reason) if reason == "bad code"
+        ));
+
+        let Poll::Done(tokens) =
+            classify(response(r#"{"access_token":"at","expires_in":3600}"#), Some("rt"), 1000)
+        else {
+            panic!("expected tokens");
+        };
+        assert_eq!(tokens.refresh_token.as_deref(), Some("rt"));
+        assert_eq!(tokens.expires_at, Some(4600));
+        assert!(tokens.is_fresh(4000));
+        assert!(!tokens.is_fresh(4560));
+    }
+}
@@ -4,11 +4,27 @@
 //!   cargo run --release --bin forge-cli -- \
 //!     --workspace /path/to/project \
 //!     "what vision model are we using?"
+//!
+//!   forge-cli chat                            # interactive terminal chat
+//!   forge-cli config list                     # effective settings and their source
+//!   forge-cli config set tool_output_max 20000 --scope workspace
+//!   forge-cli config set search_url https://... --profile work
+//!   forge-cli config use work                 # default profile
+//!   forge-cli models openai                   # models the provider offers
+//!   forge-cli replay ~/.forge/recordings/x.jsonl --workspace fixture  # regression check
+//!   forge-cli login gateway                   # OAuth device login ([auth.gateway] in config)
+//!   forge-cli onboard --output ONBOARDING.md  # explain the repository
+//!   forge-cli --staged "rename Foo to Bar"    # run tools locally, review each edited file at the end
+//!   forge-cli --staged --profile --profile-folded turn.folded "..."  # per-phase timings, flamegraph input
 
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use forge_agent::config::{self, Scope};
+use forge_agent::i18n::tr;
+use forge_agent::profiling::{self, Profile};
 
 // ── ANSI colors ──────────────────────────────────────────────────
 const CYAN: &str = "\x1b[36m";
@@ -21,13 +37,340 @@ const RESET: &str = "\x1b[0m";
 
 #[derive(Parser)]
 #[command(name = "forge-cli", about = "Test the Forge AI agent via forge-search")]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
     /// Workspace directory the agent will operate on
-    #[arg(long, default_value = ".")]
+    #[arg(long, default_value = ".", global = true)]
     workspace: String,
 
     /// The prompt to send to the agent
-    prompt: String,
+    prompt: Option<String>,
+
+    /// Run the agent's tools locally, then show each edited file's diff and ask whether to keep it
+    #[arg(long)]
+    staged: bool,
+
+    /// With --staged, keep every edit without asking
+    #[arg(long, requires = "staged")]
+    yes: bool,
+
+    /// Print how long each phase of the turn took (prompt assembly, model, each tool, approvals)
+    #[arg(long)]
+    profile: bool,
+
+    /// With --profile, also write the timings as folded stacks (inferno, flamegraph.pl)
+    #[arg(long, value_name = "PATH", requires = "profile")]
+    profile_folded: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Interactive terminal chat (multi-line input, @file picker, approvals)
+    Chat,
+    /// Manage ~/.forge/config.toml and the workspace's .forge/config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Log in to a provider configured under [auth.<provider>] with the OAuth device flow
+    Login { provider: String },
+    /// Forget the stored OAuth tokens for a provider
+    Logout { provider: String },
+    /// List the models a provider offers (openai, anthropic, gemini, openrouter, ollama)
+    Models {
+        provider: String,
+        /// Skip the cache
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Re-run a recorded session's tool calls in --workspace and report differences
+    Replay { recording: PathBuf },
+    /// Write an onboarding report of --workspace (layout, entry points, how to run, architecture diagram)
+    Onboard {
+        /// Write it here instead of printing it
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// List or delete the semantic indexes of workspaces indexed from this machine
+    Index {
+        #[command(subcommand)]
+        action: IndexAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum IndexAction {
+    /// Show each indexed workspace, most recently used first
+    List,
+    /// Delete a workspace's index (--workspace's unless an id from `list` is given)
+    Delete { workspace_id: Option<String> },
+    /// Print the files search ranking favours most, with their weights (for tuning rank_* settings)
+    Rank {
+        /// How many files to show
+        #[arg(long, default_value_t = 30)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Show the effective settings and where each comes from
+    List,
+    /// Print one setting
+    Get { key: String },
+    /// Set a value (global unless --scope workspace or --profile is given)
+    Set {
+        key: String,
+        value: String,
+        #[command(flatten)]
+        target: ConfigTarget,
+    },
+    /// Remove a value
+    Unset {
+        key: String,
+        #[command(flatten)]
+        target: ConfigTarget,
+    },
+    /// List the defined profiles
+    Profiles,
+    /// Make a profile the default
+    Use {
+        name: String,
+        /// `global` or `workspace`
+        #[arg(long, default_value = "global")]
+        scope: String,
+    },
+}
+
+#[derive(clap::Args)]
+struct ConfigTarget {
+    /// `global` or `workspace`
+    #[arg(long, default_value = "global")]
+    scope: String,
+    /// Write into `[profiles.<name>]` of the global file
+    #[arg(long)]
+    profile: Option<String>,
+}
+
+async fn run_replay(recording: &std::path::Path, workspace: &std::path::Path) -> anyhow::Result<()> {
+    let turns = forge_agent::replay::load(recording)?;
+    let report = forge_agent::replay::replay(&turns, workspace).await;
+    for d in &report.divergences {
+        println!("{RED}✗{RESET} turn {} {BOLD}{}{RESET} ({})", d.turn + 1, d.tool, d.call_id);
+        println!("{DIM}  expected:{RESET} {}", d.expected.lines().next().unwrap_or_default());
+        println!("{DIM}  actual:  {RESET} {}", d.actual.lines().next().unwrap_or_default());
+    }
+    println!(
+        "{} tool calls replayed, {} skipped, {} diverged",
+        report.replayed,
+        report.skipped,
+        report.divergences.len(),
+    );
+    if !report.divergences.is_empty() {
+        anyhow::bail!("replay diverged from the recording");
+    }
+    Ok(())
+}
+
+fn run_onboard(output: Option<&std::path::Path>, workspace: &std::path::Path) -> anyhow::Result<()> {
+    let markdown = forge_agent::onboarding::build(workspace, Vec::new()).markdown();
+    match output {
+        Some(path) => {
+            std::fs::write(path, markdown)?;
+            eprintln!("{GREEN}Wrote {}{RESET}", path.display());
+        }
+        None => print!("{markdown}"),
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal (no is the default).
+fn confirm(question: &str) -> bool {
+    use std::io::Write;
+    eprint!("{question} [y/N] ");
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok() && matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+fn print_colored_diff(diff: &str) {
+    for line in diff.lines() {
+        let color = if line.starts_with("+++") || line.starts_with("---") {
+            BOLD
+        } else if line.starts_with('+') {
+            GREEN
+        } else if line.starts_with('-') {
+            RED
+        } else if line.starts_with("@@") {
+            CYAN
+        } else {
+            ""
+        };
+        eprintln!("{color}{line}{RESET}");
+    }
+}
+
+/// Run `prompt` with the local agent loop, then review its edits file by file.
+async fn run_staged(prompt: String, workspace: &std::path::Path, keep_all: bool, profile: Arc<Mutex<Profile>>) -> anyhow::Result<()> {
+    use forge_agent::agent_loop::{self, AgentEvent};
+    use std::io::Write;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let turn = tokio::spawn(profiling::scope(
+        profile,
+        agent_loop::run_staged_turn(
+            forge_agent::api::chat_api()?,
+            prompt,
+            workspace.to_path_buf(),
+            uuid::Uuid::new_v4().to_string(),
+            tx,
+        ),
+    ));
+    while let Some(event) = rx.recv().await {
+        match event {
+            AgentEvent::Text(text) => {
+                print!("{text}");
+                let _ = std::io::stdout().flush();
+            }
+            AgentEvent::Thinking(message) => eprintln!("{DIM}{message}{RESET}"),
+            AgentEvent::ToolStart { summary, .. } => eprintln!("{CYAN}[tool]{RESET} {summary}"),
+            AgentEvent::ToolEnd { name, success: false } => eprintln!("{RED}[tool]{RESET} {name} failed"),
+            AgentEvent::ToolEnd { .. } => {}
+            AgentEvent::Approval { summary, reply } => {
+                let _ = reply.send(confirm(&format!("{YELLOW}Allow{RESET} {summary}?")));
+            }
+            AgentEvent::Done => println!(),
+            AgentEvent::Error(error) => eprintln!("{RED}{BOLD}[error]{RESET} {error}"),
+        }
+    }
+    let staged = turn.await?;
+
+    let changes = staged.changes();
+    if changes.is_empty() {
+        return Ok(());
+    }
+    eprintln!("\n{BOLD}{} file(s) changed{RESET}", changes.len());
+    let mut reverted = 0;
+    for change in &changes {
+        eprintln!();
+        print_colored_diff(&change.diff());
+        if keep_all || confirm(&format!("Apply {BOLD}{}{RESET}?", change.path)) {
+            continue;
+        }
+        change.revert(workspace)?;
+        reverted += 1;
+        eprintln!("{DIM}Skipped {}{RESET}", change.path);
+    }
+    eprintln!("{GREEN}Applied {} of {} file(s){RESET}", changes.len() - reverted, changes.len());
+    Ok(())
+}
+
+/// Print the turn's timings and cache use, and write the timings as folded
+/// stacks to `folded`.
+fn report_profile(profile: &Profile, folded: Option<&std::path::Path>) {
+    eprintln!("\n{BOLD}Profile{RESET}\n{}", profile.table());
+    for cache in forge_agent::lru_cache::metrics().iter().filter(|c| c.hits + c.misses > 0) {
+        eprintln!("{DIM}cache {cache}{RESET}");
+    }
+    if let Some(path) = folded {
+        match std::fs::write(path, profile.folded()) {
+            Ok(()) => eprintln!("{DIM}Folded stacks in {} (inferno-flamegraph < {0} > turn.svg){RESET}", path.display()),
+            Err(e) => eprintln!("{RED}Error:{RESET} writing {}: {e}", path.display()),
+        }
+    }
+}
+
+async fn run_index(action: IndexAction, workspace: &std::path::Path) -> anyhow::Result<()> {
+    use forge_agent::{forge_search, index_state};
+    match action {
+        IndexAction::List => {
+            let current = forge_search::workspace_id(workspace);
+            for (id, state) in index_state::list() {
+                let marker = if id == current { "*" } else { " " };
+                let path = state.path.map(|p| p.display().to_string()).unwrap_or_default();
+                println!(
+                    "{marker} {id}  {} files, {} KB  {DIM}{path}{RESET}",
+                    state.files_indexed,
+                    state.size_bytes / 1024
+                );
+            }
+        }
+        IndexAction::Delete { workspace_id } => {
+            let id = workspace_id.unwrap_or_else(|| forge_search::workspace_id(workspace));
+            forge_search::client().clear_index(&id).await?;
+            eprintln!("{GREEN}Deleted{RESET} the index of {id}");
+        }
+        IndexAction::Rank { limit } => {
+            use forge_agent::tools::ranking::{self, PathWeights};
+            let weights = PathWeights::configured();
+            eprintln!(
+                "{DIM}boost {} under [{}], tests -{}, generated -{}{RESET}",
+                weights.boost,
+                weights.boost_dirs.join(", "),
+                weights.test_decay,
+                weights.generated_decay
+            );
+            for (path, score) in ranking::top_files(workspace, &weights, limit) {
+                println!("{score:+.3}  {path}");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn config_scope(scope: &str, profile: Option<String>, workspace: &std::path::Path) -> Scope {
+    match (profile, scope) {
+        (Some(name), _) => Scope::Profile(name),
+        (None, "workspace") => Scope::Workspace(workspace.to_path_buf()),
+        (None, "global") => Scope::Global,
+        (None, other) => {
+            eprintln!("{RED}Error:{RESET} Unknown scope '{other}' (expected global or workspace)");
+            std::process::exit(2);
+        }
+    }
+}
+
+fn run_config(action: ConfigAction, workspace: &std::path::Path) -> anyhow::Result<()> {
+    match action {
+        ConfigAction::List => {
+            let cfg = config::Config::load(Some(workspace));
+            if let Some(profile) = cfg.active_profile() {
+                println!("{DIM}# profile: {profile}{RESET}");
+            }
+            for (key, _, source) in cfg.entries() {
+                let value = cfg.display_value(key).unwrap_or_default();
+                println!("{key} = {value}  {DIM}({source}){RESET}");
+            }
+        }
+        ConfigAction::Get { key } => match config::Config::load(Some(workspace)).display_value(&key) {
+            Some(value) => println!("{value}"),
+            None => std::process::exit(1),
+        },
+        ConfigAction::Set { key, value, target } => {
+            let path = config::set(&config_scope(&target.scope, target.profile, workspace), &key, &value)?;
+            eprintln!("{GREEN}Set{RESET} {key} in {}", path.display());
+        }
+        ConfigAction::Unset { key, target } => {
+            if !config::unset(&config_scope(&target.scope, target.profile, workspace), &key)? {
+                eprintln!("{YELLOW}{key} was not set{RESET}");
+            }
+        }
+        ConfigAction::Profiles => {
+            let cfg = config::Config::load(Some(workspace));
+            for name in cfg.profiles() {
+                let marker = if cfg.active_profile() == Some(name) { "*" } else { " " };
+                println!("{marker} {name}");
+            }
+        }
+        ConfigAction::Use { name, scope } => {
+            let path = config::use_profile(&config_scope(&scope, None, workspace), &name)?;
+            eprintln!("{GREEN}Using profile{RESET} {name} ({})", path.display());
+        }
+    }
+    Ok(())
 }
 
 #[tokio::main]
@@ -48,10 +391,59 @@ async fn main() {
         std::process::exit(1);
     });
 
-    let workspace_id = workspace_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("default");
+    if let Some(command) = cli.command {
+        config::activate(&workspace_path);
+        let result = match command {
+            Command::Config { action } => run_config(action, &workspace_path),
+            Command::Login { provider } => forge_agent::auth::login(&provider, |code| {
+                let url = code.verification_uri_complete.as_ref().unwrap_or(&code.verification_uri);
+                eprintln!("{CYAN}[login]{RESET} Open {BOLD}{url}{RESET} and enter the code {BOLD}{}{RESET}", code.user_code);
+                eprintln!("{DIM}Waiting for approval...{RESET}");
+            })
+            .await
+            .map(|_| eprintln!("{GREEN}Logged in to {provider}{RESET}")),
+            Command::Logout { provider } => forge_agent::auth::logout(&provider)
+                .map(|_| eprintln!("Logged out of {provider}")),
+            Command::Chat => forge_agent::tui::run(workspace_path.clone()).await,
+            Command::Models { provider, refresh } => {
+                let key = forge_agent::secrets::api_key(&provider);
+                let models = if refresh {
+                    forge_agent::models::list_models(&provider, key.as_deref()).await
+                } else {
+                    Ok(forge_agent::models::models(&provider, key.as_deref()).await)
+                };
+                models.map(|models| models.iter().for_each(|m| println!("{m}")))
+            }
+            Command::Replay { recording } => run_replay(&recording, &workspace_path).await,
+            Command::Index { action } => run_index(action, &workspace_path).await,
+            Command::Onboard { output } => run_onboard(output.as_deref(), &workspace_path),
+        };
+        if let Err(e) = result {
+            eprintln!("{RED}Error:{RESET} {e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    let Some(prompt) = cli.prompt else {
+        eprintln!("{RED}Error:{RESET} {}", tr("cli.no_prompt", &[]));
+        std::process::exit(2);
+    };
+    config::activate(&workspace_path);
+
+    let profile = Arc::new(Mutex::new(Profile::new()));
+    if cli.staged {
+        let result = run_staged(prompt, &workspace_path, cli.yes, profile.clone()).await;
+        if cli.profile {
+            report_profile(&profile.lock().unwrap(), cli.profile_folded.as_deref());
+        }
+        if let Err(e) = result {
+            eprintln!("{RED}Error:{RESET} {e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let workspace_id = &forge_agent::forge_search::workspace_id(&workspace_path);
 
     eprintln!(
         "{CYAN}[context]{RESET} Workspace: {} (id: {})",
@@ -63,32 +455,42 @@ async fn main() {
 
     // Check auth
     if !client.is_signed_in().await {
-        eprintln!("{YELLOW}Warning:{RESET} Not signed in to forge-search. Some features may be limited.");
-        eprintln!("{DIM}Sign in at: {}{RESET}", client.login_url());
+        eprintln!("{YELLOW}Warning:{RESET} {}", tr("cli.not_signed_in", &[]));
+        eprintln!("{DIM}{}{RESET}", tr("cli.sign_in_at", &[&client.login_url()]));
     }
 
     // First, trigger indexing
-    eprintln!("{CYAN}[index]{RESET} Syncing workspace with forge-search...");
+    eprintln!("{CYAN}[index]{RESET} {}", tr("cli.indexing", &[]));
     let index_start = Instant::now();
-    match client.scan_directory(workspace_id, &workspace_path).await {
+    let scanned = client.scan_directory(workspace_id, &workspace_path).await;
+    profile.lock().unwrap().add("index", index_start.elapsed());
+    match scanned {
         Ok(result) => {
             eprintln!(
-                "{CYAN}[index]{RESET} Indexed {} files ({} symbols) in {:.1}s",
-                result.files_indexed,
-                result.nodes_created,
-                index_start.elapsed().as_secs_f64(),
+                "{CYAN}[index]{RESET} {}",
+                tr("cli.indexed", &[
+                    &result.files_indexed.to_string(),
+                    &result.nodes_created.to_string(),
+                    &format!("{:.1}", index_start.elapsed().as_secs_f64()),
+                ]),
             );
         }
         Err(e) => {
-            eprintln!("{YELLOW}[index]{RESET} Indexing failed: {e}");
+            eprintln!("{YELLOW}[index]{RESET} {}", tr("cli.index_failed", &[&e.to_string()]));
         }
     }
 
     // Send chat request
-    eprintln!("{CYAN}[chat]{RESET} Sending prompt to forge-search...");
+    eprintln!("{CYAN}[chat]{RESET} {}", tr("cli.sending", &[]));
     let chat_start = Instant::now();
 
-    match client.chat(workspace_id, &cli.prompt, true, true).await {
+    let response = client.chat(workspace_id, &prompt, true, true).await;
+    // The server runs the tools here, so the turn is one phase
+    profile.lock().unwrap().add("chat", chat_start.elapsed());
+    if cli.profile {
+        report_profile(&profile.lock().unwrap(), cli.profile_folded.as_deref());
+    }
+    match response {
         Ok(response) => {
             let answer = response
                 .get("answer")
@@ -96,8 +498,8 @@ async fn main() {
                 .unwrap_or("No response");
 
             eprintln!(
-                "{GREEN}{BOLD}[done]{RESET} Response received in {:.1}s",
-                chat_start.elapsed().as_secs_f64(),
+                "{GREEN}{BOLD}[done]{RESET} {}",
+                tr("cli.done", &[&format!("{:.1}", chat_start.elapsed().as_secs_f64())]),
             );
             eprintln!();
             println!("{}", answer);
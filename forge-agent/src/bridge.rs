@@ -227,6 +227,22 @@ pub trait ProxyBridge: Send + Sync {
     /// Get diff between two refs.
     async fn git_diff(&self, from: &str, to: &str) -> Result<String>;
 
+    // ── Editor ───────────────────────────────────────────────────
+
+    /// Open a file in the editor, with the cursor at `line`/`column`
+    /// (1-based) when given.
+    async fn open_file(&self, path: &Path, line: Option<u32>, column: Option<u32>) -> Result<()>;
+
+    /// Select a file in the file explorer.
+    async fn reveal_in_explorer(&self, path: &Path) -> Result<()>;
+
+    /// Show a file's uncommitted changes in a diff tab.
+    async fn show_diff(&self, path: &Path) -> Result<()>;
+
+    /// Replace the editor diagnostics (squiggles, problems panel) published
+    /// for `path` with `diagnostics`; an empty list clears them.
+    async fn publish_diagnostics(&self, path: &Path, diagnostics: &[LspDiagnostic]) -> Result<()>;
+
     // ── Workspace info ───────────────────────────────────────────
 
     /// Get the workspace root directory.
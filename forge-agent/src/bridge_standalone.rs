@@ -352,6 +352,24 @@ impl ProxyBridge for StandaloneBridge {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
+    // ── Editor ────────────────────────────────────────────────────
+
+    async fn open_file(&self, path: &Path, _line: Option<u32>, _column: Option<u32>) -> Result<()> {
+        anyhow::bail!("no editor attached to open {}", path.display())
+    }
+
+    async fn reveal_in_explorer(&self, path: &Path) -> Result<()> {
+        anyhow::bail!("no editor attached to reveal {}", path.display())
+    }
+
+    async fn show_diff(&self, path: &Path) -> Result<()> {
+        anyhow::bail!("no editor attached to show changes in {}", path.display())
+    }
+
+    async fn publish_diagnostics(&self, path: &Path, _diagnostics: &[LspDiagnostic]) -> Result<()> {
+        anyhow::bail!("no editor attached to show diagnostics for {}", path.display())
+    }
+
     // ── Workspace info ───────────────────────────────────────────
 
     fn workspace_root(&self) -> &Path {
@@ -0,0 +1,243 @@
+//! A summary of what one agent task did, written when the model calls
+//! `attempt_completion`: what changed and why, the files touched, the commits
+//! made and the tests run. Attached to the completion message and usable as
+//! a PR description.
+
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+/// Longest PR title; the completion's first line is cut to this.
+const MAX_TITLE: usize = 72;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl FileChange {
+    /// The net change of `path` from `before` to `after` (empty = absent).
+    pub fn new(path: &str, before: &str, after: &str) -> Self {
+        let kind = match (before.is_empty(), after.is_empty()) {
+            (true, false) => ChangeKind::Added,
+            (false, true) => ChangeKind::Deleted,
+            _ => ChangeKind::Modified,
+        };
+        let diff = similar::TextDiff::from_lines(before, after);
+        let (mut added, mut removed) = (0, 0);
+        for change in diff.iter_all_changes() {
+            match change.tag() {
+                similar::ChangeTag::Insert => added += 1,
+                similar::ChangeTag::Delete => removed += 1,
+                similar::ChangeTag::Equal => {}
+            }
+        }
+        Self { path: path.to_string(), kind, added, removed }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub id: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRun {
+    pub command: String,
+    pub passed: bool,
+}
+
+/// What a task did, collected while it runs.
+#[derive(Debug, Clone, Default)]
+pub struct TaskLog {
+    /// HEAD when the task started, to list the commits it made.
+    base: Option<String>,
+    /// The `result` of `attempt_completion`, once called.
+    completion: Option<String>,
+    tests: Vec<TestRun>,
+}
+
+impl TaskLog {
+    pub fn start(workspace: &Path) -> Self {
+        Self { base: head(workspace), ..Default::default() }
+    }
+
+    /// Note a tool call the model made (server-side or in the IDE).
+    pub fn record_call(&mut self, name: &str, args: &Value) {
+        if name == "attempt_completion" {
+            let result = args.get("result").and_then(|r| r.as_str()).unwrap_or_default();
+            self.completion = Some(result.trim().to_string());
+        }
+    }
+
+    /// Note the outcome of a tool call run in the IDE.
+    pub fn record_result(&mut self, name: &str, args: &Value, success: bool) {
+        self.record_call(name, args);
+        if !matches!(name, "run" | "execute_command" | "execute_background") {
+            return;
+        }
+        let command = args.get("command").and_then(|c| c.as_str()).unwrap_or_default().trim();
+        if is_test_command(command) {
+            self.tests.push(TestRun { command: command.to_string(), passed: success });
+        }
+    }
+
+    pub fn completed(&self) -> bool {
+        self.completion.is_some()
+    }
+
+    /// The summary of a completed task, given its net file changes.
+    pub fn summary(&self, workspace: &Path, files: Vec<FileChange>) -> Option<TaskSummary> {
+        let completion = self.completion.clone()?;
+        let commits = self.base.as_deref().map(|base| commits_since(workspace, base)).unwrap_or_default();
+        Some(TaskSummary { completion, files, commits, tests: self.tests.clone() })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskSummary {
+    /// What the model said it did.
+    pub completion: String,
+    pub files: Vec<FileChange>,
+    pub commits: Vec<Commit>,
+    pub tests: Vec<TestRun>,
+}
+
+impl TaskSummary {
+    /// A PR title: the completion's first line, shortened.
+    pub fn title(&self) -> String {
+        let first = self.completion.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("Agent changes");
+        let first = first.trim_start_matches('#').trim();
+        if first.chars().count() <= MAX_TITLE {
+            return first.to_string();
+        }
+        let cut: String = first.chars().take(MAX_TITLE - 1).collect();
+        format!("{}…", cut.trim_end())
+    }
+
+    /// The PR body, in Markdown.
+    pub fn markdown(&self) -> String {
+        let mut out = String::from("## Summary\n\n");
+        out.push_str(if self.completion.is_empty() { "No description given." } else { &self.completion });
+        out.push('\n');
+
+        if !self.files.is_empty() {
+            let (added, removed) =
+                self.files.iter().fold((0, 0), |(a, r), f| (a + f.added, r + f.removed));
+            out.push_str(&format!(
+                "\n## Changes\n\n{} file{} changed, +{added} −{removed}\n\n",
+                self.files.len(),
+                if self.files.len() == 1 { "" } else { "s" }
+            ));
+            for file in &self.files {
+                let kind = match file.kind {
+                    ChangeKind::Added => "added",
+                    ChangeKind::Modified => "modified",
+                    ChangeKind::Deleted => "deleted",
+                };
+                out.push_str(&format!("- `{}` — {kind} (+{} −{})\n", file.path, file.added, file.removed));
+            }
+        }
+
+        if !self.commits.is_empty() {
+            out.push_str("\n## Commits\n\n");
+            for commit in &self.commits {
+                out.push_str(&format!("- `{}` {}\n", commit.id, commit.subject));
+            }
+        }
+
+        out.push_str("\n## Tests\n\n");
+        if self.tests.is_empty() {
+            out.push_str("No tests were run.\n");
+        }
+        for test in &self.tests {
+            let outcome = if test.passed { "passed" } else { "failed" };
+            out.push_str(&format!("- `{}` {outcome}\n", test.command));
+        }
+        out
+    }
+}
+
+/// Whether `command` runs a test suite.
+pub fn is_test_command(command: &str) -> bool {
+    const RUNNERS: &[&str] = &[
+        "cargo test", "cargo nextest", "npm test", "npm run test", "yarn test", "pnpm test",
+        "bun test", "pytest", "python -m pytest", "python3 -m pytest", "go test", "jest",
+        "npx jest", "vitest", "npx vitest", "mvn test", "gradle test", "./gradlew test",
+        "dotnet test", "rspec", "bundle exec rspec", "mix test", "ctest", "swift test",
+    ];
+    command
+        .split(&['&', ';', '|'][..])
+        .map(str::trim)
+        .any(|part| RUNNERS.iter().any(|r| part == *r || part.starts_with(&format!("{r} "))))
+}
+
+fn head(workspace: &Path) -> Option<String> {
+    let output = Command::new("git").arg("rev-parse").arg("HEAD").current_dir(workspace).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn commits_since(workspace: &Path, base: &str) -> Vec<Commit> {
+    let Ok(output) = Command::new("git")
+        .args(["log", "--reverse", "--format=%h%x09%s", &format!("{base}..HEAD")])
+        .current_dir(workspace)
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (id, subject) = line.split_once('\t')?;
+            Some(Commit { id: id.to_string(), subject: subject.to_string() })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_task_summary_markdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = TaskLog::start(dir.path());
+        log.record_result("run", &json!({ "command": "cargo build && cargo test -p core" }), true);
+        log.record_result("run", &json!({ "command": "ls -la" }), true);
+        assert!(log.summary(dir.path(), Vec::new()).is_none());
+        log.record_call("attempt_completion", &json!({ "result": "Fix the parser's handling of empty input\n\nIt returned None." }));
+        assert!(log.completed());
+
+        let files = vec![
+            FileChange::new("src/parser.rs", "a\nb\n", "a\nc\nd\n"),
+            FileChange::new("tests/empty.rs", "", "test\n"),
+        ];
+        assert_eq!(files[0].kind, ChangeKind::Modified);
+        assert_eq!((files[0].added, files[0].removed), (2, 1));
+        assert_eq!(files[1].kind, ChangeKind::Added);
+
+        let summary = log.summary(dir.path(), files).unwrap();
+        assert_eq!(summary.title(), "Fix the parser's handling of empty input");
+        assert!(summary.commits.is_empty());
+        let body = summary.markdown();
+        assert!(body.contains("It returned None."));
+        assert!(body.contains("2 files changed, +3 −1"));
+        assert!(body.contains("- `src/parser.rs` — modified (+2 −1)"));
+        assert!(body.contains("- `cargo build && cargo test -p core` passed"));
+        assert!(!body.contains("ls -la"));
+        assert!(!body.contains("## Commits"));
+    }
+}
@@ -0,0 +1,184 @@
+//! Edit checkpoints for "retry from here".
+//!
+//! Before the agent first touches a file in a turn, its content (or the fact
+//! that it didn't exist) is saved under
+//! `~/.forge/checkpoints/<conversation_id>/turn-<n>.json`. Restoring turn `n`
+//! puts every file edited in turn `n` or later back the way it was when that
+//! turn started and drops those checkpoints, so the conversation can be
+//! truncated there and the instruction sent again.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+pub struct Checkpoints {
+    dir: PathBuf,
+}
+
+impl Checkpoints {
+    /// Checkpoints of `conversation_id` in the default location.
+    pub fn for_conversation(conversation_id: &str) -> Option<Self> {
+        let root = dirs::home_dir()?.join(".forge").join("checkpoints");
+        Some(Self::in_dir(root.join(sanitize(conversation_id))))
+    }
+
+    pub fn in_dir(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn turn_file(&self, turn: u32) -> PathBuf {
+        self.dir.join(format!("turn-{turn}.json"))
+    }
+
+    fn load(&self, turn: u32) -> BTreeMap<String, Option<String>> {
+        crate::encryption::read_to_string(&self.turn_file(turn))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save `path` (relative to `workspace`) as it is before its first edit
+    /// in `turn`; later edits in the same turn keep the first copy.
+    pub fn record(&self, turn: u32, workspace: &Path, path: &str) -> Result<()> {
+        self.record_with(turn, path, || crate::tools::buffers::read_to_string(&workspace.join(path)).ok())
+    }
+
+    /// [`Self::record`] for a change that already happened, e.g. by a
+    /// command: `before` is the old content, `None` if the file didn't exist.
+    pub fn record_content(&self, turn: u32, path: &str, before: Option<String>) -> Result<()> {
+        self.record_with(turn, path, || before)
+    }
+
+    fn record_with(&self, turn: u32, path: &str, before: impl FnOnce() -> Option<String>) -> Result<()> {
+        let mut files = self.load(turn);
+        if files.contains_key(path) {
+            return Ok(());
+        }
+        files.insert(path.to_string(), before());
+        std::fs::create_dir_all(&self.dir).with_context(|| format!("creating {}", self.dir.display()))?;
+        crate::encryption::write(&self.turn_file(turn), &serde_json::to_string(&files)?)?;
+        Ok(())
+    }
+
+    /// Turns that have a checkpoint, ascending.
+    pub fn turns(&self) -> Vec<u32> {
+        let mut turns: Vec<u32> = std::fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                name.strip_prefix("turn-")?.strip_suffix(".json")?.parse().ok()
+            })
+            .collect();
+        turns.sort_unstable();
+        turns
+    }
+
+    /// Put files edited in `turn` or later back to their state at the start
+    /// of `turn` and forget those checkpoints. Returns the restored paths.
+    pub fn restore(&self, turn: u32, workspace: &Path) -> Result<Vec<String>> {
+        let later: Vec<u32> = self.turns().into_iter().filter(|&t| t >= turn).collect();
+        // The earliest copy of each file is its state when `turn` started
+        let mut originals: BTreeMap<String, Option<String>> = BTreeMap::new();
+        for &t in &later {
+            for (path, content) in self.load(t) {
+                originals.entry(path).or_insert(content);
+            }
+        }
+        for (path, content) in &originals {
+            let full = workspace.join(path);
+            match content {
+                Some(content) => {
+                    if let Some(parent) = full.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    crate::tools::buffers::write(&full, content).with_context(|| format!("restoring {path}"))?;
+                }
+                None if full.exists() => {
+                    std::fs::remove_file(&full).with_context(|| format!("removing {path}"))?;
+                }
+                None => {}
+            }
+        }
+        for t in later {
+            let _ = std::fs::remove_file(self.turn_file(t));
+        }
+        Ok(originals.into_keys().collect())
+    }
+
+    /// Drop all checkpoints of the conversation.
+    pub fn clear(&self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Workspace-relative paths a file tool call is about to change.
+pub fn paths_touched(tool: &str, args: &serde_json::Value) -> Vec<String> {
+    if let Some(path) = args.get("path").and_then(|p| p.as_str()) {
+        return vec![path.to_string()];
+    }
+    if tool != "apply_patch" {
+        return Vec::new();
+    }
+    // V4A patches name their files in headers
+    let input = args.get("input").and_then(|i| i.as_str()).unwrap_or("");
+    input
+        .lines()
+        .filter_map(|line| {
+            ["*** Update File:", "*** Add File:", "*** Delete File:", "*** Move to:"]
+                .iter()
+                .find_map(|header| line.strip_prefix(header))
+        })
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+fn sanitize(id: &str) -> String {
+    id.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_restore_from_earlier_turn() {
+        let workspace = tempdir().unwrap();
+        let store = tempdir().unwrap();
+        let checkpoints = Checkpoints::in_dir(store.path().join("conv"));
+        let ws = workspace.path();
+        std::fs::write(ws.join("a.txt"), "v0").unwrap();
+
+        // Turn 0 edits a.txt; turn 1 edits it again and creates b.txt
+        checkpoints.record(0, ws, "a.txt").unwrap();
+        std::fs::write(ws.join("a.txt"), "v1").unwrap();
+        checkpoints.record(1, ws, "a.txt").unwrap();
+        checkpoints.record(1, ws, "b.txt").unwrap();
+        std::fs::write(ws.join("a.txt"), "v2").unwrap();
+        std::fs::write(ws.join("b.txt"), "new").unwrap();
+        checkpoints.record(1, ws, "a.txt").unwrap();
+        assert_eq!(checkpoints.turns(), vec![0, 1]);
+
+        assert_eq!(checkpoints.restore(1, ws).unwrap(), vec!["a.txt", "b.txt"]);
+        assert_eq!(std::fs::read_to_string(ws.join("a.txt")).unwrap(), "v1");
+        assert!(!ws.join("b.txt").exists());
+        assert_eq!(checkpoints.turns(), vec![0]);
+
+        checkpoints.restore(0, ws).unwrap();
+        assert_eq!(std::fs::read_to_string(ws.join("a.txt")).unwrap(), "v0");
+        assert!(checkpoints.turns().is_empty());
+    }
+
+    #[test]
+    fn test_paths_touched() {
+        assert_eq!(paths_touched("write_file", &json!({ "path": "src/a.rs" })), vec!["src/a.rs"]);
+        let patch = "*** Begin Patch\n*** Update File: a.rs\n@@\n-x\n+y\n*** Add File: b.rs\n+z\n*** End Patch";
+        assert_eq!(paths_touched("apply_patch", &json!({ "input": patch })), vec!["a.rs", "b.rs"]);
+        assert!(paths_touched("run", &json!({ "command": "ls" })).is_empty());
+    }
+}
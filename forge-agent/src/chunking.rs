@@ -0,0 +1,267 @@
+//! Symbol-aware chunks for the semantic index.
+//!
+//! Files are split at definition boundaries rather than every N lines, so a
+//! chunk holds a whole function with its doc comment, tagged with the
+//! symbols it defines. A definition too large for one chunk is split at its
+//! inner definitions (an impl's methods, a class's members), and past that
+//! by lines with some overlap. Code between definitions (imports, top-level
+//! statements) is grouped into chunks of its own, and small neighbours are
+//! merged. Files without a grammar are split by lines.
+
+use std::path::Path;
+
+use serde::Serialize;
+use tree_sitter::Node;
+
+use crate::syntax::{self, LangConfig};
+
+/// Longest chunk, in lines.
+pub const MAX_CHUNK_LINES: usize = 80;
+/// Lines repeated between chunks split by lines.
+const OVERLAP_LINES: usize = 5;
+/// Adjacent chunks are merged while they fit in this many lines.
+const MERGE_LINES: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Chunk {
+    /// 1-based, inclusive.
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Symbols defined in the chunk, qualified by container (`Foo::bar`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub symbols: Vec<String>,
+}
+
+/// Chunks for `content`, the text of `path`.
+pub fn chunk_file(path: &Path, content: &str) -> Vec<Chunk> {
+    if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("sql")) {
+        return chunk_sql(content, MAX_CHUNK_LINES);
+    }
+    chunk_with(syntax::config_for(path), content, MAX_CHUNK_LINES)
+}
+
+/// SQL has no grammar here; its CREATE statements stand in for definitions.
+fn chunk_sql(content: &str, max_lines: usize) -> Vec<Chunk> {
+    let rows = content.lines().count();
+    let mut builder = Builder::new(None, content, max_lines);
+    let mut next = 0;
+    for definition in crate::sql::definitions(content) {
+        let (start, end) = (definition.line - 1, (definition.end_line - 1).min(rows.saturating_sub(1)));
+        if start < next {
+            continue;
+        }
+        if start > next {
+            builder.extend_gap(next, start - 1);
+        }
+        builder.flush_gap();
+        builder.split_lines(start, end, vec![definition.name]);
+        next = end + 1;
+    }
+    if next < rows {
+        builder.extend_gap(next, rows - 1);
+    }
+    builder.flush_gap();
+    merge_small(builder.chunks, MERGE_LINES.min(max_lines))
+}
+
+fn chunk_with(config: Option<&LangConfig>, content: &str, max_lines: usize) -> Vec<Chunk> {
+    let rows = content.lines().count();
+    if rows == 0 {
+        return Vec::new();
+    }
+    let Some((config, tree)) = config.and_then(|c| Some((c, c.parse(content)?))) else {
+        let mut builder = Builder::new(None, content, max_lines);
+        builder.split_lines(0, rows - 1, Vec::new());
+        return builder.chunks;
+    };
+    let mut builder = Builder::new(Some(config), content, max_lines);
+    builder.visit_children(config.top_level(tree.root_node()), None);
+    builder.flush_gap();
+    merge_small(builder.chunks, MERGE_LINES.min(max_lines))
+}
+
+struct Builder<'a> {
+    config: Option<&'a LangConfig>,
+    src: &'a [u8],
+    max_lines: usize,
+    chunks: Vec<Chunk>,
+    /// Rows of non-definition code waiting to become a chunk.
+    gap: Option<(usize, usize)>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(config: Option<&'a LangConfig>, content: &'a str, max_lines: usize) -> Self {
+        Self { config, src: content.as_bytes(), max_lines, chunks: Vec::new(), gap: None }
+    }
+
+    fn visit_children(&mut self, parent: Node, scope: Option<&str>) {
+        let Some(config) = self.config else { return };
+        let mut cursor = parent.walk();
+        let children: Vec<Node> = parent.named_children(&mut cursor).collect();
+        // Comments and attributes waiting to see what they're attached to
+        let mut leading: Option<(usize, usize)> = None;
+        for child in children {
+            if config.leading.contains(&child.kind()) {
+                let (start, _) = leading.unwrap_or((child.start_position().row, 0));
+                leading = Some((start, syntax::end_row(child)));
+                continue;
+            }
+            let definition = config.unwrap(child);
+            if config.is_definition(definition) {
+                let start = leading.take().map_or(child.start_position().row, |(start, _)| start);
+                self.definition(child, definition, start, scope);
+            } else {
+                if let Some((start, end)) = leading.take() {
+                    self.extend_gap(start, end);
+                }
+                self.extend_gap(child.start_position().row, syntax::end_row(child));
+            }
+        }
+        if let Some((start, end)) = leading {
+            self.extend_gap(start, end);
+        }
+    }
+
+    fn definition(&mut self, outer: Node, definition: Node, start: usize, scope: Option<&str>) {
+        let Some(config) = self.config else { return };
+        self.flush_gap();
+        let end = syntax::end_row(outer);
+        let name = config.definition_name(definition, self.src).map(|name| qualify(config, scope, &name));
+
+        if end - start < self.max_lines {
+            let mut symbols: Vec<String> = name.iter().cloned().collect();
+            self.members(definition, name.as_deref().or(scope), &mut symbols);
+            self.push(start, end, symbols);
+            return;
+        }
+        if config.is_container(definition) {
+            if let Some(body) = definition.child_by_field_name("body") {
+                // The header (signature, fields before the body) carries the name
+                self.push(start, body.start_position().row, name.iter().cloned().collect());
+                self.visit_children(body, name.as_deref().or(scope));
+                self.flush_gap();
+                return;
+            }
+        }
+        self.split_lines(start, end, name.into_iter().collect());
+    }
+
+    /// Names defined inside a container that fits in one chunk.
+    fn members(&self, definition: Node, scope: Option<&str>, symbols: &mut Vec<String>) {
+        let Some(config) = self.config else { return };
+        let Some(body) = definition.child_by_field_name("body").filter(|_| config.is_container(definition)) else {
+            return;
+        };
+        let mut cursor = body.walk();
+        for child in body.named_children(&mut cursor) {
+            let member = config.unwrap(child);
+            if config.is_definition(member) {
+                if let Some(name) = config.definition_name(member, self.src) {
+                    symbols.push(qualify(config, scope, &name));
+                }
+            }
+        }
+    }
+
+    fn extend_gap(&mut self, start: usize, end: usize) {
+        match self.gap {
+            Some((gap_start, _)) if end - gap_start < self.max_lines => self.gap = Some((gap_start, end)),
+            Some(_) => {
+                self.flush_gap();
+                self.gap = Some((start, end));
+            }
+            None => self.gap = Some((start, end)),
+        }
+    }
+
+    fn flush_gap(&mut self) {
+        if let Some((start, end)) = self.gap.take() {
+            self.split_lines(start, end, Vec::new());
+        }
+    }
+
+    /// Windows of at most `max_lines`, overlapping by a few lines.
+    fn split_lines(&mut self, start: usize, end: usize, symbols: Vec<String>) {
+        let mut from = start;
+        loop {
+            let to = end.min(from + self.max_lines - 1);
+            self.push(from, to, symbols.clone());
+            if to == end {
+                break;
+            }
+            from = (to + 1).saturating_sub(OVERLAP_LINES).max(from + 1);
+        }
+    }
+
+    fn push(&mut self, start_row: usize, end_row: usize, symbols: Vec<String>) {
+        self.chunks.push(Chunk { start_line: start_row + 1, end_line: end_row + 1, symbols });
+    }
+}
+
+fn qualify(config: &LangConfig, scope: Option<&str>, name: &str) -> String {
+    match scope {
+        Some(scope) => format!("{scope}{}{name}", config.separator),
+        None => name.to_string(),
+    }
+}
+
+/// Merge runs of small adjacent chunks (constants, one-line functions).
+fn merge_small(chunks: Vec<Chunk>, max_lines: usize) -> Vec<Chunk> {
+    let mut merged: Vec<Chunk> = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        if let Some(last) = merged.last_mut() {
+            let adjacent = chunk.start_line > last.end_line && chunk.start_line <= last.end_line + 2;
+            if adjacent && chunk.end_line + 1 - last.start_line <= max_lines {
+                last.end_line = chunk.end_line;
+                for symbol in chunk.symbols {
+                    if !last.symbols.contains(&symbol) {
+                        last.symbols.push(symbol);
+                    }
+                }
+                continue;
+            }
+        }
+        merged.push(chunk);
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(chunks: &[Chunk]) -> Vec<(usize, usize, Vec<&str>)> {
+        chunks.iter().map(|c| (c.start_line, c.end_line, c.symbols.iter().map(String::as_str).collect())).collect()
+    }
+
+    #[test]
+    fn test_chunks_follow_definitions() {
+        let rust = syntax::config_for(Path::new("lib.rs"));
+        let filler = |n: usize| "    let _x = 1;\n".repeat(n);
+        let src = format!(
+            "use std::fmt;\n\n/// Docs\nfn small() {{\n{}}}\n\nstruct Big;\n\nimpl Big {{\n    fn one() {{\n{}    }}\n\n    fn two() {{\n{}    }}\n}}\n",
+            filler(2),
+            filler(3),
+            filler(3)
+        );
+        // With 8-line chunks the impl (13 lines) splits at its methods; the
+        // doc comment stays with `small`, and small neighbours merge
+        let chunks = chunk_with(rust, &src, 8);
+        assert_eq!(
+            spans(&chunks),
+            vec![(1, 7, vec!["small"]), (9, 16, vec!["Big", "Big::one"]), (18, 22, vec!["Big::two"])]
+        );
+
+        // The whole file fits in one default chunk
+        let chunks = chunk_file(Path::new("lib.rs"), &src);
+        assert_eq!(spans(&chunks), vec![(1, 23, vec!["small", "Big", "Big::one", "Big::two"])]);
+    }
+
+    #[test]
+    fn test_unknown_languages_split_by_lines() {
+        let text = "line\n".repeat(170);
+        let chunks = chunk_file(Path::new("notes.txt"), &text);
+        let ranges: Vec<(usize, usize)> = chunks.iter().map(|c| (c.start_line, c.end_line)).collect();
+        assert_eq!(ranges, [(1, 80), (76, 155), (151, 170)]);
+    }
+}
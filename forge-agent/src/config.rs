@@ -0,0 +1,441 @@
+//! Agent settings: global config, named profiles and per-workspace overrides.
+//!
+//! Settings are read from two TOML files:
+//! - `~/.forge/config.toml` (global)
+//! - `<workspace>/.forge/config.toml` (per project)
+//!
+//! Either may define `[profiles.<name>]` tables (e.g. `work`, `personal`);
+//! the active profile is `FORGE_PROFILE`, else the workspace's `profile`
+//! key, else the global one. Precedence, lowest to highest:
+//!
+//! 1. global settings
+//! 2. global `[profiles.<active>]`
+//! 3. workspace settings
+//! 4. workspace `[profiles.<active>]`
+//! 5. `FORGE_*` environment variables
+//!
+//! Keys are the `FORGE_*` variable names without the prefix, lowercased
+//! (`FORGE_PROCESS_POLICY` is `process_policy`). Nested tables flatten to
+//! dotted keys, so `[keys] groq = "..."` is `keys.groq`.
+//!
+//! ```toml
+//! profile = "work"
+//! process_policy = "confirm"
+//!
+//! [profiles.work]
+//! search_url = "https://forge-search.internal.example.com"
+//! tool_output_max = 20000
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+use anyhow::{anyhow, Context, Result};
+
+const CONFIG_FILE: &str = "config.toml";
+const ENV_PREFIX: &str = "FORGE_";
+
+/// Which layer a setting came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Global,
+    GlobalProfile(String),
+    Workspace,
+    WorkspaceProfile(String),
+    Env,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Global => write!(f, "global"),
+            Self::GlobalProfile(name) => write!(f, "global profile '{name}'"),
+            Self::Workspace => write!(f, "workspace"),
+            Self::WorkspaceProfile(name) => write!(f, "workspace profile '{name}'"),
+            Self::Env => write!(f, "environment"),
+        }
+    }
+}
+
+/// Where `set` / `unset` write.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    Global,
+    Workspace(PathBuf),
+    /// A `[profiles.<name>]` table in the global file.
+    Profile(String),
+}
+
+/// The merged settings for one workspace.
+#[derive(Clone, Default)]
+pub struct Config {
+    values: BTreeMap<String, (String, Source)>,
+    profile: Option<String>,
+    profiles: Vec<String>,
+}
+
+/// Secrets (`keys.*`, `*_api_key`, tokens) are redacted so a logged config
+/// never leaks them.
+impl std::fmt::Debug for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values: BTreeMap<&str, String> = self
+            .entries()
+            .map(|(k, _, _)| (k, self.display_value(k).unwrap_or_default()))
+            .collect();
+        f.debug_struct("Config")
+            .field("profile", &self.profile)
+            .field("values", &values)
+            .finish()
+    }
+}
+
+impl Config {
+    /// Merge the global file, the workspace file (if any) and the environment.
+    pub fn load(workspace: Option<&Path>) -> Self {
+        let global = global_path().map(|p| read_table(&p)).unwrap_or_default();
+        let local = workspace
+            .map(|w| read_table(&workspace_path(w)))
+            .unwrap_or_default();
+        Self::merge(&global, &local, std::env::vars())
+    }
+
+    fn merge(
+        global: &toml::Table,
+        local: &toml::Table,
+        env: impl Iterator<Item = (String, String)>,
+    ) -> Self {
+        let env: Vec<(String, String)> = env.collect();
+        let profile = env
+            .iter()
+            .find(|(k, _)| k == "FORGE_PROFILE")
+            .map(|(_, v)| v.clone())
+            .or_else(|| local.get("profile").and_then(|v| v.as_str()).map(String::from))
+            .or_else(|| global.get("profile").and_then(|v| v.as_str()).map(String::from))
+            .filter(|p| !p.is_empty());
+
+        let mut config = Self { profile: profile.clone(), ..Default::default() };
+        for table in [global, local] {
+            if let Some(profiles) = table.get("profiles").and_then(|v| v.as_table()) {
+                config.profiles.extend(profiles.keys().cloned());
+            }
+        }
+        config.profiles.sort();
+        config.profiles.dedup();
+
+        let profile_table = |table: &toml::Table| -> Option<toml::Table> {
+            let name = profile.as_deref()?;
+            table.get("profiles")?.get(name)?.as_table().cloned()
+        };
+        config.apply(global, Source::Global);
+        if let (Some(table), Some(name)) = (profile_table(global), &profile) {
+            config.apply(&table, Source::GlobalProfile(name.clone()));
+        }
+        config.apply(local, Source::Workspace);
+        if let (Some(table), Some(name)) = (profile_table(local), &profile) {
+            config.apply(&table, Source::WorkspaceProfile(name.clone()));
+        }
+        for (name, value) in env {
+            if let Some(key) = env_key(&name) {
+                config.values.insert(key, (value, Source::Env));
+            }
+        }
+        config
+    }
+
+    fn apply(&mut self, table: &toml::Table, source: Source) {
+        let mut flat = Vec::new();
+        flatten("", table, &mut flat);
+        for (key, value) in flat {
+            if key == "profile" || key.starts_with("profiles.") {
+                continue;
+            }
+            self.values.insert(key, (value, source.clone()));
+        }
+    }
+
+    /// Value of `key` (e.g. `process_policy`, `keys.groq`).
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|(v, _)| v.as_str())
+    }
+
+    /// Value of `key` for printing, with secrets redacted.
+    pub fn display_value(&self, key: &str) -> Option<String> {
+        let value = self.get(key)?;
+        Some(if crate::secrets::is_secret_key(key) {
+            crate::secrets::redact(value)
+        } else {
+            value.to_string()
+        })
+    }
+
+    /// Value of `key` and the layer that set it.
+    pub fn get_with_source(&self, key: &str) -> Option<(&str, &Source)> {
+        self.values.get(key).map(|(v, s)| (v.as_str(), s))
+    }
+
+    /// All settings, sorted by key.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str, &Source)> {
+        self.values.iter().map(|(k, (v, s))| (k.as_str(), v.as_str(), s))
+    }
+
+    /// Settings under `prefix.` (e.g. `keys`), with the prefix stripped.
+    pub fn section(&self, prefix: &str) -> BTreeMap<String, String> {
+        let prefix = format!("{prefix}.");
+        self.values
+            .iter()
+            .filter_map(|(k, (v, _))| Some((k.strip_prefix(&prefix)?.to_string(), v.clone())))
+            .collect()
+    }
+
+    pub fn active_profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Profiles defined in either file.
+    pub fn profiles(&self) -> &[String] {
+        &self.profiles
+    }
+}
+
+fn current() -> &'static RwLock<Option<Config>> {
+    static INSTANCE: OnceLock<RwLock<Option<Config>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| RwLock::new(None))
+}
+
+/// Load the settings for `workspace` and make them the ones [`var`] reads.
+/// Called at the start of every agent turn so edits apply without a restart.
+pub fn activate(workspace: &Path) -> Config {
+    let config = Config::load(Some(workspace));
+    *current().write().unwrap() = Some(config.clone());
+    config
+}
+
+/// The active settings (global only until a workspace is activated).
+pub fn active() -> Config {
+    if let Some(config) = current().read().unwrap().as_ref() {
+        return config.clone();
+    }
+    Config::load(None)
+}
+
+/// Setting for the `FORGE_*` variable `name`: the environment first, then the
+/// active config files.
+pub fn var(name: &str) -> Option<String> {
+    if let Ok(value) = std::env::var(name) {
+        return Some(value);
+    }
+    let key = env_key(name)?;
+    let current = current().read().unwrap();
+    match current.as_ref() {
+        Some(config) => config.get(&key).map(String::from),
+        None => Config::load(None).get(&key).map(String::from),
+    }
+}
+
+/// Write `key = value` in `scope`. Values that parse as TOML numbers or
+/// booleans are stored as such, everything else as a string.
+pub fn set(scope: &Scope, key: &str, value: &str) -> Result<PathBuf> {
+    let path = scope_path(scope)?;
+    let mut doc = read_document(&path)?;
+    let parsed = value
+        .parse::<i64>()
+        .map(toml_edit::value)
+        .or_else(|_| value.parse::<bool>().map(toml_edit::value))
+        .unwrap_or_else(|_| toml_edit::value(value));
+    let mut item = doc.as_item_mut();
+    for part in scope_prefix(scope).iter().map(String::as_str).chain(key.split('.')) {
+        if item.get(part).is_none() {
+            item[part] = toml_edit::table();
+        }
+        item = &mut item[part];
+    }
+    *item = parsed;
+    write_document(&path, &doc)?;
+    Ok(path)
+}
+
+/// Remove `key` from `scope`. Returns whether it was set.
+pub fn unset(scope: &Scope, key: &str) -> Result<bool> {
+    let path = scope_path(scope)?;
+    let mut doc = read_document(&path)?;
+    let parts: Vec<&str> = scope_prefix(scope)
+        .iter()
+        .map(String::as_str)
+        .chain(key.split('.'))
+        .collect();
+    let (last, parents) = parts.split_last().ok_or_else(|| anyhow!("empty key"))?;
+    let mut item = doc.as_item_mut();
+    for part in parents {
+        match item.get_mut(part) {
+            Some(next) => item = next,
+            None => return Ok(false),
+        }
+    }
+    let removed = item
+        .as_table_like_mut()
+        .and_then(|t| t.remove(last))
+        .is_some();
+    if removed {
+        write_document(&path, &doc)?;
+    }
+    Ok(removed)
+}
+
+/// Make `name` the default profile for `scope` (global or workspace).
+pub fn use_profile(scope: &Scope, name: &str) -> Result<PathBuf> {
+    if matches!(scope, Scope::Profile(_)) {
+        return Err(anyhow!("the active profile is set globally or per workspace"));
+    }
+    set(scope, "profile", name)
+}
+
+pub fn global_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".forge").join(CONFIG_FILE))
+}
+
+pub fn workspace_path(workspace: &Path) -> PathBuf {
+    workspace.join(".forge").join(CONFIG_FILE)
+}
+
+fn scope_path(scope: &Scope) -> Result<PathBuf> {
+    match scope {
+        Scope::Global | Scope::Profile(_) => {
+            global_path().ok_or_else(|| anyhow!("no home directory"))
+        }
+        Scope::Workspace(workspace) => Ok(workspace_path(workspace)),
+    }
+}
+
+fn scope_prefix(scope: &Scope) -> Vec<String> {
+    match scope {
+        Scope::Profile(name) => vec!["profiles".to_string(), name.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// `FORGE_PROCESS_POLICY` -> `process_policy`.
+fn env_key(name: &str) -> Option<String> {
+    let key = name.strip_prefix(ENV_PREFIX)?;
+    (!key.is_empty() && key != "PROFILE").then(|| key.to_ascii_lowercase())
+}
+
+fn flatten(prefix: &str, table: &toml::Table, out: &mut Vec<(String, String)>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+        match value {
+            toml::Value::Table(inner) => flatten(&key, inner, out),
+            toml::Value::String(s) => out.push((key, s.clone())),
+            other => out.push((key, other.to_string())),
+        }
+    }
+}
+
+fn read_table(path: &Path) -> toml::Table {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return toml::Table::new();
+    };
+    match content.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(e) => {
+            tracing::warn!("Ignoring invalid config {}: {e}", path.display());
+            toml::Table::new()
+        }
+    }
+}
+
+fn read_document(path: &Path) -> Result<toml_edit::Document> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => content
+            .parse()
+            .with_context(|| format!("invalid config {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Default::default()),
+        Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+fn write_document(path: &Path, doc: &toml_edit::Document) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, doc.to_string())
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(s: &str) -> toml::Table {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_precedence() {
+        let global = table(
+            r#"
+            profile = "personal"
+            process_policy = "confirm"
+            tool_output_max = 12000
+            [keys]
+            groq = "g-global"
+            [profiles.work]
+            search_url = "https://work.example.com"
+            keys.groq = "g-work"
+            [profiles.personal]
+            search_url = "https://personal.example.com"
+            "#,
+        );
+        let local = table(
+            r#"
+            profile = "work"
+            tool_output_max = 30000
+            [profiles.work]
+            process_policy = "refuse"
+            "#,
+        );
+
+        let config = Config::merge(&global, &local, std::iter::empty());
+        assert_eq!(config.active_profile(), Some("work"));
+        assert_eq!(config.profiles(), ["personal", "work"]);
+        assert_eq!(config.get("search_url"), Some("https://work.example.com"));
+        assert_eq!(config.get("tool_output_max"), Some("30000"));
+        assert_eq!(
+            config.get_with_source("process_policy"),
+            Some(("refuse", &Source::WorkspaceProfile("work".into())))
+        );
+        assert_eq!(config.section("keys").get("groq").map(String::as_str), Some("g-work"));
+        assert!(config.get("profile").is_none());
+        assert!(!format!("{config:?}").contains("g-work"));
+
+        let env = vec![
+            ("FORGE_PROFILE".to_string(), "personal".to_string()),
+            ("FORGE_TOOL_OUTPUT_MAX".to_string(), "500".to_string()),
+            ("PATH".to_string(), "/bin".to_string()),
+        ];
+        let config = Config::merge(&global, &local, env.into_iter());
+        assert_eq!(config.active_profile(), Some("personal"));
+        assert_eq!(config.get("search_url"), Some("https://personal.example.com"));
+        assert_eq!(config.get_with_source("tool_output_max"), Some(("500", &Source::Env)));
+        assert_eq!(config.get("process_policy"), Some("confirm"));
+        assert!(config.get("path").is_none());
+    }
+
+    #[test]
+    fn test_set_and_unset() {
+        let dir = tempfile::tempdir().unwrap();
+        let scope = Scope::Workspace(dir.path().to_path_buf());
+        set(&scope, "tool_output_max", "20000").unwrap();
+        set(&scope, "keys.groq", "secret").unwrap();
+        use_profile(&scope, "work").unwrap();
+
+        let config = Config::load(Some(dir.path()));
+        assert_eq!(config.get_with_source("tool_output_max"), Some(("20000", &Source::Workspace)));
+        assert_eq!(config.get("keys.groq"), Some("secret"));
+        assert_eq!(config.active_profile(), Some("work"));
+
+        assert!(unset(&scope, "keys.groq").unwrap());
+        assert!(!unset(&scope, "keys.groq").unwrap());
+        assert!(Config::load(Some(dir.path())).get("keys.groq").is_none());
+    }
+}
@@ -0,0 +1,188 @@
+//! Persistent documentation cache for fetched pages.
+//!
+//! Docs fetched by the `fetch` tool are stored under `~/.forge/docs` with
+//! their ETag / Last-Modified validators:
+//! - Fresh entries (younger than the TTL) are served without a request
+//! - Stale entries are revalidated; a 304 just bumps the timestamp
+//! - If the network fails, a stale entry is served rather than an error
+//! - Offline mode (`FORGE_OFFLINE=1`) serves only cached docs
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Default time-to-live for cached docs (24h). Override with `FORGE_DOCS_TTL_SECS`.
+const DEFAULT_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Max number of cached pages kept on disk (oldest are evicted).
+const MAX_CACHED_DOCS: usize = 500;
+
+/// A cached documentation page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedDoc {
+    pub url: String,
+    pub markdown: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// Unix timestamp (seconds) of the last successful fetch or revalidation.
+    pub fetched_at: i64,
+}
+
+/// On-disk docs cache.
+#[derive(Debug, Clone)]
+pub struct DocsCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl DocsCache {
+    /// Cache at `~/.forge/docs` with the TTL from `FORGE_DOCS_TTL_SECS`.
+    pub fn new() -> Option<Self> {
+        let dir = dirs::home_dir()?.join(".forge").join("docs");
+        let ttl = crate::config::var("FORGE_DOCS_TTL_SECS")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TTL_SECS);
+        Some(Self::with_dir(dir, Duration::from_secs(ttl)))
+    }
+
+    pub fn with_dir(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self { dir: dir.into(), ttl }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Look up a cached page (fresh or stale).
+    pub fn get(&self, url: &str) -> Option<CachedDoc> {
+        let content = std::fs::read_to_string(self.entry_path(url)).ok()?;
+        let doc: CachedDoc = serde_json::from_str(&content).ok()?;
+        // Guard against hash collisions
+        (doc.url == url).then_some(doc)
+    }
+
+    /// Whether a cached page is still within the TTL.
+    pub fn is_fresh(&self, doc: &CachedDoc) -> bool {
+        let age = chrono::Utc::now().timestamp() - doc.fetched_at;
+        age >= 0 && (age as u64) < self.ttl.as_secs()
+    }
+
+    /// Store (or replace) a page.
+    pub fn put(&self, doc: &CachedDoc) {
+        if let Err(e) = std::fs::create_dir_all(&self.dir) {
+            tracing::warn!("Failed to create docs cache dir: {e}");
+            return;
+        }
+        let Ok(json) = serde_json::to_string(doc) else {
+            return;
+        };
+        if let Err(e) = std::fs::write(self.entry_path(&doc.url), json) {
+            tracing::warn!("Failed to write docs cache entry: {e}");
+            return;
+        }
+        self.evict(MAX_CACHED_DOCS);
+    }
+
+    /// Mark a page as revalidated (server answered 304 Not Modified).
+    pub fn touch(&self, mut doc: CachedDoc) -> CachedDoc {
+        doc.fetched_at = chrono::Utc::now().timestamp();
+        self.put(&doc);
+        doc
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(url.as_bytes())))
+    }
+
+    /// Keep at most `max_files` entries, removing the least recently written.
+    fn evict(&self, max_files: usize) {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut files: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+            .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+            .collect();
+        if files.len() <= max_files {
+            return;
+        }
+        files.sort_by_key(|(t, _)| *t);
+        for (_, path) in files.iter().take(files.len() - max_files) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Whether offline mode is enabled (`FORGE_OFFLINE=1|true`).
+pub fn offline_mode() -> bool {
+    crate::config::var("FORGE_OFFLINE")
+        .map(|v| matches!(v.as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Stable 64-bit FNV-1a hash, used for cache file names.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn doc(url: &str, fetched_at: i64) -> CachedDoc {
+        CachedDoc {
+            url: url.to_string(),
+            markdown: "# Docs".to_string(),
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+            fetched_at,
+        }
+    }
+
+    #[test]
+    fn test_put_get_roundtrip() {
+        let dir = tempdir().unwrap();
+        let cache = DocsCache::with_dir(dir.path(), Duration::from_secs(60));
+        assert!(cache.get("https://docs.rs/serde").is_none());
+
+        cache.put(&doc("https://docs.rs/serde", chrono::Utc::now().timestamp()));
+        let hit = cache.get("https://docs.rs/serde").unwrap();
+        assert_eq!(hit.etag.as_deref(), Some("\"abc\""));
+        assert!(cache.is_fresh(&hit));
+    }
+
+    #[test]
+    fn test_stale_and_touch() {
+        let dir = tempdir().unwrap();
+        let cache = DocsCache::with_dir(dir.path(), Duration::from_secs(60));
+        let old = doc("https://example.com", chrono::Utc::now().timestamp() - 3600);
+        cache.put(&old);
+        let hit = cache.get("https://example.com").unwrap();
+        assert!(!cache.is_fresh(&hit));
+
+        let touched = cache.touch(hit);
+        assert!(cache.is_fresh(&touched));
+        assert!(cache.is_fresh(&cache.get("https://example.com").unwrap()));
+    }
+
+    #[test]
+    fn test_evict_keeps_limit() {
+        let dir = tempdir().unwrap();
+        let cache = DocsCache::with_dir(dir.path(), Duration::from_secs(60));
+        for i in 0..5 {
+            cache.put(&doc(&format!("https://example.com/{i}"), 0));
+        }
+        cache.evict(2);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
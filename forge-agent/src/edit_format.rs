@@ -0,0 +1,396 @@
+//! How the model writes file edits.
+//!
+//! Frontier models do well with the `edit_file` tool's exact-match replace;
+//! weaker (especially local) models fail at it, so edits can also be written
+//! in the answer text:
+//!
+//! - `search_replace`: Aider-style blocks, preceded by the file path
+//!   ```text
+//!   src/main.rs
+//!   <<<<<<< SEARCH
+//!   old lines
+//!   =======
+//!   new lines
+//!   >>>>>>> REPLACE
+//!   ```
+//! - `whole_file`: the path, then the complete new file in a code fence
+//! - `udiff`: a unified diff with `--- a/path` / `+++ b/path` headers
+//!
+//! Set `edit_format` in the config or `FORGE_EDIT_FORMAT`; `auto` (the
+//! default) picks from the planner model's name. Text edits are turned into
+//! the equivalent `edit_file` / `write_file` / `apply_patch` calls, so they
+//! go through the usual review, and common model mistakes (line-number
+//! prefixes, numberless hunk headers) are repaired before applying. Edits
+//! that still fail are sent back with [`repair_prompt`].
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde_json::json;
+
+use crate::forge_search::ToolCallInfo;
+
+/// Most times failed text edits are sent back for another attempt.
+pub const MAX_REPAIR_ROUNDS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditFormat {
+    /// `edit_file` / `write_file` / `apply_patch` tool calls.
+    Tool,
+    SearchReplace,
+    WholeFile,
+    UnifiedDiff,
+}
+
+impl EditFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tool => "tool",
+            Self::SearchReplace => "search_replace",
+            Self::WholeFile => "whole_file",
+            Self::UnifiedDiff => "udiff",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "tool" | "tools" => Some(Self::Tool),
+            "search_replace" | "diff" => Some(Self::SearchReplace),
+            "whole_file" | "whole" => Some(Self::WholeFile),
+            "udiff" | "unified_diff" => Some(Self::UnifiedDiff),
+            _ => None,
+        }
+    }
+
+    /// Whether edits arrive in the answer text rather than as tool calls.
+    pub fn is_text(&self) -> bool {
+        *self != Self::Tool
+    }
+
+    /// The format that works best for `model`: tools for frontier models,
+    /// unified diffs for GPT-4 Turbo (which elides code in other formats),
+    /// whole files for small local models and search/replace otherwise.
+    pub fn for_model(model: &str) -> Self {
+        let model = model.to_lowercase();
+        let name = model.rsplit('/').next().unwrap_or(&model);
+        if parameter_billions(name).is_some_and(|b| b <= 14.0) {
+            return Self::WholeFile;
+        }
+        if ["gpt-4-turbo", "gpt-4-1106", "gpt-4-0125"].iter().any(|p| name.starts_with(p)) {
+            return Self::UnifiedDiff;
+        }
+        let frontier = ["claude", "gpt-4o", "gpt-4.1", "gpt-5", "o1", "o3", "o4", "gemini-2", "gemini-3"];
+        if frontier.iter().any(|p| name.starts_with(p)) {
+            return Self::Tool;
+        }
+        if model.starts_with("ollama") {
+            return Self::WholeFile;
+        }
+        Self::SearchReplace
+    }
+
+    /// The configured format, resolving `auto` from the planner model.
+    pub fn configured() -> Self {
+        let configured = crate::config::var("FORGE_EDIT_FORMAT");
+        if let Some(format) = configured.as_deref().and_then(Self::parse) {
+            return format;
+        }
+        crate::model_routing::model_for(crate::model_routing::ModelRole::Planner)
+            .map(|m| Self::for_model(&m))
+            .unwrap_or(Self::Tool)
+    }
+
+    /// What to tell the model about the format (empty for tool calls).
+    pub fn instructions(&self) -> &'static str {
+        match self {
+            Self::Tool => "",
+            Self::SearchReplace => {
+                "Do not use the edit_file, write_file or apply_patch tools. Write each edit in your answer as \
+                 the file path on its own line followed by a SEARCH/REPLACE block:\n\
+                 path/to/file.ext\n<<<<<<< SEARCH\nexact lines to find\n=======\nreplacement lines\n>>>>>>> REPLACE\n\
+                 The SEARCH part must match the file exactly, including indentation; keep it short but unique. \
+                 Use an empty SEARCH part to create a new file."
+            }
+            Self::WholeFile => {
+                "Do not use the edit_file, write_file or apply_patch tools. To change a file, write its path on \
+                 its own line followed by the complete new contents in a code fence. Never elide unchanged code."
+            }
+            Self::UnifiedDiff => {
+                "Do not use the edit_file, write_file or apply_patch tools. Write edits in your answer as unified \
+                 diffs in a ```diff fence, with `--- a/path` and `+++ b/path` headers and `@@` hunks. Include \
+                 a few unchanged context lines around each change and never elide code."
+            }
+        }
+    }
+}
+
+/// A file edit found in answer text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub path: String,
+    pub kind: EditKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditKind {
+    /// Replace `search` with `replace`; an empty `search` creates the file.
+    Replace { search: String, replace: String },
+    Whole(String),
+    Diff(String),
+}
+
+/// The edits in `text` written in `format` (none for [`EditFormat::Tool`]).
+pub fn parse(format: EditFormat, text: &str) -> Vec<TextEdit> {
+    match format {
+        EditFormat::Tool => Vec::new(),
+        EditFormat::SearchReplace => parse_search_replace(text),
+        EditFormat::WholeFile => parse_whole_files(text),
+        EditFormat::UnifiedDiff => parse_unified_diffs(text),
+    }
+}
+
+/// The edits in `text` as the equivalent tool calls, with ids
+/// `<id_prefix>-<n>`.
+pub fn tool_calls(format: EditFormat, text: &str, id_prefix: &str) -> Vec<ToolCallInfo> {
+    parse(format, text)
+        .into_iter()
+        .enumerate()
+        .map(|(i, edit)| {
+            let (name, args) = match edit.kind {
+                EditKind::Replace { search, replace } if search.trim().is_empty() => {
+                    ("write_file", json!({ "path": edit.path, "content": replace }))
+                }
+                EditKind::Replace { search, replace } => {
+                    ("edit_file", json!({ "path": edit.path, "old_str": search, "new_str": replace }))
+                }
+                // A whole-file edit replaces the file on purpose
+                EditKind::Whole(content) => {
+                    ("write_file", json!({ "path": edit.path, "content": content, "overwrite": true }))
+                }
+                EditKind::Diff(patch) => ("apply_patch", json!({ "path": edit.path, "patch": patch })),
+            };
+            ToolCallInfo { id: format!("{id_prefix}-{}", i + 1), name: name.to_string(), args }
+        })
+        .collect()
+}
+
+/// The follow-up message for text edits that failed, as `(path, error)`.
+pub fn repair_prompt(format: EditFormat, failures: &[(String, String)]) -> String {
+    let mut out = String::from("Some of your edits could not be applied:\n");
+    for (path, error) in failures {
+        out.push_str(&format!("\n- {path}: {error}"));
+    }
+    out.push_str(
+        "\n\nThe other edits were applied. Read the current contents of these files and resend only the \
+         failed edits.\n\n",
+    );
+    out.push_str(format.instructions());
+    out
+}
+
+/// `path/to/file.rs` on its own line, possibly in backticks or bold.
+fn path_line(line: &str) -> Option<String> {
+    let path = line.trim().trim_matches(|c| c == '`' || c == '*').trim_end_matches(':');
+    let plausible = !path.is_empty()
+        && !path.contains(char::is_whitespace)
+        && (path.contains('.') || path.contains('/'))
+        && !path.starts_with(['<', '=', '>', '#', '-', '+', '@'])
+        && !path.contains("://");
+    plausible.then(|| path.trim_start_matches("./").to_string())
+}
+
+fn is_fence(line: &str) -> bool {
+    line.trim_start().starts_with("```")
+}
+
+fn parse_search_replace(text: &str) -> Vec<TextEdit> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut edits = Vec::new();
+    let mut path: Option<String> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim();
+        if line.starts_with("<<<<<<<") && line.contains("SEARCH") {
+            let Some(sep) = (i + 1..lines.len()).find(|&j| lines[j].trim_start().starts_with("=======")) else {
+                break;
+            };
+            let Some(end) = (sep + 1..lines.len()).find(|&j| lines[j].trim_start().starts_with(">>>>>>>")) else {
+                break;
+            };
+            if let Some(path) = &path {
+                let (search, replace) = strip_line_numbers(&lines[i + 1..sep], &lines[sep + 1..end]);
+                edits.push(TextEdit { path: path.clone(), kind: EditKind::Replace { search, replace } });
+            }
+            i = end + 1;
+            continue;
+        }
+        if let Some(p) = path_line(line) {
+            path = Some(p);
+        } else if let Some(p) = is_fence(line).then(|| fence_path(line)).flatten() {
+            path = Some(p);
+        }
+        i += 1;
+    }
+    edits
+}
+
+/// `` ```rust src/main.rs `` style fence info naming the file.
+fn fence_path(fence: &str) -> Option<String> {
+    fence.trim_start().trim_start_matches('`').split_whitespace().find_map(path_line)
+}
+
+/// Drop `12: ` / `12 | ` prefixes copied from numbered file views, when
+/// every non-empty line has one.
+fn strip_line_numbers(search: &[&str], replace: &[&str]) -> (String, String) {
+    static NUMBERED: OnceLock<Regex> = OnceLock::new();
+    let re = NUMBERED.get_or_init(|| Regex::new(r"^\s*\d+\s*[:|]\s?").expect("valid line number pattern"));
+    let numbered = |lines: &[&str]| {
+        lines.iter().any(|l| !l.trim().is_empty()) && lines.iter().all(|l| l.trim().is_empty() || re.is_match(l))
+    };
+    let strip = |lines: &[&str], strip: bool| -> String {
+        lines
+            .iter()
+            .map(|l| if strip { re.replace(l, "").into_owned() } else { l.to_string() })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let search_numbered = numbered(search);
+    let replace_numbered = search_numbered && numbered(replace);
+    (strip(search, search_numbered), strip(replace, replace_numbered))
+}
+
+fn parse_whole_files(text: &str) -> Vec<TextEdit> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut edits = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if !is_fence(lines[i]) {
+            i += 1;
+            continue;
+        }
+        let Some(close) = (i + 1..lines.len()).find(|&j| lines[j].trim() == "```") else {
+            break;
+        };
+        let previous = lines[..i].iter().rev().find(|l| !l.trim().is_empty());
+        let path = fence_path(lines[i]).or_else(|| previous.and_then(|l| path_line(l)));
+        if let Some(path) = path {
+            let mut content = lines[i + 1..close].join("\n");
+            content.push('\n');
+            edits.push(TextEdit { path, kind: EditKind::Whole(content) });
+        }
+        i = close + 1;
+    }
+    edits
+}
+
+fn parse_unified_diffs(text: &str) -> Vec<TextEdit> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut edits = Vec::new();
+    let mut i = 0;
+    while i + 1 < lines.len() {
+        if !(lines[i].starts_with("--- ") && lines[i + 1].starts_with("+++ ")) {
+            i += 1;
+            continue;
+        }
+        let target = lines[i + 1][4..].split('\t').next().unwrap_or_default().trim();
+        let mut end = i + 2;
+        while end < lines.len() {
+            let line = lines[end];
+            let next_header = line.starts_with("--- ") && lines.get(end + 1).is_some_and(|l| l.starts_with("+++ "));
+            let in_hunk = line.is_empty() || line.starts_with([' ', '+', '-', '@', '\\']);
+            if next_header || !in_hunk || is_fence(line) {
+                break;
+            }
+            end += 1;
+        }
+        if target != "/dev/null" {
+            let path = target.strip_prefix("b/").unwrap_or(target).to_string();
+            let patch = repair_hunk_headers(&lines[i..end]);
+            edits.push(TextEdit { path, kind: EditKind::Diff(patch) });
+        }
+        i = end;
+    }
+    edits
+}
+
+/// Give `@@ ... @@` headers without line numbers a placeholder range; the
+/// patcher locates hunks by their context anyway.
+fn repair_hunk_headers(lines: &[&str]) -> String {
+    let mut out: Vec<String> = lines
+        .iter()
+        .map(|l| {
+            if l.starts_with("@@") && !l.contains(" -") {
+                "@@ -1,1 +1,1 @@".to_string()
+            } else {
+                l.to_string()
+            }
+        })
+        .collect();
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    out.join("\n") + "\n"
+}
+
+/// `7` for `qwen2.5-coder:7b`, `1.5` for `deepseek-r1-1.5b`.
+fn parameter_billions(name: &str) -> Option<f64> {
+    static SIZE: OnceLock<Regex> = OnceLock::new();
+    let re = SIZE.get_or_init(|| Regex::new(r"[:\-_](\d+(?:\.\d+)?)b\b").expect("valid model size pattern"));
+    re.captures(name)?.get(1)?.as_str().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_model() {
+        assert_eq!(EditFormat::for_model("claude-sonnet-4-20250514"), EditFormat::Tool);
+        assert_eq!(EditFormat::for_model("openai/gpt-4o"), EditFormat::Tool);
+        assert_eq!(EditFormat::for_model("gpt-4-turbo-2024-04-09"), EditFormat::UnifiedDiff);
+        assert_eq!(EditFormat::for_model("qwen2.5-coder:7b"), EditFormat::WholeFile);
+        assert_eq!(EditFormat::for_model("llama-3.3-70b-instruct"), EditFormat::SearchReplace);
+        assert_eq!(EditFormat::parse("unified-diff"), Some(EditFormat::UnifiedDiff));
+    }
+
+    #[test]
+    fn test_parse_search_replace() {
+        let text = "Here is the fix:\n\n`src/lib.rs`\n```rust\n<<<<<<< SEARCH\n3: fn a() {}\n4 | fn b() {}\n=======\n\
+                    fn a() { 1 }\n>>>>>>> REPLACE\n```\n\nnew.txt\n<<<<<<< SEARCH\n=======\nhello\n>>>>>>> REPLACE\n";
+        let edits = parse(EditFormat::SearchReplace, text);
+        assert_eq!(
+            edits,
+            vec![
+                TextEdit {
+                    path: "src/lib.rs".into(),
+                    kind: EditKind::Replace { search: "fn a() {}\nfn b() {}".into(), replace: "fn a() { 1 }".into() },
+                },
+                TextEdit { path: "new.txt".into(), kind: EditKind::Replace { search: "".into(), replace: "hello".into() } },
+            ]
+        );
+        let calls = tool_calls(EditFormat::SearchReplace, text, "edit");
+        assert_eq!(calls[0].name, "edit_file");
+        assert_eq!(calls[1].name, "write_file");
+        assert_eq!(calls[1].id, "edit-2");
+    }
+
+    #[test]
+    fn test_parse_whole_file_and_udiff() {
+        let text = "Updated file:\n\nsrc/main.rs\n```rust\nfn main() {}\n```\n\n```\nno path here\n```";
+        assert_eq!(
+            parse(EditFormat::WholeFile, text),
+            vec![TextEdit { path: "src/main.rs".into(), kind: EditKind::Whole("fn main() {}\n".into()) }]
+        );
+
+        let text = "```diff\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ ... @@\n fn main() {\n-    old();\n+    new();\n }\n```";
+        assert_eq!(
+            parse(EditFormat::UnifiedDiff, text),
+            vec![TextEdit {
+                path: "src/main.rs".into(),
+                kind: EditKind::Diff(
+                    "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,1 +1,1 @@\n fn main() {\n-    old();\n+    new();\n }\n".into()
+                ),
+            }]
+        );
+    }
+}
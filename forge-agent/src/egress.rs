@@ -0,0 +1,98 @@
+//! Off-machine data controls.
+//!
+//! One switch for teams with strict data-egress rules:
+//!
+//! ```toml
+//! local_only = true        # nothing leaves the machine but LLM requests
+//! local_only_llm = "ollama" # ...and those only to a local Ollama ("provider": the chosen one)
+//! ```
+//!
+//! With `local_only` set, forge-search (cloud chat, indexing, search), doc
+//! prefetching and the `fetch` tool are off; the chosen LLM provider is the
+//! only outbound traffic left, or none at all with `local_only_llm = "ollama"`.
+//! `FORGE_LOCAL_ONLY=1` turns it on without touching the config.
+
+use anyhow::{anyhow, Result};
+
+/// Which LLM traffic local-only mode still allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmEgress {
+    /// The configured provider, wherever it is.
+    Provider,
+    /// Only a local Ollama.
+    Ollama,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Egress {
+    pub local_only: bool,
+    pub llm: LlmEgress,
+}
+
+impl Egress {
+    pub fn configured() -> Self {
+        let local_only = crate::config::var("FORGE_LOCAL_ONLY").is_some_and(|v| is_true(&v));
+        let llm = match crate::config::active().get("local_only_llm") {
+            Some(v) if v.trim().eq_ignore_ascii_case("ollama") => LlmEgress::Ollama,
+            _ => LlmEgress::Provider,
+        };
+        Self { local_only, llm }
+    }
+
+    /// Calls to Forge's cloud services: forge-search chat, indexing and
+    /// search, and doc prefetching.
+    pub fn check_cloud(&self, what: &str) -> Result<()> {
+        if self.local_only {
+            return Err(anyhow!("{what} is disabled in local-only mode (local_only = true)"));
+        }
+        Ok(())
+    }
+
+    /// Whether requests to LLM `provider` may leave the machine.
+    pub fn allows_llm(&self, provider: &str) -> bool {
+        !self.local_only || self.llm == LlmEgress::Provider || provider == "ollama"
+    }
+
+    /// Any other outbound request, e.g. the `fetch` tool.
+    pub fn allows_other(&self) -> bool {
+        !self.local_only
+    }
+}
+
+fn is_true(v: &str) -> bool {
+    matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+/// Whether local-only mode is on.
+pub fn local_only() -> bool {
+    Egress::configured().local_only
+}
+
+/// [`Egress::check_cloud`] with the current config.
+pub fn check_cloud(what: &str) -> Result<()> {
+    Egress::configured().check_cloud(what)
+}
+
+/// [`Egress::allows_llm`] with the current config.
+pub fn allows_llm(provider: &str) -> bool {
+    Egress::configured().allows_llm(provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_only_rules() {
+        let open = Egress { local_only: false, llm: LlmEgress::Ollama };
+        assert!(open.check_cloud("indexing").is_ok() && open.allows_llm("openai") && open.allows_other());
+
+        let local = Egress { local_only: true, llm: LlmEgress::Provider };
+        let err = local.check_cloud("indexing").unwrap_err().to_string();
+        assert!(err.contains("local-only"), "{err}");
+        assert!(local.allows_llm("anthropic") && !local.allows_other());
+
+        let ollama = Egress { local_only: true, llm: LlmEgress::Ollama };
+        assert!(ollama.allows_llm("ollama") && !ollama.allows_llm("openai"));
+    }
+}
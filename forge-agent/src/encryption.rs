@@ -0,0 +1,155 @@
+//! At-rest encryption for session files.
+//!
+//! Recordings and edit checkpoints hold source code, and possibly secrets,
+//! under `~/.forge`. With `encrypt_sessions = true` in the config (or
+//! `FORGE_ENCRYPT_SESSIONS`) they are written encrypted with
+//! ChaCha20-Poly1305, using a key generated on first use and kept in the OS
+//! keychain. Text is sealed per line, so append-only JSONL files stay
+//! appendable. Reading accepts both forms: files written before the setting
+//! changed still load.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// Prefix of a sealed line: `forge-enc1:<base64(nonce ++ ciphertext)>`.
+const PREFIX: &str = "forge-enc1:";
+const SERVICE: &str = "forge-ide";
+const KEY_ACCOUNT: &str = "session-encryption-key";
+
+/// Whether new session files should be encrypted.
+pub fn enabled() -> bool {
+    crate::config::var("FORGE_ENCRYPT_SESSIONS")
+        .is_some_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+}
+
+pub struct Sealer {
+    key: LessSafeKey,
+}
+
+impl Sealer {
+    pub fn with_key(bytes: &[u8; 32]) -> Self {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, bytes).expect("32-byte key");
+        Self { key: LessSafeKey::new(key) }
+    }
+
+    /// The sealer with the keychain key, created on first use.
+    pub fn from_keychain() -> Result<&'static Sealer> {
+        static SEALER: OnceLock<Sealer> = OnceLock::new();
+        if let Some(sealer) = SEALER.get() {
+            return Ok(sealer);
+        }
+        let sealer = Self::with_key(&keychain_key()?);
+        Ok(SEALER.get_or_init(|| sealer))
+    }
+
+    /// Encrypt one line of text (no newlines in the output).
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| anyhow!("no randomness for a nonce"))?;
+        let mut data = plaintext.as_bytes().to_vec();
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+            .map_err(|_| anyhow!("encryption failed"))?;
+        let mut out = nonce.to_vec();
+        out.extend(data);
+        Ok(format!("{PREFIX}{}", BASE64.encode(out)))
+    }
+
+    /// Decrypt a line from [`Self::seal`]; other lines are returned as is.
+    pub fn open(&self, line: &str) -> Result<String> {
+        let Some(encoded) = line.trim_end().strip_prefix(PREFIX) else {
+            return Ok(line.to_string());
+        };
+        let data = BASE64.decode(encoded).context("corrupt encrypted line")?;
+        if data.len() < NONCE_LEN {
+            return Err(anyhow!("corrupt encrypted line"));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("corrupt encrypted line"))?;
+        let mut ciphertext = ciphertext.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut ciphertext)
+            .map_err(|_| anyhow!("cannot decrypt: wrong key or tampered file"))?;
+        Ok(String::from_utf8(plaintext.to_vec())?)
+    }
+
+    /// [`Self::open`] every line of `text`.
+    pub fn open_lines(&self, text: &str) -> Result<String> {
+        let lines: Result<Vec<String>> = text.lines().map(|line| self.open(line)).collect();
+        Ok(lines?.join("\n"))
+    }
+}
+
+fn keychain_key() -> Result<[u8; 32]> {
+    let entry = keyring::Entry::new(SERVICE, KEY_ACCOUNT).context("opening the keychain")?;
+    match entry.get_password() {
+        Ok(stored) => {
+            let bytes = BASE64.decode(stored.trim()).context("corrupt session key in the keychain")?;
+            bytes.try_into().map_err(|_| anyhow!("session key in the keychain has the wrong length"))
+        }
+        Err(keyring::Error::NoEntry) => {
+            let mut key = [0u8; 32];
+            SystemRandom::new().fill(&mut key).map_err(|_| anyhow!("no randomness for a key"))?;
+            entry.set_password(&BASE64.encode(key)).context("storing the session key in the keychain")?;
+            Ok(key)
+        }
+        Err(e) => Err(e).context("reading the session key from the keychain"),
+    }
+}
+
+/// One line as it should be written: sealed when encryption is on.
+pub fn seal_line(line: &str) -> Result<String> {
+    if !enabled() {
+        return Ok(line.to_string());
+    }
+    Sealer::from_keychain()?.seal(line)
+}
+
+/// Write a session file, sealing each line when encryption is on.
+pub fn write(path: &Path, contents: &str) -> Result<()> {
+    let contents = if enabled() {
+        let sealer = Sealer::from_keychain()?;
+        contents.lines().map(|line| sealer.seal(line)).collect::<Result<Vec<_>>>()?.join("\n")
+    } else {
+        contents.to_string()
+    };
+    std::fs::write(path, contents).with_context(|| format!("writing {}", path.display()))
+}
+
+/// Read a session file, decrypting sealed lines. Plaintext files don't touch
+/// the keychain.
+pub fn read_to_string(path: &Path) -> Result<String> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    if !text.contains(PREFIX) {
+        return Ok(text);
+    }
+    Sealer::from_keychain()?.open_lines(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let sealer = Sealer::with_key(&[7; 32]);
+        let line = r#"{"request":{"question":"fn main() {}"}}"#;
+        let sealed = sealer.seal(line).unwrap();
+        assert!(sealed.starts_with(PREFIX) && !sealed.contains("main") && !sealed.contains('\n'));
+        assert_ne!(sealed, sealer.seal(line).unwrap(), "fresh nonce per line");
+        assert_eq!(sealer.open(&sealed).unwrap(), line);
+
+        // Mixed files (encryption turned on midway) read back in order
+        let file = format!("plain\n{}\n", sealer.seal("secret").unwrap());
+        assert_eq!(sealer.open_lines(&file).unwrap(), "plain\nsecret");
+
+        assert!(Sealer::with_key(&[8; 32]).open(&sealed).is_err());
+    }
+}
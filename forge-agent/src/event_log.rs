@@ -0,0 +1,171 @@
+//! The events the chat panel was sent during a conversation's latest turn,
+//! numbered and kept next to its session
+//! (`~/.forge/sessions/<conversation_id>.events`).
+//!
+//! If the panel loses the proxy mid-task (it restarted, or the connection
+//! dropped), it asks for the events after the last one it saw and replays
+//! them, and the turn's status (`<conversation_id>.turn`) tells it whether
+//! the task is still running, finished, or died with the old proxy.
+//! Sequence numbers keep growing across turns; only the latest turn's
+//! events are kept.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnState {
+    Running,
+    Done,
+    /// The proxy running it went away before it finished.
+    Interrupted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurnStatus {
+    pub state: TurnState,
+    /// The user's message, shortened.
+    pub prompt: String,
+    pub started_at: i64,
+    /// Sequence number of the turn's first event.
+    pub first_seq: u64,
+    /// Of its latest event; `first_seq - 1` before the first one.
+    pub last_seq: u64,
+}
+
+/// One conversation's event log.
+#[derive(Debug, Clone)]
+pub struct EventLog {
+    dir: PathBuf,
+    conversation_id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    seq: u64,
+    event: Value,
+}
+
+impl EventLog {
+    /// The log of `conversation_id`, if there's a home directory for it.
+    pub fn open(conversation_id: &str) -> Option<Self> {
+        Some(Self::in_dir(&crate::session::sessions_dir()?, conversation_id))
+    }
+
+    fn in_dir(dir: &Path, conversation_id: &str) -> Self {
+        Self { dir: dir.to_path_buf(), conversation_id: conversation_id.to_string() }
+    }
+
+    fn events_path(&self) -> PathBuf {
+        crate::session::session_path(&self.dir, &self.conversation_id).with_extension("events")
+    }
+
+    fn status_path(&self) -> PathBuf {
+        crate::session::session_path(&self.dir, &self.conversation_id).with_extension("turn")
+    }
+
+    /// Start a turn for `prompt`, dropping the previous turn's events.
+    /// Returns the sequence number of its first event.
+    pub fn begin_turn(&self, prompt: &str) -> std::io::Result<u64> {
+        let first_seq = self.status().map(|s| s.last_seq + 1).unwrap_or(1);
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.events_path(), "")?;
+        let status = TurnStatus {
+            state: TurnState::Running,
+            prompt: prompt.chars().take(80).collect(),
+            started_at: chrono::Utc::now().timestamp(),
+            first_seq,
+            last_seq: first_seq - 1,
+        };
+        self.write_status(&status)?;
+        Ok(first_seq)
+    }
+
+    /// Record event `seq`.
+    pub fn append(&self, seq: u64, event: &Value) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(&Entry { seq, event: event.clone() }).map_err(std::io::Error::other)?;
+        line.push('\n');
+        // One write, so a crash tears at most this line
+        OpenOptions::new().create(true).append(true).open(self.events_path())?.write_all(line.as_bytes())
+    }
+
+    /// Mark the current turn finished (or interrupted).
+    pub fn finish(&self, state: TurnState) -> std::io::Result<()> {
+        match self.status() {
+            Some(status) => self.write_status(&TurnStatus { state, ..status }),
+            None => Ok(()),
+        }
+    }
+
+    /// The current turn's events after `after`, in order. A torn last line
+    /// is skipped.
+    pub fn since(&self, after: u64) -> Vec<(u64, Value)> {
+        self.entries().into_iter().filter(|e| e.seq > after).map(|e| (e.seq, e.event)).collect()
+    }
+
+    /// The latest turn's status, `None` if the conversation never had one.
+    pub fn status(&self) -> Option<TurnStatus> {
+        let mut status: TurnStatus = serde_json::from_str(&std::fs::read_to_string(self.status_path()).ok()?).ok()?;
+        if let Some(last) = self.entries().last() {
+            status.last_seq = status.last_seq.max(last.seq);
+        }
+        Some(status)
+    }
+
+    fn entries(&self) -> Vec<Entry> {
+        let Ok(content) = std::fs::read_to_string(self.events_path()) else {
+            return Vec::new();
+        };
+        content
+            .split_inclusive('\n')
+            .take_while(|line| line.ends_with('\n'))
+            .map_while(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    fn write_status(&self, status: &TurnStatus) -> std::io::Result<()> {
+        let path = self.status_path();
+        let tmp = path.with_extension("turn.tmp");
+        std::fs::write(&tmp, serde_json::to_string(status).map_err(std::io::Error::other)?)?;
+        std::fs::rename(tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_event_log_resume() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = EventLog::in_dir(dir.path(), "conv-1");
+        assert!(log.status().is_none());
+
+        assert_eq!(log.begin_turn("Fix the parser").unwrap(), 1);
+        for seq in 1..=3 {
+            log.append(seq, &json!({ "method": "agent_text_chunk", "params": { "text": seq.to_string() } })).unwrap();
+        }
+        let status = log.status().unwrap();
+        assert_eq!((status.state, status.first_seq, status.last_seq), (TurnState::Running, 1, 3));
+        // The panel saw event 1 before the proxy went away
+        let missed: Vec<u64> = log.since(1).into_iter().map(|(seq, _)| seq).collect();
+        assert_eq!(missed, vec![2, 3]);
+
+        // A torn last line is ignored
+        std::fs::OpenOptions::new().append(true).open(log.events_path()).unwrap().write_all(b"{\"seq\":4,").unwrap();
+        assert_eq!(log.since(0).len(), 3);
+
+        log.finish(TurnState::Interrupted).unwrap();
+        assert_eq!(log.status().unwrap().state, TurnState::Interrupted);
+
+        // The next turn keeps numbering and drops the old events
+        assert_eq!(log.begin_turn("Now add tests").unwrap(), 4);
+        assert!(log.since(0).is_empty());
+        assert_eq!(log.status().unwrap().last_seq, 3);
+    }
+}
@@ -9,12 +9,19 @@
 //!   - AI chat (Groq Kimi-K2)
 //!
 //! No API keys needed in the IDE. Just a JWT token from SSO.
+//!
+//! Requests time out (`FORGE_SEARCH_TIMEOUT_SECS`, 30s; longer for uploads
+//! and chat) and transient failures (connection errors, timeouts, 429 and
+//! 5xx) are retried with backoff. After repeated failures the service is
+//! treated as down for a while and calls fail at once with [`Unavailable`],
+//! so callers can fall back to local search instead of waiting.
 
 use anyhow::{anyhow, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use walkdir::WalkDir;
 
@@ -30,11 +37,114 @@ pub fn client() -> &'static ForgeSearchClient {
     CLIENT.get_or_init(ForgeSearchClient::new)
 }
 
+// ── Resilience ───────────────────────────────────────────────────
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Uploads and (non-streaming) chat answers take longer than a search.
+const INDEX_TIMEOUT: Duration = Duration::from_secs(120);
+const CHAT_TIMEOUT: Duration = Duration::from_secs(300);
+/// A streamed answer that sends nothing for this long is abandoned.
+const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+/// Retries after the first attempt, for requests that are safe to repeat.
+const MAX_RETRIES: u32 = 2;
+const BACKOFF: Duration = Duration::from_millis(500);
+/// Consecutive failures that open the breaker, and how long it stays open.
+const BREAKER_THRESHOLD: u32 = 3;
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// forge-search could not be reached (or was recently found unreachable).
+#[derive(Debug, thiserror::Error)]
+#[error("forge-search is unreachable: {0}")]
+pub struct Unavailable(pub String);
+
+/// Whether `err` means the service is down, as opposed to a rejected request.
+pub fn is_unavailable(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<Unavailable>().is_some()
+}
+
+#[derive(Default)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl Breaker {
+    fn is_open(&self, now: Instant) -> bool {
+        self.open_until.is_some_and(|until| now < until)
+    }
+
+    fn record(&mut self, ok: bool, now: Instant) {
+        if ok {
+            *self = Self::default();
+            return;
+        }
+        self.failures += 1;
+        if self.failures >= BREAKER_THRESHOLD {
+            if !self.is_open(now) {
+                tracing::warn!("forge-search failed {} times in a row; using local fallbacks for {}s", self.failures, BREAKER_COOLDOWN.as_secs());
+            }
+            self.open_until = Some(now + BREAKER_COOLDOWN);
+        }
+    }
+}
+
+fn breaker() -> &'static Mutex<Breaker> {
+    static BREAKER: OnceLock<Mutex<Breaker>> = OnceLock::new();
+    BREAKER.get_or_init(Default::default)
+}
+
+/// False while recent failures have marked the service as down.
+pub fn available() -> bool {
+    !breaker().lock().is_ok_and(|b| b.is_open(Instant::now()))
+}
+
+fn record_outcome(ok: bool) {
+    if let Ok(mut breaker) = breaker().lock() {
+        breaker.record(ok, Instant::now());
+    }
+}
+
+fn request_timeout() -> Duration {
+    let secs = crate::config::var("FORGE_SEARCH_TIMEOUT_SECS")
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|&s: &u64| s > 0)
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Timeout and retries for a request to `path`. Chat turns aren't repeated.
+fn policy(path: &str) -> (Duration, u32) {
+    if path.starts_with("/chat") {
+        (CHAT_TIMEOUT, 0)
+    } else if path.starts_with("/index") {
+        (INDEX_TIMEOUT, MAX_RETRIES)
+    } else {
+        (request_timeout(), MAX_RETRIES)
+    }
+}
+
+fn is_transient(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
 /// Check if user has a forge-search auth token (sync, no network).
 pub fn is_authenticated() -> bool {
     AuthToken::exists()
 }
 
+/// The index id for the workspace at `path`: its folder name plus a hash of
+/// the canonical path, so two checkouts named `app` get separate indexes.
+pub fn workspace_id(path: &Path) -> String {
+    let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let name: String = canonical
+        .file_name()
+        .map(|n| n.to_string_lossy().chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect())
+        .unwrap_or_else(|| "default".to_string());
+    let digest = ring::digest::digest(&ring::digest::SHA256, canonical.to_string_lossy().as_bytes());
+    let hash: String = digest.as_ref()[..6].iter().map(|b| format!("{b:02x}")).collect();
+    format!("{name}-{hash}")
+}
+
 // ── Auth token persistence ───────────────────────────────────────
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
@@ -137,6 +247,13 @@ pub enum SseEvent {
     RequiresAction {
         tool_calls: Vec<ToolCallInfo>,
     },
+    /// Tokens the model call used so far (sent with or before `done`)
+    Usage {
+        #[serde(default)]
+        input_tokens: u64,
+        #[serde(default)]
+        output_tokens: u64,
+    },
     /// Final answer complete
     Done {
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -166,8 +283,8 @@ pub struct ForgeSearchClient {
 
 impl ForgeSearchClient {
     pub fn new() -> Self {
-        let base_url = std::env::var("FORGE_SEARCH_URL")
-            .unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+        let base_url = crate::config::var("FORGE_SEARCH_URL")
+            .unwrap_or_else(|| DEFAULT_API_URL.to_string());
 
         let auth = AuthToken::load();
         tracing::info!(
@@ -177,8 +294,9 @@ impl ForgeSearchClient {
         );
 
         Self {
-            http: Client::builder()
-                .connect_timeout(std::time::Duration::from_secs(30))
+            http: crate::http::client_builder()
+                .connect_timeout(Duration::from_secs(10))
+                .read_timeout(STREAM_IDLE_TIMEOUT)
                 .no_gzip()
                 .build()
                 .unwrap_or_default(),
@@ -237,16 +355,48 @@ impl ForgeSearchClient {
 
     // ── API calls ────────────────────────────────────────────────
 
-    async fn post(&self, path: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
-        let url = format!("{}{}", self.base_url, path);
-        let token = self.auth.read().await.token.clone();
+    /// Send the request `build` makes, with the timeout and retries of
+    /// [`policy`] for `path`, through the circuit breaker.
+    async fn send(&self, path: &str, build: impl Fn() -> reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let (timeout, retries) = policy(path);
+        let mut attempt = 0;
+        loop {
+            if !available() {
+                return Err(Unavailable("recent requests failed; retrying shortly".into()).into());
+            }
+            let result = build().timeout(timeout).send().await;
+            let failed = match &result {
+                Ok(resp) => is_transient(resp.status()),
+                Err(_) => true,
+            };
+            record_outcome(!failed);
+            if !failed || attempt >= retries {
+                return match result {
+                    Ok(resp) => Ok(resp),
+                    Err(e) => Err(Unavailable(e.to_string()).into()),
+                };
+            }
+            attempt += 1;
+            tracing::debug!("forge-search {path} failed, retry {attempt}/{retries}");
+            tokio::time::sleep(BACKOFF * 2u32.pow(attempt - 1)).await;
+        }
+    }
 
-        let mut req = self.http.post(&url).json(body);
-        if !token.is_empty() {
-            req = req.header("Authorization", format!("Bearer {}", token));
+    async fn authorized(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let token = self.auth.read().await.token.clone();
+        if token.is_empty() {
+            req
+        } else {
+            req.header("Authorization", format!("Bearer {}", token))
         }
+    }
 
-        let resp = req.send().await?;
+    async fn post(&self, path: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+        crate::egress::check_cloud("forge-search")?;
+        let url = format!("{}{}", self.base_url, path);
+        let req = self.authorized(self.http.post(&url).json(&crate::redaction::outbound(body))).await;
+
+        let resp = self.send(path, || req.try_clone().expect("JSON requests can be cloned")).await?;
 
         if resp.status() == 401 {
             return Err(anyhow!("Not authenticated — please sign in"));
@@ -261,21 +411,25 @@ impl ForgeSearchClient {
     }
 
     async fn get(&self, path: &str) -> Result<serde_json::Value> {
+        crate::egress::check_cloud("forge-search")?;
         let url = format!("{}{}", self.base_url, path);
-        let token = self.auth.read().await.token.clone();
+        let req = self.authorized(self.http.get(&url)).await;
 
-        let mut req = self.http.get(&url);
-        if !token.is_empty() {
-            req = req.header("Authorization", format!("Bearer {}", token));
-        }
-
-        let resp = req.send().await?;
+        let resp = self.send(path, || req.try_clone().expect("GET requests can be cloned")).await?;
         Ok(resp.json().await?)
     }
 
+    async fn delete(&self, path: &str) -> Result<reqwest::Response> {
+        crate::egress::check_cloud("forge-search")?;
+        let url = format!("{}{}", self.base_url, path);
+        let req = self.authorized(self.http.delete(&url)).await;
+        self.send(path, || req.try_clone().expect("DELETE requests can be cloned")).await
+    }
+
     // ── Search ───────────────────────────────────────────────────
 
     pub async fn search(&self, workspace_id: &str, query: &str, top_k: usize) -> Result<serde_json::Value> {
+        crate::index_state::update(workspace_id, |state| state.last_used = Some(crate::index_state::now()));
         self.post("/search", &serde_json::json!({
             "workspace_id": workspace_id,
             "query": query,
@@ -296,12 +450,78 @@ impl ForgeSearchClient {
         })).await
     }
 
+    /// Drop files (workspace-relative paths) that no longer exist from the index.
+    pub async fn remove_files(&self, workspace_id: &str, paths: &[String]) -> Result<serde_json::Value> {
+        self.post("/index", &serde_json::json!({
+            "workspace_id": workspace_id,
+            "files": [],
+            "deleted": paths,
+        })).await
+    }
+
     /// Index a single file (called on save)
     pub async fn index_file(&self, workspace_id: &str, path: &str, content: &str) -> Result<serde_json::Value> {
-        self.index_files(workspace_id, vec![serde_json::json!({
+        let quota = crate::index_state::quota_bytes();
+        if crate::index_state::get(workspace_id).size_bytes + content.len() as u64 > quota {
+            return Err(anyhow!(
+                "index quota of {} MB reached for {workspace_id}; re-index the workspace or raise FORGE_INDEX_QUOTA_MB",
+                quota / (1024 * 1024)
+            ));
+        }
+        let result = self.index_files(workspace_id, vec![serde_json::json!({
             "path": path,
             "content": content,
-        })]).await
+            "chunks": crate::chunking::chunk_file(Path::new(path), content),
+        })]).await;
+        crate::index_state::update(workspace_id, |state| match &result {
+            Ok(_) => {
+                state.size_bytes += content.len() as u64;
+                state.last_refresh = Some(crate::index_state::now());
+                state.last_error = None;
+            }
+            Err(e) => state.last_error = Some(e.to_string()),
+        });
+        result
+    }
+
+    /// Re-index `path` (a file or directory inside `workdir`).
+    pub async fn reindex_path(&self, workspace_id: &str, workdir: &Path, path: &Path) -> Result<IndexResult> {
+        let path = if path.is_absolute() { path.to_path_buf() } else { workdir.join(path) };
+        let rel = path.strip_prefix(workdir).map_err(|_| anyhow!("{} is outside the workspace", path.display()))?;
+        if path.is_file() {
+            let content = std::fs::read_to_string(&path)?;
+            let resp = self.index_file(workspace_id, &rel.display().to_string(), &content).await?;
+            return Ok(IndexResult::from_response(&resp));
+        }
+        let mut files = collect_source_files(&path);
+        for file in &mut files {
+            let inner = file["path"].as_str().unwrap_or_default().to_string();
+            file["path"] = serde_json::Value::String(rel.join(inner).display().to_string());
+        }
+        let result = self.index_batches(workspace_id, files, |_, _| {}).await?;
+        crate::index_state::update(workspace_id, |state| state.last_refresh = Some(crate::index_state::now()));
+        Ok(result)
+    }
+
+    /// Delete the workspace's index on the server.
+    pub async fn clear_index(&self, workspace_id: &str) -> Result<serde_json::Value> {
+        let resp = self.delete(&format!("/index/{workspace_id}")).await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("API error {}", resp.status()));
+        }
+        crate::index_state::reset(workspace_id);
+        Ok(resp.json().await.unwrap_or_default())
+    }
+
+    /// Delete the indexes of the least recently used workspaces beyond
+    /// `FORGE_INDEX_MAX_WORKSPACES`, never `current`'s.
+    async fn evict_unused(&self, current: &str) {
+        for id in crate::index_state::evictions(crate::index_state::max_workspaces(), current) {
+            match self.clear_index(&id).await {
+                Ok(_) => tracing::info!("Deleted the index of unused workspace {id}"),
+                Err(e) => tracing::warn!("Could not delete the index of {id}: {e}"),
+            }
+        }
     }
 
     // ── Trace ────────────────────────────────────────────────────
@@ -369,20 +589,24 @@ impl ForgeSearchClient {
     /// - Incremental text output
     pub async fn chat_stream(&self, body: &serde_json::Value) -> Result<impl futures_util::Stream<Item = SseEvent>> {
         use futures_util::StreamExt;
+        crate::egress::check_cloud("The cloud agent")?;
         
+        if !available() {
+            return Err(Unavailable("recent requests failed; retrying shortly".into()).into());
+        }
         let url = format!("{}/chat/stream", self.base_url);
-        let token = self.auth.read().await.token.clone();
 
-        let mut req = self.http.post(&url)
-            .json(body)
+        let req = self.http.post(&url)
+            .json(&crate::redaction::outbound(body))
             .header("Accept", "text/event-stream")
             .header("Accept-Encoding", "identity"); // Disable compression — SSE must not be gzip'd
-        
-        if !token.is_empty() {
-            req = req.header("Authorization", format!("Bearer {}", token));
-        }
+        let req = self.authorized(req).await;
 
-        let resp = req.send().await?;
+        // No overall timeout: the stream lasts the whole turn. The client's
+        // read timeout ends it if the server goes quiet.
+        let resp = req.send().await;
+        record_outcome(resp.as_ref().is_ok_and(|r| !is_transient(r.status())));
+        let resp = resp.map_err(|e| Unavailable(e.to_string()))?;
         let status = resp.status();
         
         if !status.is_success() {
@@ -422,6 +646,14 @@ impl ForgeSearchClient {
             })
         };
 
+        // Session recording (see crate::replay)
+        let recorder = crate::replay::Recorder::for_request(body);
+        let stream = stream.inspect(move |event| {
+            if let Some(recorder) = &recorder {
+                recorder.event(event);
+            }
+        });
+
         Ok(stream)
     }
 
@@ -442,6 +674,7 @@ impl ForgeSearchClient {
             .and_then(|v| v.as_i64())
             .unwrap_or(0);
 
+        crate::index_state::update(workspace_id, |state| state.symbols = total_nodes);
         Ok((total_nodes > 0, total_nodes))
     }
 
@@ -464,20 +697,43 @@ impl ForgeSearchClient {
     where
         F: FnMut(usize, usize),
     {
+        crate::index_state::touch(workspace_id, workdir);
+        self.evict_unused(workspace_id).await;
+
         // Collect source files
         let files = collect_source_files(workdir);
+        crate::index_state::update(workspace_id, |state| state.size_bytes = 0);
+        let result = self.index_batches(workspace_id, files, &mut on_progress).await?;
+        crate::index_state::update(workspace_id, |state| {
+            state.symbols = result.nodes_created as i64;
+            state.last_refresh = Some(crate::index_state::now());
+        });
+
+        tracing::info!(
+            "Indexing complete: {} files, {} symbols, {} edges",
+            result.files_indexed, result.nodes_created, result.relationships_created
+        );
 
+        Ok(result)
+    }
+
+    /// Send `files` in batches, keeping the workspace's [`crate::index_state`] current.
+    async fn index_batches<F>(
+        &self,
+        workspace_id: &str,
+        files: Vec<serde_json::Value>,
+        mut on_progress: F,
+    ) -> Result<IndexResult>
+    where
+        F: FnMut(usize, usize),
+    {
         if files.is_empty() {
-            return Ok(IndexResult {
-                files_indexed: 0,
-                nodes_created: 0,
-                relationships_created: 0,
-                embeddings_generated: 0,
-            });
+            return Ok(IndexResult::default());
         }
 
         let total = files.len();
         tracing::info!("Scanning {} files for workspace {}", total, workspace_id);
+        crate::index_state::update(workspace_id, |state| state.pending = total);
 
         // Send in batches of 50 for better progress feedback
         const BATCH_SIZE: usize = 50;
@@ -486,33 +742,33 @@ impl ForgeSearchClient {
 
         for batch in files.chunks(BATCH_SIZE) {
             let batch_vec: Vec<serde_json::Value> = batch.to_vec();
-            
+            let bytes: usize = batch.iter().filter_map(|f| f["content"].as_str()).map(str::len).sum();
+
             match self.index_files(workspace_id, batch_vec).await {
                 Ok(resp) => {
-                    result.files_indexed += resp.get("files_indexed")
-                        .and_then(|v| v.as_i64()).unwrap_or(0) as usize;
-                    result.nodes_created += resp.get("nodes_created")
-                        .and_then(|v| v.as_i64()).unwrap_or(0) as usize;
-                    result.relationships_created += resp.get("relationships_created")
-                        .and_then(|v| v.as_i64()).unwrap_or(0) as usize;
-                    result.embeddings_generated += resp.get("embeddings_generated")
-                        .and_then(|v| v.as_i64()).unwrap_or(0) as usize;
+                    let batch_result = IndexResult::from_response(&resp);
+                    crate::index_state::update(workspace_id, |state| {
+                        state.files_indexed += batch_result.files_indexed;
+                        state.size_bytes += bytes as u64;
+                        state.last_error = None;
+                    });
+                    result.files_indexed += batch_result.files_indexed;
+                    result.nodes_created += batch_result.nodes_created;
+                    result.relationships_created += batch_result.relationships_created;
+                    result.embeddings_generated += batch_result.embeddings_generated;
                 }
                 Err(e) => {
                     tracing::warn!("Batch index failed: {}", e);
+                    crate::index_state::update(workspace_id, |state| state.last_error = Some(e.to_string()));
                     // Continue with other batches
                 }
             }
 
             sent += batch.len();
+            crate::index_state::update(workspace_id, |state| state.pending = total - sent);
             on_progress(sent, total);
         }
 
-        tracing::info!(
-            "Indexing complete: {} files, {} symbols, {} edges",
-            result.files_indexed, result.nodes_created, result.relationships_created
-        );
-
         Ok(result)
     }
 
@@ -527,15 +783,7 @@ impl ForgeSearchClient {
 
     /// Stop file watching for a workspace.
     pub async fn stop_watching(&self, workspace_id: &str) -> Result<serde_json::Value> {
-        let url = format!("{}/watch/{}", self.base_url, workspace_id);
-        let token = self.auth.read().await.token.clone();
-
-        let mut req = self.http.delete(&url);
-        if !token.is_empty() {
-            req = req.header("Authorization", format!("Bearer {}", token));
-        }
-
-        let resp = req.send().await?;
+        let resp = self.delete(&format!("/watch/{workspace_id}")).await?;
         Ok(resp.json().await?)
     }
 
@@ -690,6 +938,13 @@ fn parse_sse_event_with_type(event_type: &str, json_str: &str) -> Result<SseEven
                 .unwrap_or_default();
             Ok(SseEvent::RequiresAction { tool_calls })
         }
+        "usage" => {
+            let data: serde_json::Value = serde_json::from_str(json_str)?;
+            Ok(SseEvent::Usage {
+                input_tokens: data.get("input_tokens").and_then(|v| v.as_u64()).unwrap_or(0),
+                output_tokens: data.get("output_tokens").and_then(|v| v.as_u64()).unwrap_or(0),
+            })
+        }
         "done" => {
             let data: serde_json::Value = serde_json::from_str(json_str)?;
             Ok(SseEvent::Done {
@@ -723,6 +978,18 @@ pub struct IndexResult {
     pub embeddings_generated: usize,
 }
 
+impl IndexResult {
+    fn from_response(resp: &serde_json::Value) -> Self {
+        let count = |key: &str| resp.get(key).and_then(|v| v.as_i64()).unwrap_or(0) as usize;
+        Self {
+            files_indexed: count("files_indexed"),
+            nodes_created: count("nodes_created"),
+            relationships_created: count("relationships_created"),
+            embeddings_generated: count("embeddings_generated"),
+        }
+    }
+}
+
 // ── File Collection ──────────────────────────────────────────────
 
 /// Directories to skip when scanning for source files.
@@ -763,8 +1030,11 @@ pub fn is_indexable_file(name: &str) -> bool {
 }
 
 /// Collect source files from a directory for indexing.
+/// Stops at the workspace's upload quota ([`crate::index_state::quota_bytes`]).
 pub fn collect_source_files(workdir: &Path) -> Vec<serde_json::Value> {
     let mut files = Vec::new();
+    let quota = crate::index_state::quota_bytes();
+    let mut total_bytes = 0u64;
 
     for entry in WalkDir::new(workdir)
         .max_depth(8)
@@ -814,8 +1084,20 @@ pub fn collect_source_files(workdir: &Path) -> Vec<serde_json::Value> {
                 }
             }
 
+            total_bytes += content.len() as u64;
+            if total_bytes > quota {
+                tracing::warn!(
+                    "Index quota of {} MB reached at {} files; the rest of {} isn't indexed",
+                    quota / (1024 * 1024),
+                    files.len(),
+                    workdir.display()
+                );
+                break;
+            }
+
             files.push(serde_json::json!({
                 "path": rel_path.display().to_string(),
+                "chunks": crate::chunking::chunk_file(rel_path, &content),
                 "content": content,
             }));
         }
@@ -914,4 +1196,24 @@ data: {"answer": "Task completed successfully"}
             panic!("Expected Done event, got {:?}", events[5]);
         }
     }
+
+    #[test]
+    fn test_breaker_opens_and_recovers() {
+        let now = Instant::now();
+        let mut breaker = Breaker::default();
+        for _ in 1..BREAKER_THRESHOLD {
+            breaker.record(false, now);
+        }
+        assert!(!breaker.is_open(now));
+        breaker.record(false, now);
+        assert!(breaker.is_open(now));
+        assert!(!breaker.is_open(now + BREAKER_COOLDOWN));
+        breaker.record(true, now);
+        assert!(!breaker.is_open(now) && breaker.failures == 0);
+
+        assert_eq!(policy("/chat/stream").1, 0);
+        assert!(is_transient(reqwest::StatusCode::BAD_GATEWAY));
+        assert!(!is_transient(reqwest::StatusCode::UNAUTHORIZED));
+        assert!(is_unavailable(&Unavailable("down".into()).into()));
+    }
 }
@@ -0,0 +1,240 @@
+//! Formatting the agent's edits when it completes a task.
+//!
+//! With `format_on_completion = true` (or `FORGE_FORMAT_ON_COMPLETION=1`),
+//! the files touched in a message are run through the project's formatter
+//! once the model calls `attempt_completion`, before the turn's changes are
+//! collected, so the formatting lands in the same reviewable change-set.
+//!
+//! A `.pre-commit-config.yaml` takes precedence (`pre-commit run --files`);
+//! otherwise files go to rustfmt, prettier (when the project configures it),
+//! `ruff format` or gofmt by extension. Formatters that aren't installed are
+//! skipped.
+
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Formatter {
+    PreCommit,
+    Rustfmt,
+    Prettier,
+    Ruff,
+    Gofmt,
+}
+
+impl Formatter {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PreCommit => "pre-commit",
+            Self::Rustfmt => "rustfmt",
+            Self::Prettier => "prettier",
+            Self::Ruff => "ruff format",
+            Self::Gofmt => "gofmt",
+        }
+    }
+
+    fn for_extension(ext: &str, workspace: &Path) -> Option<Self> {
+        match ext {
+            "rs" => Some(Self::Rustfmt),
+            "py" | "pyi" => Some(Self::Ruff),
+            "go" => Some(Self::Gofmt),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "css" | "scss" | "less" | "html" | "vue" | "svelte" | "json"
+            | "md" | "yaml" | "yml"
+                if uses_prettier(workspace) =>
+            {
+                Some(Self::Prettier)
+            }
+            _ => None,
+        }
+    }
+
+    /// Not used for rustfmt, see [`rustfmt`].
+    fn command(&self, workspace: &Path, files: &[String]) -> Command {
+        let mut command = match self {
+            Self::Rustfmt => unreachable!("rustfmt formats one file at a time"),
+            Self::PreCommit => {
+                let mut c = Command::new("pre-commit");
+                c.args(["run", "--files"]);
+                c
+            }
+            Self::Prettier => {
+                let mut c = Command::new("npx");
+                c.args(["--no-install", "prettier", "--write"]);
+                c
+            }
+            Self::Ruff => {
+                let mut c = Command::new("ruff");
+                c.arg("format");
+                c
+            }
+            Self::Gofmt => {
+                let mut c = Command::new("gofmt");
+                c.arg("-w");
+                c
+            }
+        };
+        command.args(files).current_dir(workspace);
+        command
+    }
+}
+
+/// Whether formatting on completion is turned on (off by default).
+pub fn enabled() -> bool {
+    crate::config::var("FORGE_FORMAT_ON_COMPLETION")
+        .is_some_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+}
+
+/// Which formatter handles which of `paths` (workspace-relative). Files
+/// without a formatter, or that no longer exist, are left out.
+pub fn plan(workspace: &Path, paths: &[String]) -> Vec<(Formatter, Vec<String>)> {
+    let mut plan: Vec<(Formatter, Vec<String>)> = Vec::new();
+    let pre_commit = workspace.join(".pre-commit-config.yaml").is_file();
+    for path in paths {
+        if !workspace.join(path).is_file() {
+            continue;
+        }
+        let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or_default();
+        let formatter = if pre_commit { Some(Formatter::PreCommit) } else { Formatter::for_extension(ext, workspace) };
+        let Some(formatter) = formatter else {
+            continue;
+        };
+        match plan.iter_mut().find(|(f, _)| *f == formatter) {
+            Some((_, files)) if !files.contains(path) => files.push(path.clone()),
+            Some(_) => {}
+            None => plan.push((formatter, vec![path.clone()])),
+        }
+    }
+    plan
+}
+
+/// Format `paths` in place; returns the formatters that ran. Failures
+/// (missing tools, syntax errors) are logged and otherwise ignored.
+pub fn format_files(workspace: &Path, paths: &[String]) -> Vec<Formatter> {
+    let mut ran = Vec::new();
+    for (formatter, files) in plan(workspace, paths) {
+        if formatter == Formatter::Rustfmt {
+            let edition = rust_edition(workspace);
+            if files.iter().map(|f| rustfmt(workspace, f, &edition)).fold(false, |any, ok| any | ok) {
+                ran.push(formatter);
+            }
+            continue;
+        }
+        match formatter.command(workspace, &files).output() {
+            // pre-commit exits 1 when its hooks changed files
+            Ok(out) if out.status.success() || formatter == Formatter::PreCommit => ran.push(formatter),
+            Ok(out) => tracing::warn!(
+                "{} failed on {} file(s): {}",
+                formatter.name(),
+                files.len(),
+                String::from_utf8_lossy(&out.stderr).trim()
+            ),
+            Err(e) => tracing::debug!("{} not run: {e}", formatter.name()),
+        }
+    }
+    ran
+}
+
+/// Format one Rust file through stdin: given a path, rustfmt would also
+/// reformat every module the file declares.
+fn rustfmt(workspace: &Path, path: &str, edition: &str) -> bool {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let full = workspace.join(path);
+    let Ok(source) = std::fs::read_to_string(&full) else {
+        return false;
+    };
+    let child = Command::new("rustfmt")
+        .args(["--edition", edition, "--emit", "stdout"])
+        // rustfmt.toml is looked up from here
+        .current_dir(full.parent().unwrap_or(workspace))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            tracing::debug!("rustfmt not run: {e}");
+            return false;
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(source.as_bytes());
+    }
+    match child.wait_with_output() {
+        Ok(out) if out.status.success() => {
+            let formatted = String::from_utf8_lossy(&out.stdout);
+            formatted == source || std::fs::write(&full, formatted.as_bytes()).is_ok()
+        }
+        Ok(out) => {
+            tracing::warn!("rustfmt failed on {path}: {}", String::from_utf8_lossy(&out.stderr).trim());
+            false
+        }
+        Err(_) => false,
+    }
+}
+
+/// Whether the project has prettier set up (config file or dependency).
+fn uses_prettier(workspace: &Path) -> bool {
+    const CONFIGS: &[&str] = &[
+        ".prettierrc", ".prettierrc.json", ".prettierrc.yaml", ".prettierrc.yml", ".prettierrc.js", ".prettierrc.cjs",
+        ".prettierrc.mjs", ".prettierrc.toml", "prettier.config.js", "prettier.config.cjs", "prettier.config.mjs",
+    ];
+    CONFIGS.iter().any(|c| workspace.join(c).is_file())
+        || std::fs::read_to_string(workspace.join("package.json")).is_ok_and(|p| p.contains("\"prettier\""))
+}
+
+/// The workspace's Rust edition from its Cargo.toml, 2021 if unstated.
+fn rust_edition(workspace: &Path) -> String {
+    std::fs::read_to_string(workspace.join("Cargo.toml"))
+        .ok()
+        .and_then(|toml| {
+            toml.lines()
+                .find_map(|l| l.trim().strip_prefix("edition")?.trim().strip_prefix('=').map(|v| v.trim().trim_matches('"').to_string()))
+        })
+        .filter(|e| !e.is_empty())
+        .unwrap_or_else(|| "2021".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for file in ["src/main.rs", "src/lib.rs", "app.py", "web/index.ts", "README.md"] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let paths: Vec<String> =
+            ["src/main.rs", "app.py", "web/index.ts", "src/lib.rs", "src/main.rs", "gone.rs", "README.md"]
+                .iter()
+                .map(|p| p.to_string())
+                .collect();
+
+        // No prettier set up: TypeScript and Markdown are left alone
+        let planned = plan(root, &paths);
+        assert_eq!(
+            planned,
+            vec![
+                (Formatter::Rustfmt, vec!["src/main.rs".to_string(), "src/lib.rs".to_string()]),
+                (Formatter::Ruff, vec!["app.py".to_string()]),
+            ]
+        );
+
+        std::fs::write(root.join("package.json"), r#"{"devDependencies": {"prettier": "^3"}}"#).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "[package]\nedition = \"2018\"\n").unwrap();
+        assert_eq!(plan(root, &paths)[2], (Formatter::Prettier, vec!["web/index.ts".to_string(), "README.md".to_string()]));
+        assert_eq!(rust_edition(root), "2018");
+
+        std::fs::write(root.join(".pre-commit-config.yaml"), "repos: []\n").unwrap();
+        let planned = plan(root, &paths);
+        assert_eq!(planned.len(), 1);
+        assert_eq!(planned[0].0, Formatter::PreCommit);
+        assert_eq!(planned[0].1.len(), 5);
+    }
+}
@@ -0,0 +1,85 @@
+//! Proxy and CA settings shared by every outbound HTTP client.
+//!
+//! Read from the config (see [`crate::config`]) with the usual environment
+//! variables as fallback:
+//! - `http_proxy`: proxy URL for all requests (`HTTPS_PROXY` / `HTTP_PROXY`)
+//! - `no_proxy`: comma-separated hosts and domains that bypass it (`NO_PROXY`)
+//! - `ca_bundle`: PEM file with extra root certificates, e.g. a corporate CA
+//!
+//! The IDE side still uses reqwest 0.11, so besides [`client_builder`] the
+//! settings are exposed as plain values for it to apply.
+
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkSettings {
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl NetworkSettings {
+    pub fn from_config() -> Self {
+        let config = crate::config::active();
+        let setting = |key: &str, env: &[&str]| {
+            config
+                .get(key)
+                .map(String::from)
+                .or_else(|| env.iter().find_map(|name| std::env::var(name).ok()))
+                .filter(|v| !v.trim().is_empty())
+        };
+        Self {
+            proxy: setting(
+                "http_proxy",
+                &["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"],
+            ),
+            no_proxy: setting("no_proxy", &["NO_PROXY", "no_proxy"]),
+            ca_bundle: setting("ca_bundle", &[]).map(|p| PathBuf::from(shellexpand::tilde(&p).as_ref())),
+        }
+    }
+
+    /// Contents of the CA bundle, if one is configured and readable.
+    pub fn ca_pem(&self) -> Option<Vec<u8>> {
+        let path = self.ca_bundle.as_ref()?;
+        match std::fs::read(path) {
+            Ok(pem) => Some(pem),
+            Err(e) => {
+                tracing::warn!("Cannot read CA bundle {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if let Some(url) = &self.proxy {
+            match reqwest::Proxy::all(url) {
+                Ok(proxy) => {
+                    let no_proxy = self.no_proxy.as_deref().and_then(reqwest::NoProxy::from_string);
+                    builder = builder.proxy(proxy.no_proxy(no_proxy));
+                }
+                Err(e) => tracing::warn!("Ignoring invalid proxy URL {url}: {e}"),
+            }
+        }
+        if let Some(pem) = self.ca_pem() {
+            match reqwest::Certificate::from_pem_bundle(&pem) {
+                Ok(certs) => {
+                    for cert in certs {
+                        builder = builder.add_root_certificate(cert);
+                    }
+                }
+                Err(e) => tracing::warn!("Ignoring invalid CA bundle: {e}"),
+            }
+        }
+        builder
+    }
+}
+
+/// A client builder with the configured proxy and CA certificates.
+pub fn client_builder() -> reqwest::ClientBuilder {
+    NetworkSettings::from_config().apply(reqwest::Client::builder())
+}
+
+/// A client with the configured proxy and CA certificates.
+pub fn client() -> reqwest::Client {
+    client_builder().build().unwrap_or_default()
+}
@@ -0,0 +1,233 @@
+//! Translations of user-facing agent strings (CLI output, approval prompts).
+//!
+//! The UI language is the `language` setting (`FORGE_LANGUAGE`), else the
+//! locale from `LC_ALL` / `LC_MESSAGES` / `LANG`; unknown languages fall back
+//! to English. Text sent to the model stays English; `respond_in`
+//! (`FORGE_RESPOND_IN`, e.g. `German`) asks the model to answer in another
+//! language through the turn template.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Es,
+    De,
+    Fr,
+    Ja,
+    Zh,
+}
+
+impl Lang {
+    /// `de_DE.UTF-8`, `de-DE`, `de` -> `De`.
+    pub fn parse(locale: &str) -> Option<Self> {
+        let code = locale.split(['_', '-', '.']).next()?.to_ascii_lowercase();
+        Some(match code.as_str() {
+            "en" => Self::En,
+            "es" => Self::Es,
+            "de" => Self::De,
+            "fr" => Self::Fr,
+            "ja" => Self::Ja,
+            "zh" => Self::Zh,
+            _ => return None,
+        })
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The UI language.
+pub fn ui_language() -> Lang {
+    crate::config::var("FORGE_LANGUAGE")
+        .or_else(|| {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .find_map(|name| std::env::var(name).ok().filter(|v| !v.is_empty()))
+        })
+        .and_then(|locale| Lang::parse(&locale))
+        .unwrap_or(Lang::En)
+}
+
+/// Language the model should answer in, if configured.
+pub fn response_language() -> Option<String> {
+    crate::config::var("FORGE_RESPOND_IN").filter(|v| !v.trim().is_empty())
+}
+
+/// key -> [en, es, de, fr, ja, zh]. `{0}`, `{1}` are arguments.
+const MESSAGES: &[(&str, [&str; 6])] = &[
+    ("approval.run_command", [
+        "Run command: {0}",
+        "Ejecutar comando: {0}",
+        "Befehl ausführen: {0}",
+        "Exécuter la commande : {0}",
+        "コマンドを実行: {0}",
+        "运行命令：{0}",
+    ]),
+    ("approval.start_background", [
+        "Start background process: {0}",
+        "Iniciar proceso en segundo plano: {0}",
+        "Hintergrundprozess starten: {0}",
+        "Démarrer un processus en arrière-plan : {0}",
+        "バックグラウンドプロセスを開始: {0}",
+        "启动后台进程：{0}",
+    ]),
+    ("approval.rename", [
+        "Rename symbol to: {0}",
+        "Renombrar símbolo a: {0}",
+        "Symbol umbenennen in: {0}",
+        "Renommer le symbole en : {0}",
+        "シンボル名を変更: {0}",
+        "将符号重命名为：{0}",
+    ]),
+    ("approval.generate_tests", [
+        "Generate and run tests for: {0}",
+        "Generar y ejecutar pruebas para: {0}",
+        "Tests generieren und ausführen für: {0}",
+        "Générer et exécuter des tests pour : {0}",
+        "テストを生成して実行: {0}",
+        "生成并运行测试：{0}",
+    ]),
+    ("approval.risky_tool", [
+        "Execute risky tool: {0}",
+        "Ejecutar herramienta de riesgo: {0}",
+        "Riskantes Werkzeug ausführen: {0}",
+        "Exécuter un outil à risque : {0}",
+        "リスクのあるツールを実行: {0}",
+        "执行高风险工具：{0}",
+    ]),
+    ("approval.prompt", [
+        "{0} — Accept to run, Reject to skip",
+        "{0} — Aceptar para ejecutar, Rechazar para omitir",
+        "{0} — Annehmen zum Ausführen, Ablehnen zum Überspringen",
+        "{0} — Accepter pour exécuter, Refuser pour ignorer",
+        "{0} — 承認で実行、拒否でスキップ",
+        "{0} — 接受以运行，拒绝以跳过",
+    ]),
+    ("approval.rejected", [
+        "Command rejected by user",
+        "Comando rechazado por el usuario",
+        "Befehl vom Benutzer abgelehnt",
+        "Commande refusée par l'utilisateur",
+        "ユーザーがコマンドを拒否しました",
+        "用户拒绝了该命令",
+    ]),
+    ("approval.accepted", [
+        "Changes accepted",
+        "Cambios aceptados",
+        "Änderungen angenommen",
+        "Modifications acceptées",
+        "変更を承認しました",
+        "已接受更改",
+    ]),
+    ("status.executing", [
+        "Executing command...",
+        "Ejecutando comando...",
+        "Befehl wird ausgeführt...",
+        "Exécution de la commande...",
+        "コマンドを実行中...",
+        "正在执行命令...",
+    ]),
+    ("cli.not_signed_in", [
+        "Not signed in to forge-search. Some features may be limited.",
+        "No has iniciado sesión en forge-search. Algunas funciones pueden estar limitadas.",
+        "Nicht bei forge-search angemeldet. Einige Funktionen sind eingeschränkt.",
+        "Non connecté à forge-search. Certaines fonctionnalités peuvent être limitées.",
+        "forge-search にサインインしていません。一部の機能が制限されます。",
+        "未登录 forge-search，部分功能可能受限。",
+    ]),
+    ("cli.sign_in_at", [
+        "Sign in at: {0}",
+        "Inicia sesión en: {0}",
+        "Anmelden unter: {0}",
+        "Connectez-vous sur : {0}",
+        "サインイン: {0}",
+        "登录地址：{0}",
+    ]),
+    ("cli.indexing", [
+        "Syncing workspace with forge-search...",
+        "Sincronizando el espacio de trabajo con forge-search...",
+        "Arbeitsbereich wird mit forge-search synchronisiert...",
+        "Synchronisation de l'espace de travail avec forge-search...",
+        "ワークスペースを forge-search と同期中...",
+        "正在与 forge-search 同步工作区...",
+    ]),
+    ("cli.indexed", [
+        "Indexed {0} files ({1} symbols) in {2}s",
+        "Indexados {0} archivos ({1} símbolos) en {2}s",
+        "{0} Dateien ({1} Symbole) in {2}s indexiert",
+        "{0} fichiers indexés ({1} symboles) en {2}s",
+        "{0} ファイル（{1} シンボル）を {2} 秒でインデックス化",
+        "已索引 {0} 个文件（{1} 个符号），用时 {2} 秒",
+    ]),
+    ("cli.index_failed", [
+        "Indexing failed: {0}",
+        "Error de indexación: {0}",
+        "Indexierung fehlgeschlagen: {0}",
+        "Échec de l'indexation : {0}",
+        "インデックス作成に失敗: {0}",
+        "索引失败：{0}",
+    ]),
+    ("cli.sending", [
+        "Sending prompt to forge-search...",
+        "Enviando la solicitud a forge-search...",
+        "Anfrage wird an forge-search gesendet...",
+        "Envoi de la requête à forge-search...",
+        "forge-search にプロンプトを送信中...",
+        "正在向 forge-search 发送请求...",
+    ]),
+    ("cli.done", [
+        "Response received in {0}s",
+        "Respuesta recibida en {0}s",
+        "Antwort nach {0}s erhalten",
+        "Réponse reçue en {0}s",
+        "{0} 秒で応答を受信",
+        "{0} 秒内收到响应",
+    ]),
+    ("cli.no_prompt", [
+        "No prompt given",
+        "No se indicó ninguna solicitud",
+        "Keine Anfrage angegeben",
+        "Aucune requête fournie",
+        "プロンプトが指定されていません",
+        "未提供提示",
+    ]),
+];
+
+/// `key` in `lang` with `args` substituted; the key itself when unknown.
+pub fn tr_in(lang: Lang, key: &str, args: &[&str]) -> String {
+    let Some((_, texts)) = MESSAGES.iter().find(|(k, _)| *k == key) else {
+        return key.to_string();
+    };
+    let mut text = texts[lang.index()].to_string();
+    for (i, arg) in args.iter().enumerate() {
+        text = text.replace(&format!("{{{i}}}"), arg);
+    }
+    text
+}
+
+/// `key` in the UI language.
+pub fn tr(key: &str, args: &[&str]) -> String {
+    tr_in(ui_language(), key, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(Lang::parse("de_DE.UTF-8"), Some(Lang::De));
+        assert_eq!(Lang::parse("pt-BR"), None);
+        assert_eq!(tr_in(Lang::En, "approval.run_command", &["ls"]), "Run command: ls");
+        assert_eq!(tr_in(Lang::Fr, "cli.indexed", &["3", "10", "0.5"]), "3 fichiers indexés (10 symboles) en 0.5s");
+        assert_eq!(tr_in(Lang::Ja, "no.such.key", &[]), "no.such.key");
+        // Every message has every placeholder in every language
+        for (key, texts) in MESSAGES {
+            for i in 0..3 {
+                let placeholder = format!("{{{i}}}");
+                let in_en = texts[0].contains(&placeholder);
+                assert!(texts.iter().all(|t| t.contains(&placeholder) == in_en), "{key} {placeholder}");
+            }
+        }
+    }
+}
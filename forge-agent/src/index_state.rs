@@ -0,0 +1,175 @@
+//! What this machine knows about each workspace's semantic index.
+//!
+//! forge-search only reports a symbol count, so the client keeps its own
+//! record per workspace id (see [`crate::forge_search::workspace_id`]):
+//! files sent and indexed, files still queued, bytes uploaded, when the last
+//! scan finished, how the last request failed, and when the workspace was
+//! last used. The record is kept in `index-state.json` in the config
+//! directory, and drives the limits on what the server stores:
+//!
+//! - `FORGE_INDEX_QUOTA_MB`: source uploaded per workspace (50)
+//! - `FORGE_INDEX_MAX_WORKSPACES`: indexes kept; the least recently used
+//!   beyond this are deleted (20)
+//!
+//! The IDE's index status widget reads it through `IndexStatus`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "index-state.json";
+const DEFAULT_QUOTA_MB: u64 = 50;
+const DEFAULT_MAX_WORKSPACES: usize = 20;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexState {
+    /// The workspace folder.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Files the server accepted, over every scan since the last clear.
+    #[serde(default)]
+    pub files_indexed: usize,
+    /// Files collected but not yet sent.
+    #[serde(default, skip_serializing)]
+    pub pending: usize,
+    /// Symbols the server reported for the workspace.
+    #[serde(default)]
+    pub symbols: i64,
+    /// Bytes of source uploaded since the last full scan.
+    #[serde(default)]
+    pub size_bytes: u64,
+    /// Unix seconds of the last finished scan or re-index.
+    #[serde(default)]
+    pub last_refresh: Option<u64>,
+    /// Unix seconds the workspace was last searched or indexed.
+    #[serde(default)]
+    pub last_used: Option<u64>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+fn state_file() -> Option<PathBuf> {
+    if cfg!(test) {
+        return None;
+    }
+    dirs::config_dir().map(|d| d.join("forge-ide").join(STATE_FILE))
+}
+
+fn states() -> &'static Mutex<HashMap<String, IndexState>> {
+    static STATES: OnceLock<Mutex<HashMap<String, IndexState>>> = OnceLock::new();
+    STATES.get_or_init(|| {
+        let saved = state_file()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Mutex::new(saved)
+    })
+}
+
+fn save(states: &HashMap<String, IndexState>) {
+    let Some(path) = state_file() else { return };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(states) {
+        if let Err(e) = std::fs::write(&path, json) {
+            tracing::debug!("Could not save {}: {e}", path.display());
+        }
+    }
+}
+
+/// The recorded state of `workspace_id` (default if never indexed here).
+pub fn get(workspace_id: &str) -> IndexState {
+    states().lock().ok().and_then(|s| s.get(workspace_id).cloned()).unwrap_or_default()
+}
+
+/// Every workspace indexed from this machine, most recently used first.
+pub fn list() -> Vec<(String, IndexState)> {
+    let mut all: Vec<(String, IndexState)> =
+        states().lock().map(|s| s.iter().map(|(id, st)| (id.clone(), st.clone())).collect()).unwrap_or_default();
+    all.sort_by(|a, b| b.1.last_used.cmp(&a.1.last_used).then_with(|| a.0.cmp(&b.0)));
+    all
+}
+
+pub fn update(workspace_id: &str, f: impl FnOnce(&mut IndexState)) {
+    if let Ok(mut states) = states().lock() {
+        f(states.entry(workspace_id.to_string()).or_default());
+        save(&states);
+    }
+}
+
+/// Note that the workspace at `path` is in use (keeps it out of eviction).
+pub fn touch(workspace_id: &str, path: &Path) {
+    update(workspace_id, |state| {
+        state.path = Some(path.to_path_buf());
+        state.last_used = Some(now());
+    });
+}
+
+/// Forget `workspace_id`, after its index was deleted.
+pub fn reset(workspace_id: &str) {
+    if let Ok(mut states) = states().lock() {
+        states.remove(workspace_id);
+        save(&states);
+    }
+}
+
+/// The least recently used workspaces beyond the `max` most recent, which
+/// should be deleted. `keep` is never among them.
+pub fn evictions(max: usize, keep: &str) -> Vec<String> {
+    list().into_iter().map(|(id, _)| id).filter(|id| id != keep).skip(max.saturating_sub(1)).collect()
+}
+
+/// Bytes of source a workspace may upload.
+pub fn quota_bytes() -> u64 {
+    let mb = crate::config::var("FORGE_INDEX_QUOTA_MB").and_then(|v| v.trim().parse().ok()).unwrap_or(DEFAULT_QUOTA_MB);
+    mb * 1024 * 1024
+}
+
+/// Workspace indexes kept before the least recently used are deleted.
+pub fn max_workspaces() -> usize {
+    crate::config::var("FORGE_INDEX_MAX_WORKSPACES")
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_MAX_WORKSPACES)
+}
+
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_and_reset() {
+        let id = "index-state-test";
+        assert_eq!(get(id), IndexState::default());
+        update(id, |s| s.pending = 3);
+        update(id, |s| {
+            s.pending -= 1;
+            s.files_indexed += 1;
+        });
+        assert_eq!((get(id).pending, get(id).files_indexed), (2, 1));
+        reset(id);
+        assert_eq!(get(id), IndexState::default());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        for (id, used) in [("lru-a", 100), ("lru-b", 300), ("lru-c", 200)] {
+            update(id, |s| s.last_used = Some(now() + 1_000_000 + used));
+        }
+        // Keep two: the current workspace plus the most recent other one
+        let evict = evictions(2, "lru-a");
+        assert!(evict.contains(&"lru-c".to_string()) && !evict.contains(&"lru-a".to_string()));
+        assert!(!evict.contains(&"lru-b".to_string()));
+        for id in ["lru-a", "lru-b", "lru-c"] {
+            reset(id);
+        }
+    }
+}
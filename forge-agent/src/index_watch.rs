@@ -0,0 +1,188 @@
+//! Keep the semantic index in step with the workspace on disk.
+//!
+//! The IDE's file watcher reports every change (saves, `git checkout`,
+//! generated files, edits from other programs) through [`files_changed`].
+//! Changes are collected per workspace until it has been quiet for
+//! [`DEBOUNCE`] (at most [`MAX_DELAY`] after the first), then the changed
+//! files are uploaded and the removed ones dropped from the index. Only
+//! workspaces already indexed from this machine are updated; a workspace
+//! nobody indexed doesn't start uploading because a file changed.
+//!
+//! `FORGE_INDEX_WATCH=false` turns this off, leaving re-indexing on save.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::forge_search::{is_indexable_file, should_skip_dir};
+
+/// Quiet time before a batch is sent.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+/// Longest a change waits while others keep coming.
+const MAX_DELAY: Duration = Duration::from_secs(10);
+const POLL: Duration = Duration::from_millis(250);
+/// Changes sent per request; a larger batch (a branch switch) is split.
+const MAX_BATCH: usize = 50;
+/// Files larger than this aren't indexed (matches the full scan).
+const MAX_FILE_SIZE: u64 = 100_000;
+
+struct Pending {
+    paths: HashSet<PathBuf>,
+    first: Instant,
+    last: Instant,
+}
+
+fn queues() -> &'static Mutex<HashMap<PathBuf, Pending>> {
+    static QUEUES: OnceLock<Mutex<HashMap<PathBuf, Pending>>> = OnceLock::new();
+    QUEUES.get_or_init(Default::default)
+}
+
+/// Whether watcher-driven indexing is on.
+pub fn enabled() -> bool {
+    crate::config::var("FORGE_INDEX_WATCH").is_none_or(|v| !matches!(v.trim(), "0" | "false" | "off"))
+}
+
+/// Queue `paths` (absolute, inside `workdir`) for re-indexing.
+pub fn files_changed(workdir: &Path, paths: impl IntoIterator<Item = PathBuf>) {
+    if !enabled() {
+        return;
+    }
+    let paths: Vec<PathBuf> = paths.into_iter().filter(|p| indexable(workdir, p)).collect();
+    if paths.is_empty() {
+        return;
+    }
+    let Ok(mut queues) = queues().lock() else { return };
+    let now = Instant::now();
+    match queues.get_mut(workdir) {
+        Some(pending) => {
+            pending.paths.extend(paths);
+            pending.last = now;
+        }
+        None => {
+            queues.insert(workdir.to_path_buf(), Pending { paths: paths.into_iter().collect(), first: now, last: now });
+            let workdir = workdir.to_path_buf();
+            std::thread::spawn(move || debounce(workdir));
+        }
+    }
+}
+
+/// Source files outside skipped directories (`.git`, `node_modules`, ...).
+fn indexable(workdir: &Path, path: &Path) -> bool {
+    let Ok(rel) = path.strip_prefix(workdir) else {
+        return false;
+    };
+    let mut components = rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>();
+    let Some(name) = components.pop() else {
+        return false;
+    };
+    components.iter().all(|dir| !should_skip_dir(dir)) && is_indexable_file(&name)
+}
+
+fn debounce(workdir: PathBuf) {
+    let paths = loop {
+        std::thread::sleep(POLL);
+        let Ok(mut queues) = queues().lock() else { return };
+        let Some(pending) = queues.get(&workdir) else { return };
+        let now = Instant::now();
+        if now.duration_since(pending.last) >= DEBOUNCE || now.duration_since(pending.first) >= MAX_DELAY {
+            break queues.remove(&workdir).map(|p| p.paths).unwrap_or_default();
+        }
+    };
+    flush(&workdir, paths);
+}
+
+/// Changed files to upload (`{path, content, chunks}`) and the relative
+/// paths of removed ones.
+fn build_update(workdir: &Path, paths: HashSet<PathBuf>) -> (Vec<serde_json::Value>, Vec<String>) {
+    let mut files = Vec::new();
+    let mut removed = Vec::new();
+    let mut paths: Vec<PathBuf> = paths.into_iter().collect();
+    paths.sort();
+    for path in paths {
+        let Ok(rel) = path.strip_prefix(workdir) else { continue };
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        match std::fs::metadata(&path) {
+            Ok(meta) if meta.is_file() && meta.len() <= MAX_FILE_SIZE => {
+                if let Ok(content) = std::fs::read_to_string(&path) {
+                    files.push(serde_json::json!({
+                        "path": rel,
+                        "chunks": crate::chunking::chunk_file(Path::new(&rel), &content),
+                        "content": content,
+                    }));
+                }
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => removed.push(rel),
+            Err(_) => {}
+        }
+    }
+    (files, removed)
+}
+
+fn flush(workdir: &Path, paths: HashSet<PathBuf>) {
+    let workspace_id = crate::forge_search::workspace_id(workdir);
+    if !crate::forge_search::is_authenticated()
+        || crate::egress::local_only()
+        || !crate::trust::is_trusted(workdir)
+        || crate::index_state::get(&workspace_id).last_refresh.is_none()
+    {
+        return;
+    }
+    let (files, removed) = build_update(workdir, paths);
+    if files.is_empty() && removed.is_empty() {
+        return;
+    }
+    let Ok(rt) = tokio::runtime::Builder::new_current_thread().enable_all().build() else {
+        return;
+    };
+    rt.block_on(async {
+        let client = crate::forge_search::client();
+        if !removed.is_empty() {
+            if let Err(e) = client.remove_files(&workspace_id, &removed).await {
+                tracing::warn!("Removing {} files from the index failed: {e}", removed.len());
+            }
+        }
+        for batch in files.chunks(MAX_BATCH) {
+            let bytes: u64 = batch.iter().map(|f| f["content"].as_str().map_or(0, str::len) as u64).sum();
+            let result = client.index_files(&workspace_id, batch.to_vec()).await;
+            crate::index_state::update(&workspace_id, |state| match &result {
+                Ok(_) => {
+                    state.size_bytes += bytes;
+                    state.last_refresh = Some(crate::index_state::now());
+                    state.last_error = None;
+                }
+                Err(e) => state.last_error = Some(e.to_string()),
+            });
+            match result {
+                Ok(_) => tracing::debug!("Re-indexed {} changed files in {workspace_id}", batch.len()),
+                Err(e) => tracing::warn!("Re-indexing changed files failed: {e}"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn main() {}\n").unwrap();
+
+        assert!(indexable(root, &root.join("src/lib.rs")));
+        assert!(!indexable(root, &root.join("node_modules/x/index.js")));
+        assert!(!indexable(root, &root.join(".git/HEAD")));
+        assert!(!indexable(Path::new("/elsewhere"), &root.join("src/lib.rs")));
+
+        let paths = [root.join("src/lib.rs"), root.join("src/gone.rs")].into_iter().collect();
+        let (files, removed) = build_update(root, paths);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0]["path"], "src/lib.rs");
+        assert_eq!(files[0]["chunks"][0]["symbols"][0], "main");
+        assert_eq!(removed, ["src/gone.rs"]);
+    }
+}
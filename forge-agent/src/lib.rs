@@ -1,10 +1,50 @@
+pub mod agent_loop;
+pub mod api;
+pub mod approvals;
+pub mod attachments;
+pub mod auth;
 pub mod bridge;
 pub mod bridge_standalone;
+pub mod changelog;
+pub mod checkpoint;
+pub mod chunking;
+pub mod config;
+pub mod edit_format;
+pub mod egress;
+pub mod encryption;
+pub mod event_log;
 pub mod loop_detection;
+pub mod lru_cache;
 pub mod output_masking;
 pub mod tools;
+pub mod tui;
 pub mod forge_search;
+pub mod http;
+pub mod i18n;
+pub mod index_state;
+pub mod index_watch;
+pub mod progress;
 pub mod project_memory;
+pub mod prompt_template;
+pub mod redaction;
+pub mod replay;
+pub mod secrets;
+pub mod speech;
+pub mod sql;
+pub mod syntax;
+pub mod self_correction;
+pub mod session;
+pub mod staging;
+pub mod trust;
+pub mod usage;
+pub mod manifest;
+pub mod model_routing;
+pub mod onboarding;
+pub mod models;
+pub mod docs_cache;
+pub mod formatting;
+pub mod profiling;
+pub mod workspace_roots;
 
 // Re-export key types
 pub use bridge::ProxyBridge;
@@ -0,0 +1,312 @@
+//! Size-bounded in-memory caches.
+//!
+//! The agent's in-memory caches (model conversations, read fingerprints,
+//! workspace snapshots) live as long as the proxy, so each is an
+//! [`LruCache`] with a cap on entries and on approximate bytes: past
+//! either, the least recently used entries go. Every cache reports hits,
+//! misses, evictions and its size to [`metrics`].
+//!
+//! `FORGE_CACHE_LIMITS` overrides the caps per cache, e.g.
+//! `conversations=32mb,read_fingerprints=5000`: a size (`kb`, `mb`, `gb`)
+//! caps bytes, a plain number caps entries.
+
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Counters of one cache, shared with [`metrics`].
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    entries: AtomicUsize,
+    bytes: AtomicUsize,
+}
+
+/// A cache's counters at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheMetrics {
+    pub name: &'static str,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: usize,
+    pub max_entries: usize,
+    pub max_bytes: usize,
+}
+
+impl std::fmt::Display for CacheMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lookups = self.hits + self.misses;
+        let hit_rate = if lookups == 0 { 0.0 } else { 100.0 * self.hits as f64 / lookups as f64 };
+        write!(
+            f,
+            "{}: {}/{} entries, {}/{} KB, {hit_rate:.0}% hits of {lookups}, {} evicted",
+            self.name,
+            self.entries,
+            self.max_entries,
+            self.bytes / 1024,
+            self.max_bytes / 1024,
+            self.evictions
+        )
+    }
+}
+
+type Registry = Mutex<Vec<(&'static str, usize, usize, Arc<Counters>)>>;
+
+fn registry() -> &'static Registry {
+    static INSTANCE: OnceLock<Registry> = OnceLock::new();
+    INSTANCE.get_or_init(Default::default)
+}
+
+/// Every cache created so far, by creation order.
+pub fn metrics() -> Vec<CacheMetrics> {
+    registry()
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, max_entries, max_bytes, c)| CacheMetrics {
+            name: *name,
+            hits: c.hits.load(Ordering::Relaxed),
+            misses: c.misses.load(Ordering::Relaxed),
+            evictions: c.evictions.load(Ordering::Relaxed),
+            entries: c.entries.load(Ordering::Relaxed),
+            bytes: c.bytes.load(Ordering::Relaxed),
+            max_entries: *max_entries,
+            max_bytes: *max_bytes,
+        })
+        .collect()
+}
+
+struct Slot<V> {
+    value: V,
+    bytes: usize,
+    tick: u64,
+}
+
+/// A map that keeps the most recently used entries within `max_entries`
+/// and `max_bytes`, as measured by its weigher.
+pub struct LruCache<K, V> {
+    name: &'static str,
+    slots: HashMap<K, Slot<V>>,
+    /// Keys by last use, oldest first.
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    bytes: usize,
+    max_entries: usize,
+    max_bytes: usize,
+    weigh: fn(&K, &V) -> usize,
+    counters: Arc<Counters>,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// A cache named `name` (for [`metrics`] and `FORGE_CACHE_LIMITS`) with
+    /// these default caps; `weigh` estimates an entry's bytes.
+    pub fn new(name: &'static str, max_entries: usize, max_bytes: usize, weigh: fn(&K, &V) -> usize) -> Self {
+        let (max_entries, max_bytes) =
+            configured_limits(crate::config::var("FORGE_CACHE_LIMITS").as_deref(), name, max_entries, max_bytes);
+        let counters = Arc::new(Counters::default());
+        registry().lock().unwrap().push((name, max_entries, max_bytes, counters.clone()));
+        Self {
+            name,
+            slots: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            bytes: 0,
+            max_entries: max_entries.max(1),
+            max_bytes,
+            weigh,
+            counters,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    fn touch(&mut self, key: &K) {
+        self.tick += 1;
+        if let Some(slot) = self.slots.get_mut(key) {
+            self.order.remove(&slot.tick);
+            slot.tick = self.tick;
+            self.order.insert(self.tick, key.clone());
+        }
+    }
+
+    fn lookup<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let found = self.slots.get_key_value(key).map(|(k, _)| k.clone());
+        let counter = if found.is_some() { &self.counters.hits } else { &self.counters.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        if let Some(key) = &found {
+            self.touch(key);
+        }
+        found
+    }
+
+    /// The entry for `key`, now the most recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let key = self.lookup(key)?;
+        self.slots.get(&key).map(|slot| &slot.value)
+    }
+
+    /// Like [`get`](Self::get); call [`reweigh`](Self::reweigh) after
+    /// changing the entry's size.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let key = self.lookup(key)?;
+        self.slots.get_mut(&key).map(|slot| &mut slot.value)
+    }
+
+    /// Whether `key` is cached, without using it.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.slots.contains_key(key)
+    }
+
+    /// The entry for `key`, inserted with `default` if missing.
+    pub fn get_or_insert_with(&mut self, key: K, default: impl FnOnce() -> V) -> &mut V {
+        if self.lookup(&key).is_none() {
+            self.insert(key.clone(), default());
+        }
+        &mut self.slots.get_mut(&key).expect("just inserted").value
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.remove(&key);
+        let bytes = (self.weigh)(&key, &value);
+        self.tick += 1;
+        self.order.insert(self.tick, key.clone());
+        self.slots.insert(key.clone(), Slot { value, bytes, tick: self.tick });
+        self.bytes += bytes;
+        self.evict(Some(&key));
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.slots.remove(key)?;
+        self.order.remove(&slot.tick);
+        self.bytes -= slot.bytes;
+        self.publish();
+        Some(slot.value)
+    }
+
+    /// Re-measure `key` after its value changed, evicting others if the
+    /// cache is now over its caps.
+    pub fn reweigh<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some((k, slot)) = self.slots.get_key_value(key) else {
+            return;
+        };
+        let (bytes, k) = ((self.weigh)(k, &slot.value), k.clone());
+        let slot = self.slots.get_mut(key).expect("looked up above");
+        self.bytes = self.bytes - slot.bytes + bytes;
+        slot.bytes = bytes;
+        self.evict(Some(&k));
+    }
+
+    /// Drop the least recently used entries until within the caps, except
+    /// `keep` (the entry just written), even if it alone is over.
+    fn evict(&mut self, keep: Option<&K>) {
+        while self.slots.len() > self.max_entries || self.bytes > self.max_bytes {
+            let Some(oldest) = self.order.values().find(|k| Some(*k) != keep).cloned() else {
+                break;
+            };
+            tracing::debug!("Cache {} full ({} entries, {} bytes), evicting the oldest", self.name, self.slots.len(), self.bytes);
+            self.remove(&oldest);
+            self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        self.publish();
+    }
+
+    fn publish(&self) {
+        self.counters.entries.store(self.slots.len(), Ordering::Relaxed);
+        self.counters.bytes.store(self.bytes, Ordering::Relaxed);
+    }
+}
+
+/// The caps of cache `name` from `FORGE_CACHE_LIMITS`-style `limits`.
+fn configured_limits(limits: Option<&str>, name: &str, max_entries: usize, max_bytes: usize) -> (usize, usize) {
+    let (mut entries, mut bytes) = (max_entries, max_bytes);
+    for (cache, limit) in limits.unwrap_or_default().split(',').filter_map(|l| l.split_once('=')) {
+        if cache.trim() != name {
+            continue;
+        }
+        let limit = limit.trim().to_ascii_lowercase();
+        let size = [("gb", 1 << 30), ("mb", 1 << 20), ("kb", 1 << 10), ("b", 1)]
+            .iter()
+            .find_map(|(suffix, unit)| Some(limit.strip_suffix(suffix)?.trim().parse::<usize>().ok()? * unit));
+        match (size, limit.parse::<usize>()) {
+            (Some(size), _) => bytes = size,
+            (None, Ok(n)) => entries = n,
+            _ => tracing::warn!("FORGE_CACHE_LIMITS: can't read '{limit}' for {name}"),
+        }
+    }
+    (entries, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_cache() {
+        let mut cache: LruCache<String, String> = LruCache::new("test_lru", 3, 20, |k, v| k.len() + v.len());
+        for key in ["a", "b", "c"] {
+            cache.insert(key.to_string(), "12345".to_string());
+        }
+        // Using "a" makes "b" the oldest
+        assert!(cache.get("a").is_some());
+        cache.insert("d".to_string(), "x".to_string());
+        assert!(!cache.contains_key("b") && cache.contains_key("a"));
+        assert_eq!(cache.len(), 3);
+
+        // 6 + 6 + 2 bytes; growing "d" past 20 pushes out the oldest
+        cache.get_mut("d").unwrap().push_str("xxxxxxxx");
+        cache.reweigh("d");
+        assert!(!cache.contains_key("c"));
+        assert!(cache.get("zzz").is_none());
+        // The newest entry stays even when it alone is over the cap
+        cache.insert("huge".to_string(), "x".repeat(50));
+        assert_eq!(cache.len(), 1);
+        *cache.get_or_insert_with("e".to_string(), String::new) += "1";
+        assert_eq!(cache.get("e").map(String::as_str), Some("1"));
+
+        let m = metrics().into_iter().find(|m| m.name == "test_lru").unwrap();
+        assert_eq!((m.entries, m.bytes, m.evictions), (1, 1, 5));
+        assert_eq!((m.hits, m.misses), (3, 2));
+        assert!(m.to_string().starts_with("test_lru: 1/3 entries"));
+
+        let limits = Some("conversations=32mb, test=7 ,test=2kb,other=1");
+        assert_eq!(configured_limits(limits, "test", 1, 1), (7, 2048));
+        assert_eq!(configured_limits(limits, "conversations", 10, 1), (10, 32 << 20));
+        assert_eq!(configured_limits(None, "test", 5, 6), (5, 6));
+    }
+}
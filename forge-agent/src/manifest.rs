@@ -0,0 +1,299 @@
+//! Workspace dependency resolution from package manifests.
+//!
+//! Reads Cargo.toml / package.json / pyproject.toml (plus their lockfiles when
+//! present) so documentation lookups can be matched to the exact versions the
+//! project uses, and libraries the project doesn't depend on can be skipped.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+/// Package ecosystem a dependency belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+    Python,
+}
+
+/// A direct dependency declared in a workspace manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dependency {
+    pub name: String,
+    /// Resolved version from the lockfile, falling back to the manifest
+    /// requirement (e.g. `^1.2`) when no lockfile exists.
+    pub version: Option<String>,
+    pub ecosystem: Ecosystem,
+}
+
+// ══════════════════════════════════════════════════════════════════
+//  RESOLUTION
+// ══════════════════════════════════════════════════════════════════
+
+/// Collect the direct dependencies declared at the workspace root.
+pub fn workspace_dependencies(workdir: &Path) -> Vec<Dependency> {
+    let mut deps = Vec::new();
+    deps.extend(cargo_dependencies(workdir));
+    deps.extend(npm_dependencies(workdir));
+    deps.extend(python_dependencies(workdir));
+    deps
+}
+
+/// Dependencies whose name is mentioned in `query`.
+///
+/// Matching is case-insensitive and treats `-` and `_` as equivalent, so
+/// "tokio_util" in a question matches the `tokio-util` crate.
+pub fn relevant_dependencies<'a>(query: &str, deps: &'a [Dependency]) -> Vec<&'a Dependency> {
+    let words: Vec<String> = query
+        .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == '@' || c == '/' || c == '.'))
+        .map(|w| normalize(w.trim_matches('.')))
+        .filter(|w| w.len() > 1)
+        .collect();
+
+    deps.iter()
+        .filter(|d| {
+            let name = normalize(&d.name);
+            words.iter().any(|w| *w == name)
+        })
+        .collect()
+}
+
+fn normalize(name: &str) -> String {
+    name.to_lowercase().replace('_', "-")
+}
+
+fn cargo_dependencies(workdir: &Path) -> Vec<Dependency> {
+    let Ok(manifest) = std::fs::read_to_string(workdir.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Ok(manifest) = manifest.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+
+    let locked = cargo_lock_versions(workdir);
+    let mut deps = Vec::new();
+    let tables = [
+        manifest.get("dependencies"),
+        manifest.get("dev-dependencies"),
+        manifest.get("workspace").and_then(|w| w.get("dependencies")),
+    ];
+    for table in tables.into_iter().flatten().filter_map(|t| t.as_table()) {
+        for (key, spec) in table {
+            // `foo = { package = "bar" }` renames the crate
+            let name = spec
+                .get("package")
+                .and_then(|p| p.as_str())
+                .unwrap_or(key)
+                .to_string();
+            if deps.iter().any(|d: &Dependency| d.name == name) {
+                continue;
+            }
+            let requirement = match spec {
+                toml::Value::String(v) => Some(v.clone()),
+                _ => spec.get("version").and_then(|v| v.as_str()).map(String::from),
+            };
+            let version = locked.get(&name).cloned().or(requirement);
+            deps.push(Dependency { name, version, ecosystem: Ecosystem::Cargo });
+        }
+    }
+    deps
+}
+
+fn cargo_lock_versions(workdir: &Path) -> HashMap<String, String> {
+    let Ok(lock) = std::fs::read_to_string(workdir.join("Cargo.lock")) else {
+        return HashMap::new();
+    };
+    let Ok(lock) = lock.parse::<toml::Table>() else {
+        return HashMap::new();
+    };
+    let mut versions = HashMap::new();
+    for pkg in lock.get("package").and_then(|p| p.as_array()).into_iter().flatten() {
+        let (Some(name), Some(version)) = (
+            pkg.get("name").and_then(|v| v.as_str()),
+            pkg.get("version").and_then(|v| v.as_str()),
+        ) else {
+            continue;
+        };
+        // Multiple versions may be locked; the first one listed wins.
+        versions.entry(name.to_string()).or_insert_with(|| version.to_string());
+    }
+    versions
+}
+
+fn npm_dependencies(workdir: &Path) -> Vec<Dependency> {
+    let Ok(manifest) = std::fs::read_to_string(workdir.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(manifest) = serde_json::from_str::<serde_json::Value>(&manifest) else {
+        return Vec::new();
+    };
+
+    let lock = std::fs::read_to_string(workdir.join("package-lock.json"))
+        .ok()
+        .and_then(|l| serde_json::from_str::<serde_json::Value>(&l).ok());
+
+    let mut deps = Vec::new();
+    for section in ["dependencies", "devDependencies", "peerDependencies"] {
+        let Some(obj) = manifest.get(section).and_then(|d| d.as_object()) else {
+            continue;
+        };
+        for (name, requirement) in obj {
+            if deps.iter().any(|d: &Dependency| &d.name == name) {
+                continue;
+            }
+            let locked = lock
+                .as_ref()
+                .and_then(|l| l.get("packages"))
+                .and_then(|p| p.get(format!("node_modules/{}", name)))
+                .and_then(|p| p.get("version"))
+                .and_then(|v| v.as_str());
+            let version = locked.or(requirement.as_str()).map(String::from);
+            deps.push(Dependency { name: name.clone(), version, ecosystem: Ecosystem::Npm });
+        }
+    }
+    deps
+}
+
+fn python_dependencies(workdir: &Path) -> Vec<Dependency> {
+    let mut deps = Vec::new();
+
+    if let Some(pyproject) = std::fs::read_to_string(workdir.join("pyproject.toml"))
+        .ok()
+        .and_then(|s| s.parse::<toml::Table>().ok())
+    {
+        // PEP 621: [project] dependencies = ["requests>=2.31", ...]
+        let pep621 = pyproject
+            .get("project")
+            .and_then(|p| p.get("dependencies"))
+            .and_then(|d| d.as_array());
+        for spec in pep621.into_iter().flatten().filter_map(|s| s.as_str()) {
+            if let Some(dep) = parse_requirement(spec) {
+                deps.push(dep);
+            }
+        }
+        // Poetry: [tool.poetry.dependencies] requests = "^2.31"
+        let poetry = pyproject
+            .get("tool")
+            .and_then(|t| t.get("poetry"))
+            .and_then(|p| p.get("dependencies"))
+            .and_then(|d| d.as_table());
+        for (name, spec) in poetry.into_iter().flatten() {
+            if name == "python" {
+                continue;
+            }
+            let version = match spec {
+                toml::Value::String(v) => Some(v.clone()),
+                _ => spec.get("version").and_then(|v| v.as_str()).map(String::from),
+            };
+            deps.push(Dependency { name: name.clone(), version, ecosystem: Ecosystem::Python });
+        }
+    }
+
+    if let Ok(requirements) = std::fs::read_to_string(workdir.join("requirements.txt")) {
+        for line in requirements.lines() {
+            if let Some(dep) = parse_requirement(line) {
+                if !deps.iter().any(|d| normalize(&d.name) == normalize(&dep.name)) {
+                    deps.push(dep);
+                }
+            }
+        }
+    }
+    deps
+}
+
+/// Parse a PEP 508 requirement like `requests[socks]>=2.31; python_version>"3"`.
+fn parse_requirement(spec: &str) -> Option<Dependency> {
+    let spec = spec.split('#').next()?.split(';').next()?.trim();
+    if spec.is_empty() || spec.starts_with('-') {
+        return None;
+    }
+    let name_end = spec
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .unwrap_or(spec.len());
+    let name = &spec[..name_end];
+    if name.is_empty() {
+        return None;
+    }
+    let rest = spec[name_end..].trim();
+    let rest = match rest.strip_prefix('[') {
+        Some(r) => r.split_once(']').map(|(_, v)| v.trim()).unwrap_or(""),
+        None => rest,
+    };
+    let version = rest
+        .strip_prefix("==")
+        .map(|v| v.trim().to_string())
+        .or_else(|| (!rest.is_empty()).then(|| rest.to_string()));
+    Some(Dependency { name: name.to_string(), version, ecosystem: Ecosystem::Python })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_cargo_with_lockfile() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"x\"\n\n[dependencies]\nserde = \"1\"\ntokio = { version = \"1\", features = [\"full\"] }\nhttp2 = { package = \"h2\", version = \"0.4\" }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("Cargo.lock"),
+            "[[package]]\nname = \"serde\"\nversion = \"1.0.200\"\n\n[[package]]\nname = \"h2\"\nversion = \"0.4.5\"\n",
+        )
+        .unwrap();
+
+        let deps = workspace_dependencies(dir.path());
+        let get = |n: &str| deps.iter().find(|d| d.name == n).unwrap().version.clone();
+        assert_eq!(get("serde").as_deref(), Some("1.0.200"));
+        assert_eq!(get("tokio").as_deref(), Some("1"));
+        assert_eq!(get("h2").as_deref(), Some("0.4.5"));
+    }
+
+    #[test]
+    fn test_npm_and_python() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"dependencies": {"react": "^18.2.0"}, "devDependencies": {"vitest": "^1.0.0"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("package-lock.json"),
+            r#"{"packages": {"node_modules/react": {"version": "18.3.1"}}}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("requirements.txt"),
+            "# comment\nrequests[socks]==2.31.0\nfastapi>=0.110 ; python_version > '3.8'\n-r other.txt\n",
+        )
+        .unwrap();
+
+        let deps = workspace_dependencies(dir.path());
+        let get = |n: &str| deps.iter().find(|d| d.name == n).unwrap().clone();
+        assert_eq!(get("react").version.as_deref(), Some("18.3.1"));
+        assert_eq!(get("vitest").version.as_deref(), Some("^1.0.0"));
+        assert_eq!(get("requests").version.as_deref(), Some("2.31.0"));
+        assert_eq!(get("fastapi").version.as_deref(), Some(">=0.110"));
+        assert_eq!(get("requests").ecosystem, Ecosystem::Python);
+        assert_eq!(deps.len(), 4);
+    }
+
+    #[test]
+    fn test_relevant_dependencies() {
+        let deps = vec![
+            Dependency { name: "tokio-util".into(), version: None, ecosystem: Ecosystem::Cargo },
+            Dependency { name: "serde".into(), version: None, ecosystem: Ecosystem::Cargo },
+            Dependency { name: "@tanstack/query".into(), version: None, ecosystem: Ecosystem::Npm },
+        ];
+        let hits = relevant_dependencies("How do I use tokio_util codecs with @tanstack/query?", &deps);
+        let names: Vec<_> = hits.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["tokio-util", "@tanstack/query"]);
+        assert!(relevant_dependencies("how does django work", &deps).is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+//! Role-based model selection: a strong model where the agent plans, cheaper
+//! ones for tool-execution turns and summaries.
+//!
+//! Configured under `[models]` (see [`crate::config`]); any role left unset
+//! falls back to `model`, and with neither the server picks its default:
+//!
+//! ```toml
+//! [models]
+//! planner = "claude-opus-4-20250514"
+//! executor = "claude-sonnet-4-20250514"
+//! summarizer = "gemini-2.5-flash"
+//! ```
+//!
+//! The chosen model is sent as `model` in the forge-search chat request.
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelRole {
+    /// Turns that decide what to do: the user's message, plan mode.
+    Planner,
+    /// Turns that only feed tool results back.
+    Executor,
+    /// Summaries of tool output and similar side requests.
+    Summarizer,
+}
+
+impl ModelRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Planner => "planner",
+            Self::Executor => "executor",
+            Self::Summarizer => "summarizer",
+        }
+    }
+
+    /// The role of a chat turn: the turn carrying the user's question (or any
+    /// turn in plan mode) plans; turns that return tool results execute.
+    pub fn for_turn(has_question: bool, plan_mode: bool) -> Self {
+        if has_question || plan_mode {
+            Self::Planner
+        } else {
+            Self::Executor
+        }
+    }
+}
+
+/// The configured model for `role`, if any.
+pub fn model_for(role: ModelRole) -> Option<String> {
+    let config = crate::config::active();
+    config
+        .get(&format!("models.{}", role.as_str()))
+        .or_else(|| config.get("model"))
+        .filter(|m| !m.is_empty())
+        .map(String::from)
+}
+
+/// Set `model` in a chat request body for `role` (left alone when unset).
+pub fn apply(body: &mut Value, role: ModelRole) {
+    if let Some(model) = model_for(role) {
+        tracing::debug!("Using {model} for the {} role", role.as_str());
+        body["model"] = Value::String(model);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_for_turn() {
+        assert_eq!(ModelRole::for_turn(true, false), ModelRole::Planner);
+        assert_eq!(ModelRole::for_turn(false, true), ModelRole::Planner);
+        assert_eq!(ModelRole::for_turn(false, false), ModelRole::Executor);
+    }
+}
@@ -0,0 +1,190 @@
+//! Model lists fetched from the providers instead of hardcoded ones.
+//!
+//! - OpenAI (and compatible gateways): `GET {openai_base_url}/models`
+//! - Anthropic: `GET /v1/models`
+//! - Gemini: `GET /v1beta/models`, keeping models that support `generateContent`
+//! - OpenRouter: the public catalog at `/api/v1/models`
+//! - Ollama: `GET {ollama_url}/api/tags` (local, no key)
+//!
+//! Lists are cached in `~/.forge/models/<provider>.json` for a day
+//! (`FORGE_MODELS_TTL_SECS`); a failed refresh falls back to the stale copy.
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const DEFAULT_TTL_SECS: u64 = 24 * 60 * 60;
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const OLLAMA_URL: &str = "http://localhost:11434";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedModels {
+    fetched_at: SystemTime,
+    models: Vec<String>,
+}
+
+fn cache_path(provider: &str) -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".forge").join("models").join(format!("{provider}.json")))
+}
+
+fn ttl() -> Duration {
+    Duration::from_secs(
+        crate::config::var("FORGE_MODELS_TTL_SECS")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TTL_SECS),
+    )
+}
+
+fn read_cache(provider: &str) -> Option<CachedModels> {
+    let content = std::fs::read_to_string(cache_path(provider)?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_cache(provider: &str, models: &[String]) {
+    let Some(path) = cache_path(provider) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let cached = CachedModels { fetched_at: SystemTime::now(), models: models.to_vec() };
+    if let Ok(json) = serde_json::to_string(&cached) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+/// The cached list for `provider` if it is still fresh (no network).
+pub fn cached_models(provider: &str) -> Option<Vec<String>> {
+    let cached = read_cache(provider)?;
+    let age = cached.fetched_at.elapsed().unwrap_or(Duration::MAX);
+    (age < ttl()).then_some(cached.models)
+}
+
+/// Models offered by `provider`, from the cache or the provider's API.
+/// Empty when neither has anything.
+pub async fn models(provider: &str, api_key: Option<&str>) -> Vec<String> {
+    if let Some(models) = cached_models(provider) {
+        return models;
+    }
+    match list_models(provider, api_key).await {
+        Ok(models) if !models.is_empty() => {
+            write_cache(provider, &models);
+            models
+        }
+        Ok(_) => Vec::new(),
+        Err(e) => {
+            tracing::debug!("Listing {provider} models failed: {e:#}");
+            read_cache(provider).map(|c| c.models).unwrap_or_default()
+        }
+    }
+}
+
+/// [`models`] for callers without an async runtime (the IDE's UI threads).
+pub fn models_blocking(provider: &str, api_key: Option<&str>) -> Vec<String> {
+    match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(rt) => rt.block_on(models(provider, api_key)),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Ask `provider` for its models, bypassing the cache.
+pub async fn list_models(provider: &str, api_key: Option<&str>) -> Result<Vec<String>> {
+    if !crate::egress::allows_llm(provider) {
+        return Err(anyhow!("{provider} is blocked in local-only mode (local_only_llm = \"ollama\")"));
+    }
+    let client = crate::http::client();
+    let key = || api_key.ok_or_else(|| anyhow!("no API key for {provider}"));
+    let config = crate::config::active();
+    let request = match provider {
+        "openai" => {
+            let base = config.get("openai_base_url").unwrap_or(OPENAI_BASE_URL);
+            client
+                .get(format!("{}/models", base.trim_end_matches('/')))
+                .bearer_auth(key()?)
+        }
+        "anthropic" => client
+            .get("https://api.anthropic.com/v1/models?limit=1000")
+            .header("x-api-key", key()?)
+            .header("anthropic-version", "2023-06-01"),
+        "gemini" => client
+            .get("https://generativelanguage.googleapis.com/v1beta/models?pageSize=1000")
+            .header("x-goog-api-key", key()?),
+        "openrouter" => client.get("https://openrouter.ai/api/v1/models"),
+        "ollama" => {
+            let base = config.get("ollama_url").unwrap_or(OLLAMA_URL);
+            client.get(format!("{}/api/tags", base.trim_end_matches('/')))
+        }
+        other => return Err(anyhow!("model listing is not supported for {other}")),
+    };
+    let resp = request.timeout(Duration::from_secs(15)).send().await?;
+    if !resp.status().is_success() {
+        return Err(anyhow!("{provider} returned {}", resp.status()));
+    }
+    let body: Value = resp.json().await?;
+    Ok(parse_models(provider, &body))
+}
+
+fn parse_models(provider: &str, body: &Value) -> Vec<String> {
+    let items = |key: &str| body.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    let mut models: Vec<String> = match provider {
+        "ollama" => items("models")
+            .iter()
+            .filter_map(|m| m.get("name")?.as_str().map(String::from))
+            .collect(),
+        "gemini" => items("models")
+            .iter()
+            .filter(|m| {
+                m.get("supportedGenerationMethods")
+                    .and_then(|v| v.as_array())
+                    .map_or(true, |methods| methods.iter().any(|x| x == "generateContent"))
+            })
+            .filter_map(|m| {
+                let name = m.get("name")?.as_str()?;
+                Some(name.strip_prefix("models/").unwrap_or(name).to_string())
+            })
+            .collect(),
+        // OpenAI, Anthropic and OpenRouter: { "data": [{ "id": ... }] }
+        _ => items("data")
+            .iter()
+            .filter_map(|m| m.get("id")?.as_str().map(String::from))
+            .collect(),
+    };
+    if provider == "openai" {
+        // Drop embedding, audio, image and moderation models
+        models.retain(|m| {
+            !["embedding", "whisper", "tts", "dall-e", "moderation", "davinci", "babbage"]
+                .iter()
+                .any(|skip| m.contains(skip))
+        });
+    }
+    models.sort();
+    models.dedup();
+    models
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_models() {
+        let openai = json!({ "data": [{ "id": "gpt-4o" }, { "id": "text-embedding-3-small" }, { "id": "o3-mini" }] });
+        assert_eq!(parse_models("openai", &openai), vec!["gpt-4o", "o3-mini"]);
+
+        let gemini = json!({ "models": [
+            { "name": "models/gemini-2.5-pro", "supportedGenerationMethods": ["generateContent"] },
+            { "name": "models/text-embedding-004", "supportedGenerationMethods": ["embedContent"] },
+        ]});
+        assert_eq!(parse_models("gemini", &gemini), vec!["gemini-2.5-pro"]);
+
+        let ollama = json!({ "models": [{ "name": "qwen2.5-coder:7b" }, { "name": "llama3.2:latest" }] });
+        assert_eq!(parse_models("ollama", &ollama), vec!["llama3.2:latest", "qwen2.5-coder:7b"]);
+
+        let openrouter = json!({ "data": [{ "id": "anthropic/claude-sonnet-4" }] });
+        assert_eq!(parse_models("openrouter", &openrouter), vec!["anthropic/claude-sonnet-4"]);
+    }
+}
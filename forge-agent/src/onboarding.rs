@@ -0,0 +1,582 @@
+//! "Explain this repository": an onboarding document for a workspace.
+//!
+//! Combines the workspace's size and languages, a map of its modules (the
+//! directories with a package manifest) and how they depend on each other,
+//! the entry points (`main` functions, server bootstraps), its run commands
+//! and its main dependencies, with a Mermaid diagram of the architecture.
+//! Built by `forge-cli onboard` and the IDE's "Explain This Repository".
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+use crate::manifest::{self, Dependency, Ecosystem};
+use crate::tools::workspace_diff::{self, WorkspaceSummary};
+
+/// Files looked at; the report says when it stopped early.
+const MAX_FILES: usize = 20_000;
+/// Larger files are counted but not read.
+const MAX_READ_BYTES: u64 = 256 * 1024;
+const MAX_ENTRY_POINTS: usize = 30;
+/// Entry points drawn in the diagram.
+const MAX_DIAGRAM_ENTRY_POINTS: usize = 10;
+/// Manifests deeper than this don't make a module.
+const MAX_MODULE_DEPTH: usize = 3;
+
+/// Manifest file names and the kind of package they make.
+const MANIFESTS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Cargo"),
+    ("package.json", "npm"),
+    ("go.mod", "Go"),
+    ("pyproject.toml", "Python"),
+    ("setup.py", "Python"),
+    ("pom.xml", "Maven"),
+    ("build.gradle", "Gradle"),
+    ("build.gradle.kts", "Gradle"),
+];
+
+/// Path components whose files are never entry points.
+const NOT_ENTRY_DIRS: &[&str] =
+    &["tests", "test", "examples", "benches", "fixtures", "testdata", "node_modules", "vendor"];
+
+/// A way to run something in the workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunCommand {
+    pub name: String,
+    pub command: String,
+    /// File it was found in.
+    pub source: String,
+}
+
+/// A directory with a package manifest (or, without any, a top-level
+/// directory), with what's under it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Module {
+    /// Relative to the workspace; empty for the root.
+    pub path: String,
+    pub name: String,
+    /// "Cargo", "npm", ...; `None` for plain directories.
+    pub kind: Option<&'static str>,
+    pub files: usize,
+    pub lines: u64,
+    /// Language -> lines.
+    pub languages: BTreeMap<&'static str, u64>,
+    /// Paths of the modules this one depends on.
+    pub depends_on: Vec<String>,
+}
+
+impl Module {
+    /// The language with the most lines.
+    pub fn language(&self) -> Option<&'static str> {
+        self.languages.iter().max_by_key(|(_, lines)| **lines).map(|(lang, _)| *lang)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Main,
+    Server,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPoint {
+    pub path: String,
+    /// 1-indexed.
+    pub line: usize,
+    pub kind: EntryKind,
+    pub language: &'static str,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub name: String,
+    pub summary: WorkspaceSummary,
+    /// Whether the walk stopped at [`MAX_FILES`].
+    pub truncated: bool,
+    pub modules: Vec<Module>,
+    pub entry_points: Vec<EntryPoint>,
+    pub dependencies: Vec<Dependency>,
+    pub run_commands: Vec<RunCommand>,
+}
+
+/// Build the report for `workspace`. `run_commands` are the ones the caller
+/// detected; when empty, the workspace's manifests are read for some.
+pub fn build(workspace: &Path, run_commands: Vec<RunCommand>) -> Report {
+    let name = workspace.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut report = Report {
+        name,
+        dependencies: manifest::workspace_dependencies(workspace),
+        run_commands: if run_commands.is_empty() { manifest_run_commands(workspace) } else { run_commands },
+        ..Default::default()
+    };
+
+    let mut files: Vec<(String, u64, Option<&'static str>)> = Vec::new();
+    let mut manifests: Vec<(String, &'static str)> = Vec::new();
+    let walker = ignore::WalkBuilder::new(workspace).hidden(true).git_ignore(true).build();
+    for entry in walker.filter_map(|e| e.ok()) {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if files.len() >= MAX_FILES {
+            report.truncated = true;
+            break;
+        }
+        let Ok(rel) = entry.path().strip_prefix(workspace) else {
+            continue;
+        };
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        let file_name = rel.rsplit('/').next().unwrap_or(&rel);
+        if let Some((_, kind)) = MANIFESTS.iter().find(|(m, _)| *m == file_name) {
+            let dir = rel.rsplit_once('/').map_or("", |(dir, _)| dir).to_string();
+            if dir.split('/').filter(|c| !c.is_empty()).count() <= MAX_MODULE_DEPTH
+                && !manifests.iter().any(|(d, _)| *d == dir)
+            {
+                manifests.push((dir, *kind));
+            }
+        }
+
+        let ext = Path::new(&rel).extension().and_then(|e| e.to_str()).unwrap_or_default();
+        let language = workspace_diff::language(ext);
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        let content = (size <= MAX_READ_BYTES).then(|| std::fs::read(entry.path()).ok()).flatten();
+        let lines = content.as_deref().map_or(0, |c| {
+            if c.iter().take(8192).any(|b| *b == 0) { 0 } else { count_lines(c) }
+        });
+        if let (Some(language), Some(content)) = (language, &content) {
+            if report.entry_points.len() < MAX_ENTRY_POINTS && may_be_entry_point(&rel) {
+                if let Some(entry) = find_entry_point(&rel, language, &String::from_utf8_lossy(content)) {
+                    report.entry_points.push(entry);
+                }
+            }
+        }
+        files.push((rel, lines, language));
+    }
+
+    report.summary.total_files = files.len();
+    for (_, lines, language) in &files {
+        report.summary.total_lines += lines;
+        if let Some(language) = language {
+            let entry = report.summary.languages.entry(language).or_default();
+            entry.0 += 1;
+            entry.1 += lines;
+        }
+    }
+    report.modules = modules(workspace, &files, &manifests);
+    report.entry_points.sort_by(|a, b| a.path.cmp(&b.path));
+    report
+}
+
+fn count_lines(content: &[u8]) -> u64 {
+    let newlines = content.iter().filter(|b| **b == b'\n').count() as u64;
+    newlines + u64::from(!content.is_empty() && !content.ends_with(b"\n"))
+}
+
+fn may_be_entry_point(path: &str) -> bool {
+    !path.split('/').any(|c| NOT_ENTRY_DIRS.contains(&c))
+        && !path.contains("_test.")
+        && !path.contains(".test.")
+        && !path.contains(".spec.")
+}
+
+/// The first server bootstrap in `content`, else its `main`.
+fn find_entry_point(path: &str, language: &'static str, content: &str) -> Option<EntryPoint> {
+    let (main, server): (&[&str], &[&str]) = match language {
+        "Rust" => (
+            &["fn main(", "async fn main("],
+            &["HttpServer::new(", "axum::serve(", "Server::bind(", "warp::serve(", "rocket::build("],
+        ),
+        "Go" => (&["func main()"], &["http.ListenAndServe(", "grpc.NewServer(", ".ListenAndServe("]),
+        "Python" => (
+            &["if __name__ == \"__main__\"", "if __name__ == '__main__'"],
+            &["uvicorn.run(", "app.run(", "serve_forever(", "web.run_app("],
+        ),
+        "TypeScript" | "JavaScript" => {
+            (&[], &["app.listen(", "server.listen(", "createServer(", "Bun.serve(", "Deno.serve("])
+        }
+        "Java" | "Kotlin" => (
+            &["public static void main(", "fun main("],
+            &["SpringApplication.run(", "runApplication<", "embeddedServer("],
+        ),
+        "C#" => (&["static void Main(", "static async Task Main("], &["WebApplication.CreateBuilder("]),
+        "C" | "C++" => (&["int main("], &[]),
+        "Swift" | "Dart" => (&["func main(", "void main("], &[]),
+        _ => return None,
+    };
+    let find = |patterns: &[&str]| {
+        content.lines().position(|line| {
+            let line = line.trim_start();
+            !line.starts_with("//") && !line.starts_with('#') && patterns.iter().any(|p| line.contains(p))
+        })
+    };
+    let (line, kind) = match find(server) {
+        Some(line) => (line, EntryKind::Server),
+        None => (find(main)?, EntryKind::Main),
+    };
+    Some(EntryPoint { path: path.to_string(), line: line + 1, kind, language })
+}
+
+/// Modules from the manifest directories (top-level directories when only
+/// the root has one), each file counted in the deepest one holding it.
+fn modules(
+    workspace: &Path,
+    files: &[(String, u64, Option<&'static str>)],
+    manifests: &[(String, &'static str)],
+) -> Vec<Module> {
+    let mut modules: Vec<Module> = manifests
+        .iter()
+        .map(|(dir, kind)| Module {
+            path: dir.clone(),
+            name: package_name(&workspace.join(dir), kind).unwrap_or_else(|| dir_name(workspace, dir)),
+            kind: Some(*kind),
+            ..Default::default()
+        })
+        .collect();
+    if modules.iter().all(|m| m.path.is_empty()) {
+        let mut dirs: Vec<&str> = files.iter().filter_map(|(f, _, _)| f.split_once('/').map(|(d, _)| d)).collect();
+        dirs.sort_unstable();
+        dirs.dedup();
+        for dir in dirs {
+            if !modules.iter().any(|m| m.path == dir) {
+                modules.push(Module { path: dir.to_string(), name: dir.to_string(), ..Default::default() });
+            }
+        }
+    }
+    if !modules.iter().any(|m| m.path.is_empty()) {
+        modules.push(Module { name: dir_name(workspace, ""), ..Default::default() });
+    }
+
+    for (file, lines, language) in files {
+        let Some(module) = modules
+            .iter_mut()
+            .filter(|m| m.path.is_empty() || file.starts_with(&format!("{}/", m.path)))
+            .max_by_key(|m| m.path.len())
+        else {
+            continue;
+        };
+        module.files += 1;
+        module.lines += lines;
+        if let Some(language) = language {
+            *module.languages.entry(language).or_default() += lines;
+        }
+    }
+    // The root only matters if it has a manifest or files of its own
+    modules.retain(|m| !m.path.is_empty() || m.kind.is_some() || m.files > 0);
+
+    let paths: Vec<String> = modules.iter().map(|m| m.path.clone()).collect();
+    let names: HashMap<String, String> = modules.iter().map(|m| (m.name.clone(), m.path.clone())).collect();
+    for module in &mut modules {
+        let deps = match module.kind {
+            Some("Cargo") => cargo_path_dependencies(workspace, &module.path),
+            Some("npm") => npm_workspace_dependencies(workspace, &module.path, &names),
+            _ => Vec::new(),
+        };
+        module.depends_on = deps.into_iter().filter(|d| *d != module.path && paths.contains(d)).collect();
+        module.depends_on.sort();
+        module.depends_on.dedup();
+    }
+    modules.sort_by(|a, b| a.path.cmp(&b.path));
+    modules
+}
+
+fn dir_name(workspace: &Path, dir: &str) -> String {
+    if dir.is_empty() {
+        workspace.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| ".".to_string())
+    } else {
+        dir.rsplit('/').next().unwrap_or(dir).to_string()
+    }
+}
+
+fn package_name(dir: &Path, kind: &str) -> Option<String> {
+    match kind {
+        "Cargo" => {
+            let manifest: toml::Table = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?.parse().ok()?;
+            Some(manifest.get("package")?.get("name")?.as_str()?.to_string())
+        }
+        "npm" => {
+            let manifest: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(dir.join("package.json")).ok()?).ok()?;
+            Some(manifest.get("name")?.as_str()?.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Workspace-relative directories of a crate's `path` dependencies,
+/// including `workspace = true` ones declared with a path at the root.
+fn cargo_path_dependencies(workspace: &Path, dir: &str) -> Vec<String> {
+    let read = |dir: &Path| -> Option<toml::Table> {
+        std::fs::read_to_string(dir.join("Cargo.toml")).ok()?.parse().ok()
+    };
+    let Some(manifest) = read(&workspace.join(dir)) else {
+        return Vec::new();
+    };
+    let root = read(workspace);
+    let root_deps = root.as_ref().and_then(|r| r.get("workspace")?.get("dependencies")?.as_table());
+
+    let mut deps = Vec::new();
+    for section in ["dependencies", "build-dependencies"] {
+        let Some(table) = manifest.get(section).and_then(|t| t.as_table()) else {
+            continue;
+        };
+        for (name, spec) in table {
+            let (base, path) = match spec.get("path").and_then(|p| p.as_str()) {
+                Some(path) => (dir, path),
+                None if spec.get("workspace").and_then(|w| w.as_bool()) == Some(true) => {
+                    match root_deps.and_then(|d| d.get(name)?.get("path")?.as_str()) {
+                        Some(path) => ("", path),
+                        None => continue,
+                    }
+                }
+                None => continue,
+            };
+            if let Some(path) = normalize(&Path::new(base).join(path)) {
+                deps.push(path);
+            }
+        }
+    }
+    deps
+}
+
+/// Directories of the workspace packages an npm package depends on.
+fn npm_workspace_dependencies(workspace: &Path, dir: &str, names: &HashMap<String, String>) -> Vec<String> {
+    let Some(manifest) = std::fs::read_to_string(workspace.join(dir).join("package.json"))
+        .ok()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+    else {
+        return Vec::new();
+    };
+    ["dependencies", "devDependencies", "peerDependencies"]
+        .iter()
+        .filter_map(|section| manifest.get(section)?.as_object())
+        .flat_map(|deps| deps.keys())
+        .filter_map(|name| names.get(name).cloned())
+        .collect()
+}
+
+/// `a/b/../c` -> `a/c`; `None` if it leaves the workspace.
+fn normalize(path: &Path) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Run commands from the root manifests, for when nothing better was
+/// detected (the CLI).
+pub fn manifest_run_commands(workspace: &Path) -> Vec<RunCommand> {
+    let mut commands = Vec::new();
+    let mut add = |name: &str, command: String, source: &str| {
+        commands.push(RunCommand { name: name.to_string(), command, source: source.to_string() });
+    };
+    if workspace.join("Cargo.toml").is_file() {
+        add("Build", "cargo build".to_string(), "Cargo.toml");
+        add("Test", "cargo test".to_string(), "Cargo.toml");
+    }
+    if let Some(scripts) = std::fs::read_to_string(workspace.join("package.json"))
+        .ok()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+        .and_then(|m| m.get("scripts")?.as_object().cloned())
+    {
+        for name in scripts.keys() {
+            add(name, format!("npm run {name}"), "package.json");
+        }
+    }
+    if workspace.join("go.mod").is_file() {
+        add("Build", "go build ./...".to_string(), "go.mod");
+        add("Test", "go test ./...".to_string(), "go.mod");
+    }
+    if workspace.join("pyproject.toml").is_file() {
+        add("Test", "python -m pytest".to_string(), "pyproject.toml");
+    }
+    if workspace.join("CMakeLists.txt").is_file() {
+        add("Build", "cmake -S . -B build && cmake --build build".to_string(), "CMakeLists.txt");
+        add("Test", "ctest --test-dir build --output-on-failure".to_string(), "CMakeLists.txt");
+    }
+    if let Ok(makefile) = std::fs::read_to_string(workspace.join("Makefile")) {
+        for line in makefile.lines() {
+            let Some((target, _)) = line.split_once(':') else {
+                continue;
+            };
+            let valid = !target.is_empty()
+                && !line.starts_with(['\t', ' ', '.', '#'])
+                && !line.contains(":=")
+                && target.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if valid {
+                add(target, format!("make {target}"), "Makefile");
+            }
+        }
+    }
+    commands
+}
+
+impl Report {
+    /// The onboarding document, in Markdown.
+    pub fn markdown(&self) -> String {
+        let mut out = format!("# {}: onboarding\n\n", self.name);
+
+        out.push_str("## At a glance\n\n");
+        out.push_str(&format!("{}", self.summary));
+        if self.truncated {
+            out.push_str(&format!(" (stopped counting at {MAX_FILES} files)"));
+        }
+        out.push_str("\n\n");
+        let mut languages: Vec<_> = self.summary.languages.iter().collect();
+        languages.sort_by(|a, b| b.1 .1.cmp(&a.1 .1));
+        if !languages.is_empty() {
+            out.push_str("| Language | Files | Lines |\n|---|---:|---:|\n");
+            for (language, (files, lines)) in languages {
+                out.push_str(&format!("| {language} | {files} | {lines} |\n"));
+            }
+            out.push('\n');
+        }
+
+        out.push_str("## Layout\n\n| Module | Path | Kind | Language | Files | Lines |\n|---|---|---|---|---:|---:|\n");
+        for module in &self.modules {
+            let path = if module.path.is_empty() { "." } else { &module.path };
+            out.push_str(&format!(
+                "| {} | `{path}` | {} | {} | {} | {} |\n",
+                module.name,
+                module.kind.unwrap_or("directory"),
+                module.language().unwrap_or("-"),
+                module.files,
+                module.lines
+            ));
+        }
+
+        out.push_str("\n## Architecture\n\n```mermaid\n");
+        out.push_str(&self.mermaid());
+        out.push_str("```\n");
+
+        out.push_str("\n## Entry points\n\n");
+        if self.entry_points.is_empty() {
+            out.push_str("None found.\n");
+        }
+        for entry in &self.entry_points {
+            let kind = match entry.kind {
+                EntryKind::Main => "main",
+                EntryKind::Server => "server bootstrap",
+            };
+            out.push_str(&format!("- `{}:{}` — {kind} ({})\n", entry.path, entry.line, entry.language));
+        }
+
+        out.push_str("\n## How to run\n\n");
+        if self.run_commands.is_empty() {
+            out.push_str("No run configurations detected.\n");
+        }
+        for run in &self.run_commands {
+            out.push_str(&format!("- `{}` — {} (from {})\n", run.command, run.name, run.source));
+        }
+
+        if !self.dependencies.is_empty() {
+            out.push_str("\n## Dependencies\n\n");
+            for (ecosystem, label) in [(Ecosystem::Cargo, "Cargo"), (Ecosystem::Npm, "npm"), (Ecosystem::Python, "Python")] {
+                let deps: Vec<String> = self
+                    .dependencies
+                    .iter()
+                    .filter(|d| d.ecosystem == ecosystem)
+                    .map(|d| match &d.version {
+                        Some(version) => format!("{} {version}", d.name),
+                        None => d.name.clone(),
+                    })
+                    .collect();
+                if !deps.is_empty() {
+                    out.push_str(&format!("- {label}: {}\n", deps.join(", ")));
+                }
+            }
+        }
+        out
+    }
+
+    /// The modules, what they depend on, and the entry points in them.
+    pub fn mermaid(&self) -> String {
+        let id: HashMap<&str, String> =
+            self.modules.iter().enumerate().map(|(i, m)| (m.path.as_str(), format!("m{i}"))).collect();
+        let mut out = String::from("graph TD\n");
+        for module in &self.modules {
+            let detail = match module.language() {
+                Some(language) => format!("{language} · {} files", module.files),
+                None => format!("{} files", module.files),
+            };
+            out.push_str(&format!("  {}[\"{}<br/>{detail}\"]\n", id[module.path.as_str()], label(&module.name)));
+        }
+        for module in &self.modules {
+            for dep in &module.depends_on {
+                out.push_str(&format!("  {} --> {}\n", id[module.path.as_str()], id[dep.as_str()]));
+            }
+        }
+        for (i, entry) in self.entry_points.iter().take(MAX_DIAGRAM_ENTRY_POINTS).enumerate() {
+            let module = self
+                .modules
+                .iter()
+                .filter(|m| m.path.is_empty() || entry.path.starts_with(&format!("{}/", m.path)))
+                .max_by_key(|m| m.path.len());
+            let kind = if entry.kind == EntryKind::Server { "server" } else { "main" };
+            out.push_str(&format!("  e{i}([\"{kind}: {}\"])\n", label(&entry.path)));
+            if let Some(module) = module {
+                out.push_str(&format!("  e{i} -.-> {}\n", id[module.path.as_str()]));
+            }
+        }
+        out
+    }
+}
+
+fn label(text: &str) -> String {
+    text.replace('"', "'")
+}
+
+/// Where the IDE writes the report.
+pub fn report_path(workspace: &Path) -> PathBuf {
+    workspace.join(".forge").join("onboarding.md")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_onboarding_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("Cargo.toml", "[workspace]\nmembers = [\"app\", \"core\"]\n\n[workspace.dependencies]\ncore = { path = \"core\" }\n");
+        write("app/Cargo.toml", "[package]\nname = \"app\"\n\n[dependencies]\ncore = { workspace = true }\n");
+        write("app/src/main.rs", "use core::run;\n\nfn main() {\n    run();\n}\n");
+        write("core/Cargo.toml", "[package]\nname = \"core\"\n");
+        write("core/src/lib.rs", "pub fn run() {}\n");
+        write("core/tests/it.rs", "fn main() {}\n");
+        write("Makefile", "all: build\n.PHONY: all\nbuild:\n\tcargo build\n");
+
+        let report = build(root, Vec::new());
+        assert_eq!(report.summary.total_files, 7);
+        let names: Vec<&str> = report.modules.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names.len(), 3);
+        let app = report.modules.iter().find(|m| m.path == "app").unwrap();
+        assert_eq!(app.kind, Some("Cargo"));
+        assert_eq!(app.language(), Some("Rust"));
+        assert_eq!(app.depends_on, ["core"]);
+
+        assert_eq!(report.entry_points.len(), 1);
+        assert_eq!(report.entry_points[0].path, "app/src/main.rs");
+        assert_eq!(report.entry_points[0].line, 3);
+        assert!(report.run_commands.iter().any(|r| r.command == "cargo test"));
+        assert!(report.run_commands.iter().any(|r| r.command == "make build"));
+
+        let markdown = report.markdown();
+        assert!(markdown.contains("```mermaid\ngraph TD\n"));
+        assert!(markdown.contains("- `app/src/main.rs:3` — main (Rust)"));
+        let mermaid = report.mermaid();
+        let id = |path: &str| format!("m{}", report.modules.iter().position(|m| m.path == path).unwrap());
+        assert!(mermaid.contains(&format!("  {} --> {}\n", id("app"), id("core"))));
+    }
+}
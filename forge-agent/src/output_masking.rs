@@ -6,8 +6,24 @@
 //! - Large outputs (> 12000): head + tail + save to temp file
 //!
 //! Shell output gets special treatment to always preserve exit codes and errors.
+//!
+//! [`postprocess`] is what tool results go through before they are sent to
+//! the model. It caps each tool's output at a per-tool limit, keeping:
+//! - error lines from known formats (rustc, tsc, eslint, gcc/clang, go,
+//!   pytest/Python tracebacks, jest), pulled out of the omitted middle
+//! - a head/tail window sized to the limit
+//!
+//! Limits are configured with environment variables:
+//! - `FORGE_TOOL_OUTPUT_MAX`: default limit in chars (12000)
+//! - `FORGE_TOOL_OUTPUT_LIMITS`: per-tool overrides, e.g. `run=40000,grep=8000`
+//! - `FORGE_TOOL_OUTPUT_SUMMARIZE`: opt-in; outputs over this many chars are
+//!   summarized by the model instead of windowed (falls back to the window)
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-use std::path::PathBuf;
+use regex::Regex;
 
 /// Thresholds
 const SMALL_THRESHOLD: usize = 4_000;
@@ -160,6 +176,275 @@ pub fn mask_output(output: &str, kind: OutputKind) -> MaskedOutput {
     }
 }
 
+/// Default per-tool limit (chars) when nothing is configured.
+const DEFAULT_TOOL_LIMIT: usize = 12_000;
+
+/// Default limit for tools whose output is a command's output.
+const DEFAULT_SHELL_LIMIT: usize = 20_000;
+
+/// Most error lines kept from an oversized output.
+const MAX_ERROR_LINES: usize = 40;
+
+/// Tools whose output is a command's output.
+const SHELL_TOOLS: &[&str] = &[
+    "run",
+    "execute_command",
+    "execute_background",
+    "shell_session",
+    "process",
+    "read_process_output",
+    "read_run_output",
+];
+
+/// Tools whose output is shown to the user verbatim.
+const DISPLAY_TOOLS: &[&str] = &["show_code", "show_diagram"];
+
+/// Kind of output `tool_name` produces.
+pub fn output_kind(tool_name: &str) -> OutputKind {
+    if DISPLAY_TOOLS.contains(&tool_name) {
+        OutputKind::Display
+    } else if SHELL_TOOLS.contains(&tool_name) {
+        OutputKind::Shell
+    } else {
+        OutputKind::Default
+    }
+}
+
+/// Size limits for tool results sent to the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputLimits {
+    pub default_max: usize,
+    pub per_tool: HashMap<String, usize>,
+    /// Outputs longer than this are summarized by the model. `None` = off.
+    pub summarize_over: Option<usize>,
+}
+
+impl Default for OutputLimits {
+    fn default() -> Self {
+        Self {
+            default_max: DEFAULT_TOOL_LIMIT,
+            per_tool: HashMap::new(),
+            summarize_over: None,
+        }
+    }
+}
+
+impl OutputLimits {
+    pub fn from_env() -> Self {
+        let var = crate::config::var;
+        Self::parse(
+            var("FORGE_TOOL_OUTPUT_MAX").as_deref(),
+            var("FORGE_TOOL_OUTPUT_LIMITS").as_deref(),
+            var("FORGE_TOOL_OUTPUT_SUMMARIZE").as_deref(),
+        )
+    }
+
+    fn parse(max: Option<&str>, per_tool: Option<&str>, summarize: Option<&str>) -> Self {
+        let size = |s: &str| s.trim().parse::<usize>().ok().filter(|&n| n > 0);
+        let per_tool = per_tool
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (tool, limit) = entry.split_once('=')?;
+                Some((tool.trim().to_string(), size(limit)?))
+            })
+            .collect();
+        Self {
+            default_max: max.and_then(size).unwrap_or(DEFAULT_TOOL_LIMIT),
+            per_tool,
+            summarize_over: summarize.and_then(size),
+        }
+    }
+
+    /// Limit for `tool_name` (`None` for display tools, which are never cut).
+    pub fn limit_for(&self, tool_name: &str) -> Option<usize> {
+        if let Some(&limit) = self.per_tool.get(tool_name) {
+            return Some(limit);
+        }
+        match output_kind(tool_name) {
+            OutputKind::Display => None,
+            OutputKind::Shell => Some(self.default_max.max(DEFAULT_SHELL_LIMIT)),
+            OutputKind::Default => Some(self.default_max),
+        }
+    }
+}
+
+/// Post-process a tool result before it is sent to the model: window it to
+/// the tool's limit, or summarize it when that is enabled.
+pub async fn postprocess(tool_name: &str, output: &str, workdir: &Path) -> String {
+    let limits = OutputLimits::from_env();
+    if let Some(threshold) = limits.summarize_over {
+        if output.len() > threshold && output_kind(tool_name) != OutputKind::Display {
+            if let Some(summary) = summarize(tool_name, output, workdir).await {
+                let saved = save_full_output(output)
+                    .map(|p| format!("\nFull output saved to: {}", p.display()))
+                    .unwrap_or_default();
+                return format!(
+                    "[Summary of {} chars of {tool_name} output]\n{summary}{}{saved}",
+                    output.len(),
+                    error_section(output),
+                );
+            }
+        }
+    }
+    window_output(tool_name, output, &limits).text
+}
+
+/// Cap `output` at the tool's limit: shell metadata, error lines from the
+/// omitted part, then as much head and tail as fits.
+pub fn window_output(tool_name: &str, output: &str, limits: &OutputLimits) -> MaskedOutput {
+    let unchanged = MaskedOutput {
+        text: output.to_string(),
+        saved_path: None,
+        was_truncated: false,
+    };
+    let Some(limit) = limits.limit_for(tool_name) else {
+        return unchanged;
+    };
+    if output.len() <= limit {
+        return unchanged;
+    }
+
+    let (prefix, body) = if output_kind(tool_name) == OutputKind::Shell {
+        extract_shell_metadata(output)
+    } else {
+        (String::new(), output.to_string())
+    };
+    let errors = error_section(&body);
+    let budget = limit.saturating_sub(prefix.len() + errors.len()).max(limit / 4);
+    // Errors and summaries tend to be at the end
+    let head_end = floor_char_boundary(&body, budget / 3);
+    let tail_start = ceil_char_boundary(&body, body.len().saturating_sub(budget - budget / 3));
+    let omitted = &body[head_end..tail_start.max(head_end)];
+
+    let saved_path = save_full_output(output);
+    let save_notice = saved_path
+        .as_ref()
+        .map(|p| format!(" Full output saved to: {}", p.display()))
+        .unwrap_or_default();
+    let text = format!(
+        "{prefix}{head}\n\n[... {} chars omitted ({} total lines, {} total chars) ...]\n\n{tail}{errors}\n\n[Output truncated to the {tool_name} limit of {limit} chars.{save_notice}]",
+        omitted.len(),
+        output.lines().count(),
+        output.len(),
+        head = &body[..head_end],
+        tail = &body[tail_start.max(head_end)..],
+    );
+    MaskedOutput {
+        text,
+        saved_path,
+        was_truncated: true,
+    }
+}
+
+/// Error lines recognised in `output`, one per line, deduplicated. Rust and
+/// gcc-style diagnostics keep their `-->` location line.
+pub fn extract_error_lines(output: &str) -> Vec<String> {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            // rustc / cargo
+            r"^error(\[E\d+\])?: ",
+            r"^thread '.*' panicked at",
+            // gcc / clang / go / generic file:line:col: error
+            r"^\S+:\d+:\d+: (fatal )?error",
+            r"^\S+\.go:\d+:\d+: ",
+            r"^--- FAIL: ",
+            // tsc
+            r"^\S+\(\d+,\d+\): error TS\d+",
+            r"^\S+:\d+:\d+ - error TS\d+",
+            // eslint
+            r"^\s+\d+:\d+\s+error\s",
+            // python / pytest
+            r"^Traceback \(most recent call last\)",
+            r"^[A-Za-z_.]*(Error|Exception): ",
+            r"^FAILED ",
+            r"^E\s{2,}",
+            // jest
+            r"^\s*● ",
+        ]
+        .iter()
+        .map(|p| Regex::new(p).expect("valid error pattern"))
+        .collect()
+    });
+
+    let lines: Vec<&str> = output.lines().collect();
+    let mut found: Vec<String> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if !patterns.iter().any(|p| p.is_match(line)) {
+            continue;
+        }
+        let mut entry = line.trim_end().to_string();
+        if let Some(next) = lines.get(i + 1).filter(|l| l.trim_start().starts_with("--> ")) {
+            entry.push('\n');
+            entry.push_str(next.trim_end());
+        }
+        if !found.contains(&entry) {
+            found.push(entry);
+        }
+        if found.len() >= MAX_ERROR_LINES {
+            break;
+        }
+    }
+    found
+}
+
+fn error_section(output: &str) -> String {
+    let errors = extract_error_lines(output);
+    if errors.is_empty() {
+        String::new()
+    } else {
+        format!("\n\n[Error lines]\n{}", errors.join("\n"))
+    }
+}
+
+/// Ask the model to summarize a large tool output. `None` on any failure.
+async fn summarize(tool_name: &str, output: &str, workdir: &Path) -> Option<String> {
+    // The model sees head and tail; the middle of a huge log rarely matters
+    const MAX_INPUT: usize = 60_000;
+    let input = if output.len() > MAX_INPUT {
+        let head = &output[..floor_char_boundary(output, MAX_INPUT / 2)];
+        let tail = &output[ceil_char_boundary(output, output.len() - MAX_INPUT / 2)..];
+        format!("{head}\n[...]\n{tail}")
+    } else {
+        output.to_string()
+    };
+    let workspace_id = crate::forge_search::workspace_id(workdir);
+    let prompt = format!(
+        "Summarize this output of the `{tool_name}` tool for a coding agent in at most 30 lines. \
+         Keep every error and failing test with its file and line, the final status, and \
+         anything the agent must act on. Drop progress noise.\n\n```\n{input}\n```"
+    );
+    let mut body = serde_json::json!({
+        "workspace_id": workspace_id,
+        "question": prompt,
+    });
+    crate::model_routing::apply(&mut body, crate::model_routing::ModelRole::Summarizer);
+    let resp = crate::forge_search::client()
+        .chat_with_body(&body)
+        .await
+        .map_err(|e| tracing::warn!("output_masking: summarization failed: {}", e))
+        .ok()?;
+    let answer = resp.get("answer").and_then(|v| v.as_str())?.trim();
+    (!answer.is_empty()).then(|| answer.to_string())
+}
+
+fn floor_char_boundary(s: &str, mut i: usize) -> usize {
+    i = i.min(s.len());
+    while !s.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+fn ceil_char_boundary(s: &str, mut i: usize) -> usize {
+    i = i.min(s.len());
+    while !s.is_char_boundary(i) {
+        i += 1;
+    }
+    i
+}
+
 /// Extract shell-specific metadata (exit code, errors) that must be preserved.
 /// Returns (metadata_prefix, remaining_body).
 fn extract_shell_metadata(output: &str) -> (String, String) {
@@ -286,6 +571,58 @@ mod tests {
         // saved_path may or may not exist depending on disk permissions in CI
     }
 
+    #[test]
+    fn test_output_limits_from_env_values() {
+        let limits = OutputLimits::parse(Some("5000"), Some("run=40000, grep=800,bad=x"), Some("0"));
+        assert_eq!(limits.limit_for("grep"), Some(800));
+        assert_eq!(limits.limit_for("run"), Some(40_000));
+        assert_eq!(limits.limit_for("read_file"), Some(5_000));
+        assert_eq!(limits.limit_for("execute_command"), Some(DEFAULT_SHELL_LIMIT));
+        assert_eq!(limits.limit_for("show_code"), None);
+        assert_eq!(limits.summarize_over, None);
+        assert_eq!(OutputLimits::parse(None, None, None), OutputLimits::default());
+    }
+
+    #[test]
+    fn test_window_keeps_errors_from_the_middle() {
+        let mut output = "Exit code: 101\n".to_string();
+        for i in 0..2000 {
+            output.push_str(&format!("   Compiling crate{i} v0.1.0\n"));
+            if i == 1000 {
+                output.push_str("error[E0308]: mismatched types\n  --> src/main.rs:4:5\n");
+            }
+        }
+        output.push_str("error: could not compile `app`\n");
+
+        let limits = OutputLimits::parse(None, Some("run=3000"), None);
+        let masked = window_output("run", &output, &limits);
+        assert!(masked.was_truncated);
+        assert!(masked.text.starts_with("Exit code: 101"));
+        assert!(masked.text.contains("error[E0308]: mismatched types\n  --> src/main.rs:4:5"));
+        assert!(masked.text.contains("Compiling crate1999"));
+        assert!(masked.text.len() < 4_000);
+
+        let small = window_output("run", "ok", &limits);
+        assert_eq!(small.text, "ok");
+    }
+
+    #[test]
+    fn test_extract_error_lines() {
+        let output = "\
+src/app.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.
+main.c:10:5: error: expected ';' before '}' token
+  12:3  error  'x' is unused  no-unused-vars
+FAILED tests/test_api.py::test_login - AssertionError
+ValueError: bad input
+ok line
+main.c:10:5: error: expected ';' before '}' token
+";
+        let errors = extract_error_lines(output);
+        assert_eq!(errors.len(), 5);
+        assert!(errors[0].contains("TS2322"));
+        assert!(!errors.iter().any(|e| e == "ok line"));
+    }
+
     #[test]
     fn test_shell_metadata_preserved() {
         let output = "exit code: 1\nerror: compilation failed\nsome output here\nmore output\n"
@@ -0,0 +1,138 @@
+//! Per-phase timings of an agent turn (`forge-cli --profile`).
+//!
+//! Code that runs a phase (prompt assembly, the model stream, each tool
+//! call, approvals) reports how long it took with [`record`] or [`timed`];
+//! inside [`scope`] that adds to a [`Profile`], elsewhere it costs a
+//! task-local lookup. The profile prints as a table, or as folded stacks
+//! (`turn;tool:grep 1234`) for inferno or flamegraph.pl.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    static PROFILE: Arc<Mutex<Profile>>;
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Phase {
+    pub name: String,
+    pub count: u32,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+/// Phase timings in first-seen order.
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    phases: Vec<Phase>,
+    started: Option<Instant>,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self { phases: Vec::new(), started: Some(Instant::now()) }
+    }
+
+    pub fn add(&mut self, name: &str, elapsed: Duration) {
+        let phase = match self.phases.iter().position(|p| p.name == name) {
+            Some(i) => &mut self.phases[i],
+            None => {
+                self.phases.push(Phase { name: name.to_string(), ..Default::default() });
+                self.phases.last_mut().unwrap()
+            }
+        };
+        phase.count += 1;
+        phase.total += elapsed;
+        phase.max = phase.max.max(elapsed);
+    }
+
+    pub fn phases(&self) -> &[Phase] {
+        &self.phases
+    }
+
+    /// Wall time since the profile started.
+    pub fn wall(&self) -> Duration {
+        self.started.map(|s| s.elapsed()).unwrap_or_default()
+    }
+
+    /// One row per phase, slowest first, with its share of the wall time.
+    pub fn table(&self) -> String {
+        let wall = self.wall();
+        let mut phases: Vec<&Phase> = self.phases.iter().collect();
+        phases.sort_by(|a, b| b.total.cmp(&a.total));
+        let width = phases.iter().map(|p| p.name.len()).max().unwrap_or(0).max(5);
+        let mut out = format!("{:<width$}  {:>5}  {:>10}  {:>10}  {:>6}\n", "phase", "calls", "total", "max", "share");
+        for p in phases {
+            let share = if wall.is_zero() { 0.0 } else { 100.0 * p.total.as_secs_f64() / wall.as_secs_f64() };
+            out.push_str(&format!(
+                "{:<width$}  {:>5}  {:>10}  {:>10}  {:>5.1}%\n",
+                p.name,
+                p.count,
+                format!("{:.1?}", p.total),
+                format!("{:.1?}", p.max),
+                share
+            ));
+        }
+        out.push_str(&format!("{:<width$}  {:>5}  {:>10}\n", "wall", "", format!("{:.1?}", wall)));
+        out
+    }
+
+    /// Folded stacks in microseconds; time outside any phase is `turn`.
+    pub fn folded(&self) -> String {
+        let in_phases: Duration = self.phases.iter().map(|p| p.total).sum();
+        let mut out = String::new();
+        for p in &self.phases {
+            out.push_str(&format!("turn;{} {}\n", p.name.replace([';', ' '], "_"), p.total.as_micros()));
+        }
+        out.push_str(&format!("turn {}\n", self.wall().saturating_sub(in_phases).as_micros()));
+        out
+    }
+}
+
+/// Run `f` recording its phases into `profile`.
+pub async fn scope<F: Future>(profile: Arc<Mutex<Profile>>, f: F) -> F::Output {
+    PROFILE.scope(profile, f).await
+}
+
+/// Add `elapsed` to phase `name` of the current profile, if any.
+pub fn record(name: &str, elapsed: Duration) {
+    let _ = PROFILE.try_with(|profile| profile.lock().unwrap().add(name, elapsed));
+}
+
+/// Run `f` as phase `name`.
+pub async fn timed<F: Future>(name: &str, f: F) -> F::Output {
+    let start = Instant::now();
+    let output = f.await;
+    record(name, start.elapsed());
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_profile_phases() {
+        let profile = Arc::new(Mutex::new(Profile::new()));
+        scope(profile.clone(), async {
+            record("tool:grep", Duration::from_millis(5));
+            record("tool:grep", Duration::from_millis(3));
+            timed("model", tokio::time::sleep(Duration::from_millis(20))).await;
+        })
+        .await;
+        // Outside the scope nothing is recorded
+        record("tool:grep", Duration::from_secs(60));
+
+        let profile = profile.lock().unwrap();
+        let grep = &profile.phases()[0];
+        assert_eq!((grep.count, grep.total, grep.max), (2, Duration::from_millis(8), Duration::from_millis(5)));
+        assert!(profile.phases()[1].total >= Duration::from_millis(20));
+
+        let table = profile.table();
+        assert!(table.starts_with("phase") && table.lines().nth(1).unwrap().starts_with("model"), "{table}");
+        let folded = profile.folded();
+        assert!(folded.starts_with("turn;tool:grep 8000\nturn;model "), "{folded}");
+        assert_eq!(folded.lines().count(), 3);
+    }
+}
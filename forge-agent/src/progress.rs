@@ -0,0 +1,124 @@
+//! Progress of one agent run (a user message and its tool round-trips) for
+//! the chat panel's status row: which step it's on, what it's doing, how long
+//! it has been going and the tokens used so far.
+//!
+//! Token counts are what the backend reports with [`SseEvent::Usage`]; until
+//! a turn reports any, its streamed text is estimated at [`CHARS_PER_TOKEN`].
+//!
+//! [`SseEvent::Usage`]: crate::forge_search::SseEvent::Usage
+
+use std::time::{Duration, Instant};
+
+/// Rough size of a token in English text and code.
+pub const CHARS_PER_TOKEN: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct Progress {
+    started: Instant,
+    step: u32,
+    activity: String,
+    tool: Option<String>,
+    /// Tokens reported by finished turns.
+    tokens: u64,
+    /// Reported by the current turn (replaced, as backends send running totals).
+    turn_tokens: Option<u64>,
+    /// Text streamed in the current turn, for the estimate.
+    turn_chars: usize,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            step: 0,
+            activity: "Starting".to_string(),
+            tool: None,
+            tokens: 0,
+            turn_tokens: None,
+            turn_chars: 0,
+        }
+    }
+
+    /// A model call starts.
+    pub fn next_step(&mut self) {
+        self.tokens = self.tokens();
+        self.turn_tokens = None;
+        self.turn_chars = 0;
+        self.step += 1;
+        self.tool = None;
+        self.activity = "Waiting for the model".to_string();
+    }
+
+    pub fn set_activity(&mut self, activity: impl Into<String>) {
+        self.activity = activity.into();
+    }
+
+    pub fn tool_started(&mut self, name: &str, summary: &str) {
+        self.tool = Some(name.to_string());
+        self.activity = summary.to_string();
+    }
+
+    pub fn tool_finished(&mut self) {
+        self.tool = None;
+        self.activity = "Running tools".to_string();
+    }
+
+    pub fn text(&mut self, text: &str) {
+        self.turn_chars += text.len();
+        self.activity = "Writing".to_string();
+    }
+
+    pub fn usage(&mut self, input_tokens: u64, output_tokens: u64) {
+        self.turn_tokens = Some(input_tokens + output_tokens);
+    }
+
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+
+    pub fn activity(&self) -> &str {
+        &self.activity
+    }
+
+    pub fn tool(&self) -> Option<&str> {
+        self.tool.as_deref()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Tokens so far, estimated for a turn that hasn't reported usage.
+    pub fn tokens(&self) -> u64 {
+        self.tokens + self.turn_tokens.unwrap_or((self.turn_chars / CHARS_PER_TOKEN) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_prefer_reported_usage() {
+        let mut progress = Progress::new();
+        progress.next_step();
+        progress.text(&"x".repeat(40));
+        assert_eq!(progress.tokens(), 10);
+
+        progress.usage(100, 5);
+        progress.usage(100, 20);
+        assert_eq!(progress.tokens(), 120);
+
+        progress.next_step();
+        progress.tool_started("grep", "grep TODO");
+        assert_eq!((progress.step(), progress.tool()), (2, Some("grep")));
+        progress.text("abcdefgh");
+        assert_eq!(progress.tokens(), 122);
+    }
+}
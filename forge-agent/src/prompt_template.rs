@@ -0,0 +1,186 @@
+//! Templates for the context the IDE sends with the user's message.
+//!
+//! The first chat turn carries the question plus workspace context (roots,
+//! size, recent changes). That block is rendered from a handlebars-style
+//! template so teams can tune it without forking:
+//!
+//! - `{{name}}` inserts a variable
+//! - `{{#if name}}...{{else}}...{{/if}}` renders a block when `name` is non-empty
+//!
+//! The built-in template can be replaced by `~/.forge/prompts/turn.md`.
+//! Markdown files in the workspace's `.forge/prompts/` are prompt fragments:
+//! they are joined into the `rules` variable.
+//!
+//! Variables: `question`, `mode` (`agent` or `plan`), `roots`, `workspace`,
+//! `changes`, `external_changes`, `memory` (FORGE.md contents), `rules`,
+//! `respond_in` (see [`crate::i18n::response_language`]), `edit_format`
+//! (instructions for text edit formats, see [`crate::edit_format`]),
+//! `tools` (the active tool set, see [`crate::tools::selection`]),
+//! `restricted` (set when the workspace isn't trusted, see [`crate::trust`]).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const TURN_TEMPLATE: &str = "turn";
+
+const DEFAULT_TURN: &str = "{{question}}\
+{{#if attachments}}\n\n[Attached files:\n{{attachments}}]{{/if}}\
+{{#if rules}}\n\n[Project rules:\n{{rules}}]{{/if}}\
+{{#if roots}}\n\n[{{roots}}]{{/if}}\
+{{#if workspace}}\n\n[Workspace: {{workspace}}]{{/if}}\
+{{#if changes}}\n\n[{{changes}}]{{/if}}\
+{{#if external_changes}}\n\n[Files changed outside the agent since the last message: {{external_changes}}. Use workspace_diff for details.]{{/if}}\
+{{#if edit_format}}\n\n[{{edit_format}}]{{/if}}\
+{{#if tools}}\n\n[{{tools}}]{{/if}}\
+{{#if restricted}}\n\n[{{restricted}}]{{/if}}\
+{{#if respond_in}}\n\n[Respond in {{respond_in}}.]{{/if}}";
+
+/// Most characters of workspace prompt fragments to include.
+const MAX_RULES_CHARS: usize = 8000;
+
+fn user_prompts_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".forge").join("prompts"))
+}
+
+/// The template `name`: the user's override, else the built-in one.
+pub fn template(name: &str) -> String {
+    user_prompts_dir()
+        .and_then(|dir| std::fs::read_to_string(dir.join(format!("{name}.md"))).ok())
+        .unwrap_or_else(|| builtin(name).to_string())
+}
+
+fn builtin(name: &str) -> &'static str {
+    match name {
+        TURN_TEMPLATE => DEFAULT_TURN,
+        _ => "",
+    }
+}
+
+/// The workspace's `.forge/prompts/*.md` fragments, in file name order.
+pub fn workspace_fragments(workspace: &Path) -> String {
+    let Ok(entries) = std::fs::read_dir(workspace.join(".forge").join("prompts")) else {
+        return String::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "md"))
+        .collect();
+    paths.sort();
+    let mut out = String::new();
+    for path in paths {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if !out.is_empty() {
+            out.push_str("\n\n");
+        }
+        out.push_str(content.trim());
+        if out.len() > MAX_RULES_CHARS {
+            let cut = (0..=MAX_RULES_CHARS).rev().find(|&i| out.is_char_boundary(i)).unwrap_or(0);
+            out.truncate(cut);
+            break;
+        }
+    }
+    out
+}
+
+/// Render `template` with `vars`; unknown variables render empty.
+pub fn render(template: &str, vars: &HashMap<&str, String>) -> String {
+    let mut out = String::new();
+    render_into(template, vars, &mut out);
+    out
+}
+
+fn render_into(mut rest: &str, vars: &HashMap<&str, String>, out: &mut String) {
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return;
+        };
+        let tag = after[..end].trim();
+        rest = &after[end + 2..];
+        if let Some(name) = tag.strip_prefix("#if ") {
+            let (body, tail) = split_block(rest);
+            let (then, otherwise) = split_else(body);
+            let set = vars.get(name.trim()).is_some_and(|v| !v.trim().is_empty());
+            render_into(if set { then } else { otherwise }, vars, out);
+            rest = tail;
+        } else if let Some(value) = vars.get(tag) {
+            out.push_str(value);
+        }
+    }
+    out.push_str(rest);
+}
+
+/// Split at the `{{/if}}` matching an already-opened block.
+fn split_block(s: &str) -> (&str, &str) {
+    let mut depth = 0;
+    let mut i = 0;
+    while let Some(pos) = s[i..].find("{{") {
+        let at = i + pos;
+        let tag_end = s[at..].find("}}").map(|e| at + e + 2).unwrap_or(s.len());
+        let tag = s[at + 2..tag_end.saturating_sub(2).max(at + 2)].trim();
+        if tag.starts_with("#if ") {
+            depth += 1;
+        } else if tag == "/if" {
+            if depth == 0 {
+                return (&s[..at], &s[tag_end..]);
+            }
+            depth -= 1;
+        }
+        i = tag_end;
+    }
+    (s, "")
+}
+
+/// Split a block body at its top-level `{{else}}`.
+fn split_else(body: &str) -> (&str, &str) {
+    let mut depth = 0;
+    let mut i = 0;
+    while let Some(pos) = body[i..].find("{{") {
+        let at = i + pos;
+        let tag_end = body[at..].find("}}").map(|e| at + e + 2).unwrap_or(body.len());
+        let tag = body[at + 2..tag_end.saturating_sub(2).max(at + 2)].trim();
+        match tag {
+            t if t.starts_with("#if ") => depth += 1,
+            "/if" => depth -= 1,
+            "else" if depth == 0 => return (&body[..at], &body[tag_end..]),
+            _ => {}
+        }
+        i = tag_end;
+    }
+    (body, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut vars = HashMap::new();
+        vars.insert("question", "Fix the build".to_string());
+        vars.insert("mode", "plan".to_string());
+        let tpl = "{{question}}{{#if rules}} rules: {{rules}}{{else}} (no rules{{#if mode}}, {{mode}} mode{{/if}}){{/if}}!";
+        assert_eq!(render(tpl, &vars), "Fix the build (no rules, plan mode)!");
+        vars.insert("rules", "use tabs".to_string());
+        assert_eq!(render(tpl, &vars), "Fix the build rules: use tabs!");
+        assert_eq!(render("{{missing}}x{{", &vars), "x{{");
+    }
+
+    #[test]
+    fn test_default_turn_template() {
+        let mut vars = HashMap::new();
+        vars.insert("question", "Why?".to_string());
+        assert_eq!(render(DEFAULT_TURN, &vars), "Why?");
+        vars.insert("changes", "1 file changed since last turn (0 by the agent, 1 outside it)".to_string());
+        vars.insert("external_changes", "src/main.rs".to_string());
+        assert_eq!(
+            render(DEFAULT_TURN, &vars),
+            "Why?\n\n[1 file changed since last turn (0 by the agent, 1 outside it)]\n\n\
+             [Files changed outside the agent since the last message: src/main.rs. Use workspace_diff for details.]"
+        );
+    }
+}
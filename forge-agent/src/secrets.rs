@@ -0,0 +1,121 @@
+//! Provider API keys in the OS keychain.
+//!
+//! Keys are stored under the `forge-ide` service with the provider name
+//! (`groq`, `openai`, ...) as the account: Keychain on macOS, Credential
+//! Manager on Windows, the Secret Service on Linux. Lookup order:
+//!
+//! 1. `<PROVIDER>_API_KEY` environment variable
+//! 2. `keys.<provider>` in the config files (see [`crate::config`])
+//! 3. the keychain
+//! 4. a still-valid OAuth access token from [`crate::auth`]
+//!
+//! Plaintext keys in `ai-keys.toml` are moved into the keychain by
+//! [`migrate_keys_file`]. Where no keychain is available (headless Linux
+//! without a Secret Service) storing fails and callers keep the file.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+const SERVICE: &str = "forge-ide";
+
+/// `groq` -> `GROQ_API_KEY`.
+pub fn env_var(provider: &str) -> String {
+    format!("{}_API_KEY", provider.to_ascii_uppercase().replace('-', "_"))
+}
+
+/// The API key for `provider`, from the environment, config or keychain.
+pub fn api_key(provider: &str) -> Option<String> {
+    std::env::var(env_var(provider))
+        .ok()
+        .or_else(|| crate::config::active().get(&format!("keys.{provider}")).map(String::from))
+        .or_else(|| stored(provider))
+        .or_else(|| crate::auth::cached_access_token(provider))
+        .filter(|k| !k.trim().is_empty())
+}
+
+/// The key stored in the keychain for `provider`.
+pub fn stored(provider: &str) -> Option<String> {
+    keyring::Entry::new(SERVICE, provider).ok()?.get_password().ok()
+}
+
+/// Store `key` for `provider` in the keychain (an empty key deletes it).
+pub fn store(provider: &str, key: &str) -> Result<()> {
+    if key.trim().is_empty() {
+        return delete(provider);
+    }
+    keyring::Entry::new(SERVICE, provider)
+        .and_then(|entry| entry.set_password(key))
+        .with_context(|| format!("storing the {provider} key in the keychain"))
+}
+
+pub fn delete(provider: &str) -> Result<()> {
+    match keyring::Entry::new(SERVICE, provider).and_then(|entry| entry.delete_credential()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("removing the {provider} key from the keychain")),
+    }
+}
+
+/// Move the `[keys]` table of a TOML file (e.g. `ai-keys.toml`) into the
+/// keychain. Keys that can't be stored stay in the file. Returns how many
+/// were moved.
+pub fn migrate_keys_file(path: &Path) -> Result<usize> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Ok(0);
+    };
+    let mut doc: toml_edit::Document = content
+        .parse()
+        .with_context(|| format!("invalid {}", path.display()))?;
+    let Some(keys) = doc.get_mut("keys").and_then(|k| k.as_table_like_mut()) else {
+        return Ok(0);
+    };
+    let plaintext: Vec<(String, String)> = keys
+        .iter()
+        .filter_map(|(provider, v)| Some((provider.to_string(), v.as_str()?.to_string())))
+        .collect();
+    let mut moved = 0;
+    for (provider, key) in plaintext {
+        if key.trim().is_empty() || store(&provider, &key).is_ok() {
+            keys.remove(&provider);
+            moved += usize::from(!key.trim().is_empty());
+        }
+    }
+    if moved > 0 {
+        std::fs::write(path, doc.to_string())
+            .with_context(|| format!("writing {}", path.display()))?;
+        tracing::info!("Moved {moved} API key(s) from {} to the keychain", path.display());
+    }
+    Ok(moved)
+}
+
+/// Whether a config key holds a secret and must not be printed.
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.starts_with("keys.")
+        || ["api_key", "token", "secret", "password"].iter().any(|s| key.contains(s))
+}
+
+/// `sk-abc...wxyz` -> `****wxyz`; short values are hidden entirely.
+pub fn redact(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() < 12 {
+        return "****".to_string();
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("****{tail}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redaction() {
+        assert_eq!(env_var("groq"), "GROQ_API_KEY");
+        assert_eq!(redact("sk-proj-1234567890abcd"), "****abcd");
+        assert_eq!(redact("short"), "****");
+        assert!(is_secret_key("keys.openai"));
+        assert!(is_secret_key("gateway_api_key"));
+        assert!(!is_secret_key("tool_output_max"));
+    }
+}
@@ -0,0 +1,174 @@
+//! Self-correction after edits.
+//!
+//! Before the agent first edits a file in a prompt, the file's current lint
+//! errors are recorded. After each round of edits the changed files are
+//! linted again; errors that were not there before are fed back to the model
+//! for up to `max_retries` fix attempts, after which the user is told.
+//!
+//! Settings: `self_correction` (default on) and `max_retries` (default 2),
+//! or `FORGE_SELF_CORRECTION` / `FORGE_MAX_RETRIES`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::tools::{lint_file, LintError, LintSeverity};
+
+/// Default number of fix attempts before giving up.
+const DEFAULT_MAX_RETRIES: usize = 2;
+
+/// Most new errors listed in one message.
+const MAX_LISTED_ERRORS: usize = 20;
+
+/// What to do after a round of edits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    /// No new errors (or self-correction is off).
+    Clean,
+    /// Feed this back to the model.
+    Retry(String),
+    /// Out of attempts: tell the user.
+    GiveUp(String),
+}
+
+/// Tracks lint errors across the edits of one prompt.
+pub struct SelfCorrection {
+    enabled: bool,
+    max_retries: usize,
+    attempts: usize,
+    /// Errors each file had before the agent touched it.
+    baseline: HashMap<PathBuf, HashSet<String>>,
+}
+
+impl SelfCorrection {
+    pub fn new(enabled: bool, max_retries: usize) -> Self {
+        Self { enabled, max_retries, attempts: 0, baseline: HashMap::new() }
+    }
+
+    /// Settings from the active config.
+    pub fn from_config() -> Self {
+        let enabled = crate::config::var("FORGE_SELF_CORRECTION")
+            .map_or(true, |v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0" | "off" | "no"));
+        let max_retries = crate::config::var("FORGE_MAX_RETRIES")
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_MAX_RETRIES);
+        Self::new(enabled, max_retries)
+    }
+
+    /// Record `path`'s errors before its first edit in this prompt.
+    pub async fn before_edit(&mut self, path: &str, workdir: &Path) {
+        let full = workdir.join(path);
+        if !self.enabled || self.baseline.contains_key(&full) {
+            return;
+        }
+        let errors = if full.is_file() { lint(full.clone(), workdir.to_path_buf()).await } else { Vec::new() };
+        self.baseline.insert(full, errors.iter().map(error_key).collect());
+    }
+
+    /// Lint the files changed in the last round of edits and decide what to
+    /// do about errors they didn't have before.
+    pub async fn after_edits(&mut self, paths: &[String], workdir: &Path) -> Check {
+        if !self.enabled || paths.is_empty() {
+            return Check::Clean;
+        }
+        let mut new_errors = Vec::new();
+        for path in paths {
+            let full = workdir.join(path);
+            if !full.is_file() {
+                continue;
+            }
+            let known = self.baseline.get(&full);
+            new_errors.extend(
+                lint(full.clone(), workdir.to_path_buf())
+                    .await
+                    .into_iter()
+                    .filter(|e| known.map_or(true, |k| !k.contains(&error_key(e)))),
+            );
+        }
+        self.decide(&new_errors)
+    }
+
+    fn decide(&mut self, new_errors: &[LintError]) -> Check {
+        if new_errors.is_empty() {
+            return Check::Clean;
+        }
+        let list = format_errors(new_errors);
+        if self.attempts >= self.max_retries {
+            return Check::GiveUp(format!(
+                "The agent's edits still have {} new error(s) after {} fix attempt(s):\n{list}",
+                new_errors.len(),
+                self.attempts,
+            ));
+        }
+        self.attempts += 1;
+        Check::Retry(format!(
+            "Diagnostics after your edits found {} new error(s):\n{list}\nFix them (attempt {} of {}).",
+            new_errors.len(),
+            self.attempts,
+            self.max_retries,
+        ))
+    }
+}
+
+async fn lint(path: PathBuf, workdir: PathBuf) -> Vec<LintError> {
+    tokio::task::spawn_blocking(move || lint_file(&path, &workdir))
+        .await
+        .map(|r| r.errors.into_iter().filter(|e| e.severity == LintSeverity::Error).collect())
+        .unwrap_or_default()
+}
+
+/// Identity of an error across edits: line numbers shift, messages don't.
+fn error_key(error: &LintError) -> String {
+    format!("{}\u{0}{}", error.file, error.message)
+}
+
+fn format_errors(errors: &[LintError]) -> String {
+    let mut out = String::new();
+    for e in errors.iter().take(MAX_LISTED_ERRORS) {
+        let location = match e.line {
+            Some(line) => format!("{}:{line}", e.file),
+            None => e.file.clone(),
+        };
+        out.push_str(&format!("- {location}: {}\n", e.message));
+    }
+    if errors.len() > MAX_LISTED_ERRORS {
+        out.push_str(&format!("- ... and {} more\n", errors.len() - MAX_LISTED_ERRORS));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(message: &str) -> LintError {
+        LintError {
+            file: "src/main.rs".into(),
+            line: Some(3),
+            column: None,
+            message: message.into(),
+            severity: LintSeverity::Error,
+        }
+    }
+
+    #[test]
+    fn test_retries_then_gives_up() {
+        let mut sc = SelfCorrection::new(true, 1);
+        assert_eq!(sc.decide(&[]), Check::Clean);
+        let errors = [error("mismatched types")];
+        match sc.decide(&errors) {
+            Check::Retry(msg) => {
+                assert!(msg.contains("src/main.rs:3: mismatched types"));
+                assert!(msg.contains("attempt 1 of 1"));
+            }
+            other => panic!("expected a retry, got {other:?}"),
+        }
+        assert!(matches!(sc.decide(&errors), Check::GiveUp(_)));
+    }
+
+    #[test]
+    fn test_error_key_ignores_line() {
+        let mut moved = error("mismatched types");
+        moved.line = Some(10);
+        assert_eq!(error_key(&moved), error_key(&error("mismatched types")));
+    }
+}
@@ -0,0 +1,420 @@
+//! Per-conversation state the agent keeps across turns, persisted to
+//! `~/.forge/sessions/<conversation_id>.json`.
+//!
+//! Holds the agent's working notes: the decisions, assumptions and open
+//! questions it records with `think` while it works, so a long task can be
+//! audited without scrolling back through the chat.
+//!
+//! Also holds the task list the model keeps with `focus_chain`
+//! ([`crate::tools::focus_chain`]).
+//!
+//! Saving appends what changed to `<conversation_id>.journal` rather than
+//! rewriting the session, so a crash mid-save loses at most that change.
+//! Loading replays the journal over the last snapshot, skipping a torn
+//! final line; every [`COMPACT_AFTER`] entries the journal is folded into
+//! a new snapshot. `session_fsync` (`FORGE_SESSION_FSYNC`) says when data
+//! is flushed to disk: `always` (default), `compact` (snapshots only) or
+//! `never`.
+
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Most notes kept per session; the oldest go first.
+pub const MAX_NOTES: usize = 500;
+
+/// Journal entries after which the session is compacted into a snapshot.
+pub const COMPACT_AFTER: usize = 100;
+
+/// When session writes are fsynced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Every journal append and snapshot.
+    #[default]
+    Always,
+    /// Snapshots only; a power loss can drop the latest appends.
+    Compact,
+    /// Never; left to the OS.
+    Never,
+}
+
+impl FsyncPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "always" | "on" | "true" | "1" => Some(Self::Always),
+            "compact" | "snapshot" => Some(Self::Compact),
+            "never" | "off" | "false" | "0" => Some(Self::Never),
+            _ => None,
+        }
+    }
+
+    /// The configured policy, [`FsyncPolicy::Always`] if unset or invalid.
+    pub fn configured() -> Self {
+        crate::config::var("FORGE_SESSION_FSYNC").and_then(|v| Self::parse(&v)).unwrap_or_default()
+    }
+}
+
+/// One change to a session, as a line of its journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalEntry {
+    Note { note: Note },
+    Tasks { tasks: Vec<Task> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteKind {
+    Decision,
+    Assumption,
+    Question,
+    Thought,
+}
+
+impl NoteKind {
+    /// `think`'s `kind`; anything else is a plain thought.
+    pub fn parse(kind: &str) -> Self {
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "decision" => Self::Decision,
+            "assumption" => Self::Assumption,
+            "question" | "open_question" => Self::Question,
+            _ => Self::Thought,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Decision => "decision",
+            Self::Assumption => "assumption",
+            Self::Question => "question",
+            Self::Thought => "thought",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    /// Unix seconds.
+    pub at: i64,
+    pub kind: NoteKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Done,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::InProgress => "in_progress",
+            Self::Done => "done",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    /// 1-based, in list order.
+    pub id: u32,
+    pub description: String,
+    #[serde(default)]
+    pub status: TaskStatus,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub conversation_id: String,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    #[serde(default)]
+    pub tasks: Vec<Task>,
+    /// Notes added since the last save.
+    #[serde(skip)]
+    unsaved_notes: Vec<Note>,
+    /// The task list as last saved.
+    #[serde(skip)]
+    saved_tasks: Vec<Task>,
+    /// Entries in the journal on disk.
+    #[serde(skip)]
+    journal_len: usize,
+}
+
+impl Session {
+    /// The saved session of `conversation_id`, or a new one.
+    pub fn load(conversation_id: &str) -> Self {
+        match sessions_dir() {
+            Some(dir) => Self::load_from(&dir, conversation_id),
+            None => Self::new(conversation_id),
+        }
+    }
+
+    pub fn save(&mut self) {
+        let Some(dir) = sessions_dir() else {
+            return;
+        };
+        if let Err(e) = self.save_to(&dir, FsyncPolicy::configured()) {
+            tracing::warn!("Saving session {} failed: {e}", self.conversation_id);
+        }
+    }
+
+    fn new(conversation_id: &str) -> Self {
+        Self { conversation_id: conversation_id.to_string(), ..Default::default() }
+    }
+
+    fn load_from(dir: &Path, conversation_id: &str) -> Self {
+        let path = session_path(dir, conversation_id);
+        // Left by a crash during compaction; the snapshot it was replacing
+        // and the journal are still whole
+        let _ = std::fs::remove_file(path.with_extension("json.tmp"));
+        let mut session = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| match serde_json::from_str::<Self>(&content) {
+                Ok(session) => Some(session),
+                Err(e) => {
+                    tracing::warn!("Session snapshot {} is unreadable, using its journal only: {e}", path.display());
+                    None
+                }
+            })
+            .unwrap_or_else(|| Self::new(conversation_id));
+        session.replay(&journal_path(dir, conversation_id));
+        session.saved_tasks = session.tasks.clone();
+        session
+    }
+
+    /// Apply the journal's entries. An unterminated or unparsable line is a
+    /// write cut short by a crash: it and anything after it are dropped.
+    fn replay(&mut self, journal: &Path) {
+        let Ok(content) = std::fs::read(journal) else {
+            return;
+        };
+        let mut valid_len = 0;
+        while let Some(end) = content[valid_len..].iter().position(|&b| b == b'\n') {
+            let Ok(entry) = serde_json::from_slice::<JournalEntry>(&content[valid_len..valid_len + end]) else {
+                break;
+            };
+            match entry {
+                JournalEntry::Note { note } => self.push_note(note),
+                JournalEntry::Tasks { tasks } => self.tasks = tasks,
+            }
+            self.journal_len += 1;
+            valid_len += end + 1;
+        }
+        if valid_len < content.len() {
+            tracing::warn!("Dropping the torn end of session journal {}", journal.display());
+            // So new entries don't land after the torn line
+            if let Ok(file) = OpenOptions::new().write(true).open(journal) {
+                let _ = file.set_len(valid_len as u64);
+            }
+        }
+    }
+
+    /// Persist what changed since the last save: appended to the journal,
+    /// or as a new snapshot once the journal is long.
+    fn save_to(&mut self, dir: &Path, fsync: FsyncPolicy) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let mut entries: Vec<JournalEntry> = self.unsaved_notes.iter().map(|note| JournalEntry::Note { note: note.clone() }).collect();
+        if self.tasks != self.saved_tasks {
+            entries.push(JournalEntry::Tasks { tasks: self.tasks.clone() });
+        }
+        if entries.is_empty() {
+            return Ok(());
+        }
+        if self.journal_len + entries.len() >= COMPACT_AFTER {
+            self.compact(dir, fsync)?;
+        } else {
+            let mut lines = String::new();
+            for entry in &entries {
+                lines.push_str(&serde_json::to_string(entry).map_err(std::io::Error::other)?);
+                lines.push('\n');
+            }
+            let mut journal = OpenOptions::new().create(true).append(true).open(journal_path(dir, &self.conversation_id))?;
+            // One write, so a crash tears at most the last entry
+            journal.write_all(lines.as_bytes())?;
+            if fsync == FsyncPolicy::Always {
+                journal.sync_data()?;
+            }
+            self.journal_len += entries.len();
+            journaled().lock().unwrap().insert((dir.to_path_buf(), self.conversation_id.clone()));
+        }
+        self.unsaved_notes.clear();
+        self.saved_tasks = self.tasks.clone();
+        Ok(())
+    }
+
+    /// Replace the snapshot with the whole session and empty the journal.
+    fn compact(&mut self, dir: &Path, fsync: FsyncPolicy) -> std::io::Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        let path = session_path(dir, &self.conversation_id);
+        let tmp = path.with_extension("json.tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(content.as_bytes())?;
+        if fsync != FsyncPolicy::Never {
+            file.sync_all()?;
+        }
+        std::fs::rename(tmp, path)?;
+        if fsync != FsyncPolicy::Never {
+            sync_dir(dir);
+        }
+        // The snapshot has everything; a crash before this only means the
+        // journal is replayed over it again, which gives the same session
+        match std::fs::remove_file(journal_path(dir, &self.conversation_id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        self.journal_len = 0;
+        Ok(())
+    }
+
+    /// Add a note; blank ones and repeats of the last one are dropped.
+    /// Returns whether it was added.
+    pub fn add_note(&mut self, kind: NoteKind, text: &str) -> bool {
+        let text = text.trim();
+        if text.is_empty() || self.notes.last().is_some_and(|n| n.kind == kind && n.text == text) {
+            return false;
+        }
+        let note = Note { at: chrono::Utc::now().timestamp(), kind, text: text.to_string() };
+        self.unsaved_notes.push(note.clone());
+        self.push_note(note);
+        true
+    }
+
+    fn push_note(&mut self, note: Note) {
+        self.notes.push(note);
+        if self.notes.len() > MAX_NOTES {
+            self.notes.drain(..self.notes.len() - MAX_NOTES);
+        }
+    }
+}
+
+/// The working note a tool call records, if any (`think`).
+pub fn note_from_tool(name: &str, args: &Value) -> Option<(NoteKind, String)> {
+    if name != "think" {
+        return None;
+    }
+    let text = args.get("thought").and_then(|v| v.as_str())?.trim();
+    let kind = args.get("kind").and_then(|v| v.as_str()).map_or(NoteKind::Thought, NoteKind::parse);
+    (!text.is_empty()).then(|| (kind, text.to_string()))
+}
+
+/// Sessions with journal entries written by this process.
+fn journaled() -> &'static Mutex<HashSet<(PathBuf, String)>> {
+    static INSTANCE: OnceLock<Mutex<HashSet<(PathBuf, String)>>> = OnceLock::new();
+    INSTANCE.get_or_init(Default::default)
+}
+
+/// Fold the journals this process wrote into fsynced snapshots, whatever
+/// the fsync policy, e.g. before the proxy exits. Returns how many.
+pub fn flush() -> usize {
+    let sessions: Vec<_> = journaled().lock().unwrap().drain().collect();
+    let mut flushed = 0;
+    for (dir, conversation_id) in sessions {
+        let mut session = Session::load_from(&dir, &conversation_id);
+        match session.compact(&dir, FsyncPolicy::Always) {
+            Ok(()) => flushed += 1,
+            Err(e) => tracing::warn!("Flushing session {conversation_id} failed: {e}"),
+        }
+    }
+    flushed
+}
+
+pub(crate) fn sessions_dir() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".forge").join("sessions"))
+}
+
+pub(crate) fn session_path(dir: &Path, conversation_id: &str) -> PathBuf {
+    let name: String = conversation_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    dir.join(format!("{name}.json"))
+}
+
+fn journal_path(dir: &Path, conversation_id: &str) -> PathBuf {
+    session_path(dir, conversation_id).with_extension("journal")
+}
+
+/// Make a rename in `dir` durable. Directories can't be opened for this on
+/// Windows, where the rename is durable on its own.
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_notes_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut session = Session::load_from(dir.path(), "conv/1");
+        assert!(session.notes.is_empty());
+
+        let args = serde_json::json!({ "thought": " Keep the v1 API ", "kind": "Decision" });
+        let (kind, text) = note_from_tool("think", &args).unwrap();
+        assert_eq!(kind, NoteKind::Decision);
+        assert!(session.add_note(kind, &text));
+        assert!(!session.add_note(kind, &text));
+        assert!(!session.add_note(NoteKind::Thought, "  "));
+        assert!(session.add_note(NoteKind::parse("open_question"), "Is the cache shared?"));
+        assert_eq!(note_from_tool("grep", &args), None);
+        session.save_to(dir.path(), FsyncPolicy::Never).unwrap();
+
+        let loaded = Session::load_from(dir.path(), "conv/1");
+        assert_eq!(loaded, session);
+        assert_eq!(loaded.notes[0].text, "Keep the v1 API");
+        assert_eq!(loaded.notes[1].kind, NoteKind::Question);
+        assert!(dir.path().join("conv_1.journal").is_file());
+    }
+
+    #[test]
+    fn test_session_journal_recovery_and_compaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("c.journal");
+        let mut session = Session::load_from(dir.path(), "c");
+        session.add_note(NoteKind::Decision, "first");
+        session.tasks.push(Task { id: 1, description: "Fix it".to_string(), status: TaskStatus::Pending });
+        session.save_to(dir.path(), FsyncPolicy::Always).unwrap();
+        // Nothing changed: nothing appended
+        let len = std::fs::metadata(&journal).unwrap().len();
+        session.save_to(dir.path(), FsyncPolicy::Always).unwrap();
+        assert_eq!(std::fs::metadata(&journal).unwrap().len(), len);
+
+        // A crash mid-append leaves half a line
+        let mut file = OpenOptions::new().append(true).open(&journal).unwrap();
+        file.write_all(br#"{"op":"note","note":{"at":1,"kin"#).unwrap();
+        let mut loaded = Session::load_from(dir.path(), "c");
+        assert_eq!(loaded, session);
+        assert_eq!(std::fs::metadata(&journal).unwrap().len(), len);
+        loaded.add_note(NoteKind::Thought, "after the crash");
+        loaded.save_to(dir.path(), FsyncPolicy::Always).unwrap();
+        assert_eq!(Session::load_from(dir.path(), "c").notes.len(), 2);
+
+        for i in 0..COMPACT_AFTER {
+            loaded.add_note(NoteKind::Thought, &format!("note {i}"));
+            loaded.save_to(dir.path(), FsyncPolicy::Compact).unwrap();
+        }
+        assert!(dir.path().join("c.json").is_file());
+        assert!(loaded.journal_len < COMPACT_AFTER);
+        let reloaded = Session::load_from(dir.path(), "c");
+        assert_eq!(reloaded, loaded);
+        assert_eq!(reloaded.notes.len(), COMPACT_AFTER + 2);
+        assert_eq!(reloaded.tasks[0].description, "Fix it");
+
+        assert_eq!(FsyncPolicy::parse(" Compact "), Some(FsyncPolicy::Compact));
+        assert_eq!(FsyncPolicy::parse("sometimes"), None);
+    }
+}
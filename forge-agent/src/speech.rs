@@ -0,0 +1,146 @@
+//! Speech-to-text for dictating chat messages.
+//!
+//! ```toml
+//! stt = "local"            # "groq" (default), "openai" or "local"
+//! stt_model = "whisper-1"  # provider model, defaults per provider
+//! whisper_cpp = "whisper-cli"                        # local: the whisper.cpp binary
+//! whisper_model = "~/.forge/models/ggml-base.en.bin" # local: a ggml model
+//! ```
+//!
+//! Providers get the recording over their OpenAI-compatible transcription
+//! endpoint; `local` runs whisper.cpp and nothing leaves the machine, which
+//! is the default (and the only choice) in local-only mode.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+/// Where dictation is transcribed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stt {
+    /// An OpenAI-compatible `/audio/transcriptions` endpoint.
+    Provider { provider: String, url: &'static str, model: String },
+    /// whisper.cpp on this machine.
+    Local { binary: String, model: PathBuf },
+}
+
+impl Stt {
+    pub fn configured() -> Result<Self> {
+        let var = crate::config::var;
+        Self::from_settings(
+            var("FORGE_STT").as_deref(),
+            var("FORGE_STT_MODEL"),
+            var("FORGE_WHISPER_CPP"),
+            var("FORGE_WHISPER_MODEL"),
+            crate::egress::local_only(),
+        )
+    }
+
+    fn from_settings(
+        stt: Option<&str>,
+        model: Option<String>,
+        whisper_cpp: Option<String>,
+        whisper_model: Option<String>,
+        local_only: bool,
+    ) -> Result<Self> {
+        let default = if local_only { "local" } else { "groq" };
+        let choice = stt.map(|s| s.trim().to_ascii_lowercase()).filter(|s| !s.is_empty());
+        let choice = choice.as_deref().unwrap_or(default);
+        let (url, default_model) = match choice {
+            "local" => {
+                let model = whisper_model
+                    .map(|m| expand_home(&m))
+                    .or_else(|| dirs::home_dir().map(|h| h.join(".forge").join("models").join("ggml-base.en.bin")))
+                    .ok_or_else(|| anyhow!("no home directory for the default whisper_model"))?;
+                return Ok(Self::Local { binary: whisper_cpp.unwrap_or_else(|| "whisper-cli".to_string()), model });
+            }
+            "groq" => ("https://api.groq.com/openai/v1/audio/transcriptions", "whisper-large-v3-turbo"),
+            "openai" => ("https://api.openai.com/v1/audio/transcriptions", "whisper-1"),
+            other => bail!("unknown stt '{other}' (expected groq, openai or local)"),
+        };
+        if local_only {
+            bail!("{choice} speech-to-text is disabled in local-only mode; set stt = \"local\" to use whisper.cpp");
+        }
+        Ok(Self::Provider {
+            provider: choice.to_string(),
+            url,
+            model: model.unwrap_or_else(|| default_model.to_string()),
+        })
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Transcribe a 16 kHz WAV recording with whisper.cpp.
+pub fn transcribe_local(binary: &str, model: &Path, wav: &[u8]) -> Result<String> {
+    if !model.is_file() {
+        bail!(
+            "whisper.cpp model not found at {}; download a ggml model (e.g. ggml-base.en.bin) and set whisper_model",
+            model.display()
+        );
+    }
+    let path = crate::tools::resources::TempPath::new("dictation", "wav");
+    std::fs::write(path.path(), wav).context("writing the recording")?;
+    let output = Command::new(binary)
+        .arg("-m")
+        .arg(model)
+        .arg("-f")
+        .arg(path.path())
+        .args(["-nt", "-np"])
+        .output();
+    drop(path);
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            bail!("{binary} not found; install whisper.cpp or set whisper_cpp to its path")
+        }
+        Err(e) => return Err(e).with_context(|| format!("running {binary}")),
+    };
+    if !output.status.success() {
+        bail!("{binary} failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(clean_transcript(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// One line of text from a transcript, without whisper's non-speech markers
+/// (`[BLANK_AUDIO]`, `(music)`, ...).
+pub fn clean_transcript(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| {
+            !line.is_empty()
+                && !((line.starts_with('[') && line.ends_with(']'))
+                    || (line.starts_with('(') && line.ends_with(')')))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stt_settings() {
+        let groq = Stt::from_settings(None, None, None, None, false).unwrap();
+        assert!(matches!(groq, Stt::Provider { ref model, .. } if model == "whisper-large-v3-turbo"));
+
+        let openai = Stt::from_settings(Some("OpenAI"), Some("gpt-4o-transcribe".into()), None, None, false).unwrap();
+        assert!(matches!(openai, Stt::Provider { ref provider, ref model, .. }
+            if provider == "openai" && model == "gpt-4o-transcribe"));
+
+        let local = Stt::from_settings(None, None, None, Some("/m/ggml.bin".into()), true).unwrap();
+        assert_eq!(local, Stt::Local { binary: "whisper-cli".into(), model: "/m/ggml.bin".into() });
+
+        assert!(Stt::from_settings(Some("groq"), None, None, None, true).is_err());
+        assert!(Stt::from_settings(Some("azure"), None, None, None, false).is_err());
+
+        assert_eq!(clean_transcript(" Fix the\n[BLANK_AUDIO]\n login bug.\n"), "Fix the login bug.");
+    }
+}
@@ -0,0 +1,191 @@
+//! Definitions in SQL files, read from their CREATE statements, so schema
+//! migrations show up in the index and `list_definitions`, and the
+//! Database Manager can point a table at the migration that creates it.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// SQL files read by [`table_locations`]; the rest are left out.
+const MAX_FILES: usize = 2_000;
+const MAX_FILE_BYTES: u64 = 2_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlKind {
+    Table,
+    View,
+    Function,
+    Procedure,
+    Index,
+    Type,
+    Trigger,
+    Sequence,
+    Schema,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlDefinition {
+    pub kind: SqlKind,
+    /// As written, unquoted: `orders`, `public.orders`.
+    pub name: String,
+    /// 1-based lines of the statement, inclusive.
+    pub line: usize,
+    pub end_line: usize,
+}
+
+impl SqlDefinition {
+    /// The name without its schema.
+    pub fn unqualified(&self) -> &str {
+        self.name.rsplit('.').next().unwrap_or(&self.name)
+    }
+}
+
+fn create_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r#"(?i)\bcreate\s+(?:or\s+replace\s+)?(?:(?:global|local)\s+)?(?:temp(?:orary)?\s+|unlogged\s+|virtual\s+)?(?:unique\s+)?(table|materialized\s+view|view|function|procedure|index|type|trigger|sequence|schema)\s+(?:if\s+not\s+exists\s+)?(?:concurrently\s+)?(?:if\s+not\s+exists\s+)?([A-Za-z_"`\[][\w$."`\[\]]*)"#,
+        )
+        .unwrap()
+    })
+}
+
+/// The CREATE statements in `content`, in order. Comments, strings and
+/// dollar-quoted function bodies are skipped.
+pub fn definitions(content: &str) -> Vec<SqlDefinition> {
+    let masked = mask(content);
+    let line_of = |offset: usize| content[..offset].matches('\n').count() + 1;
+    create_re()
+        .captures_iter(&masked)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            let name: String = caps[2].chars().filter(|c| !matches!(c, '"' | '`' | '[' | ']')).collect();
+            // `CREATE INDEX ON t (...)` has no name
+            if name.is_empty() || name.eq_ignore_ascii_case("on") {
+                return None;
+            }
+            let kind = match caps[1].to_ascii_lowercase().split_whitespace().last()? {
+                "table" => SqlKind::Table,
+                "view" => SqlKind::View,
+                "function" => SqlKind::Function,
+                "procedure" => SqlKind::Procedure,
+                "index" => SqlKind::Index,
+                "type" => SqlKind::Type,
+                "trigger" => SqlKind::Trigger,
+                "sequence" => SqlKind::Sequence,
+                _ => SqlKind::Schema,
+            };
+            let end = masked[whole.end()..].find(';').map_or(content.len(), |i| whole.end() + i);
+            Some(SqlDefinition { kind, name, line: line_of(whole.start()), end_line: line_of(end) })
+        })
+        .collect()
+}
+
+/// `content` with comments, string literals and dollar-quoted bodies
+/// blanked out, keeping byte offsets and newlines.
+fn mask(content: &str) -> String {
+    let bytes = content.as_bytes();
+    let mut out = bytes.to_vec();
+    let blank = |out: &mut Vec<u8>, from: usize, to: usize| {
+        for b in &mut out[from..to] {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+    };
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &content[i..];
+        let end = if rest.starts_with("--") {
+            rest.find('\n').map_or(bytes.len(), |n| i + n)
+        } else if rest.starts_with("/*") {
+            rest[2..].find("*/").map_or(bytes.len(), |n| i + 2 + n + 2)
+        } else if rest.starts_with('\'') {
+            rest[1..].find('\'').map_or(bytes.len(), |n| i + 1 + n + 1)
+        } else if let Some(tag) = dollar_tag(rest) {
+            rest[tag.len()..].find(tag).map_or(bytes.len(), |n| i + tag.len() + n + tag.len())
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+        blank(&mut out, i, end);
+        i = end;
+    }
+    // Only ASCII bytes were replaced, and only whole characters
+    String::from_utf8(out).unwrap_or_else(|_| content.to_string())
+}
+
+/// `$$` or `$tag$` at the start of `s`.
+fn dollar_tag(s: &str) -> Option<&str> {
+    let rest = s.strip_prefix('$')?;
+    let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    rest[len..].starts_with('$').then(|| &s[..len + 2])
+}
+
+/// Where each table and view is first created in the workspace's SQL
+/// files, by lower-case unqualified name: the file and the 1-based line.
+/// Files are read in path order, so numbered migrations win over later
+/// ones that recreate the table.
+pub fn table_locations(workspace: &Path) -> HashMap<String, (PathBuf, usize)> {
+    let mut files: Vec<PathBuf> = ignore::WalkBuilder::new(workspace)
+        .hidden(true)
+        .git_ignore(true)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .filter(|e| e.path().extension().is_some_and(|x| x.eq_ignore_ascii_case("sql")))
+        .filter(|e| e.metadata().is_ok_and(|m| m.len() <= MAX_FILE_BYTES))
+        .take(MAX_FILES)
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+
+    let mut locations = HashMap::new();
+    for path in files {
+        let Ok(content) = std::fs::read_to_string(&path) else { continue };
+        for def in definitions(&content) {
+            if matches!(def.kind, SqlKind::Table | SqlKind::View) {
+                locations.entry(def.unqualified().to_lowercase()).or_insert_with(|| (path.clone(), def.line));
+            }
+        }
+    }
+    locations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sql_definitions() {
+        let sql = "-- create table not_this (id int);\n\
+                   CREATE TABLE IF NOT EXISTS \"public\".\"orders\" (\n  id serial primary key,\n  note text default 'create table nope'\n);\n\
+                   CREATE INDEX ON orders (id);\n\
+                   create unique index orders_note_idx on orders (note);\n\
+                   CREATE OR REPLACE FUNCTION touch() RETURNS trigger AS $$\nBEGIN\n  CREATE TABLE inner_tmp (x int);\nEND;\n$$ LANGUAGE plpgsql;\n\
+                   create materialized view order_totals as select 1;\n";
+        let defs = definitions(sql);
+        let summary: Vec<(SqlKind, &str, usize, usize)> =
+            defs.iter().map(|d| (d.kind, d.name.as_str(), d.line, d.end_line)).collect();
+        assert_eq!(
+            summary,
+            [
+                (SqlKind::Table, "public.orders", 2, 5),
+                (SqlKind::Index, "orders_note_idx", 7, 7),
+                (SqlKind::Function, "touch", 8, 12),
+                (SqlKind::View, "order_totals", 13, 13),
+            ]
+        );
+        assert_eq!(defs[0].unqualified(), "orders");
+
+        let dir = tempfile::tempdir().unwrap();
+        let migrations = dir.path().join("db/migrations");
+        std::fs::create_dir_all(&migrations).unwrap();
+        std::fs::write(migrations.join("002_recreate.sql"), "DROP TABLE orders;\nCREATE TABLE orders (id int);\n").unwrap();
+        std::fs::write(migrations.join("001_init.sql"), "\nCREATE TABLE Orders (id int);\n").unwrap();
+        let locations = table_locations(dir.path());
+        assert_eq!(locations["orders"], (migrations.join("001_init.sql"), 2));
+    }
+}
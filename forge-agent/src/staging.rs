@@ -0,0 +1,129 @@
+//! Staged edits for the terminal agent (`forge-cli --staged`).
+//!
+//! File tools still run, so the agent sees its own changes, but each file's
+//! content from before its first edit is kept. At the end of the turn the
+//! changes are shown as diffs and each file is kept or reverted, like the
+//! IDE's review.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Tools whose edits are staged rather than approved one call at a time.
+pub fn is_file_edit(tool_name: &str) -> bool {
+    matches!(
+        tool_name,
+        "write_file" | "write_file_range" | "edit_file" | "apply_patch" | "delete_file" | "restore_file"
+    )
+}
+
+#[derive(Debug)]
+pub struct StagedEdits {
+    workspace: PathBuf,
+    /// Content before the first edit, `None` if the file didn't exist.
+    originals: BTreeMap<String, Option<Vec<u8>>>,
+}
+
+/// A file the turn changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagedFile {
+    pub path: String,
+    pub old: Option<Vec<u8>>,
+    pub new: Option<Vec<u8>>,
+}
+
+impl StagedEdits {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace, originals: BTreeMap::new() }
+    }
+
+    /// Remember the files `tool_name` is about to edit.
+    pub fn before(&mut self, tool_name: &str, args: &serde_json::Value) {
+        for path in crate::tools::workspace_diff::tool_edit_paths(tool_name, args) {
+            let full = self.workspace.join(path);
+            if self.originals.contains_key(path) || full.is_dir() {
+                continue;
+            }
+            self.originals.insert(path.to_string(), std::fs::read(full).ok());
+        }
+    }
+
+    /// Files whose content differs from before the turn, by path.
+    pub fn changes(&self) -> Vec<StagedFile> {
+        self.originals
+            .iter()
+            .filter_map(|(path, old)| {
+                let new = std::fs::read(self.workspace.join(path)).ok();
+                (new != *old).then(|| StagedFile { path: path.clone(), old: old.clone(), new })
+            })
+            .collect()
+    }
+}
+
+impl StagedFile {
+    /// Unified diff of the change, `a/` and `b/` prefixed like git's.
+    pub fn diff(&self) -> String {
+        let old = self.old.as_deref().map(String::from_utf8_lossy).unwrap_or_default();
+        let new = self.new.as_deref().map(String::from_utf8_lossy).unwrap_or_default();
+        let old_header = if self.old.is_some() { format!("a/{}", self.path) } else { "/dev/null".to_string() };
+        let new_header = if self.new.is_some() { format!("b/{}", self.path) } else { "/dev/null".to_string() };
+        similar::TextDiff::from_lines(old.as_ref(), new.as_ref())
+            .unified_diff()
+            .context_radius(3)
+            .header(&old_header, &new_header)
+            .to_string()
+    }
+
+    /// Put the file back as it was before the turn.
+    pub fn revert(&self, workspace: &Path) -> io::Result<()> {
+        let full = workspace.join(&self.path);
+        match &self.old {
+            Some(content) => {
+                if let Some(parent) = full.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(full, content)
+            }
+            None if full.exists() => std::fs::remove_file(full),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_staged_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(root.join("same.txt"), "x\n").unwrap();
+        let mut staged = StagedEdits::new(root.to_path_buf());
+
+        staged.before("edit_file", &json!({ "path": "a.txt" }));
+        std::fs::write(root.join("a.txt"), "one\n2\n").unwrap();
+        // A second edit keeps the first original
+        staged.before("write_file", &json!({ "path": "a.txt" }));
+        std::fs::write(root.join("a.txt"), "one\n2\nthree\n").unwrap();
+        staged.before("apply_patch", &json!({ "input": "*** Begin Patch\n*** Add File: new.txt\n+hi\n*** Update File: same.txt\n*** End Patch" }));
+        std::fs::write(root.join("new.txt"), "hi\n").unwrap();
+
+        let changes = staged.changes();
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["a.txt", "new.txt"]);
+        let diff = changes[0].diff();
+        assert!(diff.starts_with("--- a/a.txt\n+++ b/a.txt\n"));
+        assert!(diff.contains("-two\n") && diff.contains("+2\n+three\n"));
+        assert!(changes[1].diff().starts_with("--- /dev/null\n+++ b/new.txt\n"));
+
+        for change in &changes {
+            change.revert(root).unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(root.join("a.txt")).unwrap(), "one\ntwo\n");
+        assert!(!root.join("new.txt").exists());
+        assert!(staged.changes().is_empty());
+    }
+}
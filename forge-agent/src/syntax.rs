@@ -0,0 +1,323 @@
+//! Tree-sitter grammars shared by the indexer and the symbol tools.
+//!
+//! [`lang_configs`] lists the languages forge-agent links a grammar for,
+//! with the node kinds that define symbols in each. JavaScript goes through
+//! the TSX grammar (see Cargo.toml).
+
+use std::path::Path;
+
+use tree_sitter::{Language, Node, Parser, Tree};
+
+pub struct LangConfig {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    language: fn() -> Language,
+    /// Node kinds that define a named symbol.
+    pub definitions: &'static [&'static str],
+    /// Definitions whose `body` holds more definitions (classes, impls).
+    pub containers: &'static [&'static str],
+    /// Nodes wrapping a definition (`export`, decorators), with the field
+    /// the definition is in.
+    pub wrappers: &'static [(&'static str, &'static str)],
+    /// Comments and attributes that belong to the definition after them.
+    pub leading: &'static [&'static str],
+    /// Joins a container and member name: `Foo::bar`, `Foo.bar`.
+    pub separator: &'static str,
+    /// Node kinds that name something; what a rename touches (not strings
+    /// or comments).
+    pub identifiers: &'static [&'static str],
+}
+
+static CONFIGS: &[LangConfig] = &[
+    LangConfig {
+        name: "rust",
+        extensions: &["rs"],
+        language: tree_sitter_rust::language,
+        definitions: &[
+            "function_item",
+            "function_signature_item",
+            "struct_item",
+            "enum_item",
+            "union_item",
+            "trait_item",
+            "impl_item",
+            "mod_item",
+            "macro_definition",
+            "const_item",
+            "static_item",
+            "type_item",
+        ],
+        containers: &["impl_item", "trait_item", "mod_item"],
+        wrappers: &[],
+        leading: &["line_comment", "block_comment", "attribute_item"],
+        separator: "::",
+        identifiers: &["identifier", "type_identifier", "field_identifier", "shorthand_field_identifier"],
+    },
+    LangConfig {
+        name: "python",
+        extensions: &["py", "pyi"],
+        language: tree_sitter_python::language,
+        definitions: &["function_definition", "class_definition"],
+        containers: &["class_definition"],
+        wrappers: &[("decorated_definition", "definition")],
+        leading: &["comment"],
+        separator: ".",
+        identifiers: &["identifier"],
+    },
+    LangConfig {
+        name: "typescript",
+        extensions: &["ts", "mts", "cts"],
+        language: tree_sitter_typescript::language_typescript,
+        definitions: TS_DEFINITIONS,
+        containers: &["class_declaration", "abstract_class_declaration"],
+        wrappers: &[("export_statement", "declaration")],
+        leading: &["comment"],
+        separator: ".",
+        identifiers: TS_IDENTIFIERS,
+    },
+    LangConfig {
+        name: "tsx",
+        extensions: &["tsx", "js", "jsx", "mjs", "cjs"],
+        language: tree_sitter_typescript::language_tsx,
+        definitions: TS_DEFINITIONS,
+        containers: &["class_declaration", "abstract_class_declaration"],
+        wrappers: &[("export_statement", "declaration")],
+        leading: &["comment"],
+        separator: ".",
+        identifiers: TS_IDENTIFIERS,
+    },
+    LangConfig {
+        name: "go",
+        extensions: &["go"],
+        language: tree_sitter_go::language,
+        definitions: &["function_declaration", "method_declaration", "type_declaration", "const_declaration", "var_declaration"],
+        containers: &[],
+        wrappers: &[],
+        leading: &["comment"],
+        separator: ".",
+        identifiers: &["identifier", "type_identifier", "field_identifier"],
+    },
+    LangConfig {
+        name: "c",
+        extensions: &["c", "h"],
+        language: tree_sitter_c::language,
+        definitions: &["function_definition", "struct_specifier", "union_specifier", "enum_specifier", "type_definition"],
+        containers: &[],
+        wrappers: &[],
+        leading: &["comment"],
+        separator: ".",
+        identifiers: &["identifier", "type_identifier", "field_identifier"],
+    },
+    LangConfig {
+        name: "cpp",
+        extensions: &["cc", "cpp", "cxx", "c++", "hh", "hpp", "hxx", "h++", "ipp", "tpp"],
+        language: tree_sitter_cpp::language,
+        definitions: &[
+            "function_definition",
+            "class_specifier",
+            "struct_specifier",
+            "union_specifier",
+            "enum_specifier",
+            "namespace_definition",
+            "type_definition",
+            "alias_declaration",
+        ],
+        containers: &["class_specifier", "struct_specifier", "namespace_definition"],
+        // A template's declaration is its last child, with no field name
+        wrappers: &[("template_declaration", "")],
+        leading: &["comment"],
+        separator: "::",
+        identifiers: &["identifier", "type_identifier", "field_identifier", "namespace_identifier"],
+    },
+    LangConfig {
+        name: "hcl",
+        extensions: &["tf", "tfvars", "hcl"],
+        language: tree_sitter_hcl::language,
+        definitions: &["block"],
+        containers: &[],
+        wrappers: &[],
+        leading: &["comment"],
+        separator: ".",
+        identifiers: &["identifier"],
+    },
+];
+
+const TS_DEFINITIONS: &[&str] = &[
+    "function_declaration",
+    "generator_function_declaration",
+    "class_declaration",
+    "abstract_class_declaration",
+    "interface_declaration",
+    "type_alias_declaration",
+    "enum_declaration",
+    "method_definition",
+    "lexical_declaration",
+];
+
+const TS_IDENTIFIERS: &[&str] = &[
+    "identifier",
+    "property_identifier",
+    "type_identifier",
+    "shorthand_property_identifier",
+    "shorthand_property_identifier_pattern",
+];
+
+pub fn lang_configs() -> &'static [LangConfig] {
+    CONFIGS
+}
+
+/// The grammar for `path`, by extension.
+pub fn config_for(path: &Path) -> Option<&'static LangConfig> {
+    config_for_extension(path.extension()?.to_str()?)
+}
+
+pub fn config_for_extension(ext: &str) -> Option<&'static LangConfig> {
+    let ext = ext.to_ascii_lowercase();
+    CONFIGS.iter().find(|c| c.extensions.contains(&ext.as_str()))
+}
+
+impl LangConfig {
+    /// The node whose children are the file's top-level items: the root,
+    /// or for HCL the `body` under it.
+    pub fn top_level<'t>(&self, root: Node<'t>) -> Node<'t> {
+        match root.named_child(0) {
+            Some(body) if self.name == "hcl" && body.kind() == "body" => body,
+            _ => root,
+        }
+    }
+
+    pub fn parse(&self, content: &str) -> Option<Tree> {
+        let mut parser = Parser::new();
+        parser.set_language(&(self.language)()).ok()?;
+        parser.parse(content, None)
+    }
+
+    /// The definition inside `node` if it's a wrapper, else `node`.
+    pub fn unwrap<'t>(&self, node: Node<'t>) -> Node<'t> {
+        match self.wrappers.iter().find(|(kind, _)| *kind == node.kind()) {
+            Some((_, "")) => node.named_child(node.named_child_count().saturating_sub(1)).unwrap_or(node),
+            Some((_, field)) => node.child_by_field_name(field).unwrap_or(node),
+            None => node,
+        }
+    }
+
+    pub fn is_definition(&self, node: Node) -> bool {
+        self.definitions.contains(&node.kind())
+    }
+
+    pub fn is_container(&self, node: Node) -> bool {
+        self.containers.contains(&node.kind())
+    }
+
+    /// The name a definition introduces. Impls are named after their type,
+    /// Go methods after their receiver (`Server.Start`), C functions and
+    /// typedefs after their innermost declarator, Terraform blocks after
+    /// their address (`aws_instance.web`, `var.region`).
+    pub fn definition_name(&self, node: Node, src: &[u8]) -> Option<String> {
+        let text = |n: Node| n.utf8_text(src).ok().map(str::to_string);
+        match node.kind() {
+            "impl_item" => {
+                let ty = text(node.child_by_field_name("type")?)?;
+                Some(ty.split('<').next().unwrap_or(&ty).trim().to_string())
+            }
+            "method_declaration" if self.name == "go" => {
+                let name = text(node.child_by_field_name("name")?)?;
+                match node.child_by_field_name("receiver").and_then(|r| find_kind(r, "type_identifier")) {
+                    Some(receiver) => Some(format!("{}.{name}", text(receiver)?)),
+                    None => Some(name),
+                }
+            }
+            "function_definition" | "type_definition" if node.child_by_field_name("declarator").is_some() => {
+                let mut declarator = node.child_by_field_name("declarator")?;
+                while let Some(inner) = declarator.child_by_field_name("declarator") {
+                    declarator = inner;
+                }
+                text(declarator)
+            }
+            "block" if self.name == "hcl" => {
+                let mut cursor = node.walk();
+                let mut parts = node.named_children(&mut cursor).take_while(|c| matches!(c.kind(), "identifier" | "string_lit"));
+                let block_type = text(parts.next()?)?;
+                let labels: Vec<String> = parts.filter_map(text).map(|l| l.trim_matches('"').to_string()).collect();
+                let prefix = match block_type.as_str() {
+                    "resource" => None,
+                    "variable" => Some("var"),
+                    _ => Some(block_type.as_str()),
+                };
+                Some(prefix.into_iter().map(str::to_string).chain(labels).collect::<Vec<_>>().join("."))
+            }
+            "lexical_declaration" | "type_declaration" | "const_declaration" | "var_declaration" => {
+                let mut cursor = node.walk();
+                let first = node.named_children(&mut cursor).find_map(|c| c.child_by_field_name("name"));
+                text(first?)
+            }
+            _ => text(node.child_by_field_name("name")?),
+        }
+    }
+}
+
+fn find_kind<'t>(node: Node<'t>, kind: &str) -> Option<Node<'t>> {
+    if node.kind() == kind {
+        return Some(node);
+    }
+    let mut cursor = node.walk();
+    let children: Vec<Node<'t>> = node.named_children(&mut cursor).collect();
+    children.into_iter().find_map(|c| find_kind(c, kind))
+}
+
+/// Last row a node covers; a node ending at column 0 ends on the row before.
+pub fn end_row(node: Node) -> usize {
+    let (start, end) = (node.start_position(), node.end_position());
+    if end.column == 0 && end.row > start.row {
+        end.row - 1
+    } else {
+        end.row
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definition_names() {
+        let go = config_for(Path::new("main.go")).unwrap();
+        let src = "package main\n\nfunc (s *Server) Start() {}\n\ntype Config struct{}\n";
+        let tree = go.parse(src).unwrap();
+        let mut cursor = tree.root_node().walk();
+        let names: Vec<String> = tree
+            .root_node()
+            .named_children(&mut cursor)
+            .filter(|n| go.is_definition(*n))
+            .filter_map(|n| go.definition_name(n, src.as_bytes()))
+            .collect();
+        assert_eq!(names, ["Server.Start", "Config"]);
+        assert_eq!(config_for(Path::new("app.jsx")).unwrap().name, "tsx");
+        assert!(config_for(Path::new("README.md")).is_none());
+
+        let cpp = config_for(Path::new("server.hpp")).unwrap();
+        let src = "namespace net {\nclass Server {};\n}\ntemplate <typename T> T *make(int n) { return 0; }\nvoid Server::start() {}\n";
+        let tree = cpp.parse(src).unwrap();
+        let mut cursor = tree.root_node().walk();
+        let names: Vec<String> = tree
+            .root_node()
+            .named_children(&mut cursor)
+            .map(|n| cpp.unwrap(n))
+            .filter(|n| cpp.is_definition(*n))
+            .filter_map(|n| cpp.definition_name(n, src.as_bytes()))
+            .collect();
+        assert_eq!(names, ["net", "make", "Server::start"]);
+
+        let hcl = config_for(Path::new("main.tf")).unwrap();
+        let src = "variable \"region\" {}\n\nresource \"aws_instance\" \"web\" {\n  ami = var.region\n}\n\nlocals {\n  x = 1\n}\n";
+        let tree = hcl.parse(src).unwrap();
+        let top = hcl.top_level(tree.root_node());
+        let mut cursor = top.walk();
+        let names: Vec<String> = top
+            .named_children(&mut cursor)
+            .filter(|n| hcl.is_definition(*n))
+            .filter_map(|n| hcl.definition_name(n, src.as_bytes()))
+            .collect();
+        assert_eq!(names, ["var.region", "aws_instance.web", "locals"]);
+    }
+}
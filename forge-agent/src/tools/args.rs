@@ -0,0 +1,327 @@
+//! Typed tool arguments.
+//!
+//! Each struct is both what the tool deserializes its arguments into and,
+//! through schemars, the `parameters` schema [`super::definitions`] sends to
+//! the model, so the two can't drift apart. Field doc comments become the
+//! parameter descriptions.
+
+use schemars::gen::SchemaSettings;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::ToolResult;
+
+/// Deserialize `args` for `tool`; the error is a result the model can fix.
+pub fn parse<T: DeserializeOwned>(tool: &str, args: &Value) -> Result<T, ToolResult> {
+    // No arguments at all is the same as `{}`
+    let args = if args.is_null() { Value::Object(Default::default()) } else { args.clone() };
+    serde_json::from_value(args).map_err(|e| ToolResult::err(format!("invalid arguments for {tool}: {e}")))
+}
+
+/// The `parameters` schema for `T`, in the plain shape the definitions use
+/// (optional fields are just not required; no `$schema`, `title`, or refs).
+pub fn parameters<T: JsonSchema>() -> Value {
+    let generator = SchemaSettings::draft07()
+        .with(|s| {
+            s.option_nullable = false;
+            s.option_add_null_type = false;
+            s.inline_subschemas = true;
+        })
+        .into_generator();
+    let mut schema = serde_json::to_value(generator.into_root_schema_for::<T>()).unwrap_or_default();
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("$schema");
+        object.remove("title");
+        object.remove("definitions");
+        object.entry("properties").or_insert_with(|| Value::Object(Default::default()));
+    }
+    schema
+}
+
+/// A tool definition with parameters derived from `T`.
+pub fn definition<T: JsonSchema>(name: &str, description: &str) -> Value {
+    serde_json::json!({
+        "name": name,
+        "description": description,
+        "parameters": parameters::<T>(),
+    })
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ReadFileArgs {
+    /// Path to the file
+    pub path: String,
+    /// Optional start line (1-indexed)
+    pub start_line: Option<u64>,
+    /// Optional end line
+    pub end_line: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WriteFileArgs {
+    /// Path for the file
+    pub path: String,
+    /// Content to write
+    pub content: String,
+    /// Replace an existing file you haven't read (default false)
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WriteFileRangeArgs {
+    /// Path to the file
+    pub path: String,
+    /// First line to replace (1-indexed)
+    pub start_line: u64,
+    /// Last line to replace; start_line - 1 inserts before start_line
+    pub end_line: u64,
+    /// The current text of those lines, without read_file's line numbers (empty for an insert); the write is refused if they differ
+    pub expected: String,
+    /// The new text for the range
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct EditFileArgs {
+    /// Path to the file
+    pub path: String,
+    /// Exact text to find and replace
+    pub old_str: Option<String>,
+    /// Replacement text
+    pub new_str: String,
+    /// First line of a range to replace instead of old_str (1-indexed)
+    pub start_line: Option<u64>,
+    /// Last line of the range to replace
+    pub end_line: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ApplyPatchArgs {
+    /// V4A format patch with *** Begin Patch, *** Update File:, - removals, + additions
+    pub input: Option<String>,
+    /// Path to file (for unified diff format)
+    pub path: Option<String>,
+    /// Unified diff patch content (for single file)
+    pub patch: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ListFilesArgs {
+    /// Directory path
+    #[serde(default = "current_dir")]
+    pub path: String,
+    /// List recursively, as a tree
+    #[serde(default)]
+    pub recursive: bool,
+    /// Rough size limit of the recursive tree in tokens (default 2000)
+    pub max_tokens: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DeleteFileArgs {
+    /// Path to file or directory to delete
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct RestoreFileArgs {
+    /// Path the file or directory was deleted from (omit to list the trash)
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct GrepArgs {
+    /// Exact text or regex pattern
+    pub pattern: String,
+    /// Directory to search (default: current)
+    #[serde(default = "current_dir")]
+    pub path: String,
+    /// File filter, e.g., '*.rs'
+    #[serde(alias = "file_pattern")]
+    pub glob: Option<String>,
+    /// Ignore case
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Context lines (0-5)
+    #[serde(default)]
+    pub context: u64,
+    /// Max matches to return (default 100)
+    pub max_results: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct GlobArgs {
+    /// Pattern relative to `path`, like '*.rs', '**/*.test.ts', 'src/**/*.{ts,tsx}'; a leading '!' excludes
+    #[serde(default)]
+    pub pattern: String,
+    /// More patterns, combined with `pattern`, e.g. ['**/*.rs', '!**/tests/**']
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Base directory
+    #[serde(default = "current_dir")]
+    pub path: String,
+    /// Order: 'path' (default), 'mtime' (newest first) or 'size' (largest first)
+    pub sort: Option<GlobSort>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GlobSort {
+    Path,
+    Mtime,
+    Size,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct OpenInEditorArgs {
+    /// File to show, relative to the workspace
+    pub path: String,
+    /// Line to put the cursor on (1-indexed)
+    pub line: Option<u32>,
+    /// Column on that line (1-indexed)
+    pub column: Option<u32>,
+    /// 'open' (default), 'reveal' (select it in the file explorer) or 'diff' (its uncommitted changes)
+    #[serde(default)]
+    pub action: EditorAction,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EditorAction {
+    #[default]
+    Open,
+    Reveal,
+    Diff,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginAction {
+    Search,
+    List,
+    Install,
+    Remove,
+    Enable,
+    Disable,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct PluginsArgs {
+    /// search the registry, list installed plugins, or install/remove/enable/disable one
+    pub action: PluginAction,
+    /// What to search for, e.g. a language name (search)
+    pub query: Option<String>,
+    /// The plugin's author.name id from search or list, or its unique name (install, remove, enable, disable)
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SettingChange {
+    /// section.name as in settings.toml, e.g. "core.color-theme", "editor.font-size", "ui.scale"
+    pub key: String,
+    /// The new value, of the setting's type (string, number or boolean)
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct KeymapChange {
+    /// e.g. "ctrl+shift+p", or "ctrl+k ctrl+s" for a chord
+    pub key: String,
+    /// The command id, or "-command" to remove a default binding
+    pub command: String,
+    /// Context the binding applies in, e.g. "editor_focus"
+    pub when: Option<String>,
+    /// Modal modes it applies in: any of i, n, v, t
+    pub mode: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ModifySettingsArgs {
+    /// Settings to change
+    #[serde(default)]
+    pub settings: Vec<SettingChange>,
+    /// Key bindings to add
+    #[serde(default)]
+    pub keymaps: Vec<KeymapChange>,
+    /// Only show the diff, write nothing
+    #[serde(default)]
+    pub preview: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct AskFollowupQuestionArgs {
+    /// The question to ask
+    pub question: String,
+    /// Suggested answers, shown as buttons (e.g. ["Yes", "No"]); the user can still type their own
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// The suggested answer to highlight, one of `options`
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct FocusChainArgs {
+    /// 'set' (replace the list with `tasks`), 'add' (append `tasks`) or 'update' (set task `id` to `status`)
+    pub action: FocusChainAction,
+    /// Task descriptions, for 'set' and 'add'
+    #[serde(default)]
+    pub tasks: Vec<String>,
+    /// The task to update, as numbered in the list
+    pub id: Option<u32>,
+    /// New status for 'update': 'pending', 'in_progress' or 'done'
+    pub status: Option<FocusChainStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusChainAction {
+    Set,
+    Add,
+    Update,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusChainStatus {
+    Pending,
+    InProgress,
+    Done,
+}
+
+fn current_dir() -> String {
+    ".".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parameters_shape() {
+        assert_eq!(
+            parameters::<ReadFileArgs>(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path to the file" },
+                    "start_line": { "type": "integer", "format": "uint64", "minimum": 0.0, "description": "Optional start line (1-indexed)" },
+                    "end_line": { "type": "integer", "format": "uint64", "minimum": 0.0, "description": "Optional end line" }
+                },
+                "required": ["path"]
+            })
+        );
+        assert_eq!(parameters::<GlobArgs>()["required"], json!(["pattern"]));
+    }
+
+    #[test]
+    fn test_parse() {
+        let args: GrepArgs = parse("grep", &json!({ "pattern": "fn main", "file_pattern": "*.rs" })).unwrap();
+        assert_eq!((args.path.as_str(), args.glob.as_deref(), args.context), (".", Some("*.rs"), 0));
+        let err = parse::<ReadFileArgs>("read_file", &json!({})).unwrap_err();
+        assert_eq!(err.output, "invalid arguments for read_file: missing field `path`");
+    }
+}
@@ -0,0 +1,462 @@
+//! Dependency vulnerability and license audit.
+//!
+//! Runs the ecosystem's audit CLI when available:
+//! - Rust: `cargo audit --json`, licenses from `cargo metadata`
+//! - Node: `npm audit --json`, licenses from package-lock.json
+//! - Python: `pip-audit -f json`
+//!
+//! When the CLI is missing, pinned dependency versions from the manifests are
+//! checked directly against the OSV database (api.osv.dev).
+
+use super::ToolResult;
+use crate::manifest::{self, Dependency, Ecosystem};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::process::Command;
+
+const OSV_QUERY_BATCH_URL: &str = "https://api.osv.dev/v1/querybatch";
+/// OSV accepts up to 1000 queries per batch; keep requests small.
+const OSV_BATCH_SIZE: usize = 200;
+
+/// A known vulnerability affecting a dependency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vulnerability {
+    pub package: String,
+    pub version: String,
+    pub id: String,
+    pub severity: String,
+    pub title: String,
+    /// Version(s) that fix the issue, if known.
+    pub fixed_in: Option<String>,
+}
+
+/// Audit results for one ecosystem.
+#[derive(Debug, Default)]
+pub struct AuditReport {
+    pub ecosystem: String,
+    /// Where the data came from (`cargo audit`, `osv`, ...).
+    pub source: String,
+    pub vulnerabilities: Vec<Vulnerability>,
+    /// License expression → number of packages using it.
+    pub licenses: BTreeMap<String, usize>,
+    pub notes: Vec<String>,
+}
+
+/// audit_dependencies tool - "are my deps safe to ship?"
+pub async fn audit_dependencies(args: &Value, workdir: &Path) -> ToolResult {
+    let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+    let dir = workdir.join(path);
+    let include_licenses = args.get("licenses").and_then(|v| v.as_bool()).unwrap_or(true);
+
+    let mut reports = Vec::new();
+    if dir.join("Cargo.toml").exists() {
+        reports.push(audit_cargo(&dir, include_licenses).await);
+    }
+    if dir.join("package.json").exists() {
+        reports.push(audit_npm(&dir, include_licenses).await);
+    }
+    if dir.join("pyproject.toml").exists() || dir.join("requirements.txt").exists() {
+        reports.push(audit_python(&dir).await);
+    }
+
+    if reports.is_empty() {
+        return ToolResult::err(format!(
+            "No supported manifest found in {} (Cargo.toml, package.json, pyproject.toml, requirements.txt)",
+            path
+        ));
+    }
+
+    let vulnerable = reports.iter().any(|r| !r.vulnerabilities.is_empty());
+    let output = reports.iter().map(format_report).collect::<Vec<_>>().join("\n");
+    if vulnerable {
+        ToolResult::err(output)
+    } else {
+        ToolResult::ok(output)
+    }
+}
+
+// ── Rust ────────────────────────────────────────────────────────────
+
+async fn audit_cargo(dir: &Path, include_licenses: bool) -> AuditReport {
+    let mut report = AuditReport { ecosystem: "Rust".to_string(), ..Default::default() };
+
+    match run_json(dir, "cargo", &["audit", "--json"]).await {
+        Some(json) => {
+            report.source = "cargo audit".to_string();
+            report.vulnerabilities = parse_cargo_audit(&json);
+        }
+        None => osv_fallback(dir, Ecosystem::Cargo, "cargo-audit", &mut report).await,
+    }
+
+    if include_licenses {
+        if let Some(meta) = run_json(dir, "cargo", &["metadata", "--format-version", "1"]).await {
+            for pkg in meta.get("packages").and_then(|p| p.as_array()).into_iter().flatten() {
+                let license = pkg.get("license").and_then(|l| l.as_str()).unwrap_or("UNKNOWN");
+                *report.licenses.entry(license.to_string()).or_default() += 1;
+            }
+        }
+    }
+    report
+}
+
+/// Parse `cargo audit --json` output.
+pub fn parse_cargo_audit(json: &Value) -> Vec<Vulnerability> {
+    let list = json
+        .get("vulnerabilities")
+        .and_then(|v| v.get("list"))
+        .and_then(|l| l.as_array());
+    list.into_iter()
+        .flatten()
+        .map(|v| {
+            let advisory = v.get("advisory").unwrap_or(&Value::Null);
+            let pkg = v.get("package").unwrap_or(&Value::Null);
+            let patched = v
+                .get("versions")
+                .and_then(|ver| ver.get("patched"))
+                .and_then(|p| p.as_array())
+                .map(|p| p.iter().filter_map(|x| x.as_str()).collect::<Vec<_>>().join(", "))
+                .filter(|s| !s.is_empty());
+            Vulnerability {
+                package: str_field(pkg, "name"),
+                version: str_field(pkg, "version"),
+                id: str_field(advisory, "id"),
+                // RustSEC has no severity label; informational advisories
+                // (unmaintained, unsound) are tagged as such.
+                severity: advisory
+                    .get("informational")
+                    .and_then(|i| i.as_str())
+                    .unwrap_or("vulnerability")
+                    .to_string(),
+                title: str_field(advisory, "title"),
+                fixed_in: patched,
+            }
+        })
+        .collect()
+}
+
+// ── Node ────────────────────────────────────────────────────────────
+
+async fn audit_npm(dir: &Path, include_licenses: bool) -> AuditReport {
+    let mut report = AuditReport { ecosystem: "Node".to_string(), ..Default::default() };
+
+    match run_json(dir, "npm", &["audit", "--json"]).await {
+        Some(json) => {
+            report.source = "npm audit".to_string();
+            report.vulnerabilities = parse_npm_audit(&json);
+        }
+        None => osv_fallback(dir, Ecosystem::Npm, "npm", &mut report).await,
+    }
+
+    if include_licenses {
+        let lock = std::fs::read_to_string(dir.join("package-lock.json"))
+            .ok()
+            .and_then(|l| serde_json::from_str::<Value>(&l).ok());
+        let packages = lock.as_ref().and_then(|l| l.get("packages")).and_then(|p| p.as_object());
+        for (key, pkg) in packages.into_iter().flatten() {
+            if key.is_empty() {
+                continue; // the root project
+            }
+            let license = pkg.get("license").and_then(|l| l.as_str()).unwrap_or("UNKNOWN");
+            *report.licenses.entry(license.to_string()).or_default() += 1;
+        }
+        if packages.is_none() {
+            report.notes.push("No package-lock.json; license summary unavailable".to_string());
+        }
+    }
+    report
+}
+
+/// Parse `npm audit --json` (npm 7+ format).
+pub fn parse_npm_audit(json: &Value) -> Vec<Vulnerability> {
+    let mut vulns = Vec::new();
+    let Some(map) = json.get("vulnerabilities").and_then(|v| v.as_object()) else {
+        return vulns;
+    };
+    for (name, entry) in map {
+        let severity = str_field(entry, "severity");
+        let range = str_field(entry, "range");
+        let fixed_in = match entry.get("fixAvailable") {
+            Some(Value::Object(fix)) => fix.get("version").and_then(|v| v.as_str()).map(String::from),
+            Some(Value::Bool(true)) => Some("available".to_string()),
+            _ => None,
+        };
+        // `via` holds advisory objects, or names of vulnerable transitive deps
+        let advisories: Vec<&Value> = entry
+            .get("via")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter(|v| v.is_object())
+            .collect();
+        if advisories.is_empty() {
+            continue;
+        }
+        for adv in advisories {
+            vulns.push(Vulnerability {
+                package: name.clone(),
+                version: range.clone(),
+                id: adv
+                    .get("url")
+                    .and_then(|u| u.as_str())
+                    .and_then(|u| u.rsplit('/').next())
+                    .unwrap_or("")
+                    .to_string(),
+                severity: adv
+                    .get("severity")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or(&severity)
+                    .to_string(),
+                title: str_field(adv, "title"),
+                fixed_in: fixed_in.clone(),
+            });
+        }
+    }
+    vulns
+}
+
+// ── Python ──────────────────────────────────────────────────────────
+
+async fn audit_python(dir: &Path) -> AuditReport {
+    let mut report = AuditReport { ecosystem: "Python".to_string(), ..Default::default() };
+
+    let args: &[&str] = if dir.join("requirements.txt").exists() {
+        &["-f", "json", "-r", "requirements.txt"]
+    } else {
+        &["-f", "json"]
+    };
+    match run_json(dir, "pip-audit", args).await {
+        Some(json) => {
+            report.source = "pip-audit".to_string();
+            report.vulnerabilities = parse_pip_audit(&json);
+        }
+        None => osv_fallback(dir, Ecosystem::Python, "pip-audit", &mut report).await,
+    }
+    report
+        .notes
+        .push("License summary not available for Python (pip-licenses not run)".to_string());
+    report
+}
+
+/// Parse `pip-audit -f json` output.
+pub fn parse_pip_audit(json: &Value) -> Vec<Vulnerability> {
+    // Newer versions wrap the list in {"dependencies": [...]}
+    let deps = json
+        .get("dependencies")
+        .and_then(|d| d.as_array())
+        .or_else(|| json.as_array());
+    let mut vulns = Vec::new();
+    for dep in deps.into_iter().flatten() {
+        for v in dep.get("vulns").and_then(|v| v.as_array()).into_iter().flatten() {
+            let fixed = v
+                .get("fix_versions")
+                .and_then(|f| f.as_array())
+                .map(|f| f.iter().filter_map(|x| x.as_str()).collect::<Vec<_>>().join(", "))
+                .filter(|s| !s.is_empty());
+            vulns.push(Vulnerability {
+                package: str_field(dep, "name"),
+                version: str_field(dep, "version"),
+                id: str_field(v, "id"),
+                severity: "unknown".to_string(),
+                title: str_field(v, "description").chars().take(160).collect(),
+                fixed_in: fixed,
+            });
+        }
+    }
+    vulns
+}
+
+// ── OSV fallback ────────────────────────────────────────────────────
+
+async fn osv_fallback(dir: &Path, ecosystem: Ecosystem, cli: &str, report: &mut AuditReport) {
+    report.source = "osv.dev".to_string();
+    report.notes.push(format!("{} not available; queried OSV for pinned versions", cli));
+
+    let deps: Vec<Dependency> = manifest::workspace_dependencies(dir)
+        .into_iter()
+        .filter(|d| d.ecosystem == ecosystem)
+        .collect();
+    let pinned: Vec<(&Dependency, String)> = deps
+        .iter()
+        .filter_map(|d| exact_version(d.version.as_deref()?).map(|v| (d, v)))
+        .collect();
+    let skipped = deps.len() - pinned.len();
+    if skipped > 0 {
+        report.notes.push(format!(
+            "{} dependencies skipped (no exact version; add a lockfile for full coverage)",
+            skipped
+        ));
+    }
+
+    let osv_ecosystem = match ecosystem {
+        Ecosystem::Cargo => "crates.io",
+        Ecosystem::Npm => "npm",
+        Ecosystem::Python => "PyPI",
+    };
+    let client = crate::http::client();
+    for batch in pinned.chunks(OSV_BATCH_SIZE) {
+        let queries: Vec<Value> = batch
+            .iter()
+            .map(|(d, v)| {
+                serde_json::json!({
+                    "package": { "name": d.name, "ecosystem": osv_ecosystem },
+                    "version": v,
+                })
+            })
+            .collect();
+        let resp = client
+            .post(OSV_QUERY_BATCH_URL)
+            .json(&serde_json::json!({ "queries": queries }))
+            .timeout(std::time::Duration::from_secs(30))
+            .send()
+            .await;
+        let json: Value = match resp {
+            Ok(r) if r.status().is_success() => r.json().await.unwrap_or(Value::Null),
+            Ok(r) => {
+                report.notes.push(format!("OSV query failed: {}", r.status()));
+                return;
+            }
+            Err(e) => {
+                report.notes.push(format!("OSV query failed: {}", e));
+                return;
+            }
+        };
+        let results = json.get("results").and_then(|r| r.as_array());
+        for ((dep, version), result) in batch.iter().zip(results.into_iter().flatten()) {
+            for v in result.get("vulns").and_then(|v| v.as_array()).into_iter().flatten() {
+                report.vulnerabilities.push(Vulnerability {
+                    package: dep.name.clone(),
+                    version: version.clone(),
+                    id: str_field(v, "id"),
+                    severity: "unknown".to_string(),
+                    title: String::new(),
+                    fixed_in: None,
+                });
+            }
+        }
+    }
+}
+
+/// Strip `=`/`==` from an exact requirement; ranges like `^1.2` are not pinned.
+fn exact_version(v: &str) -> Option<String> {
+    let v = v.trim().trim_start_matches("==").trim_start_matches('=').trim();
+    let exact = !v.is_empty() && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+');
+    (exact && v.chars().next()?.is_ascii_digit() && v.matches('.').count() >= 2).then(|| v.to_string())
+}
+
+// ── Helpers ─────────────────────────────────────────────────────────
+
+/// Run a command and parse its stdout as JSON. Audit tools exit non-zero when
+/// they find vulnerabilities, so the exit code is ignored. Returns None when
+/// the command is missing or prints no JSON.
+async fn run_json(dir: &Path, program: &str, args: &[&str]) -> Option<Value> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .ok()?;
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+fn str_field(v: &Value, key: &str) -> String {
+    v.get(key).and_then(|x| x.as_str()).unwrap_or("").to_string()
+}
+
+fn format_report(report: &AuditReport) -> String {
+    let mut out = format!("=== {} ({}) ===\n", report.ecosystem, report.source);
+    if report.vulnerabilities.is_empty() {
+        out.push_str("No known vulnerabilities.\n");
+    } else {
+        out.push_str(&format!("{} vulnerabilit(ies):\n", report.vulnerabilities.len()));
+        for v in &report.vulnerabilities {
+            out.push_str(&format!(
+                "- {}@{} [{}] {}{}\n",
+                v.package,
+                v.version,
+                v.severity,
+                v.id,
+                if v.title.is_empty() { String::new() } else { format!(": {}", v.title) },
+            ));
+            if let Some(fix) = &v.fixed_in {
+                out.push_str(&format!("    fixed in: {}\n", fix));
+            }
+        }
+    }
+    if !report.licenses.is_empty() {
+        let mut licenses: Vec<_> = report.licenses.iter().collect();
+        licenses.sort_by(|a, b| b.1.cmp(a.1));
+        out.push_str("Licenses: ");
+        out.push_str(
+            &licenses
+                .iter()
+                .map(|(l, n)| format!("{} ({})", l, n))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        out.push('\n');
+    }
+    for note in &report.notes {
+        out.push_str(&format!("note: {}\n", note));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_cargo_audit() {
+        let json = json!({
+            "vulnerabilities": { "found": true, "count": 1, "list": [{
+                "advisory": { "id": "RUSTSEC-2023-0001", "title": "Bad thing", "cvss": null },
+                "package": { "name": "foo", "version": "0.1.0" },
+                "versions": { "patched": [">=0.1.1"] }
+            }]}
+        });
+        let vulns = parse_cargo_audit(&json);
+        assert_eq!(vulns.len(), 1);
+        assert_eq!(vulns[0].id, "RUSTSEC-2023-0001");
+        assert_eq!(vulns[0].fixed_in.as_deref(), Some(">=0.1.1"));
+    }
+
+    #[test]
+    fn test_parse_npm_audit() {
+        let json = json!({
+            "vulnerabilities": {
+                "lodash": {
+                    "severity": "high", "range": "<4.17.21",
+                    "via": [{ "title": "Prototype Pollution", "url": "https://github.com/advisories/GHSA-xxxx", "severity": "high" }],
+                    "fixAvailable": { "name": "lodash", "version": "4.17.21" }
+                },
+                "wrapper": { "severity": "high", "range": "*", "via": ["lodash"], "fixAvailable": true }
+            }
+        });
+        let vulns = parse_npm_audit(&json);
+        assert_eq!(vulns.len(), 1);
+        assert_eq!(vulns[0].id, "GHSA-xxxx");
+        assert_eq!(vulns[0].fixed_in.as_deref(), Some("4.17.21"));
+    }
+
+    #[test]
+    fn test_parse_pip_audit() {
+        let json = json!({ "dependencies": [
+            { "name": "requests", "version": "2.0.0", "vulns": [{ "id": "PYSEC-1", "fix_versions": ["2.31.0"], "description": "leak" }] },
+            { "name": "ok", "version": "1.0.0", "vulns": [] }
+        ]});
+        let vulns = parse_pip_audit(&json);
+        assert_eq!(vulns.len(), 1);
+        assert_eq!(vulns[0].package, "requests");
+    }
+
+    #[test]
+    fn test_exact_version() {
+        assert_eq!(exact_version("1.0.200").as_deref(), Some("1.0.200"));
+        assert_eq!(exact_version("==2.31.0").as_deref(), Some("2.31.0"));
+        assert_eq!(exact_version("^1.2.0"), None);
+        assert_eq!(exact_version("1"), None);
+        assert_eq!(exact_version(">=0.110"), None);
+    }
+}
@@ -0,0 +1,179 @@
+//! Files open in the IDE.
+//!
+//! The editor may hold unsaved changes the disk doesn't have yet. When the
+//! agent runs inside the IDE, the proxy installs an [`OpenBuffers`] so file
+//! tools read the editor text instead of the stale disk copy, and every write
+//! is also pushed to the open editor as an (undoable) edit rather than being
+//! clobbered by or clobbering it. Standalone (CLI, tests) there is no
+//! overlay and these are plain filesystem calls.
+//!
+//! It also remembers what the agent last saw of each file. If the user edits
+//! a file after the agent read it, mutating tools fail with a "file changed
+//! since read" error instead of writing over the edit, and the agent has to
+//! read the file again. Content is compared rather than mtimes because an
+//! unsaved editor change doesn't touch the file on disk.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+use crate::lru_cache::LruCache;
+
+pub trait OpenBuffers: Send + Sync {
+    /// The editor's current text of `path`, if it's open.
+    fn text(&self, path: &Path) -> Option<String>;
+    /// A tool wrote `content` to `path`; bring the open editor (if any) in line.
+    fn written(&self, path: &Path, content: &str);
+}
+
+static OVERLAY: RwLock<Option<Arc<dyn OpenBuffers>>> = RwLock::new(None);
+
+/// Install the IDE's open buffers (the proxy does this once at startup).
+pub fn set_overlay(buffers: Arc<dyn OpenBuffers>) {
+    *OVERLAY.write().unwrap() = Some(buffers);
+}
+
+fn overlay() -> Option<Arc<dyn OpenBuffers>> {
+    OVERLAY.read().unwrap().clone()
+}
+
+/// Read `path`, preferring the open editor's text.
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    if let Some(text) = overlay().and_then(|o| o.text(path)) {
+        return Ok(text);
+    }
+    std::fs::read_to_string(path)
+}
+
+/// Write `path` and update the open editor to match.
+pub fn write(path: &Path, content: impl AsRef<str>) -> io::Result<()> {
+    let content = content.as_ref();
+    std::fs::write(path, content)?;
+    if let Some(overlay) = overlay() {
+        overlay.written(path, content);
+    }
+    Ok(())
+}
+
+/// Whether `path` is open in the editor.
+pub fn is_open(path: &Path) -> bool {
+    overlay().is_some_and(|o| o.text(path).is_some())
+}
+
+/// Marker in the error of a tool refused by [`check_unchanged`].
+pub const CONFLICT: &str = "file changed since read";
+
+fn seen() -> &'static Mutex<LruCache<PathBuf, u64>> {
+    static SEEN: OnceLock<Mutex<LruCache<PathBuf, u64>>> = OnceLock::new();
+    SEEN.get_or_init(|| Mutex::new(LruCache::new("read_fingerprints", 20_000, 8 << 20, |path, _| path.as_os_str().len() + 8)))
+}
+
+/// `a/./b` and `a/b` are the same file.
+fn key(path: &Path) -> PathBuf {
+    path.components().collect()
+}
+
+fn fingerprint(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The agent has seen `content` of `path` (it read or wrote it).
+pub fn mark_seen(path: &Path, content: &str) {
+    seen().lock().unwrap().insert(key(path), fingerprint(content));
+}
+
+/// Whether the agent has read or written `path` this session.
+pub fn was_seen(path: &Path) -> bool {
+    seen().lock().unwrap().get(&key(path)).is_some()
+}
+
+/// Fail when `path`, whose text is now `current`, differs from what the
+/// agent last saw. Files it never read are fine.
+pub fn check_unchanged(path: &Path, current: &str) -> Result<(), String> {
+    match seen().lock().unwrap().get(&key(path)).copied() {
+        Some(seen) if seen != fingerprint(current) => Err(format!(
+            "{}: {CONFLICT}. It was edited after you last read it; read it again with read_file and redo the edit on the current content.",
+            path.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a tool output is a [`check_unchanged`] refusal.
+pub fn is_conflict(output: &str) -> bool {
+    output.contains(CONFLICT)
+}
+
+/// [`write`], and remember `content` as what the agent last saw.
+pub fn write_seen(path: &Path, content: impl AsRef<str>) -> io::Result<()> {
+    let content = content.as_ref();
+    write(path, content)?;
+    mark_seen(path, content);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[derive(Default)]
+    struct Editor {
+        open: Mutex<HashMap<PathBuf, String>>,
+    }
+
+    impl OpenBuffers for Editor {
+        fn text(&self, path: &Path) -> Option<String> {
+            self.open.lock().unwrap().get(path).cloned()
+        }
+
+        fn written(&self, path: &Path, content: &str) {
+            if let Some(text) = self.open.lock().unwrap().get_mut(path) {
+                *text = content.to_string();
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_buffer_overlay() {
+        let dir = tempdir().unwrap();
+        let open = dir.path().join("open.rs");
+        let closed = dir.path().join("closed.rs");
+        std::fs::write(&open, "saved").unwrap();
+        std::fs::write(&closed, "on disk").unwrap();
+
+        let editor = Arc::new(Editor::default());
+        editor.open.lock().unwrap().insert(open.clone(), "unsaved".to_string());
+        set_overlay(editor.clone());
+
+        assert_eq!(read_to_string(&open).unwrap(), "unsaved");
+        assert_eq!(read_to_string(&closed).unwrap(), "on disk");
+        assert!(is_open(&open) && !is_open(&closed));
+
+        write(&open, "from agent").unwrap();
+        assert_eq!(std::fs::read_to_string(&open).unwrap(), "from agent");
+        assert_eq!(editor.text(&open).as_deref(), Some("from agent"));
+    }
+
+    #[test]
+    fn test_change_since_read_is_a_conflict() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        assert!(check_unchanged(&path, "anything").is_ok());
+
+        mark_seen(&path, "fn a() {}");
+        assert!(check_unchanged(&dir.path().join(".").join("lib.rs"), "fn a() {}").is_ok());
+        let err = check_unchanged(&path, "fn a() { edited }").unwrap_err();
+        assert!(is_conflict(&err));
+
+        write_seen(&path, "fn b() {}").unwrap();
+        assert!(check_unchanged(&path, "fn b() {}").is_ok());
+    }
+}
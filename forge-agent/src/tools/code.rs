@@ -26,7 +26,10 @@ pub async fn list_definitions(args: &Value, workdir: &Path) -> ToolResult {
         Ok(symbols) if !symbols.is_empty() => {
             let output: Vec<String> = symbols
                 .iter()
-                .map(|s| format!("{:4}: {} {} - {}", s.start_line, s.kind, s.name, s.signature))
+                .map(|s| match &s.doc {
+                    Some(doc) => format!("{:4}: {} {} - {}\n      /// {doc}", s.start_line, s.kind, s.name, s.signature),
+                    None => format!("{:4}: {} {} - {}", s.start_line, s.kind, s.name, s.signature),
+                })
                 .collect();
             ToolResult::ok(output.join("\n"))
         }
@@ -93,12 +96,14 @@ pub async fn get_definition(args: &Value, workdir: &Path) -> ToolResult {
                     .collect::<Vec<_>>()
                     .join("\n");
 
+                let doc = sym.doc.as_deref().map(|d| format!("/// {d}\n")).unwrap_or_default();
                 return ToolResult::ok(format!(
-                    "Found {} '{}' in {}:{}\n{}",
+                    "Found {} '{}' in {}:{}\n{}{}",
                     sym.kind,
                     sym.name,
                     rel_path.display(),
                     sym.start_line,
+                    doc,
                     context
                 ));
             }
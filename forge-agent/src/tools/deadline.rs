@@ -0,0 +1,135 @@
+//! Per-tool time limits.
+//!
+//! Every tool call runs under a deadline so one runaway call (grep over a
+//! huge tree, a slow semantic search, a project-wide `cargo check`) can't
+//! stall the turn. Tools that walk files check [`expired`] and return what
+//! they found so far, marked partial; a call still running a moment after
+//! its deadline is cancelled and the model gets a timeout error instead.
+//!
+//! - `FORGE_TOOL_TIMEOUT`: default limit in seconds (60)
+//! - `FORGE_TOOL_TIMEOUTS`: per-tool overrides, e.g. `grep=10,diagnostics=600`
+//!
+//! `run`, `shell_session`, `process`, `port` and `run_project` manage their
+//! own timeouts and have no limit unless one is set for them here.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use super::{Tool, ToolResult};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Builds, linters and package managers get longer.
+const SLOW_TIMEOUT: Duration = Duration::from_secs(300);
+/// How long past the deadline a call may take to wrap up with partial results.
+const GRACE: Duration = Duration::from_secs(2);
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+pub struct Timeouts {
+    default: Duration,
+    per_tool: HashMap<String, Duration>,
+}
+
+impl Timeouts {
+    pub fn configured() -> Self {
+        use crate::config::var;
+        Self::parse(var("FORGE_TOOL_TIMEOUT").as_deref(), var("FORGE_TOOL_TIMEOUTS").as_deref())
+    }
+
+    fn parse(default: Option<&str>, per_tool: Option<&str>) -> Self {
+        let secs = |s: &str| s.trim().parse::<u64>().ok().filter(|&n| n > 0).map(Duration::from_secs);
+        let per_tool = per_tool
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (tool, limit) = entry.split_once('=')?;
+                Some((tool.trim().to_string(), secs(limit)?))
+            })
+            .collect();
+        Self { default: default.and_then(secs).unwrap_or(DEFAULT_TIMEOUT), per_tool }
+    }
+
+    /// Limit for `tool` called as `name` (`None`: no limit).
+    pub fn for_tool(&self, name: &str, tool: Tool) -> Option<Duration> {
+        if let Some(&limit) = self.per_tool.get(name) {
+            return Some(limit);
+        }
+        match tool {
+            Tool::Run | Tool::ShellSession | Tool::Process | Tool::Port | Tool::RunProject => None,
+            Tool::Diagnostics | Tool::AuditDependencies | Tool::Review | Tool::GenerateTests | Tool::SdkManager => {
+                Some(SLOW_TIMEOUT.max(self.default))
+            }
+            _ => Some(self.default),
+        }
+    }
+}
+
+/// Whether the current tool call is past its deadline. Long loops check
+/// this and stop early with [`partial_note`].
+pub fn expired() -> bool {
+    DEADLINE.try_with(|deadline| Instant::now() >= *deadline).unwrap_or(false)
+}
+
+/// Appended to results cut short by [`expired`].
+pub fn partial_note() -> &'static str {
+    "... (time limit reached: results are partial, narrow the search)"
+}
+
+/// Run `call` for tool `name` under `limit`.
+pub async fn limit(name: &str, limit: Option<Duration>, call: impl Future<Output = ToolResult>) -> ToolResult {
+    let Some(limit) = limit else {
+        return call.await;
+    };
+    let deadline = Instant::now() + limit;
+    match DEADLINE.scope(deadline, tokio::time::timeout(limit + GRACE, call)).await {
+        Ok(result) => result,
+        Err(_) => {
+            tracing::warn!("Tool {name} timed out after {limit:?}");
+            ToolResult::err(format!(
+                "`{name}` timed out after {}s and was cancelled. Narrow the request (a smaller path, a more \
+                 specific pattern), or raise the limit with FORGE_TOOL_TIMEOUTS={name}=<seconds>.",
+                limit.as_secs()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timeouts() {
+        let timeouts = Timeouts::parse(Some("30"), Some("grep=5, run=900,bad=x"));
+        assert_eq!(timeouts.for_tool("grep", Tool::Grep), Some(Duration::from_secs(5)));
+        assert_eq!(timeouts.for_tool("run", Tool::Run), Some(Duration::from_secs(900)));
+        assert_eq!(timeouts.for_tool("glob", Tool::Glob), Some(Duration::from_secs(30)));
+        assert_eq!(timeouts.for_tool("diagnostics", Tool::Diagnostics), Some(SLOW_TIMEOUT));
+        assert_eq!(Timeouts::parse(None, None).for_tool("run", Tool::Run), None);
+    }
+
+    #[tokio::test]
+    async fn test_limit_cancels_and_cooperates() {
+        let stuck = limit("semantic_search", Some(Duration::from_millis(10)), async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            ToolResult::ok("never")
+        });
+        let result = tokio::time::timeout(Duration::from_secs(10), stuck).await.unwrap();
+        assert!(!result.success && result.output.contains("timed out"));
+
+        let cooperative = limit("grep", Some(Duration::from_millis(10)), async {
+            let mut found = 0;
+            while !expired() {
+                found += 1;
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            ToolResult::ok(format!("{found} matches\n{}", partial_note()))
+        })
+        .await;
+        assert!(cooperative.success && cooperative.output.contains("partial"));
+        assert!(!expired(), "no deadline outside a tool call");
+    }
+}
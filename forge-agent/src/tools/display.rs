@@ -3,7 +3,10 @@
 //! These tools allow the agent to present information in a structured, readable way
 //! without modifying files or executing commands.
 
+use std::path::{Path, PathBuf};
+
 use serde_json::Value;
+use crate::tools::args::{AskFollowupQuestionArgs, EditorAction, OpenInEditorArgs};
 use crate::tools::ToolResult;
 
 /// Show a code block in the chat with syntax highlighting.
@@ -62,3 +65,146 @@ pub async fn show_diagram(args: &Value, _workdir: &std::path::Path) -> ToolResul
         diagram_code.len()
     ))
 }
+
+/// Lines past which a diagram or code block also opens in the full-window
+/// canvas instead of only showing in the chat.
+pub const CANVAS_OPEN_LINES: usize = 60;
+
+/// Whether `content` is big enough to open in the canvas by itself.
+pub fn opens_canvas(content: &str) -> bool {
+    content.lines().count() > CANVAS_OPEN_LINES
+}
+
+/// A diagram (`language` "mermaid") or code block as a markdown document,
+/// for saving it out of the canvas.
+pub fn canvas_markdown(title: Option<&str>, language: &str, content: &str) -> String {
+    let heading = title.map(|t| format!("# {}\n\n", t.trim())).unwrap_or_default();
+    // A fence longer than any backtick run in the content
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{heading}{fence}{language}\n{}\n{fence}\n", content.trim_end())
+}
+
+/// mermaid.ink URL rendering `source` as `format`: "svg" or "img" (PNG).
+pub fn mermaid_ink_url(source: &str, format: &str) -> String {
+    use base64::Engine;
+    let request = serde_json::json!({ "code": source, "mermaid": { "theme": "dark" } });
+    let encoded = base64::engine::general_purpose::STANDARD.encode(request.to_string());
+    format!("https://mermaid.ink/{format}/{encoded}")
+}
+
+/// The file `open_in_editor` points at, if it is a workspace path that
+/// exists (a diff may be of a deleted file).
+pub fn editor_target(args: &OpenInEditorArgs, workdir: &Path) -> Result<PathBuf, ToolResult> {
+    let relative = Path::new(&args.path);
+    if relative.is_absolute() || relative.components().any(|c| c == std::path::Component::ParentDir) {
+        return Err(ToolResult::err(format!("{} is not a path inside the workspace", args.path)));
+    }
+    let path = workdir.join(relative);
+    if !path.exists() && args.action != EditorAction::Diff {
+        return Err(ToolResult::err(format!("{} does not exist", args.path)));
+    }
+    Ok(path)
+}
+
+/// What the agent tells the model after showing the user a location.
+pub fn editor_summary(args: &OpenInEditorArgs) -> String {
+    let location = match (args.line, args.column) {
+        (Some(line), Some(column)) => format!("{}:{line}:{column}", args.path),
+        (Some(line), None) => format!("{}:{line}", args.path),
+        _ => args.path.clone(),
+    };
+    match args.action {
+        EditorAction::Open => format!("Opened {location} in the editor"),
+        EditorAction::Reveal => format!("Revealed {} in the file explorer", args.path),
+        EditorAction::Diff => format!("Opened the changes to {} in a diff tab", args.path),
+    }
+}
+
+/// Open a file or reveal a location in the editor.
+///
+/// The IDE handles this tool itself (lapce-proxy sends the editor the
+/// location); without an editor attached this only checks the path.
+pub async fn open_in_editor(args: OpenInEditorArgs, workdir: &Path) -> ToolResult {
+    if let Err(invalid) = editor_target(&args, workdir) {
+        return invalid;
+    }
+    ToolResult::ok(format!("No editor is attached; the user can open {} themselves", args.path))
+}
+
+/// Most suggested answers shown for one question.
+pub const MAX_FOLLOWUP_OPTIONS: usize = 6;
+
+/// `ask_followup_question` arguments ready to show: blank and repeated
+/// options dropped, at most [`MAX_FOLLOWUP_OPTIONS`], and a default that
+/// isn't one of them ignored.
+pub fn followup(args: AskFollowupQuestionArgs) -> AskFollowupQuestionArgs {
+    let mut options: Vec<String> = Vec::new();
+    for option in args.options {
+        let option = option.trim().to_string();
+        if !option.is_empty() && !options.contains(&option) && options.len() < MAX_FOLLOWUP_OPTIONS {
+            options.push(option);
+        }
+    }
+    let default = args.default.map(|d| d.trim().to_string()).filter(|d| options.contains(d));
+    AskFollowupQuestionArgs { question: args.question.trim().to_string(), options, default }
+}
+
+/// The tool result for the user's answer (`None`: they didn't give one).
+pub fn followup_answer(answer: Option<&str>) -> ToolResult {
+    match answer.map(str::trim).filter(|a| !a.is_empty()) {
+        Some(answer) => ToolResult::ok(format!("The user answered: {answer}")),
+        None => ToolResult::err("The user did not answer. Continue with your best judgment, or ask again in your reply."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editor_target() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "").unwrap();
+        let args = |path: &str, action| OpenInEditorArgs { path: path.into(), line: Some(3), column: None, action };
+
+        assert_eq!(editor_target(&args("a.rs", EditorAction::Open), dir.path()).unwrap(), dir.path().join("a.rs"));
+        assert!(editor_target(&args("gone.rs", EditorAction::Open), dir.path()).is_err());
+        assert!(editor_target(&args("gone.rs", EditorAction::Diff), dir.path()).is_ok());
+        assert!(editor_target(&args("../a.rs", EditorAction::Open), dir.path()).is_err());
+        assert_eq!(editor_summary(&args("a.rs", EditorAction::Open)), "Opened a.rs:3 in the editor");
+    }
+
+    #[test]
+    fn test_canvas_markdown() {
+        assert_eq!(
+            canvas_markdown(Some("Flow "), "mermaid", "graph TD; A-->B;\n"),
+            "# Flow\n\n```mermaid\ngraph TD; A-->B;\n```\n"
+        );
+        // Content with a fence of its own gets a longer one
+        let md = canvas_markdown(None, "markdown", "```rust\nfn main() {}\n```");
+        assert!(md.starts_with("````markdown\n") && md.ends_with("\n````\n"));
+        assert!(!opens_canvas("a\nb"));
+        assert!(opens_canvas(&"x\n".repeat(CANVAS_OPEN_LINES + 1)));
+        assert!(mermaid_ink_url("graph TD;", "svg").starts_with("https://mermaid.ink/svg/"));
+    }
+
+    #[test]
+    fn test_followup_cleans_options() {
+        let args = AskFollowupQuestionArgs {
+            question: " Which one? ".into(),
+            options: vec!["Yes".into(), " ".into(), "No ".into(), "Yes".into()],
+            default: Some("Maybe".into()),
+        };
+        let args = followup(args);
+        assert_eq!(args.question, "Which one?");
+        assert_eq!(args.options, ["Yes", "No"]);
+        assert_eq!(args.default, None);
+        assert_eq!(followup_answer(Some(" No ")).output, "The user answered: No");
+        assert!(!followup_answer(None).success);
+    }
+}
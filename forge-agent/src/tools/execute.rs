@@ -16,6 +16,35 @@ const DEFAULT_TIMEOUT_SECS: u64 = 120;
 /// Maximum allowed timeout (10 minutes).
 const MAX_TIMEOUT_SECS: u64 = 600;
 
+/// Where foreground commands run when the host (the IDE) can show
+/// terminals. Configured with `FORGE_COMMAND_TERMINAL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalPolicy {
+    /// A real PTY in the terminal panel the user can watch and type into
+    /// (default).
+    Visible,
+    /// Captured in the background without a terminal.
+    Hidden,
+}
+
+impl TerminalPolicy {
+    pub fn from_env() -> Self {
+        match crate::config::var("FORGE_COMMAND_TERMINAL").as_deref() {
+            Some("hidden") => Self::Hidden,
+            _ => Self::Visible,
+        }
+    }
+
+    /// Policy for one call; a `visible` argument overrides the setting.
+    pub fn for_call(args: &Value) -> Self {
+        match args.get("visible").and_then(|v| v.as_bool()) {
+            Some(true) => Self::Visible,
+            Some(false) => Self::Hidden,
+            None => Self::from_env(),
+        }
+    }
+}
+
 /// Execute a shell command with timeout protection.
 pub async fn run(args: &Value, workdir: &Path) -> ToolResult {
     let Some(command) = args.get("command").and_then(|v| v.as_str()) else {
@@ -37,6 +66,7 @@ pub async fn run(args: &Value, workdir: &Path) -> ToolResult {
         .arg("-c")
         .arg(&command)
         .current_dir(workdir)
+        .envs(super::sdk_env::project_env(workdir))
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
@@ -93,6 +123,14 @@ pub async fn run(args: &Value, workdir: &Path) -> ToolResult {
     }
 }
 
+/// Run a command in a persistent shell session.
+///
+/// Sessions are PTYs owned by the IDE, which keeps them alive between calls.
+pub async fn shell_session(_args: &Value, _workdir: &Path) -> ToolResult {
+    // This will be executed by the IDE
+    ToolResult::ok("PENDING_IDE_EXECUTION")
+}
+
 /// Collect stdout + stderr from a child process.
 /// Returns (output_string, was_truncated, exit_code).
 ///
@@ -1,7 +1,11 @@
+use super::args::{
+    ApplyPatchArgs, DeleteFileArgs, EditFileArgs, ListFilesArgs, ReadFileArgs, RestoreFileArgs, WriteFileArgs, WriteFileRangeArgs,
+};
+use super::buffers;
+use super::fs_changes::{ChangeKind, FsChange};
 use super::ToolResult;
 use serde_json::Value;
 use std::path::Path;
-use tokio::fs;
 use walkdir::WalkDir;
 
 // ══════════════════════════════════════════════════════════════════
@@ -208,21 +212,19 @@ fn try_regex_replace(content: &str, old_str: &str, new_str: &str) -> Option<Repl
 }
 
 /// Read file contents
-pub async fn read(args: &Value, workdir: &Path) -> ToolResult {
-    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'path' parameter");
-    };
-
+pub async fn read(args: ReadFileArgs, workdir: &Path) -> ToolResult {
+    let path = args.path.as_str();
     let full_path = workdir.join(path);
     
-    let content = match fs::read_to_string(&full_path).await {
+    let content = match buffers::read_to_string(&full_path) {
         Ok(c) => c,
         Err(e) => return ToolResult::err(format!("Failed to read {path}: {e}")),
     };
+    buffers::mark_seen(&full_path, &content);
 
     // Handle line range
-    let start = args.get("start_line").and_then(|v| v.as_u64()).map(|n| n as usize);
-    let end = args.get("end_line").and_then(|v| v.as_u64()).map(|n| n as usize);
+    let start = args.start_line.map(|n| n as usize);
+    let end = args.end_line.map(|n| n as usize);
 
     if start.is_some() || end.is_some() {
         let lines: Vec<&str> = content.lines().collect();
@@ -264,18 +266,20 @@ pub async fn read(args: &Value, workdir: &Path) -> ToolResult {
 }
 
 /// Write new file
-pub async fn write(args: &Value, workdir: &Path) -> ToolResult {
-    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'path' parameter");
-    };
-    let Some(content) = args.get("content").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'content' parameter");
-    };
+pub async fn write(args: WriteFileArgs, workdir: &Path) -> ToolResult {
+    let (path, content) = (args.path.as_str(), args.content.as_str());
 
     let full_path = workdir.join(path);
 
+    if let Err(e) = check_overwrite(path, &full_path, args.overwrite) {
+        return ToolResult::err(e);
+    }
+
     // Capture old content for diff preview (empty if file doesn't exist)
-    let old_content = std::fs::read_to_string(&full_path).unwrap_or_default();
+    let old_content = buffers::read_to_string(&full_path).unwrap_or_default();
+    if let Err(e) = buffers::check_unchanged(&full_path, &old_content) {
+        return ToolResult::err(e);
+    }
 
     // Create parent directories
     if let Some(parent) = full_path.parent() {
@@ -284,7 +288,7 @@ pub async fn write(args: &Value, workdir: &Path) -> ToolResult {
         }
     }
 
-    match std::fs::write(&full_path, content) {
+    match buffers::write_seen(&full_path, content) {
         Ok(_) => {
             let meta = super::FileEditMeta {
                 path: path.to_string(),
@@ -298,6 +302,36 @@ pub async fn write(args: &Value, workdir: &Path) -> ToolResult {
     }
 }
 
+/// Refuse to replace an existing file the agent hasn't read or written this
+/// session unless `overwrite` is set, describing the file so the model can
+/// tell whether it meant to.
+pub(crate) fn check_overwrite(path: &str, full_path: &Path, overwrite: bool) -> Result<(), String> {
+    if overwrite || !full_path.is_file() || buffers::was_seen(full_path) {
+        return Ok(());
+    }
+    const SHOWN: usize = 8;
+    let content = buffers::read_to_string(full_path).unwrap_or_default();
+    let ext = full_path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let definitions: Vec<String> = super::treesitter::parse_definitions(&content, ext)
+        .unwrap_or_default()
+        .into_iter()
+        .take(SHOWN)
+        .map(|s| format!("  {:4}: {} {}", s.start_line, s.kind, s.name))
+        .collect();
+    let preview = if definitions.is_empty() {
+        content.lines().take(SHOWN).enumerate().map(|(i, l)| format!("  {:4}|{l}", i + 1)).collect::<Vec<_>>()
+    } else {
+        definitions
+    };
+    let size = std::fs::metadata(full_path).map(|m| m.len()).unwrap_or(content.len() as u64);
+    Err(format!(
+        "{path} already exists ({} lines, {size} bytes) and you haven't read it this session:\n{}\n\
+Read it and edit it with edit_file or write_file_range, pick another path, or pass overwrite: true to replace it.",
+        content.lines().count(),
+        preview.join("\n")
+    ))
+}
+
 /// Replace text in file.
 ///
 /// Supports two modes:
@@ -305,26 +339,21 @@ pub async fn write(args: &Value, workdir: &Path) -> ToolResult {
 ///    If multiple matches, returns line numbers so the agent can add context.
 /// 2. Line-range mode (start_line + end_line): replaces lines in that range
 ///    with new_str. Safer when old_str matching is ambiguous.
-pub async fn replace(args: &Value, workdir: &Path) -> ToolResult {
-    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'path' parameter");
-    };
-    let Some(new_str) = args.get("new_str").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'new_str' parameter");
-    };
+pub async fn replace(args: EditFileArgs, workdir: &Path) -> ToolResult {
+    let (path, new_str) = (args.path.as_str(), args.new_str.as_str());
 
     let full_path = workdir.join(path);
 
-    let content = match std::fs::read_to_string(&full_path) {
+    let content = match buffers::read_to_string(&full_path) {
         Ok(c) => c,
         Err(e) => return ToolResult::err(format!("Failed to read {path}: {e}")),
     };
+    if let Err(e) = buffers::check_unchanged(&full_path, &content) {
+        return ToolResult::err(e);
+    }
 
     // ── Mode 2: Line-range replacement ──
-    let start_line = args.get("start_line").and_then(|v| v.as_u64());
-    let end_line = args.get("end_line").and_then(|v| v.as_u64());
-
-    if let (Some(start), Some(end)) = (start_line, end_line) {
+    if let (Some(start), Some(end)) = (args.start_line, args.end_line) {
         let lines: Vec<&str> = content.lines().collect();
         let start = start as usize;
         let end = end as usize;
@@ -356,7 +385,7 @@ pub async fn replace(args: &Value, workdir: &Path) -> ToolResult {
             new_content.push('\n');
         }
 
-        return match std::fs::write(&full_path, &new_content) {
+        return match buffers::write_seen(&full_path, &new_content) {
             Ok(_) => {
                 let meta = super::FileEditMeta {
                     path: path.to_string(),
@@ -371,7 +400,7 @@ pub async fn replace(args: &Value, workdir: &Path) -> ToolResult {
     }
 
     // ── Mode 1: Multi-strategy old_str match (Exact -> Flexible -> Regex) ──
-    let Some(old_str) = args.get("old_str").and_then(|v| v.as_str()) else {
+    let Some(old_str) = args.old_str.as_deref() else {
         return ToolResult::err("Missing 'old_str' parameter (or use start_line+end_line for line-range replacement)");
     };
 
@@ -381,7 +410,7 @@ pub async fn replace(args: &Value, workdir: &Path) -> ToolResult {
 
     // Try all 3 strategies: exact, flexible (whitespace-tolerant), regex
     if let Some(result) = try_replace(&content, old_str, new_str) {
-        return match std::fs::write(&full_path, &result.new_content) {
+        return match buffers::write_seen(&full_path, &result.new_content) {
             Ok(_) => {
                 let strategy_note = match result.strategy {
                     MatchStrategy::Exact => String::new(),
@@ -411,7 +440,7 @@ pub async fn replace(args: &Value, workdir: &Path) -> ToolResult {
     
     let options = mpatch::ApplyOptions::new().with_fuzz_factor(0.6);
     if let Ok(new_content) = mpatch::patch_content_str(&fuzzy_patch, Some(&content), &options) {
-        return match std::fs::write(&full_path, &new_content) {
+        return match buffers::write_seen(&full_path, &new_content) {
             Ok(_) => {
                 let meta = super::FileEditMeta {
                     path: path.to_string(),
@@ -465,35 +494,145 @@ Make sure the content is correct.{hint_msg}"
     ))
 }
 
+/// Existing files longer than this should be changed with
+/// `write_file_range` or `edit_file`, not rewritten with `write_file` (the
+/// local agent's system prompt says the same).
+pub const LARGE_FILE_LINES: usize = 300;
+
+/// Replace a line range of a file, after checking the lines are still what
+/// the model expects.
+pub async fn write_range(args: WriteFileRangeArgs, workdir: &Path) -> ToolResult {
+    let path = args.path.as_str();
+    let full_path = workdir.join(path);
+    let content = match buffers::read_to_string(&full_path) {
+        Ok(c) => c,
+        Err(e) => return ToolResult::err(format!("Failed to read {path}: {e}")),
+    };
+    if let Err(e) = buffers::check_unchanged(&full_path, &content) {
+        return ToolResult::err(e);
+    }
+    let range = match replace_range(&content, args.start_line as usize, args.end_line as usize, &args.expected, &args.content) {
+        Ok(range) => range,
+        Err(e) => return ToolResult::err(format!("{path}: {e}")),
+    };
+    if let Err(e) = buffers::write_seen(&full_path, &range.new_content) {
+        return ToolResult::err(format!("Failed to write: {e}"));
+    }
+    let moved = if range.start != args.start_line as usize {
+        format!("; the expected lines had moved to {}-{}", range.start, range.end)
+    } else {
+        String::new()
+    };
+    let meta = super::FileEditMeta { path: path.to_string(), old_content: content, new_content: range.new_content };
+    ToolResult::ok(format!(
+        "Updated {path} (replaced lines {}-{} with {} line{}{moved})",
+        range.start,
+        range.end,
+        range.written,
+        if range.written == 1 { "" } else { "s" }
+    ))
+    .with_file_edit(meta)
+}
+
+struct RangeReplacement {
+    new_content: String,
+    /// The 1-based lines replaced, inclusive.
+    start: usize,
+    end: usize,
+    written: usize,
+}
+
+/// `content` with lines `start..=end` (1-based; `end = start - 1` inserts
+/// before `start`) replaced by `replacement`. The lines must match
+/// `expected`, ignoring trailing whitespace; if they don't but `expected`
+/// appears exactly once elsewhere, that is replaced instead.
+fn replace_range(content: &str, start: usize, end: usize, expected: &str, replacement: &str) -> Result<RangeReplacement, String> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    if start == 0 {
+        return Err("start_line is 1-indexed (start from 1)".to_string());
+    }
+    if end + 1 < start || end > lines.len() {
+        return Err(format!("line range {start}-{end} is out of bounds (the file has {} lines)", lines.len()));
+    }
+    let normalize = |text: &str| text.lines().map(str::trim_end).collect::<Vec<_>>().join("\n");
+    let expected = normalize(expected);
+    let count = end + 1 - start;
+    let block = |from: usize| normalize(&lines[from..from + count].concat());
+
+    let mut from = start - 1;
+    if block(from) != expected {
+        let matches: Vec<usize> = if count == 0 {
+            Vec::new()
+        } else {
+            (0..=lines.len() - count).filter(|i| block(*i) == expected).collect()
+        };
+        match matches[..] {
+            [only] => from = only,
+            _ => {
+                let actual: String = lines[from..from + count]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, l)| format!("{:4}|{}\n", from + i + 1, l.trim_end_matches(['\r', '\n'])))
+                    .collect();
+                return Err(format!(
+                    "lines {start}-{end} don't match `expected`{}. They are now:\n{actual}Read the file again and retry with the current lines.",
+                    if matches.len() > 1 { format!(" and it appears {} times elsewhere", matches.len()) } else { String::new() }
+                ));
+            }
+        }
+    }
+
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut new_content: String = lines[..from].concat();
+    if !new_content.is_empty() && !new_content.ends_with('\n') {
+        new_content.push_str(newline);
+    }
+    let written = replacement.lines().count();
+    for line in replacement.lines() {
+        new_content.push_str(line.trim_end_matches('\r'));
+        new_content.push_str(newline);
+    }
+    let rest = lines[from + count..].concat();
+    if rest.is_empty() && !content.ends_with('\n') && written > 0 {
+        // Keep a file without a final newline that way
+        new_content.truncate(new_content.len() - newline.len());
+    }
+    new_content.push_str(&rest);
+    Ok(RangeReplacement { new_content, start: from + 1, end: from + count, written })
+}
+
 /// Apply unified diff patch
-pub async fn apply_patch(args: &Value, workdir: &Path) -> ToolResult {
-    // Check if this is V4A format (has "*** Begin Patch" or "*** Update File:")
-    if let Some(input) = args.get("input").and_then(|v| v.as_str()) {
-        if input.contains("*** Begin Patch") || input.contains("*** Update File:") 
-            || input.contains("*** Add File:") || input.contains("*** Delete File:") {
-            return apply_v4a_patch(args, workdir).await;
+pub async fn apply_patch(args: ApplyPatchArgs, workdir: &Path) -> ToolResult {
+    // `input` is a V4A patch (its parser reports a malformed one); a unified
+    // diff comes in `patch`
+    if let Some(input) = args.input.as_deref() {
+        if input.contains("***") || args.patch.is_none() {
+            return apply_v4a_patch(input, workdir).await;
         }
     }
     
     // Traditional unified diff format
-    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
+    let Some(path) = args.path.as_deref() else {
         return ToolResult::err("Missing 'path' parameter");
     };
-    let Some(patch) = args.get("patch").and_then(|v| v.as_str()) else {
+    let Some(patch) = args.patch.as_deref() else {
         return ToolResult::err("Missing 'patch' parameter");
     };
 
     let full_path = workdir.join(path);
 
-    let content = match std::fs::read_to_string(&full_path) {
+    let content = match buffers::read_to_string(&full_path) {
         Ok(c) => c,
         Err(_) => String::new(), // New file
     };
+    if let Err(e) = buffers::check_unchanged(&full_path, &content) {
+        return ToolResult::err(e);
+    }
 
     // Parse and apply unified diff
     match apply_unified_diff(&content, patch) {
         Ok(new_content) => {
-            if let Err(e) = std::fs::write(&full_path, &new_content) {
+            if let Err(e) = buffers::write_seen(&full_path, &new_content) {
                 return ToolResult::err(format!("Failed to write: {e}"));
             }
             ToolResult::ok(format!("Patched {path}"))
@@ -503,10 +642,8 @@ pub async fn apply_patch(args: &Value, workdir: &Path) -> ToolResult {
 }
 
 /// Delete a file or directory
-pub async fn delete(args: &Value, workdir: &Path) -> ToolResult {
-    let Some(path) = args.get("path").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'path' parameter");
-    };
+pub async fn delete(args: DeleteFileArgs, workdir: &Path) -> ToolResult {
+    let path = args.path.as_str();
     
     let full_path = workdir.join(path);
     
@@ -540,26 +677,49 @@ pub async fn delete(args: &Value, workdir: &Path) -> ToolResult {
             ));
         }
         
-        match std::fs::remove_dir_all(&full_path) {
-            Ok(_) => ToolResult::ok(format!("Deleted directory: {}", path)),
+        match super::trash::move_to_trash(workdir, path) {
+            Ok(_) => ToolResult::ok(format!("Deleted directory: {path} (moved to the trash; restore_file can bring it back)")),
             Err(e) => ToolResult::err(format!("Failed to delete directory: {}", e)),
         }
     } else {
-        match std::fs::remove_file(&full_path) {
-            Ok(_) => ToolResult::ok(format!("Deleted file: {}", path)),
+        let old_content = buffers::read_to_string(&full_path).ok();
+        match super::trash::move_to_trash(workdir, path) {
+            Ok(_) => ToolResult::ok(format!("Deleted file: {path} (moved to the trash; restore_file can bring it back)"))
+                .with_fs_changes(vec![FsChange { path: path.to_string(), kind: ChangeKind::Deleted, old_content }]),
             Err(e) => ToolResult::err(format!("Failed to delete file: {}", e)),
         }
     }
 }
 
+/// Restore a path `delete` moved to the trash, or list the trash.
+pub async fn restore(args: RestoreFileArgs, workdir: &Path) -> ToolResult {
+    let Some(path) = args.path else {
+        let entries = super::trash::list(workdir);
+        if entries.is_empty() {
+            return ToolResult::ok("The trash is empty");
+        }
+        let lines: Vec<String> = entries
+            .iter()
+            .map(|e| format!("{}{}", e.path, if e.is_dir { "/" } else { "" }))
+            .collect();
+        return ToolResult::ok(format!("Deleted, most recent first:\n{}", lines.join("\n")));
+    };
+    match super::trash::restore(workdir, Some(&path)) {
+        Ok(entry) => ToolResult::ok(format!("Restored {}", entry.path))
+            .with_fs_changes(vec![FsChange { path: entry.path, kind: ChangeKind::Created, old_content: None }]),
+        Err(e) => ToolResult::err(e),
+    }
+}
+
 /// List files in directory.
 /// Filters out dot-files, dot-directories, and common non-project directories
 /// (node_modules, target, .git, etc.) to match Cursor's list_dir behavior.
-pub async fn list(args: &Value, workdir: &Path) -> ToolResult {
+/// Recursive listings are a tree pruned to a token budget
+/// ([`super::project_tree`]).
+pub async fn list(args: ListFilesArgs, workdir: &Path) -> ToolResult {
     use super::should_skip_dir;
 
-    let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
-    let recursive = args.get("recursive").and_then(|v| v.as_bool()).unwrap_or(false);
+    let (path, recursive) = (args.path.as_str(), args.recursive);
 
     let full_path = workdir.join(path);
 
@@ -567,44 +727,25 @@ pub async fn list(args: &Value, workdir: &Path) -> ToolResult {
         return ToolResult::err(format!("Path does not exist: {path}"));
     }
 
-    let mut entries = Vec::new();
-
     if recursive {
-        for entry in WalkDir::new(&full_path)
-            .max_depth(10)
-            .into_iter()
-            .filter_entry(|e| {
-                let name = e.file_name().to_string_lossy();
-                if e.file_type().is_dir() {
-                    return !should_skip_dir(&name);
-                }
-                // Skip hidden files
-                !name.starts_with('.')
-            })
-            .filter_map(|e| e.ok())
-        {
-            if let Ok(rel) = entry.path().strip_prefix(&full_path) {
-                let rel_str = rel.to_string_lossy();
-                if !rel_str.is_empty() {
-                    entries.push(rel_str.to_string());
-                }
+        let max_tokens = args.max_tokens.unwrap_or(super::project_tree::DEFAULT_MAX_TOKENS);
+        return ToolResult::ok(super::project_tree::build(&full_path, max_tokens));
+    }
+
+    let mut entries = Vec::new();
+    if let Ok(dir) = std::fs::read_dir(&full_path) {
+        for entry in dir.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Skip dot-files/dirs and ignored directories
+            if name.starts_with('.') {
+                continue;
             }
-        }
-    } else {
-        if let Ok(dir) = std::fs::read_dir(&full_path) {
-            for entry in dir.filter_map(|e| e.ok()) {
-                let name = entry.file_name().to_string_lossy().to_string();
-                // Skip dot-files/dirs and ignored directories
-                if name.starts_with('.') {
-                    continue;
-                }
-                let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
-                if is_dir && should_skip_dir(&name) {
-                    continue;
-                }
-                let suffix = if is_dir { "/" } else { "" };
-                entries.push(format!("{name}{suffix}"));
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            if is_dir && should_skip_dir(&name) {
+                continue;
             }
+            let suffix = if is_dir { "/" } else { "" };
+            entries.push(format!("{name}{suffix}"));
         }
     }
 
@@ -776,8 +917,9 @@ pub async fn read_many(args: &Value, workdir: &Path) -> ToolResult {
         }
 
         // Try to read the file
-        match std::fs::read_to_string(file_path) {
+        match buffers::read_to_string(file_path) {
             Ok(content) => {
+                buffers::mark_seen(file_path, &content);
                 output.push_str(&format!("--- {} ---\n", rel_path));
 
                 let remaining = READ_MANY_MAX_CHARS.saturating_sub(output.len());
@@ -851,272 +993,56 @@ fn apply_unified_diff(original: &str, patch: &str) -> Result<String, String> {
     }
 }
 
-/// Apply a V4A format patch (used by apply_patch tool)
-/// Format:
-/// *** Begin Patch
-/// *** Update File: path/to/file
-/// @@ class ClassName (optional context)
-/// context line
-/// - removed line
-/// + added line
-/// context line
-/// *** End Patch
-pub async fn apply_v4a_patch(args: &Value, workdir: &Path) -> ToolResult {
-    let Some(input) = args.get("input").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'input' parameter");
-    };
-    
-    // Extract patch content between *** Begin Patch and *** End Patch
-    let patch_start = input.find("*** Begin Patch");
-    let patch_end = input.find("*** End Patch");
-    
-    let patch_content = match (patch_start, patch_end) {
-        (Some(start), Some(end)) => &input[start..end + "*** End Patch".len()],
-        _ => input, // Try to parse the whole input
-    };
-    
-    let mut results = Vec::new();
-    let mut current_file: Option<String> = None;
-    let mut current_action: Option<&str> = None;
-    let mut hunks: Vec<Hunk> = Vec::new();
-    let mut current_hunk = Hunk::default();
-    
-    for line in patch_content.lines() {
-        let line = line.trim_end();
-        
-        // File headers
-        if line.starts_with("*** Add File:") {
-            // Process previous file if any
-            if let Some(ref file) = current_file {
-                let result = apply_hunks_to_file(file, &hunks, current_action, workdir);
-                results.push(result);
-            }
-            current_file = Some(line.trim_start_matches("*** Add File:").trim().to_string());
-            current_action = Some("add");
-            hunks.clear();
-            current_hunk = Hunk::default();
-        } else if line.starts_with("*** Update File:") {
-            if let Some(ref file) = current_file {
-                let result = apply_hunks_to_file(file, &hunks, current_action, workdir);
-                results.push(result);
-            }
-            current_file = Some(line.trim_start_matches("*** Update File:").trim().to_string());
-            current_action = Some("update");
-            hunks.clear();
-            current_hunk = Hunk::default();
-        } else if line.starts_with("*** Delete File:") {
-            if let Some(ref file) = current_file {
-                let result = apply_hunks_to_file(file, &hunks, current_action, workdir);
-                results.push(result);
-            }
-            current_file = Some(line.trim_start_matches("*** Delete File:").trim().to_string());
-            current_action = Some("delete");
-            hunks.clear();
-            current_hunk = Hunk::default();
-        } else if line.starts_with("@@") {
-            // Context marker - save current hunk if it has content
-            if !current_hunk.is_empty() {
-                hunks.push(current_hunk.clone());
-                current_hunk = Hunk::default();
-            }
-            current_hunk.context_markers.push(line.trim_start_matches("@@").trim().to_string());
-        } else if line.starts_with("*** Begin Patch") || line.starts_with("*** End Patch") {
-            // Ignore markers
-        } else if line.starts_with('-') && !line.starts_with("---") {
-            current_hunk.removals.push(line[1..].to_string());
-            current_hunk.lines.push(HunkLine::Remove(line[1..].to_string()));
-        } else if line.starts_with('+') && !line.starts_with("+++") {
-            current_hunk.additions.push(line[1..].to_string());
-            current_hunk.lines.push(HunkLine::Add(line[1..].to_string()));
-        } else if current_file.is_some() && !line.is_empty() {
-            // Context line
-            current_hunk.context.push(line.to_string());
-            current_hunk.lines.push(HunkLine::Context(line.to_string()));
-        }
-    }
-    
-    // Process last file
-    if let Some(ref file) = current_file {
-        if !current_hunk.is_empty() {
-            hunks.push(current_hunk);
-        }
-        let result = apply_hunks_to_file(file, &hunks, current_action, workdir);
-        results.push(result);
-    }
-    
-    if results.is_empty() {
-        return ToolResult::err("No valid patch content found");
-    }
-    
-    let success = results.iter().all(|r| r.0);
-    let output = results.iter().map(|r| r.1.clone()).collect::<Vec<_>>().join("\n");
-    
-    if success {
-        ToolResult::ok(output)
-    } else {
-        ToolResult { success: false, output, file_edit: None, needs_approval: None }
-    }
-}
-
-#[derive(Default, Clone)]
-struct Hunk {
-    context_markers: Vec<String>,
-    context: Vec<String>,
-    removals: Vec<String>,
-    additions: Vec<String>,
-    lines: Vec<HunkLine>,
+/// Apply a V4A format patch (used by apply_patch tool); see [`super::v4a`].
+pub async fn apply_v4a_patch(input: &str, workdir: &Path) -> ToolResult {
+    super::v4a::apply(input, workdir)
 }
 
-#[derive(Clone)]
-enum HunkLine {
-    Context(String),
-    Remove(String),
-    Add(String),
-}
-
-impl Hunk {
-    fn is_empty(&self) -> bool {
-        self.lines.is_empty()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_range() {
+        let content = "fn a() {}\nfn b() {\n    1\n}\nfn c() {}\n";
+        let r = replace_range(content, 2, 4, "fn b() {\n    1  \n}", "fn b() {\n    2\n}\n").unwrap();
+        assert_eq!(r.new_content, "fn a() {}\nfn b() {\n    2\n}\nfn c() {}\n");
+        assert_eq!((r.start, r.end, r.written), (2, 4, 3));
+
+        // The lines moved down by one: found by their content
+        let shifted = format!("// header\n{content}");
+        let r = replace_range(&shifted, 2, 4, "fn b() {\n    1\n}", "fn b() {}").unwrap();
+        assert_eq!(r.new_content, "// header\nfn a() {}\nfn b() {}\nfn c() {}\n");
+        assert_eq!(r.start, 3);
+
+        // Insert before line 1, CRLF and a missing final newline are kept
+        let r = replace_range("x\r\ny", 1, 0, "", "use z;").unwrap();
+        assert_eq!(r.new_content, "use z;\r\nx\r\ny");
+        let r = replace_range("x\r\ny", 2, 2, "y", "w").unwrap();
+        assert_eq!(r.new_content, "x\r\nw");
+
+        let err = replace_range(content, 2, 2, "fn z() {", "").err().unwrap();
+        assert!(err.contains("   2|fn b() {"), "{err}");
+        assert!(replace_range(content, 5, 9, "", "").is_err());
     }
-}
 
-fn apply_hunks_to_file(file_path: &str, hunks: &[Hunk], action: Option<&str>, workdir: &Path) -> (bool, String) {
-    let full_path = workdir.join(file_path);
-    
-    match action {
-        Some("delete") => {
-            match std::fs::remove_file(&full_path) {
-                Ok(_) => (true, format!("Deleted {}", file_path)),
-                Err(e) => (false, format!("Failed to delete {}: {}", file_path, e)),
-            }
-        }
-        Some("add") => {
-            // Collect all addition lines
-            let content: String = hunks.iter()
-                .flat_map(|h| h.additions.iter())
-                .cloned()
-                .collect::<Vec<_>>()
-                .join("\n");
-            
-            // Create parent directories
-            if let Some(parent) = full_path.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
-            
-            match std::fs::write(&full_path, content) {
-                Ok(_) => (true, format!("Created {}", file_path)),
-                Err(e) => (false, format!("Failed to create {}: {}", file_path, e)),
-            }
-        }
-        Some("update") | _ => {
-            // Read existing file
-            let content = match std::fs::read_to_string(&full_path) {
-                Ok(c) => c,
-                Err(e) => return (false, format!("Failed to read {}: {}", file_path, e)),
-            };
-            
-            let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-            
-            for hunk in hunks {
-                // Find the location to apply this hunk
-                if let Some(pos) = find_hunk_position(&lines, hunk) {
-                    // Apply the hunk
-                    let mut new_lines = Vec::new();
-                    let mut i = 0;
-                    let mut in_hunk = false;
-                    let mut hunk_line_idx = 0;
-                    
-                    while i < lines.len() {
-                        if i == pos && !in_hunk {
-                            in_hunk = true;
-                        }
-                        
-                        if in_hunk && hunk_line_idx < hunk.lines.len() {
-                            match &hunk.lines[hunk_line_idx] {
-                                HunkLine::Context(_) => {
-                                    new_lines.push(lines[i].clone());
-                                    i += 1;
-                                    hunk_line_idx += 1;
-                                }
-                                HunkLine::Remove(_) => {
-                                    // Skip this line (remove it)
-                                    i += 1;
-                                    hunk_line_idx += 1;
-                                }
-                                HunkLine::Add(content) => {
-                                    new_lines.push(content.clone());
-                                    hunk_line_idx += 1;
-                                    // Don't increment i - we're inserting
-                                }
-                            }
-                        } else {
-                            new_lines.push(lines[i].clone());
-                            i += 1;
-                            if in_hunk {
-                                in_hunk = false;
-                            }
-                        }
-                    }
-                    
-                    // Handle remaining additions at the end
-                    while hunk_line_idx < hunk.lines.len() {
-                        if let HunkLine::Add(content) = &hunk.lines[hunk_line_idx] {
-                            new_lines.push(content.clone());
-                        }
-                        hunk_line_idx += 1;
-                    }
-                    
-                    lines = new_lines;
-                } else {
-                    return (false, format!("Could not find location to apply hunk in {}", file_path));
-                }
-            }
-            
-            // Write back
-            match std::fs::write(&full_path, lines.join("\n")) {
-                Ok(_) => (true, format!("Updated {}", file_path)),
-                Err(e) => (false, format!("Failed to write {}: {}", file_path, e)),
-            }
-        }
+    #[tokio::test]
+    async fn test_write_overwrite_protection() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("config.py"), "import os\n\ndef load():\n    pass\n").unwrap();
+        let args = |overwrite| WriteFileArgs { path: "config.py".into(), content: "x = 1\n".into(), overwrite };
+
+        let refused = write(args(false), dir.path()).await;
+        assert!(!refused.success);
+        assert!(refused.output.contains("config.py already exists (4 lines"), "{}", refused.output);
+        assert!(refused.output.contains("3: fn load"), "{}", refused.output);
+
+        // New files, files the agent has read, and overwrite: true are fine
+        assert!(write(WriteFileArgs { path: "new.py".into(), ..args(false) }, dir.path()).await.success);
+        let read_args = ReadFileArgs { path: "config.py".into(), start_line: None, end_line: None };
+        read(read_args, dir.path()).await;
+        assert!(write(args(false), dir.path()).await.success);
+        std::fs::write(dir.path().join("other.py"), "y = 2\n").unwrap();
+        assert!(write(WriteFileArgs { path: "other.py".into(), ..args(true) }, dir.path()).await.success);
     }
 }
-
-fn find_hunk_position(lines: &[String], hunk: &Hunk) -> Option<usize> {
-    // Get the context lines before the first change
-    let mut context_before = Vec::new();
-    for line in &hunk.lines {
-        match line {
-            HunkLine::Context(c) => context_before.push(c.clone()),
-            HunkLine::Remove(_) | HunkLine::Add(_) => break,
-        }
-    }
-    
-    if context_before.is_empty() {
-        // No context, try to match the first removal line
-        if let Some(HunkLine::Remove(first_remove)) = hunk.lines.iter().find(|l| matches!(l, HunkLine::Remove(_))) {
-            for (i, line) in lines.iter().enumerate() {
-                if line.trim() == first_remove.trim() {
-                    return Some(i);
-                }
-            }
-        }
-        return Some(0); // Default to start of file
-    }
-    
-    // Search for matching context
-    'outer: for i in 0..lines.len() {
-        if i + context_before.len() > lines.len() {
-            break;
-        }
-        
-        for (j, ctx) in context_before.iter().enumerate() {
-            if lines[i + j].trim() != ctx.trim() {
-                continue 'outer;
-            }
-        }
-        
-        return Some(i);
-    }
-    
-    None
-}
@@ -0,0 +1,122 @@
+//! `focus_chain`: the task list the model keeps while it works. Lives in the
+//! conversation's [`Session`]; the IDE shows it as the plan.
+
+use serde_json::Value;
+
+use super::args::{self, FocusChainAction, FocusChainArgs, FocusChainStatus};
+use super::ToolResult;
+use crate::session::{Session, Task, TaskStatus};
+
+/// Apply a `focus_chain` call to `session`. On error the list is unchanged.
+pub fn apply(session: &mut Session, args: &Value) -> ToolResult {
+    let args: FocusChainArgs = match args::parse("focus_chain", args) {
+        Ok(args) => args,
+        Err(result) => return result,
+    };
+    let descriptions: Vec<String> =
+        args.tasks.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+    match args.action {
+        FocusChainAction::Set | FocusChainAction::Add if descriptions.is_empty() => {
+            return ToolResult::err("focus_chain: `tasks` needs at least one task description");
+        }
+        FocusChainAction::Set => {
+            session.tasks.clear();
+            push_tasks(&mut session.tasks, descriptions);
+        }
+        FocusChainAction::Add => push_tasks(&mut session.tasks, descriptions),
+        FocusChainAction::Update => {
+            let (Some(id), Some(status)) = (args.id, args.status) else {
+                return ToolResult::err("focus_chain: 'update' needs `id` and `status`");
+            };
+            if let Err(e) = update(&mut session.tasks, id, status_of(status)) {
+                return ToolResult::err(format!("focus_chain: {e}\n\n{}", render(&session.tasks)));
+            }
+        }
+    }
+    ToolResult::ok(render(&session.tasks))
+}
+
+fn status_of(status: FocusChainStatus) -> TaskStatus {
+    match status {
+        FocusChainStatus::Pending => TaskStatus::Pending,
+        FocusChainStatus::InProgress => TaskStatus::InProgress,
+        FocusChainStatus::Done => TaskStatus::Done,
+    }
+}
+
+fn push_tasks(tasks: &mut Vec<Task>, descriptions: Vec<String>) {
+    for description in descriptions {
+        let id = tasks.len() as u32 + 1;
+        tasks.push(Task { id, description, status: TaskStatus::Pending });
+    }
+}
+
+/// Done is final, and only one task is in progress at a time.
+fn update(tasks: &mut [Task], id: u32, status: TaskStatus) -> Result<(), String> {
+    if status == TaskStatus::InProgress {
+        if let Some(other) = tasks.iter().find(|t| t.id != id && t.status == TaskStatus::InProgress) {
+            return Err(format!(
+                "task {} is already in progress; mark it done or pending first",
+                other.id
+            ));
+        }
+    }
+    let task = tasks.iter_mut().find(|t| t.id == id).ok_or_else(|| format!("there is no task {id}"))?;
+    if task.status == TaskStatus::Done && status != TaskStatus::Done {
+        return Err(format!("task {id} is already done"));
+    }
+    task.status = status;
+    Ok(())
+}
+
+/// The list as the model sees it after each call.
+pub fn render(tasks: &[Task]) -> String {
+    if tasks.is_empty() {
+        return "No tasks.".to_string();
+    }
+    let done = tasks.iter().filter(|t| t.status == TaskStatus::Done).count();
+    let mut out = format!("Tasks ({done}/{} done):", tasks.len());
+    for task in tasks {
+        let mark = match task.status {
+            TaskStatus::Pending => " ",
+            TaskStatus::InProgress => "~",
+            TaskStatus::Done => "x",
+        };
+        out.push_str(&format!("\n{}. [{mark}] {}", task.id, task.description));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_focus_chain_transitions() {
+        let mut session = Session::default();
+        let result = apply(&mut session, &json!({ "action": "set", "tasks": ["Read the parser", " ", "Fix the bug"] }));
+        assert!(result.success);
+        assert_eq!(session.tasks.len(), 2);
+        assert!(apply(&mut session, &json!({ "action": "add", "tasks": ["Run the tests"] })).success);
+        assert_eq!(session.tasks[2].id, 3);
+
+        let update = |session: &mut Session, id: u32, status: &str| {
+            apply(session, &json!({ "action": "update", "id": id, "status": status })).success
+        };
+        assert!(update(&mut session, 1, "in_progress"));
+        // One at a time
+        assert!(!update(&mut session, 2, "in_progress"));
+        assert!(update(&mut session, 1, "done"));
+        // Done is final
+        assert!(!update(&mut session, 1, "pending"));
+        assert!(!update(&mut session, 9, "done"));
+        assert!(update(&mut session, 2, "in_progress"));
+        assert!(!apply(&mut session, &json!({ "action": "update", "id": 2 })).success);
+
+        assert_eq!(
+            render(&session.tasks),
+            "Tasks (1/3 done):\n1. [x] Read the parser\n2. [~] Fix the bug\n3. [ ] Run the tests"
+        );
+    }
+}
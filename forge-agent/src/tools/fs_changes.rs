@@ -0,0 +1,231 @@
+//! Files changed by a command.
+//!
+//! Commands write to the workspace without going through the file tools, so
+//! their edits had no diff preview and no rollback. A [`Snapshot`] taken
+//! before the command and compared with the tree afterwards gives the
+//! change-set: created, modified and deleted files, with the old content of
+//! text files so each change can be reviewed and reverted like a file edit.
+//! `.git` and gitignored paths aren't tracked.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use super::{FileEditMeta, ToolResult};
+
+/// Workspaces with more files than this aren't tracked.
+const MAX_FILES: usize = 20_000;
+/// Old content is kept for text files up to this size...
+const MAX_CONTENT_FILE: u64 = 256 * 1024;
+/// ...while the snapshot holds less than this in total.
+const MAX_CONTENT_TOTAL: usize = 32 * 1024 * 1024;
+/// Changed paths listed in the tool output.
+const MAX_LISTED: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// One file a command changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FsChange {
+    /// Relative path within the workspace.
+    pub path: String,
+    pub kind: ChangeKind,
+    /// Content before the command, for text files small enough to keep.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_content: Option<String>,
+}
+
+impl FsChange {
+    /// What a checkpoint needs to undo this change: `Some(None)` for a file
+    /// that didn't exist, `None` when the old content wasn't kept.
+    pub fn before(&self) -> Option<Option<String>> {
+        match self.kind {
+            ChangeKind::Created => Some(None),
+            _ => self.old_content.clone().map(Some),
+        }
+    }
+
+    /// The change as a file edit, for diff preview. `None` for binary files
+    /// and ones too large to have been kept.
+    pub fn edit_meta(&self, root: &Path) -> Option<FileEditMeta> {
+        let old_content = match self.kind {
+            ChangeKind::Created => String::new(),
+            _ => self.old_content.clone()?,
+        };
+        let new_content = match self.kind {
+            ChangeKind::Deleted => String::new(),
+            _ => std::fs::read_to_string(root.join(&self.path)).ok()?,
+        };
+        Some(FileEditMeta { path: self.path.clone(), old_content, new_content })
+    }
+}
+
+struct Entry {
+    len: u64,
+    modified: Option<SystemTime>,
+    hash: Option<u64>,
+    content: Option<String>,
+}
+
+/// The workspace's files at one point in time.
+pub struct Snapshot {
+    root: PathBuf,
+    files: HashMap<String, Entry>,
+}
+
+impl Snapshot {
+    /// `None` for workspaces too large to track.
+    pub fn take(root: &Path) -> Option<Self> {
+        let mut files = HashMap::new();
+        let mut kept = 0;
+        for (path, meta) in walk(root) {
+            if files.len() == MAX_FILES {
+                tracing::debug!("Not tracking command changes: more than {MAX_FILES} files");
+                return None;
+            }
+            let mut entry = Entry { len: meta.len(), modified: meta.modified().ok(), hash: None, content: None };
+            if meta.len() <= MAX_CONTENT_FILE {
+                if let Ok(bytes) = std::fs::read(root.join(&path)) {
+                    entry.hash = Some(hash(&bytes));
+                    if kept + bytes.len() <= MAX_CONTENT_TOTAL {
+                        if let Ok(text) = String::from_utf8(bytes) {
+                            kept += text.len();
+                            entry.content = Some(text);
+                        }
+                    }
+                }
+            }
+            files.insert(path, entry);
+        }
+        Some(Self { root: root.to_path_buf(), files })
+    }
+
+    /// What changed since the snapshot was taken, by path.
+    pub fn changes(&self) -> Vec<FsChange> {
+        let mut changes = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for (path, meta) in walk(&self.root) {
+            let Some(entry) = self.files.get(&path) else {
+                changes.push(FsChange { path, kind: ChangeKind::Created, old_content: None });
+                continue;
+            };
+            seen.insert(path.clone());
+            if entry.len == meta.len() && entry.modified == meta.modified().ok() {
+                continue;
+            }
+            // Touched but rewritten with the same bytes isn't a change
+            if let Some(before) = entry.hash {
+                if std::fs::read(self.root.join(&path)).is_ok_and(|bytes| hash(&bytes) == before) {
+                    continue;
+                }
+            }
+            changes.push(FsChange { path, kind: ChangeKind::Modified, old_content: entry.content.clone() });
+        }
+        for (path, entry) in &self.files {
+            if !seen.contains(path) {
+                changes.push(FsChange { path: path.clone(), kind: ChangeKind::Deleted, old_content: entry.content.clone() });
+            }
+        }
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        changes
+    }
+}
+
+fn walk(root: &Path) -> impl Iterator<Item = (String, std::fs::Metadata)> + '_ {
+    ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|e| e.file_name() != ".git")
+        .build()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(move |e| {
+            let rel = e.path().strip_prefix(root).ok()?.to_string_lossy().replace('\\', "/");
+            Some((rel, e.metadata().ok()?))
+        })
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// `A path` / `M path` / `D path` lines for the model.
+pub fn summary(changes: &[FsChange]) -> String {
+    let mut lines: Vec<String> = changes
+        .iter()
+        .take(MAX_LISTED)
+        .map(|c| {
+            let mark = match c.kind {
+                ChangeKind::Created => 'A',
+                ChangeKind::Modified => 'M',
+                ChangeKind::Deleted => 'D',
+            };
+            format!("  {mark} {}", c.path)
+        })
+        .collect();
+    if changes.len() > MAX_LISTED {
+        lines.push(format!("  ... and {} more", changes.len() - MAX_LISTED));
+    }
+    format!("Files changed by the command:\n{}", lines.join("\n"))
+}
+
+/// Run a command and attach the files it changed under `root` to its result.
+pub async fn tracked(root: &Path, command: impl Future<Output = ToolResult>) -> ToolResult {
+    let dir = root.to_path_buf();
+    let before = tokio::task::spawn_blocking(move || Snapshot::take(&dir)).await.ok().flatten();
+    let result = command.await;
+    let Some(before) = before else {
+        return result;
+    };
+    let changes = tokio::task::spawn_blocking(move || before.changes()).await.unwrap_or_default();
+    result.with_fs_changes(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_snapshot_changes() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("keep.txt"), "same").unwrap();
+        std::fs::write(root.join("edit.txt"), "old").unwrap();
+        std::fs::write(root.join("gone.txt"), "bye").unwrap();
+        std::fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        let before = Snapshot::take(root).unwrap();
+
+        std::fs::write(root.join("keep.txt"), "same").unwrap();
+        std::fs::write(root.join("edit.txt"), "new content").unwrap();
+        std::fs::remove_file(root.join("gone.txt")).unwrap();
+        std::fs::create_dir(root.join("build")).unwrap();
+        std::fs::write(root.join("build/out.o"), "ignored").unwrap();
+        std::fs::write(root.join("added.txt"), "hi").unwrap();
+
+        let changes = before.changes();
+        let kinds: Vec<(&str, ChangeKind)> = changes.iter().map(|c| (c.path.as_str(), c.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![("added.txt", ChangeKind::Created), ("edit.txt", ChangeKind::Modified), ("gone.txt", ChangeKind::Deleted)]
+        );
+
+        let edit = changes[1].edit_meta(root).unwrap();
+        assert_eq!((edit.old_content.as_str(), edit.new_content.as_str()), ("old", "new content"));
+        assert_eq!(changes[0].before(), Some(None));
+        assert_eq!(changes[2].before(), Some(Some("bye".to_string())));
+        assert!(summary(&changes).contains("  D gone.txt"));
+    }
+}
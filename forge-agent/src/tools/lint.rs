@@ -5,32 +5,61 @@
 //! - Python: python -m py_compile, ruff check
 //! - JavaScript/TypeScript: eslint, tsc --noEmit
 //! - Go: go vet, go build
+//! - C/C++: clang-tidy, clang-check (with compile_commands.json when found)
+//! - Terraform: terraform validate
+//! - Kubernetes YAML: kubeconform
 
 use super::ToolResult;
+use crate::bridge::{DiagnosticSeverity, LspDiagnostic};
 use serde_json::Value;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Diagnostics tool - callable by the LLM to check code for errors
 pub async fn diagnostics(args: &Value, workdir: &Path) -> ToolResult {
+    match run_diagnostics(args, workdir).await {
+        Ok((_, result)) => format_diagnostics(args, &result),
+        Err(e) => e,
+    }
+}
+
+/// Run the linter for the tool's `path`, returning the linted path (file or
+/// directory) with the result so callers can also publish it to the editor.
+pub async fn run_diagnostics(args: &Value, workdir: &Path) -> Result<(PathBuf, LintResult), ToolResult> {
     let Some(path_str) = args.get("path").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'path' parameter");
+        return Err(ToolResult::err("Missing 'path' parameter"));
     };
     
     let auto_fix = args.get("fix").and_then(|v| v.as_bool()).unwrap_or(false);
     let target_path = workdir.join(path_str);
     
     if !target_path.exists() {
-        return ToolResult::err(format!("Path does not exist: {}", path_str));
+        return Err(ToolResult::err(format!("Path does not exist: {}", path_str)));
     }
     
     // Determine if this is a file or directory
-    let result = if target_path.is_dir() {
-        run_project_diagnostics(&target_path, auto_fix)
-    } else {
-        lint_file(&target_path, workdir)
-    };
-    
+    // Linters block; run them off the async thread so the tool's time limit
+    // can cancel the call
+    let workdir_owned = workdir.to_path_buf();
+    let target = target_path.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        if target.is_dir() {
+            run_project_diagnostics(&target, auto_fix)
+        } else {
+            lint_file(&target, &workdir_owned)
+        }
+    })
+    .await;
+    match result {
+        Ok(result) => Ok((target_path, result)),
+        Err(e) => Err(ToolResult::err(format!("Diagnostics failed: {e}"))),
+    }
+}
+
+/// Chat output for a diagnostics run.
+pub fn format_diagnostics(args: &Value, result: &LintResult) -> ToolResult {
+    let path_str = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
     if result.success {
         ToolResult::ok(format!("No errors found in {}", path_str))
     } else if result.errors.is_empty() {
@@ -59,6 +88,35 @@ pub async fn diagnostics(args: &Value, workdir: &Path) -> ToolResult {
     }
 }
 
+/// A run's errors as editor diagnostics (source "forge-lint"), by absolute
+/// file. Linters report paths relative to where they ran (the linted
+/// directory) or absolute. A linted file is always present, with nothing
+/// when it came back clean, so stale diagnostics for it can be cleared.
+pub fn editor_diagnostics(target: &Path, result: &LintResult) -> BTreeMap<PathBuf, Vec<LspDiagnostic>> {
+    let base = if target.is_dir() { target } else { target.parent().unwrap_or(target) };
+    let mut by_file: BTreeMap<PathBuf, Vec<LspDiagnostic>> = BTreeMap::new();
+    if target.is_file() {
+        by_file.insert(target.to_path_buf(), Vec::new());
+    }
+    for err in &result.errors {
+        let file = Path::new(&err.file);
+        let path = if file.is_absolute() { file.to_path_buf() } else { base.join(file) };
+        by_file.entry(path.clone()).or_default().push(LspDiagnostic {
+            path,
+            line: err.line.unwrap_or(1) as u32,
+            column: err.column.unwrap_or(1) as u32,
+            severity: match err.severity {
+                LintSeverity::Error => DiagnosticSeverity::Error,
+                LintSeverity::Warning => DiagnosticSeverity::Warning,
+                LintSeverity::Info => DiagnosticSeverity::Info,
+            },
+            message: err.message.clone(),
+            source: Some("forge-lint".to_string()),
+        });
+    }
+    by_file
+}
+
 /// Run project-level diagnostics (for directories)
 fn run_project_diagnostics(dir: &Path, auto_fix: bool) -> LintResult {
     // Check for Cargo.toml (Rust project)
@@ -81,6 +139,16 @@ fn run_project_diagnostics(dir: &Path, auto_fix: bool) -> LintResult {
         return run_python_diagnostics(dir, auto_fix);
     }
     
+    // Check for *.tf files (Terraform module)
+    if has_extension(dir, "tf") {
+        return run_terraform_validate(dir);
+    }
+    
+    // Check for CMakeLists.txt or a compilation database (C/C++ project)
+    if dir.join("CMakeLists.txt").exists() || compilation_database(dir, dir).is_some() {
+        return run_cpp_diagnostics(dir, auto_fix);
+    }
+    
     LintResult::ok() // No recognized project type
 }
 
@@ -451,6 +519,9 @@ pub fn lint_file(file_path: &Path, workdir: &Path) -> LintResult {
         "js" | "jsx" => lint_javascript(file_path, workdir),
         "ts" | "tsx" => lint_typescript(file_path, workdir),
         "go" => lint_go(file_path, workdir),
+        ext if C_EXTENSIONS.contains(&ext) || CPP_EXTENSIONS.contains(&ext) => lint_cpp(file_path, workdir),
+        "tf" => run_terraform_validate(file_path.parent().unwrap_or(workdir)),
+        "yaml" | "yml" => lint_kubernetes(file_path, workdir),
         _ => LintResult::ok(), // No linter available, assume ok
     }
 }
@@ -748,6 +819,235 @@ fn parse_go_errors(output: &str, file_path: &Path) -> Vec<LintError> {
     errors
 }
 
+const C_EXTENSIONS: &[&str] = &["c", "h"];
+const CPP_EXTENSIONS: &[&str] = &["cc", "cpp", "cxx", "c++", "hh", "hpp", "hxx", "h++"];
+/// Where CMake and Meson usually put `compile_commands.json`.
+const BUILD_DIRS: &[&str] = &["build", "out", "builddir", "cmake-build-debug", "cmake-build-release"];
+/// Most files of a compilation database checked in one project run.
+const MAX_CLANG_FILES: usize = 50;
+
+/// The directory holding the `compile_commands.json` for `start`: `start`
+/// or one of its build directories, then each parent up to `workdir`.
+fn compilation_database(start: &Path, workdir: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidates = std::iter::once(dir.to_path_buf()).chain(BUILD_DIRS.iter().map(|b| dir.join(b)));
+        for candidate in candidates {
+            if candidate.join("compile_commands.json").is_file() {
+                return Some(candidate);
+            }
+        }
+        if dir == workdir || !dir.starts_with(workdir) {
+            break;
+        }
+    }
+    None
+}
+
+/// Run project-wide C/C++ diagnostics over the files in the compilation
+/// database.
+fn run_cpp_diagnostics(dir: &Path, auto_fix: bool) -> LintResult {
+    let Some(db) = compilation_database(dir, dir) else {
+        return LintResult::failed(
+            vec![],
+            "No compile_commands.json found; configure with -DCMAKE_EXPORT_COMPILE_COMMANDS=ON to check C/C++ files".to_string(),
+        );
+    };
+    let Ok(content) = std::fs::read_to_string(db.join("compile_commands.json")) else {
+        return LintResult::ok();
+    };
+    let entries: Vec<Value> = serde_json::from_str(&content).unwrap_or_default();
+    let mut files: Vec<PathBuf> = Vec::new();
+    for entry in &entries {
+        let Some(file) = entry.get("file").and_then(|f| f.as_str()) else { continue };
+        let base = entry.get("directory").and_then(|d| d.as_str()).map(PathBuf::from).unwrap_or_else(|| db.clone());
+        let file = base.join(file);
+        if file.starts_with(dir) && !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    files.truncate(MAX_CLANG_FILES);
+    if files.is_empty() {
+        return LintResult::ok();
+    }
+    run_clang(&files, Some(&db), &[], auto_fix, dir)
+}
+
+/// Lint C/C++ files with clang-tidy, or clang-check when it isn't installed
+fn lint_cpp(file_path: &Path, workdir: &Path) -> LintResult {
+    let file_dir = file_path.parent().unwrap_or(workdir);
+    if let Some(db) = compilation_database(file_dir, workdir) {
+        return run_clang(&[file_path.to_path_buf()], Some(&db), &[], false, workdir);
+    }
+    // No compilation database: guess the flags
+    let mut flags = vec![format!("-I{}", workdir.display()), format!("-I{}", workdir.join("include").display())];
+    let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if CPP_EXTENSIONS.contains(&ext) {
+        flags.extend(["-x".to_string(), "c++".to_string(), "-std=c++17".to_string()]);
+    }
+    run_clang(&[file_path.to_path_buf()], None, &flags, false, workdir)
+}
+
+/// Run clang-tidy (falling back to clang-check) on `files`, with `-p db` or
+/// the compile `flags` after `--`.
+fn run_clang(files: &[PathBuf], db: Option<&Path>, flags: &[String], auto_fix: bool, dir: &Path) -> LintResult {
+    for tool in ["clang-tidy", "clang-check"] {
+        let mut cmd = Command::new(tool);
+        if tool == "clang-tidy" {
+            cmd.arg("--quiet");
+            if auto_fix {
+                cmd.arg("--fix");
+            }
+        }
+        if let Some(db) = db {
+            cmd.arg("-p").arg(db);
+        }
+        cmd.args(files);
+        if db.is_none() {
+            cmd.arg("--").args(flags);
+        }
+        let Ok(out) = cmd.current_dir(dir).output() else {
+            continue; // Not installed
+        };
+        let output = format!("{}{}", String::from_utf8_lossy(&out.stdout), String::from_utf8_lossy(&out.stderr));
+        let errors = parse_clang_errors(&output);
+        if errors.is_empty() && out.status.success() {
+            return LintResult::ok();
+        }
+        return LintResult::failed(errors, output);
+    }
+    LintResult::ok() // Linter not available
+}
+
+/// Parse clang-tidy/clang-check output
+fn parse_clang_errors(output: &str) -> Vec<LintError> {
+    // Format: file:line:col: severity: message [check]
+    let Ok(re) = regex::Regex::new(r"^(.+?):(\d+):(\d+): (fatal error|error|warning): (.*)$") else {
+        return Vec::new();
+    };
+    output
+        .lines()
+        .filter_map(|line| {
+            let caps = re.captures(line)?;
+            Some(LintError {
+                file: caps[1].to_string(),
+                line: caps[2].parse().ok(),
+                column: caps[3].parse().ok(),
+                message: caps[5].trim().to_string(),
+                severity: if &caps[4] == "warning" { LintSeverity::Warning } else { LintSeverity::Error },
+            })
+        })
+        .collect()
+}
+
+fn has_extension(dir: &Path, ext: &str) -> bool {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).any(|e| e.path().extension().is_some_and(|x| x == ext)))
+        .unwrap_or(false)
+}
+
+/// Validate the Terraform module in `dir` (one directory: Terraform checks
+/// modules, not files)
+fn run_terraform_validate(dir: &Path) -> LintResult {
+    let output = Command::new("terraform")
+        .args(["validate", "-json", "-no-color"])
+        .current_dir(dir)
+        .output();
+
+    match output {
+        Ok(out) => {
+            let stdout = String::from_utf8_lossy(&out.stdout);
+            let errors = parse_terraform_errors(&stdout, dir);
+            if out.status.success() && errors.is_empty() {
+                LintResult::ok()
+            } else {
+                LintResult::failed(errors, format!("{}{}", stdout, String::from_utf8_lossy(&out.stderr)))
+            }
+        }
+        Err(_) => LintResult::ok(), // Linter not available
+    }
+}
+
+/// Parse `terraform validate -json` output
+fn parse_terraform_errors(output: &str, dir: &Path) -> Vec<LintError> {
+    let Ok(report) = serde_json::from_str::<Value>(output) else {
+        return Vec::new();
+    };
+    let diagnostics = report.get("diagnostics").and_then(|d| d.as_array()).cloned().unwrap_or_default();
+    diagnostics
+        .iter()
+        .map(|d| {
+            let range = d.get("range");
+            let start = range.and_then(|r| r.get("start"));
+            let file = range.and_then(|r| r.get("filename")).and_then(|f| f.as_str()).unwrap_or_default();
+            let summary = d.get("summary").and_then(|s| s.as_str()).unwrap_or_default();
+            let detail = d.get("detail").and_then(|s| s.as_str()).unwrap_or_default();
+            LintError {
+                file: if file.is_empty() { dir.display().to_string() } else { file.to_string() },
+                line: start.and_then(|s| s.get("line")).and_then(|l| l.as_u64()).map(|l| l as usize),
+                column: start.and_then(|s| s.get("column")).and_then(|c| c.as_u64()).map(|c| c as usize),
+                message: if detail.is_empty() { summary.to_string() } else { format!("{summary}: {detail}") },
+                severity: match d.get("severity").and_then(|s| s.as_str()) {
+                    Some("warning") => LintSeverity::Warning,
+                    _ => LintSeverity::Error,
+                },
+            }
+        })
+        .collect()
+}
+
+/// Check Kubernetes manifests against their schemas with kubeconform; other
+/// YAML is left alone
+fn lint_kubernetes(file_path: &Path, workdir: &Path) -> LintResult {
+    let Ok(content) = std::fs::read_to_string(file_path) else {
+        return LintResult::ok();
+    };
+    let is_manifest = content.lines().any(|l| l.starts_with("apiVersion:"))
+        && content.lines().any(|l| l.starts_with("kind:"));
+    if !is_manifest {
+        return LintResult::ok();
+    }
+    let output = Command::new("kubeconform")
+        .args(["-summary=false", "-ignore-missing-schemas", "-output", "json"])
+        .arg(file_path)
+        .current_dir(workdir)
+        .output();
+
+    match output {
+        Ok(out) => {
+            let stdout = String::from_utf8_lossy(&out.stdout);
+            let errors = parse_kubeconform_errors(&stdout);
+            if out.status.success() && errors.is_empty() {
+                LintResult::ok()
+            } else {
+                LintResult::failed(errors, stdout.to_string())
+            }
+        }
+        Err(_) => LintResult::ok(), // Linter not available
+    }
+}
+
+/// Parse `kubeconform -output json` output
+fn parse_kubeconform_errors(output: &str) -> Vec<LintError> {
+    let Ok(report) = serde_json::from_str::<Value>(output) else {
+        return Vec::new();
+    };
+    let resources = report.get("resources").and_then(|r| r.as_array()).cloned().unwrap_or_default();
+    resources
+        .iter()
+        .filter(|r| matches!(r.get("status").and_then(|s| s.as_str()), Some("statusInvalid" | "statusError")))
+        .map(|r| {
+            let field = |name: &str| r.get(name).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+            LintError {
+                file: field("filename"),
+                line: None,
+                column: None,
+                message: format!("{} {}: {}", field("kind"), field("name"), field("msg")),
+                severity: LintSeverity::Error,
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -796,4 +1096,62 @@ mod tests {
         assert!(formatted.contains("ERROR"));
         assert!(formatted.contains("mismatched types"));
     }
+
+    #[test]
+    fn test_clang_diagnostics() {
+        let output = "/src/net/server.cpp:12:5: warning: use nullptr [modernize-use-nullptr]\n    p = 0;\n    ^\n/src/net/server.h:3:10: fatal error: 'asio.hpp' file not found\n/src/net/server.cpp:4:1: note: declared here\n";
+        let errors = parse_clang_errors(output);
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].line, errors[0].column, errors[0].severity), (Some(12), Some(5), LintSeverity::Warning));
+        assert_eq!(errors[0].message, "use nullptr [modernize-use-nullptr]");
+        assert_eq!(errors[1].file, "/src/net/server.h");
+        assert_eq!(errors[1].severity, LintSeverity::Error);
+
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("build")).unwrap();
+        fs::create_dir_all(dir.path().join("src/net")).unwrap();
+        assert_eq!(compilation_database(&dir.path().join("src/net"), dir.path()), None);
+        fs::write(dir.path().join("build/compile_commands.json"), "[]").unwrap();
+        assert_eq!(compilation_database(&dir.path().join("src/net"), dir.path()), Some(dir.path().join("build")));
+    }
+
+    #[test]
+    fn test_infra_diagnostics() {
+        let terraform = r#"{"valid":false,"error_count":1,"diagnostics":[{"severity":"error","summary":"Unsupported argument","detail":"An argument named \"amii\" is not expected here.","range":{"filename":"main.tf","start":{"line":4,"column":3}}}]}"#;
+        let errors = parse_terraform_errors(terraform, Path::new("infra"));
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].file.as_str(), errors[0].line, errors[0].column), ("main.tf", Some(4), Some(3)));
+        assert_eq!(errors[0].message, "Unsupported argument: An argument named \"amii\" is not expected here.");
+
+        let kubeconform = r#"{"resources":[{"filename":"deploy.yaml","kind":"Deployment","name":"web","version":"apps/v1","status":"statusInvalid","msg":"missing properties: 'selector'"},{"filename":"deploy.yaml","kind":"Service","name":"web","status":"statusValid","msg":""}]}"#;
+        let errors = parse_kubeconform_errors(kubeconform);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Deployment web: missing properties: 'selector'");
+    }
+
+    #[test]
+    fn test_editor_diagnostics() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("main.py");
+        fs::write(&file_path, "print(1)\n").unwrap();
+        let error = |file: &str, line| LintError {
+            file: file.to_string(),
+            line,
+            column: None,
+            message: "boom".to_string(),
+            severity: LintSeverity::Warning,
+        };
+
+        let clean = editor_diagnostics(&file_path, &LintResult::ok());
+        assert_eq!(clean.len(), 1);
+        assert!(clean[&file_path].is_empty());
+
+        let errors = vec![error("src/lib.rs", Some(3)), error("src/lib.rs", None), error("/abs/x.rs", Some(1))];
+        let by_file = editor_diagnostics(dir.path(), &LintResult::failed(errors, String::new()));
+        let lib = &by_file[&dir.path().join("src/lib.rs")];
+        assert_eq!(lib.len(), 2);
+        assert_eq!((lib[0].line, lib[0].column, lib[0].severity), (3, 1, DiagnosticSeverity::Warning));
+        assert_eq!(lib[1].line, 1);
+        assert_eq!(by_file[Path::new("/abs/x.rs")].len(), 1);
+    }
 }
@@ -0,0 +1,215 @@
+//! Advisory locks on the files agents edit.
+//!
+//! Several conversations can run at once in one proxy. Without locks, two
+//! of them editing the same file interleave their writes and the last one
+//! silently wins. A file a conversation edits is locked to it until its
+//! message is done ([`TurnLocks`]); another conversation's edit then waits
+//! for the lock or fails with an error naming the task holding it.
+//! Calls outside a conversation ([`scope`]) lock only for the call.
+//!
+//! - `FORGE_FILE_LOCK`: `wait` (default) or `fail` at once
+//! - `FORGE_FILE_LOCK_WAIT`: seconds to wait before failing (30)
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const DEFAULT_WAIT: Duration = Duration::from_secs(30);
+const POLL: Duration = Duration::from_millis(100);
+
+tokio::task_local! {
+    static OWNER: Owner;
+}
+
+/// Who holds a lock: a conversation, or one tool call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Owner {
+    pub id: String,
+    /// Shown to whoever else wants the file, e.g. "conversation 'Fix the parser'".
+    pub task: String,
+}
+
+#[derive(Debug, Clone)]
+struct Holder {
+    owner: Owner,
+    tool: String,
+    since: Instant,
+    /// Released when the call ends rather than with the conversation.
+    per_call: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    Wait(Duration),
+    Fail,
+}
+
+impl LockMode {
+    pub fn configured() -> Self {
+        use crate::config::var;
+        Self::parse(var("FORGE_FILE_LOCK").as_deref(), var("FORGE_FILE_LOCK_WAIT").as_deref())
+    }
+
+    fn parse(mode: Option<&str>, wait: Option<&str>) -> Self {
+        match mode.map(|m| m.trim().to_ascii_lowercase()).as_deref() {
+            Some("fail") => Self::Fail,
+            _ => Self::Wait(wait.and_then(|s| s.trim().parse().ok()).map(Duration::from_secs).unwrap_or(DEFAULT_WAIT)),
+        }
+    }
+}
+
+fn table() -> &'static Mutex<HashMap<PathBuf, Holder>> {
+    static INSTANCE: OnceLock<Mutex<HashMap<PathBuf, Holder>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Run `f` with the file locks it takes held by `owner`.
+pub async fn scope<F: Future>(owner: Owner, f: F) -> F::Output {
+    OWNER.scope(owner, f).await
+}
+
+/// Releases `owner`'s locks when dropped, e.g. at the end of a message.
+pub struct TurnLocks(String);
+
+impl TurnLocks {
+    pub fn new(owner: &Owner) -> Self {
+        Self(owner.id.clone())
+    }
+}
+
+impl Drop for TurnLocks {
+    fn drop(&mut self) {
+        release(&self.0);
+    }
+}
+
+/// Release every lock `owner_id` holds.
+pub fn release(owner_id: &str) {
+    table().lock().unwrap().retain(|_, holder| holder.owner.id != owner_id);
+}
+
+/// Locks held for one tool call, released when dropped.
+pub struct CallLocks(Option<String>);
+
+impl Drop for CallLocks {
+    fn drop(&mut self) {
+        if let Some(id) = &self.0 {
+            release(id);
+        }
+    }
+}
+
+/// Lock `paths` (relative to `workdir`) for a `tool` call, all or none,
+/// waiting per `mode` while another owner has any of them.
+pub async fn acquire(workdir: &Path, paths: &[&str], tool: &str, mode: LockMode) -> Result<CallLocks, String> {
+    static CALLS: AtomicU64 = AtomicU64::new(0);
+    let (owner, per_call) = match OWNER.try_with(Owner::clone) {
+        Ok(owner) => (owner, false),
+        Err(_) => {
+            let n = CALLS.fetch_add(1, Ordering::Relaxed);
+            (Owner { id: format!("call-{n}"), task: format!("another `{tool}` call") }, true)
+        }
+    };
+    let paths: Vec<PathBuf> = paths.iter().map(|p| normalize(&workdir.join(p))).collect();
+    let started = Instant::now();
+    loop {
+        match try_lock(&paths, &owner, tool, per_call) {
+            Ok(()) => return Ok(CallLocks(per_call.then_some(owner.id))),
+            Err((path, holder)) => {
+                let waited = started.elapsed();
+                let give_up = match mode {
+                    LockMode::Fail => true,
+                    LockMode::Wait(limit) => waited >= limit,
+                };
+                if give_up {
+                    return Err(busy_message(workdir, &path, &holder, waited));
+                }
+            }
+        }
+        tokio::time::sleep(POLL).await;
+    }
+}
+
+fn try_lock(paths: &[PathBuf], owner: &Owner, tool: &str, per_call: bool) -> Result<(), (PathBuf, Holder)> {
+    let mut table = table().lock().unwrap();
+    for path in paths {
+        if let Some(holder) = table.get(path).filter(|h| h.owner.id != owner.id) {
+            return Err((path.clone(), holder.clone()));
+        }
+    }
+    for path in paths {
+        let holder = Holder { owner: owner.clone(), tool: tool.to_string(), since: Instant::now(), per_call };
+        // A conversation keeps its first lock's time
+        table.entry(path.clone()).and_modify(|h| h.tool = tool.to_string()).or_insert(holder);
+    }
+    Ok(())
+}
+
+fn busy_message(workdir: &Path, path: &Path, holder: &Holder, waited: Duration) -> String {
+    let shown = path.strip_prefix(workdir).unwrap_or(path).display();
+    let held = holder.since.elapsed().as_secs();
+    let waited = if waited.as_secs() > 0 { format!(" Waited {}s.", waited.as_secs()) } else { String::new() };
+    let until = if holder.per_call { "its call finishes" } else { "that task's message is done" };
+    format!(
+        "{shown} is locked by {} (last `{}`, for {held}s) and stays locked until {until}.{waited} \
+         Work on other files meanwhile, or try again later; editing it now would overwrite the other task's changes.",
+        holder.owner.task, holder.tool
+    )
+}
+
+/// `path` without `.` and `..`, so different spellings share a lock.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            other => normal.push(other),
+        }
+    }
+    normal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_locks() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let alice = Owner { id: "a".into(), task: "conversation 'Fix the parser'".into() };
+        let bob = Owner { id: "b".into(), task: "conversation 'Add logging'".into() };
+
+        let turn = TurnLocks::new(&alice);
+        let call = scope(alice.clone(), acquire(root, &["src/lib.rs"], "edit_file", LockMode::Fail)).await.unwrap();
+        drop(call);
+        // Held past the call, and again by the same conversation
+        assert!(scope(alice.clone(), acquire(root, &["./src/../src/lib.rs"], "write_file", LockMode::Fail)).await.is_ok());
+
+        let err = scope(bob.clone(), acquire(root, &["src/main.rs", "src/lib.rs"], "apply_patch", LockMode::Fail))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.starts_with("src/lib.rs is locked by conversation 'Fix the parser' (last `write_file`"), "{err}");
+        // All or none: main.rs wasn't taken
+        assert!(acquire(root, &["src/main.rs"], "write_file", LockMode::Fail).await.is_ok());
+
+        let waiting = tokio::spawn({
+            let root = root.to_path_buf();
+            scope(bob, async move { acquire(&root, &["src/lib.rs"], "edit_file", LockMode::Wait(Duration::from_secs(5))).await.is_ok() })
+        });
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        drop(turn);
+        assert!(waiting.await.unwrap());
+        release("b");
+
+        assert_eq!(LockMode::parse(Some("FAIL"), None), LockMode::Fail);
+        assert_eq!(LockMode::parse(None, Some("5")), LockMode::Wait(Duration::from_secs(5)));
+    }
+}
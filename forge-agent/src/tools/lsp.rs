@@ -142,7 +142,15 @@ pub async fn lsp_rename(
     let path = workdir.join(path_str);
     match bridge.rename_symbol(&path, line as u32, column as u32, new_name).await {
         Ok(_) => ToolResult::ok(format!("Successfully renamed symbol to '{}'", new_name)),
-        Err(e) => ToolResult::err(format!("LSP rename failed: {e}")),
+        // Most likely no language server for this file: rename by syntax tree
+        Err(e) => {
+            let result = super::rename::rename(workdir, path_str, line, column, new_name);
+            if result.success {
+                result
+            } else {
+                ToolResult::err(format!("LSP rename failed: {e}; {}", result.output))
+            }
+        }
     }
 }
 
@@ -1,16 +1,43 @@
+pub mod args;
+pub mod buffers;
+pub mod deadline;
 mod execute;
 pub mod files;
+pub mod focus_chain;
+pub mod fs_changes;
 pub(crate) mod search;
 mod code;
 mod process;
-mod treesitter;
+pub mod ownership;
+pub mod plugin;
+pub mod process_log;
+pub mod project_tree;
+pub mod ranking;
+pub mod rename;
+mod platform;
+pub mod treesitter;
 pub mod lint;
-mod display;
+pub mod locks;
+pub mod display;
 mod run_config;
 mod git;
+pub mod rerank;
+pub mod resources;
+pub mod review;
+pub mod schema;
+pub mod selection;
+pub mod settings;
+pub mod trash;
+mod testgen;
+mod audit;
 mod sdk_manager;
+pub mod sdk_env;
 pub mod lsp;
+pub mod v4a;
+pub mod volts;
 pub mod web;
+pub mod workspace_diff;
+pub mod working_set;
 
 pub use lint::{lint_file, LintResult, LintError, LintSeverity};
 
@@ -27,6 +54,7 @@ pub use run_config::*;
 pub use git::*;
 pub use sdk_manager::*;
 pub use web::*;
+pub use execute::TerminalPolicy;
 
 // Re-export ensure_indexed for external callers (lapce-proxy)
 pub use search::ensure_indexed;
@@ -37,10 +65,12 @@ pub enum Tool {
     // File operations
     ReadFile,
     WriteFile,      // write_file (was write_to_file)
+    WriteFileRange,
     EditFile,       // edit_file  (was replace_in_file)
     ApplyPatch,
     ListFiles,
     DeleteFile,
+    RestoreFile,
 
     // Search
     Grep,
@@ -48,6 +78,7 @@ pub enum Tool {
 
     // Diagnostics
     Diagnostics,
+    AuditDependencies,
 
     // Process management — consolidated
     Run,            // run(command, background?, timeout_secs?)
@@ -56,22 +87,30 @@ pub enum Tool {
 
     // Code intelligence
     References,     // references(symbol, path?) — was find_symbol_references
+    GenerateTests,  // generate_tests(symbol, max_retries?, ...)
     Lsp,            // lsp(action, path, line, column, new_name?)
 
     // Display
     ShowCode,
     ShowDiagram,
+    OpenInEditor,   // open_in_editor(path, line?, column?, action?)
 
     // Run configuration
     RunProject,
     StopProject,
     ListRunConfigs,
+    ReadRunOutput,
+    ShellSession,
+    WorkspaceDiff,
 
     // Git operations
     Git,
+    Review,         // review(range?, staged?, path?)
 
     // SDK Management
     SdkManager,
+    Plugins,        // plugins(action, query?, name?)
+    ModifySettings, // modify_settings(settings?, keymaps?, preview?)
 
     // Web
     Fetch,
@@ -94,27 +133,38 @@ impl Tool {
             // New canonical names
             Self::ReadFile => "read_file",
             Self::WriteFile => "write_file",
+            Self::WriteFileRange => "write_file_range",
             Self::EditFile => "edit_file",
             Self::ApplyPatch => "apply_patch",
             Self::ListFiles => "list_files",
             Self::DeleteFile => "delete_file",
+            Self::RestoreFile => "restore_file",
             Self::Grep => "grep",
             Self::Glob => "glob",
             Self::Diagnostics => "diagnostics",
+            Self::AuditDependencies => "audit_dependencies",
             Self::Run => "run",
             Self::Process => "process",
             Self::Port => "port",
             Self::References => "references",
+            Self::GenerateTests => "generate_tests",
             Self::Lsp => "lsp",
             Self::ShowCode => "show_code",
             Self::ShowDiagram => "show_diagram",
+            Self::OpenInEditor => "open_in_editor",
             Self::RunProject => "run_project",
             Self::StopProject => "stop_project",
             Self::Git => "git",
+            Self::Review => "review",
             Self::SdkManager => "sdk_manager",
+            Self::Plugins => "plugins",
+            Self::ModifySettings => "modify_settings",
             Self::Fetch => "fetch",
             Self::WorkspaceSymbols => "workspace_symbols",
             Self::ListRunConfigs => "list_run_configs",
+            Self::ReadRunOutput => "read_run_output",
+            Self::ShellSession => "shell_session",
+            Self::WorkspaceDiff => "workspace_diff",
             // Interaction
             Self::AttemptCompletion => "attempt_completion",
             Self::AskFollowupQuestion => "ask_followup_question",
@@ -130,27 +180,38 @@ impl Tool {
             // New canonical names
             "read_file"    => Some(Self::ReadFile),
             "write_file"   => Some(Self::WriteFile),
+            "write_file_range" => Some(Self::WriteFileRange),
             "edit_file"    => Some(Self::EditFile),
             "apply_patch"  => Some(Self::ApplyPatch),
             "list_files"   => Some(Self::ListFiles),
             "delete_file"  => Some(Self::DeleteFile),
+            "restore_file" => Some(Self::RestoreFile),
             "grep"         => Some(Self::Grep),
             "glob"         => Some(Self::Glob),
             "diagnostics"  => Some(Self::Diagnostics),
+            "audit_dependencies" => Some(Self::AuditDependencies),
             "run"          => Some(Self::Run),
             "process"      => Some(Self::Process),
             "port"         => Some(Self::Port),
             "references"   => Some(Self::References),
+            "generate_tests" => Some(Self::GenerateTests),
             "lsp"          => Some(Self::Lsp),
             "show_code"    => Some(Self::ShowCode),
             "show_diagram" => Some(Self::ShowDiagram),
+            "open_in_editor" => Some(Self::OpenInEditor),
             "run_project"  => Some(Self::RunProject),
             "stop_project" => Some(Self::StopProject),
             "git"          => Some(Self::Git),
+            "review"       => Some(Self::Review),
             "sdk_manager"  => Some(Self::SdkManager),
+            "plugins"      => Some(Self::Plugins),
+            "modify_settings" => Some(Self::ModifySettings),
             "fetch"             => Some(Self::Fetch),
             "workspace_symbols" => Some(Self::WorkspaceSymbols),
             "list_run_configs"  => Some(Self::ListRunConfigs),
+            "read_run_output"   => Some(Self::ReadRunOutput),
+            "shell_session"     => Some(Self::ShellSession),
+            "workspace_diff"    => Some(Self::WorkspaceDiff),
             "attempt_completion"       => Some(Self::AttemptCompletion),
             "ask_followup_question"    => Some(Self::AskFollowupQuestion),
             "think"                    => Some(Self::Think),
@@ -166,13 +227,18 @@ impl Tool {
         matches!(
             self,
             Self::WriteFile
+                | Self::WriteFileRange
                 | Self::EditFile
                 | Self::ApplyPatch
                 | Self::DeleteFile
+                | Self::RestoreFile
                 | Self::Run
                 | Self::Process  // kill action
                 | Self::Port     // kill action
                 | Self::Lsp      // rename action
+                | Self::GenerateTests
+                | Self::Plugins  // install/remove/enable/disable
+                | Self::ModifySettings
         )
     }
 }
@@ -211,15 +277,18 @@ pub struct ToolResult {
     /// The caller should show a confirmation dialog and re-execute if approved.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub needs_approval: Option<bool>,
+    /// Files a command changed, found by diffing the workspace around it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fs_changes: Vec<fs_changes::FsChange>,
 }
 
 impl ToolResult {
     pub fn ok(output: impl Into<String>) -> Self {
-        Self { success: true, output: output.into(), file_edit: None, needs_approval: None }
+        Self { success: true, output: output.into(), file_edit: None, needs_approval: None, fs_changes: Vec::new() }
     }
 
     pub fn err(output: impl Into<String>) -> Self {
-        Self { success: false, output: output.into(), file_edit: None, needs_approval: None }
+        Self { success: false, output: output.into(), file_edit: None, needs_approval: None, fs_changes: Vec::new() }
     }
 
     /// Attach file edit metadata (for diff preview).
@@ -228,6 +297,15 @@ impl ToolResult {
         self
     }
 
+    /// Attach the files a command changed, listing them in the output.
+    pub fn with_fs_changes(mut self, changes: Vec<fs_changes::FsChange>) -> Self {
+        if !changes.is_empty() {
+            self.output = format!("{}\n\n{}", self.output.trim_end(), fs_changes::summary(&changes));
+            self.fs_changes = changes;
+        }
+        self
+    }
+
     /// Mark this result as needing user approval before execution.
     pub fn awaiting_approval(tool_name: &str, summary: &str) -> Self {
         Self {
@@ -235,6 +313,7 @@ impl ToolResult {
             output: format!("[APPROVAL REQUIRED] Tool '{}' wants to: {}", tool_name, summary),
             file_edit: None,
             needs_approval: Some(true),
+            fs_changes: Vec::new(),
         }
     }
 }
@@ -268,6 +347,9 @@ pub type ApprovalCallback = Box<dyn Fn(&str, &str) -> bool + Send + Sync>;
 /// Options for `execute()`.
 pub struct ExecuteOptions {
     pub plan_mode: bool,
+    /// The workspace isn't trusted: plan mode plus no commands or network
+    /// (see [`crate::trust`]).
+    pub restricted: bool,
     pub approval_policy: ApprovalPolicy,
     /// Optional callback for synchronous approval.
     /// Receives (tool_name, human-readable summary).
@@ -280,6 +362,7 @@ impl Default for ExecuteOptions {
     fn default() -> Self {
         Self {
             plan_mode: false,
+            restricted: false,
             approval_policy: ApprovalPolicy::AutoApproveAll,
             approval_callback: None,
             loop_detector: None,
@@ -293,12 +376,37 @@ pub async fn execute(tool: &ToolCall, workdir: &Path, plan_mode: bool) -> ToolRe
     execute_with_options(tool, workdir, &ExecuteOptions { plan_mode, ..Default::default() }).await
 }
 
+/// Call a tool that takes a typed args struct (see [`args`]), turning
+/// arguments that don't deserialize into an error result.
+macro_rules! typed {
+    ($f:path, $tool:expr, $workdir:expr) => {
+        match args::parse(&$tool.name, &$tool.arguments) {
+            Ok(parsed) => $f(parsed, $workdir).await,
+            Err(invalid) => invalid,
+        }
+    };
+}
+
 /// Execute a tool call with full options (approval, loop detection).
 pub async fn execute_with_options(tool: &ToolCall, workdir: &Path, opts: &ExecuteOptions) -> ToolResult {
     use std::time::Instant;
     let start = Instant::now();
+
+    if opts.restricted && !crate::trust::allowed_when_restricted(&tool.name) {
+        return ToolResult::err(crate::trust::refusal(&tool.name));
+    }
     
     let Some(t) = Tool::from_name(&tool.name) else {
+        // Plugin tools run in the plugin, which only the IDE can reach
+        if plugin::is_registered(&tool.name) {
+            if opts.plan_mode {
+                return ToolResult::err("Cannot run plugin tools in plan mode");
+            }
+            if let Err(message) = schema::validate_call(&tool.name, &tool.arguments) {
+                return ToolResult::err(message);
+            }
+            return ToolResult::ok("PENDING_IDE_EXECUTION");
+        }
         return ToolResult::err(format!("Unknown tool: {}", tool.name));
     };
 
@@ -307,6 +415,22 @@ pub async fn execute_with_options(tool: &ToolCall, workdir: &Path, opts: &Execut
         return ToolResult::err("Cannot modify files in plan mode");
     }
 
+    // Malformed arguments go back to the model as one fixable message
+    if let Err(message) = schema::validate_call(&tool.name, &tool.arguments) {
+        tracing::warn!("{message}");
+        return ToolResult::err(message);
+    }
+
+    // A path into another workspace root runs the tool against that root
+    let routed;
+    let (tool, workdir) = match crate::workspace_roots::WorkspaceRoots::discover(workdir).route(&tool.arguments) {
+        Some((root, arguments)) => {
+            routed = (ToolCall { arguments, ..tool.clone() }, root);
+            (&routed.0, routed.1.as_path())
+        }
+        None => (tool, workdir),
+    };
+
     // ── Loop detection ──────────────────────────────────────────
     if let Some(ref detector) = opts.loop_detector {
         let args_json = serde_json::to_string(&tool.arguments).unwrap_or_default();
@@ -342,43 +466,80 @@ pub async fn execute_with_options(tool: &ToolCall, workdir: &Path, opts: &Execut
         }
     }
 
-    // ── Execute ─────────────────────────────────────────────────
-    let result = match t {
-        // ── New canonical tools ───────────────────────────────────────────
-        Tool::ReadFile => files::read(&tool.arguments, workdir).await,
-        Tool::WriteFile => files::write(&tool.arguments, workdir).await,
-        Tool::EditFile => files::replace(&tool.arguments, workdir).await,
-        Tool::ApplyPatch => files::apply_patch(&tool.arguments, workdir).await,
-        Tool::ListFiles => files::list(&tool.arguments, workdir).await,
-        Tool::DeleteFile => files::delete(&tool.arguments, workdir).await,
-        Tool::Grep => search::grep(&tool.arguments, workdir).await,
-        Tool::Glob => search::glob_search(&tool.arguments, workdir).await,
-        Tool::Diagnostics => lint::diagnostics(&tool.arguments, workdir).await,
-        Tool::Run => process::run_command(&tool.arguments, workdir).await,
-        Tool::Process => process::manage_process(&tool.arguments, workdir).await,
-        Tool::Port => process::manage_port(&tool.arguments, workdir).await,
-        Tool::References => code::find_references(&tool.arguments, workdir).await,
-        Tool::Lsp => ToolResult::err("lsp tool must be executed via ProxyBridge in dispatch.rs"),
-        Tool::ShowCode => display::show_code(&tool.arguments, workdir).await,
-        Tool::ShowDiagram => display::show_diagram(&tool.arguments, workdir).await,
-        Tool::RunProject => run_config::run_project(&tool.arguments, workdir).await,
-        Tool::StopProject => run_config::stop_project(&tool.arguments, workdir).await,
-        Tool::Git => git::git(&tool.arguments, workdir).await,
-        Tool::SdkManager => sdk_manager::sdk_manager(&tool.arguments, workdir).await,
-        Tool::Fetch => web::fetch_webpage(&tool.arguments).await,
-        Tool::WorkspaceSymbols => search::workspace_symbols(&tool.arguments, workdir).await,
-        Tool::ListRunConfigs => run_config::list_run_configs(&tool.arguments, workdir).await,
-
-        // Handled specially by the agent
-        Tool::AttemptCompletion
-        | Tool::AskFollowupQuestion
-        | Tool::PlanModeRespond
-        | Tool::ActModeRespond
-        | Tool::FocusChain
-        | Tool::Think => ToolResult::ok(""),
+    // ── File locks ──────────────────────────────────────────────
+    // Another conversation editing the same file waits or fails here
+    let _locks = if crate::staging::is_file_edit(&tool.name) {
+        let paths = workspace_diff::tool_edit_paths(&tool.name, &tool.arguments);
+        match locks::acquire(workdir, &paths, &tool.name, locks::LockMode::configured()).await {
+            Ok(held) => Some(held),
+            Err(message) => return ToolResult::err(message),
+        }
+    } else {
+        None
     };
+
+    // ── Execute ─────────────────────────────────────────────────
+    let timeout = deadline::Timeouts::configured().for_tool(&tool.name, t);
+    let result = deadline::limit(&tool.name, timeout, async {
+        match t {
+            // ── New canonical tools ───────────────────────────────────────────
+            Tool::ReadFile => typed!(files::read, tool, workdir),
+            Tool::WriteFile => typed!(files::write, tool, workdir),
+            Tool::WriteFileRange => typed!(files::write_range, tool, workdir),
+            Tool::EditFile => typed!(files::replace, tool, workdir),
+            Tool::ApplyPatch => typed!(files::apply_patch, tool, workdir),
+            Tool::ListFiles => typed!(files::list, tool, workdir),
+            Tool::DeleteFile => typed!(files::delete, tool, workdir),
+            Tool::RestoreFile => typed!(files::restore, tool, workdir),
+            Tool::Grep => typed!(search::grep, tool, workdir),
+            Tool::Glob => typed!(search::glob_search, tool, workdir),
+            Tool::Diagnostics => lint::diagnostics(&tool.arguments, workdir).await,
+            Tool::AuditDependencies => audit::audit_dependencies(&tool.arguments, workdir).await,
+            Tool::Run => process::run_command(&tool.arguments, workdir).await,
+            Tool::Process => process::manage_process(&tool.arguments, workdir).await,
+            Tool::Port => process::manage_port(&tool.arguments, workdir).await,
+            Tool::References => code::find_references(&tool.arguments, workdir).await,
+            Tool::GenerateTests => testgen::generate_tests(&tool.arguments, workdir).await,
+            Tool::Lsp => ToolResult::err("lsp tool must be executed via ProxyBridge in dispatch.rs"),
+            Tool::ShowCode => display::show_code(&tool.arguments, workdir).await,
+            Tool::ShowDiagram => display::show_diagram(&tool.arguments, workdir).await,
+            Tool::OpenInEditor => typed!(display::open_in_editor, tool, workdir),
+            Tool::RunProject => run_config::run_project(&tool.arguments, workdir).await,
+            Tool::StopProject => run_config::stop_project(&tool.arguments, workdir).await,
+            Tool::Git => git::git(&tool.arguments, workdir).await,
+            Tool::Review => review::review(&tool.arguments, workdir).await,
+            Tool::SdkManager => sdk_manager::sdk_manager(&tool.arguments, workdir).await,
+            Tool::Plugins => typed!(volts::plugins, tool, workdir),
+            Tool::ModifySettings => typed!(settings::modify_settings, tool, workdir),
+            Tool::Fetch => web::fetch_webpage(&tool.arguments).await,
+            Tool::WorkspaceSymbols => search::workspace_symbols(&tool.arguments, workdir).await,
+            Tool::ListRunConfigs => run_config::list_run_configs(&tool.arguments, workdir).await,
+            Tool::ReadRunOutput => run_config::read_run_output(&tool.arguments, workdir).await,
+            Tool::ShellSession => execute::shell_session(&tool.arguments, workdir).await,
+            Tool::WorkspaceDiff => workspace_diff::workspace_diff(&tool.arguments, workdir).await,
+
+            // Handled specially by the agent
+            Tool::AttemptCompletion
+            | Tool::AskFollowupQuestion
+            | Tool::PlanModeRespond
+            | Tool::ActModeRespond
+            | Tool::FocusChain
+            | Tool::Think => ToolResult::ok(""),
+        }
+    })
+    .await;
+
+    if result.success
+        && matches!(t, Tool::WriteFile | Tool::WriteFileRange | Tool::EditFile | Tool::ApplyPatch | Tool::DeleteFile | Tool::RestoreFile)
+    {
+        workspace_diff::record_tool_edits(workdir, &tool.name, &tool.arguments);
+    }
+    if result.success {
+        working_set::record_tool_call(workdir, &tool.name, &tool.arguments);
+    }
     
     let elapsed = start.elapsed();
+    crate::profiling::record(&format!("tool:{}", tool.name), elapsed);
     if elapsed.as_millis() > 100 {
         tracing::info!("⏱ Tool {} completed in {:?}", tool.name, elapsed);
     } else {
@@ -418,6 +579,12 @@ fn make_approval_summary(tool: &ToolCall) -> String {
                 .unwrap_or("<unknown>");
             format!("Edit {}", path)
         }
+        "write_file_range" => {
+            let path = tool.arguments.get("path").and_then(|v| v.as_str()).unwrap_or("<unknown>");
+            let start = tool.arguments.get("start_line").and_then(|v| v.as_u64()).unwrap_or(0);
+            let end = tool.arguments.get("end_line").and_then(|v| v.as_u64()).unwrap_or(0);
+            format!("Replace lines {start}-{end} of {path}")
+        }
         "apply_patch" => "Apply multi-file patch".to_string(),
         "delete_file" => {
             let path = tool.arguments.get("path")
@@ -425,6 +592,10 @@ fn make_approval_summary(tool: &ToolCall) -> String {
                 .unwrap_or("<unknown>");
             format!("Delete {}", path)
         }
+        "restore_file" => match tool.arguments.get("path").and_then(|v| v.as_str()) {
+            Some(path) => format!("Restore {path} from the trash"),
+            None => "List the trash".to_string(),
+        },
         "process" => {
             let action = tool.arguments.get("action").and_then(|v| v.as_str()).unwrap_or("?");
             let pid = tool.arguments.get("pid").and_then(|v| v.as_u64())
@@ -442,6 +613,31 @@ fn make_approval_summary(tool: &ToolCall) -> String {
             let path = tool.arguments.get("path").and_then(|v| v.as_str()).unwrap_or("");
             format!("LSP {} in {}", action, path)
         }
+        "plugins" => {
+            let action = tool.arguments.get("action").and_then(|v| v.as_str()).unwrap_or("?");
+            let name = tool.arguments.get("name").and_then(|v| v.as_str()).unwrap_or("<unknown>");
+            match action {
+                "search" | "list" => format!("{action} plugins"),
+                _ => format!("{action} the {name} plugin"),
+            }
+        }
+        "modify_settings" => {
+            let mut changes: Vec<String> = Vec::new();
+            for s in tool.arguments.get("settings").and_then(|v| v.as_array()).into_iter().flatten() {
+                let key = s.get("key").and_then(|v| v.as_str()).unwrap_or("?");
+                changes.push(format!("{} = {}", key, s.get("value").unwrap_or(&Value::Null)));
+            }
+            for k in tool.arguments.get("keymaps").and_then(|v| v.as_array()).into_iter().flatten() {
+                let key = k.get("key").and_then(|v| v.as_str()).unwrap_or("?");
+                let command = k.get("command").and_then(|v| v.as_str()).unwrap_or("?");
+                changes.push(format!("{} → {}", key, command));
+            }
+            format!("Change IDE settings: {}", changes.join(", "))
+        }
+        "generate_tests" => {
+            let symbol = tool.arguments.get("symbol").and_then(|v| v.as_str()).unwrap_or("<unknown>");
+            format!("Generate and run tests for {}", symbol)
+        }
         _ => format!("Execute tool '{}'", tool.name),
     }
 }
@@ -457,101 +653,67 @@ pub fn definitions(plan_mode: bool) -> Vec<Value> {
                 "properties": {
                     "command": { "type": "string", "description": "The shell command to execute" },
                     "background": { "type": "boolean", "description": "Run in background and return immediately with PID (default: false)" },
-                    "timeout_secs": { "type": "integer", "description": "Optional timeout in seconds (default 120, max 600)" }
+                    "timeout_secs": { "type": "integer", "description": "Optional timeout in seconds (default 120, max 600)" },
+                    "visible": { "type": "boolean", "description": "Run in a terminal the user can see and type into (default: per IDE setting, usually true). Set false for noisy helper commands." }
                 },
                 "required": ["command"]
             }
         }),
-        serde_json::json!({
-            "name": "read_file",
-            "description": "Read the contents of a file",
-            "parameters": {
-                "type": "object",
-                "properties": {
-                    "path": { "type": "string", "description": "Path to the file" },
-                    "start_line": { "type": "integer", "description": "Optional start line (1-indexed)" },
-                    "end_line": { "type": "integer", "description": "Optional end line" }
-                },
-                "required": ["path"]
-            }
-        }),
-        serde_json::json!({
-            "name": "write_file",
-            "description": "Create or overwrite a file with the given content.",
-            "parameters": {
-                "type": "object",
-                "properties": {
-                    "path": { "type": "string", "description": "Path for the file" },
-                    "content": { "type": "string", "description": "Content to write" }
-                },
-                "required": ["path", "content"]
-            }
-        }),
-        serde_json::json!({
-            "name": "edit_file",
-            "description": "Replace an exact string in a file. old_str must match exactly (including whitespace and indentation).",
-            "parameters": {
-                "type": "object",
-                "properties": {
-                    "path": { "type": "string", "description": "Path to the file" },
-                    "old_str": { "type": "string", "description": "Exact text to find and replace" },
-                    "new_str": { "type": "string", "description": "Replacement text" }
-                },
-                "required": ["path", "old_str", "new_str"]
-            }
-        }),
-        serde_json::json!({
-            "name": "apply_patch",
-            "description": "Apply a patch to one or more files. Supports two formats:\n1. V4A format (multi-file): Use 'input' parameter with *** Begin Patch / *** Update File: / *** End Patch markers\n2. Unified diff format (single file): Use 'path' and 'patch' parameters",
-            "parameters": {
-                "type": "object",
-                "properties": {
-                    "input": { "type": "string", "description": "V4A format patch with *** Begin Patch, *** Update File:, - removals, + additions" },
-                    "path": { "type": "string", "description": "Path to file (for unified diff format)" },
-                    "patch": { "type": "string", "description": "Unified diff patch content (for single file)" }
-                }
-            }
-        }),
-        serde_json::json!({
-            "name": "list_files",
-            "description": "List files in a directory",
-            "parameters": {
-                "type": "object",
-                "properties": {
-                    "path": { "type": "string", "description": "Directory path" },
-                    "recursive": { "type": "boolean", "description": "List recursively" }
-                },
-                "required": ["path"]
-            }
-        }),
-        serde_json::json!({
-            "name": "delete_file",
-            "description": "Delete a file or empty directory. Protected paths like .git, node_modules, Cargo.toml cannot be deleted.",
-            "parameters": {
-                "type": "object",
-                "properties": {
-                    "path": { "type": "string", "description": "Path to file or directory to delete" }
-                },
-                "required": ["path"]
-            }
-        }),
+        args::definition::<args::ReadFileArgs>(
+            "read_file",
+            "Read the contents of a file",
+        ),
+        args::definition::<args::WriteFileArgs>(
+            "write_file",
+            &format!(
+                "Create or overwrite a file with the given content. Won't replace an existing file you haven't read unless overwrite is true. Sends the whole file, so don't use it to change existing files over {} lines: use write_file_range or edit_file.",
+                files::LARGE_FILE_LINES
+            ),
+        ),
+        args::definition::<args::WriteFileRangeArgs>(
+            "write_file_range",
+            "Replace lines start_line..end_line of an existing file with content, sending only that range. expected must be the lines' current text; if they moved, the unique match elsewhere is replaced, and if they changed the write is refused and the current lines returned. Prefer this to write_file for large files.",
+        ),
+        args::definition::<args::EditFileArgs>(
+            "edit_file",
+            "Replace an exact string in a file. old_str must match exactly (including whitespace and indentation). Alternatively, give start_line and end_line to replace that line range with new_str.",
+        ),
+        args::definition::<args::ApplyPatchArgs>(
+            "apply_patch",
+            "Apply a patch to one or more files. Supports two formats:\n1. V4A format (multi-file): Use 'input' parameter with *** Begin Patch / *** Update File: / *** End Patch markers. Hunks that match are applied even if others fail; the result lists each hunk, so resend only the failed ones.\n2. Unified diff format (single file): Use 'path' and 'patch' parameters",
+        ),
+        args::definition::<args::ListFilesArgs>(
+            "list_files",
+            "List files in a directory. With recursive, shows the tree pruned to max_tokens: important directories are opened first and long directories end in '… N more'; list a subdirectory to see more of it.",
+        ),
+        args::definition::<args::DeleteFileArgs>(
+            "delete_file",
+            "Delete a file or small directory. It is moved to .forge/trash, so restore_file can bring it back. Protected paths like .git, node_modules, Cargo.toml cannot be deleted.",
+        ),
+        args::definition::<args::RestoreFileArgs>(
+            "restore_file",
+            "Restore a file or directory deleted by delete_file or apply_patch to where it was. Omit path to list what's in the trash.",
+        ),
         serde_json::json!({
             "name": "process",
-            "description": "Manage background processes. Actions: output (read stdout/stderr from PID), status (check if running), kill (terminate by PID).",
+            "description": "Manage background processes. Actions: output (read stdout/stderr from PID), status (check if running), kill (terminate by PID). Processes not started by the agent or IDE need the user's approval and confirm: true.",
             "parameters": {
                 "type": "object",
                 "properties": {
                     "action": { "type": "string", "enum": ["output", "status", "kill"], "description": "Process action to perform" },
                     "pid": { "type": "integer", "description": "Process ID (required for output/kill; omit for status to list all)" },
                     "tail_lines": { "type": "integer", "description": "Lines from end to return for output (default: 100)" },
-                    "force": { "type": "boolean", "description": "Use SIGKILL instead of SIGTERM for kill (default: false)" }
+                    "since": { "type": "integer", "description": "For output: byte offset to read the full persisted log from (0 = start). The result gives the next offset to continue from." },
+                    "force": { "type": "boolean", "description": "Use SIGKILL instead of SIGTERM for kill (default: false)" },
+                    "group": { "type": "boolean", "description": "For kill: also terminate the process's children via its process group / job (default: true)" },
+                    "confirm": { "type": "boolean", "description": "Set only after the user approved killing a process the agent/IDE did not start" }
                 },
                 "required": ["action"]
             }
         }),
         serde_json::json!({
             "name": "port",
-            "description": "Manage ports. Actions: check (is port in use?), wait (block until port accepts connections), kill (terminate process using port).",
+            "description": "Manage ports. Actions: check (is port in use?), wait (block until port accepts connections), kill (terminate process using port; processes not started by the agent or IDE need the user's approval and confirm: true).",
             "parameters": {
                 "type": "object",
                 "properties": {
@@ -560,7 +722,15 @@ pub fn definitions(plan_mode: bool) -> Vec<Value> {
                     "host": { "type": "string", "description": "Host (default: localhost)" },
                     "timeout": { "type": "integer", "description": "Max seconds to wait (for wait action, default: 30)" },
                     "http_check": { "type": "boolean", "description": "Also verify HTTP 2xx/3xx (for wait action)" },
-                    "force": { "type": "boolean", "description": "Use SIGKILL for kill action (default: false)" }
+                    "check": { "type": "string", "enum": ["tcp", "http", "grpc", "command"], "description": "Readiness check for wait action (default: inferred from the options below, else tcp)" },
+                    "path": { "type": "string", "description": "HTTP path for the http check (default: /)" },
+                    "expect_status": { "type": "array", "items": { "type": "integer" }, "description": "Status codes that mean ready, e.g. [200, 404] for servers without a / route (default: any 2xx/3xx)" },
+                    "expect_body": { "type": "string", "description": "Substring the HTTP response body must contain" },
+                    "tls": { "type": "boolean", "description": "Use HTTPS/TLS for http and grpc checks (self-signed certs accepted)" },
+                    "grpc_service": { "type": "string", "description": "Service for the gRPC health protocol check (default: whole server)" },
+                    "probe_command": { "type": "string", "description": "Shell command run in the workspace; ready when it exits 0" },
+                    "force": { "type": "boolean", "description": "Use SIGKILL for kill action (default: false)" },
+                    "confirm": { "type": "boolean", "description": "Set only after the user approved killing a process the agent/IDE did not start" }
                 },
                 "required": ["action", "port"]
             }
@@ -578,47 +748,39 @@ pub fn definitions(plan_mode: bool) -> Vec<Value> {
                 "required": ["query"]
             }
         }),
+        args::definition::<args::GrepArgs>(
+            "grep",
+            "LITERAL text search - use ONLY when you know the exact string to find (specific function name, error message, import statement). Fast but requires exact match.",
+        ),
+        args::definition::<args::GlobArgs>(
+            "glob",
+            "Find files by name/extension pattern. Returns file paths only.",
+        ),
         serde_json::json!({
-            "name": "grep",
-            "description": "LITERAL text search - use ONLY when you know the exact string to find (specific function name, error message, import statement). Fast but requires exact match.",
-            "parameters": {
-                "type": "object",
-                "properties": {
-                    "pattern": { "type": "string", "description": "Exact text or regex pattern" },
-                    "path": { "type": "string", "description": "Directory to search (default: current)" },
-                    "glob": { "type": "string", "description": "File filter, e.g., '*.rs'" },
-                    "case_insensitive": { "type": "boolean", "description": "Ignore case" },
-                    "context": { "type": "integer", "description": "Context lines (0-5)" }
-                },
-                "required": ["pattern"]
-            }
-        }),
-        serde_json::json!({
-            "name": "glob",
-            "description": "Find files by name/extension pattern. Returns file paths only.",
+            "name": "diagnostics",
+            "description": "Get compiler/linter errors and warnings for a file or directory. Use this to check code for errors before or after making changes.",
             "parameters": {
                 "type": "object",
                 "properties": {
-                    "pattern": { "type": "string", "description": "Pattern like '*.rs', '**/*.test.ts'" },
-                    "path": { "type": "string", "description": "Base directory" }
+                    "path": { "type": "string", "description": "File or directory to check. For directories, runs the appropriate build tool (cargo check, tsc, etc.)" }
                 },
-                "required": ["pattern"]
+                "required": ["path"]
             }
         }),
         serde_json::json!({
-            "name": "diagnostics",
-            "description": "Get compiler/linter errors and warnings for a file or directory. Use this to check code for errors before or after making changes.",
+            "name": "audit_dependencies",
+            "description": "Audit project dependencies for known vulnerabilities and summarize their licenses. Uses cargo audit / npm audit / pip-audit when installed, otherwise queries the OSV database. Use for 'are my deps safe to ship' questions.",
             "parameters": {
                 "type": "object",
                 "properties": {
-                    "path": { "type": "string", "description": "File or directory to check. For directories, runs the appropriate build tool (cargo check, tsc, etc.)" }
-                },
-                "required": ["path"]
+                    "path": { "type": "string", "description": "Project directory (default: workspace root)" },
+                    "licenses": { "type": "boolean", "description": "Include a license summary (default: true)" }
+                }
             }
         }),
         serde_json::json!({
             "name": "lsp",
-            "description": "Language server operations — 100% accurate code intelligence from the IDE's LSP client. Actions: definition (jump to exact definition), references (find all usages), hover (get type info and docs), rename (atomically rename symbol everywhere).",
+            "description": "Language server operations — 100% accurate code intelligence from the IDE's LSP client. Actions: definition (jump to exact definition), references (find all usages), hover (get type info and docs), rename (atomically rename symbol everywhere; without a language server, falls back to a tree-sitter rename of matching identifiers and returns its diff).",
             "parameters": {
                 "type": "object",
                 "properties": {
@@ -631,6 +793,21 @@ pub fn definitions(plan_mode: bool) -> Vec<Value> {
                 "required": ["action", "path", "line", "column"]
             }
         }),
+        serde_json::json!({
+            "name": "generate_tests",
+            "description": "Generate a test file for a symbol, run it, and fix failing tests automatically (up to max_retries rounds). Collects the symbol's definition and references as context. Writes the test file and runs the test command.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "symbol": { "type": "string", "description": "Function, method, or type to test" },
+                    "path": { "type": "string", "description": "Optional: directory to search for the symbol" },
+                    "test_path": { "type": "string", "description": "Optional: where to write the tests (default: language convention)" },
+                    "test_command": { "type": "string", "description": "Optional: command that runs the generated tests" },
+                    "max_retries": { "type": "integer", "description": "Fix-up rounds after the first attempt (default 2, max 5)" }
+                },
+                "required": ["symbol"]
+            }
+        }),
         serde_json::json!({
             "name": "workspace_symbols",
             "description": "Search for symbols (functions, classes, variables) across the entire workspace using LSP.",
@@ -667,6 +844,10 @@ pub fn definitions(plan_mode: bool) -> Vec<Value> {
                 "required": ["diagram_code"]
             }
         }),
+        args::definition::<args::OpenInEditorArgs>(
+            "open_in_editor",
+            "Show the user a file in the IDE: open it at a line, select it in the file explorer, or open its uncommitted changes in a diff tab. Use it to point at what you are talking about (a failing test, the function you changed) instead of only naming the path.",
+        ),
         serde_json::json!({
             "name": "list_run_configs",
             "description": "List all available run configurations detected from the project. This automatically finds npm/yarn scripts, cargo bins, python modules, go packages, and other runnable targets. Use this BEFORE running a project to see what's available.",
@@ -681,9 +862,9 @@ pub fn definitions(plan_mode: bool) -> Vec<Value> {
             "parameters": {
                 "type": "object",
                 "properties": {
-                    "config_name": { "type": "string", "description": "Name of detected run configuration (e.g., 'npm run dev', 'cargo run', 'python main.py'). Get this from list_run_configs()." },
+                    "config_name": { "type": "string", "description": "Name of a detected, user or compound run configuration (e.g., 'npm run dev', 'cargo run', 'python main.py'). Get this from list_run_configs(). A compound launches all of its members, each in its own terminal; a config's depends-on tasks run first." },
                     "command": { "type": "string", "description": "Custom command to run if config_name not provided. Use config_name when possible." },
-                    "mode": { "type": "string", "enum": ["run", "debug"], "description": "Run mode: 'run' for normal execution, 'debug' to enable breakpoints. Default: 'run'" }
+                    "mode": { "type": "string", "enum": ["run", "debug"], "description": "Run mode: 'run' for normal execution, 'debug' to enable breakpoints. Debug configs are generated automatically for cargo/go run (lldb), python scripts (debugpy) and node (--inspect). Default: 'run'" }
                 }
             }
         }),
@@ -693,7 +874,41 @@ pub fn definitions(plan_mode: bool) -> Vec<Value> {
             "parameters": {
                 "type": "object",
                 "properties": {
-                    "config_name": { "type": "string", "description": "Name of the run configuration to stop. If not provided, stops the most recent one." }
+                    "config_name": { "type": "string", "description": "Name of the run configuration to stop. A compound name stops all of its members. If not provided, stops the most recent one." }
+                }
+            }
+        }),
+        serde_json::json!({
+            "name": "read_run_output",
+            "description": "Read the recent terminal output of a project started with run_project (or from the IDE's run menu), and whether it is still running or its exit code. Use it to check that a server started, or to diagnose why it crashed.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "config_name": { "type": "string", "description": "Name of the run configuration (as passed to run_project). Optional when only one run has been started." },
+                    "tail_lines": { "type": "integer", "description": "Number of trailing lines to return (default: 100, 0 for all captured output)" }
+                }
+            }
+        }),
+        serde_json::json!({
+            "name": "shell_session",
+            "description": "Run commands in a persistent shell that keeps its state between calls: cd, activated virtualenvs (source .venv/bin/activate), exported env vars and shell functions carry over. Each session is a visible terminal. Use it for multi-step setups; use run for one-off commands.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["run", "read", "interrupt", "close", "list"], "description": "run (default): run command; read: check on a command that outlived its timeout; interrupt: send Ctrl-C; close: end the session; list: show sessions" },
+                    "session": { "type": "string", "description": "Session name (default: \"default\"). Started on first run." },
+                    "command": { "type": "string", "description": "Command to run (action run)" },
+                    "timeout_secs": { "type": "integer", "description": "Seconds to wait for the command (default: 120, max: 600). A command still running stays in the session; check it with action read." }
+                }
+            }
+        }),
+        serde_json::json!({
+            "name": "workspace_diff",
+            "description": "List files added, modified or deleted in the workspace since the user's last message, marked agent (written by your edit tools) or external (the user, other programs, or commands you ran). Use it to notice edits the user made between messages before building on stale file contents.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "scope": { "type": "string", "enum": ["turn", "last_turn"], "description": "turn (default): changes since the user's last message; last_turn: changes during the previous turn and before the last message" }
                 }
             }
         }),
@@ -742,6 +957,18 @@ pub fn definitions(plan_mode: bool) -> Vec<Value> {
                 "required": ["operation"]
             }
         }),
+        serde_json::json!({
+            "name": "review",
+            "description": "Review code changes and report structured findings (severity, file, line, suggestion). Use when the user asks to review their changes, a commit, or a branch. Defaults to unstaged working tree changes.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "range": { "type": "string", "description": "Git range or commit to review, e.g. 'main..HEAD' or 'abc123'. Overrides 'staged'." },
+                    "staged": { "type": "boolean", "description": "Review staged changes instead of the working tree (default: false)" },
+                    "path": { "type": "string", "description": "Optional: limit the review to a file or directory" }
+                }
+            }
+        }),
         serde_json::json!({
             "name": "sdk_manager",
             "description": "Manage development tools and runtimes (Node.js, Python, Rust, Go, etc.) via proto. Better than raw commands - handles cross-platform installation, version management, and project detection automatically. Operations: install, list_installed, list_available, detect_project, uninstall, versions.",
@@ -769,28 +996,34 @@ pub fn definitions(plan_mode: bool) -> Vec<Value> {
                 "required": ["operation"]
             }
         }),
-        serde_json::json!({
-            "name": "ask_followup_question",
-            "description": "Ask the user a clarifying question",
-            "parameters": {
-                "type": "object",
-                "properties": {
-                    "question": { "type": "string", "description": "The question to ask" }
-                },
-                "required": ["question"]
-            }
-        }),
+        args::definition::<args::PluginsArgs>(
+            "plugins",
+            "Find and manage the IDE's plugins, e.g. to add language support when setting up a project. search the registry or list installed plugins, then install, remove, enable or disable one by its author.name id (the user approves each change).",
+        ),
+        args::definition::<args::ModifySettingsArgs>(
+            "modify_settings",
+            "Change the IDE's own settings (settings.toml) or add key bindings (keymaps.toml), e.g. the color theme, font size or line numbers. Keys and values are checked against the known settings and installed themes; use preview to show the diff without writing. The user approves each change.",
+        ),
+        args::definition::<args::AskFollowupQuestionArgs>(
+            "ask_followup_question",
+            "Ask the user a clarifying question and wait for the answer. For yes/no or pick-one questions, pass `options` so they can answer with a click.",
+        ),
         serde_json::json!({
             "name": "think",
-            "description": "Write out your reasoning or thoughts about the current task",
+            "description": "Write out your reasoning or thoughts about the current task. Thoughts are kept as working notes the user can review, so record decisions, assumptions and open questions with their kind.",
             "parameters": {
                 "type": "object",
                 "properties": {
-                    "thought": { "type": "string", "description": "Your reasoning or thoughts" }
+                    "thought": { "type": "string", "description": "Your reasoning or thoughts" },
+                    "kind": { "type": "string", "enum": ["thought", "decision", "assumption", "question"], "description": "What the note is (default: thought)" }
                 },
                 "required": ["thought"]
             }
         }),
+        args::definition::<args::FocusChainArgs>(
+            "focus_chain",
+            "Keep a task list for multi-step work: 'set' it when you start, mark one task 'in_progress' at a time and 'done' when finished. The user sees it as the plan.",
+        ),
         serde_json::json!({
             "name": "attempt_completion",
             "description": "Signal task completion with a result message",
@@ -808,9 +1041,17 @@ pub fn definitions(plan_mode: bool) -> Vec<Value> {
     if plan_mode {
         tools.retain(|t| {
             let name = t["name"].as_str().unwrap_or("");
-            !matches!(name, "run" | "write_file" | "edit_file" | "apply_patch" | "delete_file")
+            !matches!(
+                name,
+                "run" | "write_file" | "write_file_range" | "edit_file" | "apply_patch" | "delete_file" | "restore_file" | "generate_tests"
+            )
         });
     }
 
+    // Plugin tools can do anything, so they're left out of plan mode too
+    if !plan_mode {
+        tools.extend(plugin::definitions());
+    }
+
     tools
 }
@@ -0,0 +1,185 @@
+//! Registry of processes started by the agent or the IDE.
+//!
+//! `process` kill and `port` kill only act freely on processes in this
+//! registry (or their descendants, e.g. the `node` spawned by `npm run dev`).
+//! Anything else belongs to the user, and is handled per
+//! `FORGE_PROCESS_POLICY`:
+//! - `confirm` (default): refuse until the call is retried with `confirm: true`
+//!   after asking the user
+//! - `refuse`: never touch foreign processes
+//! - `allow`: previous behaviour, no checks
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use super::platform::{parent_pid, process_command};
+use super::ToolResult;
+
+/// Who started a registered process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessOrigin {
+    /// Agent tool calls (run / execute_background).
+    Agent,
+    /// IDE run configurations.
+    Ide,
+}
+
+impl ProcessOrigin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Agent => "agent",
+            Self::Ide => "ide",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OwnedProcess {
+    pub pid: u32,
+    pub command: String,
+    /// Port the process was seen listening on, if any.
+    pub port: Option<u16>,
+    pub origin: ProcessOrigin,
+    pub started_at: Instant,
+}
+
+/// What to do with processes outside the registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForeignProcessPolicy {
+    Confirm,
+    Refuse,
+    Allow,
+}
+
+impl ForeignProcessPolicy {
+    pub fn from_env() -> Self {
+        match crate::config::var("FORGE_PROCESS_POLICY").as_deref() {
+            Some("refuse") => Self::Refuse,
+            Some("allow") => Self::Allow,
+            _ => Self::Confirm,
+        }
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<u32, OwnedProcess>> {
+    static INSTANCE: OnceLock<Mutex<HashMap<u32, OwnedProcess>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Record a process started by the agent or the IDE.
+pub fn register(pid: u32, command: &str, origin: ProcessOrigin) {
+    if pid == 0 {
+        return;
+    }
+    registry().lock().unwrap().insert(
+        pid,
+        OwnedProcess {
+            pid,
+            command: command.to_string(),
+            port: None,
+            origin,
+            started_at: Instant::now(),
+        },
+    );
+}
+
+/// Forget a process once it has exited (its PID may be reused).
+pub fn unregister(pid: u32) {
+    registry().lock().unwrap().remove(&pid);
+}
+
+/// Remember that an owned process (or a descendant) listens on `port`.
+pub fn note_port(pid: u32, port: u16) {
+    let Some(owner) = owner_of(pid) else {
+        return;
+    };
+    if let Some(entry) = registry().lock().unwrap().get_mut(&owner.pid) {
+        entry.port = Some(port);
+    }
+}
+
+/// All registered processes, oldest first.
+pub fn list() -> Vec<OwnedProcess> {
+    let mut procs: Vec<OwnedProcess> = registry().lock().unwrap().values().cloned().collect();
+    procs.sort_by_key(|p| p.started_at);
+    procs
+}
+
+/// The registered process `pid` is, or descends from.
+pub fn owner_of(pid: u32) -> Option<OwnedProcess> {
+    let mut current = pid;
+    // Bounded walk up the process tree
+    for _ in 0..32 {
+        if let Some(owned) = registry().lock().unwrap().get(&current) {
+            return Some(owned.clone());
+        }
+        match parent_pid(current) {
+            Some(ppid) if ppid > 1 && ppid != current => current = ppid,
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Check whether the agent may signal `pid`.
+///
+/// Returns `Err` with the tool result to send back when the process is not
+/// owned and the policy doesn't allow touching it. `description` says what
+/// the call wants to do (e.g. "kill PID 123 on port 3000").
+pub fn check_foreign(
+    tool_name: &str,
+    pid: u32,
+    description: &str,
+    confirmed: bool,
+) -> Result<(), ToolResult> {
+    if owner_of(pid).is_some() {
+        return Ok(());
+    }
+    let command = process_command(pid)
+        .map(|c| format!(" ({})", c))
+        .unwrap_or_default();
+    match ForeignProcessPolicy::from_env() {
+        ForeignProcessPolicy::Allow => Ok(()),
+        ForeignProcessPolicy::Confirm if confirmed => Ok(()),
+        ForeignProcessPolicy::Confirm => Err(ToolResult::awaiting_approval(
+            tool_name,
+            &format!(
+                "{description}{command}, which was not started by the agent or the IDE. \
+                 Ask the user first, then retry with confirm: true."
+            ),
+        )),
+        ForeignProcessPolicy::Refuse => Err(ToolResult::err(format!(
+            "Refusing to {description}{command}: it was not started by the agent or the IDE \
+             (FORGE_PROCESS_POLICY=refuse). Ask the user to stop it."
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_and_descendants() {
+        let me = std::process::id();
+        register(me, "cargo test", ProcessOrigin::Agent);
+        assert_eq!(owner_of(me).unwrap().command, "cargo test");
+        assert!(check_foreign("process", me, "kill", false).is_ok());
+
+        // A child of a registered process is owned through its parent
+        #[cfg(unix)]
+        {
+            let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+            let owner = owner_of(child.id()).unwrap();
+            assert_eq!(owner.pid, me);
+            note_port(child.id(), 3000);
+            assert_eq!(list().iter().find(|p| p.pid == me).unwrap().port, Some(3000));
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        unregister(me);
+        assert!(owner_of(me).is_none());
+    }
+}
@@ -0,0 +1,309 @@
+//! OS-specific process and port helpers for the process/port tools.
+//!
+//! - Unix: `lsof` for port owners, `kill` for signals (to a PID or a whole
+//!   process group), `ps` for the process tree
+//! - Windows: `netstat -ano` for port owners, `taskkill` for termination,
+//!   a ToolHelp snapshot for the process tree, and Job Objects so a
+//!   background command can be terminated together with its children
+
+use tokio::process::Command;
+
+/// PIDs listening on (or connected via) local `port`.
+#[cfg(not(windows))]
+pub async fn port_pids(port: u16) -> Result<Vec<u32>, String> {
+    let output = Command::new("lsof")
+        .args(["-i", &format!(":{port}"), "-t"])
+        .output()
+        .await
+        .map_err(|e| format!("lsof failed: {e}"))?;
+    let mut pids: Vec<u32> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| l.trim().parse().ok())
+        .collect();
+    pids.dedup();
+    Ok(pids)
+}
+
+/// PIDs listening on local `port`.
+#[cfg(windows)]
+pub async fn port_pids(port: u16) -> Result<Vec<u32>, String> {
+    let output = Command::new("netstat")
+        .args(["-ano", "-p", "tcp"])
+        .output()
+        .await
+        .map_err(|e| format!("netstat failed: {e}"))?;
+    Ok(parse_netstat(&String::from_utf8_lossy(&output.stdout), port))
+}
+
+/// Parse `netstat -ano` output for the PIDs listening on `port`.
+///
+/// Lines look like `  TCP    0.0.0.0:3000    0.0.0.0:0    LISTENING    1234`
+/// (IPv6 local addresses are bracketed: `[::]:3000`).
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_netstat(output: &str, port: u16) -> Vec<u32> {
+    let suffix = format!(":{port}");
+    let mut pids = Vec::new();
+    for line in output.lines() {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        let [proto, local, _remote, state, pid] = cols[..] else {
+            continue;
+        };
+        if !proto.eq_ignore_ascii_case("tcp") || state != "LISTENING" || !local.ends_with(&suffix) {
+            continue;
+        }
+        if let Ok(pid) = pid.parse::<u32>() {
+            if pid != 0 && !pids.contains(&pid) {
+                pids.push(pid);
+            }
+        }
+    }
+    pids
+}
+
+/// Ask `pid` to terminate (SIGTERM), or kill it outright with `force`.
+#[cfg(not(windows))]
+pub async fn terminate(pid: u32, force: bool) -> Result<(), String> {
+    let signal = if force { "-9" } else { "-15" };
+    let output = Command::new("kill")
+        .arg(signal)
+        .arg(pid.to_string())
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Terminate `pid` and its child processes. Without `force`, windowed apps
+/// get a close request first.
+#[cfg(windows)]
+pub async fn terminate(pid: u32, force: bool) -> Result<(), String> {
+    let pid_str = pid.to_string();
+    let mut args = vec!["/PID", pid_str.as_str(), "/T"];
+    if force {
+        args.push("/F");
+    }
+    let output = Command::new("taskkill")
+        .args(&args)
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+    // Console processes ignore the polite request; fall back to /F
+    if !output.status.success() && !force {
+        return Box::pin(terminate(pid, true)).await;
+    }
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Terminate the process group led by `pgid` (the process and everything it
+/// spawned that didn't start its own group).
+#[cfg(not(windows))]
+pub async fn terminate_group(pgid: u32, force: bool) -> Result<(), String> {
+    let signal = if force { "-9" } else { "-15" };
+    let output = Command::new("kill")
+        .args([signal, "--", &format!("-{pgid}")])
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// `taskkill /T` already walks the process tree.
+#[cfg(windows)]
+pub async fn terminate_group(pgid: u32, force: bool) -> Result<(), String> {
+    terminate(pgid, force).await
+}
+
+/// [`terminate_group`] for callers without a runtime, e.g. at shutdown.
+#[cfg(not(windows))]
+pub fn terminate_group_blocking(pgid: u32, force: bool) -> Result<(), String> {
+    let signal = if force { "-9" } else { "-15" };
+    let output = std::process::Command::new("kill")
+        .args([signal, "--", &format!("-{pgid}")])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(windows)]
+pub fn terminate_group_blocking(pgid: u32, force: bool) -> Result<(), String> {
+    let pid = pgid.to_string();
+    let mut args = vec!["/PID", pid.as_str(), "/T"];
+    if force {
+        args.push("/F");
+    }
+    let output = std::process::Command::new("taskkill").args(&args).output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Name of the signal `terminate` sends, for messages.
+pub fn terminate_name(force: bool) -> &'static str {
+    match (cfg!(windows), force) {
+        (true, _) => "taskkill",
+        (false, true) => "SIGKILL",
+        (false, false) => "SIGTERM",
+    }
+}
+
+/// Parent PID of `pid`.
+#[cfg(not(windows))]
+pub fn parent_pid(pid: u32) -> Option<u32> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "ppid=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Command line of `pid`, shortened for messages.
+#[cfg(not(windows))]
+pub fn process_command(pid: u32) -> Option<String> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "command=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let command = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!command.is_empty()).then(|| command.chars().take(80).collect())
+}
+
+#[cfg(windows)]
+pub fn parent_pid(pid: u32) -> Option<u32> {
+    win::process_entry(pid).map(|(ppid, _)| ppid)
+}
+
+#[cfg(windows)]
+pub fn process_command(pid: u32) -> Option<String> {
+    win::process_entry(pid).map(|(_, exe)| exe)
+}
+
+#[cfg(windows)]
+pub use win::JobObject;
+
+#[cfg(windows)]
+mod win {
+    use std::mem::size_of;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
+    };
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    /// (parent pid, executable name) of `pid`.
+    pub fn process_entry(pid: u32) -> Option<(u32, String)> {
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+            if snapshot == INVALID_HANDLE_VALUE {
+                return None;
+            }
+            let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+            entry.dwSize = size_of::<PROCESSENTRY32W>() as u32;
+            let mut found = None;
+            let mut ok = Process32FirstW(snapshot, &mut entry) != 0;
+            while ok {
+                if entry.th32ProcessID == pid {
+                    let len = entry.szExeFile.iter().position(|&c| c == 0).unwrap_or(0);
+                    let exe = String::from_utf16_lossy(&entry.szExeFile[..len]);
+                    found = Some((entry.th32ParentProcessID, exe));
+                    break;
+                }
+                ok = Process32NextW(snapshot, &mut entry) != 0;
+            }
+            CloseHandle(snapshot);
+            found
+        }
+    }
+
+    /// A Job Object holding a background command and everything it spawns.
+    /// Dropping it (e.g. when the agent exits) kills the whole tree.
+    pub struct JobObject(HANDLE);
+
+    // The handle is only used through thread-safe Win32 calls
+    unsafe impl Send for JobObject {}
+    unsafe impl Sync for JobObject {}
+
+    impl JobObject {
+        /// Create a job and assign the process `handle` to it.
+        pub fn assign(handle: HANDLE) -> Option<Self> {
+            unsafe {
+                let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+                if job.is_null() {
+                    return None;
+                }
+                let job = Self(job);
+                let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+                info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                let set = SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const _,
+                    size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                );
+                if set == 0 || AssignProcessToJobObject(job.0, handle) == 0 {
+                    return None;
+                }
+                Some(job)
+            }
+        }
+
+        /// Terminate every process in the job.
+        pub fn terminate(&self) -> bool {
+            unsafe { TerminateJobObject(self.0, 1) != 0 }
+        }
+    }
+
+    impl Drop for JobObject {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netstat() {
+        let output = "\
+Active Connections
+
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1040
+  TCP    0.0.0.0:3000           0.0.0.0:0              LISTENING       8120
+  TCP    127.0.0.1:3000         127.0.0.1:52144        ESTABLISHED     8120
+  TCP    127.0.0.1:52144        127.0.0.1:3000         ESTABLISHED     9001
+  TCP    [::]:3000              [::]:0                 LISTENING       8120
+  TCP    0.0.0.0:30000          0.0.0.0:0              LISTENING       77
+";
+        assert_eq!(parse_netstat(output, 3000), vec![8120]);
+        assert_eq!(parse_netstat(output, 135), vec![1040]);
+        assert!(parse_netstat(output, 8080).is_empty());
+    }
+}
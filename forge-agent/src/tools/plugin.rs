@@ -0,0 +1,104 @@
+//! Agent tools contributed by plugins.
+//!
+//! A Volt plugin registers a tool (name, description, JSON schema) through
+//! the plugin catalog; it then shows up in [`super::definitions`] and calls
+//! to it come back as `PENDING_IDE_EXECUTION` so the proxy can forward them
+//! to the plugin that owns it. Built-in tool names can't be taken over.
+
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginTool {
+    pub name: String,
+    pub description: String,
+    /// JSON schema of the arguments, same shape as the built-in definitions.
+    pub parameters: Value,
+    /// The registering plugin (its catalog plugin id).
+    pub owner: u64,
+}
+
+fn registry() -> &'static RwLock<BTreeMap<String, PluginTool>> {
+    static REGISTRY: OnceLock<RwLock<BTreeMap<String, PluginTool>>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Register (or re-register) a plugin tool.
+pub fn register(tool: PluginTool) -> Result<(), String> {
+    if tool.name.is_empty() || !tool.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("invalid tool name `{}`: use letters, digits, '_' and '-'", tool.name));
+    }
+    if super::Tool::from_name(&tool.name).is_some() {
+        return Err(format!("`{}` is a built-in tool", tool.name));
+    }
+    if !tool.parameters.is_object() {
+        return Err(format!("parameters of `{}` must be a JSON schema object", tool.name));
+    }
+    let mut tools = registry().write().unwrap();
+    if let Some(existing) = tools.get(&tool.name) {
+        if existing.owner != tool.owner {
+            return Err(format!("`{}` is already registered by another plugin", tool.name));
+        }
+    }
+    tools.insert(tool.name.clone(), tool);
+    Ok(())
+}
+
+/// Drop every tool registered by `owner` (the plugin stopped or reloaded).
+pub fn unregister_owner(owner: u64) {
+    registry().write().unwrap().retain(|_, t| t.owner != owner);
+}
+
+pub fn get(name: &str) -> Option<PluginTool> {
+    registry().read().unwrap().get(name).cloned()
+}
+
+pub fn is_registered(name: &str) -> bool {
+    registry().read().unwrap().contains_key(name)
+}
+
+/// Definitions of all plugin tools, for the model.
+pub fn definitions() -> Vec<Value> {
+    registry()
+        .read()
+        .unwrap()
+        .values()
+        .map(|t| {
+            serde_json::json!({
+                "name": t.name,
+                "description": t.description,
+                "parameters": t.parameters,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str, owner: u64) -> PluginTool {
+        PluginTool {
+            name: name.to_string(),
+            description: "Look up a ticket".to_string(),
+            parameters: json!({ "type": "object", "properties": { "id": { "type": "string" } }, "required": ["id"] }),
+            owner,
+        }
+    }
+
+    #[test]
+    fn test_register_and_unregister() {
+        register(tool("test_ticket_lookup", 7)).unwrap();
+        assert!(is_registered("test_ticket_lookup"));
+        assert!(super::super::definitions(false).iter().any(|d| d["name"] == "test_ticket_lookup"));
+        assert!(register(tool("test_ticket_lookup", 8)).unwrap_err().contains("another plugin"));
+        assert!(register(tool("read_file", 7)).unwrap_err().contains("built-in"));
+        assert!(register(tool("bad name", 7)).is_err());
+
+        unregister_owner(7);
+        assert!(!is_registered("test_ticket_lookup"));
+    }
+}
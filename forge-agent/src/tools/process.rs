@@ -3,6 +3,9 @@
 //! These tools enable handling of long-running processes (dev servers, watchers)
 //! without blocking the agent or hitting timeouts.
 
+use super::ownership::{self, ProcessOrigin};
+use super::platform;
+use super::process_log::{self, ProcessLog};
 use super::ToolResult;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -25,12 +28,19 @@ fn background_processes() -> &'static Arc<Mutex<HashMap<u32, BackgroundProcess>>
     INSTANCE.get_or_init(|| Arc::new(Mutex::new(HashMap::new())))
 }
 
+type SharedLog = Arc<std::sync::Mutex<ProcessLog>>;
+
 /// A background process with output capture.
 struct BackgroundProcess {
     pid: u32,
     command: String,
     started_at: Instant,
     output_buffer: Arc<Mutex<String>>,
+    /// Full output on disk under `.forge/logs` (None if it couldn't be created).
+    log: Option<SharedLog>,
+    /// Job Object holding the process and its children.
+    #[cfg(windows)]
+    job: Option<platform::JobObject>,
     /// Handle to the child process (None if already reaped).
     child: Option<Child>,
 }
@@ -58,21 +68,34 @@ pub async fn execute_background(args: &Value, workdir: &Path) -> ToolResult {
         .and_then(|v| v.as_u64())
         .unwrap_or(5_000); // 5 s cap; exits immediately on first output
 
-    // Spawn the process
-    let mut child = match Command::new("sh")
-        .arg("-c")
+    // Spawn the process in its own process group, so killing it also stops
+    // whatever it spawned (node -> esbuild, cargo -> rustc)
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
         .arg(command)
         .current_dir(workdir)
+        .envs(super::sdk_env::project_env(workdir))
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-    {
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    cmd.process_group(0);
+    let mut child = match cmd.spawn() {
         Ok(c) => c,
         Err(e) => return ToolResult::err(format!("Failed to spawn: {e}")),
     };
 
     let pid = child.id().unwrap_or(0);
+    ownership::register(pid, command, ProcessOrigin::Agent);
+    #[cfg(windows)]
+    let job = child.raw_handle().and_then(|handle| platform::JobObject::assign(handle as _));
     let output_buffer = Arc::new(Mutex::new(String::new()));
+    let log: Option<SharedLog> = match ProcessLog::create(workdir, pid, command) {
+        Ok(log) => Some(Arc::new(std::sync::Mutex::new(log))),
+        Err(e) => {
+            tracing::warn!("Failed to create log for PID {pid}: {e}");
+            None
+        }
+    };
 
     // Notification channel: background reader sends a signal when the first
     // line of output (or stderr) is available so we don't have to sleep
@@ -81,12 +104,16 @@ pub async fn execute_background(args: &Value, workdir: &Path) -> ToolResult {
     let first_output_tx = Arc::new(std::sync::Mutex::new(Some(first_output_tx)));
 
     let buffer_clone = output_buffer.clone();
+    let log1 = log.clone();
     let tx1 = first_output_tx.clone();
     let stdout = child.stdout.take();
     tokio::spawn(async move {
         if let Some(stdout) = stdout {
             let mut reader = BufReader::new(stdout).lines();
             while let Ok(Some(line)) = reader.next_line().await {
+                if let Some(log) = &log1 {
+                    log.lock().unwrap().append(format!("{line}\n").as_bytes());
+                }
                 {
                     let mut buf = buffer_clone.lock().await;
                     if buf.len() < MAX_OUTPUT_CHARS * 2 {
@@ -105,12 +132,16 @@ pub async fn execute_background(args: &Value, workdir: &Path) -> ToolResult {
     });
 
     let buffer_clone2 = output_buffer.clone();
+    let log2 = log.clone();
     let tx2 = first_output_tx.clone();
     let stderr = child.stderr.take();
     tokio::spawn(async move {
         if let Some(stderr) = stderr {
             let mut reader = BufReader::new(stderr).lines();
             while let Ok(Some(line)) = reader.next_line().await {
+                if let Some(log) = &log2 {
+                    log.lock().unwrap().append(format!("[stderr] {line}\n").as_bytes());
+                }
                 {
                     let mut buf = buffer_clone2.lock().await;
                     if buf.len() < MAX_OUTPUT_CHARS * 2 {
@@ -145,6 +176,9 @@ pub async fn execute_background(args: &Value, workdir: &Path) -> ToolResult {
         command: command.to_string(),
         started_at: Instant::now(),
         output_buffer: output_buffer.clone(),
+        log: log.clone(),
+        #[cfg(windows)]
+        job,
         child: Some(child),
     };
 
@@ -157,10 +191,16 @@ pub async fn execute_background(args: &Value, workdir: &Path) -> ToolResult {
         buf[..len].to_string()
     };
 
+    let log_line = log
+        .as_ref()
+        .map(|l| format!("Log: .forge/logs/{}.*.log\n", l.lock().unwrap().stem()))
+        .unwrap_or_default();
+
     ToolResult::ok(format!(
         "Process started in background.\n\
          PID: {pid}\n\
          Running: {is_running}\n\
+         {log_line}\
          --- Initial output ---\n\
          {initial_output}"
     ))
@@ -172,7 +212,9 @@ pub async fn execute_background(args: &Value, workdir: &Path) -> ToolResult {
 /// - pid: Process ID to read output from
 /// - tail_lines: Number of lines from the end (default: 100)
 /// - follow_seconds: Seconds to wait for new output (default: 0)
-pub async fn read_process_output(args: &Value, _workdir: &Path) -> ToolResult {
+/// - since: Byte offset to read the full on-disk log from, instead of the
+///   in-memory tail. The result includes the offset to continue from.
+pub async fn read_process_output(args: &Value, workdir: &Path) -> ToolResult {
     let Some(pid) = args.get("pid").and_then(|v| v.as_u64()).map(|p| p as u32) else {
         return ToolResult::err("Missing 'pid' parameter");
     };
@@ -200,6 +242,15 @@ pub async fn read_process_output(args: &Value, _workdir: &Path) -> ToolResult {
         ));
     };
 
+    if let Some(since) = args.get("since").and_then(|v| v.as_u64()) {
+        let Some(log) = &proc.log else {
+            return ToolResult::err(format!("No on-disk log for PID {pid}"));
+        };
+        let stem = log.lock().unwrap().stem().to_string();
+        let status = if proc.child.is_some() { "running" } else { "exited" };
+        return log_result(workdir, &stem, since, status);
+    }
+
     let output = {
         let buf = proc.output_buffer.lock().await;
         let lines: Vec<&str> = buf.lines().collect();
@@ -228,6 +279,27 @@ pub async fn read_process_output(args: &Value, _workdir: &Path) -> ToolResult {
     ))
 }
 
+/// Format a page of a process log, with the offset to continue from.
+pub fn log_result(workdir: &Path, stem: &str, since: u64, status: &str) -> ToolResult {
+    match process_log::read(workdir, stem, since, MAX_OUTPUT_CHARS) {
+        Ok(chunk) => {
+            let skipped = if chunk.start > since {
+                format!(" (bytes {since}..{} were rotated out)", chunk.start)
+            } else {
+                String::new()
+            };
+            let more = if chunk.more { "more output available" } else { "end of log" };
+            ToolResult::ok(format!(
+                "Status: {status} | Bytes {}..{}{skipped} | {more}\n\
+                 Next offset: {}\n\
+                 --- Output ---\n{}",
+                chunk.start, chunk.next, chunk.next, chunk.text
+            ))
+        }
+        Err(e) => ToolResult::err(format!("Failed to read log {stem}: {e}")),
+    }
+}
+
 /// Check status of background processes.
 ///
 /// Args:
@@ -268,12 +340,28 @@ pub async fn check_process_status(args: &Value, _workdir: &Path) -> ToolResult {
         ));
     }
 
+    // Processes started elsewhere (IDE run configs, agent terminals)
+    let others: Vec<_> = ownership::list()
+        .into_iter()
+        .filter(|p| !processes.contains_key(&p.pid))
+        .collect();
+
     // Return all processes
-    if processes.is_empty() {
+    if processes.is_empty() && others.is_empty() {
         return ToolResult::ok("No background processes running.");
     }
 
     let mut lines = vec!["Active background processes:".to_string()];
+    for proc in &others {
+        let cmd_preview: String = proc.command.chars().take(50).collect();
+        let port = proc.port.map(|p| format!(" | port {p}")).unwrap_or_default();
+        lines.push(format!(
+            "  PID {}: running ({}){port} | {:.1}s | {cmd_preview}",
+            proc.pid,
+            proc.origin.as_str(),
+            proc.started_at.elapsed().as_secs_f64()
+        ));
+    }
     for (pid, proc) in processes.iter_mut() {
         let is_running = if let Some(ref mut child) = proc.child {
             child.try_wait().map(|s| s.is_none()).unwrap_or(false)
@@ -296,26 +384,52 @@ pub async fn check_process_status(args: &Value, _workdir: &Path) -> ToolResult {
 /// Args:
 /// - pid: Process ID to kill
 /// - force: Use SIGKILL instead of SIGTERM (default: false)
+/// - confirm: The user approved killing a process the agent didn't start
 pub async fn kill_process(args: &Value, _workdir: &Path) -> ToolResult {
     let Some(pid) = args.get("pid").and_then(|v| v.as_u64()).map(|p| p as u32) else {
         return ToolResult::err("Missing 'pid' parameter");
     };
 
     let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+    let confirmed = args.get("confirm").and_then(|v| v.as_bool()).unwrap_or(false);
+    // Kill the process's whole group (its children) unless told otherwise
+    let group = args.get("group").and_then(|v| v.as_bool()).unwrap_or(true);
 
     let mut processes = background_processes().lock().await;
 
     if let Some(proc) = processes.get_mut(&pid) {
+        // The job takes the children down with the process
+        #[cfg(windows)]
+        if let (true, Some(job)) = (group, &proc.job) {
+            if job.terminate() {
+                if let Some(mut child) = proc.child.take() {
+                    let _ = child.wait().await;
+                }
+                ownership::unregister(pid);
+                return ToolResult::ok(format!("Process {pid} and its children terminated."));
+            }
+        }
         if let Some(ref mut child) = proc.child {
-            let result = if force {
+            let result = if group {
+                // Background commands lead their own group (see execute_background)
+                match platform::terminate_group(pid, force).await {
+                    Ok(()) => {
+                        if !force {
+                            tokio::time::sleep(Duration::from_millis(500)).await;
+                        }
+                        // Stragglers ignoring SIGTERM still hold the leader
+                        match child.try_wait() {
+                            Ok(Some(_)) => Ok(()),
+                            _ => child.kill().await,
+                        }
+                    }
+                    Err(_) => child.kill().await,
+                }
+            } else if force {
                 child.kill().await
             } else {
-                // Send SIGTERM via kill command
-                let _ = Command::new("kill")
-                    .arg("-15")
-                    .arg(pid.to_string())
-                    .output()
-                    .await;
+                // Ask politely first
+                let _ = platform::terminate(pid, false).await;
                 // Wait briefly
                 tokio::time::sleep(Duration::from_millis(500)).await;
                 child.try_wait().map(|_| ()).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
@@ -326,7 +440,9 @@ pub async fn kill_process(args: &Value, _workdir: &Path) -> ToolResult {
                     // Wait for exit
                     let _ = child.wait().await;
                     proc.child = None;
-                    return ToolResult::ok(format!("Process {pid} terminated."));
+                    ownership::unregister(pid);
+                    let what = if group { " and its process group" } else { "" };
+                    return ToolResult::ok(format!("Process {pid}{what} terminated."));
                 }
                 Err(e) => {
                     return ToolResult::err(format!("Failed to kill process {pid}: {e}"));
@@ -337,36 +453,50 @@ pub async fn kill_process(args: &Value, _workdir: &Path) -> ToolResult {
         }
     }
 
+    // Untracked PIDs may be the user's own processes
+    if let Err(result) = ownership::check_foreign("process", pid, &format!("kill PID {pid}"), confirmed) {
+        return result;
+    }
+
+    // Agent terminals and IDE run configs start session leaders, so their
+    // group can go too; never signal the group of a foreign process
+    let leader = group && ownership::owner_of(pid).is_some_and(|owner| owner.pid == pid);
+    if leader && platform::terminate_group(pid, force).await.is_ok() {
+        ownership::unregister(pid);
+        return ToolResult::ok(format!(
+            "Sent {} to process group {pid}",
+            platform::terminate_name(force)
+        ));
+    }
+
     // Try system kill for non-tracked processes
-    let signal = if force { "-9" } else { "-15" };
-    let result = Command::new("kill")
-        .arg(signal)
-        .arg(pid.to_string())
-        .output()
-        .await;
-
-    match result {
-        Ok(output) if output.status.success() => {
-            ToolResult::ok(format!("Sent {} to PID {pid}", if force { "SIGKILL" } else { "SIGTERM" }))
-        }
-        Ok(output) => {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            ToolResult::err(format!("Failed to kill PID {pid}: {stderr}"))
+    match platform::terminate(pid, force).await {
+        Ok(()) => {
+            ownership::unregister(pid);
+            ToolResult::ok(format!("Sent {} to PID {pid}", platform::terminate_name(force)))
         }
         Err(e) => ToolResult::err(format!("Failed to kill PID {pid}: {e}")),
     }
 }
 
-/// Wait until a port is accepting connections (and optionally responding to HTTP).
+/// Wait until a port is accepting connections and passes a readiness check.
 ///
 /// Args:
 /// - port: Port number to check
 /// - host: Host to check (default: localhost)
 /// - timeout: Max seconds to wait (default: 30)
 /// - interval: Seconds between checks (default: 1)
-/// - http_check: If true, verify HTTP GET returns 2xx/3xx (default: false)
+/// - check: Readiness profile: "tcp" | "http" | "grpc" | "command" (default:
+///   inferred from the other args, else "tcp")
+/// - http_check: Shorthand for check = "http"
 /// - path: HTTP path to check (default: "/")
-pub async fn wait_for_port(args: &Value, _workdir: &Path) -> ToolResult {
+/// - expect_status: Status code (or array of codes) that means ready
+///   (default: any 2xx/3xx)
+/// - expect_body: Substring the HTTP response body must contain
+/// - tls: Use HTTPS / TLS; self-signed certificates are accepted
+/// - grpc_service: Service name for the gRPC health check (default: "" = server)
+/// - probe_command: Shell command run in the workspace; ready when it exits 0
+pub async fn wait_for_port(args: &Value, workdir: &Path) -> ToolResult {
     let Some(port) = args.get("port").and_then(|v| v.as_u64()).map(|p| p as u16) else {
         return ToolResult::err("Missing 'port' parameter");
     };
@@ -386,15 +516,10 @@ pub async fn wait_for_port(args: &Value, _workdir: &Path) -> ToolResult {
         .and_then(|v| v.as_u64())
         .unwrap_or(1);
 
-    let http_check = args
-        .get("http_check")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-
-    let path = args
-        .get("path")
-        .and_then(|v| v.as_str())
-        .unwrap_or("/");
+    let check = match HealthCheck::from_args(args, host, port) {
+        Ok(check) => check,
+        Err(e) => return ToolResult::err(e),
+    };
 
     let start = Instant::now();
     let timeout = Duration::from_secs(timeout_secs);
@@ -410,8 +535,7 @@ pub async fn wait_for_port(args: &Value, _workdir: &Path) -> ToolResult {
         // First check TCP connection
         match tokio::time::timeout(Duration::from_secs(2), TcpStream::connect(&addr)).await {
             Ok(Ok(_)) => {
-                // TCP connection successful
-                if !http_check {
+                if check == HealthCheck::Tcp {
                     return ToolResult::ok(format!(
                         "Port {port} is now accepting connections!\n\
                          Host: {host}\n\
@@ -421,21 +545,18 @@ pub async fn wait_for_port(args: &Value, _workdir: &Path) -> ToolResult {
                     ));
                 }
 
-                // HTTP health check requested
-                let url = format!("http://{host}:{port}{path}");
-                match http_health_check(&url).await {
-                    Ok(status) => {
+                match check.probe(workdir).await {
+                    Ok(detail) => {
                         return ToolResult::ok(format!(
                             "Server is healthy!\n\
-                             URL: {url}\n\
-                             Status: {status}\n\
+                             {detail}\n\
                              Time waited: {:.1}s\n\
                              Attempts: {attempts}",
                             start.elapsed().as_secs_f64()
                         ));
                     }
                     Err(e) => {
-                        last_error = format!("HTTP check failed: {e}");
+                        last_error = format!("{} check failed: {e}", check.name());
                         // Continue waiting - server might still be starting
                     }
                 }
@@ -458,10 +579,140 @@ pub async fn wait_for_port(args: &Value, _workdir: &Path) -> ToolResult {
     ))
 }
 
-/// Perform a simple HTTP GET and check for success status.
-async fn http_health_check(url: &str) -> Result<u16, String> {
+/// Readiness check run once the port accepts TCP connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HealthCheck {
+    Tcp,
+    Http {
+        url: String,
+        /// Empty means any 2xx/3xx.
+        expect_status: Vec<u16>,
+        expect_body: Option<String>,
+    },
+    Grpc {
+        url: String,
+        service: String,
+    },
+    Command {
+        command: String,
+    },
+}
+
+impl HealthCheck {
+    fn from_args(args: &Value, host: &str, port: u16) -> Result<Self, String> {
+        let str_arg = |key: &str| args.get(key).and_then(|v| v.as_str()).map(String::from);
+        let expect_status: Vec<u16> = match args.get("expect_status") {
+            Some(Value::Array(codes)) => codes.iter().filter_map(|c| c.as_u64()).map(|c| c as u16).collect(),
+            Some(code) => code.as_u64().map(|c| vec![c as u16]).unwrap_or_default(),
+            None => Vec::new(),
+        };
+        let expect_body = str_arg("expect_body");
+        let probe_command = str_arg("probe_command");
+        let tls = args.get("tls").and_then(|v| v.as_bool()).unwrap_or(false);
+        let scheme = if tls { "https" } else { "http" };
+
+        let check = match str_arg("check") {
+            Some(check) => check,
+            None if probe_command.is_some() => "command".to_string(),
+            None if args.get("grpc_service").is_some() => "grpc".to_string(),
+            None if args.get("http_check").and_then(|v| v.as_bool()).unwrap_or(false)
+                || !expect_status.is_empty()
+                || expect_body.is_some() =>
+            {
+                "http".to_string()
+            }
+            None => "tcp".to_string(),
+        };
+
+        match check.as_str() {
+            "tcp" => Ok(Self::Tcp),
+            "http" => {
+                let path = str_arg("path").unwrap_or_else(|| "/".to_string());
+                Ok(Self::Http {
+                    url: format!("{scheme}://{host}:{port}{path}"),
+                    expect_status,
+                    expect_body,
+                })
+            }
+            "grpc" => Ok(Self::Grpc {
+                url: format!("{scheme}://{host}:{port}/grpc.health.v1.Health/Check"),
+                service: str_arg("grpc_service").unwrap_or_default(),
+            }),
+            "command" => probe_command
+                .map(|command| Self::Command { command })
+                .ok_or_else(|| "check 'command' requires 'probe_command'".to_string()),
+            other => Err(format!(
+                "Unknown check '{other}'. Valid values: tcp, http, grpc, command"
+            )),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Tcp => "TCP",
+            Self::Http { .. } => "HTTP",
+            Self::Grpc { .. } => "gRPC health",
+            Self::Command { .. } => "Probe command",
+        }
+    }
+
+    /// Run the check once. `Ok` carries a description of the healthy result.
+    async fn probe(&self, workdir: &Path) -> Result<String, String> {
+        match self {
+            Self::Tcp => Ok(String::new()),
+            Self::Http { url, expect_status, expect_body } => {
+                let (status, body) = http_health_check(url, expect_body.is_some()).await?;
+                if !status_matches(status, expect_status) {
+                    return Err(format!("HTTP {status}"));
+                }
+                if let Some(expected) = expect_body {
+                    if !body.contains(expected.as_str()) {
+                        return Err(format!("HTTP {status}, body does not contain {expected:?}"));
+                    }
+                }
+                Ok(format!("URL: {url}\nStatus: {status}"))
+            }
+            Self::Grpc { url, service } => {
+                let status = grpc_health_check(url, service).await?;
+                if status == GRPC_SERVING {
+                    Ok(format!("gRPC health: SERVING ({url})"))
+                } else {
+                    Err(format!("gRPC health status {}", grpc_status_name(status)))
+                }
+            }
+            Self::Command { command } => {
+                let output = tokio::time::timeout(
+                    Duration::from_secs(10),
+                    Command::new("sh").arg("-c").arg(command).current_dir(workdir).output(),
+                )
+                .await
+                .map_err(|_| "probe timed out after 10s".to_string())?
+                .map_err(|e| e.to_string())?;
+                if output.status.success() {
+                    Ok(format!("Probe: {command}"))
+                } else {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    Err(format!("exit {:?}: {}", output.status.code(), stderr.trim()))
+                }
+            }
+        }
+    }
+}
+
+fn status_matches(status: u16, expected: &[u16]) -> bool {
+    if expected.is_empty() {
+        (200..400).contains(&status)
+    } else {
+        expected.contains(&status)
+    }
+}
+
+/// Perform an HTTP GET, returning the status (and body when requested).
+async fn http_health_check(url: &str, read_body: bool) -> Result<(u16, String), String> {
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(5))
+        // Dev servers commonly use self-signed certificates
+        .danger_accept_invalid_certs(true)
         .build()
         .map_err(|e| e.to_string())?;
 
@@ -472,11 +723,114 @@ async fn http_health_check(url: &str) -> Result<u16, String> {
         .map_err(|e| e.to_string())?;
 
     let status = response.status().as_u16();
-    
-    if status >= 200 && status < 400 {
-        Ok(status)
+    let body = if read_body {
+        response.text().await.map_err(|e| e.to_string())?
     } else {
-        Err(format!("HTTP {status}"))
+        String::new()
+    };
+    Ok((status, body))
+}
+
+/// `grpc.health.v1.HealthCheckResponse.ServingStatus.SERVING`
+const GRPC_SERVING: u64 = 1;
+
+/// Call `grpc.health.v1.Health/Check` and return the serving status.
+async fn grpc_health_check(url: &str, service: &str) -> Result<u64, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .http2_prior_knowledge()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    // HealthCheckRequest { string service = 1; } in a gRPC length-prefixed frame
+    let mut message = Vec::new();
+    if !service.is_empty() {
+        message.push(0x0a);
+        encode_varint(service.len() as u64, &mut message);
+        message.extend_from_slice(service.as_bytes());
+    }
+    let mut frame = vec![0u8];
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&message);
+
+    let response = client
+        .post(url)
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(frame)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Errors come back as a trailers-only response with grpc-status in the headers
+    if let Some(code) = response.headers().get("grpc-status").and_then(|v| v.to_str().ok()) {
+        if code != "0" {
+            let message = response
+                .headers()
+                .get("grpc-message")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("");
+            return Err(format!("grpc-status {code} {message}").trim().to_string());
+        }
+    }
+    let body = response.bytes().await.map_err(|e| e.to_string())?;
+    parse_health_response(&body)
+}
+
+/// Decode the `status` field from a framed HealthCheckResponse.
+fn parse_health_response(body: &[u8]) -> Result<u64, String> {
+    if body.len() < 5 {
+        return Err("empty gRPC response".to_string());
+    }
+    let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+    let message = body.get(5..5 + len).ok_or("truncated gRPC response")?;
+    let mut i = 0;
+    // Proto3 omits default values: no field means UNKNOWN (0)
+    let mut status = 0;
+    while i < message.len() {
+        let tag = message[i];
+        i += 1;
+        match tag {
+            0x08 => status = decode_varint(message, &mut i)?,
+            // Skip unknown varint fields
+            t if t & 0x07 == 0 => {
+                decode_varint(message, &mut i)?;
+            }
+            _ => return Err("unexpected field in HealthCheckResponse".to_string()),
+        }
+    }
+    Ok(status)
+}
+
+fn encode_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn decode_varint(bytes: &[u8], i: &mut usize) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = *bytes.get(*i).ok_or("truncated varint")?;
+        *i += 1;
+        value |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("varint too long".to_string())
+}
+
+fn grpc_status_name(status: u64) -> &'static str {
+    match status {
+        0 => "UNKNOWN",
+        1 => "SERVING",
+        2 => "NOT_SERVING",
+        3 => "SERVICE_UNKNOWN",
+        _ => "invalid",
     }
 }
 
@@ -500,16 +854,14 @@ pub async fn check_port(args: &Value, _workdir: &Path) -> ToolResult {
     match tokio::time::timeout(Duration::from_secs(1), TcpStream::connect(&addr)).await {
         Ok(Ok(_)) => {
             // Port is in use, try to find what's using it
-            let pid_info = if let Ok(output) = Command::new("lsof")
-                .args(["-i", &format!(":{port}"), "-t"])
-                .output()
-                .await
-            {
-                let pids = String::from_utf8_lossy(&output.stdout);
-                let pids: Vec<&str> = pids.trim().lines().collect();
+            let pid_info = if let Ok(pids) = platform::port_pids(port).await {
+                for &pid in &pids {
+                    ownership::note_port(pid, port);
+                }
                 if pids.is_empty() {
                     "PID: unknown".to_string()
                 } else {
+                    let pids: Vec<String> = pids.iter().map(|p| p.to_string()).collect();
                     format!("PIDs: {}", pids.join(", "))
                 }
             } else {
@@ -527,46 +879,41 @@ pub async fn check_port(args: &Value, _workdir: &Path) -> ToolResult {
 /// Args:
 /// - port: Port number
 /// - force: Use SIGKILL instead of SIGTERM (default: false)
+/// - confirm: The user approved killing a process the agent didn't start
 pub async fn kill_port(args: &Value, _workdir: &Path) -> ToolResult {
     let Some(port) = args.get("port").and_then(|v| v.as_u64()).map(|p| p as u16) else {
         return ToolResult::err("Missing 'port' parameter");
     };
 
     let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+    let confirmed = args.get("confirm").and_then(|v| v.as_bool()).unwrap_or(false);
 
     // Find PIDs using this port
-    let output = match Command::new("lsof")
-        .args(["-i", &format!(":{port}"), "-t"])
-        .output()
-        .await
-    {
-        Ok(o) => o,
+    let pids = match platform::port_pids(port).await {
+        Ok(pids) => pids,
         Err(e) => return ToolResult::err(format!("Failed to find process on port {port}: {e}")),
     };
 
-    let pids_str = String::from_utf8_lossy(&output.stdout);
-    let pids: Vec<&str> = pids_str.trim().lines().filter(|s| !s.is_empty()).collect();
-
     if pids.is_empty() {
         return ToolResult::ok(format!("No process found using port {port}"));
     }
 
-    let signal = if force { "-9" } else { "-15" };
+    // Check every listener before signalling any of them
+    for &pid in &pids {
+        let description = format!("kill PID {pid} on port {port}");
+        if let Err(result) = ownership::check_foreign("port", pid, &description, confirmed) {
+            return result;
+        }
+    }
+
     let mut killed = Vec::new();
     let mut failed = Vec::new();
 
     for pid in pids {
-        let result = Command::new("kill")
-            .arg(signal)
-            .arg(pid)
-            .output()
-            .await;
-
-        match result {
-            Ok(o) if o.status.success() => killed.push(pid.to_string()),
-            Ok(o) => {
-                let err = String::from_utf8_lossy(&o.stderr);
-                failed.push(format!("{pid}: {err}"));
+        match platform::terminate(pid, force).await {
+            Ok(()) => {
+                ownership::unregister(pid);
+                killed.push(pid.to_string());
             }
             Err(e) => failed.push(format!("{pid}: {e}")),
         }
@@ -604,7 +951,7 @@ pub async fn run_command(args: &Value, workdir: &Path) -> ToolResult {
     if background {
         execute_background(args, workdir).await
     } else {
-        super::execute::run(args, workdir).await
+        super::fs_changes::tracked(workdir, super::execute::run(args, workdir)).await
     }
 }
 
@@ -688,4 +1035,60 @@ mod tests {
         assert!(result.success);
         assert!(result.output.contains("AVAILABLE"));
     }
+
+    #[test]
+    fn test_health_check_from_args() {
+        let check = |args: Value| HealthCheck::from_args(&args, "localhost", 8080);
+        assert_eq!(check(serde_json::json!({})), Ok(HealthCheck::Tcp));
+        assert_eq!(
+            check(serde_json::json!({ "expect_status": [200, 404], "path": "/health", "tls": true })),
+            Ok(HealthCheck::Http {
+                url: "https://localhost:8080/health".to_string(),
+                expect_status: vec![200, 404],
+                expect_body: None,
+            })
+        );
+        assert_eq!(
+            check(serde_json::json!({ "check": "grpc" })),
+            Ok(HealthCheck::Grpc {
+                url: "http://localhost:8080/grpc.health.v1.Health/Check".to_string(),
+                service: String::new(),
+            })
+        );
+        assert!(check(serde_json::json!({ "check": "command" })).is_err());
+
+        assert!(status_matches(302, &[]));
+        assert!(!status_matches(404, &[]));
+        assert!(status_matches(404, &[404]));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_kill_process_kills_group() {
+        let dir = tempfile::tempdir().unwrap();
+        // The shell stays the leader; `sleep` is a grandchild-style straggler
+        let args = serde_json::json!({ "command": "sleep 30 & echo $!; wait", "initial_wait_ms": 2000 });
+        let result = execute_background(&args, dir.path()).await;
+        assert!(result.success, "{}", result.output);
+        let pid: u32 = result.output.lines().find_map(|l| l.strip_prefix("PID: ")).unwrap().parse().unwrap();
+        let sleeper: u32 = result.output.lines().last().unwrap().trim().parse().unwrap();
+
+        let result = kill_process(&serde_json::json!({ "pid": pid }), dir.path()).await;
+        assert!(result.success, "{}", result.output);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let alive = std::process::Command::new("kill")
+            .args(["-0", &sleeper.to_string()])
+            .status()
+            .unwrap()
+            .success();
+        assert!(!alive, "grandchild {sleeper} survived the group kill");
+    }
+
+    #[test]
+    fn test_parse_health_response() {
+        assert_eq!(parse_health_response(&[0, 0, 0, 0, 2, 0x08, 0x01]), Ok(GRPC_SERVING));
+        assert_eq!(parse_health_response(&[0, 0, 0, 0, 2, 0x08, 0x02]), Ok(2));
+        assert_eq!(parse_health_response(&[0, 0, 0, 0, 0]), Ok(0));
+        assert!(parse_health_response(&[0, 0, 0, 0, 3, 0x08]).is_err());
+    }
 }
@@ -0,0 +1,243 @@
+//! On-disk logs for background processes.
+//!
+//! In-memory output buffers are capped, so long-running processes (dev
+//! servers, watchers) lose their early output. Every background process also
+//! writes its output to `.forge/logs/<pid>-<command>.<offset>.log` in the
+//! workspace, where `<offset>` is the byte offset of the segment's first byte
+//! in the process's whole output. Segments rotate so the total stays under
+//! `FORGE_PROCESS_LOG_MAX_BYTES` (default 20 MiB); the oldest are deleted.
+//!
+//! Readers ask for output `since` an offset and get back the next offset, so
+//! the agent (and the IDE) can page through the full history or follow it.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Default total size of a process's log segments.
+const DEFAULT_MAX_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Number of segments the total is split across.
+const SEGMENTS: u64 = 4;
+
+/// Directory holding process logs for a workspace.
+pub fn logs_dir(workdir: &Path) -> PathBuf {
+    workdir.join(".forge").join("logs")
+}
+
+fn max_total_bytes() -> u64 {
+    crate::config::var("FORGE_PROCESS_LOG_MAX_BYTES")
+        .and_then(|v| v.parse().ok())
+        .filter(|&v: &u64| v > 0)
+        .unwrap_or(DEFAULT_MAX_BYTES)
+}
+
+/// Writer for one process's log.
+pub struct ProcessLog {
+    dir: PathBuf,
+    stem: String,
+    segment_bytes: u64,
+    file: File,
+    /// Total bytes written since the process started.
+    written: u64,
+    segment_start: u64,
+}
+
+impl ProcessLog {
+    /// Create the log for a new process under `workdir/.forge/logs`.
+    pub fn create(workdir: &Path, pid: u32, command: &str) -> io::Result<Self> {
+        let dir = logs_dir(workdir);
+        fs::create_dir_all(&dir)?;
+        // Logs are local state, never something to commit
+        let ignore = dir.join(".gitignore");
+        if !ignore.exists() {
+            let _ = fs::write(ignore, "*\n");
+        }
+        let stem = format!("{pid}-{}", slug(command));
+        let segment_bytes = (max_total_bytes() / SEGMENTS).max(1);
+        Self::open(dir, stem, segment_bytes)
+    }
+
+    fn open(dir: PathBuf, stem: String, segment_bytes: u64) -> io::Result<Self> {
+        // A reused PID must not inherit stale segments
+        for (_, path) in segments(&dir, &stem) {
+            let _ = fs::remove_file(path);
+        }
+        let file = File::create(segment_path(&dir, &stem, 0))?;
+        Ok(Self {
+            dir,
+            stem,
+            segment_bytes,
+            file,
+            written: 0,
+            segment_start: 0,
+        })
+    }
+
+    /// Name identifying this log, for [`read`].
+    pub fn stem(&self) -> &str {
+        &self.stem
+    }
+
+    /// Offset just past the last byte written.
+    pub fn offset(&self) -> u64 {
+        self.written
+    }
+
+    pub fn append(&mut self, data: &[u8]) {
+        if let Err(e) = self.try_append(data) {
+            tracing::warn!("Failed to write process log {}: {}", self.stem, e);
+        }
+    }
+
+    fn try_append(&mut self, data: &[u8]) -> io::Result<()> {
+        if self.written - self.segment_start >= self.segment_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(data)?;
+        self.written += data.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.segment_start = self.written;
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(segment_path(&self.dir, &self.stem, self.segment_start))?;
+        let existing = segments(&self.dir, &self.stem);
+        let excess = existing.len().saturating_sub(SEGMENTS as usize);
+        for (_, path) in existing.into_iter().take(excess) {
+            let _ = fs::remove_file(path);
+        }
+        Ok(())
+    }
+}
+
+/// A range of a process's output read back from its log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogChunk {
+    pub text: String,
+    /// Offset of the first returned byte (later than the requested offset
+    /// when that part of the log was already rotated away).
+    pub start: u64,
+    /// Offset to pass as `since` to continue reading.
+    pub next: u64,
+    /// More output exists past `next`.
+    pub more: bool,
+}
+
+/// Read up to `max_bytes` of the log `stem` starting at offset `since`.
+pub fn read(workdir: &Path, stem: &str, since: u64, max_bytes: usize) -> io::Result<LogChunk> {
+    read_in(&logs_dir(workdir), stem, since, max_bytes)
+}
+
+fn read_in(dir: &Path, stem: &str, since: u64, max_bytes: usize) -> io::Result<LogChunk> {
+    let segs = segments(dir, stem);
+    if segs.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("no log for {stem}")));
+    }
+    let start = since.max(segs[0].0);
+    let mut pos = start;
+    let mut out = Vec::new();
+    let mut more = false;
+    for (i, (seg_start, path)) in segs.iter().enumerate() {
+        let seg_end = match segs.get(i + 1) {
+            Some((next_start, _)) => *next_start,
+            None => seg_start + fs::metadata(path)?.len(),
+        };
+        if pos >= seg_end {
+            continue;
+        }
+        if out.len() >= max_bytes {
+            more = true;
+            break;
+        }
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(pos - seg_start))?;
+        let want = ((seg_end - pos) as usize).min(max_bytes - out.len());
+        let mut buf = vec![0; want];
+        file.read_exact(&mut buf)?;
+        out.extend_from_slice(&buf);
+        pos += want as u64;
+        if pos < seg_end {
+            more = true;
+            break;
+        }
+    }
+    Ok(LogChunk {
+        text: String::from_utf8_lossy(&out).to_string(),
+        start,
+        next: pos,
+        more,
+    })
+}
+
+fn segment_path(dir: &Path, stem: &str, start: u64) -> PathBuf {
+    dir.join(format!("{stem}.{start}.log"))
+}
+
+/// Existing segments of `stem`, oldest first.
+fn segments(dir: &Path, stem: &str) -> Vec<(u64, PathBuf)> {
+    let prefix = format!("{stem}.");
+    let mut segs: Vec<(u64, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_str()?.to_string();
+            let offset = name.strip_prefix(&prefix)?.strip_suffix(".log")?.parse().ok()?;
+            Some((offset, e.path()))
+        })
+        .collect();
+    segs.sort_by_key(|(offset, _)| *offset);
+    segs
+}
+
+fn slug(command: &str) -> String {
+    let slug: String = command
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let slug: Vec<&str> = slug.split('-').filter(|s| !s.is_empty()).collect();
+    slug.join("-").chars().take(40).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rotation_and_offsets() {
+        let dir = tempdir().unwrap();
+        let mut log = ProcessLog::open(dir.path().to_path_buf(), "1-npm-run-dev".into(), 10).unwrap();
+        for i in 0..10 {
+            log.append(format!("line {i:02}\n").as_bytes());
+        }
+        assert_eq!(log.offset(), 80);
+        // 8-byte lines, 10-byte segments: a segment per 2 lines, 4 kept
+        assert_eq!(segments(dir.path(), "1-npm-run-dev").len(), 4);
+
+        let chunk = read_in(dir.path(), "1-npm-run-dev", 0, 1000).unwrap();
+        assert_eq!(chunk.start, 16);
+        assert!(chunk.text.starts_with("line 02\n"));
+        assert!(chunk.text.ends_with("line 09\n"));
+        assert!(!chunk.more);
+
+        let chunk = read_in(dir.path(), "1-npm-run-dev", 40, 12).unwrap();
+        assert_eq!(chunk.text, "line 05\nline");
+        assert_eq!(chunk.next, 52);
+        assert!(chunk.more);
+
+        let chunk = read_in(dir.path(), "1-npm-run-dev", 80, 100).unwrap();
+        assert_eq!(chunk.text, "");
+        assert_eq!(chunk.next, 80);
+    }
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("npm run dev -- --port=3000"), "npm-run-dev-port-3000");
+    }
+}
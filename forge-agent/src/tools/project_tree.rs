@@ -0,0 +1,237 @@
+//! The directory tree `list_files` shows with `recursive`, pruned to a
+//! token budget.
+//!
+//! Directories are opened breadth first, the most important first (source
+//! over tests over docs over assets), while the budget lasts; the rest show
+//! as `name/ (N files)`. Each directory lists at most [`MAX_CHILDREN`]
+//! entries plus a `… N more` line, except manifests and READMEs, which are
+//! always listed.
+
+use std::path::Path;
+
+use crate::progress::CHARS_PER_TOKEN;
+
+/// Budget when the caller gives none.
+pub const DEFAULT_MAX_TOKENS: usize = 2_000;
+
+/// Entries listed per directory (manifests don't count).
+const MAX_CHILDREN: usize = 25;
+const MAX_DEPTH: usize = 10;
+/// Entries scanned; deeper or later ones are left out.
+const MAX_ENTRIES: usize = 50_000;
+
+const MANIFESTS: &[&str] = &[
+    "Cargo.toml", "package.json", "go.mod", "pyproject.toml", "setup.py", "requirements.txt",
+    "pom.xml", "build.gradle", "build.gradle.kts", "Gemfile", "composer.json", "CMakeLists.txt",
+    "Makefile", "Dockerfile", "docker-compose.yml", "tsconfig.json", "pubspec.yaml", "mix.exs",
+];
+
+const SOURCE_DIRS: &[&str] = &[
+    "src", "lib", "app", "apps", "cmd", "pkg", "internal", "crates", "packages", "server",
+    "client", "api", "core", "services", "components",
+];
+const TEST_DIRS: &[&str] = &["tests", "test", "spec", "__tests__", "e2e", "benches", "examples"];
+const DOC_DIRS: &[&str] = &["docs", "doc", "scripts", "tools", "config", "configs", ".github"];
+const ASSET_DIRS: &[&str] = &[
+    "assets", "static", "public", "images", "img", "icons", "fonts", "media", "resources",
+    "vendor", "third_party", "generated", "fixtures", "testdata", "snapshots", "locales", "i18n",
+];
+
+struct Node {
+    name: String,
+    is_dir: bool,
+    depth: usize,
+    priority: i32,
+    children: Vec<usize>,
+    /// Files under a directory, all levels.
+    files: usize,
+}
+
+/// The tree under `root`, within about `max_tokens` tokens.
+pub fn build(root: &Path, max_tokens: usize) -> String {
+    let mut nodes = vec![Node { name: String::new(), is_dir: true, depth: 0, priority: 0, children: Vec::new(), files: 0 }];
+    scan(root, 0, &mut nodes);
+    count_files(&mut nodes, 0);
+
+    // Open directories level by level, most important first, while they fit
+    let budget = max_tokens.saturating_mul(CHARS_PER_TOKEN);
+    let mut open = vec![false; nodes.len()];
+    let mut used = 0;
+    let mut level = vec![0];
+    while !level.is_empty() {
+        level.sort_by(|a, b| nodes[*b].priority.cmp(&nodes[*a].priority).then(nodes[*a].name.cmp(&nodes[*b].name)));
+        let mut next = Vec::new();
+        for dir in level {
+            let (shown, hidden) = visible_children(&nodes, dir);
+            let cost = shown.iter().map(|c| line_len(&nodes[*c])).sum::<usize>()
+                + if hidden > 0 { nodes[dir].depth * 2 + 16 } else { 0 };
+            // The root is always opened
+            if dir != 0 && used + cost > budget {
+                continue;
+            }
+            used += cost;
+            open[dir] = true;
+            next.extend(shown.into_iter().filter(|c| nodes[*c].is_dir && !nodes[*c].children.is_empty()));
+        }
+        level = next;
+    }
+
+    let mut out = String::new();
+    render(&nodes, &open, 0, &mut out);
+    out.truncate(out.trim_end().len());
+    out
+}
+
+fn scan(dir: &Path, parent: usize, nodes: &mut Vec<Node>) {
+    let depth = nodes[parent].depth + 1;
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        if nodes.len() >= MAX_ENTRIES {
+            return;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        if (is_dir && super::should_skip_dir(&name)) || (!is_dir && name.starts_with('.')) {
+            continue;
+        }
+        let id = nodes.len();
+        nodes.push(Node { priority: priority(&name, is_dir), name, is_dir, depth, children: Vec::new(), files: 0 });
+        nodes[parent].children.push(id);
+        if is_dir {
+            scan(&entry.path(), id, nodes);
+        }
+    }
+}
+
+fn count_files(nodes: &mut [Node], id: usize) -> usize {
+    if !nodes[id].is_dir {
+        return 1;
+    }
+    let children = nodes[id].children.clone();
+    let files = children.into_iter().map(|c| count_files(nodes, c)).sum();
+    nodes[id].files = files;
+    files
+}
+
+fn is_manifest(name: &str) -> bool {
+    MANIFESTS.contains(&name) || name.to_ascii_lowercase().starts_with("readme")
+}
+
+/// Higher is shown (and opened) first.
+fn priority(name: &str, is_dir: bool) -> i32 {
+    if is_dir {
+        let lower = name.to_ascii_lowercase();
+        return if SOURCE_DIRS.contains(&lower.as_str()) {
+            40
+        } else if TEST_DIRS.contains(&lower.as_str()) {
+            20
+        } else if DOC_DIRS.contains(&lower.as_str()) {
+            10
+        } else if ASSET_DIRS.contains(&lower.as_str()) {
+            -10
+        } else {
+            30
+        };
+    }
+    if is_manifest(name) {
+        return 100;
+    }
+    let ext = Path::new(name).extension().and_then(|e| e.to_str()).unwrap_or_default();
+    if super::workspace_diff::language(ext).is_some() {
+        25
+    } else if matches!(ext, "toml" | "json" | "yaml" | "yml" | "md" | "proto" | "graphql" | "sql") {
+        5
+    } else if matches!(ext, "lock" | "png" | "jpg" | "jpeg" | "gif" | "svg" | "ico" | "woff" | "woff2" | "ttf" | "map") {
+        -20
+    } else {
+        0
+    }
+}
+
+/// The children listed for `dir` (manifests plus the top [`MAX_CHILDREN`]
+/// by priority), in display order, and how many are left out.
+fn visible_children(nodes: &[Node], dir: usize) -> (Vec<usize>, usize) {
+    let children = &nodes[dir].children;
+    let (mut shown, mut rest): (Vec<usize>, Vec<usize>) =
+        children.iter().partition(|c| !nodes[**c].is_dir && is_manifest(&nodes[**c].name));
+    rest.sort_by(|a, b| nodes[*b].priority.cmp(&nodes[*a].priority));
+    let hidden = rest.len().saturating_sub(MAX_CHILDREN);
+    rest.truncate(MAX_CHILDREN);
+    shown.extend(rest);
+    // Directories first, then by name
+    shown.sort_by(|a, b| nodes[*b].is_dir.cmp(&nodes[*a].is_dir).then(nodes[*a].name.cmp(&nodes[*b].name)));
+    (shown, hidden)
+}
+
+/// Characters of `node`'s line, as a closed directory if it is one.
+fn line_len(node: &Node) -> usize {
+    let indent = (node.depth - 1) * 2;
+    let suffix = if node.is_dir { 12 } else { 0 };
+    indent + node.name.len() + suffix + 1
+}
+
+fn render(nodes: &[Node], open: &[bool], dir: usize, out: &mut String) {
+    let (shown, hidden) = visible_children(nodes, dir);
+    let indent = "  ".repeat(nodes[dir].depth);
+    for child in shown {
+        let node = &nodes[child];
+        match (node.is_dir, open[child]) {
+            (false, _) => out.push_str(&format!("{indent}{}\n", node.name)),
+            (true, true) => {
+                out.push_str(&format!("{indent}{}/\n", node.name));
+                render(nodes, open, child, out);
+            }
+            (true, false) => {
+                let files = if node.files == 1 { "1 file".to_string() } else { format!("{} files", node.files) };
+                out.push_str(&format!("{indent}{}/ ({files})\n", node.name));
+            }
+        }
+    }
+    if hidden > 0 {
+        out.push_str(&format!("{indent}… {hidden} more\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_tree_pruning() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let write = |path: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        };
+        write("Cargo.toml");
+        write("src/main.rs");
+        write("src/parser/mod.rs");
+        for i in 0..40 {
+            write(&format!("assets/icon{i:02}.png"));
+        }
+        write("node_modules/x/index.js");
+        write(".env");
+
+        let full = build(root, DEFAULT_MAX_TOKENS);
+        assert_eq!(
+            full,
+            format!(
+                "assets/\n{}  … 15 more\nsrc/\n  parser/\n    mod.rs\n  main.rs\nCargo.toml",
+                (0..25).map(|i| format!("  icon{i:02}.png\n")).collect::<String>()
+            )
+        );
+
+        // A small budget opens src before assets, and keeps the manifest
+        let small = build(root, 20);
+        assert_eq!(small, "assets/ (40 files)\nsrc/\n  parser/ (1 file)\n  main.rs\nCargo.toml");
+    }
+}
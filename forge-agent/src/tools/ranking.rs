@@ -0,0 +1,159 @@
+//! Path weights for `codebase_search` ranking.
+//!
+//! Added to each hit's score along with the working-set boost, so results
+//! can favour the parts of a repo that matter and sink tests and generated
+//! code. Set in config (or as `FORGE_*` variables):
+//!
+//! - `rank_boost_dirs`: comma-separated directories whose files get the boost
+//! - `rank_boost`: added for files under those directories (default 0.1)
+//! - `rank_test_decay`: subtracted for test files (default 0.05)
+//! - `rank_generated_decay`: subtracted for generated and vendored code
+//!   (default 0.1)
+//!
+//! `forge index rank` prints the top files with their weights for tuning.
+
+use std::path::Path;
+
+const DEFAULT_BOOST: f64 = 0.1;
+const DEFAULT_TEST_DECAY: f64 = 0.05;
+const DEFAULT_GENERATED_DECAY: f64 = 0.1;
+
+const TEST_DIRS: &[&str] = &["test", "tests", "spec", "specs", "__tests__", "e2e", "testdata", "fixtures"];
+const GENERATED_DIRS: &[&str] = &[
+    "generated", "gen", "vendor", "third_party", "node_modules", "dist", "build", "target", "out",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathWeights {
+    /// Workspace-relative, without trailing slashes.
+    pub boost_dirs: Vec<String>,
+    pub boost: f64,
+    pub test_decay: f64,
+    pub generated_decay: f64,
+}
+
+impl Default for PathWeights {
+    fn default() -> Self {
+        Self {
+            boost_dirs: Vec::new(),
+            boost: DEFAULT_BOOST,
+            test_decay: DEFAULT_TEST_DECAY,
+            generated_decay: DEFAULT_GENERATED_DECAY,
+        }
+    }
+}
+
+impl PathWeights {
+    /// No adjustment at all.
+    pub fn none() -> Self {
+        Self { boost_dirs: Vec::new(), boost: 0.0, test_decay: 0.0, generated_decay: 0.0 }
+    }
+
+    pub fn configured() -> Self {
+        use crate::config::var;
+        let number = |name: &str, default: f64| {
+            var(name).and_then(|v| v.trim().parse::<f64>().ok()).filter(|v| v.is_finite()).unwrap_or(default)
+        };
+        let boost_dirs = var("FORGE_RANK_BOOST_DIRS")
+            .map(|dirs| {
+                dirs.split(',')
+                    .map(|d| d.trim().trim_start_matches("./").trim_matches('/').to_string())
+                    .filter(|d| !d.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            boost_dirs,
+            boost: number("FORGE_RANK_BOOST", DEFAULT_BOOST),
+            test_decay: number("FORGE_RANK_TEST_DECAY", DEFAULT_TEST_DECAY),
+            generated_decay: number("FORGE_RANK_GENERATED_DECAY", DEFAULT_GENERATED_DECAY),
+        }
+    }
+
+    /// Added to the score of a hit in `path` (workspace-relative).
+    pub fn weight(&self, path: &str) -> f64 {
+        let path = path.trim_start_matches("./").replace('\\', "/");
+        let mut weight = 0.0;
+        if self.boost_dirs.iter().any(|dir| path.starts_with(&format!("{dir}/"))) {
+            weight += self.boost;
+        }
+        if is_generated(&path) {
+            weight -= self.generated_decay;
+        } else if is_test(&path) {
+            weight -= self.test_decay;
+        }
+        weight
+    }
+}
+
+/// Whether `path` is a test file or lives in a test directory.
+pub fn is_test(path: &str) -> bool {
+    let mut parts: Vec<&str> = path.split('/').collect();
+    let name = parts.pop().unwrap_or_default();
+    let stem = name.split('.').next().unwrap_or_default();
+    parts.iter().any(|p| TEST_DIRS.contains(&p.to_ascii_lowercase().as_str()))
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+}
+
+/// Whether `path` is generated, vendored or a build output.
+pub fn is_generated(path: &str) -> bool {
+    let mut parts: Vec<&str> = path.split('/').collect();
+    let name = parts.pop().unwrap_or_default();
+    parts.iter().any(|p| GENERATED_DIRS.contains(&p.to_ascii_lowercase().as_str()))
+        || name.contains(".generated.")
+        || name.contains(".pb.")
+        || name.ends_with("_pb2.py")
+        || name.contains(".min.")
+        || name.ends_with(".g.dart")
+}
+
+/// The workspace's files with the highest weights (path weights plus the
+/// working-set boost), best first, ties by path.
+pub fn top_files(workspace: &Path, weights: &PathWeights, limit: usize) -> Vec<(String, f64)> {
+    let mut files: Vec<(String, f64)> = ignore::WalkBuilder::new(workspace)
+        .hidden(true)
+        .git_ignore(true)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|e| {
+            let rel = e.path().strip_prefix(workspace).ok()?.to_string_lossy().replace('\\', "/");
+            let score = weights.weight(&rel) + super::working_set::boost(workspace, &rel);
+            Some((rel, score))
+        })
+        .collect();
+    files.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    files.truncate(limit);
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_weights() {
+        let weights = PathWeights { boost_dirs: vec!["src/core".into()], ..Default::default() };
+        assert_eq!(weights.weight("src/core/engine.rs"), DEFAULT_BOOST);
+        assert_eq!(weights.weight("src/core_utils.rs"), 0.0);
+        assert_eq!(weights.weight("src/core/engine_test.go"), DEFAULT_BOOST - DEFAULT_TEST_DECAY);
+        assert_eq!(weights.weight("web/src/app.spec.ts"), -DEFAULT_TEST_DECAY);
+        assert_eq!(weights.weight("vendor/lib/tests/x.rs"), -DEFAULT_GENERATED_DECAY);
+        assert_eq!(weights.weight("proto/api.pb.go"), -DEFAULT_GENERATED_DECAY);
+        assert_eq!(PathWeights::none().weight("tests/x.rs"), 0.0);
+
+        let dir = tempfile::tempdir().unwrap();
+        for path in ["src/core/engine.rs", "src/lib.rs", "tests/it.rs"] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let top = top_files(dir.path(), &weights, 10);
+        let order: Vec<&str> = top.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(order, ["src/core/engine.rs", "src/lib.rs", "tests/it.rs"]);
+    }
+}
@@ -0,0 +1,269 @@
+//! Renaming a symbol with tree-sitter, for when no language server is
+//! running for the file's language.
+//!
+//! Only identifier nodes ([`LangConfig::identifiers`]) whose text is the old
+//! name change, so strings and comments are left alone. A name defined
+//! nowhere in the workspace but used inside a function (a local or a
+//! parameter) is renamed in that function only; anything else is renamed in
+//! every file of the same language.
+
+use std::path::Path;
+
+use tree_sitter::{Node, Point};
+
+use super::fs_changes::{ChangeKind, FsChange};
+use super::ToolResult;
+use crate::syntax::{self, LangConfig};
+
+/// Files scanned for occurrences; larger workspaces are cut off.
+const MAX_FILES: usize = 5_000;
+/// Files larger than this are skipped.
+const MAX_FILE_BYTES: u64 = 1_000_000;
+
+/// One file a rename changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRename {
+    /// Relative to the workspace.
+    pub path: String,
+    pub before: String,
+    pub after: String,
+    pub occurrences: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenamePlan {
+    pub old_name: String,
+    pub new_name: String,
+    /// Whether the rename is confined to the function the name is used in.
+    pub local: bool,
+    pub files: Vec<FileRename>,
+}
+
+impl RenamePlan {
+    /// A unified diff of every file the rename changes.
+    pub fn preview(&self) -> String {
+        self.files
+            .iter()
+            .map(|f| crate::approvals::edit_preview(&f.path, &f.before, &f.after))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn apply(&self, workspace: &Path) -> Result<(), String> {
+        for file in &self.files {
+            std::fs::write(workspace.join(&file.path), &file.after)
+                .map_err(|e| format!("failed to write {}: {e}", file.path))?;
+        }
+        Ok(())
+    }
+}
+
+/// Rename the identifier at `line`:`column` (1-indexed) of `path` and
+/// return the diff.
+pub fn rename(workspace: &Path, path: &str, line: u64, column: u64, new_name: &str) -> ToolResult {
+    let plan = match plan(workspace, path, line, column, new_name) {
+        Ok(plan) => plan,
+        Err(e) => return ToolResult::err(format!("Rename failed: {e}")),
+    };
+    if let Err(e) = plan.apply(workspace) {
+        return ToolResult::err(format!("Rename failed: {e}"));
+    }
+    let occurrences: usize = plan.files.iter().map(|f| f.occurrences).sum();
+    let changes = plan
+        .files
+        .iter()
+        .map(|f| FsChange { path: f.path.clone(), kind: ChangeKind::Modified, old_content: Some(f.before.clone()) })
+        .collect();
+    ToolResult::ok(format!(
+        "Renamed `{}` to `{}`: {occurrences} occurrence{} in {} file{}{} (tree-sitter; no language server was available, so check the diff)\n\n{}",
+        plan.old_name,
+        plan.new_name,
+        if occurrences == 1 { "" } else { "s" },
+        plan.files.len(),
+        if plan.files.len() == 1 { "" } else { "s" },
+        if plan.local { ", within its function" } else { "" },
+        plan.preview()
+    ))
+    .with_fs_changes(changes)
+}
+
+/// The edits renaming the identifier at `line`:`column` (1-indexed) to
+/// `new_name`, without writing them.
+pub fn plan(workspace: &Path, path: &str, line: u64, column: u64, new_name: &str) -> Result<RenamePlan, String> {
+    let full = workspace.join(path);
+    let lang = syntax::config_for(&full).ok_or_else(|| format!("no tree-sitter grammar for {path}"))?;
+    if !is_identifier(new_name) {
+        return Err(format!("'{new_name}' is not a valid identifier"));
+    }
+    let content = std::fs::read_to_string(&full).map_err(|e| format!("failed to read {path}: {e}"))?;
+    let tree = lang.parse(&content).ok_or_else(|| format!("failed to parse {path}"))?;
+    let src = content.as_bytes();
+    let target = identifier_at(lang, tree.root_node(), &content, line, column)
+        .ok_or_else(|| format!("no identifier at {path}:{line}:{column}"))?;
+    let old_name = target.utf8_text(src).unwrap_or_default().to_string();
+    if old_name == new_name {
+        return Err(format!("the symbol is already named '{new_name}'"));
+    }
+
+    let files = same_language_files(workspace, lang);
+    let defined = files.iter().any(|(_, content, lang)| defines(lang, content, &old_name));
+    let scope = if defined { None } else { enclosing_function(lang, target) };
+
+    let mut renames = Vec::new();
+    if let Some(scope) = scope {
+        let ranges = occurrences(lang, scope, src, &old_name);
+        renames.push(file_rename(path, &content, &ranges, new_name));
+    } else {
+        for (rel, content, lang) in &files {
+            let Some(tree) = lang.parse(content) else { continue };
+            let ranges = occurrences(lang, tree.root_node(), content.as_bytes(), &old_name);
+            if !ranges.is_empty() {
+                renames.push(file_rename(rel, content, &ranges, new_name));
+            }
+        }
+    }
+    Ok(RenamePlan { old_name, new_name: new_name.to_string(), local: scope.is_some(), files: renames })
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// The identifier node at a 1-indexed character position, or just before it
+/// (a cursor right after the name).
+fn identifier_at<'t>(lang: &LangConfig, root: Node<'t>, content: &str, line: u64, column: u64) -> Option<Node<'t>> {
+    let row = (line.max(1) - 1) as usize;
+    let text = content.lines().nth(row)?;
+    let char_col = (column.max(1) - 1) as usize;
+    [char_col, char_col.saturating_sub(1)].into_iter().find_map(|col| {
+        let byte = text.char_indices().nth(col).map(|(i, _)| i).unwrap_or(text.len());
+        let point = Point { row, column: byte };
+        let node = root.descendant_for_point_range(point, point)?;
+        lang.identifiers.contains(&node.kind()).then_some(node)
+    })
+}
+
+/// The workspace files parsed by `lang`'s grammar (TypeScript and
+/// JavaScript count as one): relative path, content and grammar.
+fn same_language_files(workspace: &Path, lang: &LangConfig) -> Vec<(String, String, &'static LangConfig)> {
+    let family = |name: &str| if name == "tsx" { "typescript" } else { name }.to_string();
+    let walker = ignore::WalkBuilder::new(workspace)
+        .hidden(true)
+        .git_ignore(true)
+        .filter_entry(|e| {
+            e.file_type().is_some_and(|t| t.is_file()) || !super::should_skip_dir(&e.file_name().to_string_lossy())
+        })
+        .build();
+    walker
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .filter(|e| e.metadata().is_ok_and(|m| m.len() <= MAX_FILE_BYTES))
+        .filter_map(|e| {
+            let config = syntax::config_for(e.path()).filter(|c| family(c.name) == family(lang.name))?;
+            Some((e, config))
+        })
+        .take(MAX_FILES)
+        .filter_map(|(e, config)| {
+            let rel = e.path().strip_prefix(workspace).ok()?.to_string_lossy().replace('\\', "/");
+            Some((rel, std::fs::read_to_string(e.path()).ok()?, config))
+        })
+        .collect()
+}
+
+/// Whether `content` has a definition named `name` (or `Container.name`).
+fn defines(lang: &LangConfig, content: &str, name: &str) -> bool {
+    if !content.contains(name) {
+        return false;
+    }
+    let Some(tree) = lang.parse(content) else {
+        return false;
+    };
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if lang.is_definition(node) {
+            if let Some(defined) = lang.definition_name(node, content.as_bytes()) {
+                if defined == name || defined.ends_with(&format!("{}{name}", lang.separator)) {
+                    return true;
+                }
+            }
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.named_children(&mut cursor));
+    }
+    false
+}
+
+/// The innermost definition around `node` that isn't a container, i.e. the
+/// function a local lives in.
+fn enclosing_function<'t>(lang: &LangConfig, node: Node<'t>) -> Option<Node<'t>> {
+    let mut current = node.parent();
+    while let Some(n) = current {
+        if lang.is_definition(n) && !lang.is_container(n) {
+            return Some(n);
+        }
+        current = n.parent();
+    }
+    None
+}
+
+/// Byte ranges of the identifiers under `node` spelled `name`.
+fn occurrences(lang: &LangConfig, node: Node, src: &[u8], name: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut stack = vec![node];
+    while let Some(n) = stack.pop() {
+        if lang.identifiers.contains(&n.kind()) && n.utf8_text(src).is_ok_and(|t| t == name) {
+            ranges.push((n.start_byte(), n.end_byte()));
+            continue;
+        }
+        let mut cursor = n.walk();
+        stack.extend(n.children(&mut cursor));
+    }
+    ranges.sort_unstable();
+    ranges
+}
+
+fn file_rename(path: &str, content: &str, ranges: &[(usize, usize)], new_name: &str) -> FileRename {
+    let mut after = content.to_string();
+    for (start, end) in ranges.iter().rev() {
+        after.replace_range(start..end, new_name);
+    }
+    FileRename { path: path.to_string(), before: content.to_string(), after, occurrences: ranges.len() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_sitter_rename() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "pub fn parse(input: &str) -> usize {\n    let count = input.len();\n    // count the input\n    count\n}\n\nfn other() -> usize {\n    let count = 1;\n    count\n}\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {\n    println!(\"parse\");\n    lib::parse(\"x\");\n}\n").unwrap();
+
+        // A function: renamed in every file, not in strings or comments
+        let result = rename(root, "src/lib.rs", 1, 8, "parse_input");
+        assert!(result.success, "{}", result.output);
+        assert!(result.output.contains("2 occurrences in 2 files"));
+        assert!(result.output.contains("+pub fn parse_input(input: &str)"));
+        let main = std::fs::read_to_string(root.join("src/main.rs")).unwrap();
+        assert_eq!(main, "fn main() {\n    println!(\"parse\");\n    lib::parse_input(\"x\");\n}\n");
+
+        // A local: only in its own function
+        let plan = plan(root, "src/lib.rs", 2, 9, "total").unwrap();
+        assert!(plan.local);
+        assert_eq!(plan.files[0].occurrences, 2);
+        assert!(plan.files[0].after.contains("// count the input\n    total\n}"));
+        assert!(plan.files[0].after.contains("let count = 1;"));
+
+        assert!(!rename(root, "src/lib.rs", 1, 8, "1abc").success);
+        assert!(!rename(root, "src/lib.rs", 3, 8, "x").success);
+    }
+}
@@ -0,0 +1,224 @@
+//! Second-stage ordering for `codebase_search`.
+//!
+//! forge-search ranks hits by embedding similarity alone, which often puts
+//! boilerplate that shares vocabulary with the query above the file that
+//! implements it. With reranking on, more candidates are fetched and scored
+//! again against the query, and the best ones kept.
+//!
+//! - `FORGE_RERANK`: `off` (default), `local`, `jina` or `cohere`
+//! - `FORGE_RERANK_MODEL`: model for the provider (its default reranker otherwise)
+//!
+//! `local` runs on the machine: it scores query terms found in each hit's
+//! symbol name, path and code, and blends that with the similarity score.
+//! `jina` and `cohere` send the query and the candidates' code to the
+//! provider's rerank API with the key from `JINA_API_KEY` / `COHERE_API_KEY`
+//! (or the keychain). A failed call keeps the original order.
+
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+/// Candidates fetched for reranking, per result kept.
+pub const CANDIDATE_FACTOR: usize = 3;
+/// Code sent per candidate, in chars.
+const MAX_DOCUMENT_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reranker {
+    Local,
+    Provider { name: String, model: String },
+}
+
+impl Reranker {
+    /// `None` when reranking is off or the setting is unknown.
+    pub fn configured() -> Option<Self> {
+        use crate::config::var;
+        Self::parse(var("FORGE_RERANK").as_deref()?, var("FORGE_RERANK_MODEL"))
+    }
+
+    fn parse(kind: &str, model: Option<String>) -> Option<Self> {
+        let (name, default_model) = match kind.trim().to_ascii_lowercase().as_str() {
+            "" | "off" | "false" | "0" => return None,
+            "local" => return Some(Self::Local),
+            "jina" => ("jina", "jina-reranker-v2-base-multilingual"),
+            "cohere" => ("cohere", "rerank-v3.5"),
+            other => {
+                tracing::warn!("Unknown FORGE_RERANK value {other:?}; reranking is off");
+                return None;
+            }
+        };
+        Some(Self::Provider { name: name.to_string(), model: model.unwrap_or_else(|| default_model.to_string()) })
+    }
+
+    /// `results` (forge-search hits) reordered for `query`, at most `top_n`.
+    pub async fn rerank(&self, query: &str, mut results: Vec<Value>, top_n: usize) -> Vec<Value> {
+        let scores = match self {
+            Self::Local => local_scores(query, &results),
+            Self::Provider { name, model } => match provider_scores(name, model, query, &results).await {
+                Ok(scores) => scores,
+                Err(e) => {
+                    tracing::warn!("Reranking with {name} failed, keeping search order: {e}");
+                    results.truncate(top_n);
+                    return results;
+                }
+            },
+        };
+        let mut ranked: Vec<(f64, Value)> = scores.into_iter().zip(results).collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranked
+            .into_iter()
+            .take(top_n)
+            .map(|(score, mut hit)| {
+                hit["rerank_score"] = score.into();
+                hit
+            })
+            .collect()
+    }
+}
+
+fn field<'a>(hit: &'a Value, key: &str) -> &'a str {
+    hit.get(key).and_then(Value::as_str).unwrap_or("")
+}
+
+/// Lowercase words of `text`, with identifiers split at `_`, `-` and case
+/// changes (`parseConfigFile` -> parse, config, file).
+fn terms(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut current = String::new();
+        let mut prev_lower = false;
+        for c in word.chars() {
+            if c.is_uppercase() && prev_lower && !current.is_empty() {
+                terms.push(std::mem::take(&mut current));
+            }
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+            current.extend(c.to_lowercase());
+        }
+        if !current.is_empty() {
+            terms.push(current);
+        }
+    }
+    terms
+}
+
+/// Query terms (ignoring short and common words) found in each hit's name,
+/// path and code, weighted in that order and blended half-and-half with
+/// the similarity score.
+fn local_scores(query: &str, results: &[Value]) -> Vec<f64> {
+    const STOP: &[&str] = &["the", "and", "for", "where", "what", "how", "does", "that", "this", "with", "from", "is"];
+    let query: HashSet<String> = terms(query).into_iter().filter(|t| t.len() > 2 && !STOP.contains(&t.as_str())).collect();
+    results
+        .iter()
+        .map(|hit| {
+            let similarity = hit.get("score").and_then(Value::as_f64).unwrap_or(0.0);
+            if query.is_empty() {
+                return similarity;
+            }
+            let name: HashSet<String> = terms(field(hit, "name")).into_iter().collect();
+            let path: HashSet<String> = terms(field(hit, "file_path")).into_iter().collect();
+            let code: HashSet<String> = terms(field(hit, "content")).into_iter().collect();
+            let matched: f64 = query
+                .iter()
+                .map(|t| {
+                    if name.contains(t) {
+                        1.0
+                    } else if path.contains(t) {
+                        0.6
+                    } else if code.contains(t) {
+                        0.3
+                    } else {
+                        0.0
+                    }
+                })
+                .sum();
+            0.5 * similarity + 0.5 * matched / query.len() as f64
+        })
+        .collect()
+}
+
+/// What the provider sees of a hit.
+fn document(hit: &Value) -> String {
+    let doc = format!("{}\n{}\n{}", field(hit, "file_path"), field(hit, "name"), field(hit, "content"));
+    doc.chars().take(MAX_DOCUMENT_CHARS).collect()
+}
+
+async fn provider_scores(provider: &str, model: &str, query: &str, results: &[Value]) -> Result<Vec<f64>> {
+    if !crate::egress::Egress::configured().allows_other() {
+        return Err(anyhow!("reranking with {provider} is disabled in local-only mode"));
+    }
+    let key = crate::secrets::api_key(provider)
+        .ok_or_else(|| anyhow!("no API key: set {}", crate::secrets::env_var(provider)))?;
+    let url = match provider {
+        "jina" => "https://api.jina.ai/v1/rerank",
+        _ => "https://api.cohere.com/v2/rerank",
+    };
+    let body = serde_json::json!({
+        "model": model,
+        "query": query,
+        "documents": results.iter().map(document).collect::<Vec<_>>(),
+        "top_n": results.len(),
+    });
+    let resp = crate::http::client()
+        .post(url)
+        .bearer_auth(key)
+        .timeout(std::time::Duration::from_secs(15))
+        .json(&crate::redaction::outbound(&body))
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(anyhow!("API error {status}: {}", &text[..text.len().min(200)]));
+    }
+    parse_scores(&resp.json().await?, results.len())
+}
+
+/// Scores by candidate index from a `{"results": [{"index", "relevance_score"}]}`
+/// answer (the shape both Jina and Cohere use). Unranked candidates score 0.
+fn parse_scores(body: &Value, count: usize) -> Result<Vec<f64>> {
+    let ranked = body.get("results").and_then(Value::as_array).ok_or_else(|| anyhow!("no results in rerank response"))?;
+    let mut scores = vec![0.0; count];
+    for item in ranked {
+        let index = item.get("index").and_then(Value::as_u64).map(|i| i as usize);
+        let score = item.get("relevance_score").and_then(Value::as_f64);
+        if let (Some(index), Some(score)) = (index, score) {
+            if let Some(slot) = scores.get_mut(index) {
+                *slot = score;
+            }
+        }
+    }
+    Ok(scores)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_local_rerank_lifts_implementation() {
+        let results = vec![
+            json!({"file_path": "src/util/strings.rs", "name": "format_config", "content": "fn format_config() {}", "score": 0.82}),
+            json!({"file_path": "src/config/parser.rs", "name": "parseConfigFile", "content": "fn parse(path) {}", "score": 0.74}),
+            json!({"file_path": "README.md", "name": "", "content": "where config lives", "score": 0.80}),
+        ];
+        let ranked = Reranker::Local.rerank("where is the config file parsed", results, 2).await;
+        let paths: Vec<&str> = ranked.iter().map(|r| field(r, "file_path")).collect();
+        assert_eq!(paths, ["src/config/parser.rs", "src/util/strings.rs"]);
+        assert!(ranked[0]["rerank_score"].as_f64().is_some());
+    }
+
+    #[test]
+    fn test_parse_settings_and_scores() {
+        assert_eq!(Reranker::parse("off", None), None);
+        assert_eq!(Reranker::parse("Local", None), Some(Reranker::Local));
+        assert_eq!(
+            Reranker::parse("jina", Some("m".into())),
+            Some(Reranker::Provider { name: "jina".into(), model: "m".into() })
+        );
+        let body = json!({"results": [{"index": 2, "relevance_score": 0.9}, {"index": 0, "relevance_score": 0.1}, {"index": 7, "relevance_score": 1.0}]});
+        assert_eq!(parse_scores(&body, 3).unwrap(), [0.1, 0.0, 0.9]);
+        assert_eq!(terms("parseConfigFile snake_case"), ["parse", "config", "file", "snake", "case"]);
+    }
+}
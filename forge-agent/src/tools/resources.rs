@@ -0,0 +1,234 @@
+//! What the agent leaves running or on disk, cleaned up when the proxy exits.
+//!
+//! Processes the agent started are already in [`ownership`]; temp files are
+//! tracked here as [`TempPath`]s, and the IDE adds its own steps (rejecting
+//! pending approvals, closing terminals) with [`on_shutdown`]. [`shutdown`]
+//! runs those steps, flushes session journals, terminates the agent's
+//! process groups (SIGTERM, then SIGKILL after a grace period) and removes
+//! the temp files. [`sweep_orphans`] removes temp files left by a proxy that
+//! died before it could.
+
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::ownership::{self, ProcessOrigin};
+use super::platform;
+
+/// How long process groups get to exit after SIGTERM.
+pub const GRACE: Duration = Duration::from_secs(2);
+
+/// Temp files are `forge-<kind>-<pid>-<unique>`, so a later proxy can tell
+/// whose they are.
+const TEMP_PREFIX: &str = "forge-";
+
+type Hook = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct Registry {
+    temp: HashMap<u64, PathBuf>,
+    hooks: Vec<(&'static str, Hook)>,
+    shut_down: bool,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static INSTANCE: OnceLock<Mutex<Registry>> = OnceLock::new();
+    INSTANCE.get_or_init(Default::default)
+}
+
+/// A temp file, removed when dropped or at [`shutdown`], whichever is first.
+#[derive(Debug)]
+pub struct TempPath {
+    id: u64,
+    path: PathBuf,
+}
+
+impl TempPath {
+    /// A fresh path in the temp dir for a `kind` of file, e.g.
+    /// `("attachment", "pdf")`.
+    pub fn new(kind: &str, extension: &str) -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT.fetch_add(1, Ordering::Relaxed);
+        let name = format!("{TEMP_PREFIX}{kind}-{}-{}.{extension}", std::process::id(), uuid::Uuid::new_v4());
+        let path = std::env::temp_dir().join(name);
+        registry().lock().unwrap().temp.insert(id, path.clone());
+        Self { id, path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        registry().lock().unwrap().temp.remove(&self.id);
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Run `hook` at [`shutdown`], before processes are terminated.
+pub fn on_shutdown(name: &'static str, hook: impl FnOnce() + Send + 'static) {
+    registry().lock().unwrap().hooks.push((name, Box::new(hook)));
+}
+
+/// What [`shutdown`] did.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ShutdownReport {
+    pub hooks: Vec<&'static str>,
+    pub sessions_flushed: usize,
+    /// Process groups that exited on SIGTERM.
+    pub terminated: Vec<u32>,
+    /// Process groups still running after the grace period.
+    pub killed: Vec<u32>,
+    pub temp_removed: usize,
+}
+
+impl std::fmt::Display for ShutdownReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ran {} hooks, flushed {} sessions, terminated {} and killed {} process groups, removed {} temp files",
+            self.hooks.len(),
+            self.sessions_flushed,
+            self.terminated.len(),
+            self.killed.len(),
+            self.temp_removed
+        )
+    }
+}
+
+/// Release everything the agent holds. Only the first call does anything.
+pub fn shutdown(grace: Duration) -> ShutdownReport {
+    let (hooks, temp) = {
+        let mut registry = registry().lock().unwrap();
+        if registry.shut_down {
+            return ShutdownReport::default();
+        }
+        registry.shut_down = true;
+        (std::mem::take(&mut registry.hooks), std::mem::take(&mut registry.temp))
+    };
+    let groups: Vec<u32> = ownership::list().into_iter().filter(|p| p.origin == ProcessOrigin::Agent).map(|p| p.pid).collect();
+    let mut report = cleanup(hooks, groups, temp.into_values().collect(), grace);
+    report.sessions_flushed = crate::session::flush();
+    tracing::info!("Agent shutdown: {report}");
+    report
+}
+
+fn cleanup(hooks: Vec<(&'static str, Hook)>, groups: Vec<u32>, temp: Vec<PathBuf>, grace: Duration) -> ShutdownReport {
+    let mut report = ShutdownReport::default();
+    for (name, hook) in hooks {
+        if std::panic::catch_unwind(AssertUnwindSafe(hook)).is_err() {
+            tracing::error!("Shutdown hook '{name}' panicked");
+        }
+        report.hooks.push(name);
+    }
+
+    // Background commands and agent terminals lead their own groups
+    for &pgid in &groups {
+        if let Err(e) = platform::terminate_group_blocking(pgid, false) {
+            tracing::debug!("SIGTERM to process group {pgid}: {e}");
+        }
+    }
+    let deadline = Instant::now() + grace;
+    let mut running = groups;
+    loop {
+        let (alive, exited): (Vec<u32>, Vec<u32>) = running.into_iter().partition(|&pid| is_alive(pid));
+        report.terminated.extend(exited);
+        running = alive;
+        if running.is_empty() || Instant::now() >= deadline {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    for &pgid in &running {
+        tracing::warn!("Process group {pgid} ignored SIGTERM, killing it");
+        let _ = platform::terminate_group_blocking(pgid, true);
+    }
+    report.killed = running;
+    for pid in report.terminated.iter().chain(&report.killed) {
+        ownership::unregister(*pid);
+    }
+
+    for path in temp {
+        if std::fs::remove_file(&path).is_ok() {
+            report.temp_removed += 1;
+        }
+    }
+    report
+}
+
+/// Whether `pid` is running; a zombie nobody reaped has exited.
+fn is_alive(pid: u32) -> bool {
+    platform::process_command(pid).is_some_and(|command| !command.contains("<defunct>"))
+}
+
+/// Remove temp files whose proxy is gone, e.g. after a crash. Returns how many.
+pub fn sweep_orphans() -> usize {
+    sweep_dir(&std::env::temp_dir())
+}
+
+fn sweep_dir(dir: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(pid) = temp_owner(&name) else {
+            continue;
+        };
+        if pid != std::process::id() && !is_alive(pid) && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// The proxy PID in a [`TempPath`] file name.
+fn temp_owner(name: &str) -> Option<u32> {
+    let mut parts = name.strip_prefix(TEMP_PREFIX)?.splitn(3, '-');
+    let (_kind, pid, _unique) = (parts.next()?, parts.next()?, parts.next()?);
+    pid.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shutdown_cleanup() {
+        use std::os::unix::process::CommandExt;
+        use std::sync::Arc;
+        use std::sync::atomic::AtomicBool;
+
+        let dir = tempfile::tempdir().unwrap();
+        // Ignores SIGTERM, so it has to be killed
+        let stubborn = std::process::Command::new("sh").args(["-c", "trap '' TERM; sleep 30"]).process_group(0).spawn().unwrap();
+        let polite = std::process::Command::new("sleep").arg("30").process_group(0).spawn().unwrap();
+        let temp = TempPath::new("test", "txt");
+        std::fs::write(temp.path(), "x").unwrap();
+        let path = temp.path().to_path_buf();
+        std::mem::forget(temp);
+
+        let ran = Arc::new(AtomicBool::new(false));
+        let flag = ran.clone();
+        let hooks: Vec<(&'static str, Hook)> = vec![("reject approvals", Box::new(move || flag.store(true, Ordering::SeqCst)))];
+        let report = cleanup(hooks, vec![stubborn.id(), polite.id()], vec![path.clone()], Duration::from_millis(300));
+        assert!(ran.load(Ordering::SeqCst));
+        assert_eq!((report.terminated, report.killed), (vec![polite.id()], vec![stubborn.id()]));
+        assert_eq!(report.temp_removed, 1);
+        assert!(!path.exists());
+
+        // A dead proxy's temp files are orphans, a live one's aren't
+        std::fs::write(dir.path().join("forge-attachment-999999999-a.pdf"), "").unwrap();
+        std::fs::write(dir.path().join(format!("forge-dictation-{}-b.wav", std::process::id())), "").unwrap();
+        std::fs::write(dir.path().join("forge-notes.txt"), "").unwrap();
+        assert_eq!(sweep_dir(dir.path()), 1);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
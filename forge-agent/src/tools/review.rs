@@ -0,0 +1,394 @@
+//! Code review of a git diff (staged changes, working tree, or a commit range).
+//!
+//! The diff is split into hunks and each hunk is sent to forge-search with a
+//! review prompt. The model answers with a JSON array of findings which are
+//! anchored back to the new-file line numbers of the hunk.
+
+use super::{LintSeverity, ToolResult};
+use serde_json::Value;
+use std::path::Path;
+use tokio::process::Command;
+
+/// Max hunks reviewed in one call (larger diffs are truncated).
+const MAX_REVIEW_HUNKS: usize = 40;
+/// Max hunks reviewed concurrently.
+const REVIEW_CONCURRENCY: usize = 4;
+/// Hunk bodies larger than this are cut before being sent for review.
+const MAX_HUNK_CHARS: usize = 8_000;
+
+/// A single hunk of a unified diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk {
+    /// Path of the file after the change (relative to the repo root).
+    pub file: String,
+    /// First line of the hunk in the new file (1-indexed).
+    pub new_start: usize,
+    /// Number of lines the hunk spans in the new file.
+    pub new_len: usize,
+    /// The `@@` header followed by the hunk lines.
+    pub body: String,
+}
+
+/// A structured review finding.
+#[derive(Debug, Clone)]
+pub struct ReviewFinding {
+    pub file: String,
+    pub line: usize,
+    pub severity: LintSeverity,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+/// Which changes to review.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewTarget {
+    /// Unstaged working tree changes.
+    WorkingTree,
+    /// Staged changes (`git diff --staged`).
+    Staged,
+    /// A git range such as `main..HEAD` or a single commit.
+    Range(String),
+}
+
+impl ReviewTarget {
+    pub fn from_args(args: &Value) -> Self {
+        if let Some(range) = args.get("range").and_then(|v| v.as_str()) {
+            if !range.trim().is_empty() {
+                return Self::Range(range.trim().to_string());
+            }
+        }
+        if args.get("staged").and_then(|v| v.as_bool()).unwrap_or(false) {
+            Self::Staged
+        } else {
+            Self::WorkingTree
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::WorkingTree => "working tree changes".to_string(),
+            Self::Staged => "staged changes".to_string(),
+            Self::Range(r) => format!("range {}", r),
+        }
+    }
+}
+
+/// Review tool - callable by the LLM ("review my changes").
+pub async fn review(args: &Value, workdir: &Path) -> ToolResult {
+    let target = ReviewTarget::from_args(args);
+    let path = args.get("path").and_then(|v| v.as_str());
+
+    match collect_findings(workdir, &target, path).await {
+        Ok((hunks, findings)) => ToolResult::ok(format_findings(&target, hunks, &findings)),
+        Err(e) => ToolResult::err(e),
+    }
+}
+
+/// Run the review and return `(hunks_reviewed, findings)`.
+///
+/// Used by the `review` tool and by the proxy, which additionally publishes
+/// the findings as editor diagnostics.
+pub async fn collect_findings(
+    workdir: &Path,
+    target: &ReviewTarget,
+    path: Option<&str>,
+) -> Result<(usize, Vec<ReviewFinding>), String> {
+    let diff = git_diff(workdir, target, path).await?;
+    let mut hunks = parse_diff_hunks(&diff);
+    if hunks.is_empty() {
+        return Ok((0, Vec::new()));
+    }
+    if hunks.len() > MAX_REVIEW_HUNKS {
+        tracing::warn!("Review truncated to {} of {} hunks", MAX_REVIEW_HUNKS, hunks.len());
+        hunks.truncate(MAX_REVIEW_HUNKS);
+    }
+
+    let workspace_id = crate::forge_search::workspace_id(workdir);
+
+    use futures::StreamExt;
+    let reviewed = hunks.len();
+    let results: Vec<Result<Vec<ReviewFinding>, String>> = futures::stream::iter(hunks)
+        .map(|hunk| {
+            let workspace_id = workspace_id.clone();
+            async move { review_hunk(&workspace_id, &hunk).await }
+        })
+        .buffered(REVIEW_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut findings = Vec::new();
+    let mut last_err = None;
+    for r in results {
+        match r {
+            Ok(f) => findings.extend(f),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    if findings.is_empty() {
+        if let Some(e) = last_err {
+            return Err(format!("Review failed: {}", e));
+        }
+    }
+
+    findings.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    Ok((reviewed, findings))
+}
+
+async fn git_diff(workdir: &Path, target: &ReviewTarget, path: Option<&str>) -> Result<String, String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("diff").arg("--no-color").arg("--unified=3");
+    match target {
+        ReviewTarget::WorkingTree => {}
+        ReviewTarget::Staged => {
+            cmd.arg("--staged");
+        }
+        ReviewTarget::Range(range) => {
+            if range.starts_with('-') {
+                return Err(format!("Invalid range '{}'", range));
+            }
+            // A single commit reviews that commit's own changes
+            if range.contains("..") {
+                cmd.arg(range);
+            } else {
+                cmd.arg(format!("{}^!", range));
+            }
+        }
+    }
+    if let Some(p) = path {
+        cmd.arg("--").arg(p);
+    }
+
+    let output = cmd
+        .current_dir(workdir)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run git diff: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Split a unified diff into per-file hunks. Deleted files are skipped since
+/// there is nothing left to annotate.
+pub fn parse_diff_hunks(diff: &str) -> Vec<DiffHunk> {
+    let mut hunks = Vec::new();
+    let mut file: Option<String> = None;
+    let mut current: Option<DiffHunk> = None;
+
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            hunks.extend(current.take());
+            file = None;
+        } else if let (Some(rest), None) = (line.strip_prefix("+++ "), &current) {
+            file = match rest {
+                "/dev/null" => None,
+                p => Some(p.strip_prefix("b/").unwrap_or(p).to_string()),
+            };
+        } else if line.starts_with("--- ") && current.is_none() {
+            // old-file header, nothing to record
+        } else if line.starts_with("@@") {
+            hunks.extend(current.take());
+            let Some(f) = &file else { continue };
+            let (new_start, new_len) = parse_new_range(line).unwrap_or((1, 0));
+            current = Some(DiffHunk {
+                file: f.clone(),
+                new_start,
+                new_len,
+                body: format!("{}\n", line),
+            });
+        } else if let Some(h) = current.as_mut() {
+            if line.starts_with([' ', '+', '-', '\\']) || line.is_empty() {
+                h.body.push_str(line);
+                h.body.push('\n');
+            }
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+/// Parse `+start,len` out of a `@@ -a,b +c,d @@` header.
+fn parse_new_range(header: &str) -> Option<(usize, usize)> {
+    let plus = header.split_whitespace().find(|p| p.starts_with('+'))?;
+    let mut parts = plus[1..].splitn(2, ',');
+    let start = parts.next()?.parse().ok()?;
+    let len = parts.next().map(|l| l.parse().unwrap_or(1)).unwrap_or(1);
+    Some((start, len))
+}
+
+async fn review_hunk(workspace_id: &str, hunk: &DiffHunk) -> Result<Vec<ReviewFinding>, String> {
+    let mut body = hunk.body.clone();
+    if body.len() > MAX_HUNK_CHARS {
+        let mut cut = MAX_HUNK_CHARS;
+        while !body.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        body.truncate(cut);
+        body.push_str("\n... (hunk truncated)\n");
+    }
+
+    let prompt = format!(
+        "You are reviewing a single diff hunk from `{file}` (new-file lines {start}-{end}).\n\
+         Report only real problems: bugs, security issues, races, error handling gaps, \
+         and clear maintainability problems. Do not comment on style that a formatter would fix.\n\
+         Respond with ONLY a JSON array (empty if there is nothing to report). Each element:\n\
+         {{\"severity\": \"error\" | \"warning\" | \"info\", \"line\": <new-file line number>, \
+         \"message\": \"...\", \"suggestion\": \"...\"}}\n\n\
+         ```diff\n{body}```",
+        file = hunk.file,
+        start = hunk.new_start,
+        end = hunk.new_start + hunk.new_len.saturating_sub(1),
+        body = body,
+    );
+
+    let resp = crate::forge_search::client()
+        .chat_with_body(&serde_json::json!({
+            "workspace_id": workspace_id,
+            "question": prompt,
+        }))
+        .await
+        .map_err(|e| e.to_string())?;
+    let answer = resp.get("answer").and_then(|v| v.as_str()).unwrap_or("");
+    Ok(parse_findings(answer, hunk))
+}
+
+/// Extract findings from the model answer. Tolerates surrounding prose and
+/// code fences; line numbers outside the hunk are clamped to its start.
+pub fn parse_findings(answer: &str, hunk: &DiffHunk) -> Vec<ReviewFinding> {
+    let (Some(start), Some(end)) = (answer.find('['), answer.rfind(']')) else {
+        return Vec::new();
+    };
+    if end < start {
+        return Vec::new();
+    }
+    let Ok(Value::Array(items)) = serde_json::from_str::<Value>(&answer[start..=end]) else {
+        return Vec::new();
+    };
+
+    let hunk_end = hunk.new_start + hunk.new_len.saturating_sub(1);
+    items
+        .iter()
+        .filter_map(|item| {
+            let message = item.get("message").and_then(|v| v.as_str())?.trim();
+            if message.is_empty() {
+                return None;
+            }
+            let severity = match item.get("severity").and_then(|v| v.as_str()).unwrap_or("warning") {
+                "error" | "critical" | "high" => LintSeverity::Error,
+                "info" | "note" | "low" => LintSeverity::Info,
+                _ => LintSeverity::Warning,
+            };
+            let line = item
+                .get("line")
+                .and_then(|v| v.as_u64())
+                .map(|l| l as usize)
+                .filter(|l| *l >= hunk.new_start && *l <= hunk_end.max(hunk.new_start))
+                .unwrap_or(hunk.new_start);
+            let suggestion = item
+                .get("suggestion")
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty());
+            Some(ReviewFinding {
+                file: hunk.file.clone(),
+                line,
+                severity,
+                message: message.to_string(),
+                suggestion,
+            })
+        })
+        .collect()
+}
+
+pub fn format_findings(target: &ReviewTarget, hunks: usize, findings: &[ReviewFinding]) -> String {
+    if hunks == 0 {
+        return format!("No changes to review ({}).", target.describe());
+    }
+    if findings.is_empty() {
+        return format!("Reviewed {} hunk(s) of {}: no issues found.", hunks, target.describe());
+    }
+
+    let mut output = format!(
+        "Reviewed {} hunk(s) of {}: {} finding(s)\n\n",
+        hunks,
+        target.describe(),
+        findings.len()
+    );
+    for f in findings {
+        let severity = match f.severity {
+            LintSeverity::Error => "ERROR",
+            LintSeverity::Warning => "WARNING",
+            LintSeverity::Info => "INFO",
+        };
+        output.push_str(&format!("{}:{}: [{}] {}\n", f.file, f.line, severity, f.message));
+        if let Some(s) = &f.suggestion {
+            output.push_str(&format!("    suggestion: {}\n", s));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@
+ fn a() {}
++fn b() {}
+ fn c() {}
+@@ -10,2 +11,3 @@ impl Foo {
+     x
++    y
+diff --git a/old.rs b/old.rs
+deleted file mode 100644
+--- a/old.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+";
+
+    #[test]
+    fn test_parse_diff_hunks() {
+        let hunks = parse_diff_hunks(DIFF);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].file, "src/lib.rs");
+        assert_eq!((hunks[0].new_start, hunks[0].new_len), (1, 4));
+        assert!(hunks[0].body.contains("+fn b() {}"));
+        assert_eq!((hunks[1].new_start, hunks[1].new_len), (11, 3));
+        assert!(!hunks[1].body.contains("gone"));
+    }
+
+    #[test]
+    fn test_parse_findings_with_fences() {
+        let hunk = &parse_diff_hunks(DIFF)[1];
+        let answer = "Here you go:\n```json\n[\
+            {\"severity\": \"error\", \"line\": 12, \"message\": \"y is unused\", \"suggestion\": \"remove it\"},\
+            {\"severity\": \"nit\", \"line\": 99, \"message\": \"out of range\"},\
+            {\"severity\": \"info\", \"message\": \"  \"}\
+        ]\n```";
+        let findings = parse_findings(answer, hunk);
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].severity, LintSeverity::Error);
+        assert_eq!(findings[0].line, 12);
+        assert_eq!(findings[0].suggestion.as_deref(), Some("remove it"));
+        assert_eq!(findings[1].severity, LintSeverity::Warning);
+        assert_eq!(findings[1].line, 11);
+    }
+
+    #[test]
+    fn test_parse_findings_empty_or_garbage() {
+        let hunk = &parse_diff_hunks(DIFF)[0];
+        assert!(parse_findings("[]", hunk).is_empty());
+        assert!(parse_findings("no issues", hunk).is_empty());
+        assert!(parse_findings("] oops [", hunk).is_empty());
+    }
+}
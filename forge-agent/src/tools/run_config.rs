@@ -4,6 +4,7 @@
 //! - List detected run configurations (npm scripts, cargo bins, etc.)
 //! - Run projects through the IDE's proper run system
 //! - Stop running processes
+//! - Read back the output of a run
 
 use serde_json::Value;
 use crate::tools::ToolResult;
@@ -81,3 +82,12 @@ pub async fn stop_project(args: &Value, _workdir: &std::path::Path) -> ToolResul
     // This will be executed by the IDE
     ToolResult::ok("PENDING_IDE_EXECUTION")
 }
+
+/// Read the recent output of a project started with run_project().
+///
+/// The IDE keeps the tail of each run config's terminal output, so the
+/// agent can tell whether a server came up or why it crashed.
+pub async fn read_run_output(_args: &Value, _workdir: &std::path::Path) -> ToolResult {
+    // This will be executed by the IDE
+    ToolResult::ok("PENDING_IDE_EXECUTION")
+}
@@ -0,0 +1,165 @@
+//! Argument validation against the JSON schemas in [`super::definitions`].
+//!
+//! Runs before a tool executes so malformed arguments come back as one clear
+//! message the model can act on ("field `start_line` must be integer")
+//! instead of whatever the tool code trips over. Covers the schema subset
+//! the definitions use: `type`, `properties`, `required`, `enum`, `items`.
+//! Unknown fields are allowed.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use serde_json::Value;
+
+/// The `parameters` schema of tool `name`, if it has a definition.
+pub fn schema_for(name: &str) -> Option<&'static Value> {
+    static SCHEMAS: OnceLock<HashMap<String, Value>> = OnceLock::new();
+    SCHEMAS
+        .get_or_init(|| {
+            super::definitions(false)
+                .into_iter()
+                .filter_map(|d| Some((d["name"].as_str()?.to_string(), d.get("parameters")?.clone())))
+                .filter(|(name, _)| !super::plugin::is_registered(name))
+                .collect()
+        })
+        .get(name)
+}
+
+/// Check `args` for tool `name`; `Err` holds the message for the model.
+pub fn validate_call(name: &str, args: &Value) -> Result<(), String> {
+    // Plugin tools register after the built-in schemas are cached
+    let plugin_schema;
+    let schema = match schema_for(name) {
+        Some(schema) => schema,
+        None => match super::plugin::get(name) {
+            Some(tool) => {
+                plugin_schema = tool.parameters;
+                &plugin_schema
+            }
+            None => return Ok(()),
+        },
+    };
+    // No arguments at all is the same as `{}`
+    let empty = Value::Object(Default::default());
+    let args = if args.is_null() { &empty } else { args };
+    let mut problems = Vec::new();
+    check(args, schema, "", &mut problems);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("invalid arguments for {name}: {}", problems.join("; ")))
+    }
+}
+
+fn check(value: &Value, schema: &Value, path: &str, problems: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type").and_then(|t| t.as_str()) {
+        if !has_type(value, expected) {
+            problems.push(match path {
+                "" => format!("arguments must be {} (got {})", article(expected), describe(value)),
+                _ => format!("field `{path}` must be {expected} (got {})", describe(value)),
+            });
+            return;
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            let options: Vec<String> = allowed.iter().map(|v| v.to_string()).collect();
+            problems.push(format!("field `{path}` must be one of {} (got {value})", options.join(", ")));
+        }
+    }
+    if let (Some(object), Some(properties)) = (value.as_object(), schema.get("properties").and_then(|p| p.as_object())) {
+        for required in schema.get("required").and_then(|r| r.as_array()).into_iter().flatten() {
+            if let Some(field) = required.as_str() {
+                if object.get(field).map_or(true, Value::is_null) {
+                    problems.push(format!("missing required field `{}`", join(path, field)));
+                }
+            }
+        }
+        for (field, field_value) in object {
+            if let (Some(field_schema), false) = (properties.get(field), field_value.is_null()) {
+                check(field_value, field_schema, &join(path, field), problems);
+            }
+        }
+    }
+    if let (Some(items), Some(item_schema)) = (value.as_array(), schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            check(item, item_schema, &format!("{path}[{i}]"), problems);
+        }
+    }
+}
+
+fn join(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{path}.{field}")
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn article(kind: &str) -> String {
+    match kind {
+        "object" | "array" | "integer" => format!("an {kind}"),
+        _ => format!("a {kind}"),
+    }
+}
+
+fn describe(value: &Value) -> String {
+    let kind = match value {
+        Value::Null => return "null".to_string(),
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => return "array".to_string(),
+        Value::Object(_) => return "object".to_string(),
+    };
+    let shown: String = value.to_string().chars().take(40).collect();
+    format!("{kind} {shown}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_call() {
+        assert!(validate_call("read_file", &json!({ "path": "a.rs", "start_line": 3 })).is_ok());
+        assert!(validate_call("read_file", &json!({ "path": "a.rs", "start_line": null, "extra": 1 })).is_ok());
+        assert!(validate_call("no_such_tool", &json!("anything")).is_ok());
+
+        assert_eq!(
+            validate_call("read_file", &json!({ "start_line": "3" })),
+            Err("invalid arguments for read_file: missing required field `path`; \
+                 field `start_line` must be integer (got string \"3\")"
+                .to_string())
+        );
+        assert_eq!(
+            validate_call("process", &json!({ "action": "restart" })),
+            Err("invalid arguments for process: field `action` must be one of \"output\", \"status\", \"kill\" \
+                 (got \"restart\")"
+                .to_string())
+        );
+        assert_eq!(
+            validate_call("port", &json!({ "action": "wait", "port": 3000, "expect_status": [200, "404"] })),
+            Err("invalid arguments for port: field `expect_status[1]` must be integer (got string \"404\")".to_string())
+        );
+        assert_eq!(
+            validate_call("write_file", &json!("{\"path\": \"a\"}")),
+            Err("invalid arguments for write_file: arguments must be an object (got string \"{\\\"path\\\": \\\"a\\\"}\")"
+                .to_string())
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Per-project toolchain environment for agent commands.
+//!
+//! When a project pins tool versions (proto's `.prototools`, mise's
+//! `.mise.toml`, or asdf's `.tool-versions`), commands spawned by the agent
+//! should run with those toolchains, not whatever is first on the user's PATH.
+//! This resolves the pinned versions to installed directories and produces
+//! environment overrides:
+//! - `PATH` with the pinned tools' `bin` directories prepended
+//! - `JAVA_HOME` / `GOROOT` for JVM and Go toolchains
+//! - `RUSTUP_TOOLCHAIN` for a pinned Rust version
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A tool version pinned by the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedTool {
+    pub tool: String,
+    pub version: String,
+    /// File the pin came from (`.prototools`, `.mise.toml`, `.tool-versions`).
+    pub source: &'static str,
+}
+
+/// Read the tool pins declared at the workspace root.
+///
+/// Precedence when the same tool is pinned twice: `.prototools`, then
+/// `.mise.toml` / `mise.toml`, then `.tool-versions`.
+pub fn pinned_tools(workdir: &Path) -> Vec<PinnedTool> {
+    let mut pins: Vec<PinnedTool> = Vec::new();
+    let mut push = |tool: &str, version: &str, source: &'static str| {
+        let tool = canonical_tool_name(tool);
+        if !pins.iter().any(|p| p.tool == tool) {
+            pins.push(PinnedTool { tool, version: version.trim().to_string(), source });
+        }
+    };
+
+    if let Some(table) = read_toml(&workdir.join(".prototools")) {
+        for (tool, v) in &table {
+            // [plugins], [settings] etc. are tables, pins are strings
+            if let Some(version) = v.as_str() {
+                push(tool, version, ".prototools");
+            }
+        }
+    }
+
+    for name in [".mise.toml", "mise.toml"] {
+        let Some(table) = read_toml(&workdir.join(name)) else {
+            continue;
+        };
+        let tools = table.get("tools").and_then(|t| t.as_table());
+        for (tool, v) in tools.into_iter().flatten() {
+            let version = match v {
+                toml::Value::String(s) => Some(s.as_str()),
+                toml::Value::Array(a) => a.first().and_then(|x| x.as_str()),
+                _ => v.get("version").and_then(|x| x.as_str()),
+            };
+            if let Some(version) = version {
+                push(tool, version, ".mise.toml");
+            }
+        }
+    }
+
+    if let Ok(content) = std::fs::read_to_string(workdir.join(".tool-versions")) {
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut parts = line.split_whitespace();
+            if let (Some(tool), Some(version)) = (parts.next(), parts.next()) {
+                push(tool, version, ".tool-versions");
+            }
+        }
+    }
+
+    pins
+}
+
+/// Environment overrides for commands run in `workdir`.
+///
+/// Returns an empty map when nothing is pinned or none of the pinned
+/// versions are installed, so callers can apply it unconditionally.
+pub fn project_env(workdir: &Path) -> HashMap<String, String> {
+    let mut env = HashMap::new();
+    apply_project_env(workdir, &mut env);
+    env
+}
+
+/// Merge the project's toolchain overrides into `env`.
+///
+/// The pinned `bin` directories are prepended to `env["PATH"]` if present,
+/// otherwise to the current process PATH.
+pub fn apply_project_env(workdir: &Path, env: &mut HashMap<String, String>) {
+    let pins = pinned_tools(workdir);
+    if pins.is_empty() {
+        return;
+    }
+    apply_pins(&pins, &install_roots(), env);
+}
+
+fn apply_pins(pins: &[PinnedTool], roots: &[PathBuf], env: &mut HashMap<String, String>) {
+    let mut bins: Vec<PathBuf> = Vec::new();
+    for pin in pins {
+        if pin.tool == "rust" {
+            // Rust toolchains are managed by rustup, not per-version dirs
+            env.insert("RUSTUP_TOOLCHAIN".to_string(), pin.version.clone());
+            continue;
+        }
+        let Some(dir) = roots.iter().find_map(|r| find_install_dir(r, &pin.tool, &pin.version)) else {
+            tracing::debug!("Pinned {} {} ({}) is not installed", pin.tool, pin.version, pin.source);
+            continue;
+        };
+        let bin = dir.join("bin");
+        bins.push(if bin.is_dir() { bin } else { dir.clone() });
+        match pin.tool.as_str() {
+            "java" => {
+                env.insert("JAVA_HOME".to_string(), dir.to_string_lossy().to_string());
+            }
+            "go" => {
+                env.insert("GOROOT".to_string(), dir.to_string_lossy().to_string());
+            }
+            _ => {}
+        }
+    }
+    if bins.is_empty() {
+        return;
+    }
+
+    let base = env
+        .get("PATH")
+        .cloned()
+        .unwrap_or_else(|| std::env::var("PATH").unwrap_or_default());
+    let mut paths: Vec<PathBuf> = bins;
+    paths.extend(std::env::split_paths(&base));
+    if let Ok(joined) = std::env::join_paths(paths) {
+        env.insert("PATH".to_string(), joined.to_string_lossy().to_string());
+    }
+}
+
+/// Where version managers install toolchains: `<root>/<tool>/<version>`.
+fn install_roots() -> Vec<PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let proto = std::env::var("PROTO_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".proto"));
+    let mise = std::env::var("MISE_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".local").join("share").join("mise"));
+    let asdf = std::env::var("ASDF_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".asdf"));
+    vec![proto.join("tools"), mise.join("installs"), asdf.join("installs")]
+}
+
+/// Find `<root>/<tool>/<version>`, accepting partial versions ("20" matches
+/// the newest installed "20.x.y").
+fn find_install_dir(root: &Path, tool: &str, version: &str) -> Option<PathBuf> {
+    let version = version.trim_start_matches(['v', '~', '^', '=']);
+    let tool_dir = root.join(tool);
+    let exact = tool_dir.join(version);
+    if exact.is_dir() {
+        return Some(exact);
+    }
+    std::fs::read_dir(&tool_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().to_str().map(String::from))
+        .filter(|name| {
+            let name = name.trim_start_matches('v');
+            name == version || name.starts_with(&format!("{}.", version))
+        })
+        .max_by(|a, b| version_key(a).cmp(&version_key(b)))
+        .map(|name| tool_dir.join(name))
+}
+
+fn version_key(v: &str) -> Vec<u64> {
+    v.trim_start_matches('v')
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|p| p.parse().ok())
+        .collect()
+}
+
+fn canonical_tool_name(tool: &str) -> String {
+    match tool {
+        "nodejs" => "node",
+        "golang" => "go",
+        "python3" => "python",
+        other => other,
+    }
+    .to_string()
+}
+
+fn read_toml(path: &Path) -> Option<toml::Table> {
+    std::fs::read_to_string(path).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_pinned_tools_precedence() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".prototools"), "node = \"20.11.0\"\n\n[settings]\nauto-install = true\n").unwrap();
+        fs::write(dir.path().join(".mise.toml"), "[tools]\nnode = \"18\"\ngo = \"1.22\"\n").unwrap();
+        fs::write(dir.path().join(".tool-versions"), "golang 1.21.0\njava temurin-17 # lts\n").unwrap();
+
+        let pins = pinned_tools(dir.path());
+        let get = |t: &str| pins.iter().find(|p| p.tool == t).map(|p| p.version.clone());
+        assert_eq!(get("node").as_deref(), Some("20.11.0"));
+        assert_eq!(get("go").as_deref(), Some("1.22"));
+        assert_eq!(get("java").as_deref(), Some("temurin-17"));
+        assert_eq!(pins.len(), 3);
+    }
+
+    #[test]
+    fn test_apply_pins() {
+        let root = tempdir().unwrap();
+        fs::create_dir_all(root.path().join("node/20.11.0/bin")).unwrap();
+        fs::create_dir_all(root.path().join("go/1.22.1/bin")).unwrap();
+        fs::create_dir_all(root.path().join("go/1.22.10/bin")).unwrap();
+
+        let pins = vec![
+            PinnedTool { tool: "node".into(), version: "20.11.0".into(), source: ".prototools" },
+            PinnedTool { tool: "go".into(), version: "1.22".into(), source: ".prototools" },
+            PinnedTool { tool: "python".into(), version: "3.12".into(), source: ".prototools" },
+            PinnedTool { tool: "rust".into(), version: "1.79.0".into(), source: ".prototools" },
+        ];
+        let mut env = HashMap::from([("PATH".to_string(), "/usr/bin".to_string())]);
+        apply_pins(&pins, &[root.path().to_path_buf()], &mut env);
+
+        let path = env["PATH"].clone();
+        let parts: Vec<_> = std::env::split_paths(&path).collect();
+        assert_eq!(parts[0], root.path().join("node/20.11.0/bin"));
+        assert_eq!(parts[1], root.path().join("go/1.22.10/bin"));
+        assert_eq!(parts.last().unwrap(), &PathBuf::from("/usr/bin"));
+        assert_eq!(env["GOROOT"], root.path().join("go/1.22.10").to_string_lossy());
+        assert_eq!(env["RUSTUP_TOOLCHAIN"], "1.79.0");
+    }
+}
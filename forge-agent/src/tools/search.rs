@@ -1,5 +1,5 @@
+use super::args::{GlobArgs, GrepArgs};
 use super::ToolResult;
-use regex::Regex;
 use serde_json::Value;
 use std::path::Path;
 use walkdir::WalkDir;
@@ -9,11 +9,7 @@ use walkdir::WalkDir;
 /// Trigger background indexing of the workspace via forge-search.
 /// Fire-and-forget: does not block the caller.
 pub fn trigger_cloud_index(workdir: &Path) {
-    let workspace_id = workdir
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("default")
-        .to_string();
+    let workspace_id = crate::forge_search::workspace_id(workdir);
 
     let workdir = workdir.to_path_buf();
 
@@ -37,10 +33,7 @@ pub fn trigger_cloud_index(workdir: &Path) {
 /// Ensure workspace is indexed, indexing if needed.
 /// Returns (was_already_indexed, symbol_count).
 pub async fn ensure_indexed(workdir: &Path) -> (bool, i64) {
-    let workspace_id = workdir
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("default");
+    let workspace_id = &crate::forge_search::workspace_id(workdir);
 
     let client = crate::forge_search::client();
 
@@ -84,8 +77,8 @@ pub use crate::forge_search::{should_skip_dir, is_indexable_file};
 
 /// Semantic search using forge-search backend (pgvector).
 ///
-/// Always uses the forge-search API. Falls back to keyword search
-/// only if the API is unreachable or returns no results.
+/// Always uses the forge-search API. Falls back to a local search (symbol
+/// names, then keywords) if the API fails or returns no results.
 pub async fn semantic(args: &Value, workdir: &Path) -> ToolResult {
     let Some(query) = args.get("query").and_then(|v| v.as_str()) else {
         return ToolResult::err("Missing 'query' parameter");
@@ -93,27 +86,41 @@ pub async fn semantic(args: &Value, workdir: &Path) -> ToolResult {
 
     let client = crate::forge_search::client();
 
-    // Derive workspace_id from the workspace path
-    let workspace_id = workdir
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("default");
+    let workspace_id = &crate::forge_search::workspace_id(workdir);
 
     tracing::info!("forge-search query: {}", query);
 
-    let body: serde_json::Value = match client.search(workspace_id, query, 10).await {
+    // With a reranker or a working set to favour, fetch more candidates
+    // and pick the best
+    const TOP_K: usize = 10;
+    let reranker = super::rerank::Reranker::configured();
+    let reorder = reranker.is_some() || !super::working_set::is_empty(workdir);
+    let top_k = if reorder { TOP_K * super::rerank::CANDIDATE_FACTOR } else { TOP_K };
+
+    let body: serde_json::Value = match client.search(workspace_id, query, top_k).await {
         Ok(b) => b,
         Err(e) => {
             tracing::warn!("forge-search request failed: {}", e);
-            return keyword_search(query, workdir).await;
+            let reason = if crate::forge_search::is_unavailable(&e) {
+                e.to_string()
+            } else {
+                format!("forge-search request failed: {e}")
+            };
+            return local_search(query, workdir, &reason).await;
         }
     };
 
     // Format results from the API response
     let results = match body.get("results").and_then(|r| r.as_array()) {
-        Some(r) if !r.is_empty() => r,
-        _ => return keyword_search(query, workdir).await,
+        Some(r) if !r.is_empty() => r.clone(),
+        _ => return local_search(query, workdir, "no matches in the semantic index").await,
+    };
+    let mut results = match &reranker {
+        Some(reranker) => reranker.rerank(query, results, top_k).await,
+        None => results,
     };
+    super::working_set::rank(workdir, &mut results, &super::ranking::PathWeights::configured());
+    results.truncate(TOP_K);
 
     let output: Vec<String> = results
         .iter()
@@ -163,13 +170,81 @@ pub async fn semantic(args: &Value, workdir: &Path) -> ToolResult {
         .collect();
 
     if output.is_empty() {
-        return keyword_search(query, workdir).await;
+        return local_search(query, workdir, "no matches in the semantic index").await;
     }
 
     tracing::info!("forge-search returned {} results", output.len());
     ToolResult::ok(output.join("\n\n"))
 }
 
+// ── Local fallback ───────────────────────────────────────────────
+
+/// Definitions listed by the local fallback.
+const MAX_LOCAL_SYMBOLS: usize = 10;
+
+/// `codebase_search` without forge-search: definitions whose names match
+/// the query, then files containing its words, under a note saying why.
+async fn local_search(query: &str, workdir: &Path, reason: &str) -> ToolResult {
+    let symbols = symbol_search(query, workdir);
+    let keywords = keyword_search(query, workdir).await;
+    let mut sections = vec![format!("Note: {reason}. Showing local search results instead.")];
+    if !symbols.is_empty() {
+        sections.push(symbols.join("\n\n"));
+    }
+    sections.push(keywords.output);
+    ToolResult::ok(sections.join("\n\n"))
+}
+
+/// Chunks (see [`crate::chunking`]) defining a symbol whose name contains a
+/// query keyword, most keywords first.
+fn symbol_search(query: &str, workdir: &Path) -> Vec<String> {
+    let keywords: Vec<String> = extract_search_keywords(query).iter().map(|k| k.to_lowercase()).collect();
+    if keywords.is_empty() {
+        return Vec::new();
+    }
+    let mut hits = Vec::new();
+    for entry in WalkDir::new(workdir)
+        .max_depth(10)
+        .into_iter()
+        .filter_entry(|e| !e.file_type().is_dir() || !should_skip_dir(&e.file_name().to_string_lossy()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_indexable_file(&e.file_name().to_string_lossy()))
+    {
+        if super::deadline::expired() {
+            break;
+        }
+        if !entry.metadata().is_ok_and(|m| m.len() <= 100_000) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(entry.path()) else { continue };
+        let rel = entry.path().strip_prefix(workdir).unwrap_or(entry.path());
+        let lines: Vec<&str> = content.lines().collect();
+        for chunk in crate::chunking::chunk_file(rel, &content) {
+            let matched: Vec<&String> = chunk
+                .symbols
+                .iter()
+                .filter(|name| keywords.iter().any(|k| name.to_lowercase().contains(k)))
+                .collect();
+            let score = keywords.iter().filter(|k| matched.iter().any(|name| name.to_lowercase().contains(*k))).count();
+            if score == 0 {
+                continue;
+            }
+            let code = lines[chunk.start_line - 1..chunk.end_line.min(lines.len())].join("\n");
+            let names = matched.iter().map(|n| format!("`{n}`")).collect::<Vec<_>>().join(", ");
+            let text = format!(
+                "## {}:{}-{} [{names}]\n```\n{}\n```",
+                rel.display(),
+                chunk.start_line,
+                chunk.end_line,
+                truncate_lines(&code, 30),
+            );
+            hits.push((score, text));
+        }
+    }
+    hits.sort_by(|a, b| b.0.cmp(&a.0));
+    hits.into_iter().take(MAX_LOCAL_SYMBOLS).map(|(_, text)| text).collect()
+}
+
 // ── Keyword search fallback ──────────────────────────────────────
 
 /// Fallback keyword search -- used when embeddings are unavailable
@@ -232,199 +307,231 @@ async fn keyword_search(query: &str, workdir: &Path) -> ToolResult {
     }
 }
 
-// ── Grep (ripgrep) ───────────────────────────────────────────────
+// ── Grep ─────────────────────────────────────────────────────────
 
-/// Regex search (fallback when ripgrep not available)
-pub async fn files(args: &Value, workdir: &Path) -> ToolResult {
-    let Some(pattern) = args.get("pattern").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'pattern' parameter");
-    };
+/// Matches returned when the call doesn't set `max_results`.
+const DEFAULT_MAX_MATCHES: usize = 100;
+/// Files larger than this are skipped.
+const GREP_MAX_FILESIZE: u64 = 1024 * 1024;
+/// Matched and context lines are cut to this many chars (minified files).
+const GREP_MAX_LINE: usize = 300;
 
-    let path = args
-        .get("path")
-        .and_then(|v| v.as_str())
-        .unwrap_or(".");
-    let file_pattern = args
-        .get("file_pattern")
-        .and_then(|v| v.as_str())
-        .or_else(|| args.get("glob").and_then(|v| v.as_str()));
-
-    let regex = match Regex::new(pattern) {
-        Ok(r) => r,
-        Err(e) => return ToolResult::err(format!("Invalid regex: {e}")),
-    };
+/// One file's hits: match and context lines, `None` between
+/// non-adjacent groups.
+struct FileHits {
+    path: String,
+    lines: Vec<Option<(u64, bool, String)>>,
+}
 
-    let search_path = workdir.join(path);
-    let mut results = Vec::new();
-    let mut match_count = 0;
-    const MAX_MATCHES: usize = 30;
+/// Collects a file's matches with their context. The searcher merges
+/// overlapping context itself, so each line arrives once.
+struct Collector<'a> {
+    hits: &'a mut Vec<Option<(u64, bool, String)>>,
+    matches: &'a mut usize,
+    max: usize,
+    truncated: &'a mut bool,
+}
 
-    for entry in WalkDir::new(&search_path)
-        .into_iter()
-        .filter_entry(|e| {
-            let name = e.file_name().to_string_lossy();
-            if e.file_type().is_dir() {
-                return !should_skip_dir(&name);
-            }
-            true
-        })
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-    {
-        let file_path = entry.path();
+impl Collector<'_> {
+    fn push(&mut self, line_number: Option<u64>, is_match: bool, bytes: &[u8]) {
+        let text = String::from_utf8_lossy(bytes);
+        let text = text.trim_end_matches(['\r', '\n']);
+        let text = match text.char_indices().nth(GREP_MAX_LINE) {
+            Some((cut, _)) => format!("{}…", &text[..cut]),
+            None => text.to_string(),
+        };
+        self.hits.push(Some((line_number.unwrap_or(0), is_match, text)));
+    }
+}
 
-        if let Some(fp) = file_pattern {
-            let file_name = file_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("");
-            if !glob_match(fp, file_name) {
-                continue;
-            }
-        }
+impl grep_searcher::Sink for Collector<'_> {
+    type Error = std::io::Error;
 
-        let file_name = file_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("");
-        if file_name.starts_with('.') || is_binary_extension(file_name) {
-            continue;
+    fn matched(&mut self, _: &grep_searcher::Searcher, m: &grep_searcher::SinkMatch<'_>) -> Result<bool, Self::Error> {
+        if *self.matches >= self.max {
+            *self.truncated = true;
+            return Ok(false);
         }
+        *self.matches += 1;
+        self.push(m.line_number(), true, m.bytes());
+        Ok(true)
+    }
 
-        if let Ok(content) = std::fs::read_to_string(file_path) {
-            let rel_path = file_path.strip_prefix(workdir).unwrap_or(file_path);
-
-            for (line_num, line) in content.lines().enumerate() {
-                if regex.is_match(line) {
-                    results.push(format!(
-                        "{}:{}:{}",
-                        rel_path.display(),
-                        line_num + 1,
-                        line.trim()
-                    ));
-                    match_count += 1;
-
-                    if match_count >= MAX_MATCHES {
-                        results.push(format!("... (truncated at {} matches)", MAX_MATCHES));
-                        return ToolResult::ok(results.join("\n"));
-                    }
-                }
-            }
-        }
+    fn context(&mut self, _: &grep_searcher::Searcher, c: &grep_searcher::SinkContext<'_>) -> Result<bool, Self::Error> {
+        self.push(c.line_number(), false, c.bytes());
+        Ok(true)
     }
 
-    if results.is_empty() {
-        ToolResult::ok("No matches found")
-    } else {
-        ToolResult::ok(results.join("\n"))
+    fn context_break(&mut self, _: &grep_searcher::Searcher) -> Result<bool, Self::Error> {
+        self.hits.push(None);
+        Ok(true)
     }
 }
 
-/// Fast grep using ripgrep binary
-pub async fn grep(args: &Value, workdir: &Path) -> ToolResult {
-    let Some(pattern) = args.get("pattern").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'pattern' parameter");
-    };
+/// Regex search over the workspace, in process. Results are grouped by
+/// file (`12:` marks a match, `13-` context) and capped at `max_results`;
+/// a capped or timed-out search says so, so the model can narrow it.
+pub async fn grep(args: GrepArgs, workdir: &Path) -> ToolResult {
+    use grep_regex::RegexMatcherBuilder;
+    use grep_searcher::{BinaryDetection, SearcherBuilder};
 
-    let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
-    let file_glob = args.get("glob").and_then(|v| v.as_str());
-    let case_insensitive = args
-        .get("case_insensitive")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-    let context_lines = args
-        .get("context")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0) as usize;
-
-    let search_path = workdir.join(path);
-
-    // Build ripgrep command
-    // NOTE: rg respects .gitignore by default when run from a git repo,
-    // so we do NOT add manual --glob=! exclusions.
-    let mut cmd = std::process::Command::new("rg");
-    cmd.arg("--line-number")
-        .arg("--no-heading")
-        .arg("--color=never")
-        .arg("--max-count=30")
-        .arg("--max-filesize=100K");
-
-    if case_insensitive {
-        cmd.arg("-i");
+    let matcher = match RegexMatcherBuilder::new()
+        .case_insensitive(args.case_insensitive)
+        .build(&args.pattern)
+    {
+        Ok(m) => m,
+        Err(e) => return ToolResult::err(format!("Invalid regex: {e}")),
+    };
+    let context = args.context.min(5) as usize;
+    let max = args.max_results.map_or(DEFAULT_MAX_MATCHES, |n| (n as usize).clamp(1, 1000));
+    let mut searcher = SearcherBuilder::new()
+        .line_number(true)
+        .before_context(context)
+        .after_context(context)
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .build();
+
+    let search_path = workdir.join(&args.path);
+    let mut walk = ignore::WalkBuilder::new(&search_path);
+    walk.max_filesize(Some(GREP_MAX_FILESIZE)).require_git(false);
+    if let Some(glob) = &args.glob {
+        let mut overrides = ignore::overrides::OverrideBuilder::new(&search_path);
+        if let Err(e) = overrides.add(glob) {
+            return ToolResult::err(format!("Invalid glob '{glob}': {e}"));
+        }
+        match overrides.build() {
+            Ok(overrides) => {
+                walk.overrides(overrides);
+            }
+            Err(e) => return ToolResult::err(format!("Invalid glob '{glob}': {e}")),
+        }
     }
 
-    if context_lines > 0 {
-        cmd.arg(format!("-C{}", context_lines.min(5)));
+    let mut files: Vec<FileHits> = Vec::new();
+    let (mut matches, mut truncated, mut timed_out) = (0, false, false);
+    for entry in walk.build().filter_map(|e| e.ok()) {
+        if truncated {
+            break;
+        }
+        if super::deadline::expired() {
+            timed_out = true;
+            break;
+        }
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let mut hits = Vec::new();
+        let collector = Collector { hits: &mut hits, matches: &mut matches, max, truncated: &mut truncated };
+        if searcher.search_path(&matcher, entry.path(), collector).is_err() || hits.is_empty() {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(workdir).unwrap_or(entry.path());
+        files.push(FileHits { path: rel.to_string_lossy().replace('\\', "/"), lines: hits });
     }
 
-    if let Some(glob) = file_glob {
-        cmd.arg("-g").arg(glob);
+    if files.is_empty() {
+        return ToolResult::ok(if timed_out { super::deadline::partial_note() } else { "No matches found" });
     }
-
-    cmd.arg(pattern).arg(&search_path).current_dir(workdir);
-
-    match cmd.output() {
-        Ok(output) => {
-            if output.status.success() || output.status.code() == Some(1) {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                if stdout.is_empty() {
-                    ToolResult::ok("No matches found")
-                } else {
-                    let result = stdout
-                        .lines()
-                        .take(50)
-                        .map(|line| {
-                            if let Some(rel) =
-                                line.strip_prefix(&search_path.to_string_lossy().to_string())
-                            {
-                                rel.trim_start_matches('/').to_string()
-                            } else {
-                                line.to_string()
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n");
-                    ToolResult::ok(result)
-                }
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                ToolResult::err(format!("ripgrep error: {}", stderr))
-            }
-        }
-        Err(e) => {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                tracing::debug!("ripgrep not found, falling back to regex search");
-                return files(args, workdir).await;
-            }
-            ToolResult::err(format!("Failed to run ripgrep: {}", e))
-        }
+    let mut output = format_hits(&files);
+    if truncated {
+        output.push_str(&format!(
+            "\n\n... (stopped at {max} matches in {} files; narrow the pattern, path or glob, or raise max_results)",
+            files.len()
+        ));
+    } else if timed_out {
+        output.push_str(&format!("\n\n{}", super::deadline::partial_note()));
     }
+    ToolResult::ok(output)
+}
+
+fn format_hits(files: &[FileHits]) -> String {
+    files
+        .iter()
+        .map(|file| {
+            let lines: Vec<String> = file
+                .lines
+                .iter()
+                .map(|line| match line {
+                    Some((n, true, text)) => format!("{n}: {text}"),
+                    Some((n, false, text)) => format!("{n}- {text}"),
+                    None => "--".to_string(),
+                })
+                .collect();
+            format!("{}\n{}", file.path, lines.join("\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
 }
 
 // ── Glob search ──────────────────────────────────────────────────
 
-/// Find files matching a glob pattern
-pub async fn glob_search(args: &Value, workdir: &Path) -> ToolResult {
-    let Some(pattern) = args.get("pattern").and_then(|v| v.as_str()) else {
-        return ToolResult::err("Missing 'pattern' parameter");
-    };
+/// Paths listed per call.
+const GLOB_MAX_RESULTS: usize = 100;
+/// Matches collected for sorting before giving up on the rest.
+const GLOB_MAX_SCANNED: usize = 10_000;
+
+/// Include and `!`-exclude globs, matched against paths relative to the
+/// search directory. `*` stays within one directory, `**` crosses them,
+/// `{a,b}` picks alternatives.
+struct GlobFilter {
+    include: globset::GlobSet,
+    exclude: globset::GlobSet,
+    max_depth: usize,
+}
 
-    let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
-    let search_path = workdir.join(path);
+impl GlobFilter {
+    fn new(patterns: &[&str]) -> Result<Self, String> {
+        let build = |patterns: &[&str]| {
+            let mut set = globset::GlobSetBuilder::new();
+            for pattern in patterns {
+                let glob = globset::GlobBuilder::new(pattern.trim_start_matches("./"))
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| format!("Invalid glob '{pattern}': {e}"))?;
+                set.add(glob);
+            }
+            set.build().map_err(|e| e.to_string())
+        };
+        let (excludes, mut includes): (Vec<&str>, Vec<&str>) = patterns.iter().partition(|p| p.starts_with('!'));
+        let excludes: Vec<&str> = excludes.iter().map(|p| &p[1..]).collect();
+        if includes.is_empty() {
+            includes.push("**");
+        }
+        let max_depth = if includes.iter().any(|p| p.contains("**")) {
+            10
+        } else {
+            includes.iter().map(|p| p.matches('/').count() + 1).max().unwrap_or(1)
+        };
+        Ok(Self { include: build(&includes)?, exclude: build(&excludes)?, max_depth })
+    }
 
-    let mut results = Vec::new();
-    let max_results = 100;
+    fn is_match(&self, rel: &str) -> bool {
+        self.include.is_match(rel) && !self.exclude.is_match(rel)
+    }
+}
 
-    let is_recursive = pattern.contains("**");
-    let file_pattern = pattern.trim_start_matches("**/");
+/// Find files matching glob patterns, sorted by path, mtime or size.
+pub async fn glob_search(args: GlobArgs, workdir: &Path) -> ToolResult {
+    use super::args::GlobSort;
 
-    let walker = if is_recursive {
-        WalkDir::new(&search_path).max_depth(10)
-    } else {
-        WalkDir::new(&search_path).max_depth(1)
+    let patterns: Vec<&str> = std::iter::once(args.pattern.as_str())
+        .chain(args.patterns.iter().map(String::as_str))
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    if patterns.is_empty() {
+        return ToolResult::err("Missing 'pattern' parameter");
+    }
+    let filter = match GlobFilter::new(&patterns) {
+        Ok(filter) => filter,
+        Err(e) => return ToolResult::err(e),
     };
+    let search_path = workdir.join(&args.path);
 
-    for entry in walker
+    let mut found: Vec<(String, std::fs::Metadata)> = Vec::new();
+    let mut notes = Vec::new();
+    for entry in WalkDir::new(&search_path)
+        .max_depth(filter.max_depth)
         .into_iter()
         .filter_entry(|e| {
             let name = e.file_name().to_string_lossy();
@@ -436,31 +543,56 @@ pub async fn glob_search(args: &Value, workdir: &Path) -> ToolResult {
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
     {
-        let file_name = entry.file_name().to_string_lossy();
-
-        if glob_match(file_pattern, &file_name) {
-            let rel_path = entry
-                .path()
-                .strip_prefix(workdir)
-                .unwrap_or(entry.path());
-            results.push(rel_path.display().to_string());
-
-            if results.len() >= max_results {
-                results.push(format!("... (truncated at {} results)", max_results));
-                break;
-            }
+        if super::deadline::expired() {
+            notes.push(super::deadline::partial_note().to_string());
+            break;
+        }
+        let Ok(rel) = entry.path().strip_prefix(&search_path) else {
+            continue;
+        };
+        if !filter.is_match(&rel.to_string_lossy().replace('\\', "/")) {
+            continue;
+        }
+        let shown = entry.path().strip_prefix(workdir).unwrap_or(entry.path());
+        if let Ok(meta) = entry.metadata() {
+            found.push((shown.display().to_string(), meta));
+        }
+        if found.len() >= GLOB_MAX_SCANNED {
+            notes.push(format!("... (stopped scanning at {GLOB_MAX_SCANNED} matches; narrow the pattern)"));
+            break;
         }
     }
 
-    if results.is_empty() {
-        ToolResult::ok("No files found matching pattern")
-    } else {
-        ToolResult::ok(format!(
-            "Found {} files:\n{}",
-            results.len(),
-            results.join("\n")
-        ))
+    if found.is_empty() {
+        return ToolResult::ok("No files found matching pattern");
+    }
+    let sort = args.sort.unwrap_or(GlobSort::Path);
+    match sort {
+        GlobSort::Path => found.sort_by(|a, b| a.0.cmp(&b.0)),
+        GlobSort::Mtime => found.sort_by_key(|(_, meta)| std::cmp::Reverse(meta.modified().ok())),
+        GlobSort::Size => found.sort_by_key(|(_, meta)| std::cmp::Reverse(meta.len())),
     }
+    let total = found.len();
+    if total > GLOB_MAX_RESULTS {
+        notes.insert(0, format!("... ({} more not shown)", total - GLOB_MAX_RESULTS));
+    }
+    let lines: Vec<String> = found
+        .iter()
+        .take(GLOB_MAX_RESULTS)
+        .map(|(path, meta)| match sort {
+            GlobSort::Path => path.clone(),
+            GlobSort::Mtime => {
+                let modified = meta.modified().map(chrono::DateTime::<chrono::Local>::from);
+                match modified {
+                    Ok(t) => format!("{path}  ({})", t.format("%Y-%m-%d %H:%M")),
+                    Err(_) => path.clone(),
+                }
+            }
+            GlobSort::Size => format!("{path}  ({} bytes)", meta.len()),
+        })
+        .chain(notes)
+        .collect();
+    ToolResult::ok(format!("Found {total} files:\n{}", lines.join("\n")))
 }
 
 /// Workspace symbols search (fallback when forge-search not available)
@@ -485,6 +617,10 @@ pub async fn workspace_symbols(args: &Value, workdir: &Path) -> ToolResult {
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
     {
+        if super::deadline::expired() {
+            results.push(super::deadline::partial_note().to_string());
+            break;
+        }
         let file_path = entry.path();
         let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
         
@@ -523,15 +659,6 @@ fn truncate_lines(s: &str, max_lines: usize) -> String {
     }
 }
 
-fn glob_match(pattern: &str, name: &str) -> bool {
-    if pattern.starts_with("*.") {
-        let ext = pattern.trim_start_matches("*.");
-        name.ends_with(&format!(".{ext}"))
-    } else {
-        name.contains(pattern)
-    }
-}
-
 fn is_binary_extension(name: &str) -> bool {
     let binary_ext = [
         ".png", ".jpg", ".jpeg", ".gif", ".ico", ".webp", ".exe", ".dll", ".so", ".dylib",
@@ -578,8 +705,9 @@ pub fn pre_search_workspace(query: &str, workdir: &Path) -> String {
     // Build a ripgrep-compatible pattern: keyword1|keyword2|...
     let pattern = keywords.join("|");
 
-    let mut cmd = std::process::Command::new("rg");
-    cmd.arg("--line-number")
+    let mut cmd = tokio::process::Command::new("rg");
+    cmd.kill_on_drop(true)
+        .arg("--line-number")
         .arg("--no-heading")
         .arg("--color=never")
         .arg("--max-count=5")
@@ -639,4 +767,63 @@ mod tests {
         assert!(!is_indexable_file("README.md"));
         assert!(!is_indexable_file("Cargo.toml"));
     }
+
+    #[test]
+    fn test_symbol_search_finds_definitions() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("auth.rs"), "fn helper() {}\n\nfn refresh_token() {\n    helper();\n}\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "refresh token\n").unwrap();
+        let hits = symbol_search("where is the token refreshed", dir.path());
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].starts_with("## auth.rs:") && hits[0].contains("`refresh_token`"));
+        assert!(symbol_search("the", dir.path()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_grep_groups_and_merges_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let body: String = (1..=20).map(|n| if n == 5 || n == 7 { format!("hit {n}\n") } else { format!("line {n}\n") }).collect();
+        std::fs::write(dir.path().join("a.txt"), body).unwrap();
+        std::fs::write(dir.path().join("b.rs"), "fn hit() {}\n").unwrap();
+        let args = |glob: Option<&str>, max: Option<u64>| GrepArgs {
+            pattern: "hit".into(),
+            path: ".".into(),
+            glob: glob.map(String::from),
+            case_insensitive: false,
+            context: 1,
+            max_results: max,
+        };
+
+        let result = grep(args(Some("*.txt"), None), dir.path()).await;
+        // Context of the two matches overlaps at line 6: one group, no repeats
+        assert_eq!(result.output, "a.txt\n4- line 4\n5: hit 5\n6- line 6\n7: hit 7\n8- line 8");
+
+        let result = grep(args(None, Some(1)), dir.path()).await;
+        assert!(result.output.contains("stopped at 1 matches"), "{}", result.output);
+    }
+
+    #[tokio::test]
+    async fn test_glob_braces_negation_and_sort() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/tests")).unwrap();
+        for (path, size) in [("src/a.ts", 10), ("src/b.tsx", 300), ("src/c.rs", 1), ("src/tests/d.ts", 20), ("e.ts", 5)] {
+            std::fs::write(dir.path().join(path), "x".repeat(size)).unwrap();
+        }
+        let args = |pattern: &str, patterns: &[&str], sort| GlobArgs {
+            pattern: pattern.into(),
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            path: ".".into(),
+            sort,
+        };
+
+        let result = glob_search(args("src/**/*.{ts,tsx}", &["!**/tests/**"], None), dir.path()).await;
+        assert_eq!(result.output, "Found 2 files:\nsrc/a.ts\nsrc/b.tsx");
+
+        // `*` doesn't cross directories; several patterns combine
+        let result = glob_search(args("*.ts", &["src/*.rs"], None), dir.path()).await;
+        assert_eq!(result.output, "Found 2 files:\ne.ts\nsrc/c.rs");
+
+        let result = glob_search(args("**/*.ts*", &[], Some(super::super::args::GlobSort::Size)), dir.path()).await;
+        assert!(result.output.starts_with("Found 4 files:\nsrc/b.tsx  (300 bytes)\nsrc/tests/d.ts  (20 bytes)"), "{}", result.output);
+    }
 }
@@ -0,0 +1,208 @@
+//! Which tools the model gets to see.
+//!
+//! Smaller models pick tools badly once there are thirty of them, so the
+//! definitions can be cut down per model: whole categories switched off,
+//! and what's left ranked (file and search tools first, extras last) and
+//! truncated to a maximum count. Configured under `[tools]`:
+//!
+//! ```toml
+//! [tools]
+//! disabled = "web, ui"       # categories to leave out
+//! max = 24                   # for every model
+//!
+//! [tools.max_by_model]       # overrides `max` when the model name contains the key
+//! "gpt-4o-mini" = 12
+//! "flash" = 16
+//! ```
+//!
+//! With nothing configured every tool is offered and the prompt doesn't
+//! mention the tool set.
+
+use serde_json::Value;
+
+/// Broad tool groups, in ranking order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    Files,
+    Search,
+    Run,
+    Interaction,
+    Code,
+    Git,
+    Project,
+    Ui,
+    Web,
+    Plugin,
+}
+
+impl Category {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "files" | "file" => Some(Self::Files),
+            "search" => Some(Self::Search),
+            "run" | "shell" => Some(Self::Run),
+            "interaction" => Some(Self::Interaction),
+            "code" | "lsp" => Some(Self::Code),
+            "git" => Some(Self::Git),
+            "project" => Some(Self::Project),
+            "ui" => Some(Self::Ui),
+            "web" | "browser" => Some(Self::Web),
+            "plugin" | "plugins" => Some(Self::Plugin),
+            _ => None,
+        }
+    }
+
+    /// The category of tool `name`; unknown names are plugin tools.
+    pub fn of(name: &str) -> Self {
+        match name {
+            "read_file" | "write_file" | "write_file_range" | "edit_file" | "apply_patch" | "list_files" | "delete_file" | "restore_file" => Self::Files,
+            "grep" | "glob" | "codebase_search" | "workspace_symbols" => Self::Search,
+            "run" | "process" | "port" | "shell_session" => Self::Run,
+            "ask_followup_question" | "think" | "attempt_completion" | "plan_mode_respond" | "act_mode_respond"
+            | "focus_chain" => Self::Interaction,
+            "lsp" | "references" | "diagnostics" | "generate_tests" | "review" | "audit_dependencies" => Self::Code,
+            "git" | "workspace_diff" => Self::Git,
+            "list_run_configs" | "run_project" | "stop_project" | "read_run_output" | "sdk_manager" | "plugins" => Self::Project,
+            "show_code" | "show_diagram" | "open_in_editor" | "modify_settings" => Self::Ui,
+            "fetch" => Self::Web,
+            _ => Self::Plugin,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolSelection {
+    pub max_tools: Option<usize>,
+    pub disabled: Vec<Category>,
+}
+
+impl ToolSelection {
+    /// The configured selection for `model` (the server default when `None`).
+    pub fn for_model(model: Option<&str>) -> Self {
+        let config = crate::config::active();
+        let by_model = config.section("tools.max_by_model");
+        let max_tools = model
+            .and_then(|model| {
+                // Longest matching key wins, so "gpt-4o-mini" beats "gpt-4o"
+                by_model
+                    .iter()
+                    .filter(|(key, _)| model.contains(key.as_str()))
+                    .max_by_key(|(key, _)| key.len())
+                    .map(|(_, value)| value.as_str())
+            })
+            .or_else(|| config.get("tools.max"))
+            .and_then(|v| v.trim().parse().ok())
+            .filter(|&max| max > 0);
+        let disabled = config.get("tools.disabled").map(parse_categories).unwrap_or_default();
+        Self { max_tools, disabled }
+    }
+
+    /// For the configured planner model.
+    pub fn configured() -> Self {
+        let model = crate::model_routing::model_for(crate::model_routing::ModelRole::Planner);
+        Self::for_model(model.as_deref())
+    }
+
+    /// True when nothing is filtered.
+    pub fn is_unrestricted(&self) -> bool {
+        self.max_tools.is_none() && self.disabled.is_empty()
+    }
+
+    /// Drop disabled categories, rank by category (stable within one), and
+    /// keep at most `max_tools`.
+    pub fn select(&self, definitions: Vec<Value>) -> Vec<Value> {
+        let mut tools: Vec<(Category, Value)> = definitions
+            .into_iter()
+            .map(|d| (Category::of(d["name"].as_str().unwrap_or("")), d))
+            .filter(|(category, _)| !self.disabled.contains(category))
+            .collect();
+        tools.sort_by_key(|(category, _)| *category);
+        if let Some(max) = self.max_tools {
+            tools.truncate(max);
+        }
+        tools.into_iter().map(|(_, d)| d).collect()
+    }
+}
+
+/// Tool definitions after the configured selection.
+pub fn active_definitions(plan_mode: bool) -> Vec<Value> {
+    ToolSelection::configured().select(super::definitions(plan_mode))
+}
+
+/// Names of `definitions`, for the request and the prompt.
+pub fn names(definitions: &[Value]) -> Vec<String> {
+    definitions.iter().filter_map(|d| d["name"].as_str().map(String::from)).collect()
+}
+
+/// Put the tool set into a chat request: `allowed_tools` (names) when the
+/// selection restricts it, and the definitions of selected plugin tools as
+/// `extra_tools` since the server doesn't know those.
+pub fn apply(body: &mut Value, selection: &ToolSelection, definitions: &[Value]) {
+    if !selection.is_unrestricted() {
+        body["allowed_tools"] = serde_json::json!(names(definitions));
+    }
+    let plugin_tools: Vec<Value> = definitions
+        .iter()
+        .filter(|d| d["name"].as_str().is_some_and(super::plugin::is_registered))
+        .cloned()
+        .collect();
+    if !plugin_tools.is_empty() {
+        body["extra_tools"] = Value::Array(plugin_tools);
+    }
+}
+
+/// The prompt line describing the active tool set; empty when unrestricted.
+pub fn prompt_section(selection: &ToolSelection, definitions: &[Value]) -> String {
+    if selection.is_unrestricted() {
+        return String::new();
+    }
+    format!("Only these tools are available: {}", names(definitions).join(", "))
+}
+
+/// `"web, ui"` or the TOML array form `["web", "ui"]`.
+fn parse_categories(value: &str) -> Vec<Category> {
+    value
+        .trim_matches(|c| c == '[' || c == ']')
+        .split(',')
+        .map(|s| s.trim().trim_matches('"'))
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| {
+            let category = Category::parse(s);
+            if category.is_none() {
+                tracing::warn!("Unknown tool category in tools.disabled: {s}");
+            }
+            category
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn defs(names: &[&str]) -> Vec<Value> {
+        names.iter().map(|n| json!({ "name": n })).collect()
+    }
+
+    #[test]
+    fn test_select_ranks_filters_and_truncates() {
+        let all = defs(&["show_code", "attempt_completion", "grep", "fetch", "read_file", "git", "my_plugin_tool"]);
+        assert_eq!(names(&ToolSelection::default().select(all.clone())).len(), 7);
+
+        let selection = ToolSelection { max_tools: Some(4), disabled: vec![Category::Web] };
+        assert_eq!(names(&selection.select(all.clone())), ["read_file", "grep", "attempt_completion", "git"]);
+
+        let selection = ToolSelection { max_tools: None, disabled: vec![Category::Ui, Category::Plugin] };
+        assert_eq!(
+            names(&selection.select(all)),
+            ["read_file", "grep", "attempt_completion", "git", "fetch"]
+        );
+    }
+
+    #[test]
+    fn test_parse_categories() {
+        assert_eq!(parse_categories("web, ui"), vec![Category::Web, Category::Ui]);
+        assert_eq!(parse_categories("[\"browser\", \"nope\"]"), vec![Category::Web]);
+    }
+}
@@ -0,0 +1,325 @@
+//! `modify_settings`: the agent changing the IDE's settings.toml and
+//! keymaps.toml for requests like "switch me to the Lapce Light theme".
+//!
+//! Keys are checked against `defaults/settings.toml` (every setting the
+//! editor reads, with its type), values must have that type, the few
+//! string settings with fixed options must use one of them, and themes
+//! must be installed. The change is shown as a diff; `preview` stops there.
+//! The IDE runs the tool, since only it knows where its config lives; the
+//! editor picks the files up as they change.
+
+use std::path::{Path, PathBuf};
+
+use toml_edit::{Document, Item};
+
+use super::args::{KeymapChange, ModifySettingsArgs, SettingChange};
+use super::ToolResult;
+
+const DEFAULT_SETTINGS: &str = include_str!("../../../defaults/settings.toml");
+
+/// Built-in themes, besides those in theme files and plugins.
+const BUILTIN_COLOR_THEMES: &[&str] = &["Lapce Dark", "Lapce Light"];
+const BUILTIN_ICON_THEMES: &[&str] = &["Lapce Codicons"];
+
+/// String settings that only take certain values.
+const OPTIONS: &[(&str, &[&str])] = &[
+    ("editor.wrap-style", &["none", "editor-width", "wrap-width"]),
+    ("editor.render-whitespace", &["none", "all", "boundary", "trailing"]),
+    ("editor.double-click", &["single", "file", "all"]),
+    ("ui.tab-separator-height", &["Content", "Full"]),
+    ("ui.tab-close-button", &["Left", "Right", "Off"]),
+];
+
+const MODIFIERS: &[&str] = &["ctrl", "alt", "shift", "meta", "cmd", "super"];
+
+/// Where the IDE keeps its config.
+#[derive(Debug, Clone)]
+pub struct SettingsFiles {
+    pub settings: PathBuf,
+    pub keymaps: PathBuf,
+}
+
+/// Installed theme names.
+#[derive(Debug, Clone, Default)]
+pub struct Themes {
+    pub color: Vec<String>,
+    pub icon: Vec<String>,
+}
+
+impl Themes {
+    /// The built-in themes plus those named in `files` (theme TOML files
+    /// from the themes directory and plugins).
+    pub fn load(files: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut themes = Self {
+            color: BUILTIN_COLOR_THEMES.iter().map(|t| t.to_string()).collect(),
+            icon: BUILTIN_ICON_THEMES.iter().map(|t| t.to_string()).collect(),
+        };
+        for file in files {
+            let Some(table) = std::fs::read_to_string(&file).ok().and_then(|t| t.parse::<toml::Table>().ok()) else {
+                continue;
+            };
+            let name = |section: &str| table.get(section)?.get("name")?.as_str().map(String::from);
+            themes.color.extend(name("color-theme"));
+            themes.icon.extend(name("icon-theme"));
+        }
+        themes
+    }
+}
+
+/// `value` checked against the default of setting `key` (`section.name`),
+/// converted to its TOML type.
+pub fn validate(key: &str, value: &serde_json::Value, themes: &Themes) -> Result<toml::Value, String> {
+    let defaults: toml::Table = DEFAULT_SETTINGS.parse().expect("defaults/settings.toml is valid");
+    let mut default = None;
+    let mut table = Some(&defaults);
+    for part in key.split('.') {
+        default = table.and_then(|t| t.get(part));
+        table = default.and_then(|d| d.as_table());
+    }
+    let Some(default) = default.filter(|_| key.contains('.')) else {
+        return Err(format!("Unknown setting '{key}'"));
+    };
+    let wrong_type = || format!("{key} takes type {}, not {value}", default.type_str());
+    let value = match (default, value) {
+        (toml::Value::Boolean(_), serde_json::Value::Bool(b)) => toml::Value::Boolean(*b),
+        (toml::Value::Integer(_), serde_json::Value::Number(n)) => toml::Value::Integer(n.as_i64().ok_or_else(wrong_type)?),
+        (toml::Value::Float(_), serde_json::Value::Number(n)) => toml::Value::Float(n.as_f64().ok_or_else(wrong_type)?),
+        (toml::Value::String(_), serde_json::Value::String(s)) => toml::Value::String(s.clone()),
+        (toml::Value::Table(_), _) => return Err(format!("{key} is a section; set the keys in it")),
+        _ => return Err(wrong_type()),
+    };
+    if let (Some((_, options)), Some(s)) = (OPTIONS.iter().find(|(k, _)| *k == key), value.as_str()) {
+        if !options.contains(&s) {
+            return Err(format!("{key} must be one of: {}", options.join(", ")));
+        }
+    }
+    let installed = match key {
+        "core.color-theme" => Some(&themes.color),
+        "core.icon-theme" => Some(&themes.icon),
+        _ => None,
+    };
+    if let (Some(installed), Some(s)) = (installed, value.as_str()) {
+        // Theme names are matched case-insensitively, as the editor does
+        return match installed.iter().find(|t| t.eq_ignore_ascii_case(s)) {
+            Some(name) => Ok(toml::Value::String(name.clone())),
+            None => Err(format!(
+                "No theme named '{s}' is installed. Installed: {}. Themes come from plugins; the plugins tool can find one.",
+                installed.join(", ")
+            )),
+        };
+    }
+    Ok(value)
+}
+
+/// Whether `key` is a key binding like `ctrl+shift+p` or a chord
+/// `ctrl+k ctrl+s`.
+fn valid_key(key: &str) -> bool {
+    !key.trim().is_empty()
+        && key.split_whitespace().all(|stroke| {
+            let mut parts: Vec<&str> = stroke.split('+').collect();
+            let last = parts.pop().unwrap_or_default();
+            // `ctrl++` binds the plus key
+            let last = if last.is_empty() && stroke.ends_with("++") {
+                parts.pop();
+                "+"
+            } else {
+                last
+            };
+            !last.is_empty() && parts.iter().all(|m| MODIFIERS.contains(&m.to_ascii_lowercase().as_str()))
+        })
+}
+
+fn validate_keymap(keymap: &KeymapChange) -> Result<(), String> {
+    if !valid_key(&keymap.key) {
+        return Err(format!("'{}' isn't a key binding; use e.g. ctrl+shift+p, or ctrl+k ctrl+s for a chord", keymap.key));
+    }
+    let command = keymap.command.strip_prefix('-').unwrap_or(&keymap.command);
+    if command.is_empty() || !command.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-')) {
+        return Err(format!("'{}' isn't a command id (e.g. palette.command, or -command to unbind)", keymap.command));
+    }
+    if let Some(mode) = &keymap.mode {
+        if !mode.chars().all(|c| "invt".contains(c)) {
+            return Err(format!("mode '{mode}' must be made of i, n, v and t"));
+        }
+    }
+    Ok(())
+}
+
+/// `content` (a settings.toml) with `changes` made, comments kept.
+pub fn apply_settings(content: &str, changes: &[(String, toml::Value)]) -> Result<String, String> {
+    let mut doc: Document = content.parse().map_err(|e| format!("settings.toml doesn't parse: {e}"))?;
+    for (key, value) in changes {
+        let parts: Vec<&str> = key.split('.').collect();
+        let (last, sections) = parts.split_last().ok_or("empty key")?;
+        let mut item = doc.as_item_mut();
+        for section in sections {
+            if item.get(*section).is_none() {
+                item[*section] = toml_edit::table();
+            }
+            item = &mut item[*section];
+        }
+        item[*last] = toml_value(value);
+    }
+    Ok(doc.to_string())
+}
+
+fn toml_value(value: &toml::Value) -> Item {
+    match value {
+        toml::Value::Boolean(b) => toml_edit::value(*b),
+        toml::Value::Integer(i) => toml_edit::value(*i),
+        toml::Value::Float(f) => toml_edit::value(*f),
+        other => toml_edit::value(other.as_str().unwrap_or_default()),
+    }
+}
+
+/// `content` (a keymaps.toml) with `keymaps` appended.
+pub fn apply_keymaps(content: &str, keymaps: &[KeymapChange]) -> Result<String, String> {
+    let mut doc: Document = content.parse().map_err(|e| format!("keymaps.toml doesn't parse: {e}"))?;
+    if doc.get("keymaps").is_none() {
+        doc["keymaps"] = Item::ArrayOfTables(Default::default());
+    }
+    let list = doc["keymaps"].as_array_of_tables_mut().ok_or("keymaps in keymaps.toml isn't a list of tables")?;
+    for keymap in keymaps {
+        let mut table = toml_edit::Table::new();
+        table["key"] = toml_edit::value(keymap.key.as_str());
+        table["command"] = toml_edit::value(keymap.command.as_str());
+        if let Some(mode) = &keymap.mode {
+            table["mode"] = toml_edit::value(mode.as_str());
+        }
+        if let Some(when) = &keymap.when {
+            table["when"] = toml_edit::value(when.as_str());
+        }
+        list.push(table);
+    }
+    Ok(doc.to_string())
+}
+
+/// The arguments checked; the errors of all invalid ones together.
+pub fn check(args: &ModifySettingsArgs, themes: &Themes) -> Result<Vec<(String, toml::Value)>, String> {
+    if args.settings.is_empty() && args.keymaps.is_empty() {
+        return Err("Nothing to change: give settings and/or keymaps".to_string());
+    }
+    let mut errors = Vec::new();
+    let mut changes = Vec::new();
+    for SettingChange { key, value } in &args.settings {
+        let key = key.trim().to_string();
+        match validate(&key, value, themes) {
+            Ok(value) => changes.push((key, value)),
+            Err(e) => errors.push(e),
+        }
+    }
+    errors.extend(args.keymaps.iter().filter_map(|k| validate_keymap(k).err()));
+    if errors.is_empty() { Ok(changes) } else { Err(errors.join("\n")) }
+}
+
+fn diff(path: &Path, old: &str, new: &str) -> String {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(2)
+        .header(&name, &name)
+        .to_string()
+}
+
+/// Validate and make the change (or with `preview`, only show it).
+pub fn modify(args: &ModifySettingsArgs, files: &SettingsFiles, themes: &Themes) -> ToolResult {
+    let changes = match check(args, themes) {
+        Ok(changes) => changes,
+        Err(e) => return ToolResult::err(e),
+    };
+    let mut edits: Vec<(&Path, String, String)> = Vec::new();
+    if !changes.is_empty() {
+        let old = std::fs::read_to_string(&files.settings).unwrap_or_default();
+        match apply_settings(&old, &changes) {
+            Ok(new) => edits.push((&files.settings, old, new)),
+            Err(e) => return ToolResult::err(e),
+        }
+    }
+    if !args.keymaps.is_empty() {
+        let old = std::fs::read_to_string(&files.keymaps).unwrap_or_default();
+        match apply_keymaps(&old, &args.keymaps) {
+            Ok(new) => edits.push((&files.keymaps, old, new)),
+            Err(e) => return ToolResult::err(e),
+        }
+    }
+    let diffs: String = edits.iter().map(|(path, old, new)| diff(path, old, new)).collect();
+    if diffs.is_empty() {
+        return ToolResult::ok("The settings already have these values");
+    }
+    if args.preview {
+        return ToolResult::ok(format!("Preview, nothing written:\n{diffs}"));
+    }
+    for (path, _, new) in &edits {
+        if let Err(e) = std::fs::write(path, new) {
+            return ToolResult::err(format!("Writing {} failed: {e}", path.display()));
+        }
+    }
+    ToolResult::ok(format!("Updated; the IDE applies it right away.\n{diffs}"))
+}
+
+/// Checks the arguments; only the IDE can run the tool.
+pub async fn modify_settings(args: ModifySettingsArgs, _workdir: &Path) -> ToolResult {
+    // Themes are checked by the IDE, which knows what's installed
+    let errors: Vec<String> = match check(&args, &Themes::default()) {
+        Ok(_) => Vec::new(),
+        Err(e) => e.lines().filter(|l| !l.starts_with("No theme named")).map(String::from).collect(),
+    };
+    if errors.is_empty() { ToolResult::ok("PENDING_IDE_EXECUTION") } else { ToolResult::err(errors.join("\n")) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_modify_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let theme = dir.path().join("gruvbox.toml");
+        std::fs::write(&theme, "[color-theme]\nname = \"Gruvbox Dark\"\n").unwrap();
+        let themes = Themes::load([theme]);
+
+        assert_eq!(validate("editor.font-size", &json!(16), &themes).unwrap(), toml::Value::Integer(16));
+        assert_eq!(validate("ui.scale", &json!(1), &themes).unwrap(), toml::Value::Float(1.0));
+        assert_eq!(
+            validate("core.color-theme", &json!("gruvbox dark"), &themes).unwrap(),
+            toml::Value::String("Gruvbox Dark".into())
+        );
+        assert!(validate("core.color-theme", &json!("Solarized"), &themes).unwrap_err().contains("Lapce Dark, Lapce Light, Gruvbox Dark"));
+        assert!(validate("editor.font-size", &json!("16"), &themes).unwrap_err().contains("takes type integer"));
+        assert!(validate("editor.wrap-style", &json!("sometimes"), &themes).is_err());
+        assert!(validate("editor.no-such-thing", &json!(true), &themes).is_err());
+        assert!(validate("terminal.profiles", &json!("x"), &themes).is_err());
+        assert!(validate("modal", &json!(true), &themes).is_err());
+
+        let files = SettingsFiles { settings: dir.path().join("settings.toml"), keymaps: dir.path().join("keymaps.toml") };
+        std::fs::write(&files.settings, "# mine\n[core]\nmodal = true # vim\n").unwrap();
+        let args: ModifySettingsArgs = serde_json::from_value(json!({
+            "settings": [
+                { "key": "core.color-theme", "value": "Gruvbox Dark" },
+                { "key": "editor.modal-mode-relative-line-numbers", "value": true },
+            ],
+            "keymaps": [{ "key": "ctrl+k ctrl+t", "command": "palette.color_theme" }],
+            "preview": true,
+        }))
+        .unwrap();
+        let preview = modify(&args, &files, &themes);
+        assert!(preview.success && preview.output.contains("+color-theme = \"Gruvbox Dark\""));
+        assert!(!files.keymaps.exists());
+
+        let args = ModifySettingsArgs { preview: false, ..args };
+        assert!(modify(&args, &files, &themes).success);
+        let settings = std::fs::read_to_string(&files.settings).unwrap();
+        assert!(settings.starts_with("# mine\n[core]\nmodal = true # vim\ncolor-theme = \"Gruvbox Dark\"\n"));
+        assert!(settings.contains("[editor]\nmodal-mode-relative-line-numbers = true\n"));
+        let keymaps = std::fs::read_to_string(&files.keymaps).unwrap();
+        assert_eq!(keymaps, "[[keymaps]]\nkey = \"ctrl+k ctrl+t\"\ncommand = \"palette.color_theme\"\n");
+        assert_eq!(modify(&args, &files, &themes).output, "The settings already have these values");
+
+        let bad: ModifySettingsArgs = serde_json::from_value(json!({
+            "keymaps": [{ "key": "hyper+x", "command": "x" }, { "key": "ctrl++", "command": "zoom in" }],
+        }))
+        .unwrap();
+        assert_eq!(check(&bad, &themes).unwrap_err().lines().count(), 2);
+    }
+}
@@ -0,0 +1,290 @@
+//! Test generation for a single symbol.
+//!
+//! Sub-flow: collect the symbol's definition and references, ask forge-search
+//! for a test file, write it through the normal `write_file` path, run it, and
+//! feed failures back until the tests pass or `max_retries` is exhausted.
+
+use super::{code, execute, files, FileEditMeta, ToolResult};
+use serde_json::Value;
+use std::path::Path;
+
+/// Default number of fix-up rounds after the first attempt.
+const DEFAULT_MAX_RETRIES: u64 = 2;
+/// Hard cap on fix-up rounds.
+const MAX_RETRIES_CAP: u64 = 5;
+/// Max chars of references / test output sent back to the model.
+const MAX_CONTEXT_CHARS: usize = 6_000;
+
+/// Where a generated test lives and how to run it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestTarget {
+    pub path: String,
+    pub command: String,
+    pub language: &'static str,
+}
+
+/// Generate tests for a symbol, run them, and iterate on failures.
+pub async fn generate_tests(args: &Value, workdir: &Path) -> ToolResult {
+    let Some(symbol) = args.get("symbol").and_then(|v| v.as_str()) else {
+        return ToolResult::err("Missing 'symbol' parameter");
+    };
+    let max_retries = args
+        .get("max_retries")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_MAX_RETRIES)
+        .min(MAX_RETRIES_CAP);
+
+    // ── Collect context ─────────────────────────────────────────
+    let def_args = serde_json::json!({
+        "symbol": symbol,
+        "path": args.get("path").and_then(|v| v.as_str()).unwrap_or("."),
+    });
+    let definition = code::get_definition(&def_args, workdir).await;
+    if !definition.success {
+        return ToolResult::err(format!("Cannot generate tests: {}", definition.output));
+    }
+    let Some(source_file) = definition_file(&definition.output) else {
+        return ToolResult::err(format!("Could not locate the file defining '{}'", symbol));
+    };
+    let references = code::find_references(&def_args, workdir).await;
+
+    let Some(mut target) = default_test_target(&source_file, symbol) else {
+        return ToolResult::err(format!(
+            "Don't know how to generate tests for '{}'. Pass 'test_path' and 'test_command'.",
+            source_file
+        ));
+    };
+    if let Some(p) = args.get("test_path").and_then(|v| v.as_str()) {
+        target.path = p.to_string();
+    }
+    if let Some(c) = args.get("test_command").and_then(|v| v.as_str()) {
+        target.command = c.to_string();
+    }
+
+    let full_path = workdir.join(&target.path);
+    let original = std::fs::read_to_string(&full_path).unwrap_or_default();
+    let workspace_id = crate::forge_search::workspace_id(workdir);
+
+    let mut prompt = format!(
+        "Write a {lang} test file at `{path}` for the symbol `{symbol}` defined in `{source}`.\n\
+         Cover the main behaviour and edge cases. Only use APIs that exist in the code below.\n\
+         {existing}\
+         Respond with ONLY the complete file content in a single fenced code block.\n\n\
+         ## Definition\n{definition}\n\n## References\n{references}",
+        lang = target.language,
+        path = target.path,
+        symbol = symbol,
+        source = source_file,
+        existing = if original.is_empty() {
+            String::new()
+        } else {
+            format!("The file already exists; keep its existing tests:\n```\n{}\n```\n", original)
+        },
+        definition = definition.output,
+        references = truncate(&references.output, MAX_CONTEXT_CHARS),
+    );
+
+    // ── Generate → write → run loop ─────────────────────────────
+    let mut last_output = String::new();
+    for attempt in 0..=max_retries {
+        let resp = match crate::forge_search::client()
+            .chat_with_body(&serde_json::json!({
+                "workspace_id": workspace_id,
+                "question": prompt,
+            }))
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => return ToolResult::err(format!("Test generation failed: {}", e)),
+        };
+        let answer = resp.get("answer").and_then(|v| v.as_str()).unwrap_or("");
+        let content = extract_code_block(answer);
+        if content.trim().is_empty() {
+            return ToolResult::err("Test generation returned no code");
+        }
+
+        let written = files::write(
+            // An existing test file is in the prompt, so the model rewrote it knowingly
+            super::args::WriteFileArgs { path: target.path.clone(), content: content.clone(), overwrite: true },
+            workdir,
+        )
+        .await;
+        if !written.success {
+            return written;
+        }
+
+        let run = execute::run(
+            &serde_json::json!({ "command": target.command, "timeout_secs": 300 }),
+            workdir,
+        )
+        .await;
+        let meta = FileEditMeta {
+            path: target.path.clone(),
+            old_content: original.clone(),
+            new_content: content.clone(),
+        };
+        if run.success {
+            return ToolResult::ok(format!(
+                "Tests for '{}' written to {} and passing (attempt {}/{}).\nCommand: {}\n{}",
+                symbol,
+                target.path,
+                attempt + 1,
+                max_retries + 1,
+                target.command,
+                truncate(&run.output, MAX_CONTEXT_CHARS),
+            ))
+            .with_file_edit(meta);
+        }
+
+        tracing::info!("generate_tests: attempt {} for '{}' failed", attempt + 1, symbol);
+        last_output = run.output;
+        prompt = format!(
+            "The test file `{path}` you wrote fails when running `{cmd}`.\n\
+             Fix the tests (not the code under test). Respond with ONLY the complete corrected \
+             file in a single fenced code block.\n\n## Current file\n```\n{content}\n```\n\n\
+             ## Output\n```\n{output}\n```",
+            path = target.path,
+            cmd = target.command,
+            content = content,
+            output = truncate(&last_output, MAX_CONTEXT_CHARS),
+        );
+    }
+
+    let written = std::fs::read_to_string(&full_path).unwrap_or_default();
+    ToolResult::err(format!(
+        "Tests for '{}' in {} still fail after {} attempt(s). The last version was kept for review.\nCommand: {}\n{}",
+        symbol,
+        target.path,
+        max_retries + 1,
+        target.command,
+        truncate(&last_output, MAX_CONTEXT_CHARS),
+    ))
+    .with_file_edit(FileEditMeta {
+        path: target.path,
+        old_content: original,
+        new_content: written,
+    })
+}
+
+/// Pull the file path out of `get_definition` output ("Found ... in path:line").
+fn definition_file(output: &str) -> Option<String> {
+    let first = output.lines().next()?;
+    let loc = first.rsplit(" in ").next()?;
+    let (path, _line) = loc.rsplit_once(':')?;
+    Some(path.trim().to_string())
+}
+
+/// Pick a conventional test location and runner for the source file's language.
+pub fn default_test_target(source_file: &str, symbol: &str) -> Option<TestTarget> {
+    let path = Path::new(source_file);
+    let ext = path.extension().and_then(|e| e.to_str())?;
+    let stem = path.file_stem().and_then(|s| s.to_str())?;
+    let dir = path
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .filter(|p| !p.is_empty());
+    let in_dir = |name: String| match &dir {
+        Some(d) => format!("{}/{}", d, name),
+        None => name,
+    };
+    let snake = symbol
+        .chars()
+        .enumerate()
+        .flat_map(|(i, c)| {
+            let sep = (c.is_uppercase() && i > 0).then_some('_');
+            sep.into_iter().chain(c.to_lowercase())
+        })
+        .collect::<String>();
+
+    let target = match ext {
+        "rs" => {
+            let test = format!("test_{}", snake);
+            TestTarget {
+                path: format!("tests/{}.rs", test),
+                command: format!("cargo test --test {}", test),
+                language: "Rust",
+            }
+        }
+        "py" => {
+            let p = format!("tests/test_{}.py", snake);
+            TestTarget { command: format!("python -m pytest {} -q", p), path: p, language: "Python" }
+        }
+        "go" => TestTarget {
+            path: in_dir(format!("{}_test.go", stem)),
+            command: format!(
+                "go test ./{} -run 'Test{}'",
+                dir.as_deref().unwrap_or("."),
+                symbol
+            ),
+            language: "Go",
+        },
+        "ts" | "tsx" | "js" | "jsx" => {
+            let p = in_dir(format!("{}.test.{}", stem, ext));
+            TestTarget {
+                command: format!("npx --no-install vitest run {0} || npx --no-install jest {0}", p),
+                path: p,
+                language: if ext.starts_with("ts") { "TypeScript" } else { "JavaScript" },
+            }
+        }
+        _ => return None,
+    };
+    Some(target)
+}
+
+/// Return the body of the first fenced code block, or the whole answer.
+fn extract_code_block(answer: &str) -> String {
+    if let Some(start) = answer.find("```") {
+        let after = &answer[start + 3..];
+        // Skip the info string (```rust)
+        let body_start = after.find('\n').map(|i| i + 1).unwrap_or(0);
+        let body = &after[body_start..];
+        let end = body.find("```").unwrap_or(body.len());
+        return body[..end].to_string();
+    }
+    answer.to_string()
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.len() <= max {
+        return s.to_string();
+    }
+    let mut cut = max;
+    while !s.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    format!("{}\n... (truncated)", &s[..cut])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_code_block() {
+        let answer = "Sure:\n```rust\nfn a() {}\n```\ntrailing";
+        assert_eq!(extract_code_block(answer), "fn a() {}\n");
+        assert_eq!(extract_code_block("plain"), "plain");
+    }
+
+    #[test]
+    fn test_definition_file() {
+        let out = "Found function 'parse' in src/parser.rs:42\n  41|...";
+        assert_eq!(definition_file(out).as_deref(), Some("src/parser.rs"));
+        assert_eq!(definition_file("Found in lib/a.py:3\n").as_deref(), Some("lib/a.py"));
+    }
+
+    #[test]
+    fn test_default_test_target() {
+        let rs = default_test_target("src/parser.rs", "ParseError").unwrap();
+        assert_eq!(rs.path, "tests/test_parse_error.rs");
+        assert_eq!(rs.command, "cargo test --test test_parse_error");
+
+        let go = default_test_target("pkg/util/strings.go", "Reverse").unwrap();
+        assert_eq!(go.path, "pkg/util/strings_test.go");
+
+        let ts = default_test_target("web/app.ts", "render").unwrap();
+        assert_eq!(ts.path, "web/app.test.ts");
+
+        assert!(default_test_target("README.md", "x").is_none());
+    }
+}
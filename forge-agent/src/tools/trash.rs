@@ -0,0 +1,191 @@
+//! Recoverable deletes for the agent.
+//!
+//! `delete_file` and V4A `*** Delete File` move paths into
+//! `.forge/trash/<id>/` rather than removing them, next to an `entry.json`
+//! recording where they came from. `restore_file` and the IDE's
+//! "Forge: Restore Last File the Agent Deleted" move them back. Entries
+//! older than a week, or past the newest 200, are pruned on each delete.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const MAX_ENTRIES: usize = 200;
+const ENTRY_FILE: &str = "entry.json";
+const PAYLOAD: &str = "payload";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    /// Directory name under `.forge/trash`.
+    #[serde(skip)]
+    pub id: String,
+    /// Workspace-relative path it was deleted from.
+    pub path: String,
+    /// Unix milliseconds.
+    pub deleted_at: u64,
+    pub is_dir: bool,
+}
+
+pub fn trash_dir(workdir: &Path) -> PathBuf {
+    workdir.join(".forge").join("trash")
+}
+
+/// Move `rel` (workspace-relative) into the trash.
+pub fn move_to_trash(workdir: &Path, rel: &str) -> io::Result<TrashEntry> {
+    let source = workdir.join(rel);
+    let dir = trash_dir(workdir);
+    if source.starts_with(&dir) {
+        return Err(io::Error::other("already in the trash"));
+    }
+    let is_dir = fs::symlink_metadata(&source)?.is_dir();
+    fs::create_dir_all(&dir)?;
+    // The trash is local state, never something to commit
+    let ignore = dir.join(".gitignore");
+    if !ignore.exists() {
+        let _ = fs::write(ignore, "*\n");
+    }
+    prune(workdir, SystemTime::now());
+
+    let deleted_at = now_millis();
+    let (id, entry_dir) = (0..)
+        .map(|n| format!("{deleted_at}-{n}"))
+        .map(|id| (id.clone(), dir.join(id)))
+        .find(|(_, path)| !path.exists())
+        .expect("unbounded ids");
+    fs::create_dir_all(&entry_dir)?;
+    if let Err(e) = move_path(&source, &entry_dir.join(PAYLOAD)) {
+        let _ = fs::remove_dir_all(&entry_dir);
+        return Err(e);
+    }
+    let entry = TrashEntry { id, path: rel.trim_start_matches("./").replace('\\', "/"), deleted_at, is_dir };
+    fs::write(entry_dir.join(ENTRY_FILE), serde_json::to_string_pretty(&entry)?)?;
+    Ok(entry)
+}
+
+/// Everything in the trash, most recently deleted first.
+pub fn list(workdir: &Path) -> Vec<TrashEntry> {
+    let Ok(dirs) = fs::read_dir(trash_dir(workdir)) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<TrashEntry> = dirs
+        .filter_map(Result::ok)
+        .filter_map(|d| {
+            let text = fs::read_to_string(d.path().join(ENTRY_FILE)).ok()?;
+            let mut entry: TrashEntry = serde_json::from_str(&text).ok()?;
+            entry.id = d.file_name().to_string_lossy().into_owned();
+            Some(entry)
+        })
+        .collect();
+    entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then_with(|| b.id.cmp(&a.id)));
+    entries
+}
+
+/// Put a trashed path back where it was deleted from: the latest deletion
+/// of `target` (a path or entry id), or the latest of all when `None`.
+/// Refuses if something already exists there.
+pub fn restore(workdir: &Path, target: Option<&str>) -> Result<TrashEntry, String> {
+    let entries = list(workdir);
+    let entry = match target {
+        Some(target) => {
+            let target = target.trim_start_matches("./").replace('\\', "/");
+            entries.into_iter().find(|e| e.path == target || e.id == target)
+                .ok_or_else(|| format!("Nothing deleted at {target} is in the trash"))?
+        }
+        None => entries.into_iter().next().ok_or("The trash is empty")?,
+    };
+    let destination = workdir.join(&entry.path);
+    if destination.exists() {
+        return Err(format!("{} exists again; move it away before restoring", entry.path));
+    }
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Creating {} failed: {e}", parent.display()))?;
+    }
+    let entry_dir = trash_dir(workdir).join(&entry.id);
+    move_path(&entry_dir.join(PAYLOAD), &destination).map_err(|e| format!("Restoring {} failed: {e}", entry.path))?;
+    let _ = fs::remove_dir_all(entry_dir);
+    Ok(entry)
+}
+
+/// Drop entries older than [`MAX_AGE`] and all but the newest [`MAX_ENTRIES`].
+fn prune(workdir: &Path, now: SystemTime) {
+    let cutoff = now.checked_sub(MAX_AGE).map_or(0, |t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64);
+    for (i, entry) in list(workdir).into_iter().enumerate() {
+        if i >= MAX_ENTRIES || entry.deleted_at < cutoff {
+            let _ = fs::remove_dir_all(trash_dir(workdir).join(entry.id));
+        }
+    }
+}
+
+/// Rename, falling back to copy and remove across filesystems.
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if fs::symlink_metadata(from)?.is_dir() {
+        copy_dir(from, to)?;
+        fs::remove_dir_all(from)
+    } else {
+        fs::copy(from, to)?;
+        fs::remove_file(from)
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for child in fs::read_dir(from)? {
+        let child = child?;
+        let target = to.join(child.file_name());
+        if child.file_type()?.is_dir() {
+            copy_dir(&child.path(), &target)?;
+        } else {
+            fs::copy(child.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/old")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn a() {}\n").unwrap();
+        fs::write(root.join("src/old/x.rs"), "x").unwrap();
+
+        let file = move_to_trash(root, "src/lib.rs").unwrap();
+        let folder = move_to_trash(root, "src/old").unwrap();
+        assert!(!root.join("src/lib.rs").exists() && !root.join("src/old").exists());
+        assert!(!file.is_dir && folder.is_dir);
+        let paths: Vec<String> = list(root).into_iter().map(|e| e.path).collect();
+        assert_eq!(paths, ["src/old", "src/lib.rs"]);
+        assert!(move_to_trash(root, &format!(".forge/trash/{}", file.id)).is_err());
+
+        // The file was recreated meanwhile: don't clobber it
+        fs::write(root.join("src/lib.rs"), "new").unwrap();
+        assert!(restore(root, Some("src/lib.rs")).is_err());
+        fs::remove_file(root.join("src/lib.rs")).unwrap();
+        assert_eq!(restore(root, Some("./src/lib.rs")).unwrap().path, "src/lib.rs");
+        assert_eq!(fs::read_to_string(root.join("src/lib.rs")).unwrap(), "pub fn a() {}\n");
+
+        assert_eq!(restore(root, None).unwrap().path, "src/old");
+        assert_eq!(fs::read_to_string(root.join("src/old/x.rs")).unwrap(), "x");
+        assert!(list(root).is_empty());
+        assert!(restore(root, None).is_err());
+
+        move_to_trash(root, "src/lib.rs").unwrap();
+        prune(root, SystemTime::now() + MAX_AGE + Duration::from_secs(60));
+        assert!(list(root).is_empty());
+    }
+}
@@ -2,10 +2,16 @@
 //!
 //! Provides accurate symbol extraction for tools like
 //! `list_definitions`, `get_definition`, and `find_references`.
-//! Uses regex patterns for common languages.
+//! Uses regex patterns for common languages, the tree-sitter grammar for
+//! C, C++ and Terraform, whose declarations regexes can't tell apart, and
+//! indentation for YAML.
 
 use anyhow::Result;
 use regex::Regex;
+use tree_sitter::Node;
+
+use crate::sql::SqlKind;
+use crate::syntax::{self, LangConfig};
 
 /// Code symbol definition
 #[derive(Debug, Clone)]
@@ -15,6 +21,8 @@ pub struct Symbol {
     pub start_line: usize,
     pub end_line: usize,
     pub signature: String,
+    /// First sentence of the doc comment (or docstring), if there is one.
+    pub doc: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -49,8 +57,34 @@ impl std::fmt::Display for SymbolKind {
     }
 }
 
-/// Parse a file and extract symbol definitions using regex patterns.
+/// Parse a file and extract symbol definitions using regex patterns, with
+/// the first sentence of each one's doc comment.
 pub fn parse_definitions(content: &str, file_ext: &str) -> Result<Vec<Symbol>> {
+    let mut symbols = definitions(content, file_ext)?;
+    let lines: Vec<&str> = content.lines().collect();
+    for symbol in &mut symbols {
+        symbol.doc = doc_summary(&lines, symbol.start_line.saturating_sub(1), file_ext);
+    }
+    Ok(symbols)
+}
+
+fn definitions(content: &str, file_ext: &str) -> Result<Vec<Symbol>> {
+    if matches!(file_ext, "yaml" | "yml") {
+        return Ok(yaml_definitions(content));
+    }
+    if file_ext.eq_ignore_ascii_case("sql") {
+        return Ok(sql_definitions(content));
+    }
+    if let Some(config) = syntax::config_for_extension(file_ext).filter(|c| matches!(c.name, "c" | "cpp" | "hcl")) {
+        if let Some(tree) = config.parse(content) {
+            let mut symbols = Vec::new();
+            let top = config.top_level(tree.root_node());
+            grammar_definitions(config, top, content, None, false, &mut symbols);
+            symbols.sort_by_key(|s| s.start_line);
+            return Ok(symbols);
+        }
+    }
+
     let lines: Vec<&str> = content.lines().collect();
     let mut symbols = Vec::new();
 
@@ -77,6 +111,7 @@ pub fn parse_definitions(content: &str, file_ext: &str) -> Result<Vec<Symbol>> {
                         start_line: line_idx + 1, // 1-indexed
                         end_line: end_line + 1,
                         signature: line.trim().to_string(),
+                        doc: None,
                     });
                 }
             }
@@ -116,8 +151,111 @@ pub fn find_definition(content: &str, file_ext: &str, symbol_name: &str) -> Opti
     symbols.into_iter().find(|s| s.name == symbol_name)
 }
 
+/// A definition and the definitions inside it.
+#[derive(Debug, Clone)]
+pub struct OutlineItem {
+    /// Named without its container's prefix (`start`, not `Server::start`).
+    pub symbol: Symbol,
+    pub children: Vec<OutlineItem>,
+}
+
+/// The definitions in `content` nested by their line ranges, for the
+/// outline panel and breadcrumbs when no language server gives document
+/// symbols.
+pub fn outline(content: &str, file_ext: &str) -> Vec<OutlineItem> {
+    let mut symbols = parse_definitions(content, file_ext).unwrap_or_default();
+    symbols.sort_by(|a, b| a.start_line.cmp(&b.start_line).then(b.end_line.cmp(&a.end_line)));
+    let mut roots = Vec::new();
+    let mut open: Vec<OutlineItem> = Vec::new();
+    for symbol in symbols {
+        while open.last().is_some_and(|o| symbol.start_line > o.symbol.end_line || symbol.end_line > o.symbol.end_line) {
+            close_outline_item(&mut open, &mut roots);
+        }
+        open.push(OutlineItem { symbol, children: Vec::new() });
+    }
+    while !open.is_empty() {
+        close_outline_item(&mut open, &mut roots);
+    }
+    roots
+}
+
+fn close_outline_item(open: &mut Vec<OutlineItem>, roots: &mut Vec<OutlineItem>) {
+    let Some(mut item) = open.pop() else { return };
+    let Some(parent) = open.last_mut() else {
+        roots.push(item);
+        return;
+    };
+    let short = item.symbol.name.strip_prefix(parent.symbol.name.as_str()).and_then(|rest| {
+        rest.strip_prefix("::").or_else(|| rest.strip_prefix('.')).or_else(|| rest.strip_prefix('/'))
+    });
+    if let Some(short) = short.filter(|s| !s.is_empty()) {
+        item.symbol.name = short.to_string();
+    }
+    parent.children.push(item);
+}
+
 // ── Helper functions ─────────────────────────────────────────────
 
+/// Definitions under `parent` from the grammar, members qualified by their
+/// container (`net::Server::start`). `in_type` is set inside a class or
+/// struct, where functions are methods.
+fn grammar_definitions(
+    config: &LangConfig,
+    parent: Node,
+    content: &str,
+    scope: Option<&str>,
+    in_type: bool,
+    out: &mut Vec<Symbol>,
+) {
+    let mut cursor = parent.walk();
+    let children: Vec<Node> = parent.named_children(&mut cursor).collect();
+    for child in children {
+        let definition = config.unwrap(child);
+        if !config.is_definition(definition) {
+            continue;
+        }
+        // Forward declarations and `struct foo` used as a type have no body
+        if definition.kind().ends_with("_specifier") && definition.child_by_field_name("body").is_none() {
+            continue;
+        }
+        let Some(name) = config.definition_name(definition, content.as_bytes()) else {
+            continue;
+        };
+        let name = match scope {
+            Some(scope) => format!("{scope}{}{name}", config.separator),
+            None => name,
+        };
+        let kind = match definition.kind() {
+            "function_definition" if in_type => SymbolKind::Method,
+            "function_definition" => SymbolKind::Function,
+            "class_specifier" => SymbolKind::Class,
+            "struct_specifier" | "union_specifier" => SymbolKind::Struct,
+            "enum_specifier" => SymbolKind::Enum,
+            "namespace_definition" => SymbolKind::Module,
+            "block" if name.starts_with("var.") || name.starts_with("locals") => SymbolKind::Variable,
+            "block" if name.starts_with("module.") => SymbolKind::Module,
+            "block" => SymbolKind::Constant,
+            _ => SymbolKind::Type,
+        };
+        let start = child.start_position().row;
+        let signature = content.lines().nth(definition.start_position().row).unwrap_or_default().trim().to_string();
+        out.push(Symbol {
+            name: name.clone(),
+            kind,
+            start_line: start + 1,
+            end_line: syntax::end_row(child) + 1,
+            signature,
+            doc: None,
+        });
+        if config.is_container(definition) {
+            if let Some(body) = definition.child_by_field_name("body") {
+                let in_type = definition.kind() != "namespace_definition";
+                grammar_definitions(config, body, content, Some(&name), in_type, out);
+            }
+        }
+    }
+}
+
 fn get_patterns(file_ext: &str) -> Vec<(Regex, SymbolKind)> {
     match file_ext {
         "rs" => vec![
@@ -160,6 +298,198 @@ fn get_patterns(file_ext: &str) -> Vec<(Regex, SymbolKind)> {
     }
 }
 
+/// CREATE statements, named without their schema so `orders` finds
+/// `public.orders`.
+fn sql_definitions(content: &str) -> Vec<Symbol> {
+    let lines: Vec<&str> = content.lines().collect();
+    crate::sql::definitions(content)
+        .into_iter()
+        .map(|d| Symbol {
+            name: d.unqualified().to_string(),
+            kind: match d.kind {
+                SqlKind::Table => SymbolKind::Struct,
+                SqlKind::Function | SqlKind::Procedure | SqlKind::Trigger => SymbolKind::Function,
+                SqlKind::Schema => SymbolKind::Module,
+                SqlKind::Index | SqlKind::Sequence => SymbolKind::Constant,
+                SqlKind::View | SqlKind::Type => SymbolKind::Type,
+            },
+            start_line: d.line,
+            end_line: d.end_line,
+            signature: lines.get(d.line - 1).map(|l| l.trim().to_string()).unwrap_or_default(),
+            doc: None,
+        })
+        .collect()
+}
+
+/// Keys whose entries are worth listing one by one (Compose services,
+/// workflow jobs).
+const YAML_COLLECTIONS: &[&str] = &["services", "jobs", "volumes", "networks", "stages", "workflows", "targets"];
+
+/// YAML definitions: a Kubernetes object per document (`Deployment/web`),
+/// else the top-level keys and the entries of well-known collections.
+fn yaml_definitions(content: &str) -> Vec<Symbol> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut symbols = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + 1..lines.len()).find(|i| lines[*i].starts_with("---")).unwrap_or(lines.len());
+        yaml_document(&lines, start, end, &mut symbols);
+        start = end + 1;
+    }
+    symbols
+}
+
+fn yaml_document(lines: &[&str], start: usize, end: usize, symbols: &mut Vec<Symbol>) {
+    let body = move || lines[start..end].iter().enumerate().filter_map(move |(i, l)| Some((start + i, yaml_key(l)?)));
+
+    let kind = body().find(|(_, (indent, k, _))| *indent == 0 && k == "kind").map(|(_, (_, _, v))| v);
+    let metadata = body().position(|(_, (indent, k, _))| indent == 0 && k == "metadata");
+    if let (Some(kind), Some(metadata)) = (kind, metadata) {
+        let name = body()
+            .skip(metadata + 1)
+            .take_while(|(_, (indent, _, _))| *indent > 0)
+            .find(|(_, (_, k, _))| k == "name")
+            .map(|(_, (_, _, v))| v.trim_matches(['"', '\'']).to_string());
+        if let Some(name) = name {
+            let signature = format!("kind: {kind}");
+            symbols.push(Symbol {
+                name: format!("{kind}/{name}"),
+                kind: SymbolKind::Type,
+                start_line: start + 1,
+                end_line: end,
+                signature,
+                doc: None,
+            });
+            return;
+        }
+    }
+
+    let top: Vec<(usize, String)> = body().filter(|(_, (indent, _, _))| *indent == 0).map(|(i, (_, k, _))| (i, k)).collect();
+    for (n, (line, name)) in top.iter().enumerate() {
+        let block_end = top.get(n + 1).map_or(end, |(next, _)| *next);
+        symbols.push(Symbol {
+            name: name.clone(),
+            kind: SymbolKind::Constant,
+            start_line: line + 1,
+            end_line: block_end,
+            signature: lines[*line].trim().to_string(),
+            doc: None,
+        });
+        if !YAML_COLLECTIONS.contains(&name.as_str()) {
+            continue;
+        }
+        // Entries are the keys at the first indentation under the collection
+        let entries: Vec<(usize, usize, String)> = lines[line + 1..block_end]
+            .iter()
+            .enumerate()
+            .filter_map(|(i, l)| yaml_key(l).map(|(indent, k, _)| (line + 1 + i, indent, k)))
+            .collect();
+        let Some(depth) = entries.iter().map(|(_, indent, _)| *indent).min() else { continue };
+        for (i, _, entry) in entries.into_iter().filter(|(_, indent, _)| *indent == depth) {
+            symbols.push(Symbol {
+                name: format!("{name}.{entry}"),
+                kind: SymbolKind::Module,
+                start_line: i + 1,
+                end_line: i + 1,
+                signature: lines[i].trim().to_string(),
+                doc: None,
+            });
+        }
+    }
+}
+
+/// A `key: value` line's indentation, key and value.
+fn yaml_key(line: &str) -> Option<(usize, String, String)> {
+    let indent = line.len() - line.trim_start().len();
+    let (key, value) = line.trim_start().split_once(':')?;
+    let valid = !key.is_empty() && !key.starts_with(['#', '-']) && !key.contains(' ');
+    valid.then(|| (indent, key.trim_matches(['"', '\'']).to_string(), value.trim().to_string()))
+}
+
+/// The first sentence of the doc comment above line `start` (0-based), or
+/// of a Python docstring below it. Attributes and decorators between the
+/// comment and the definition are skipped.
+fn doc_summary(lines: &[&str], start: usize, file_ext: &str) -> Option<String> {
+    if file_ext == "py" || file_ext == "pyi" {
+        return docstring(lines, start);
+    }
+    let prefixes: &[&str] = match file_ext {
+        "rs" => &["///"],
+        "tf" | "tfvars" | "hcl" | "yaml" | "yml" => &["#"],
+        "sql" => &["--"],
+        _ => &["///", "//"],
+    };
+    let mut row = start;
+    let mut text: Vec<String> = Vec::new();
+    while row > 0 {
+        row -= 1;
+        let line = lines[row].trim();
+        if line.starts_with("#[") || (line.starts_with('@') && file_ext != "rs") {
+            if text.is_empty() {
+                continue;
+            }
+            break;
+        }
+        if let Some(prefix) = prefixes.iter().find(|p| line.starts_with(**p)) {
+            text.insert(0, line[prefix.len()..].trim().to_string());
+            continue;
+        }
+        // A `/** ... */` block, read upwards to its start
+        if line.ends_with("*/") && text.is_empty() && file_ext != "rs" {
+            let mut block = Vec::new();
+            loop {
+                let l = lines[row].trim();
+                block.insert(0, l.trim_start_matches("/**").trim_start_matches("/*").trim_end_matches("*/").trim_start_matches('*').trim().to_string());
+                if l.starts_with("/*") || row == 0 {
+                    break;
+                }
+                row -= 1;
+            }
+            // JSDoc tags end the description
+            text = block.into_iter().take_while(|l| !l.starts_with('@')).collect();
+        }
+        break;
+    }
+    first_sentence(&text.join(" "))
+}
+
+fn docstring(lines: &[&str], start: usize) -> Option<String> {
+    // The body starts after the line ending the signature with `:`
+    let body = (start..lines.len()).find(|i| lines[*i].trim_end().ends_with(':'))? + 1;
+    let first = lines.get(body..)?.iter().position(|l| !l.trim().is_empty())? + body;
+    let line = lines[first].trim();
+    let quote = ["\"\"\"", "'''"].into_iter().find(|q| line.starts_with(q))?;
+    let mut text = line[3..].to_string();
+    if !text.contains(quote) {
+        for l in &lines[first + 1..] {
+            text.push(' ');
+            text.push_str(l.trim());
+            if l.contains(quote) {
+                break;
+            }
+        }
+    }
+    let text = text.split(quote).next().unwrap_or_default();
+    first_sentence(text)
+}
+
+/// Longest summary kept.
+const MAX_DOC_CHARS: usize = 160;
+
+fn first_sentence(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let end = text.find(". ").map(|i| i + 1).unwrap_or(text.len());
+    let sentence = text[..end].trim();
+    if sentence.is_empty() {
+        return None;
+    }
+    if sentence.chars().count() <= MAX_DOC_CHARS {
+        return Some(sentence.to_string());
+    }
+    let cut: String = sentence.chars().take(MAX_DOC_CHARS - 1).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
+
 fn find_block_end(lines: &[&str], start: usize) -> usize {
     let mut depth = 0;
     let mut found_open = false;
@@ -196,3 +526,50 @@ fn is_keyword(name: &str, file_ext: &str) -> bool {
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_definitions() {
+        let k8s = "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: web\n  labels:\n    app: web\n---\nkind: Service\nmetadata:\n  name: \"web-svc\"\n";
+        let names: Vec<String> = parse_definitions(k8s, "yaml").unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["Deployment/web", "Service/web-svc"]);
+
+        let compose = "version: '3'\nservices:\n  api:\n    image: api\n    ports:\n      - 80\n  db:\n    image: postgres\n";
+        let symbols = parse_definitions(compose, "yml").unwrap();
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["version", "services", "services.api", "services.db"]);
+        assert_eq!((symbols[1].start_line, symbols[1].end_line), (2, 8));
+    }
+
+    #[test]
+    fn test_outline() {
+        let cpp = "namespace net {\nclass Server {\n  void start() {}\n};\n}\nvoid Server::stop() {\n}\nint main() {\n  return 0;\n}\n";
+        let items = outline(cpp, "cpp");
+        let tree: Vec<(String, Vec<String>)> = items
+            .iter()
+            .map(|i| (i.symbol.name.clone(), i.children.iter().map(|c| c.symbol.name.clone()).collect()))
+            .collect();
+        assert_eq!(tree[0], ("net".to_string(), vec!["Server".to_string()]));
+        assert_eq!(items[0].children[0].children[0].symbol.name, "start");
+        assert_eq!(tree.last().unwrap().0, "main");
+    }
+
+    #[test]
+    fn test_doc_summaries() {
+        let rust = "/// Parses the input. Returns None when empty.\n#[inline]\npub fn parse() {}\n\n// Not a doc comment\nfn other() {}\n";
+        let symbols = parse_definitions(rust, "rs").unwrap();
+        assert_eq!(symbols[0].doc.as_deref(), Some("Parses the input."));
+        assert_eq!(symbols[1].doc, None);
+
+        let ts = "/**\n * Formats a date\n * for display.\n * @param d the date\n */\nexport function format(d) {}\n";
+        let symbols = parse_definitions(ts, "ts").unwrap();
+        assert_eq!(symbols[0].doc.as_deref(), Some("Formats a date for display."));
+
+        let py = "class Cache:\n    \"\"\"An LRU cache.\n\n    Evicts the oldest entry.\n    \"\"\"\n";
+        let symbols = parse_definitions(py, "py").unwrap();
+        assert_eq!(symbols[0].doc.as_deref(), Some("An LRU cache."));
+    }
+}
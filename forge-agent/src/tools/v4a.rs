@@ -0,0 +1,531 @@
+//! V4A patches for `apply_patch`:
+//!
+//! ```text
+//! *** Begin Patch
+//! *** Update File: src/app.rs
+//! @@ fn main
+//!  context
+//! -removed
+//! +added
+//! *** Add File: src/new.rs
+//! +content
+//! *** Delete File: src/old.rs
+//! *** End Patch
+//! ```
+//!
+//! Models get the format slightly wrong often enough that the parser is
+//! lenient: the Begin/End markers and code fences are optional, headers may
+//! lack the colon, context lines may lack their leading space, and `@@`
+//! lines may carry unified-diff line numbers. Each hunk is located on its
+//! own, exactly, then ignoring whitespace, then by similarity when one
+//! region clearly fits best. Hunks that match are applied and the rest are
+//! reported one by one, with the closest region, so the model can resend
+//! only those.
+
+use std::path::Path;
+
+use super::buffers;
+use super::fs_changes::{ChangeKind, FsChange};
+use super::ToolResult;
+
+/// Lowest average line similarity (0..1) a fuzzy match needs.
+const FUZZY_THRESHOLD: f64 = 0.8;
+/// A fuzzy match must beat the runner-up by this much.
+const FUZZY_MARGIN: f64 = 0.05;
+/// File lines shown around a failed hunk's closest region.
+const SHOWN_LINES: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hunk {
+    /// The text after `@@`: a line the hunk comes after (`fn main`).
+    pub anchor: Option<String>,
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// The lines the hunk expects in the file.
+    fn old(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    fn label(&self, index: usize) -> String {
+        match &self.anchor {
+            Some(anchor) => format!("hunk {} (@@ {anchor})", index + 1),
+            None => format!("hunk {}", index + 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileOp {
+    Add(String),
+    Delete,
+    Update { move_to: Option<String>, hunks: Vec<Hunk> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    pub path: String,
+    pub op: FileOp,
+}
+
+/// The file operations in `input`, in order.
+pub fn parse(input: &str) -> Result<Vec<FilePatch>, String> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut added: Vec<String> = Vec::new();
+    let mut hunk = Hunk::default();
+
+    let finish_hunk = |files: &mut Vec<FilePatch>, hunk: &mut Hunk| {
+        let mut done = std::mem::take(hunk);
+        // Blank lines between files aren't context
+        while matches!(done.lines.last(), Some(HunkLine::Context(s)) if s.trim().is_empty()) {
+            done.lines.pop();
+        }
+        if done.lines.is_empty() && done.anchor.is_none() {
+            return;
+        }
+        if let Some(FilePatch { op: FileOp::Update { hunks, .. }, .. }) = files.last_mut() {
+            hunks.push(done);
+        }
+    };
+    let finish_file = |files: &mut Vec<FilePatch>, hunk: &mut Hunk, added: &mut Vec<String>| {
+        finish_hunk(files, hunk);
+        if let Some(FilePatch { op: FileOp::Add(content), .. }) = files.last_mut() {
+            *content = added.join("\n");
+            if !added.is_empty() {
+                content.push('\n');
+            }
+        }
+        added.clear();
+    };
+
+    for raw in input.lines() {
+        let line = raw.trim_end_matches('\r');
+        let trimmed = line.trim();
+        if let Some((kind, path)) = file_header(trimmed) {
+            finish_file(&mut files, &mut hunk, &mut added);
+            let op = match kind {
+                "add" => FileOp::Add(String::new()),
+                "delete" => FileOp::Delete,
+                _ => FileOp::Update { move_to: None, hunks: Vec::new() },
+            };
+            files.push(FilePatch { path, op });
+            continue;
+        }
+        if let Some(target) = strip_marker(trimmed, "Move to") {
+            if let Some(FilePatch { op: FileOp::Update { move_to, .. }, .. }) = files.last_mut() {
+                *move_to = Some(target);
+            }
+            continue;
+        }
+        if trimmed.starts_with("*** Begin Patch")
+            || trimmed.starts_with("*** End Patch")
+            || trimmed.starts_with("*** End of File")
+            || trimmed.starts_with("```")
+            || ["--- a/", "+++ b/", "--- /dev/null", "+++ /dev/null"].iter().any(|p| line.starts_with(p))
+        {
+            continue;
+        }
+        match files.last().map(|f| &f.op) {
+            Some(FileOp::Add(_)) => {
+                added.push(line.strip_prefix('+').unwrap_or(line).to_string());
+                continue;
+            }
+            Some(FileOp::Update { .. }) => {}
+            _ => continue,
+        }
+        if let Some(rest) = line.strip_prefix("@@") {
+            finish_hunk(&mut files, &mut hunk);
+            hunk.anchor = anchor(rest);
+        } else if let Some(rest) = line.strip_prefix('-') {
+            hunk.lines.push(HunkLine::Remove(rest.to_string()));
+        } else if let Some(rest) = line.strip_prefix('+') {
+            hunk.lines.push(HunkLine::Add(rest.to_string()));
+        } else {
+            // A missing leading space still makes a context line
+            hunk.lines.push(HunkLine::Context(line.strip_prefix(' ').unwrap_or(line).to_string()));
+        }
+    }
+    finish_file(&mut files, &mut hunk, &mut added);
+
+    if files.is_empty() {
+        return Err("No file headers found: start each file with *** Update File: <path>, *** Add File: <path> or *** Delete File: <path>".to_string());
+    }
+    Ok(files)
+}
+
+/// `("add" | "update" | "delete", path)` for a file header line.
+fn file_header(line: &str) -> Option<(&'static str, String)> {
+    [("add", "Add File"), ("update", "Update File"), ("delete", "Delete File")]
+        .into_iter()
+        .find_map(|(kind, marker)| Some((kind, strip_marker(line, marker)?)))
+}
+
+/// The path after `*** <marker>:` (colon and case optional).
+fn strip_marker(line: &str, marker: &str) -> Option<String> {
+    let rest = line.strip_prefix("***")?.trim_start();
+    let head = rest.get(..marker.len())?;
+    if !head.eq_ignore_ascii_case(marker) {
+        return None;
+    }
+    let path = rest[marker.len()..].trim_start_matches(':').trim().trim_end_matches('*').trim();
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+/// The anchor text of an `@@` line, without unified-diff line numbers.
+fn anchor(rest: &str) -> Option<String> {
+    let mut text = rest.trim();
+    if text.starts_with('-') {
+        // `@@ -12,4 +12,5 @@ fn main`
+        text = text.split_once("@@").map_or("", |(_, after)| after).trim();
+    }
+    let text = text.trim_end_matches("@@").trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// How a hunk was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Match {
+    Exact,
+    Whitespace,
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkOutcome {
+    /// At this 1-based line of the original file.
+    Applied { line: usize, by: Match },
+    Failed { reason: String },
+}
+
+/// Apply `hunks` to `content`: the new content (`None` if no hunk applied)
+/// and each hunk's outcome.
+pub fn apply_hunks(content: &str, hunks: &[Hunk]) -> (Option<String>, Vec<HunkOutcome>) {
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    // Original line number of each current line, for the report
+    let mut origin: Vec<usize> = (1..=lines.len()).collect();
+    let mut cursor = 0;
+    let mut outcomes = Vec::new();
+    let mut changed = false;
+
+    for hunk in hunks {
+        let old = hunk.old();
+        let found = if old.is_empty() {
+            // Only additions: after the anchor, else at the end
+            let at = match &hunk.anchor {
+                Some(anchor) => lines.iter().position(|l| l.contains(anchor.as_str())).map(|i| i + 1),
+                None => Some(lines.len()),
+            };
+            at.map(|at| (at, Match::Exact))
+        } else {
+            locate(&lines, &old, hunk.anchor.as_deref(), cursor)
+        };
+        let Some((at, by)) = found else {
+            outcomes.push(HunkOutcome::Failed { reason: failure(&lines, &origin, hunk) });
+            continue;
+        };
+
+        let mut replacement = Vec::new();
+        let mut file_line = at;
+        for line in &hunk.lines {
+            match line {
+                // Context keeps the file's text, whitespace and all
+                HunkLine::Context(_) => {
+                    replacement.push((lines[file_line].clone(), origin[file_line]));
+                    file_line += 1;
+                }
+                HunkLine::Remove(_) => file_line += 1,
+                HunkLine::Add(text) => replacement.push((text.clone(), 0)),
+            }
+        }
+        let line = origin.get(at).copied().unwrap_or(origin.len() + 1);
+        let inserted = replacement.len();
+        let (new_lines, new_origin): (Vec<String>, Vec<usize>) = replacement.into_iter().unzip();
+        lines.splice(at..at + old.len(), new_lines);
+        origin.splice(at..at + old.len(), new_origin);
+        cursor = at + inserted;
+        changed = true;
+        outcomes.push(HunkOutcome::Applied { line, by });
+    }
+
+    if !changed {
+        return (None, outcomes);
+    }
+    let mut out = lines.join(newline);
+    if content.ends_with('\n') || content.is_empty() {
+        out.push_str(newline);
+    }
+    (Some(out), outcomes)
+}
+
+/// Where `old` starts in `lines`: the first match at or after `cursor` (or
+/// after the anchor), at the strictest level that finds one. Before the
+/// cursor only a unique match counts.
+fn locate(lines: &[String], old: &[&str], anchor: Option<&str>, cursor: usize) -> Option<(usize, Match)> {
+    if old.len() > lines.len() {
+        return None;
+    }
+    let start = anchor
+        .and_then(|a| lines.iter().skip(cursor).position(|l| l.contains(a)).map(|i| cursor + i))
+        .unwrap_or(cursor);
+    let positions = 0..=lines.len() - old.len();
+    let same = |at: usize, eq: &dyn Fn(&str, &str) -> bool| old.iter().enumerate().all(|(i, o)| eq(lines[at + i].as_str(), o));
+    let levels: [(Match, &dyn Fn(&str, &str) -> bool); 2] = [
+        (Match::Exact, &|a, b| a == b),
+        (Match::Whitespace, &|a, b| a.split_whitespace().eq(b.split_whitespace())),
+    ];
+    for (by, eq) in levels {
+        let matches: Vec<usize> = positions.clone().filter(|at| same(*at, eq)).collect();
+        if let Some(at) = matches.iter().find(|at| **at >= start).or(matches.iter().find(|at| **at >= cursor)) {
+            return Some((*at, by));
+        }
+        if let [only] = matches[..] {
+            return Some((only, by));
+        }
+    }
+
+    let (best, score, runner_up) = best_region(lines, old);
+    (score >= FUZZY_THRESHOLD && score - runner_up >= FUZZY_MARGIN).then_some((best, Match::Fuzzy))
+}
+
+/// The region most similar to `old`: its start, score and the next best
+/// score elsewhere.
+fn best_region(lines: &[String], old: &[&str]) -> (usize, f64, f64) {
+    if old.is_empty() || old.len() > lines.len() {
+        return (0, 0.0, 0.0);
+    }
+    let scores: Vec<f64> = (0..=lines.len() - old.len())
+        .map(|at| {
+            let total: f64 = old
+                .iter()
+                .enumerate()
+                .map(|(i, o)| similar::TextDiff::from_chars(lines[at + i].trim(), o.trim()).ratio() as f64)
+                .sum();
+            total / old.len() as f64
+        })
+        .collect();
+    let Some((best, score)) = scores.iter().copied().enumerate().max_by(|a, b| a.1.total_cmp(&b.1)) else {
+        return (0, 0.0, 0.0);
+    };
+    // Overlapping windows score alike; compare with regions that don't overlap
+    let runner_up = scores
+        .iter()
+        .enumerate()
+        .filter(|(at, _)| at.abs_diff(best) >= old.len())
+        .map(|(_, s)| *s)
+        .fold(0.0, f64::max);
+    (best, score, runner_up)
+}
+
+/// Why a hunk didn't apply, with the file's closest region.
+fn failure(lines: &[String], origin: &[usize], hunk: &Hunk) -> String {
+    let old = hunk.old();
+    if old.len() > lines.len() {
+        return format!("expects {} lines but the file has {}", old.len(), lines.len());
+    }
+    let (best, score, runner_up) = best_region(lines, &old);
+    let reason = if score >= FUZZY_THRESHOLD {
+        format!("matches more than one place equally well (best {:.0}%, next {:.0}%)", score * 100.0, runner_up * 100.0)
+    } else {
+        format!("context and removed lines not found (closest region {:.0}% similar)", score * 100.0)
+    };
+    let shown: String = lines[best..(best + old.len()).min(best + SHOWN_LINES).min(lines.len())]
+        .iter()
+        .zip(&origin[best..])
+        .map(|(l, n)| format!("\n  {n:4}|{l}"))
+        .collect();
+    format!("{reason}; the closest lines are:{shown}")
+}
+
+/// Result of one file in the patch.
+struct FileReport {
+    path: String,
+    summary: String,
+    failed: Vec<String>,
+}
+
+/// Apply a V4A patch to the workspace, as far as it matches.
+pub fn apply(input: &str, workdir: &Path) -> ToolResult {
+    let files = match parse(input) {
+        Ok(files) => files,
+        Err(e) => return ToolResult::err(e),
+    };
+    let mut reports = Vec::new();
+    let mut changes = Vec::new();
+    let (mut applied, mut total) = (0, 0);
+
+    for file in files {
+        let full = workdir.join(&file.path);
+        let mut report = FileReport { path: file.path.clone(), summary: String::new(), failed: Vec::new() };
+        match file.op {
+            FileOp::Delete => {
+                let old = buffers::read_to_string(&full).ok();
+                match super::trash::move_to_trash(workdir, &file.path) {
+                    Ok(_) => {
+                        report.summary = "deleted (restore_file can bring it back)".to_string();
+                        changes.push(FsChange { path: file.path, kind: ChangeKind::Deleted, old_content: old });
+                    }
+                    Err(e) => report.failed.push(format!("delete failed: {e}")),
+                }
+            }
+            FileOp::Add(content) => {
+                if let Err(e) = super::files::check_overwrite(&file.path, &full, false) {
+                    report.failed.push(e);
+                    reports.push(report);
+                    continue;
+                }
+                let old = buffers::read_to_string(&full).ok();
+                if let Some(parent) = full.parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+                match buffers::write_seen(&full, &content) {
+                    Ok(()) => {
+                        let count = content.lines().count();
+                        report.summary = format!("created ({count} line{})", if count == 1 { "" } else { "s" });
+                        let kind = if old.is_some() { ChangeKind::Modified } else { ChangeKind::Created };
+                        changes.push(FsChange { path: file.path, kind, old_content: old });
+                    }
+                    Err(e) => report.failed.push(format!("create failed: {e}")),
+                }
+            }
+            FileOp::Update { move_to, hunks } => {
+                total += hunks.len();
+                let content = match buffers::read_to_string(&full) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        report.failed.push(format!("read failed: {e}"));
+                        reports.push(report);
+                        continue;
+                    }
+                };
+                if let Err(e) = buffers::check_unchanged(&full, &content) {
+                    report.failed.push(e);
+                    reports.push(report);
+                    continue;
+                }
+                let (new_content, outcomes) = apply_hunks(&content, &hunks);
+                let mut notes = Vec::new();
+                for (i, (hunk, outcome)) in hunks.iter().zip(&outcomes).enumerate() {
+                    match outcome {
+                        HunkOutcome::Applied { line, by } => {
+                            applied += 1;
+                            notes.push(match by {
+                                Match::Exact => format!("{} at line {line}", hunk.label(i)),
+                                Match::Whitespace => format!("{} at line {line} (ignoring whitespace)", hunk.label(i)),
+                                Match::Fuzzy => format!("{} at line {line} (fuzzy match, check it)", hunk.label(i)),
+                            });
+                        }
+                        HunkOutcome::Failed { reason } => report.failed.push(format!("{} failed: {reason}", hunk.label(i))),
+                    }
+                }
+                let target = move_to.clone().unwrap_or_else(|| file.path.clone());
+                let new_content = new_content.unwrap_or_else(|| content.clone());
+                if new_content == content && move_to.is_none() {
+                    report.summary = "unchanged".to_string();
+                    reports.push(report);
+                    continue;
+                }
+                let target_full = workdir.join(&target);
+                if let Some(parent) = target_full.parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+                if let Err(e) = buffers::write_seen(&target_full, &new_content) {
+                    report.failed.push(format!("write failed: {e}"));
+                    reports.push(report);
+                    continue;
+                }
+                if move_to.is_some() && target_full != full {
+                    let _ = std::fs::remove_file(&full);
+                    changes.push(FsChange { path: file.path.clone(), kind: ChangeKind::Deleted, old_content: Some(content) });
+                    changes.push(FsChange { path: target.clone(), kind: ChangeKind::Created, old_content: None });
+                    notes.push(format!("moved to {target}"));
+                } else {
+                    changes.push(FsChange { path: file.path.clone(), kind: ChangeKind::Modified, old_content: Some(content) });
+                }
+                report.summary = if notes.is_empty() { "updated".to_string() } else { format!("updated: {}", notes.join(", ")) };
+            }
+        }
+        reports.push(report);
+    }
+
+    let failed = reports.iter().any(|r| !r.failed.is_empty());
+    let mut output = if total > 0 {
+        format!("Applied {applied} of {total} hunks.\n")
+    } else {
+        String::new()
+    };
+    for report in &reports {
+        let mark = if report.failed.is_empty() { "✓" } else { "✗" };
+        output.push_str(&format!("{mark} {}", report.path));
+        if !report.summary.is_empty() {
+            output.push_str(&format!(" {}", report.summary));
+        }
+        output.push('\n');
+        for failure in &report.failed {
+            output.push_str(&format!("  {failure}\n"));
+        }
+    }
+    if failed {
+        output.push_str("The hunks marked applied are written. Resend only the failed hunks, with context copied from the current file.");
+    }
+    let result = if failed { ToolResult::err(output.trim_end()) } else { ToolResult::ok(output.trim_end()) };
+    result.with_fs_changes(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v4a_partial_apply() {
+        // No Begin/End markers, a header without a colon, unified line
+        // numbers and a context line without its leading space
+        let patch = "```\n*** Update File src/app.rs\n@@ -1,3 +1,3 @@ fn main\nfn main() {\n-    let x = 1;\n+    let x = 2;\n@@\n     missing();\n-    gone();\n+    here();\n\n*** Add File: src/new.rs\n+pub fn new() {}\n```\n";
+        let files = parse(patch).unwrap();
+        assert_eq!(files.len(), 2);
+        let FileOp::Update { hunks, .. } = &files[0].op else { panic!() };
+        assert_eq!(hunks[0].anchor.as_deref(), Some("fn main"));
+        assert_eq!(hunks[0].lines[0], HunkLine::Context("fn main() {".to_string()));
+        assert_eq!(files[1].op, FileOp::Add("pub fn new() {}\n".to_string()));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/app.rs"), "fn main() {\n\tlet  x = 1;\n    run();\n}\n").unwrap();
+        let result = apply(patch, dir.path());
+        assert!(!result.success);
+        assert!(result.output.starts_with("Applied 1 of 2 hunks."), "{}", result.output);
+        assert!(result.output.contains("hunk 1 (@@ fn main) at line 1 (ignoring whitespace)"));
+        assert!(result.output.contains("hunk 2 failed"));
+        assert!(result.output.contains("✓ src/new.rs created"));
+        let app = std::fs::read_to_string(dir.path().join("src/app.rs")).unwrap();
+        assert_eq!(app, "fn main() {\n    let x = 2;\n    run();\n}\n");
+
+        // A slightly wrong line is found when one region clearly fits
+        let content = "fn a() {\n    compute(1, 2);\n}\n\nfn b() {\n    other();\n}\n";
+        let hunk = Hunk {
+            anchor: None,
+            lines: vec![
+                HunkLine::Context("fn a() {".to_string()),
+                HunkLine::Remove("    compute(1, 2)".to_string()),
+                HunkLine::Add("    compute(3, 4);".to_string()),
+            ],
+        };
+        let (new, outcomes) = apply_hunks(content, &[hunk]);
+        assert_eq!(outcomes, [HunkOutcome::Applied { line: 1, by: Match::Fuzzy }]);
+        assert!(new.unwrap().starts_with("fn a() {\n    compute(3, 4);\n}\n"));
+    }
+}
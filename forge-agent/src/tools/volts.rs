@@ -0,0 +1,128 @@
+//! `plugins`: the agent finding and managing the IDE's plugins (volts), so
+//! "set up this project" can install the language support it needs.
+//!
+//! Search and list are read-only; install, remove, enable and disable are
+//! approved by the user. The IDE runs the tool: lapce-proxy queries the
+//! registry and the installed volts, and the editor does the change like
+//! its plugin panel would.
+
+use std::path::Path;
+
+use lapce_rpc::plugin::VoltInfo;
+use serde::Deserialize;
+
+use super::args::{PluginAction, PluginsArgs};
+use super::ToolResult;
+
+pub const REGISTRY_URL: &str = "https://plugins.lapce.dev/api/v1/plugins";
+
+/// Most registry results shown for one search.
+const MAX_RESULTS: usize = 15;
+
+/// A page of registry search results.
+#[derive(Debug, Deserialize)]
+pub struct SearchResults {
+    pub plugins: Vec<VoltInfo>,
+    pub total: usize,
+}
+
+pub fn search_url(query: &str) -> String {
+    format!("{REGISTRY_URL}?q={}&offset=0", urlencoding::encode(query))
+}
+
+impl PluginAction {
+    /// Whether the action changes what's installed or running.
+    pub fn is_change(&self) -> bool {
+        !matches!(self, Self::Search | Self::List)
+    }
+}
+
+/// `author.name`, how volts are named to the model.
+pub fn id(volt: &VoltInfo) -> String {
+    format!("{}.{}", volt.author, volt.name)
+}
+
+/// One line per volt.
+pub fn format_volts(volts: &[VoltInfo], total: Option<usize>) -> String {
+    if volts.is_empty() {
+        return "No plugins found".to_string();
+    }
+    let mut lines: Vec<String> = volts
+        .iter()
+        .take(MAX_RESULTS)
+        .map(|v| format!("{} {} ({}): {}", id(v), v.version, v.display_name, v.description.trim()))
+        .collect();
+    let total = total.unwrap_or(volts.len());
+    if total > lines.len() {
+        lines.push(format!("… {} more; narrow the query", total - lines.len()));
+    }
+    lines.join("\n")
+}
+
+/// The volt `name` refers to: its `author.name` id, else its name or
+/// display name if only one volt has it.
+pub fn find<'a>(name: &str, volts: &'a [VoltInfo]) -> Result<&'a VoltInfo, String> {
+    let name = name.trim();
+    if let Some(volt) = volts.iter().find(|v| id(v).eq_ignore_ascii_case(name)) {
+        return Ok(volt);
+    }
+    let matches: Vec<&VoltInfo> = volts
+        .iter()
+        .filter(|v| v.name.eq_ignore_ascii_case(name) || v.display_name.eq_ignore_ascii_case(name))
+        .collect();
+    match matches[..] {
+        [volt] => Ok(volt),
+        [] => Err(format!("No plugin named '{name}'; search for it first")),
+        _ => Err(format!(
+            "'{name}' is ambiguous, use one of: {}",
+            matches.iter().map(|v| id(v)).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+/// Checks the arguments; only the IDE can run the tool.
+pub async fn plugins(args: PluginsArgs, _workdir: &Path) -> ToolResult {
+    if args.action == PluginAction::Search && args.query.as_deref().is_none_or(|q| q.trim().is_empty()) {
+        return ToolResult::err("search needs a query");
+    }
+    if args.action.is_change() && args.name.as_deref().is_none_or(|n| n.trim().is_empty()) {
+        return ToolResult::err("install, remove, enable and disable need the plugin's name");
+    }
+    ToolResult::ok("PENDING_IDE_EXECUTION")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volt(author: &str, name: &str, display_name: &str) -> VoltInfo {
+        VoltInfo {
+            name: name.to_string(),
+            version: "0.1.0".to_string(),
+            display_name: display_name.to_string(),
+            author: author.to_string(),
+            description: "A plugin ".to_string(),
+            repository: None,
+            wasm: true,
+            updated_at_ts: 0,
+        }
+    }
+
+    #[test]
+    fn test_find_volt() {
+        let volts = vec![
+            volt("MrFoxPro", "lapce-rust", "Rust"),
+            volt("someone", "lapce-rust", "Rust (fork)"),
+            volt("dzhou121", "lapce-go", "Go"),
+        ];
+        assert_eq!(find("mrfoxpro.lapce-rust", &volts).unwrap().author, "MrFoxPro");
+        assert_eq!(find("Go", &volts).unwrap().name, "lapce-go");
+        let ambiguous = find("lapce-rust", &volts).unwrap_err();
+        assert!(ambiguous.contains("MrFoxPro.lapce-rust, someone.lapce-rust"));
+        assert!(find("zig", &volts).is_err());
+
+        assert_eq!(format_volts(&volts[2..], Some(1)), "dzhou121.lapce-go 0.1.0 (Go): A plugin");
+        assert!(format_volts(&volts, Some(40)).ends_with("… 37 more; narrow the query"));
+        assert_eq!(search_url("c++ lsp"), format!("{REGISTRY_URL}?q=c%2B%2B%20lsp&offset=0"));
+    }
+}
@@ -1,6 +1,6 @@
-use reqwest::Client;
 use serde_json::{json, Value};
 use std::time::Duration;
+use crate::docs_cache::{self, CachedDoc, DocsCache};
 use crate::tools::ToolResult;
 
 pub async fn fetch_webpage(args: &Value) -> ToolResult {
@@ -8,8 +8,26 @@ pub async fn fetch_webpage(args: &Value) -> ToolResult {
         Some(u) => u,
         None => return ToolResult::err("Missing or invalid 'url' parameter"),
     };
+    // Local-only mode serves cached docs but never goes out
+    let offline = args.get("offline").and_then(|v| v.as_bool()).unwrap_or(false)
+        || docs_cache::offline_mode()
+        || !crate::egress::Egress::configured().allows_other();
+    let refresh = args.get("refresh").and_then(|v| v.as_bool()).unwrap_or(false);
 
-    let client = match Client::builder()
+    let cache = DocsCache::new();
+    let cached = cache.as_ref().and_then(|c| c.get(url));
+
+    // Serve from cache when fresh, or always when offline
+    if let (Some(cache), Some(doc)) = (&cache, &cached) {
+        if offline || (!refresh && cache.is_fresh(doc)) {
+            return cached_result(doc, if offline { "offline" } else { "fresh" });
+        }
+    }
+    if offline {
+        return ToolResult::err(format!("Offline mode: no cached copy of {}", url));
+    }
+
+    let client = match crate::http::client_builder()
         .timeout(Duration::from_secs(30))
         .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
         .build() {
@@ -17,16 +35,52 @@ pub async fn fetch_webpage(args: &Value) -> ToolResult {
             Err(e) => return ToolResult::err(format!("Failed to build HTTP client: {}", e)),
         };
 
-    let response = match client.get(url).send().await {
+    // Revalidate a stale entry with its validators
+    let mut request = client.get(url);
+    if let Some(doc) = &cached {
+        if let Some(etag) = &doc.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &doc.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = match request.send().await {
         Ok(r) => r,
-        Err(e) => return ToolResult::err(format!("Failed to fetch URL {}: {}", url, e)),
+        Err(e) => {
+            if let Some(doc) = &cached {
+                tracing::warn!("Fetch of {} failed ({}), serving stale cache", url, e);
+                return cached_result(doc, "stale");
+            }
+            return ToolResult::err(format!("Failed to fetch URL {}: {}", url, e));
+        }
     };
 
     let status = response.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        if let (Some(cache), Some(doc)) = (&cache, cached.clone()) {
+            let doc = cache.touch(doc);
+            return cached_result(&doc, "revalidated");
+        }
+    }
     if !status.is_success() {
+        if let Some(doc) = &cached {
+            return cached_result(doc, "stale");
+        }
         return ToolResult::err(format!("Server returned error status: {}", status));
     }
 
+    let header = |name: reqwest::header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+    };
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
+
     let html = match response.text().await {
         Ok(t) => t,
         Err(e) => return ToolResult::err(format!("Failed to read response body: {}", e)),
@@ -38,6 +92,16 @@ pub async fn fetch_webpage(args: &Value) -> ToolResult {
         Err(e) => return ToolResult::err(format!("Failed to convert HTML to Markdown: {:?}", e)),
     };
 
+    if let Some(cache) = &cache {
+        cache.put(&CachedDoc {
+            url: url.to_string(),
+            markdown: markdown.clone(),
+            etag,
+            last_modified,
+            fetched_at: chrono::Utc::now().timestamp(),
+        });
+    }
+
     let result_json = json!({
         "markdown": markdown,
         "url": url,
@@ -45,3 +109,13 @@ pub async fn fetch_webpage(args: &Value) -> ToolResult {
 
     ToolResult::ok(serde_json::to_string_pretty(&result_json).unwrap_or_default())
 }
+
+fn cached_result(doc: &CachedDoc, cache_status: &str) -> ToolResult {
+    let result_json = json!({
+        "markdown": doc.markdown,
+        "url": doc.url,
+        "cache": cache_status,
+        "fetched_at": doc.fetched_at,
+    });
+    ToolResult::ok(serde_json::to_string_pretty(&result_json).unwrap_or_default())
+}
@@ -0,0 +1,184 @@
+//! The session's working set, for search ranking.
+//!
+//! Files the agent read or edited and files the user `@`-mentioned are
+//! remembered per workspace. `codebase_search` adds a boost to hits in those
+//! files, and a smaller one to hits next to them (same directory), so results
+//! favour what the user is working on over unrelated corners of a monorepo.
+//! Boosts fade with a half-life of [`HALF_LIFE`] and the set keeps the
+//! [`MAX_FILES`] most recent files.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use super::ranking::PathWeights;
+
+/// Files remembered per workspace.
+const MAX_FILES: usize = 50;
+/// Time for a boost to halve.
+const HALF_LIFE: Duration = Duration::from_secs(15 * 60);
+/// Share of a file's boost given to its directory siblings.
+const SIBLING_SHARE: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Read,
+    Edit,
+    /// `@path` in a user message.
+    Mention,
+}
+
+impl Signal {
+    /// Added to a hit's 0..1 score.
+    fn weight(self) -> f64 {
+        match self {
+            Self::Read => 0.08,
+            Self::Edit => 0.15,
+            Self::Mention => 0.2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Touch {
+    signal: Signal,
+    at: Instant,
+}
+
+type Sets = HashMap<PathBuf, HashMap<String, Touch>>;
+
+fn sets() -> &'static Mutex<Sets> {
+    static SETS: OnceLock<Mutex<Sets>> = OnceLock::new();
+    SETS.get_or_init(Default::default)
+}
+
+fn normalize(workdir: &Path, path: &str) -> String {
+    let path = Path::new(path);
+    let rel = path.strip_prefix(workdir).unwrap_or(path);
+    let rel = rel.strip_prefix("./").unwrap_or(rel);
+    rel.to_string_lossy().replace('\\', "/")
+}
+
+/// Remember `path` as part of the working set. A stronger signal for the
+/// same file (an edit after a read) replaces the weaker one.
+pub fn touch(workdir: &Path, path: &str, signal: Signal) {
+    let Ok(mut sets) = sets().lock() else { return };
+    let set = sets.entry(workdir.to_path_buf()).or_default();
+    let now = Instant::now();
+    let signal = match set.get(&normalize(workdir, path)) {
+        Some(old) if old.signal.weight() > signal.weight() => old.signal,
+        _ => signal,
+    };
+    set.insert(normalize(workdir, path), Touch { signal, at: now });
+    if set.len() > MAX_FILES {
+        if let Some(oldest) = set.iter().min_by_key(|(_, t)| t.at).map(|(p, _)| p.clone()) {
+            set.remove(&oldest);
+        }
+    }
+}
+
+/// Record the files a successful tool call read or edited.
+pub fn record_tool_call(workdir: &Path, tool_name: &str, args: &Value) {
+    let signal = match tool_name {
+        "read_file" | "show_code" => Signal::Read,
+        "write_file" | "write_file_range" | "edit_file" | "apply_patch" => Signal::Edit,
+        _ => return,
+    };
+    if let Some(path) = args.get("path").and_then(Value::as_str) {
+        touch(workdir, path, signal);
+    }
+}
+
+/// Record the `@path` mentions in a user message that name workspace files.
+pub fn record_mentions(workdir: &Path, text: &str) {
+    for word in text.split_whitespace() {
+        let Some(path) = word.strip_prefix('@') else { continue };
+        let path = path.trim_end_matches(|c: char| matches!(c, ',' | '.' | ':' | ';' | ')' | '?' | '!'));
+        if !path.is_empty() && workdir.join(path).is_file() {
+            touch(workdir, path, Signal::Mention);
+        }
+    }
+}
+
+pub fn is_empty(workdir: &Path) -> bool {
+    !sets().lock().is_ok_and(|sets| sets.get(workdir).is_some_and(|set| !set.is_empty()))
+}
+
+/// The boost for a hit in `path`.
+pub fn boost(workdir: &Path, path: &str) -> f64 {
+    let Ok(sets) = sets().lock() else { return 0.0 };
+    let Some(set) = sets.get(workdir) else { return 0.0 };
+    boost_in(set, &normalize(workdir, path), Instant::now())
+}
+
+fn boost_in(set: &HashMap<String, Touch>, path: &str, now: Instant) -> f64 {
+    let decayed = |touch: &Touch| {
+        let age = now.saturating_duration_since(touch.at).as_secs_f64();
+        touch.signal.weight() * 0.5f64.powf(age / HALF_LIFE.as_secs_f64())
+    };
+    if let Some(touch) = set.get(path) {
+        return decayed(touch);
+    }
+    let dir = parent(path);
+    set.iter()
+        .filter(|(other, _)| parent(other) == dir)
+        .map(|(_, touch)| decayed(touch) * SIBLING_SHARE)
+        .fold(0.0, f64::max)
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// Reorder search hits (`file_path`, and `rerank_score` or `score`) with
+/// the working-set boost and the path weights added to their score.
+pub fn rank(workdir: &Path, hits: &mut [Value], weights: &PathWeights) {
+    let score = |hit: &Value| {
+        let base = hit.get("rerank_score").or_else(|| hit.get("score")).and_then(Value::as_f64).unwrap_or(0.0);
+        let path = hit.get("file_path").and_then(Value::as_str).unwrap_or("");
+        base + boost(workdir, path) + weights.weight(path)
+    };
+    let mut scored: Vec<(f64, Value)> = hits.iter_mut().map(|hit| (score(hit), std::mem::take(hit))).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (slot, (_, hit)) in hits.iter_mut().zip(scored) {
+        *slot = hit;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_boost_ranks_working_set_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("billing")).unwrap();
+        std::fs::write(root.join("billing/invoice.rs"), "").unwrap();
+
+        record_mentions(root, "why does @billing/invoice.rs, and @missing.rs fail?");
+        record_tool_call(root, "read_file", &json!({"path": "./auth/login.rs"}));
+        assert!(boost(root, "billing/invoice.rs") > boost(root, "auth/login.rs"));
+        assert!(boost(root, "billing/tax.rs") > 0.0, "siblings get a share");
+        assert_eq!(boost(root, "missing.rs"), 0.0);
+
+        let mut hits = vec![
+            json!({"file_path": "vendor/lib/invoice.rs", "score": 0.80}),
+            json!({"file_path": "billing/tax.rs", "score": 0.70}),
+            json!({"file_path": "billing/invoice.rs", "score": 0.65}),
+        ];
+        rank(root, &mut hits, &PathWeights::none());
+        let order: Vec<&str> = hits.iter().map(|h| h["file_path"].as_str().unwrap()).collect();
+        assert_eq!(order, ["billing/invoice.rs", "vendor/lib/invoice.rs", "billing/tax.rs"]);
+
+        // Old touches fade
+        let set: HashMap<String, Touch> =
+            [("a.rs".to_string(), Touch { signal: Signal::Edit, at: Instant::now() })].into();
+        let later = Instant::now() + HALF_LIFE;
+        assert!((boost_in(&set, "a.rs", later) - Signal::Edit.weight() / 2.0).abs() < 0.01);
+    }
+}
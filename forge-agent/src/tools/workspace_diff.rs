@@ -0,0 +1,563 @@
+//! What changed in the workspace between agent turns.
+//!
+//! At the start of every turn (each user message) the workspace's files are
+//! snapshotted: size, mtime and a content hash, re-hashing only files whose
+//! size or mtime moved. Comparing against the previous snapshot tells which
+//! files were added, modified or deleted, and whether the agent's edit tools
+//! wrote them (`agent`) or something else did: the user, another tool, or a
+//! command the agent ran (`external`).
+//!
+//! The IDE shows out-of-band changes as a badge on the chat panel, and the
+//! `workspace_diff` tool lists changes for the agent.
+//!
+//! Snapshots also count lines, so [`summary`] (file and line totals, language
+//! breakdown) stays current from turn to turn at the cost of re-reading only
+//! the files that changed, and [`change_feed`] gives the one-line "3 files
+//! changed since last turn" the prompt cites.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use serde_json::Value;
+
+use super::ToolResult;
+use crate::lru_cache::LruCache;
+
+/// Most files a snapshot covers; larger trees are cut off.
+const MAX_FILES: usize = 50_000;
+
+/// Files larger than this are compared by size and mtime only.
+const MAX_HASH_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileState {
+    len: u64,
+    modified: Option<SystemTime>,
+    hash: u64,
+    /// Newline count (0 for files too large to hash).
+    lines: u64,
+}
+
+/// Workspace-relative path -> state.
+type Snapshot = HashMap<String, FileState>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl ChangeKind {
+    fn marker(&self) -> char {
+        match self {
+            Self::Added => 'A',
+            Self::Modified => 'M',
+            Self::Deleted => 'D',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOrigin {
+    /// Written by the agent's file edit tools.
+    Agent,
+    /// Anything else: the user, other programs, commands.
+    External,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub origin: ChangeOrigin,
+}
+
+#[derive(Default)]
+struct WorkspaceState {
+    /// Snapshot taken at the start of the current turn.
+    baseline: Option<Snapshot>,
+    /// Paths the agent's edit tools wrote since the baseline.
+    agent_paths: HashSet<String>,
+    /// Changes between the previous two turn starts.
+    last_turn: Vec<FileChange>,
+}
+
+impl WorkspaceState {
+    /// Rough memory use, for the cache's cap.
+    fn bytes(&self) -> usize {
+        let snapshot = self.baseline.as_ref().map_or(0, |b| b.keys().map(|path| path.len() + 64).sum());
+        snapshot + self.agent_paths.iter().map(String::len).sum::<usize>() + self.last_turn.len() * 64
+    }
+}
+
+fn states() -> &'static Mutex<LruCache<PathBuf, WorkspaceState>> {
+    static INSTANCE: OnceLock<Mutex<LruCache<PathBuf, WorkspaceState>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        Mutex::new(LruCache::new("workspace_snapshots", 16, 128 << 20, |path, state| path.as_os_str().len() + state.bytes()))
+    })
+}
+
+/// Mark a turn boundary: snapshot the workspace and return the changes since
+/// the previous boundary (empty on the first turn).
+pub fn begin_turn(workdir: &Path) -> Vec<FileChange> {
+    let mut states = states().lock().unwrap();
+    let state = states.get_or_insert_with(workdir.to_path_buf(), WorkspaceState::default);
+    let now = scan(workdir, state.baseline.as_ref());
+    state.last_turn = match &state.baseline {
+        Some(baseline) => diff(baseline, &now, &state.agent_paths),
+        None => Vec::new(),
+    };
+    state.baseline = Some(now);
+    state.agent_paths.clear();
+    let last_turn = state.last_turn.clone();
+    states.reweigh(workdir);
+    last_turn
+}
+
+/// "3 files changed since last turn (2 by the agent, 1 outside it)", or
+/// `None` when nothing changed.
+pub fn change_feed(changes: &[FileChange]) -> Option<String> {
+    if changes.is_empty() {
+        return None;
+    }
+    let agent = changes.iter().filter(|c| c.origin == ChangeOrigin::Agent).count();
+    let external = changes.len() - agent;
+    let files = if changes.len() == 1 { "file" } else { "files" };
+    Some(format!(
+        "{} {files} changed since last turn ({agent} by the agent, {external} outside it)",
+        changes.len()
+    ))
+}
+
+/// Directories whose subdirectories are separate areas (`packages/web`).
+const CONTAINER_DIRS: &[&str] = &["packages", "apps", "services", "crates", "libs", "modules", "projects", "cmd", "plugins"];
+
+/// Areas with less of the source than this (percent) aren't described.
+const MIN_AREA_PERCENT: u64 = 5;
+
+/// Most areas described.
+const MAX_AREAS: usize = 6;
+
+/// Size and language breakdown of a workspace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceSummary {
+    pub total_files: usize,
+    pub total_lines: u64,
+    /// Language -> (files, lines), for recognised source files.
+    pub languages: BTreeMap<&'static str, (usize, u64)>,
+    /// Top-level directory (or `packages/<name>` and the like) -> language
+    /// -> lines, for recognised source files outside the root.
+    pub areas: BTreeMap<String, BTreeMap<&'static str, u64>>,
+}
+
+impl WorkspaceSummary {
+    /// The languages most of the source is in: "Rust", or "Go, Python"
+    /// when a second one has a quarter of the lines.
+    pub fn main_languages(&self) -> Option<String> {
+        let lines: BTreeMap<&'static str, u64> =
+            self.languages.iter().map(|(lang, (_, lines))| (*lang, *lines)).collect();
+        main_languages(&lines)
+    }
+
+    /// Which language each area is in, largest areas first, when they
+    /// differ: "web: TypeScript, services: Go, infra: Terraform".
+    pub fn language_mix(&self) -> Option<String> {
+        let source_lines: u64 = self.languages.values().map(|(_, lines)| lines).sum();
+        let mut areas: Vec<(&String, u64, String)> = self
+            .areas
+            .iter()
+            .filter_map(|(area, langs)| {
+                let lines: u64 = langs.values().sum();
+                if lines * 100 < source_lines * MIN_AREA_PERCENT {
+                    return None;
+                }
+                Some((area, lines, main_languages(langs)?))
+            })
+            .collect();
+        areas.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        areas.truncate(MAX_AREAS);
+        let first = &areas.first()?.2;
+        if areas.iter().all(|(_, _, main)| main == first) {
+            return None;
+        }
+        let parts: Vec<String> = areas.iter().map(|(area, _, main)| format!("{area}: {main}")).collect();
+        Some(parts.join(", "))
+    }
+}
+
+fn main_languages(lines: &BTreeMap<&'static str, u64>) -> Option<String> {
+    let total: u64 = lines.values().sum();
+    let mut langs: Vec<(&&str, &u64)> = lines.iter().filter(|(_, l)| **l > 0).collect();
+    langs.sort_by(|a, b| b.1.cmp(a.1));
+    let (first, _) = langs.first()?;
+    match langs.get(1) {
+        Some((second, l)) if **l * 4 >= total => Some(format!("{first}, {second}")),
+        _ => Some(first.to_string()),
+    }
+}
+
+impl std::fmt::Display for WorkspaceSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} files, {} lines", self.total_files, self.total_lines)?;
+        let source_lines: u64 = self.languages.values().map(|(_, lines)| lines).sum();
+        if source_lines == 0 {
+            return Ok(());
+        }
+        let mut langs: Vec<(&&str, &(usize, u64))> = self.languages.iter().collect();
+        langs.sort_by(|a, b| b.1 .1.cmp(&a.1 .1));
+        let parts: Vec<String> = langs
+            .iter()
+            .take(4)
+            .map(|(lang, (_, lines))| format!("{lang} {}%", lines * 100 / source_lines))
+            .collect();
+        write!(f, " ({})", parts.join(", "))?;
+        if let Some(mix) = self.language_mix() {
+            write!(f, "; by directory: {mix}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Summary of the workspace as of the current turn's snapshot.
+pub fn summary(workdir: &Path) -> Option<WorkspaceSummary> {
+    let mut states = states().lock().unwrap();
+    let baseline = states.get(workdir)?.baseline.as_ref()?;
+    Some(summarize(baseline))
+}
+
+fn summarize(snapshot: &Snapshot) -> WorkspaceSummary {
+    let mut summary = WorkspaceSummary { total_files: snapshot.len(), ..Default::default() };
+    for (path, state) in snapshot {
+        summary.total_lines += state.lines;
+        let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or_default();
+        if let Some(lang) = language(ext) {
+            let entry = summary.languages.entry(lang).or_default();
+            entry.0 += 1;
+            entry.1 += state.lines;
+            if let Some(area) = area(path) {
+                *summary.areas.entry(area).or_default().entry(lang).or_default() += state.lines;
+            }
+        }
+    }
+    summary
+}
+
+/// The area a file is in: its top-level directory, or two levels under a
+/// container directory; `None` for files at the root.
+fn area(path: &str) -> Option<String> {
+    let mut dirs: Vec<&str> = path.split('/').collect();
+    dirs.pop();
+    match dirs.as_slice() {
+        [] => None,
+        [container, name, ..] if CONTAINER_DIRS.contains(container) => Some(format!("{container}/{name}")),
+        [top, ..] => Some(top.to_string()),
+    }
+}
+
+pub(crate) fn language(ext: &str) -> Option<&'static str> {
+    Some(match ext {
+        "rs" => "Rust",
+        "py" | "pyi" => "Python",
+        "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "C++",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "swift" => "Swift",
+        "dart" => "Dart",
+        "scala" => "Scala",
+        "sh" | "bash" | "zsh" => "Shell",
+        "sql" => "SQL",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "tf" | "tfvars" | "hcl" => "Terraform",
+        _ => return None,
+    })
+}
+
+/// Remember that the agent's edit tools wrote `path`.
+pub fn record_agent_edit(workdir: &Path, path: &str) {
+    let path = Path::new(path);
+    let rel = path.strip_prefix(workdir).unwrap_or(path);
+    let rel = rel.strip_prefix("./").unwrap_or(rel);
+    states()
+        .lock()
+        .unwrap()
+        .get_or_insert_with(workdir.to_path_buf(), WorkspaceState::default)
+        .agent_paths
+        .insert(rel.to_string_lossy().replace('\\', "/"));
+}
+
+/// Record the files a successful edit tool call wrote.
+pub fn record_tool_edits(workdir: &Path, tool_name: &str, args: &Value) {
+    for path in tool_edit_paths(tool_name, args) {
+        record_agent_edit(workdir, path);
+    }
+}
+
+/// The files an edit tool call touches.
+pub fn tool_edit_paths<'a>(tool_name: &str, args: &'a Value) -> Vec<&'a str> {
+    let mut paths = Vec::new();
+    if let Some(input) = args.get("input").and_then(|v| v.as_str()) {
+        paths.extend(patch_paths(input));
+    }
+    if let Some(path) = args.get("path").and_then(|v| v.as_str()) {
+        paths.push(path);
+    } else if tool_name == "apply_patch" {
+        if let Some(patch) = args.get("patch").and_then(|v| v.as_str()) {
+            paths.extend(patch_paths(patch));
+        }
+    }
+    paths
+}
+
+/// Files named in a V4A or unified diff patch.
+fn patch_paths(patch: &str) -> Vec<&str> {
+    patch
+        .lines()
+        .filter_map(|line| {
+            [
+                "*** Add File:",
+                "*** Update File:",
+                "*** Delete File:",
+                "*** Move to:",
+                "+++ b/",
+            ]
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix))
+        })
+        .map(str::trim)
+        .filter(|p| !p.is_empty() && *p != "/dev/null")
+        .collect()
+}
+
+/// Changes since the start of the current turn, or `None` before the first
+/// turn boundary.
+pub fn current_changes(workdir: &Path) -> Option<Vec<FileChange>> {
+    let mut states = states().lock().unwrap();
+    let state = states.get(workdir)?;
+    let baseline = state.baseline.as_ref()?;
+    let now = scan(workdir, Some(baseline));
+    Some(diff(baseline, &now, &state.agent_paths))
+}
+
+/// `workspace_diff` tool.
+///
+/// Args:
+/// - scope: "turn" (default) for changes since the user's last message,
+///   "last_turn" for changes between the previous message and that one
+pub async fn workspace_diff(args: &Value, workdir: &Path) -> ToolResult {
+    let scope = args.get("scope").and_then(|v| v.as_str()).unwrap_or("turn");
+    let (title, changes) = match scope {
+        "turn" => {
+            let Some(changes) = current_changes(workdir) else {
+                begin_turn(workdir);
+                return ToolResult::ok(
+                    "No snapshot existed for this workspace; tracking changes from now on.",
+                );
+            };
+            ("since the user's last message", changes)
+        }
+        "last_turn" => {
+            let mut states = states().lock().unwrap();
+            let changes = states
+                .get(workdir)
+                .map(|s| s.last_turn.clone())
+                .unwrap_or_default();
+            ("during the previous turn and before the user's last message", changes)
+        }
+        other => {
+            return ToolResult::err(format!("Unknown scope '{other}'. Valid: turn, last_turn"));
+        }
+    };
+    ToolResult::ok(format_changes(title, &changes))
+}
+
+fn format_changes(title: &str, changes: &[FileChange]) -> String {
+    if changes.is_empty() {
+        return format!("No files changed {title}.");
+    }
+    let mut out = format!("{} file(s) changed {title}:\n", changes.len());
+    for change in changes {
+        let origin = match change.origin {
+            ChangeOrigin::Agent => "agent",
+            ChangeOrigin::External => "external",
+        };
+        out.push_str(&format!("  {} {} ({origin})\n", change.kind.marker(), change.path));
+    }
+    out
+}
+
+fn diff(before: &Snapshot, after: &Snapshot, agent_paths: &HashSet<String>) -> Vec<FileChange> {
+    let origin = |path: &str| {
+        if agent_paths.contains(path) {
+            ChangeOrigin::Agent
+        } else {
+            ChangeOrigin::External
+        }
+    };
+    let mut changes: Vec<FileChange> = after
+        .iter()
+        .filter_map(|(path, state)| {
+            let kind = match before.get(path) {
+                None => ChangeKind::Added,
+                Some(old) if old.hash != state.hash || old.len != state.len => ChangeKind::Modified,
+                Some(_) => return None,
+            };
+            Some(FileChange { path: path.clone(), kind, origin: origin(path) })
+        })
+        .chain(before.keys().filter(|p| !after.contains_key(*p)).map(|path| FileChange {
+            path: path.clone(),
+            kind: ChangeKind::Deleted,
+            origin: origin(path),
+        }))
+        .collect();
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+/// Snapshot `workdir`, reusing hashes from `previous` for files whose size
+/// and mtime are unchanged.
+fn scan(workdir: &Path, previous: Option<&Snapshot>) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    let walker = ignore::WalkBuilder::new(workdir)
+        .hidden(true)
+        .git_ignore(true)
+        .build();
+    for entry in walker.filter_map(|e| e.ok()) {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if snapshot.len() >= MAX_FILES {
+            tracing::warn!("workspace_diff: more than {MAX_FILES} files, snapshot truncated");
+            break;
+        }
+        let Ok(rel) = entry.path().strip_prefix(workdir) else {
+            continue;
+        };
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let len = meta.len();
+        let modified = meta.modified().ok();
+        let unchanged = previous
+            .and_then(|p| p.get(&rel))
+            .filter(|old| old.len == len && old.modified == modified && modified.is_some());
+        let (hash, lines) = match unchanged {
+            Some(old) => (old.hash, old.lines),
+            None if len > MAX_HASH_BYTES => (0, 0),
+            None => match std::fs::read(entry.path()) {
+                Ok(bytes) => {
+                    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                    bytes.hash(&mut hasher);
+                    let lines = bytes.iter().filter(|&&b| b == b'\n').count() as u64;
+                    (hasher.finish(), lines)
+                }
+                Err(_) => continue,
+            },
+        };
+        snapshot.insert(rel, FileState { len, modified, hash, lines });
+    }
+    snapshot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_turn_changes() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("keep.rs"), "fn main() {}").unwrap();
+        std::fs::write(root.join("edit.rs"), "a").unwrap();
+        std::fs::write(root.join("gone.rs"), "x").unwrap();
+
+        assert!(begin_turn(root).is_empty());
+
+        // The agent edits one file; the user another
+        std::fs::write(root.join("edit.rs"), "ab").unwrap();
+        record_tool_edits(root, "edit_file", &serde_json::json!({ "path": "./edit.rs" }));
+        std::fs::write(root.join("new.rs"), "user").unwrap();
+        std::fs::remove_file(root.join("gone.rs")).unwrap();
+
+        let changes = current_changes(root).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                FileChange { path: "edit.rs".into(), kind: ChangeKind::Modified, origin: ChangeOrigin::Agent },
+                FileChange { path: "gone.rs".into(), kind: ChangeKind::Deleted, origin: ChangeOrigin::External },
+                FileChange { path: "new.rs".into(), kind: ChangeKind::Added, origin: ChangeOrigin::External },
+            ]
+        );
+
+        let last_turn = begin_turn(root);
+        assert_eq!(last_turn, changes);
+        assert_eq!(
+            change_feed(&last_turn).as_deref(),
+            Some("3 files changed since last turn (1 by the agent, 2 outside it)")
+        );
+        assert!(current_changes(root).unwrap().is_empty());
+        assert_eq!(change_feed(&[]), None);
+
+        let summary = summary(root).unwrap();
+        assert_eq!(summary.total_files, 3);
+        assert_eq!(summary.languages["Rust"].0, 3);
+    }
+
+    #[test]
+    fn test_summary_display() {
+        let mut snapshot = Snapshot::new();
+        let state = |lines| FileState { len: 1, modified: None, hash: 0, lines };
+        snapshot.insert("src/main.rs".into(), state(75));
+        snapshot.insert("web/app.ts".into(), state(25));
+        snapshot.insert("README.md".into(), state(10));
+        assert_eq!(
+            summarize(&snapshot).to_string(),
+            "3 files, 110 lines (Rust 75%, TypeScript 25%); by directory: src: Rust, web: TypeScript"
+        );
+    }
+
+    #[test]
+    fn test_language_mix() {
+        let mut snapshot = Snapshot::new();
+        let state = |lines| FileState { len: 1, modified: None, hash: 0, lines };
+        snapshot.insert("frontend/src/app.tsx".into(), state(500));
+        snapshot.insert("frontend/src/style.css".into(), state(100));
+        snapshot.insert("services/auth/main.go".into(), state(300));
+        snapshot.insert("services/ml/train.py".into(), state(200));
+        snapshot.insert("infra/main.tf".into(), state(100));
+        snapshot.insert("scripts/tiny.sh".into(), state(10));
+        snapshot.insert("build.rs".into(), state(40));
+        let summary = summarize(&snapshot);
+        assert_eq!(
+            summary.language_mix().as_deref(),
+            Some("frontend: TypeScript, services/auth: Go, services/ml: Python, infra: Terraform")
+        );
+        assert_eq!(summary.main_languages().as_deref(), Some("TypeScript, Go"));
+
+        // One language everywhere says nothing more
+        let mut snapshot = Snapshot::new();
+        snapshot.insert("core/lib.rs".into(), state(100));
+        snapshot.insert("cli/main.rs".into(), state(100));
+        assert_eq!(summarize(&snapshot).language_mix(), None);
+    }
+
+    #[test]
+    fn test_patch_paths() {
+        let patch = "*** Begin Patch\n*** Update File: src/a.rs\n@@\n-x\n+y\n*** Add File: src/b.rs\n+new\n*** End Patch";
+        assert_eq!(patch_paths(patch), vec!["src/a.rs", "src/b.rs"]);
+        assert_eq!(patch_paths("--- a/lib.rs\n+++ b/lib.rs\n@@ -1 +1 @@"), vec!["lib.rs"]);
+    }
+}
@@ -0,0 +1,150 @@
+//! Workspace trust.
+//!
+//! A workspace's own files can steer the agent: build scripts run by a
+//! "check", git hooks, prompt fragments telling it to fetch a URL. So until
+//! the user says they trust a workspace it runs restricted: plan mode (no
+//! edits), no commands, nothing fetched, and nothing uploaded for indexing.
+//! Decisions are kept in `~/.forge/trust.json`, keyed by canonical path.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::tools::Tool;
+
+/// Added to the first turn's prompt of a restricted workspace.
+pub const RESTRICTED_PROMPT: &str = "Restricted mode: the user hasn't trusted this workspace yet. \
+You can read and search files, but not edit them, run commands, or fetch URLs. \
+Answer or propose a plan instead, and mention that trusting the workspace enables changes.";
+
+pub struct TrustStore {
+    file: PathBuf,
+}
+
+impl TrustStore {
+    /// The user's store, `~/.forge/trust.json`.
+    pub fn user() -> Option<Self> {
+        Some(Self::at(dirs::home_dir()?.join(".forge").join("trust.json")))
+    }
+
+    pub fn at(file: PathBuf) -> Self {
+        Self { file }
+    }
+
+    fn load(&self) -> BTreeMap<String, bool> {
+        std::fs::read_to_string(&self.file)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    /// `Some(trusted)` once the user decided about `workspace`.
+    pub fn decision(&self, workspace: &Path) -> Option<bool> {
+        self.load().get(&key(workspace)).copied()
+    }
+
+    pub fn set(&self, workspace: &Path, trusted: bool) -> Result<()> {
+        let mut decisions = self.load();
+        decisions.insert(key(workspace), trusted);
+        if let Some(parent) = self.file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.file, serde_json::to_string_pretty(&decisions)?)
+            .with_context(|| format!("writing {}", self.file.display()))
+    }
+}
+
+fn key(workspace: &Path) -> String {
+    workspace
+        .canonicalize()
+        .unwrap_or_else(|_| workspace.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The user's decision about `workspace`, if any.
+pub fn decision(workspace: &Path) -> Option<bool> {
+    TrustStore::user()?.decision(workspace)
+}
+
+/// Only workspaces the user trusted run unrestricted.
+pub fn is_trusted(workspace: &Path) -> bool {
+    decision(workspace) == Some(true)
+}
+
+pub fn set_trusted(workspace: &Path, trusted: bool) -> Result<()> {
+    TrustStore::user().context("no home directory")?.set(workspace, trusted)
+}
+
+/// Whether tool `name` may run in a restricted workspace: nothing that
+/// changes files, runs a program, or reaches the network. Plugin tools run
+/// plugin code, so they're out too.
+pub fn allowed_when_restricted(name: &str) -> bool {
+    let Some(tool) = Tool::from_name(name) else {
+        return false;
+    };
+    !tool.is_mutating()
+        && !matches!(
+            tool,
+            Tool::Diagnostics
+                | Tool::AuditDependencies
+                | Tool::RunProject
+                | Tool::StopProject
+                | Tool::ShellSession
+                | Tool::Git
+                | Tool::Review
+                | Tool::SdkManager
+                | Tool::Fetch
+        )
+}
+
+/// The error for a tool refused in a restricted workspace.
+pub fn refusal(name: &str) -> String {
+    format!("`{name}` is disabled because this workspace isn't trusted (restricted mode). Ask the user to trust the workspace to enable it.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_decisions_persist() {
+        let home = tempdir().unwrap();
+        let workspace = tempdir().unwrap();
+        let store = TrustStore::at(home.path().join("trust.json"));
+        assert_eq!(store.decision(workspace.path()), None);
+
+        store.set(workspace.path(), false).unwrap();
+        assert_eq!(store.decision(workspace.path()), Some(false));
+        store.set(&workspace.path().join("."), true).unwrap();
+        assert_eq!(TrustStore::at(home.path().join("trust.json")).decision(workspace.path()), Some(true));
+    }
+
+    #[test]
+    fn test_restricted_tools() {
+        for name in ["read_file", "grep", "glob", "list_files", "workspace_diff", "attempt_completion"] {
+            assert!(allowed_when_restricted(name), "{name}");
+        }
+        for name in ["write_file", "run", "git", "fetch", "diagnostics", "some_plugin_tool"] {
+            assert!(!allowed_when_restricted(name), "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restricted_execution() {
+        use crate::tools::{execute_with_options, ExecuteOptions, ToolCall};
+        let dir = tempdir().unwrap();
+        let opts = ExecuteOptions { restricted: true, ..Default::default() };
+        let call = |name: &str, arguments| ToolCall { name: name.to_string(), arguments, thought_signature: None };
+
+        let result = execute_with_options(&call("run", serde_json::json!({ "command": "touch x" })), dir.path(), &opts).await;
+        assert!(!result.success && result.output.contains("isn't trusted"));
+        assert!(!dir.path().join("x").exists());
+
+        std::fs::write(dir.path().join("a.txt"), "hi").unwrap();
+        let result = execute_with_options(&call("read_file", serde_json::json!({ "path": "a.txt" })), dir.path(), &opts).await;
+        assert!(result.success);
+    }
+}
@@ -0,0 +1,179 @@
+//! Multi-line input buffer for the terminal chat.
+
+#[derive(Debug, Clone)]
+pub struct Editor {
+    lines: Vec<String>,
+    /// Cursor line and column (in chars).
+    row: usize,
+    col: usize,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self { lines: vec![String::new()], row: 0, col: 0 }
+    }
+}
+
+impl Editor {
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.iter().all(|l| l.trim().is_empty())
+    }
+
+    /// Take the text and reset the buffer.
+    pub fn take(&mut self) -> String {
+        let text = self.text();
+        *self = Self::default();
+        text
+    }
+
+    fn byte_col(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices().nth(self.col).map_or(line.len(), |(i, _)| i)
+    }
+
+    fn line_chars(&self, row: usize) -> usize {
+        self.lines[row].chars().count()
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        let at = self.byte_col();
+        self.lines[self.row].insert(at, c);
+        self.col += 1;
+    }
+
+    pub fn insert_str(&mut self, s: &str) {
+        for c in s.chars() {
+            if c == '\n' {
+                self.newline();
+            } else {
+                self.insert_char(c);
+            }
+        }
+    }
+
+    pub fn newline(&mut self) {
+        let at = self.byte_col();
+        let rest = self.lines[self.row].split_off(at);
+        self.row += 1;
+        self.lines.insert(self.row, rest);
+        self.col = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let at = self.byte_col();
+            self.lines[self.row].remove(at);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_chars(self.row);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// The word before the cursor, if it starts with `prefix` (e.g. `@`).
+    pub fn word_before_cursor(&self, prefix: char) -> Option<&str> {
+        let line = &self.lines[self.row][..self.byte_col()];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..];
+        word.strip_prefix(prefix)
+    }
+
+    /// Replace the word before the cursor with `replacement`.
+    pub fn replace_word_before_cursor(&mut self, replacement: &str) {
+        let end = self.byte_col();
+        let line = &self.lines[self.row];
+        let start = line[..end].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let removed = line[start..end].chars().count();
+        self.lines[self.row].replace_range(start..end, replacement);
+        self.col = self.col - removed + replacement.chars().count();
+    }
+
+    pub fn left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_chars(self.row);
+        }
+    }
+
+    pub fn right(&mut self) {
+        if self.col < self.line_chars(self.row) {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    /// Move up a line; false when already on the first one.
+    pub fn up(&mut self) -> bool {
+        if self.row == 0 {
+            return false;
+        }
+        self.row -= 1;
+        self.col = self.col.min(self.line_chars(self.row));
+        true
+    }
+
+    /// Move down a line; false when already on the last one.
+    pub fn down(&mut self) -> bool {
+        if self.row + 1 >= self.lines.len() {
+            return false;
+        }
+        self.row += 1;
+        self.col = self.col.min(self.line_chars(self.row));
+        true
+    }
+
+    pub fn home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.col = self.line_chars(self.row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editing() {
+        let mut ed = Editor::default();
+        ed.insert_str("fix é\nbug");
+        assert_eq!(ed.cursor(), (1, 3));
+        ed.up();
+        assert_eq!(ed.cursor(), (0, 3));
+        ed.newline();
+        assert_eq!(ed.text(), "fix\n é\nbug");
+        ed.backspace();
+        assert_eq!(ed.text(), "fix é\nbug");
+        ed.end();
+        ed.backspace();
+        assert_eq!(ed.text(), "fix \nbug");
+
+        ed.down();
+        ed.end();
+        ed.insert_str(" in @src/ma");
+        assert_eq!(ed.word_before_cursor('@'), Some("src/ma"));
+        ed.replace_word_before_cursor("@src/main.rs");
+        assert_eq!(ed.take(), "fix \nbug in @src/main.rs");
+        assert!(ed.is_empty());
+    }
+}
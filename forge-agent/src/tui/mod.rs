@@ -0,0 +1,377 @@
+//! Terminal chat (`forge-cli chat`), for using the agent without the IDE.
+//!
+//! - Multi-line input: Enter sends, Alt+Enter or Ctrl+J inserts a newline
+//! - `@` opens a fuzzy file picker; Tab/Enter inserts the path
+//! - Mutating tool calls open an approval dialog (y/n)
+//! - PageUp/PageDown (or Up/Down on an empty input) scroll the transcript
+//! - Ctrl+C quits
+
+mod editor;
+mod picker;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::agent_loop::{self, AgentEvent};
+use crate::api::ChatApi;
+use editor::Editor;
+use picker::Picker;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Speaker {
+    User,
+    Agent,
+    Tool,
+    Error,
+}
+
+struct Entry {
+    speaker: Speaker,
+    text: String,
+}
+
+struct Approval {
+    summary: String,
+    reply: oneshot::Sender<bool>,
+}
+
+struct App {
+    api: Arc<dyn ChatApi>,
+    workspace: PathBuf,
+    conversation_id: String,
+    transcript: Vec<Entry>,
+    /// Lines scrolled up from the bottom.
+    scroll_back: u16,
+    editor: Editor,
+    picker: Option<Picker>,
+    approval: Option<Approval>,
+    busy: bool,
+    status: String,
+    quit: bool,
+}
+
+impl App {
+    fn new(api: Arc<dyn ChatApi>, workspace: PathBuf) -> Self {
+        Self {
+            api,
+            workspace,
+            conversation_id: uuid::Uuid::new_v4().to_string(),
+            transcript: Vec::new(),
+            scroll_back: 0,
+            editor: Editor::default(),
+            picker: None,
+            approval: None,
+            busy: false,
+            status: String::new(),
+            quit: false,
+        }
+    }
+
+    fn push(&mut self, speaker: Speaker, text: impl Into<String>) {
+        self.transcript.push(Entry { speaker, text: text.into() });
+        self.scroll_back = 0;
+    }
+
+    fn on_agent_event(&mut self, event: AgentEvent) {
+        match event {
+            AgentEvent::Text(text) => match self.transcript.last_mut() {
+                Some(entry) if entry.speaker == Speaker::Agent => entry.text.push_str(&text),
+                _ => self.push(Speaker::Agent, text),
+            },
+            AgentEvent::Thinking(message) => self.status = message,
+            AgentEvent::ToolStart { summary, .. } => {
+                self.status = summary.clone();
+                self.push(Speaker::Tool, format!("▸ {summary}"));
+            }
+            AgentEvent::ToolEnd { name, success } => {
+                if !success {
+                    self.push(Speaker::Tool, format!("✗ {name} failed"));
+                }
+            }
+            AgentEvent::Approval { summary, reply } => {
+                self.approval = Some(Approval { summary, reply });
+            }
+            AgentEvent::Done => {
+                self.busy = false;
+                self.status.clear();
+            }
+            AgentEvent::Error(error) => {
+                self.busy = false;
+                self.status.clear();
+                self.push(Speaker::Error, error);
+            }
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent, events: &mpsc::UnboundedSender<AgentEvent>) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        if let Some(approval) = self.approval.take() {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    let _ = approval.reply.send(true);
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    self.push(Speaker::Tool, format!("✗ rejected: {}", approval.summary));
+                    let _ = approval.reply.send(false);
+                }
+                _ => self.approval = Some(approval),
+            }
+            return;
+        }
+        if self.picker.is_some() {
+            self.on_picker_key(key);
+            return;
+        }
+        let newline = key.code == KeyCode::Enter && key.modifiers.contains(KeyModifiers::ALT)
+            || key.code == KeyCode::Char('j') && key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            _ if newline => self.editor.newline(),
+            KeyCode::Enter => self.send(events),
+            KeyCode::Char('@') => {
+                self.editor.insert_char('@');
+                self.picker = Some(Picker::new(&self.workspace));
+            }
+            KeyCode::Char(c) => self.editor.insert_char(c),
+            KeyCode::Backspace => self.editor.backspace(),
+            KeyCode::Left => self.editor.left(),
+            KeyCode::Right => self.editor.right(),
+            KeyCode::Home => self.editor.home(),
+            KeyCode::End => self.editor.end(),
+            KeyCode::Up => {
+                if !self.editor.up() {
+                    self.scroll_back = self.scroll_back.saturating_add(1);
+                }
+            }
+            KeyCode::Down => {
+                if !self.editor.down() {
+                    self.scroll_back = self.scroll_back.saturating_sub(1);
+                }
+            }
+            KeyCode::PageUp => self.scroll_back = self.scroll_back.saturating_add(10),
+            KeyCode::PageDown => self.scroll_back = self.scroll_back.saturating_sub(10),
+            _ => {}
+        }
+    }
+
+    fn on_picker_key(&mut self, key: KeyEvent) {
+        let Some(picker) = self.picker.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.picker = None,
+            KeyCode::Up => picker.up(),
+            KeyCode::Down => picker.down(),
+            KeyCode::Enter | KeyCode::Tab => {
+                if let Some(path) = picker.selection().map(String::from) {
+                    self.editor.replace_word_before_cursor(&format!("@{path} "));
+                }
+                self.picker = None;
+            }
+            KeyCode::Backspace => {
+                self.editor.backspace();
+                match self.editor.word_before_cursor('@').map(String::from) {
+                    Some(query) => picker.set_query(&query),
+                    None => self.picker = None,
+                }
+            }
+            KeyCode::Char(' ') => {
+                self.editor.insert_char(' ');
+                self.picker = None;
+            }
+            KeyCode::Char(c) => {
+                self.editor.insert_char(c);
+                if let Some(query) = self.editor.word_before_cursor('@') {
+                    picker.set_query(query);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn send(&mut self, events: &mpsc::UnboundedSender<AgentEvent>) {
+        if self.busy || self.editor.is_empty() {
+            return;
+        }
+        let text = self.editor.take();
+        self.push(Speaker::User, text.clone());
+        self.busy = true;
+        self.status = "Thinking...".to_string();
+        crate::tools::working_set::record_mentions(&self.workspace, &text);
+        let question = with_mentioned_files(&text, &self.workspace);
+        tokio::spawn(agent_loop::run_turn(
+            self.api.clone(),
+            question,
+            self.workspace.clone(),
+            self.conversation_id.clone(),
+            events.clone(),
+        ));
+    }
+}
+
+/// Append the contents of `@path` mentions that exist in the workspace.
+fn with_mentioned_files(text: &str, workspace: &std::path::Path) -> String {
+    const MAX_FILE_CHARS: usize = 20_000;
+    let mut out = text.to_string();
+    for word in text.split_whitespace() {
+        let Some(path) = word.strip_prefix('@') else {
+            continue;
+        };
+        let Ok(content) = std::fs::read_to_string(workspace.join(path)) else {
+            continue;
+        };
+        let content: String = content.chars().take(MAX_FILE_CHARS).collect();
+        out.push_str(&format!("\n\n[{path}]\n```\n{content}\n```"));
+    }
+    out
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let input_height = (app.editor.lines().len() as u16).clamp(1, 8) + 2;
+    let [transcript_area, input_area, status_area] = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Length(input_height),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    draw_transcript(frame, app, transcript_area);
+
+    let input_lines: Vec<Line> = app.editor.lines().iter().map(|l| Line::raw(l.as_str())).collect();
+    let (row, col) = app.editor.cursor();
+    let inner_height = input_height.saturating_sub(2);
+    let input_scroll = (row as u16).saturating_sub(inner_height.saturating_sub(1));
+    frame.render_widget(
+        Paragraph::new(input_lines)
+            .scroll((input_scroll, 0))
+            .block(Block::default().borders(Borders::ALL).title(" Message (Enter: send, Alt+Enter: newline, @: file) ")),
+        input_area,
+    );
+    if app.approval.is_none() {
+        frame.set_cursor_position((
+            input_area.x + 1 + col as u16,
+            input_area.y + 1 + row as u16 - input_scroll,
+        ));
+    }
+
+    let status = if app.busy {
+        format!(" ⋯ {}", app.status)
+    } else {
+        format!(" {}  (Ctrl+C to quit)", app.workspace.display())
+    };
+    frame.render_widget(Paragraph::new(status).style(Style::default().fg(Color::DarkGray)), status_area);
+
+    if let Some(picker) = &app.picker {
+        let height = (picker.matches.len() as u16 + 2).min(input_area.y);
+        let area = Rect { x: input_area.x + 1, y: input_area.y - height, width: input_area.width.min(70), height };
+        let items: Vec<ListItem> = picker.matches.iter().map(|m| ListItem::new(m.as_str())).collect();
+        let mut state = ListState::default().with_selected(Some(picker.selected));
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(format!(" @{} ", picker.query)))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            area,
+            &mut state,
+        );
+    }
+
+    if let Some(approval) = &app.approval {
+        let area = frame.area();
+        let width = area.width.min(80);
+        let dialog = Rect { x: (area.width - width) / 2, y: area.height / 3, width, height: 5 };
+        frame.render_widget(Clear, dialog);
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::raw(approval.summary.as_str()),
+                Line::raw(""),
+                Line::from(vec![
+                    Span::styled("[y]", Style::default().fg(Color::Green)),
+                    Span::raw(" approve   "),
+                    Span::styled("[n]", Style::default().fg(Color::Red)),
+                    Span::raw(" reject"),
+                ]),
+            ])
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(" Approve tool call? ")),
+            dialog,
+        );
+    }
+}
+
+fn draw_transcript(frame: &mut Frame, app: &App, area: Rect) {
+    let mut lines: Vec<Line> = Vec::new();
+    for entry in &app.transcript {
+        let (label, style) = match entry.speaker {
+            Speaker::User => ("you", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Speaker::Agent => ("forge", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Speaker::Tool => ("", Style::default().fg(Color::DarkGray)),
+            Speaker::Error => ("error", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        };
+        if !label.is_empty() {
+            lines.push(Line::styled(label, style));
+        }
+        for line in entry.text.lines() {
+            let style = if entry.speaker == Speaker::Tool { style } else { Style::default() };
+            lines.push(Line::styled(line.to_string(), style));
+        }
+        lines.push(Line::raw(""));
+    }
+    // Wrapped height, to keep the view pinned to the bottom
+    let width = area.width.max(1) as usize;
+    let total: usize = lines.iter().map(|l| l.width().max(1).div_ceil(width)).sum();
+    let bottom = total.saturating_sub(area.height as usize) as u16;
+    let scroll = bottom.saturating_sub(app.scroll_back);
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).scroll((scroll, 0)), area);
+}
+
+/// Run the terminal chat until the user quits.
+pub async fn run(workspace: PathBuf) -> anyhow::Result<()> {
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+    let mut app = App::new(crate::api::chat_api()?, workspace);
+    let mut terminal = ratatui::init();
+    let result = async {
+        while !app.quit {
+            terminal.draw(|frame| draw(frame, &app))?;
+            while let Ok(event) = events_rx.try_recv() {
+                app.on_agent_event(event);
+            }
+            let has_input = tokio::task::block_in_place(|| event::poll(Duration::from_millis(50)))?;
+            if has_input {
+                match event::read()? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => app.on_key(key, &events_tx),
+                    Event::Paste(text) => app.editor.insert_str(&text),
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+    .await;
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_mentioned_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+        let question = with_mentioned_files("see @notes.txt and @missing.rs", dir.path());
+        assert_eq!(question, "see @notes.txt and @missing.rs\n\n[notes.txt]\n```\nhello\n```");
+    }
+}
@@ -0,0 +1,117 @@
+//! `@file` fuzzy picker over the workspace (gitignore-aware).
+
+use std::path::Path;
+
+/// Most files the picker indexes.
+const MAX_FILES: usize = 20_000;
+
+/// Matches shown at once.
+pub const MAX_MATCHES: usize = 10;
+
+pub struct Picker {
+    files: Vec<String>,
+    pub query: String,
+    pub matches: Vec<String>,
+    pub selected: usize,
+}
+
+impl Picker {
+    pub fn new(workspace: &Path) -> Self {
+        let files = ignore::WalkBuilder::new(workspace)
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+            .filter_map(|e| {
+                let rel = e.path().strip_prefix(workspace).ok()?;
+                Some(rel.to_string_lossy().replace('\\', "/"))
+            })
+            .take(MAX_FILES)
+            .collect();
+        Self::with_files(files)
+    }
+
+    fn with_files(files: Vec<String>) -> Self {
+        let mut picker = Self { files, query: String::new(), matches: Vec::new(), selected: 0 };
+        picker.set_query("");
+        picker
+    }
+
+    pub fn set_query(&mut self, query: &str) {
+        self.query = query.to_string();
+        let mut scored: Vec<(i64, &String)> = self
+            .files
+            .iter()
+            .filter_map(|f| Some((score(f, query)?, f)))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.len().cmp(&b.1.len())));
+        self.matches = scored.into_iter().take(MAX_MATCHES).map(|(_, f)| f.clone()).collect();
+        self.selected = 0;
+    }
+
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn selection(&self) -> Option<&str> {
+        self.matches.get(self.selected).map(String::as_str)
+    }
+}
+
+/// Fuzzy subsequence score of `query` in `path` (case-insensitive); `None`
+/// when not all query characters appear in order. Consecutive matches and
+/// matches in the file name score higher.
+fn score(path: &str, query: &str) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let path_lower = path.to_lowercase();
+    let name_start = path_lower.rfind('/').map_or(0, |i| i + 1);
+    let mut score = 0i64;
+    let mut last: Option<usize> = None;
+    let mut search_from = 0;
+    for qc in query.to_lowercase().chars() {
+        let offset = path_lower[search_from..].find(qc)?;
+        let at = search_from + offset;
+        score += 1;
+        if last.is_some_and(|l| l + 1 == at) {
+            score += 5;
+        }
+        if at >= name_start {
+            score += 3;
+        }
+        last = Some(at);
+        search_from = at + qc.len_utf8();
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_matching() {
+        let mut picker = Picker::with_files(vec![
+            "src/main.rs".into(),
+            "src/tools/mod.rs".into(),
+            "docs/maintenance.md".into(),
+            "Cargo.toml".into(),
+        ]);
+        picker.set_query("main");
+        assert_eq!(picker.selection(), Some("src/main.rs"));
+        picker.set_query("tmod");
+        assert_eq!(picker.matches, vec!["src/tools/mod.rs"]);
+        picker.set_query("xyz");
+        assert!(picker.matches.is_empty());
+        picker.set_query("");
+        assert_eq!(picker.matches.len(), 4);
+        picker.down();
+        assert_eq!(picker.selected, 1);
+    }
+}
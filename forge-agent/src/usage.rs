@@ -0,0 +1,274 @@
+//! Token usage and cost ledger.
+//!
+//! Every model call that reports usage is appended to `~/.forge/usage.jsonl`
+//! with its conversation, provider and model, priced from a built-in table
+//! (USD per million tokens) that the config can override or extend:
+//!
+//! ```toml
+//! [pricing."gpt-4.1"]
+//! input = 2.0
+//! output = 8.0
+//!
+//! [budget]
+//! daily = 5.0      # USD per day
+//! session = 1.0    # USD per conversation
+//! ```
+//!
+//! [`summarize`] turns the ledger into the usage dashboard's totals.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// Days of history in the provider breakdown.
+pub const PROVIDER_WINDOW_DAYS: i64 = 30;
+/// Most conversations listed in a summary.
+pub const MAX_CONVERSATIONS: usize = 100;
+
+/// Built-in prices, USD per million (input, output) tokens, by model prefix.
+/// Longer prefixes are listed first so they win.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-haiku-4", 1.0, 5.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("o4-mini", 1.1, 4.4),
+    ("o3", 2.0, 8.0),
+    ("gemini-2.5-flash", 0.3, 2.5),
+    ("gemini-2.5-pro", 1.25, 10.0),
+    ("gemini-2.0-flash", 0.1, 0.4),
+];
+
+/// One model call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// Unix seconds.
+    pub at: i64,
+    pub conversation_id: String,
+    /// The conversation's first question, on its first call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// `None` when the model has no known price.
+    pub cost_usd: Option<f64>,
+}
+
+impl UsageRecord {
+    pub fn new(conversation_id: &str, provider: &str, model: &str, input_tokens: u64, output_tokens: u64) -> Self {
+        let cost_usd = price(provider, model)
+            .map(|(input, output)| (input_tokens as f64 * input + output_tokens as f64 * output) / 1_000_000.0);
+        Self {
+            at: chrono::Utc::now().timestamp(),
+            conversation_id: conversation_id.to_string(),
+            title: None,
+            provider: provider.to_string(),
+            model: model.to_string(),
+            input_tokens,
+            output_tokens,
+            cost_usd,
+        }
+    }
+
+    pub fn with_title(mut self, question: &str) -> Self {
+        let title: String = question.trim().lines().next().unwrap_or("").chars().take(80).collect();
+        self.title = (!title.is_empty()).then_some(title);
+        self
+    }
+}
+
+/// USD per million (input, output) tokens for `model`: the config's
+/// `pricing.<model>`, else the built-in table. Local models are free.
+pub fn price(provider: &str, model: &str) -> Option<(f64, f64)> {
+    let config = crate::config::active();
+    let configured = |field: &str| {
+        config
+            .get(&format!("pricing.{model}.{field}"))
+            .and_then(|v| v.trim().parse::<f64>().ok())
+    };
+    if let (Some(input), Some(output)) = (configured("input"), configured("output")) {
+        return Some((input, output));
+    }
+    builtin_price(provider, model)
+}
+
+fn builtin_price(provider: &str, model: &str) -> Option<(f64, f64)> {
+    if provider == "ollama" {
+        return Some((0.0, 0.0));
+    }
+    // "anthropic/claude-sonnet-4" (OpenRouter) prices like "claude-sonnet-4"
+    let name = model.rsplit('/').next().unwrap_or(model);
+    PRICES
+        .iter()
+        .find(|(prefix, ..)| name.starts_with(prefix))
+        .map(|&(_, input, output)| (input, output))
+}
+
+fn ledger_path() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".forge").join("usage.jsonl"))
+}
+
+/// Append `record` to the ledger.
+pub fn record(record: &UsageRecord) {
+    let Some(path) = ledger_path() else {
+        return;
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::OpenOptions::new().create(true).append(true).open(&path))
+        .and_then(|mut file| {
+            let line = serde_json::to_string(record).unwrap_or_default();
+            writeln!(file, "{line}")
+        });
+    if let Err(e) = written {
+        tracing::warn!("Recording token usage failed: {e}");
+    }
+}
+
+/// Every record in the ledger, oldest first.
+pub fn load() -> Vec<UsageRecord> {
+    let Some(content) = ledger_path().and_then(|p| std::fs::read_to_string(p).ok()) else {
+        return Vec::new();
+    };
+    content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Totals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    /// Calls to models without a known price (not in `cost_usd`).
+    pub unpriced: u64,
+}
+
+impl Totals {
+    fn add(&mut self, record: &UsageRecord) {
+        self.requests += 1;
+        self.input_tokens += record.input_tokens;
+        self.output_tokens += record.output_tokens;
+        match record.cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationTotals {
+    pub conversation_id: String,
+    pub title: Option<String>,
+    /// Unix seconds of its last call.
+    pub last_used: i64,
+    pub totals: Totals,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Summary {
+    pub today: Totals,
+    /// The conversation asked about.
+    pub session: Totals,
+    /// Last [`PROVIDER_WINDOW_DAYS`] days by provider, costliest first.
+    pub providers: Vec<(String, Totals)>,
+    /// Most recently used first.
+    pub conversations: Vec<ConversationTotals>,
+    pub daily_budget_usd: Option<f64>,
+    pub session_budget_usd: Option<f64>,
+}
+
+/// Totals of `records` as of `now` (unix seconds, days in local time), with
+/// `conversation_id` as the current session.
+pub fn summarize(records: &[UsageRecord], now: i64, conversation_id: Option<&str>) -> Summary {
+    let day = |at: i64| Local.timestamp_opt(at, 0).single().map(|t| t.date_naive());
+    let today = day(now);
+    let window_start = now - PROVIDER_WINDOW_DAYS * 24 * 60 * 60;
+
+    let mut summary = Summary::default();
+    let mut providers: HashMap<&str, Totals> = HashMap::new();
+    let mut conversations: HashMap<&str, ConversationTotals> = HashMap::new();
+    for record in records {
+        if day(record.at) == today {
+            summary.today.add(record);
+        }
+        if Some(record.conversation_id.as_str()) == conversation_id {
+            summary.session.add(record);
+        }
+        if record.at >= window_start {
+            providers.entry(&record.provider).or_default().add(record);
+        }
+        let conversation = conversations
+            .entry(&record.conversation_id)
+            .or_insert_with(|| ConversationTotals {
+                conversation_id: record.conversation_id.clone(),
+                title: None,
+                last_used: record.at,
+                totals: Totals::default(),
+            });
+        conversation.totals.add(record);
+        conversation.last_used = conversation.last_used.max(record.at);
+        if conversation.title.is_none() {
+            conversation.title = record.title.clone();
+        }
+    }
+
+    summary.providers = providers.into_iter().map(|(p, t)| (p.to_string(), t)).collect();
+    summary.providers.sort_by(|a, b| b.1.cost_usd.total_cmp(&a.1.cost_usd).then_with(|| a.0.cmp(&b.0)));
+    summary.conversations = conversations.into_values().collect();
+    summary.conversations.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+    summary.conversations.truncate(MAX_CONVERSATIONS);
+
+    let config = crate::config::active();
+    let budget = |key: &str| config.get(key).and_then(|v| v.trim().parse::<f64>().ok()).filter(|b| *b > 0.0);
+    summary.daily_budget_usd = budget("budget.daily");
+    summary.session_budget_usd = budget("budget.session");
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_ledger() {
+        assert_eq!(builtin_price("openrouter", "anthropic/claude-sonnet-4"), Some((3.0, 15.0)));
+        assert_eq!(builtin_price("openai", "gpt-4o-mini-2024-07-18"), Some((0.15, 0.6)));
+        assert_eq!(builtin_price("openai", "some-new-model"), None);
+
+        let now = chrono::Utc::now().timestamp();
+        let at = |record: UsageRecord, at: i64| UsageRecord { at, ..record };
+        let records = vec![
+            at(UsageRecord::new("old", "openai", "gpt-4o", 1000, 0).with_title("Earlier"), now - 40 * 86400),
+            at(UsageRecord::new("c1", "anthropic", "claude-sonnet-4", 1_000_000, 100_000).with_title("Fix it"), now),
+            at(UsageRecord::new("c1", "anthropic", "claude-sonnet-4", 0, 0), now),
+            at(UsageRecord::new("c2", "openai", "mystery", 10, 10), now),
+        ];
+        let summary = summarize(&records, now, Some("c1"));
+
+        assert_eq!(summary.session.requests, 2);
+        assert!((summary.session.cost_usd - 4.5).abs() < 1e-9);
+        assert_eq!((summary.today.requests, summary.today.unpriced), (3, 1));
+        // The 40-day-old call is outside the provider window
+        let providers: Vec<&str> = summary.providers.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(providers, ["anthropic", "openai"]);
+        assert_eq!(summary.providers[1].1.requests, 1);
+
+        assert_eq!(summary.conversations.len(), 3);
+        assert_eq!(summary.conversations.last().unwrap().title.as_deref(), Some("Earlier"));
+        let c1 = summary.conversations.iter().find(|c| c.conversation_id == "c1").unwrap();
+        assert_eq!(c1.title.as_deref(), Some("Fix it"));
+    }
+}
@@ -0,0 +1,205 @@
+//! Multiple workspace roots (monorepo packages opened together).
+//!
+//! The IDE opens one folder, the primary root. More roots come from
+//! `FORGE_WORKSPACE_ROOTS` (a path list, `:`-separated, `;` on Windows) or,
+//! when that is unset, `.forge/roots` in the primary root (one path per
+//! line, `#` comments). Relative paths are relative to the primary root.
+//!
+//! Each root is addressed by its directory name: `web/src/App.tsx` is
+//! `src/App.tsx` in the root named `web`. Paths that don't start with another
+//! root's name resolve against the primary root, so single-root workspaces
+//! behave exactly as before.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceRoot {
+    /// Directory name, unique among the roots.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceRoots {
+    /// The primary root comes first.
+    roots: Vec<WorkspaceRoot>,
+}
+
+impl WorkspaceRoots {
+    /// Just the primary root.
+    pub fn single(primary: &Path) -> Self {
+        Self { roots: vec![WorkspaceRoot { name: root_name(primary), path: primary.to_path_buf() }] }
+    }
+
+    /// The primary root plus any configured extra roots that exist.
+    pub fn discover(primary: &Path) -> Self {
+        let extra: Vec<PathBuf> = match crate::config::var("FORGE_WORKSPACE_ROOTS") {
+            Some(list) => std::env::split_paths(&list).collect(),
+            None => std::fs::read_to_string(primary.join(".forge").join("roots"))
+                .map(|content| parse_roots_file(&content))
+                .unwrap_or_default(),
+        };
+        Self::from_paths(primary, extra)
+    }
+
+    fn from_paths(primary: &Path, extra: Vec<PathBuf>) -> Self {
+        let mut roots = Self::single(primary);
+        for path in extra {
+            let path = if path.is_relative() { primary.join(path) } else { path };
+            let path = path.canonicalize().unwrap_or(path);
+            if !path.is_dir() || roots.roots.iter().any(|r| r.path == path) {
+                continue;
+            }
+            let base = root_name(&path);
+            let mut name = base.clone();
+            let mut n = 2;
+            while roots.roots.iter().any(|r| r.name == name) {
+                name = format!("{base}-{n}");
+                n += 1;
+            }
+            roots.roots.push(WorkspaceRoot { name, path });
+        }
+        roots
+    }
+
+    pub fn primary(&self) -> &WorkspaceRoot {
+        &self.roots[0]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &WorkspaceRoot> {
+        self.roots.iter()
+    }
+
+    pub fn is_multi(&self) -> bool {
+        self.roots.len() > 1
+    }
+
+    /// The root `path` refers to, and the path relative to that root.
+    ///
+    /// Absolute paths inside a root and `<root name>/...` paths select that
+    /// root; everything else is relative to the primary root.
+    pub fn resolve<'a>(&'a self, path: &'a str) -> (&'a WorkspaceRoot, &'a str) {
+        let as_path = Path::new(path);
+        if as_path.is_absolute() {
+            // Deepest root first, in case roots are nested
+            let mut matches: Vec<&WorkspaceRoot> =
+                self.roots.iter().filter(|r| as_path.starts_with(&r.path)).collect();
+            matches.sort_by_key(|r| std::cmp::Reverse(r.path.components().count()));
+            if let Some(root) = matches.first() {
+                let rel = as_path
+                    .strip_prefix(&root.path)
+                    .ok()
+                    .and_then(|p| p.to_str())
+                    .unwrap_or(path);
+                return (root, rel);
+            }
+            return (self.primary(), path);
+        }
+        let trimmed = path.strip_prefix("./").unwrap_or(path);
+        for root in &self.roots[1..] {
+            if trimmed == root.name {
+                return (root, "");
+            }
+            if let Some(rest) = trimmed.strip_prefix(&root.name).and_then(|r| r.strip_prefix('/')) {
+                return (root, rest);
+            }
+        }
+        (self.primary(), path)
+    }
+
+    /// Route a tool call to the root its `path` argument refers to: returns
+    /// the root to use as workdir and the arguments with `path` made relative
+    /// to it. `None` when the call stays in the primary root.
+    pub fn route(&self, args: &Value) -> Option<(PathBuf, Value)> {
+        if !self.is_multi() {
+            return None;
+        }
+        let path = args.get("path").and_then(|v| v.as_str())?;
+        let (root, rel) = self.resolve(path);
+        if root == self.primary() {
+            return None;
+        }
+        let mut args = args.clone();
+        args["path"] = Value::String(if rel.is_empty() { ".".to_string() } else { rel.to_string() });
+        Some((root.path.clone(), args))
+    }
+
+    /// Lines for the model describing the roots, with the languages of
+    /// those [`crate::tools::workspace_diff`] has snapshotted; empty for a
+    /// single root.
+    pub fn prompt_section(&self) -> String {
+        if !self.is_multi() {
+            return String::new();
+        }
+        let mut out = String::from(
+            "Workspace roots (prefix paths with the root name to address files outside the primary root):\n",
+        );
+        for (i, root) in self.roots.iter().enumerate() {
+            let primary = if i == 0 { " (primary)" } else { "" };
+            let languages = crate::tools::workspace_diff::summary(&root.path)
+                .and_then(|s| s.main_languages())
+                .map(|l| format!(" ({l})"))
+                .unwrap_or_default();
+            out.push_str(&format!("- {}{}: {}{languages}\n", root.name, primary, root.path.display()));
+        }
+        out
+    }
+}
+
+fn root_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("root")
+        .to_string()
+}
+
+fn parse_roots_file(content: &str) -> Vec<PathBuf> {
+    content
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or_default().trim())
+        .filter(|l| !l.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_and_route() {
+        let dir = tempdir().unwrap();
+        let base = dir.path().canonicalize().unwrap();
+        for name in ["api", "web", "other/web"] {
+            std::fs::create_dir_all(base.join(name)).unwrap();
+        }
+        let roots = WorkspaceRoots::from_paths(
+            &base.join("api"),
+            parse_roots_file("../web  # frontend\n\n../other/web\n../missing\n../web\n"),
+        );
+        let names: Vec<&str> = roots.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["api", "web", "web-2"]);
+
+        assert_eq!(roots.resolve("src/main.rs"), (roots.primary(), "src/main.rs"));
+        let (root, rel) = roots.resolve("web/src/App.tsx");
+        assert_eq!((root.name.as_str(), rel), ("web", "src/App.tsx"));
+        let abs = base.join("other/web/index.ts");
+        let (root, rel) = roots.resolve(abs.to_str().unwrap());
+        assert_eq!((root.name.as_str(), rel), ("web-2", "index.ts"));
+        // A directory that merely starts with a root's name is not that root
+        assert_eq!(roots.resolve("webpack.config.js").0, roots.primary());
+
+        let (workdir, args) = roots
+            .route(&serde_json::json!({ "path": "web", "pattern": "fn" }))
+            .unwrap();
+        assert_eq!(workdir, base.join("web"));
+        assert_eq!(args, serde_json::json!({ "path": ".", "pattern": "fn" }));
+        assert!(roots.route(&serde_json::json!({ "path": "src" })).is_none());
+        assert!(roots.prompt_section().contains("- api (primary): "));
+
+        assert!(WorkspaceRoots::single(&base).route(&serde_json::json!({ "path": "web/x" })).is_none());
+    }
+}
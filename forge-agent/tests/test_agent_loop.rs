@@ -0,0 +1,121 @@
+//! Agent loop tests against the mock provider (no network).
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use forge_agent::agent_loop::{run_staged_turn, run_turn, AgentEvent};
+use forge_agent::api::mock::MockProvider;
+use tempfile::tempdir;
+use tokio::sync::mpsc;
+
+fn fixture(name: &str) -> Arc<MockProvider> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    Arc::new(MockProvider::from_file(&path).unwrap())
+}
+
+/// Run one message, answering approvals with `approve`; returns the events
+/// as readable lines.
+async fn run(provider: Arc<MockProvider>, workspace: &Path, approve: bool) -> Vec<String> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let turn = tokio::spawn(run_turn(provider, "question".into(), workspace.to_path_buf(), "test".into(), tx));
+    let mut log = Vec::new();
+    while let Some(event) = rx.recv().await {
+        match event {
+            AgentEvent::Text(text) => log.push(format!("text: {text}")),
+            AgentEvent::Thinking(message) => log.push(format!("thinking: {message}")),
+            AgentEvent::ToolStart { .. } => {}
+            AgentEvent::ToolEnd { name, success } => log.push(format!("tool: {name} {success}")),
+            AgentEvent::Approval { summary, reply } => {
+                log.push(format!("approval: {summary}"));
+                reply.send(approve).unwrap();
+            }
+            AgentEvent::Done => log.push("done".into()),
+            AgentEvent::Error(error) => log.push(format!("error: {error}")),
+        }
+    }
+    turn.await.unwrap();
+    log
+}
+
+#[tokio::test]
+async fn test_read_only_tools_run_without_approval() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "hello\n").unwrap();
+    let provider = fixture("read_then_answer.jsonl");
+
+    let log = run(provider.clone(), dir.path(), false).await;
+    assert_eq!(
+        log,
+        vec!["thinking: Reading the notes", "tool: read_file true", "text: The notes say hello.", "done"]
+    );
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0]["question"], "question");
+    let result = &requests[1]["tool_results"][0];
+    assert_eq!(result["call_id"], "call-1");
+    assert!(result["output"].as_str().unwrap().contains("hello"));
+    assert_eq!(provider.remaining(), 0);
+}
+
+#[tokio::test]
+async fn test_rejected_write_is_not_executed() {
+    let dir = tempdir().unwrap();
+    let provider = fixture("write_needs_approval.jsonl");
+
+    let log = run(provider.clone(), dir.path(), false).await;
+    assert_eq!(log, vec!["approval: write_file out.txt", "text: Finished.", "done"]);
+    assert!(!dir.path().join("out.txt").exists());
+    let result = &provider.requests()[1]["tool_results"][0];
+    assert_eq!(result["success"], false);
+    assert!(result["output"].as_str().unwrap().starts_with("User rejected"));
+}
+
+#[tokio::test]
+async fn test_approved_write_is_executed() {
+    let dir = tempdir().unwrap();
+    let provider = fixture("write_needs_approval.jsonl");
+
+    let log = run(provider.clone(), dir.path(), true).await;
+    assert_eq!(log, vec!["approval: write_file out.txt", "tool: write_file true", "text: Finished.", "done"]);
+    assert_eq!(std::fs::read_to_string(dir.path().join("out.txt")).unwrap(), "written\n");
+}
+
+#[tokio::test]
+async fn test_repeated_tool_call_is_flagged_as_loop() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "hello\n").unwrap();
+    let provider = fixture("repeated_tool_call.jsonl");
+
+    let log = run(provider.clone(), dir.path(), false).await;
+    assert_eq!(log.iter().filter(|l| *l == "tool: read_file true").count(), 2);
+    assert!(log.contains(&"tool: read_file false".to_string()));
+    let third = &provider.requests()[3]["tool_results"][0];
+    assert!(third["output"].as_str().unwrap().starts_with("Loop detected"));
+}
+
+#[tokio::test]
+async fn test_running_out_of_script_reports_an_error() {
+    let dir = tempdir().unwrap();
+    let provider = Arc::new(MockProvider::new(Vec::new()));
+
+    let log = run(provider, dir.path(), false).await;
+    assert_eq!(log, vec!["error: mock provider: no more scripted responses"]);
+}
+
+#[tokio::test]
+async fn test_staged_write_runs_without_approval_and_is_returned_for_review() {
+    let dir = tempdir().unwrap();
+    let provider = fixture("write_needs_approval.jsonl");
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let turn = tokio::spawn(run_staged_turn(provider, "question".into(), dir.path().to_path_buf(), "test".into(), tx));
+    while let Some(event) = rx.recv().await {
+        assert!(!matches!(event, AgentEvent::Approval { .. }));
+    }
+    let changes = turn.await.unwrap().changes();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].path, "out.txt");
+    changes[0].revert(dir.path()).unwrap();
+    assert!(!dir.path().join("out.txt").exists());
+}
@@ -43,7 +43,7 @@ async fn test_mpatch_unified_diff() {
         "patch": patch
     });
     
-    let result = apply_patch(&args, dir.path()).await;
+    let result = apply_patch(serde_json::from_value(args).unwrap(), dir.path()).await;
     assert!(result.success, "Apply patch failed: {}", result.output);
     
     let new_content = std::fs::read_to_string(&file_path).unwrap();
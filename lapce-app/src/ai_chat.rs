@@ -22,18 +22,37 @@ use lapce_core::directory::Directory;
 use lapce_core::mode::Mode;
 use lapce_core::command::EditCommand;
 
-// ── AI Keys Config (persisted to ai-keys.toml) ──────────────────
+/// Largest file that can be attached to a chat message.
+const MAX_ATTACHMENT_BYTES: u64 = 10 * 1024 * 1024;
+
+// ── AI Keys Config (persisted to ai-keys.toml, keys in the keychain) ──
 
 const AI_KEYS_FILE: &str = "ai-keys.toml";
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Serialize, Deserialize, Default)]
 pub struct AiKeysConfig {
+    /// Provider -> API key. Only keys the keychain couldn't take are written
+    /// to the file.
     #[serde(default)]
     pub keys: HashMap<String, String>,
     #[serde(default)]
     pub defaults: AiDefaults,
 }
 
+impl std::fmt::Debug for AiKeysConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys: HashMap<&str, String> = self
+            .keys
+            .iter()
+            .map(|(provider, key)| (provider.as_str(), forge_agent::secrets::redact(key)))
+            .collect();
+        f.debug_struct("AiKeysConfig")
+            .field("keys", &keys)
+            .field("defaults", &self.defaults)
+            .finish()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AiDefaults {
     pub provider: String,
@@ -50,23 +69,38 @@ impl Default for AiDefaults {
 }
 
 impl AiKeysConfig {
-    /// Load from disk, or return default if file doesn't exist.
+    /// Load from disk, or return default if file doesn't exist. Plaintext
+    /// keys left in the file are moved to the keychain first; environment
+    /// variables and config overrides win over stored keys.
     pub fn load() -> Self {
         let Some(path) = Directory::config_directory().map(|d| d.join(AI_KEYS_FILE)) else {
             return Self::default();
         };
-        match std::fs::read_to_string(&path) {
+        if let Err(e) = forge_agent::secrets::migrate_keys_file(&path) {
+            tracing::warn!("API key migration failed: {e:#}");
+        }
+        let mut config: Self = match std::fs::read_to_string(&path) {
             Ok(content) => toml::from_str(&content).unwrap_or_default(),
             Err(_) => Self::default(),
+        };
+        for &provider in ALL_PROVIDERS {
+            if let Some(key) = forge_agent::secrets::api_key(provider) {
+                config.keys.insert(provider.to_string(), key);
+            }
         }
+        config
     }
 
-    /// Save to disk.
+    /// Save to disk, with the keys in the keychain where possible.
     pub fn save(&self) {
         let Some(path) = Directory::config_directory().map(|d| d.join(AI_KEYS_FILE)) else {
             return;
         };
-        if let Ok(content) = toml::to_string_pretty(self) {
+        let mut on_disk = self.clone();
+        on_disk.keys.retain(|provider, key| {
+            forge_agent::secrets::store(provider, key).is_err()
+        });
+        if let Ok(content) = toml::to_string_pretty(&on_disk) {
             let _ = std::fs::write(&path, content);
         }
     }
@@ -94,7 +128,8 @@ impl AiKeysConfig {
     }
 }
 
-/// Static list of models per provider.
+/// Static list of models per provider, used until the provider's own list
+/// has been fetched (see `AiChatData::refresh_models`).
 pub fn models_for_provider(provider: &str) -> Vec<&'static str> {
     match provider {
         "gemini" => vec![
@@ -160,6 +195,8 @@ pub enum ToolCallStatus {
     Rejected,
     /// User accepted this tool call.
     Accepted,
+    /// File edit refused: the file changed since the agent read it.
+    Conflict,
 }
 
 /// A single entry in the chat, with a unique id and version for reactive re-rendering.
@@ -182,6 +219,31 @@ pub enum ChatEntryKind {
     Plan(ChatPlan),
     /// Server-side tool call (displayed in thinking section).
     ServerToolCall(ChatServerToolCall),
+    /// A question the agent is waiting on, with suggested answers.
+    Followup(ChatFollowup),
+    /// What a completed task changed, as a PR title and body.
+    TaskSummary(ChatTaskSummary),
+}
+
+/// The summary sent after `attempt_completion`.
+#[derive(Clone, Debug)]
+pub struct ChatTaskSummary {
+    pub title: String,
+    /// Markdown PR body.
+    pub body: String,
+}
+
+/// A question from `ask_followup_question`.
+#[derive(Clone, Debug)]
+pub struct ChatFollowup {
+    pub tool_call_id: String,
+    pub question: String,
+    /// Suggested answers, shown as buttons.
+    pub options: Vec<String>,
+    /// The suggestion to highlight.
+    pub default: Option<String>,
+    /// What the user answered, once they have.
+    pub answer: Option<String>,
 }
 
 /// A thinking step from the agent (server-side activity).
@@ -230,6 +292,98 @@ pub struct ChatServerToolCall {
     pub elapsed_display: String,
 }
 
+/// Codebase index state as the proxy reports it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexDetails {
+    pub symbol_count: i64,
+    pub files_indexed: usize,
+    pub pending: usize,
+    pub size_bytes: u64,
+    /// Unix seconds.
+    pub last_refresh: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+impl IndexDetails {
+    /// Lines for the status badge's menu.
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("{} symbols · {} files", self.symbol_count, self.files_indexed),
+            format!("Uploaded {}", human_bytes(self.size_bytes)),
+        ];
+        if self.pending > 0 {
+            lines.push(format!("{} files pending", self.pending));
+        }
+        lines.push(match self.last_refresh {
+            Some(at) => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(at);
+                format!("Last refreshed {}", human_age(now.saturating_sub(at)))
+            }
+            None => "Not refreshed this session".to_string(),
+        });
+        if let Some(error) = &self.last_error {
+            lines.push(format!("Last error: {}", error.chars().take(80).collect::<String>()));
+        }
+        lines
+    }
+}
+
+fn human_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 20 => format!("{:.1} MB", b as f64 / (1 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.1} KB", b as f64 / (1 << 10) as f64),
+        b => format!("{b} B"),
+    }
+}
+
+fn human_age(secs: u64) -> String {
+    match secs {
+        s if s < 60 => "just now".to_string(),
+        s if s < 3600 => format!("{} min ago", s / 60),
+        s if s < 86400 => format!("{} h ago", s / 3600),
+        s => format!("{} days ago", s / 86400),
+    }
+}
+
+/// One-line status of a running agent request, e.g.
+/// "Step 2 · grep TODO · 14s · 1.2k tokens".
+pub fn progress_line(progress: &lapce_rpc::core::AgentRunProgress) -> String {
+    let secs = progress.elapsed_ms / 1000;
+    let elapsed = if secs < 60 {
+        format!("{secs}s")
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    };
+    let tokens = match progress.tokens {
+        0 => String::new(),
+        t if t >= 1000 => format!(" · {:.1}k tokens", t as f64 / 1000.0),
+        t => format!(" · {t} tokens"),
+    };
+    format!("Step {} · {} · {elapsed}{tokens}", progress.step, progress.activity)
+}
+
+/// A tool call waiting in the approval queue.
+#[derive(Clone, Debug)]
+pub struct QueuedApproval {
+    pub approval: lapce_rpc::core::AgentPendingApproval,
+    /// What happens when nobody answers, e.g. "Rejected at 14:05:12".
+    pub timeout_note: Option<String>,
+}
+
+impl QueuedApproval {
+    pub fn new(approval: lapce_rpc::core::AgentPendingApproval) -> Self {
+        let timeout_note = approval.timeout_secs.map(|secs| {
+            let at = chrono::Local::now() + chrono::Duration::seconds(secs as i64);
+            let outcome = if approval.approve_on_timeout { "Approved" } else { "Rejected" };
+            format!("{outcome} at {}", at.format("%H:%M:%S"))
+        });
+        Self { approval, timeout_note }
+    }
+}
+
 impl ChatEntry {
     /// Stable key for `dyn_stack` that changes when content is mutated.
     pub fn key(&self) -> (u64, u64) {
@@ -298,6 +452,29 @@ pub fn new_server_tool_call(id: String, name: String, arguments: String) -> Chat
     }
 }
 
+pub fn new_followup(followup: ChatFollowup) -> ChatEntry {
+    ChatEntry {
+        id: next_entry_id(),
+        version: 0,
+        kind: ChatEntryKind::Followup(followup),
+    }
+}
+
+pub fn new_task_summary(summary: ChatTaskSummary) -> ChatEntry {
+    ChatEntry {
+        id: next_entry_id(),
+        version: 0,
+        kind: ChatEntryKind::TaskSummary(summary),
+    }
+}
+
+/// Number of user messages among `entries` (the turn index of the next one).
+fn user_turns<'a>(entries: impl Iterator<Item = &'a ChatEntry>) -> u32 {
+    entries
+        .filter(|e| matches!(&e.kind, ChatEntryKind::Message { role: ChatRole::User, .. }))
+        .count() as u32
+}
+
 // ── AiChatData ──────────────────────────────────────────────────
 
 /// Reactive data for the AI chat panel.
@@ -311,6 +488,18 @@ pub struct AiChatData {
     pub entries: RwSignal<im::Vector<ChatEntry>>,
     /// Whether the agent is currently processing
     pub is_loading: RwSignal<bool>,
+    /// Status of the running request (step, activity, time, tokens), shown
+    /// in the chat panel and the status bar; `None` when idle.
+    pub progress: RwSignal<Option<lapce_rpc::core::AgentRunProgress>>,
+    /// Tool calls waiting for approval, oldest first.
+    pub approval_queue: RwSignal<im::Vector<QueuedApproval>>,
+    /// Token usage and cost for the usage panel, from the proxy's ledger.
+    pub usage: RwSignal<Option<lapce_rpc::proxy::UsageSummary>>,
+    /// Decisions, assumptions and open questions the agent noted in this
+    /// conversation, oldest first.
+    pub working_notes: RwSignal<im::Vector<lapce_rpc::core::AgentNote>>,
+    /// Full-window view of the diagrams and code blocks the agent shows.
+    pub canvas: crate::canvas::CanvasData,
     /// Selected provider
     pub provider: RwSignal<String>,
     /// Selected model
@@ -337,6 +526,12 @@ pub struct AiChatData {
     /// Persistent conversation_id for multi-turn chat memory.
     /// Preserved across messages in the same session; reset on clear.
     pub conversation_id: RwSignal<String>,
+    /// Set after "retry from here" restored a checkpoint: the next message
+    /// asks the server to drop the conversation from that turn on.
+    pub rewound: RwSignal<bool>,
+    /// Conversation and sequence number of the last agent event shown, to
+    /// resume from after the proxy reconnects.
+    pub last_event: RwSignal<Option<(String, u64)>>,
 
     // ── Index status ────────────────────────────────────────────
     /// Human-readable codebase index status shown in the header.
@@ -344,6 +539,16 @@ pub struct AiChatData {
     pub index_status: RwSignal<String>,
     /// Index progress: 0.0..1.0 while indexing, -1.0 when idle.
     pub index_progress: RwSignal<f64>,
+    /// The proxy's record of the index, shown in the status badge's menu.
+    pub index_details: RwSignal<Option<IndexDetails>>,
+    /// Files changed outside the agent since the previous message, shown as
+    /// a badge in the header.
+    pub external_changes: RwSignal<Vec<String>>,
+    /// The user's trust decision for this workspace (`None`: not asked
+    /// yet). Anything but `Some(true)` runs the agent restricted.
+    pub workspace_trust: RwSignal<Option<bool>>,
+    /// Models listed by each configured provider's API.
+    pub discovered_models: RwSignal<HashMap<String, Vec<String>>>,
 
     // ── Thinking section state ─────────────────────────────────
     /// Whether the thinking section is collapsed.
@@ -355,6 +560,8 @@ pub struct AiChatData {
     // ── Multimodal input state ──────────────────────────────────
     /// Images pasted/attached by the user (base64-encoded).
     pub attached_images: RwSignal<Vec<lapce_rpc::proxy::AttachedImageData>>,
+    /// Other files dropped or pasted into the chat (base64-encoded).
+    pub attached_files: RwSignal<Vec<lapce_rpc::proxy::AttachedFileData>>,
     /// Whether the mic is currently recording.
     pub is_recording: RwSignal<bool>,
     /// Audio recorder instance.
@@ -368,11 +575,16 @@ impl AiChatData {
         let provider = config.defaults.provider.clone();
         let model = config.defaults.model.clone();
 
-        Self {
+        let data = Self {
             scope: cx,
             editor,
             entries: cx.create_rw_signal(im::Vector::new()),
             is_loading: cx.create_rw_signal(false),
+            progress: cx.create_rw_signal(None),
+            approval_queue: cx.create_rw_signal(im::Vector::new()),
+            usage: cx.create_rw_signal(None),
+            working_notes: cx.create_rw_signal(im::Vector::new()),
+            canvas: crate::canvas::CanvasData::new(cx, common.focus),
             provider: cx.create_rw_signal(provider),
             model: cx.create_rw_signal(model),
             keys_config: cx.create_rw_signal(config),
@@ -384,13 +596,77 @@ impl AiChatData {
             scroll_trigger: cx.create_rw_signal(0),
             index_status: cx.create_rw_signal("Checking…".to_string()),
             index_progress: cx.create_rw_signal(-1.0),
+            index_details: cx.create_rw_signal(None),
+            external_changes: cx.create_rw_signal(Vec::new()),
+            // Assume trusted until the proxy answers, so the prompt doesn't flash
+            workspace_trust: cx.create_rw_signal(Some(true)),
+            discovered_models: cx.create_rw_signal(HashMap::new()),
             conversation_id: cx.create_rw_signal(uuid::Uuid::new_v4().to_string()),
+            rewound: cx.create_rw_signal(false),
+            last_event: cx.create_rw_signal(None),
             thinking_collapsed: cx.create_rw_signal(false),
             thinking_steps: cx.create_rw_signal(im::Vector::new()),
             attached_images: cx.create_rw_signal(Vec::new()),
+            attached_files: cx.create_rw_signal(Vec::new()),
             is_recording: cx.create_rw_signal(false),
             recorder: crate::audio_recorder::AudioRecorder::new(),
+        };
+        data.refresh_models();
+        data.workspace_trust(None);
+        data
+    }
+
+    /// Ask the proxy for the workspace's trust decision, recording `set`
+    /// first when given.
+    pub fn workspace_trust(&self, set: Option<bool>) {
+        let workspace_trust = self.workspace_trust;
+        let send = create_ext_action(self.scope, move |result: Result<lapce_rpc::proxy::ProxyResponse, lapce_rpc::RpcError>| {
+            if let Ok(lapce_rpc::proxy::ProxyResponse::AgentWorkspaceTrustResponse { trusted }) = result {
+                workspace_trust.set(trusted);
+            }
+        });
+        self.common.proxy.request_async(
+            lapce_rpc::proxy::ProxyRequest::AgentWorkspaceTrust { set },
+            send,
+        );
+    }
+
+    /// Fetch the model lists of the configured providers in the background
+    /// (cached on disk for a day by forge-agent).
+    pub fn refresh_models(&self) {
+        let providers: Vec<(String, String)> = self.keys_config.with_untracked(|c| {
+            ALL_PROVIDERS
+                .iter()
+                .filter_map(|&p| Some((p.to_string(), c.key_for(p)?.to_string())))
+                .collect()
+        });
+        if providers.is_empty() {
+            return;
         }
+        let discovered_models = self.discovered_models;
+        let send = create_ext_action(self.scope, move |lists: Vec<(String, Vec<String>)>| {
+            discovered_models.update(|models| models.extend(lists));
+        });
+        std::thread::spawn(move || {
+            let lists = providers
+                .into_iter()
+                .map(|(provider, key)| {
+                    let models = forge_agent::models::models_blocking(&provider, Some(&key));
+                    (provider, models)
+                })
+                .filter(|(_, models)| !models.is_empty())
+                .collect();
+            send(lists);
+        });
+    }
+
+    /// Models to offer for `provider`: the discovered list, else the static one.
+    pub fn models_for(&self, provider: &str) -> Vec<String> {
+        self.discovered_models
+            .with(|m| m.get(provider).cloned())
+            .unwrap_or_else(|| {
+                models_for_provider(provider).into_iter().map(String::from).collect()
+            })
     }
 
     /// Whether user can chat — either signed into forge-search or has an API key.
@@ -435,6 +711,7 @@ impl AiChatData {
         if let Some(first_model) = models_for_provider(provider).first() {
             self.model.set(first_model.to_string());
         }
+        self.refresh_models();
     }
 
     /// Select a model (and its provider) and persist.
@@ -450,12 +727,12 @@ impl AiChatData {
     }
 
     /// Get available models grouped by provider (only configured providers).
-    pub fn available_models(&self) -> Vec<(String, Vec<&'static str>)> {
+    pub fn available_models(&self) -> Vec<(String, Vec<String>)> {
         let config = self.keys_config.get_untracked();
         let mut result = Vec::new();
         for &prov in ALL_PROVIDERS {
             if config.key_for(prov).is_some() {
-                result.push((prov.to_string(), models_for_provider(prov)));
+                result.push((prov.to_string(), self.models_for(prov)));
             }
         }
         result
@@ -469,6 +746,13 @@ impl AiChatData {
 
         tracing::info!("[AI_CHAT] send_message called, text_len={}", text.len());
 
+        // While the agent waits on a question, what's typed is the answer
+        if let Some(tool_call_id) = self.pending_followup() {
+            self.editor.doc().reload(lapce_xi_rope::Rope::from(""), true);
+            self.answer_followup(&tool_call_id, text.trim().to_string());
+            return;
+        }
+
         // Determine if forge-search auth is available (no API key needed).
         let forge_search_auth = self.is_forge_search_authenticated();
         tracing::info!("[AI_CHAT] forge_search_auth={}", forge_search_auth);
@@ -497,12 +781,20 @@ impl AiChatData {
             (provider, model, api_key)
         };
 
-        // Skip if empty text and no attached images
-        if text.trim().is_empty() && self.attached_images.with_untracked(|imgs| imgs.is_empty()) {
+        // Skip if empty text and nothing attached
+        if text.trim().is_empty()
+            && self.attached_images.with_untracked(|imgs| imgs.is_empty())
+            && self.attached_files.with_untracked(|files| files.is_empty())
+        {
             self.is_loading.set(false);
             return;
         }
 
+        // Turn index of this message, for the proxy's edit checkpoints
+        let turn = self.entries.with_untracked(|entries| user_turns(entries.iter()));
+        let rewind = self.rewound.get_untracked();
+        self.rewound.set(false);
+
         // Add user message
         self.entries.update(|entries| {
             entries.push_back(new_message(ChatRole::User, text.clone()));
@@ -568,6 +860,8 @@ impl AiChatData {
         let conversation_id = self.conversation_id.get_untracked();
         let images = self.attached_images.get_untracked();
         self.attached_images.set(Vec::new()); // Clear after sending
+        let attachments = self.attached_files.get_untracked();
+        self.attached_files.set(Vec::new());
         
         tracing::info!(
             "[AI_CHAT] Sending AgentPrompt via proxy RPC: conv_id={}, provider={}, model={}, images={}",
@@ -582,6 +876,9 @@ impl AiChatData {
                 api_key,
                 conversation_id,
                 attached_images: images,
+                attachments,
+                turn,
+                rewind,
             },
             send,
         );
@@ -603,13 +900,91 @@ impl AiChatData {
         tracing::info!("Added image attachment: {} (total now: {})", filename, count + 1);
     }
 
-    /// Check clipboard for images and auto-attach them.
+    /// Attach a file from disk: images go with the pasted screenshots,
+    /// anything else is sent as a file attachment.
+    pub fn add_file(&self, path: &std::path::Path) {
+        use base64::{Engine as _, engine::general_purpose::STANDARD};
+
+        let filename = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "attachment".to_string());
+        match std::fs::metadata(path) {
+            Ok(meta) if meta.len() > MAX_ATTACHMENT_BYTES => {
+                self.entries.update(|entries| {
+                    entries.push_back(new_message(
+                        ChatRole::System,
+                        format!(
+                            "{filename} is too large to attach ({} MB max)",
+                            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+                        ),
+                    ));
+                });
+                return;
+            }
+            Ok(meta) if meta.is_file() => {}
+            _ => return,
+        }
+        let Ok(data) = std::fs::read(path) else {
+            return;
+        };
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let image_mime = match ext.as_str() {
+            "png" => Some("image/png"),
+            "jpg" | "jpeg" => Some("image/jpeg"),
+            _ => None,
+        };
+        if let Some(mime) = image_mime {
+            self.add_image(STANDARD.encode(&data), mime.to_string());
+            return;
+        }
+        let mime_type = match ext.as_str() {
+            "pdf" => "application/pdf",
+            "csv" => "text/csv",
+            "tsv" => "text/tab-separated-values",
+            _ if data.contains(&0) => "application/octet-stream",
+            _ => "text/plain",
+        };
+        self.attached_files.update(|files| {
+            files.push(lapce_rpc::proxy::AttachedFileData {
+                filename,
+                data: STANDARD.encode(&data),
+                mime_type: mime_type.to_string(),
+            });
+        });
+    }
+
+    /// Remove an attached file by index.
+    pub fn remove_file(&self, index: usize) {
+        self.attached_files.update(|files| {
+            if index < files.len() {
+                files.remove(index);
+            }
+        });
+    }
+
+    /// Check clipboard for images (or copied files) and auto-attach them.
     /// Called when user pastes (Cmd+V) in the chat input.
     pub fn check_clipboard_for_image(&self) {
         use base64::{Engine as _, engine::general_purpose::STANDARD};
         
-        // Try to get image from clipboard
         if let Ok(mut clipboard) = arboard::Clipboard::new() {
+            // Files copied in the file manager
+            if let Ok(paths) = clipboard.get().file_list() {
+                if !paths.is_empty() {
+                    for path in &paths {
+                        self.add_file(path);
+                    }
+                    // Clear the editor so the file names don't paste
+                    self.editor.doc().reload(lapce_xi_rope::Rope::from(""), true);
+                    return;
+                }
+            }
+            // Try to get image from clipboard
             if let Ok(img) = clipboard.get_image() {
                 // Convert ImageData to PNG bytes
                 let width = img.width;
@@ -650,11 +1025,13 @@ impl AiChatData {
         });
     }
 
-    /// Send audio data to proxy for Whisper transcription.
+    /// Send audio data to the proxy for transcription (provider STT or a
+    /// local whisper.cpp, per the `stt` setting).
     /// On success, inserts the transcript text into the editor.
     pub fn transcribe_audio(&self, audio_data: Vec<u8>) {
         let editor = self.editor.clone();
         let is_recording = self.is_recording;
+        let entries = self.entries;
         
         let send = create_ext_action(self.scope, move |result: Result<lapce_rpc::proxy::ProxyResponse, lapce_rpc::RpcError>| {
             is_recording.set(false);
@@ -673,6 +1050,12 @@ impl AiChatData {
                 }
                 Ok(lapce_rpc::proxy::ProxyResponse::AgentError { error }) => {
                     tracing::error!("Transcription failed: {}", error);
+                    entries.update(|entries| {
+                        entries.push_back(new_message(
+                            ChatRole::System,
+                            format!("Transcription failed: {error}"),
+                        ));
+                    });
                 }
                 _ => {}
             }
@@ -684,7 +1067,7 @@ impl AiChatData {
         );
     }
 
-    /// Toggle audio recording. On start: opens mic. On stop: transcribes via Whisper.
+    /// Toggle audio recording. On start: opens mic. On stop: transcribes it.
     pub fn toggle_recording(&self) {
         if self.recorder.is_recording() {
             // Stop and transcribe
@@ -732,7 +1115,7 @@ impl AiChatData {
 
 
     pub fn notify_file_changed(&self, path: std::path::PathBuf) {
-        if !self.is_forge_search_authenticated() {
+        if !self.is_forge_search_authenticated() || forge_agent::egress::local_only() {
             return;
         }
 
@@ -744,16 +1127,15 @@ impl AiChatData {
             .common
             .workspace
             .path
-            .as_ref()
-            .and_then(|p| p.file_name())
-            .map(|n| n.to_string_lossy().to_string())
+            .as_deref()
+            .map(forge_agent::forge_search::workspace_id)
             .unwrap_or_else(|| "default".to_string());
             
         let conversation_id = self.conversation_id.get_untracked();
         let path_str = path.to_string_lossy().to_string();
 
         std::thread::spawn(move || {
-            let client = match reqwest::blocking::Client::builder()
+            let client = match lapce_proxy::blocking_http_client_builder()
                 .timeout(std::time::Duration::from_secs(5))
                 .build()
             {
@@ -778,6 +1160,61 @@ impl AiChatData {
         });
     }
 
+    /// "Retry from here": put back the files the agent changed since the
+    /// user message `entry_id`, drop that message and everything after it,
+    /// and reload its text into the input so it can be amended and resent.
+    pub fn retry_from(&self, entry_id: u64) {
+        if self.is_loading.get_untracked() {
+            return;
+        }
+        let Some((index, turn, text)) = self.entries.with_untracked(|entries| {
+            let index = entries.iter().position(|e| e.id == entry_id)?;
+            match &entries[index].kind {
+                ChatEntryKind::Message { role: ChatRole::User, content } => {
+                    Some((index, user_turns(entries.iter().take(index)), content.clone()))
+                }
+                _ => None,
+            }
+        }) else {
+            return;
+        };
+
+        let entries = self.entries;
+        let rewound = self.rewound;
+        let editor = self.editor.clone();
+        let send = create_ext_action(self.scope, move |result: Result<lapce_rpc::proxy::ProxyResponse, lapce_rpc::RpcError>| {
+            match result {
+                Ok(lapce_rpc::proxy::ProxyResponse::AgentRestoreCheckpointResponse { restored }) => {
+                    entries.update(|entries| {
+                        entries.truncate(index);
+                        if !restored.is_empty() {
+                            entries.push_back(new_message(
+                                ChatRole::System,
+                                format!("Restored {} file(s): {}", restored.len(), restored.join(", ")),
+                            ));
+                        }
+                    });
+                    editor.doc().reload(lapce_xi_rope::Rope::from(&text), true);
+                    rewound.set(true);
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    entries.update(|entries| {
+                        entries.push_back(new_message(ChatRole::System, format!("Retry failed: {}", err.message)));
+                    });
+                }
+            }
+        });
+
+        self.common.proxy.request_async(
+            lapce_rpc::proxy::ProxyRequest::AgentRestoreCheckpoint {
+                conversation_id: self.conversation_id.get_untracked(),
+                turn,
+            },
+            send,
+        );
+    }
+
     pub fn clear_chat(&self) {
         self.entries.update(|entries| entries.clear());
         self.streaming_text.set(String::new());
@@ -785,9 +1222,164 @@ impl AiChatData {
         self.is_loading.set(false);
         // New conversation = new conversation_id
         self.conversation_id.set(uuid::Uuid::new_v4().to_string());
+        self.working_notes.set(im::Vector::new());
+        self.rewound.set(false);
     }
 
     /// Trigger the scroll-to-bottom signal.
+    /// The agent's unanswered question, if it is waiting on one.
+    pub fn pending_followup(&self) -> Option<String> {
+        self.entries.with_untracked(|entries| {
+            entries.iter().rev().find_map(|e| match &e.kind {
+                ChatEntryKind::Followup(f) if f.answer.is_none() => Some(f.tool_call_id.clone()),
+                _ => None,
+            })
+        })
+    }
+
+    /// Answer the agent's question `tool_call_id` (a clicked suggestion or
+    /// typed text) and record it on the question's entry.
+    pub fn answer_followup(&self, tool_call_id: &str, answer: String) {
+        let mut answered = false;
+        self.entries.update(|entries| {
+            for entry in entries.iter_mut() {
+                if let ChatEntryKind::Followup(f) = &mut entry.kind {
+                    if f.tool_call_id == tool_call_id && f.answer.is_none() {
+                        f.answer = Some(answer.clone());
+                        entry.version += 1;
+                        answered = true;
+                    }
+                }
+            }
+        });
+        if !answered {
+            return;
+        }
+        self.common.proxy.request_async(
+            lapce_rpc::proxy::ProxyRequest::AgentAnswerFollowup {
+                tool_call_id: tool_call_id.to_string(),
+                answer,
+            },
+            |_| {},
+        );
+    }
+
+    /// Approve or reject a queued tool call.
+    pub fn answer_approval(&self, tool_call_id: &str, approve: bool) {
+        let tool_call_id = tool_call_id.to_string();
+        let request = if approve {
+            lapce_rpc::proxy::ProxyRequest::AgentApproveToolCall { tool_call_id }
+        } else {
+            lapce_rpc::proxy::ProxyRequest::AgentRejectToolCall { tool_call_id }
+        };
+        self.common.proxy.request_async(request, |_| {});
+    }
+
+    /// Approve or reject the oldest queued tool call.
+    pub fn answer_next_approval(&self, approve: bool) {
+        let next = self
+            .approval_queue
+            .with_untracked(|queue| queue.front().map(|q| q.approval.tool_call_id.clone()));
+        if let Some(tool_call_id) = next {
+            self.answer_approval(&tool_call_id, approve);
+        }
+    }
+
+    /// Approve everything in the queue (not future calls, unlike
+    /// "Approve All Future").
+    pub fn approve_all_queued(&self) {
+        let ids: Vec<String> = self.approval_queue.with_untracked(|queue| {
+            queue.iter().map(|q| q.approval.tool_call_id.clone()).collect()
+        });
+        for tool_call_id in ids {
+            self.answer_approval(&tool_call_id, true);
+        }
+    }
+
+    /// Reload the usage summary, with this conversation as the session.
+    pub fn refresh_usage(&self) {
+        let usage = self.usage;
+        let send = create_ext_action(self.scope, move |result: Result<lapce_rpc::proxy::ProxyResponse, lapce_rpc::RpcError>| {
+            match result {
+                Ok(lapce_rpc::proxy::ProxyResponse::AgentUsageSummaryResponse { summary }) => {
+                    usage.set(Some(summary));
+                }
+                Ok(_) => {}
+                Err(err) => tracing::warn!("Loading token usage failed: {}", err.message),
+            }
+        });
+        self.common.proxy.request_async(
+            lapce_rpc::proxy::ProxyRequest::AgentUsageSummary {
+                conversation_id: Some(self.conversation_id.get_untracked()),
+            },
+            send,
+        );
+    }
+
+    /// Have the proxy write the onboarding report and open it.
+    pub fn explain_repository(&self) {
+        let internal_command = self.common.internal_command;
+        let send = create_ext_action(self.scope, move |result: Result<lapce_rpc::proxy::ProxyResponse, lapce_rpc::RpcError>| {
+            match result {
+                Ok(lapce_rpc::proxy::ProxyResponse::AgentOnboardingReportResponse { path }) => {
+                    internal_command.send(crate::command::InternalCommand::OpenFile { path });
+                }
+                Ok(_) => {}
+                Err(err) => internal_command.send(crate::command::InternalCommand::ShowAlert {
+                    title: "Explaining the Repository Failed".to_string(),
+                    msg: err.message,
+                    buttons: Vec::new(),
+                }),
+            }
+        });
+        self.common
+            .proxy
+            .request_async(lapce_rpc::proxy::ProxyRequest::AgentOnboardingReport {}, send);
+    }
+
+    /// Put back whatever the agent deleted last, opening it if it's a file.
+    pub fn restore_deleted_file(&self) {
+        let internal_command = self.common.internal_command;
+        let send = create_ext_action(self.scope, move |result: Result<lapce_rpc::proxy::ProxyResponse, lapce_rpc::RpcError>| {
+            match result {
+                Ok(lapce_rpc::proxy::ProxyResponse::AgentRestoreDeletedFileResponse { path, is_dir }) => {
+                    if !is_dir {
+                        internal_command.send(crate::command::InternalCommand::OpenFile { path });
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => internal_command.send(crate::command::InternalCommand::ShowAlert {
+                    title: "Restoring the Deleted File Failed".to_string(),
+                    msg: err.message,
+                    buttons: Vec::new(),
+                }),
+            }
+        });
+        self.common
+            .proxy
+            .request_async(lapce_rpc::proxy::ProxyRequest::AgentRestoreDeletedFile {}, send);
+    }
+
+    /// Reload this conversation's working notes from its session.
+    pub fn refresh_working_notes(&self) {
+        let working_notes = self.working_notes;
+        let send = create_ext_action(self.scope, move |result: Result<lapce_rpc::proxy::ProxyResponse, lapce_rpc::RpcError>| {
+            match result {
+                Ok(lapce_rpc::proxy::ProxyResponse::AgentWorkingNotesResponse { notes }) => {
+                    working_notes.set(notes.into_iter().collect());
+                }
+                Ok(_) => {}
+                Err(err) => tracing::warn!("Loading working notes failed: {}", err.message),
+            }
+        });
+        self.common.proxy.request_async(
+            lapce_rpc::proxy::ProxyRequest::AgentWorkingNotes {
+                conversation_id: self.conversation_id.get_untracked(),
+            },
+            send,
+        );
+    }
+
     pub fn request_scroll_to_bottom(&self) {
         self.scroll_trigger.update(|v| *v += 1);
     }
@@ -795,10 +1387,15 @@ impl AiChatData {
     /// Fire a background request to forge-search `/health` to update the
     /// index status badge in the header.  Safe to call multiple times.
     pub fn refresh_index_status(&self) {
+        if forge_agent::egress::local_only() {
+            self.index_status.set("Local-only mode".to_string());
+            return;
+        }
         if !self.is_forge_search_authenticated() {
             self.index_status.set("Not connected".to_string());
             return;
         }
+        self.fetch_index_details();
 
         // Read the auth token from disk
         let token = Self::read_forge_token();
@@ -810,9 +1407,8 @@ impl AiChatData {
             .common
             .workspace
             .path
-            .as_ref()
-            .and_then(|p| p.file_name())
-            .map(|n| n.to_string_lossy().to_string())
+            .as_deref()
+            .map(forge_agent::forge_search::workspace_id)
             .unwrap_or_else(|| "default".to_string());
 
         let index_status = self.index_status;
@@ -822,7 +1418,7 @@ impl AiChatData {
         });
 
         std::thread::spawn(move || {
-            let client = match reqwest::blocking::Client::builder()
+            let client = match lapce_proxy::blocking_http_client_builder()
                 .timeout(std::time::Duration::from_secs(8))
                 .build()
             {
@@ -901,6 +1497,69 @@ impl AiChatData {
         );
     }
 
+    /// Ask the proxy for the index's details (files, pending, size, last
+    /// refresh).
+    pub fn fetch_index_details(&self) {
+        let request = lapce_rpc::proxy::ProxyRequest::IndexStatus {};
+        self.index_request(request, None);
+    }
+
+    /// Re-index one file or directory of the workspace.
+    pub fn reindex_path(&self, path: std::path::PathBuf) {
+        self.index_status.set("Re-indexing…".to_string());
+        self.index_request(lapce_rpc::proxy::ProxyRequest::IndexPath { path }, Some("Re-index failed"));
+    }
+
+    /// Delete the workspace's index on the server.
+    pub fn clear_index(&self) {
+        self.index_request(lapce_rpc::proxy::ProxyRequest::IndexClear { workspace_id: None }, Some("Clearing the index failed"));
+    }
+
+    /// Send an index request answered by `IndexStatusResponse` and update
+    /// the badge from the answer; errors replace the status when
+    /// `error_status` is given.
+    fn index_request(&self, request: lapce_rpc::proxy::ProxyRequest, error_status: Option<&'static str>) {
+        let index_status = self.index_status;
+        let index_details = self.index_details;
+        let send = create_ext_action(self.scope, move |result: Result<lapce_rpc::proxy::ProxyResponse, lapce_rpc::RpcError>| {
+            match result {
+                Ok(lapce_rpc::proxy::ProxyResponse::IndexStatusResponse {
+                    is_indexed,
+                    symbol_count,
+                    files_indexed,
+                    pending,
+                    size_bytes,
+                    last_refresh,
+                    last_error,
+                }) => {
+                    if error_status.is_some() {
+                        index_status.set(if is_indexed {
+                            format!("{} symbols indexed", symbol_count)
+                        } else {
+                            "Not indexed".to_string()
+                        });
+                    }
+                    index_details.set(Some(IndexDetails {
+                        symbol_count,
+                        files_indexed,
+                        pending,
+                        size_bytes,
+                        last_refresh,
+                        last_error,
+                    }));
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    if let Some(status) = error_status {
+                        tracing::warn!("{status}: {}", err.message);
+                        index_status.set(status.to_string());
+                    }
+                }
+            }
+        });
+        self.common.proxy.request_async(request, send);
+    }
+
     /// Read the forge-search JWT from disk (checks both Lapce and agent config dirs).
     fn read_forge_token() -> String {
         // Lapce config dir first
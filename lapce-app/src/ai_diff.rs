@@ -7,7 +7,7 @@
 use std::{collections::HashMap, path::PathBuf, rc::Rc};
 
 use floem::reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith};
-use lapce_rpc::core::AiDiffHunk;
+use lapce_rpc::core::{AgentFileEdit, AiDiffHunk};
 
 // ── Hunk status ─────────────────────────────────────────────────
 
@@ -168,6 +168,16 @@ impl PendingDiff {
         out
     }
 
+    /// Lines added and removed overall, for "+3 −1" summaries.
+    pub fn line_stats(&self) -> (usize, usize) {
+        let old_lines: Vec<&str> = self.old_content.lines().collect();
+        let new_lines: Vec<&str> = self.new_content.lines().collect();
+        let ops = lcs_diff(&old_lines, &new_lines);
+        let added = ops.iter().filter(|op| **op == DiffOp::Insert).count();
+        let removed = ops.iter().filter(|op| **op == DiffOp::Delete).count();
+        (added, removed)
+    }
+
     /// Returns lines that are additions (green) for rendering.
     /// Each entry is (line_number_in_new_content, line_text).
     pub fn added_lines(&self) -> Vec<(usize, String)> {
@@ -210,6 +220,9 @@ impl PendingDiff {
 pub struct AiDiffStore {
     /// All pending diffs, keyed by diff_id.
     pub diffs: RwSignal<HashMap<String, PendingDiff>>,
+    /// Diff ids of the last agent turn's per-file changes, in the order the
+    /// files were first edited (the "Changes from AI" panel).
+    pub turn_files: RwSignal<Vec<String>>,
     /// Whether there are any pending diffs (for UI indicators).
     pub has_pending: RwSignal<bool>,
     /// Counter for UI reactivity — incremented on any change.
@@ -220,6 +233,7 @@ impl AiDiffStore {
     pub fn new(cx: Scope) -> Self {
         Self {
             diffs: cx.create_rw_signal(HashMap::new()),
+            turn_files: cx.create_rw_signal(Vec::new()),
             has_pending: cx.create_rw_signal(false),
             version: cx.create_rw_signal(0),
         }
@@ -234,6 +248,30 @@ impl AiDiffStore {
         self.version.update(|v| *v += 1);
     }
 
+    /// Replace the turn panel with the files changed in the latest turn.
+    pub fn set_turn_changes(&self, files: Vec<AgentFileEdit>) {
+        let ids = files.iter().map(|f| f.diff_id.clone()).collect();
+        for file in files {
+            let hunks = compute_hunks(&file.old_content, &file.new_content);
+            self.add_diff(PendingDiff::new(
+                file.diff_id,
+                String::new(),
+                file.path,
+                file.old_content,
+                file.new_content,
+                hunks,
+            ));
+        }
+        self.turn_files.set(ids);
+    }
+
+    /// The latest turn's changes that are still pending, in edit order.
+    pub fn turn_changes(&self) -> Vec<PendingDiff> {
+        let ids = self.turn_files.get();
+        self.diffs
+            .with(|d| ids.iter().filter_map(|id| d.get(id).cloned()).collect())
+    }
+
     /// Get pending diffs for a specific file path.
     pub fn diffs_for_file(&self, file_path: &str) -> Vec<PendingDiff> {
         self.diffs.with_untracked(|d| {
@@ -287,15 +325,17 @@ impl AiDiffStore {
         self.version.update(|v| *v += 1);
     }
 
-    /// Accept all pending diffs.
+    /// Accept all pending diffs (they're already on disk, so this just
+    /// clears them).
     pub fn accept_all(&self) -> Vec<PendingDiff> {
         let mut resolved = Vec::new();
         self.diffs.update(|d| {
-            for diff in d.values_mut() {
+            for (_, mut diff) in d.drain() {
                 diff.accept_all();
-                resolved.push(diff.clone());
+                resolved.push(diff);
             }
         });
+        self.turn_files.set(Vec::new());
         self.update_has_pending();
         self.version.update(|v| *v += 1);
         resolved
@@ -304,6 +344,7 @@ impl AiDiffStore {
     /// Reject all pending diffs.
     pub fn reject_all(&self) {
         self.diffs.update(|d| d.clear());
+        self.turn_files.set(Vec::new());
         self.has_pending.set(false);
         self.version.update(|v| *v += 1);
     }
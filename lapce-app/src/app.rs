@@ -2581,6 +2581,7 @@ fn palette_item(
         | PaletteItemContent::ColorTheme { .. }
         | PaletteItemContent::SCMReference { .. }
         | PaletteItemContent::TerminalProfile { .. }
+        | PaletteItemContent::RunCompound { .. }
         | PaletteItemContent::IconTheme { .. } => {
             let text = item.filter_text;
             let indices = item.indices;
@@ -3350,6 +3351,7 @@ fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
         rename(window_tab_data.clone()),
         palette(window_tab_data.clone()),
         crate::search_popup::search_popup(window_tab_data.clone()),
+        crate::canvas::canvas_view(window_tab_data.clone()),
         about::about_popup(window_tab_data.clone()),
         alert::alert_box(window_tab_data.alert_data.clone()),
     ))
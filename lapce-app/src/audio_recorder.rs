@@ -6,13 +6,17 @@
 //!   // ... user speaks ...
 //!   let wav = recorder.stop(); // Returns WAV bytes for Whisper
 //!
-//! The recorder captures mono audio, encodes to 16-bit WAV on stop.
+//! The recorder captures mono audio and on stop resamples it to 16 kHz (what
+//! whisper.cpp expects) and encodes it as 16-bit WAV.
 
 use std::io::Cursor;
 use std::sync::{Arc, Mutex};
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 
+/// Sample rate of the WAV handed to speech-to-text.
+const WHISPER_SAMPLE_RATE: u32 = 16000;
+
 /// Shared sample buffer used by both the audio callback and the recorder.
 struct SharedBuffer {
     samples: Vec<f32>,
@@ -154,7 +158,8 @@ impl AudioRecorder {
         tracing::info!("Audio recording stopped: {} samples ({:.1}s at {}Hz)",
             samples.len(), samples.len() as f32 / sample_rate as f32, sample_rate);
 
-        encode_wav(&samples, sample_rate)
+        let samples = resample(&samples, sample_rate, WHISPER_SAMPLE_RATE);
+        encode_wav(&samples, WHISPER_SAMPLE_RATE)
     }
 
     pub fn is_recording(&self) -> bool {
@@ -162,6 +167,25 @@ impl AudioRecorder {
     }
 }
 
+/// Linear-interpolation resampling of mono samples; plenty for speech.
+fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from as f64 / to as f64;
+    let len = (samples.len() as f64 / ratio) as usize;
+    (0..len)
+        .map(|i| {
+            let pos = i as f64 * ratio;
+            let idx = pos as usize;
+            let frac = (pos - idx as f64) as f32;
+            let a = samples[idx];
+            let b = samples.get(idx + 1).copied().unwrap_or(a);
+            a + (b - a) * frac
+        })
+        .collect()
+}
+
 /// Encode f32 mono samples to 16-bit WAV bytes in memory.
 fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
     let spec = hound::WavSpec {
@@ -0,0 +1,467 @@
+//! Full-window canvas for the diagrams and code blocks the agent shows
+//! (`show_diagram` / `show_code`), with pan/zoom and export.
+
+use std::rc::Rc;
+
+use floem::{
+    View,
+    action::save_as,
+    event::{Event, EventListener, EventPropagation},
+    ext_event::create_ext_action,
+    file::{FileDialogOptions, FileInfo},
+    keyboard::Modifiers,
+    kurbo::{Point, Rect},
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+    style::{CursorStyle, Display, Position},
+    text::{Attrs, AttrsList, FamilyOwned, LineHeightValue, TextLayout},
+    views::{Decorators, dyn_stack, img, label, rich_text, scroll, stack},
+};
+use lapce_core::{command::FocusCommand, mode::Mode};
+use lapce_rpc::core::{AgentCanvas, AgentCanvasKind};
+
+use crate::{
+    command::{CommandExecuted, CommandKind},
+    config::color::LapceColor,
+    keypress::KeyPressFocus,
+    window_tab::{Focus, WindowTabData},
+};
+
+const MIN_ZOOM: f64 = 0.1;
+const MAX_ZOOM: f64 = 8.0;
+const ZOOM_STEP: f64 = 1.25;
+
+#[derive(Clone, Debug)]
+pub struct CanvasData {
+    pub visible: RwSignal<bool>,
+    /// The latest diagram or code block, also when the canvas is closed.
+    pub canvas: RwSignal<Option<AgentCanvas>>,
+    /// The diagram rendered as PNG, once it's loaded.
+    pub image: RwSignal<Option<Vec<u8>>>,
+    pub zoom: RwSignal<f64>,
+    /// Visible part of the content, moved by scrolling or dragging.
+    pub viewport: RwSignal<Rect>,
+    pub scroll_to: RwSignal<Option<Point>>,
+    /// Outcome of the last export or render, shown in the toolbar.
+    pub status: RwSignal<Option<String>>,
+    focus: RwSignal<Focus>,
+    scope: Scope,
+}
+
+impl CanvasData {
+    pub fn new(cx: Scope, focus: RwSignal<Focus>) -> Self {
+        Self {
+            visible: cx.create_rw_signal(false),
+            canvas: cx.create_rw_signal(None),
+            image: cx.create_rw_signal(None),
+            zoom: cx.create_rw_signal(1.0),
+            viewport: cx.create_rw_signal(Rect::ZERO),
+            scroll_to: cx.create_rw_signal(None),
+            status: cx.create_rw_signal(None),
+            focus,
+            scope: cx,
+        }
+    }
+
+    /// Make `canvas` the one the canvas shows, rendering it if it's a
+    /// diagram.
+    pub fn set(&self, canvas: AgentCanvas) {
+        if self.canvas.with_untracked(|c| c.as_ref() == Some(&canvas)) {
+            return;
+        }
+        self.zoom.set(1.0);
+        self.scroll_to.set(Some(Point::ZERO));
+        self.image.set(None);
+        self.status.set(None);
+        if canvas.kind == AgentCanvasKind::Diagram {
+            let image = self.image;
+            let status = self.status;
+            let send = create_ext_action(self.scope, move |result: Result<Vec<u8>, String>| {
+                match result {
+                    Ok(bytes) => image.set(Some(bytes)),
+                    Err(e) => status.set(Some(format!("Rendering failed: {e}"))),
+                }
+            });
+            let source = canvas.content.clone();
+            std::thread::spawn(move || send(fetch_mermaid(&source, "img")));
+        }
+        self.canvas.set(Some(canvas));
+    }
+
+    pub fn open(&self, canvas: AgentCanvas) {
+        self.set(canvas);
+        self.visible.set(true);
+        self.focus.set(Focus::Canvas);
+    }
+
+    pub fn close(&self) {
+        self.visible.set(false);
+        self.focus.set(Focus::Workbench);
+    }
+
+    pub fn zoom_by(&self, factor: f64) {
+        self.zoom.update(|z| *z = (*z * factor).clamp(MIN_ZOOM, MAX_ZOOM));
+    }
+
+    pub fn reset_zoom(&self) {
+        self.zoom.set(1.0);
+        self.scroll_to.set(Some(Point::ZERO));
+    }
+
+    /// Copy the Mermaid source or the code.
+    pub fn copy_source(&self) {
+        let Some(content) = self.canvas.with_untracked(|c| c.as_ref().map(|c| c.content.clone())) else {
+            return;
+        };
+        self.status.set(Some(match set_clipboard_text(content) {
+            Ok(()) => "Copied the source".to_string(),
+            Err(e) => format!("Copying failed: {e}"),
+        }));
+    }
+
+    /// Copy the diagram as SVG markup.
+    pub fn copy_svg(&self) {
+        let Some(source) = self.diagram_source() else {
+            return;
+        };
+        let status = self.status;
+        let send = create_ext_action(self.scope, move |result: Result<Vec<u8>, String>| {
+            let copied = result.and_then(|svg| {
+                set_clipboard_text(String::from_utf8_lossy(&svg).into_owned())
+            });
+            status.set(Some(match copied {
+                Ok(()) => "Copied as SVG".to_string(),
+                Err(e) => format!("Copying as SVG failed: {e}"),
+            }));
+        });
+        self.status.set(Some("Rendering SVG…".to_string()));
+        std::thread::spawn(move || send(fetch_mermaid(&source, "svg")));
+    }
+
+    /// Copy the rendered diagram as an image.
+    pub fn copy_png(&self) {
+        if self.diagram_source().is_none() {
+            return;
+        }
+        let copied = match self.image.get_untracked() {
+            Some(bytes) => decode_png(&bytes).and_then(|image| {
+                arboard::Clipboard::new()
+                    .and_then(|mut clipboard| clipboard.set_image(image))
+                    .map_err(|e| e.to_string())
+            }),
+            None => Err("the diagram hasn't rendered yet".to_string()),
+        };
+        self.status.set(Some(match copied {
+            Ok(()) => "Copied as PNG".to_string(),
+            Err(e) => format!("Copying as PNG failed: {e}"),
+        }));
+    }
+
+    /// Save the diagram or code as a markdown file.
+    pub fn save_markdown(&self) {
+        let Some(canvas) = self.canvas.get_untracked() else {
+            return;
+        };
+        let (language, default_name) = match canvas.kind {
+            AgentCanvasKind::Diagram => ("mermaid".to_string(), "diagram"),
+            AgentCanvasKind::Code => (canvas.language.clone().unwrap_or_default(), "code"),
+        };
+        let markdown = forge_agent::tools::display::canvas_markdown(
+            canvas.title.as_deref(),
+            &language,
+            &canvas.content,
+        );
+        let name = canvas
+            .title
+            .as_deref()
+            .map(file_stem)
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| default_name.to_string());
+        let status = self.status;
+        save_as(
+            FileDialogOptions::new()
+                .title("Save as Markdown")
+                .default_name(format!("{name}.md")),
+            move |file: Option<FileInfo>| {
+                let Some(path) = file.and_then(|mut f| f.path.pop()) else {
+                    return;
+                };
+                status.set(Some(match std::fs::write(&path, &markdown) {
+                    Ok(()) => format!("Saved {}", path.display()),
+                    Err(e) => format!("Saving failed: {e}"),
+                }));
+            },
+        );
+    }
+
+    fn diagram_source(&self) -> Option<String> {
+        self.canvas.with_untracked(|c| {
+            c.as_ref()
+                .filter(|c| c.kind == AgentCanvasKind::Diagram)
+                .map(|c| c.content.clone())
+        })
+    }
+}
+
+impl KeyPressFocus for CanvasData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(
+        &self,
+        _condition: crate::keypress::condition::Condition,
+    ) -> bool {
+        self.visible.get_untracked()
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        _count: Option<usize>,
+        _mods: Modifiers,
+    ) -> CommandExecuted {
+        if let CommandKind::Focus(FocusCommand::ModalClose) = &command.kind {
+            self.close();
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, c: &str) {
+        match c {
+            "+" | "=" => self.zoom_by(ZOOM_STEP),
+            "-" => self.zoom_by(1.0 / ZOOM_STEP),
+            "0" => self.reset_zoom(),
+            _ => {}
+        }
+    }
+
+    fn focus_only(&self) -> bool {
+        true
+    }
+}
+
+/// Render `source` with mermaid.ink as "svg" or "img" (PNG).
+fn fetch_mermaid(source: &str, format: &str) -> Result<Vec<u8>, String> {
+    if forge_agent::egress::local_only() {
+        return Err("diagrams render with mermaid.ink, which local-only mode blocks".to_string());
+    }
+    let url = forge_agent::tools::display::mermaid_ink_url(source, format);
+    let client = lapce_proxy::blocking_http_client_builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .user_agent("Mozilla/5.0")
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = client.get(&url).send().map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("mermaid.ink answered {}", resp.status()));
+    }
+    resp.bytes().map(|b| b.to_vec()).map_err(|e| e.to_string())
+}
+
+pub(crate) fn set_clipboard_text(text: String) -> Result<(), String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| e.to_string())
+}
+
+/// A PNG as RGBA pixels for the clipboard.
+fn decode_png(bytes: &[u8]) -> Result<arboard::ImageData<'static>, String> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size().ok_or("image too large")?];
+    let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    let data = &buf[..info.buffer_size()];
+    let rgba: Vec<u8> = match info.color_type {
+        png::ColorType::Rgba => data.to_vec(),
+        png::ColorType::Rgb => data.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => data.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => data.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        png::ColorType::Indexed => return Err("unexpected indexed PNG".to_string()),
+    };
+    Ok(arboard::ImageData {
+        width: info.width as usize,
+        height: info.height as usize,
+        bytes: rgba.into(),
+    })
+}
+
+/// Width and height from a PNG's header.
+fn png_size(bytes: &[u8]) -> Option<(f64, f64)> {
+    let header = bytes.get(16..24)?;
+    let width = u32::from_be_bytes(header[..4].try_into().ok()?);
+    let height = u32::from_be_bytes(header[4..].try_into().ok()?);
+    Some((width as f64, height as f64))
+}
+
+fn file_stem(title: &str) -> String {
+    title
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
+/// The canvas, over the whole window while it's open.
+pub fn canvas_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let data = window_tab_data.ai_chat.canvas.clone();
+    let visible = data.visible;
+    let canvas = data.canvas;
+    let image = data.image;
+    let zoom = data.zoom;
+    let viewport = data.viewport;
+    let scroll_to = data.scroll_to;
+    let status = data.status;
+    let is_diagram = move || canvas.with(|c| c.as_ref().is_some_and(|c| c.kind == AgentCanvasKind::Diagram));
+
+    let button = move |text: &'static str| {
+        label(move || text.to_string()).style(move |s| {
+            let config = config.get();
+            s.padding_horiz(10.0)
+                .padding_vert(2.0)
+                .margin_left(6.0)
+                .border(1.0)
+                .border_radius(4.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .cursor(CursorStyle::Pointer)
+                .hover(|s| s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND)))
+        })
+    };
+
+    let toolbar = {
+        let (d1, d2, d3, d4, d5, d6, d7, d8) = (
+            data.clone(),
+            data.clone(),
+            data.clone(),
+            data.clone(),
+            data.clone(),
+            data.clone(),
+            data.clone(),
+            data.clone(),
+        );
+        stack((
+            label(move || {
+                canvas.with(|c| {
+                    c.as_ref()
+                        .and_then(|c| c.title.clone())
+                        .unwrap_or_else(|| "Canvas".to_string())
+                })
+            })
+            .style(|s| s.font_bold().min_width(0.0).text_ellipsis()),
+            label(move || status.get().unwrap_or_default()).style(move |s| {
+                s.flex_grow(1.0)
+                    .min_width(0.0)
+                    .margin_left(12.0)
+                    .text_ellipsis()
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+            }),
+            button("−").on_click_stop(move |_| d1.zoom_by(1.0 / ZOOM_STEP)),
+            label(move || format!("{:.0}%", zoom.get() * 100.0))
+                .style(|s| s.margin_left(6.0).min_width(44.0).justify_center()),
+            button("+").on_click_stop(move |_| d2.zoom_by(ZOOM_STEP)),
+            button("Reset").on_click_stop(move |_| d3.reset_zoom()),
+            button("Copy SVG")
+                .on_click_stop(move |_| d4.copy_svg())
+                .style(move |s| s.apply_if(!is_diagram(), |s| s.hide())),
+            button("Copy PNG")
+                .on_click_stop(move |_| d5.copy_png())
+                .style(move |s| s.apply_if(!is_diagram(), |s| s.hide())),
+            button("Copy Source").on_click_stop(move |_| d6.copy_source()),
+            button("Save Markdown").on_click_stop(move |_| d7.save_markdown()),
+            button("Close").on_click_stop(move |_| d8.close()),
+        ))
+        .style(move |s| {
+            let config = config.get();
+            s.items_center()
+                .width_pct(100.0)
+                .padding_horiz(12.0)
+                .padding_vert(8.0)
+                .border_bottom(1.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .color(config.color(LapceColor::PANEL_FOREGROUND))
+        })
+    };
+
+    // Rebuilt when the content or the loaded image changes
+    let content = dyn_stack(
+        move || {
+            let loaded = image.with(|i| i.is_some());
+            canvas.with(|c| c.clone()).map(|c| (c, loaded)).into_iter()
+        },
+        |(c, loaded)| (c.content.clone(), *loaded),
+        move |(c, _)| match c.kind {
+            AgentCanvasKind::Diagram => match image.get_untracked() {
+                Some(bytes) => {
+                    let (width, _) = png_size(&bytes).unwrap_or((800.0, 600.0));
+                    img(move || bytes.clone())
+                        .style(move |s| s.width(width * zoom.get()))
+                        .into_any()
+                }
+                None => label(|| "Rendering diagram…".to_string())
+                    .style(move |s| s.color(config.get().color(LapceColor::EDITOR_DIM)))
+                    .into_any(),
+            },
+            AgentCanvasKind::Code => rich_text(move || {
+                let config = config.get();
+                let family: Vec<FamilyOwned> =
+                    FamilyOwned::parse_list(&config.editor.font_family).collect();
+                let attrs = Attrs::new()
+                    .font_size(config.editor.font_size() as f32 * zoom.get() as f32)
+                    .family(&family)
+                    .line_height(LineHeightValue::Normal(1.5))
+                    .color(config.color(LapceColor::EDITOR_FOREGROUND));
+                let mut text_layout = TextLayout::new();
+                text_layout.set_text(&c.content, AttrsList::new(attrs), None);
+                text_layout
+            })
+            .style(|s| s.selectable(true))
+            .into_any(),
+        },
+    )
+    .style(|s| s.padding(24.0));
+
+    // Drag to pan, ctrl/cmd + wheel to zoom
+    let drag_start: RwSignal<Option<(Point, Point)>> = window_tab_data.scope.create_rw_signal(None);
+    let data_wheel = data.clone();
+    let body = scroll(content.on_event(EventListener::PointerWheel, move |event| {
+        if let Event::PointerWheel(wheel) = event {
+            if wheel.modifiers.control() || wheel.modifiers.meta() {
+                data_wheel.zoom_by(if wheel.delta.y < 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP });
+                return EventPropagation::Stop;
+            }
+        }
+        EventPropagation::Continue
+    }))
+    .on_scroll(move |rect| viewport.set(rect))
+    .scroll_to(move || scroll_to.get())
+    .on_event_cont(EventListener::PointerDown, move |event| {
+        if let Event::PointerDown(pointer) = event {
+            drag_start.set(Some((pointer.pos, viewport.get_untracked().origin())));
+        }
+    })
+    .on_event_cont(EventListener::PointerMove, move |event| {
+        if let Event::PointerMove(pointer) = event {
+            if let Some((start, origin)) = drag_start.get_untracked() {
+                scroll_to.set(Some(origin + (start - pointer.pos)));
+            }
+        }
+    })
+    .on_event_cont(EventListener::PointerUp, move |_| drag_start.set(None))
+    .style(|s| s.flex_grow(1.0).min_height(0.0).width_pct(100.0));
+
+    stack((toolbar, body))
+        // Keep the workbench underneath from reacting
+        .on_event_stop(EventListener::PointerMove, |_| {})
+        .style(move |s| {
+            let config = config.get();
+            s.display(if visible.get() { Display::Flex } else { Display::None })
+                .position(Position::Absolute)
+                .size_pct(100.0, 100.0)
+                .flex_col()
+                .background(config.color(LapceColor::PANEL_BACKGROUND))
+        })
+        .debug_name("Canvas")
+}
@@ -86,7 +86,9 @@ impl CodeLensData {
                 args: Some(cargo_args.args.cargo_args),
                 cwd: None,
                 env: None,
+                env_file: None,
                 prelaunch: None,
+                depends_on: None,
                 debug_command: None,
                 dap_id: Default::default(),
                 tracing_output: mode == RunDebugMode::Debug,
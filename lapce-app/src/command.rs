@@ -422,6 +422,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.run_and_debug_stop")]
     RunAndDebugStop,
 
+    #[strum(message = "Run and Debug Re-run Last")]
+    #[strum(serialize = "run_and_debug_rerun_last")]
+    RunAndDebugRerunLast,
+
+    #[strum(message = "Run and Debug Run Previous")]
+    #[strum(serialize = "palette.run_and_debug_previous")]
+    RunAndDebugPrevious,
+
     #[strum(serialize = "source_control.checkout_reference")]
     CheckoutReference,
 
@@ -648,6 +656,46 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "git_manage_remotes")]
     GitManageRemotes,
 
+    #[strum(message = "Forge: Push to Talk (Start/Stop Dictation)")]
+    #[strum(serialize = "forge_push_to_talk")]
+    ForgePushToTalk,
+
+    #[strum(message = "Forge: Show Approval Queue")]
+    #[strum(serialize = "forge_show_approval_queue")]
+    ForgeShowApprovalQueue,
+
+    #[strum(message = "Forge: Approve Next Pending Tool Call")]
+    #[strum(serialize = "forge_approve_next")]
+    ForgeApproveNext,
+
+    #[strum(message = "Forge: Reject Next Pending Tool Call")]
+    #[strum(serialize = "forge_reject_next")]
+    ForgeRejectNext,
+
+    #[strum(message = "Forge: Approve All Pending Tool Calls")]
+    #[strum(serialize = "forge_approve_all_queued")]
+    ForgeApproveAllQueued,
+
+    #[strum(message = "Forge: Explain This Repository")]
+    #[strum(serialize = "forge_explain_repository")]
+    ForgeExplainRepository,
+
+    #[strum(message = "Forge: Show Usage & Cost")]
+    #[strum(serialize = "forge_show_usage")]
+    ForgeShowUsage,
+
+    #[strum(message = "Forge: Show Canvas (Latest Diagram or Code Block)")]
+    #[strum(serialize = "forge_show_canvas")]
+    ForgeShowCanvas,
+
+    #[strum(message = "Forge: Show Working Notes")]
+    #[strum(serialize = "forge_show_working_notes")]
+    ForgeShowWorkingNotes,
+
+    #[strum(message = "Forge: Restore Last File the Agent Deleted")]
+    #[strum(serialize = "forge_restore_deleted_file")]
+    ForgeRestoreDeletedFile,
+
     #[strum(serialize = "export_current_theme_settings")]
     #[strum(message = "Export current settings to a theme file")]
     ExportCurrentThemeSettings,
@@ -836,6 +884,9 @@ pub enum InternalCommand {
         mode: RunDebugMode,
         config: RunDebugConfig,
     },
+    RunCompound {
+        name: String,
+    },
     StartRename {
         path: PathBuf,
         placeholder: String,
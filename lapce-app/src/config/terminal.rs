@@ -96,6 +96,7 @@ impl TerminalConfig {
             arguments: profile.arguments,
             workdir,
             environment: profile.environment,
+            run_config: None,
         })
     }
 }
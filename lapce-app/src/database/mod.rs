@@ -571,6 +571,9 @@ User request: {}"#,
                 api_key,
                 conversation_id: uuid::Uuid::new_v4().to_string(),
                 attached_images: Vec::new(),
+                attachments: Vec::new(),
+                turn: 0,
+                rewind: false,
             },
             send,
         );
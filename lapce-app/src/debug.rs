@@ -13,8 +13,8 @@ use floem::{
 };
 use lapce_rpc::{
     dap_types::{
-        self, DapId, RunDebugConfig, SourceBreakpoint, StackFrame, Stopped,
-        ThreadId, Variable,
+        self, CompoundRunConfig, DapId, RunDebugConfig, RunDebugProgram, RunInput,
+        SourceBreakpoint, StackFrame, Stopped, ThreadId, Variable,
     },
     proxy::ProxyResponse,
     terminal::TermId,
@@ -43,6 +43,24 @@ impl Display for RunDebugMode {
     }
 }
 
+impl RunDebugMode {
+    /// Mode name stored in the run history.
+    pub fn history_name(&self) -> &'static str {
+        match self {
+            RunDebugMode::Run => "run",
+            RunDebugMode::Debug => "debug",
+        }
+    }
+
+    pub fn from_history(name: &str) -> Self {
+        if name == "debug" {
+            RunDebugMode::Debug
+        } else {
+            RunDebugMode::Run
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct RunDebugProcess {
     pub mode: RunDebugMode,
@@ -55,6 +73,107 @@ pub struct RunDebugProcess {
 #[derive(Deserialize, Serialize)]
 pub struct RunDebugConfigs {
     pub configs: Vec<RunDebugConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compounds: Vec<CompoundRunConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<RunInput>,
+}
+
+impl RunDebugConfigs {
+    pub fn find(&self, name: &str) -> Option<&RunDebugConfig> {
+        self.configs.iter().find(|c| c.name == name)
+    }
+
+    pub fn find_compound(&self, name: &str) -> Option<&CompoundRunConfig> {
+        self.compounds.iter().find(|c| c.name == name)
+    }
+
+    /// Fold `depends_on` into the config's prelaunch step, so the existing
+    /// prelaunch handling runs the dependencies (and their own dependencies)
+    /// to completion before the config itself starts.
+    pub fn resolve_depends_on(
+        &self,
+        config: &RunDebugConfig,
+    ) -> Result<RunDebugConfig, String> {
+        let mut steps = Vec::new();
+        let mut done = Vec::new();
+        let mut stack = vec![config.name.clone()];
+        for dep in config.depends_on.iter().flatten() {
+            self.collect_steps(dep, &mut stack, &mut done, &mut steps)?;
+        }
+        if steps.is_empty() {
+            return Ok(config.clone());
+        }
+
+        if let Some(prelaunch) = &config.prelaunch {
+            steps.push(shell_command(
+                &prelaunch.program,
+                prelaunch.args.as_deref().unwrap_or_default(),
+            ));
+        }
+        let mut resolved = config.clone();
+        resolved.prelaunch = Some(RunDebugProgram {
+            program: "sh".to_string(),
+            args: Some(vec!["-c".to_string(), steps.join(" && ")]),
+        });
+        Ok(resolved)
+    }
+
+    fn collect_steps(
+        &self,
+        name: &str,
+        stack: &mut Vec<String>,
+        done: &mut Vec<String>,
+        steps: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if done.iter().any(|d| d == name) {
+            return Ok(());
+        }
+        if stack.iter().any(|s| s == name) {
+            return Err(format!(
+                "Cycle in depends-on: {} -> {name}",
+                stack.join(" -> ")
+            ));
+        }
+        let dep = self
+            .find(name)
+            .ok_or_else(|| format!("Unknown depends-on config '{name}'"))?;
+
+        stack.push(name.to_string());
+        for sub in dep.depends_on.iter().flatten() {
+            self.collect_steps(sub, stack, done, steps)?;
+        }
+        stack.pop();
+
+        let mut step =
+            shell_command(&dep.program, dep.args.as_deref().unwrap_or_default());
+        if let Some(cwd) = dep.cwd.as_deref().filter(|c| !c.is_empty()) {
+            step = format!("(cd {} && {step})", shell_quote(cwd));
+        }
+        steps.push(step);
+        done.push(name.to_string());
+        Ok(())
+    }
+}
+
+fn shell_command(program: &str, args: &[String]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().map(|a| a.as_str()))
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg.chars().all(|c| {
+            c.is_ascii_alphanumeric() || "-_./=:,+@${}".contains(c)
+        });
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
 }
 
 #[derive(Clone)]
@@ -517,7 +636,67 @@ impl DapVariable {
 mod tests {
     use lapce_rpc::dap_types::{Scope, Variable};
 
-    use super::{DapVariable, ScopeOrVar};
+    use super::{DapVariable, RunDebugConfigs, ScopeOrVar};
+
+    #[test]
+    fn test_resolve_depends_on() {
+        let configs: RunDebugConfigs = toml::from_str(
+            r#"
+            [[configs]]
+            name = "codegen"
+            program = "cargo"
+            args = ["xtask", "codegen"]
+
+            [[configs]]
+            name = "build"
+            program = "cargo"
+            args = ["build"]
+            depends-on = ["codegen"]
+
+            [[configs]]
+            name = "web"
+            program = "npm"
+            args = ["run", "build"]
+            cwd = "${workspace}/web app"
+
+            [[configs]]
+            name = "server"
+            program = "target/debug/server"
+            depends-on = ["build", "web", "codegen"]
+
+            [[configs]]
+            name = "loop"
+            program = "true"
+            depends-on = ["loop"]
+
+            [[compounds]]
+            name = "full stack"
+            configs = ["server", "web"]
+            "#,
+        )
+        .unwrap();
+
+        let server = configs
+            .resolve_depends_on(configs.find("server").unwrap())
+            .unwrap();
+        let prelaunch = server.prelaunch.unwrap();
+        assert_eq!(prelaunch.program, "sh");
+        assert_eq!(
+            prelaunch.args.unwrap()[1],
+            "cargo xtask codegen && cargo build && (cd '${workspace}/web app' && npm run build)"
+        );
+        assert_eq!(server.program, "target/debug/server");
+
+        assert!(
+            configs
+                .resolve_depends_on(configs.find("loop").unwrap())
+                .is_err()
+        );
+        assert_eq!(
+            configs.find_compound("full stack").unwrap().configs,
+            vec!["server", "web"]
+        );
+    }
 
     #[test]
     fn test_update_count() {
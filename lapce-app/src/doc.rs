@@ -510,6 +510,48 @@ impl Doc {
         }
     }
 
+    /// An agent tool wrote `content` to this file. Apply it as one edit of
+    /// the changed span (so it can be undone and the language server gets a
+    /// normal change) and mark the buffer saved, since the disk matches.
+    pub fn apply_agent_edit(&self, content: &str) {
+        let old = self.buffer.with_untracked(|b| b.text().to_string());
+        if old == content {
+            return;
+        }
+        let prefix = old
+            .char_indices()
+            .zip(content.chars())
+            .find(|((_, a), b)| a != b)
+            .map(|((i, _), _)| i)
+            .unwrap_or_else(|| old.len().min(content.len()));
+        let suffix = old[prefix..]
+            .chars()
+            .rev()
+            .zip(content[prefix..].chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum::<usize>();
+        let selection = Selection::region(prefix, old.len() - suffix);
+        let replacement = &content[prefix..content.len() - suffix];
+        let Some((_, delta, _)) =
+            self.do_raw_edit(&[(selection, replacement)], EditType::Other)
+        else {
+            return;
+        };
+
+        let buffer_id = self.buffer_id;
+        self.editors.with_editors_untracked(|editors| {
+            for editor in editors.values() {
+                if editor.doc().buffer_id == buffer_id {
+                    editor.cursor().update(|cursor| {
+                        cursor.apply_delta(&delta);
+                    });
+                }
+            }
+        });
+        self.buffer.update(|buffer| buffer.set_pristine());
+    }
+
     pub fn do_insert(
         &self,
         cursor: &mut Cursor,
@@ -1013,9 +1055,17 @@ impl Doc {
                 }
             });
 
-            self.common.proxy.get_document_symbols(path, move |result| {
-                send(result);
-            });
+            let proxy = self.common.proxy.clone();
+            self.common
+                .proxy
+                .get_document_symbols(path.clone(), move |result| {
+                    if result.is_err() {
+                        // No language server: fall back to the tree-sitter outline
+                        proxy.get_syntax_symbols(path, send);
+                    } else {
+                        send(result);
+                    }
+                });
         }
     }
 
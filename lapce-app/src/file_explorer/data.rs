@@ -27,7 +27,7 @@ use lapce_rpc::{
         Duplicating, FileNodeItem, FileNodeViewKind, Naming, NamingState, NewNode,
         Renaming,
     },
-    proxy::ProxyResponse,
+    proxy::{ProxyRequest, ProxyResponse},
 };
 
 use crate::{
@@ -658,6 +658,16 @@ impl FileExplorerData {
 
         menu = menu.separator();
 
+        let path = path_a.clone();
+        let proxy = common.proxy.clone();
+        menu = menu.entry(MenuItem::new("Re-index for AI Search").action(move || {
+            proxy.request_async(ProxyRequest::IndexPath { path: path.clone() }, |res| {
+                if let Err(err) = res {
+                    tracing::warn!("Failed to re-index path: {:?}", err);
+                }
+            })
+        }));
+
         let internal_command = common.internal_command;
         menu = menu.entry(MenuItem::new("Refresh").action(move || {
             internal_command.send(InternalCommand::ReloadFileExplorer);
@@ -48,7 +48,9 @@ impl MapClient {
         Self {
             base_url,
             token,
-            client: reqwest::blocking::Client::new(),
+            client: lapce_proxy::blocking_http_client_builder()
+                .build()
+                .unwrap_or_default(),
         }
     }
 
@@ -687,9 +687,16 @@ impl PaletteData {
             }
         });
 
-        self.common.proxy.get_document_symbols(path, move |result| {
-            send(result);
-        });
+        let proxy = self.common.proxy.clone();
+        self.common
+            .proxy
+            .get_document_symbols(path.clone(), move |result| {
+                if result.is_err() {
+                    proxy.get_syntax_symbols(path, send);
+                } else {
+                    send(result);
+                }
+            });
     }
 
     fn format_document_symbol_resp(
@@ -933,6 +940,24 @@ impl PaletteData {
                     ));
                 }
             }
+            for compound in &configs.compounds {
+                items.push((
+                    executed_run_configs
+                        .get(&(RunDebugMode::Run, compound.name.clone())),
+                    PaletteItem {
+                        content: PaletteItemContent::RunCompound {
+                            compound: compound.clone(),
+                        },
+                        filter_text: format!(
+                            "Run {} ({})",
+                            compound.name,
+                            compound.configs.join(", ")
+                        ),
+                        score: 0,
+                        indices: vec![],
+                    },
+                ));
+            }
         }
 
         items.sort_by_key(|(executed, _item)| std::cmp::Reverse(executed.copied()));
@@ -1099,6 +1124,7 @@ impl PaletteData {
                         arguments: profile.arguments,
                         workdir: uri,
                         environment: profile.environment,
+                        run_config: None,
                     },
                 },
                 filter_text: name.to_owned(),
@@ -1269,6 +1295,13 @@ impl PaletteData {
                         },
                     );
                 }
+                PaletteItemContent::RunCompound { compound } => {
+                    self.common.internal_command.send(
+                        InternalCommand::RunCompound {
+                            name: compound.name.clone(),
+                        },
+                    );
+                }
                 PaletteItemContent::ColorTheme { name } => self
                     .common
                     .internal_command
@@ -1389,6 +1422,7 @@ impl PaletteData {
                 PaletteItemContent::Command { .. } => {}
                 PaletteItemContent::Workspace { .. } => {}
                 PaletteItemContent::RunAndDebug { .. } => {}
+                PaletteItemContent::RunCompound { .. } => {}
                 PaletteItemContent::SshHost { .. } => {}
                 #[cfg(windows)]
                 PaletteItemContent::WslHost { .. } => {}
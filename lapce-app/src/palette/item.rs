@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use lapce_core::line_ending::LineEnding;
-use lapce_rpc::dap_types::RunDebugConfig;
+use lapce_rpc::dap_types::{CompoundRunConfig, RunDebugConfig};
 use lsp_types::{Range, SymbolKind};
 
 use crate::{
@@ -65,6 +65,9 @@ pub enum PaletteItemContent {
         mode: RunDebugMode,
         config: RunDebugConfig,
     },
+    RunCompound {
+        compound: CompoundRunConfig,
+    },
     ColorTheme {
         name: String,
     },
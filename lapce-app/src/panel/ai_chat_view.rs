@@ -19,6 +19,7 @@ use floem::{
     event::EventListener,
     ext_event::create_ext_action,
     kurbo::{Point, Size},
+    menu::{Menu, MenuItem},
     reactive::{
         Scope, SignalGet, SignalUpdate, SignalWith, create_rw_signal,
     },
@@ -34,8 +35,8 @@ use super::position::PanelPosition;
 use crate::{
     ai_chat::{
         AiChatData, ChatEntry, ChatEntryKind, ChatRole, ChatToolCall, ToolCallStatus,
-        ChatPlan, ChatPlanStep, ChatPlanStepStatus, ChatServerToolCall,
-        ALL_PROVIDERS, models_for_provider,
+        ChatFollowup, ChatPlan, ChatPlanStep, ChatPlanStepStatus, ChatServerToolCall,
+        ChatTaskSummary, ALL_PROVIDERS,
     },
     config::{color::LapceColor, icon::LapceIcons},
     text_input::TextInputBuilder,
@@ -238,7 +239,7 @@ fn start_oauth_flow(
     });
     
     std::thread::spawn(move || {
-        let client = match reqwest::blocking::Client::builder()
+        let client = match lapce_proxy::blocking_http_client_builder()
             .timeout(std::time::Duration::from_secs(10))
             .build()
         {
@@ -353,10 +354,13 @@ fn chat_view(window_tab_data: Rc<WindowTabData>) -> impl View {
 
     let internal_command = window_tab_data.common.internal_command;
     let proxy = window_tab_data.common.proxy.clone();
+    let chat_data_drop = chat_data.clone();
 
     stack((
         // ── Header ──────────────────────────────────────────
         chat_header(config, chat_data_clear, window_tab_data.panel.clone()),
+        // ── Trust prompt / restricted-mode notice ───────────
+        workspace_trust_banner(config, chat_data.clone()),
         // ── Message list (scrollable) with auto-scroll ──────
         chat_message_list(config, chat_data.clone(), internal_command, proxy),
         // ── AI Diff toolbar (only shown when pending diffs exist) ──
@@ -364,10 +368,20 @@ fn chat_view(window_tab_data: Rc<WindowTabData>) -> impl View {
         // ── Input area at the bottom ────────────────────────
         chat_input_area(window_tab_data, chat_data),
     ))
+    // Files dropped on the chat are attached rather than opened
+    .on_event_stop(EventListener::DroppedFile, move |event| {
+        if let floem::event::Event::DroppedFile(file) = event {
+            if !file.path.is_dir() {
+                chat_data_drop.add_file(&file.path);
+            }
+        }
+    })
     .style(|s| s.flex_col().size_pct(100.0, 100.0))
 }
 
-/// Toolbar showing Accept All / Reject All buttons when there are pending AI diffs.
+/// "Changes from AI": the files the last agent turn changed, each with
+/// Accept/Reject, plus Accept All / Reject All for every pending diff.
+/// Only shown when there are pending AI diffs.
 fn ai_diff_toolbar(window_tab_data: Rc<WindowTabData>) -> impl View {
     let config = window_tab_data.common.config;
     let ai_diffs = window_tab_data.ai_diffs.clone();
@@ -377,14 +391,18 @@ fn ai_diff_toolbar(window_tab_data: Rc<WindowTabData>) -> impl View {
     let proxy_reject = window_tab_data.common.proxy.clone();
     let ai_diffs_accept = ai_diffs.clone();
     let ai_diffs_reject = ai_diffs.clone();
+    let ai_diffs_files = ai_diffs.clone();
+    let proxy_files = window_tab_data.common.proxy.clone();
+    let internal_command = window_tab_data.common.internal_command;
+    let workspace_path = window_tab_data.workspace.path.clone();
 
-    container(
+    let toolbar = container(
         stack((
             // Diff count label
             label(move || {
                 let _v = version.get(); // re-trigger on changes
                 let count = ai_diffs.diffs.with(|d| d.len());
-                format!("{} pending diff(s)", count)
+                format!("Changes from AI · {} pending", count)
             })
             .style(move |s| {
                 let config = config.get();
@@ -447,6 +465,31 @@ fn ai_diff_toolbar(window_tab_data: Rc<WindowTabData>) -> impl View {
                 .width_pct(100.0)
                 .padding(6.0)
         }),
+    );
+
+    // One row per file changed in the last turn
+    let files = dyn_stack(
+        move || {
+            let _v = version.get();
+            ai_diffs_files.turn_changes()
+        },
+        |diff| diff.diff_id.clone(),
+        move |diff| {
+            turn_change_row(
+                config,
+                diff,
+                window_tab_data.ai_diffs.clone(),
+                proxy_files.clone(),
+                internal_command,
+                workspace_path.clone(),
+            )
+        },
+    )
+    .style(|s| s.flex_col().width_pct(100.0).max_height(180.0));
+
+    container(
+        stack((toolbar, scroll(files).style(|s| s.width_pct(100.0).max_height(180.0))))
+            .style(|s| s.flex_col().width_pct(100.0)),
     )
     .style(move |s| {
         let config = config.get();
@@ -459,6 +502,87 @@ fn ai_diff_toolbar(window_tab_data: Rc<WindowTabData>) -> impl View {
     })
 }
 
+/// A file in the "Changes from AI" panel: path (opens the file), line
+/// counts, and Accept / Reject for that file's net change.
+fn turn_change_row(
+    config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
+    diff: crate::ai_diff::PendingDiff,
+    ai_diffs: crate::ai_diff::AiDiffStore,
+    proxy: lapce_rpc::proxy::ProxyRpcHandler,
+    internal_command: crate::listener::Listener<crate::command::InternalCommand>,
+    workspace_path: Option<std::path::PathBuf>,
+) -> impl View {
+    let (added, removed) = diff.line_stats();
+    let path = diff.file_path.clone();
+    let full_path = workspace_path
+        .map(|w| w.join(&path))
+        .unwrap_or_else(|| std::path::PathBuf::from(&path));
+    let accept_id = diff.diff_id.clone();
+    let reject_id = diff.diff_id.clone();
+    let ai_diffs_reject = ai_diffs.clone();
+    let proxy_reject = proxy.clone();
+
+    let button = move |text: &'static str| {
+        label(move || text.to_string()).style(move |s| {
+            let config = config.get();
+            s.padding_horiz(8.0)
+                .padding_vert(1.0)
+                .margin_left(4.0)
+                .border_radius(4.0)
+                .font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                .cursor(CursorStyle::Pointer)
+                .color(config.color(LapceColor::PANEL_FOREGROUND))
+                .hover(|s| {
+                    s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+                })
+        })
+    };
+
+    stack((
+        label(move || path.clone())
+            .on_click_stop(move |_| {
+                internal_command.send(crate::command::InternalCommand::OpenFile {
+                    path: full_path.clone(),
+                });
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.flex_grow(1.0)
+                    .min_width(0.0)
+                    .text_ellipsis()
+                    .cursor(CursorStyle::Pointer)
+                    .font_size((config.ui.font_size() as f32 - 1.0).max(11.0))
+                    .color(config.color(LapceColor::PANEL_FOREGROUND))
+            }),
+        label(move || format!("+{added} −{removed}")).style(move |s| {
+            let config = config.get();
+            s.margin_left(6.0)
+                .font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                .color(config.color(LapceColor::EDITOR_DIM))
+        }),
+        button("Accept").on_click_stop(move |_| {
+            ai_diffs.remove_diff(&accept_id);
+            proxy.request_async(
+                lapce_rpc::proxy::ProxyRequest::AgentDiffAccept {
+                    diff_id: accept_id.clone(),
+                    accepted_hunks: Vec::new(),
+                },
+                |_| {},
+            );
+        }),
+        button("Reject").on_click_stop(move |_| {
+            ai_diffs_reject.reject_diff(&reject_id);
+            proxy_reject.request_async(
+                lapce_rpc::proxy::ProxyRequest::AgentDiffReject {
+                    diff_id: reject_id.clone(),
+                },
+                |_| {},
+            );
+        }),
+    ))
+    .style(|s| s.items_center().width_pct(100.0).padding_horiz(10.0).padding_vert(2.0))
+}
+
 /// Header with title, index status badge, clear button, and close button.
 fn chat_header(
     config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
@@ -493,6 +617,7 @@ fn chat_header(
             .style(|s| s.items_center()),
             // Index status badge with Index button + progress bar
             index_status_badge(config, chat_data_badge),
+            external_changes_badge(config, chat_data.external_changes),
             // Spacer
             empty().style(|s| s.flex_grow(1.0)),
             // Clear button
@@ -545,8 +670,90 @@ fn chat_header(
     })
 }
 
+/// Asks whether to trust a newly opened workspace, and afterwards shows
+/// that an untrusted one runs the agent in restricted mode.
+fn workspace_trust_banner(
+    config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
+    chat_data: AiChatData,
+) -> impl View {
+    let trust = chat_data.workspace_trust;
+    let chat_data_trust = chat_data.clone();
+    let chat_data_restrict = chat_data;
+    // `only_undecided`: hide once the user chose
+    let action = move |text: &'static str, only_undecided: bool| {
+        label(move || text.to_string()).style(move |s| {
+            let config = config.get();
+            s.font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                .padding_horiz(6.0)
+                .padding_vert(2.0)
+                .border_radius(4.0)
+                .cursor(CursorStyle::Pointer)
+                .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+                .hover(|s| s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND)))
+                .apply_if(only_undecided && trust.get().is_some(), |s| s.hide())
+        })
+    };
+
+    stack((
+        label(move || match trust.get() {
+            None => "Trust this workspace? Trusted workspaces let the agent edit files, run commands and index code.".to_string(),
+            _ => "Restricted mode: the agent can read this workspace but not change it, run commands or index it.".to_string(),
+        })
+        .style(move |s| {
+            let config = config.get();
+            s.font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                .flex_grow(1.0)
+                .flex_basis(0.0)
+                .min_width(0.0)
+                .color(config.color(LapceColor::LAPCE_WARN))
+        }),
+        action("Trust", false).on_click_stop(move |_| chat_data_trust.workspace_trust(Some(true))),
+        action("Stay restricted", true)
+            .on_click_stop(move |_| chat_data_restrict.workspace_trust(Some(false))),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.items_center()
+            .gap(6.0)
+            .width_pct(100.0)
+            .padding_horiz(10.0)
+            .padding_vert(4.0)
+            .border_bottom(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .apply_if(trust.get() == Some(true), |s| s.hide())
+    })
+}
+
+/// "N changed outside agent" badge; click to dismiss.
+fn external_changes_badge(
+    config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
+    external_changes: floem::reactive::RwSignal<Vec<String>>,
+) -> impl View {
+    label(move || {
+        let files = external_changes.get();
+        match files.len() {
+            1 => format!("{} changed outside agent", files[0]),
+            n => format!("{n} files changed outside agent"),
+        }
+    })
+    .on_click_stop(move |_| external_changes.set(Vec::new()))
+    .style(move |s| {
+        let config = config.get();
+        s.font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+            .padding_horiz(6.0)
+            .padding_vert(2.0)
+            .border_radius(4.0)
+            .cursor(CursorStyle::Pointer)
+            .color(config.color(LapceColor::LAPCE_WARN))
+            .border(1.0)
+            .border_color(config.color(LapceColor::LAPCE_WARN))
+            .apply_if(external_changes.with(|f| f.is_empty()), |s| s.hide())
+    })
+}
+
 /// Index status area: shows status label and progress bar during indexing.
-/// Auto-indexing happens on first message, so no manual button needed.
+/// Auto-indexing happens on first message; clicking the badge shows the
+/// index's details and lets the user refresh, re-index or clear it.
 fn index_status_badge(
     config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
     chat_data: AiChatData,
@@ -579,7 +786,26 @@ fn index_status_badge(
         // ── Layer 2: progress bar (visible only during indexing) ──
         index_progress_view(config, index_status, index_progress),
     ))
-    .style(|s| s.flex_row().items_center())
+    .popout_menu(move || index_menu(&chat_data))
+    .style(|s| s.flex_row().items_center().cursor(CursorStyle::Pointer))
+}
+
+/// Details and actions for the codebase index.
+fn index_menu(chat_data: &AiChatData) -> Menu {
+    let mut menu = Menu::new("");
+    if let Some(details) = chat_data.index_details.get_untracked() {
+        for line in details.summary_lines() {
+            menu = menu.entry(MenuItem::new(line).enabled(false));
+        }
+        menu = menu.separator();
+    }
+    let indexing = chat_data.index_progress.get_untracked() >= 0.0;
+    let data = chat_data.clone();
+    menu = menu.entry(MenuItem::new("Refresh Status").action(move || data.refresh_index_status()));
+    let data = chat_data.clone();
+    menu = menu.entry(MenuItem::new("Re-index Workspace").enabled(!indexing).action(move || data.start_indexing()));
+    let data = chat_data.clone();
+    menu.entry(MenuItem::new("Clear Index").enabled(!indexing).action(move || data.clear_index()))
 }
 
 /// Progress bar shown during codebase indexing.
@@ -662,7 +888,8 @@ fn chat_message_list(
                     |entry: &ChatEntry| entry.key(),
                     {
                         let proxy = proxy.clone();
-                        move |entry| chat_entry_view(config, entry, internal_command, proxy.clone(), panel_width, auto_approve_session)
+                        let chat_data = chat_data.clone();
+                        move |entry| chat_entry_view(config, entry, chat_data.clone(), internal_command, proxy.clone(), panel_width, auto_approve_session)
                     },
                 )
                 .style(|s| s.flex_col().width_pct(100.0).min_width(0.0)),
@@ -739,6 +966,8 @@ fn chat_message_list(
                 // Prevent horizontal scroll — constrain child width to panel
                 .set(floem::style::OverflowX, floem::taffy::style::Overflow::Hidden)
         }),
+        // ── Agent status row (step, activity, elapsed, tokens) ──
+        progress_row(config, chat_data.progress),
     ))
     .on_resize(move |rect| {
         // Update the tracked panel width whenever the outer container resizes.
@@ -758,6 +987,28 @@ fn chat_message_list(
     })
 }
 
+/// Status row pinned under the messages while the agent works, so there's
+/// always something moving between tool updates.
+fn progress_row(
+    config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
+    progress: floem::reactive::RwSignal<Option<lapce_rpc::core::AgentRunProgress>>,
+) -> impl View {
+    label(move || progress.get().map(|p| crate::ai_chat::progress_line(&p)).unwrap_or_default())
+        .style(move |s| {
+            let config = config.get();
+            s.width_pct(100.0)
+                .min_width(0.0)
+                .padding_horiz(12.0)
+                .padding_vert(4.0)
+                .text_ellipsis()
+                .font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                .color(config.color(LapceColor::EDITOR_DIM))
+                .border_top(1.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .apply_if(progress.with(|p| p.is_none()), |s| s.hide())
+        })
+}
+
 /// "Forge is thinking..." indicator with pulsing dots.
 fn thinking_indicator(
     config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
@@ -803,6 +1054,7 @@ fn model_dropdown_panel(
     let dropdown_open = chat_data.dropdown_open;
     let keys_config = chat_data.keys_config;
     let current_model = chat_data.model;
+    let models_data = chat_data.clone();
 
     container(
         dyn_stack(
@@ -814,7 +1066,7 @@ fn model_dropdown_panel(
                 let mut items: Vec<(String, String, bool)> = Vec::new();
                 for &prov in ALL_PROVIDERS {
                     if config_val.key_for(prov).is_some() {
-                        for model in models_for_provider(prov) {
+                        for model in models_data.models_for(prov) {
                             let is_current = current_model.get() == model;
                             items.push((prov.to_string(), model.to_string(), is_current));
                         }
@@ -878,14 +1130,18 @@ fn model_dropdown_panel(
 fn chat_entry_view(
     config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
     entry: ChatEntry,
+    chat_data: AiChatData,
     internal_command: crate::listener::Listener<crate::command::InternalCommand>,
     proxy: lapce_rpc::proxy::ProxyRpcHandler,
     panel_width: floem::reactive::RwSignal<f64>,
     auto_approve_session: floem::reactive::RwSignal<bool>,
 ) -> impl View {
+    let entry_id = entry.id;
     match entry.kind {
         ChatEntryKind::Message { role, content } => {
-            message_bubble(config, role, content, panel_width).into_any()
+            // User messages can be rewound to ("retry from here")
+            let retry = (role == ChatRole::User).then_some((entry_id, chat_data));
+            message_bubble(config, role, content, panel_width, retry).into_any()
         }
         ChatEntryKind::ToolCall(tc) => {
             // Approval-pending tools get Accept/Reject/Approve-All buttons
@@ -900,13 +1156,15 @@ fn chat_entry_view(
             if is_file_tool {
                 file_tool_card(config, tc, internal_command, panel_width).into_any()
             } else {
-                tool_call_card(config, tc, panel_width).into_any()
+                tool_call_card(config, tc, panel_width, chat_data.canvas.clone()).into_any()
             }
         }
         ChatEntryKind::Plan(plan) => {
             // Show the agent's task plan with status icons
             plan_view(config, plan).into_any()
         }
+        ChatEntryKind::Followup(followup) => followup_card(config, followup, chat_data).into_any(),
+        ChatEntryKind::TaskSummary(summary) => task_summary_card(config, summary).into_any(),
         ChatEntryKind::ThinkingStep(_) | ChatEntryKind::ServerToolCall(_) => {
             // These entry types were used by the removed thinking section — render nothing.
             empty().into_any()
@@ -928,6 +1186,7 @@ fn message_bubble(
     role: ChatRole,
     content: String,
     panel_width: floem::reactive::RwSignal<f64>,
+    retry: Option<(u64, AiChatData)>,
 ) -> impl View {
     let is_user = role == ChatRole::User;
     let is_system = role == ChatRole::System;
@@ -949,20 +1208,48 @@ fn message_bubble(
 
     container(
         stack((
-            // Role label
-            label(move || role_label.to_string()).style(move |s| {
-                let config = config.get();
-                s.font_size((config.ui.font_size() as f32).max(12.0))
-                    .font_bold()
-                    .margin_bottom(4.0)
-                    .color(if is_user {
-                        config.color(LapceColor::LAPCE_ICON_ACTIVE)
-                    } else if is_system {
-                        config.color(LapceColor::LAPCE_WARN)
-                    } else {
-                        config.color(LapceColor::PANEL_FOREGROUND)
-                    })
-            }),
+            // Role label, plus "Retry from here" on user messages
+            stack((
+                label(move || role_label.to_string()).style(move |s| {
+                    let config = config.get();
+                    s.font_size((config.ui.font_size() as f32).max(12.0))
+                        .font_bold()
+                        .color(if is_user {
+                            config.color(LapceColor::LAPCE_ICON_ACTIVE)
+                        } else if is_system {
+                            config.color(LapceColor::LAPCE_WARN)
+                        } else {
+                            config.color(LapceColor::PANEL_FOREGROUND)
+                        })
+                }),
+                empty().style(|s| s.flex_grow(1.0)),
+                match retry {
+                    Some((entry_id, chat_data)) => {
+                        let is_loading = chat_data.is_loading;
+                        label(|| "Retry from here".to_string())
+                            .style(move |s| {
+                                let config = config.get();
+                                s.font_size((config.ui.font_size() as f32 - 1.0).max(11.0))
+                                    .padding_horiz(6.0)
+                                    .border_radius(4.0)
+                                    .cursor(CursorStyle::Pointer)
+                                    .color(config.color(LapceColor::EDITOR_DIM))
+                                    .hover(|s| {
+                                        s.background(
+                                            config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                                        )
+                                    })
+                                    .apply_if(is_loading.get(), |s| s.hide())
+                            })
+                            .on_click_stop(move |_| {
+                                chat_data.retry_from(entry_id);
+                            })
+                            .into_any()
+                    }
+                    None => empty().into_any(),
+                },
+            ))
+            .style(|s| s.items_center().width_pct(100.0).margin_bottom(4.0)),
             // Message content: markdown for assistant, plain text otherwise
             if is_assistant {
                 let id_counter = AtomicU64::new(0);
@@ -1105,7 +1392,7 @@ fn file_tool_card(
     panel_width: floem::reactive::RwSignal<f64>,
 ) -> impl View {
     let is_success = tc.status == ToolCallStatus::Success;
-    let is_error = tc.status == ToolCallStatus::Error;
+    let is_error = matches!(tc.status, ToolCallStatus::Error | ToolCallStatus::Conflict);
     let is_running = tc.status == ToolCallStatus::Running;
 
     // Extract file path from the JSON arguments
@@ -1159,6 +1446,7 @@ fn file_tool_card(
         ToolCallStatus::Accepted => "\u{2713}", // same as success
         ToolCallStatus::Error => "\u{2717}",
         ToolCallStatus::Rejected => "\u{2718}",
+        ToolCallStatus::Conflict => "\u{26A0}",
     };
 
     let click_path = file_path.clone();
@@ -1272,9 +1560,9 @@ fn create_terminal_text_layout(
     text_layout
 }
 
-/// Build a TextLayout for a diff string where lines starting with "+ " are green
-/// and lines starting with "- " are red. All other lines use the default foreground color.
-fn create_diff_text_layout(
+/// Build a TextLayout for a diff string where added ("+") lines are green
+/// and removed ("-") lines are red. All other lines use the default foreground color.
+pub(super) fn create_diff_text_layout(
     diff_text: &str,
     config: &crate::config::LapceConfig,
 ) -> TextLayout {
@@ -1296,9 +1584,10 @@ fn create_diff_text_layout(
     let mut byte_pos = 0usize;
     for line in diff_text.split('\n') {
         let line_len = line.len();
-        let color_opt = if line.starts_with("+ ") {
+        // "+ line" previews and unified diffs alike, but not "+++"/"---" headers
+        let color_opt = if line.starts_with('+') && !line.starts_with("+++") {
             Some(added_color)
-        } else if line.starts_with("- ") {
+        } else if line.starts_with('-') && !line.starts_with("---") {
             Some(removed_color)
         } else {
             None
@@ -1503,6 +1792,7 @@ fn approval_card(
                 let status_msg = match &tc.status {
                     ToolCallStatus::Accepted => Some("✓ Changes accepted"),
                     ToolCallStatus::Rejected => Some("↩ Changes reverted"),
+                    ToolCallStatus::Conflict => Some("⚠ File changed since the agent read it — not applied"),
                     _ => None,
                 };
                 if let Some(msg) = status_msg {
@@ -1655,10 +1945,11 @@ fn tool_call_card(
     config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
     tc: ChatToolCall,
     panel_width: floem::reactive::RwSignal<f64>,
+    canvas: crate::canvas::CanvasData,
 ) -> impl View {
     let is_running = tc.status == ToolCallStatus::Running;
     let is_success = tc.status == ToolCallStatus::Success;
-    let is_error = tc.status == ToolCallStatus::Error;
+    let is_error = matches!(tc.status, ToolCallStatus::Error | ToolCallStatus::Conflict);
 
     // Status icon character
     let status_icon = match &tc.status {
@@ -1670,6 +1961,7 @@ fn tool_call_card(
         ToolCallStatus::Accepted => "\u{2713}", // same as success
         ToolCallStatus::Error => "\u{2717}",     // X mark
         ToolCallStatus::Rejected => "\u{2718}",  // rejected
+        ToolCallStatus::Conflict => "\u{26A0}", // warning
     };
 
     let tool_name = tc.name.clone();
@@ -1700,6 +1992,24 @@ fn tool_call_card(
             (None, None, None, None, None, tc.output.clone())
         };
     
+    // What "Open in Canvas" shows
+    let canvas_item = if is_show_code {
+        code_block.clone().map(|content| lapce_rpc::core::AgentCanvas {
+            kind: lapce_rpc::core::AgentCanvasKind::Code,
+            title: code_title.clone(),
+            content,
+            language: code_language.clone(),
+        })
+    } else {
+        mermaid_diagram.clone().map(|content| lapce_rpc::core::AgentCanvas {
+            kind: lapce_rpc::core::AgentCanvasKind::Diagram,
+            title: mermaid_title.clone(),
+            content,
+            language: None,
+        })
+    };
+    let has_canvas = canvas_item.is_some();
+
     // For preview in collapsed state or has_details check, we use truncated output
     let output_preview = regular_output.clone().map(|o| {
         if o.len() > 300 {
@@ -1958,7 +2268,7 @@ fn tool_call_card(
                                                     let encoded = STANDARD.encode(json.to_string());
                                                     let url = format!("https://mermaid.ink/img/{}", encoded);
                                                     
-                                                    let client = reqwest::blocking::Client::builder()
+                                                    let client = lapce_proxy::blocking_http_client_builder()
                                                         .timeout(std::time::Duration::from_secs(15))
                                                         .user_agent("Mozilla/5.0")
                                                         .build()
@@ -2052,6 +2362,21 @@ fn tool_call_card(
                                     .apply_if(mermaid_diagram.is_none(), |s| s.hide())
                             })
                         },
+                        // Full-window canvas with pan/zoom and export
+                        label(|| "Open in Canvas (zoom, copy as SVG/PNG, save markdown)".to_string())
+                            .on_click_stop(move |_| {
+                                if let Some(item) = canvas_item.clone() {
+                                    canvas.open(item);
+                                }
+                            })
+                            .style(move |s| {
+                                let config = config.get();
+                                s.font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                                    .margin_top(6.0)
+                                    .color(config.color(LapceColor::EDITOR_LINK))
+                                    .cursor(CursorStyle::Pointer)
+                                    .apply_if(!has_canvas, |s| s.hide())
+                            }),
                         // Regular output - styled like a scrollable terminal window
                         {
                             let out = regular_output.clone();
@@ -2217,6 +2542,199 @@ fn plan_view(
     })
 }
 
+/// A question from the agent with its suggested answers as buttons; once
+/// answered, the answer replaces the buttons. Typing in the input answers too.
+fn followup_card(
+    config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
+    followup: ChatFollowup,
+    chat_data: AiChatData,
+) -> impl View {
+    let ChatFollowup { tool_call_id, question, options, default, answer } = followup;
+    let answered = answer.is_some();
+    let footer = match answer {
+        Some(answer) => label(move || format!("\u{21B3} {answer}"))
+            .style(move |s| {
+                let config = config.get();
+                s.margin_top(6.0)
+                    .font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                    .color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+            })
+            .into_any(),
+        None => stack((
+            dyn_stack(
+                move || options.clone(),
+                |option: &String| option.clone(),
+                move |option| {
+                    let is_default = default.as_ref() == Some(&option);
+                    let chat_data = chat_data.clone();
+                    let tool_call_id = tool_call_id.clone();
+                    let text = option.clone();
+                    label(move || text.clone())
+                        .on_click_stop(move |_| {
+                            chat_data.answer_followup(&tool_call_id, option.clone());
+                        })
+                        .style(move |s| {
+                            let config = config.get();
+                            s.padding_horiz(12.0)
+                                .padding_vert(4.0)
+                                .margin_right(6.0)
+                                .margin_bottom(4.0)
+                                .border_radius(4.0)
+                                .border(1.0)
+                                .font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                                .cursor(CursorStyle::Pointer)
+                                .apply_if(is_default, |s| {
+                                    s.font_bold()
+                                        .color(config.color(LapceColor::PANEL_BACKGROUND))
+                                        .background(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+                                        .border_color(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+                                })
+                                .apply_if(!is_default, |s| {
+                                    s.color(config.color(LapceColor::PANEL_FOREGROUND))
+                                        .border_color(config.color(LapceColor::LAPCE_BORDER))
+                                        .hover(|s| s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND)))
+                                })
+                        })
+                },
+            )
+            .style(|s| s.flex_row().flex_wrap(floem::taffy::style::FlexWrap::Wrap).width_pct(100.0)),
+            label(|| "Click an answer or type your own below".to_string()).style(move |s| {
+                let config = config.get();
+                s.margin_top(2.0)
+                    .font_size((config.ui.font_size() as f32 - 3.0).max(9.0))
+                    .color(config.color(LapceColor::EDITOR_DIM))
+            }),
+        ))
+        .style(|s| s.flex_col().margin_top(6.0).width_pct(100.0))
+        .into_any(),
+    };
+
+    container(
+        stack((
+            label(move || question.clone()).style(move |s| {
+                let config = config.get();
+                s.font_size((config.ui.font_size() as f32 - 1.0).max(11.0))
+                    .font_bold()
+                    .min_width(0.0)
+                    .color(config.color(LapceColor::PANEL_FOREGROUND))
+            }),
+            footer,
+        ))
+        .style(|s| s.flex_col().width_pct(100.0)),
+    )
+    .style(move |s| {
+        let config = config.get();
+        s.padding(8.0)
+            .margin_horiz(8.0)
+            .margin_vert(4.0)
+            .min_width(0.0)
+            .border(1.0)
+            .border_color(if answered {
+                config.color(LapceColor::LAPCE_BORDER).multiply_alpha(0.5)
+            } else {
+                config.color(LapceColor::LAPCE_ICON_ACTIVE)
+            })
+            .background(config.color(LapceColor::PANEL_BACKGROUND).multiply_alpha(0.5))
+    })
+}
+
+/// The summary of a completed task, collapsed to its title, with buttons
+/// to copy the body as a PR description or save it.
+fn task_summary_card(
+    config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
+    summary: ChatTaskSummary,
+) -> impl View {
+    let ChatTaskSummary { title, body } = summary;
+    let expanded = create_rw_signal(false);
+    let status = create_rw_signal(None::<String>);
+    let markdown = format!("# {title}\n\n{body}");
+
+    let button = move |text: &'static str| {
+        label(move || text.to_string()).style(move |s| {
+            let config = config.get();
+            s.padding_horiz(10.0)
+                .padding_vert(2.0)
+                .margin_right(6.0)
+                .border(1.0)
+                .border_radius(4.0)
+                .font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+                .cursor(CursorStyle::Pointer)
+                .hover(|s| s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND)))
+        })
+    };
+    let copy_body = body.clone();
+    let actions = stack((
+        button("Copy PR Body").on_click_stop(move |_| {
+            status.set(Some(match crate::canvas::set_clipboard_text(copy_body.clone()) {
+                Ok(()) => "Copied".to_string(),
+                Err(e) => format!("Copying failed: {e}"),
+            }));
+        }),
+        button("Save…").on_click_stop(move |_| {
+            let markdown = markdown.clone();
+            floem::action::save_as(
+                floem::file::FileDialogOptions::new()
+                    .title("Save PR Description")
+                    .default_name("PR_DESCRIPTION.md"),
+                move |file: Option<floem::file::FileInfo>| {
+                    let Some(path) = file.and_then(|mut f| f.path.pop()) else {
+                        return;
+                    };
+                    status.set(Some(match std::fs::write(&path, &markdown) {
+                        Ok(()) => format!("Saved {}", path.display()),
+                        Err(e) => format!("Saving failed: {e}"),
+                    }));
+                },
+            );
+        }),
+        label(move || status.get().unwrap_or_default()).style(move |s| {
+            let config = config.get();
+            s.font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                .color(config.color(LapceColor::EDITOR_DIM))
+        }),
+    ))
+    .style(|s| s.items_center().margin_top(6.0));
+
+    container(
+        stack((
+            label(move || {
+                let chevron = if expanded.get() { "\u{25BC}" } else { "\u{25B6}" };
+                format!("{chevron} Summary: {title}")
+            })
+            .on_click_stop(move |_| expanded.update(|e| *e = !*e))
+            .style(move |s| {
+                let config = config.get();
+                s.font_bold()
+                    .min_width(0.0)
+                    .cursor(CursorStyle::Pointer)
+                    .color(config.color(LapceColor::PANEL_FOREGROUND))
+            }),
+            label(move || body.clone()).style(move |s| {
+                let config = config.get();
+                s.margin_top(6.0)
+                    .min_width(0.0)
+                    .selectable(true)
+                    .font_size((config.ui.font_size() as f32 - 1.0).max(11.0))
+                    .color(config.color(LapceColor::PANEL_FOREGROUND))
+                    .apply_if(!expanded.get(), |s| s.hide())
+            }),
+            actions,
+        ))
+        .style(|s| s.flex_col().width_pct(100.0)),
+    )
+    .style(move |s| {
+        let config = config.get();
+        s.padding(8.0)
+            .margin_horiz(8.0)
+            .margin_vert(4.0)
+            .min_width(0.0)
+            .border(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .background(config.color(LapceColor::PANEL_BACKGROUND).multiply_alpha(0.5))
+    })
+}
+
 /// View for a single plan step.
 fn plan_step_view(
     config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
@@ -2283,6 +2801,7 @@ fn server_tool_call_view(
         ToolCallStatus::Accepted => "\u{2713}", // same as success
         ToolCallStatus::Error => "\u{2717}",
         ToolCallStatus::Rejected => "\u{2718}",
+        ToolCallStatus::Conflict => "\u{26A0}",
     };
 
     let tool_name = tc.name.clone();
@@ -2363,6 +2882,7 @@ fn chat_input_area(
     let is_loading = chat_data.is_loading;
     let is_recording = chat_data.is_recording;
     let attached_images = chat_data.attached_images;
+    let attached_files = chat_data.attached_files;
     let editor = chat_data.editor.clone();
 
     let is_focused =
@@ -2379,6 +2899,7 @@ fn chat_input_area(
     let chat_data_attach = chat_data.clone();
     let chat_data_preview = chat_data.clone();
     let chat_data_paste = chat_data.clone();
+    let chat_data_files = chat_data.clone();
 
     // ── Image preview strip (shown above input when images are attached) ──
     let image_preview = dyn_stack(
@@ -2440,6 +2961,64 @@ fn chat_input_area(
             .apply_if(!has_images, |s| s.hide())
     });
 
+    // ── File attachment strip (logs, CSVs, PDFs, ...) ──
+    let file_preview = dyn_stack(
+        move || {
+            let files = attached_files.get();
+            files.into_iter().enumerate().collect::<Vec<_>>()
+        },
+        |item: &(usize, lapce_rpc::proxy::AttachedFileData)| (item.0, item.1.filename.clone()),
+        move |(idx, file)| {
+            let chat_data_rm = chat_data_files.clone();
+            let filename = file.filename.clone();
+            stack((
+                svg(move || config.get().ui_svg(LapceIcons::FILE)).style(move |s| {
+                    let config = config.get();
+                    s.size(14.0, 14.0)
+                        .margin_right(4.0)
+                        .color(config.color(LapceColor::EDITOR_FOREGROUND))
+                }),
+                label(move || filename.clone()).style(move |s| {
+                    let config = config.get();
+                    s.font_size(10.0)
+                        .color(config.color(LapceColor::EDITOR_FOREGROUND))
+                }),
+                label(|| "\u{2715}".to_string()) // ✕
+                    .on_click_stop(move |_| {
+                        chat_data_rm.remove_file(idx);
+                    })
+                    .style(move |s| {
+                        let config = config.get();
+                        s.font_size(10.0)
+                            .padding_horiz(4.0)
+                            .cursor(CursorStyle::Pointer)
+                            .color(config.color(LapceColor::EDITOR_DIM))
+                            .hover(|s| s.color(config.color(LapceColor::EDITOR_FOREGROUND)))
+                    }),
+            ))
+            .style(move |s| {
+                let config = config.get();
+                s.items_center()
+                    .padding(4.0)
+                    .margin_right(4.0)
+                    .margin_bottom(4.0)
+                    .border(1.0)
+                    .border_radius(4.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    .background(config.color(LapceColor::PANEL_BACKGROUND))
+            })
+        },
+    )
+    .style(move |s| {
+        let has_files = !attached_files.get().is_empty();
+        s.flex_row()
+            .flex_wrap(floem::taffy::style::FlexWrap::Wrap)
+            .padding(4.0)
+            .min_width(0.0)
+            .margin_horiz(8.0)
+            .apply_if(!has_files, |s| s.hide())
+    });
+
     // ── Input bar: [attach] [text input] [mic] ──
     // Enter sends message, so no Send button needed
     let input_bar = stack((
@@ -2448,28 +3027,18 @@ fn chat_input_area(
             r#"<svg xmlns="http://www.w3.org/2000/svg" width="512" height="512" viewBox="0 0 512 512"><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-miterlimit="10" stroke-width="32" d="M216.08 192v143.85a40.08 40.08 0 0 0 80.15 0l.13-188.55a67.94 67.94 0 1 0-135.87 0v189.82a95.51 95.51 0 1 0 191 0V159.74"/></svg>"#.to_string()
         })
         .on_click_stop(move |_| {
-            // Open file picker for images
+            // Open file picker for images and other files
             use floem::action::open_file;
             use floem::file::FileDialogOptions;
-            use base64::{Engine as _, engine::general_purpose::STANDARD};
             
             let chat_data = chat_data_attach.clone();
             let options = FileDialogOptions::new()
-                .title("Attach Image");
+                .title("Attach File");
             
             open_file(options, move |file_info| {
-                if let Some(mut file) = file_info {
-                    // Read the file and add as base64
-                    if let Some(path) = file.path.pop() {
-                        if let Ok(data) = std::fs::read(&path) {
-                            let mime = if path.extension().and_then(|e| e.to_str()) == Some("png") {
-                                "image/png"
-                            } else {
-                                "image/jpeg"
-                            };
-                            let base64_data = STANDARD.encode(&data);
-                            chat_data.add_image(base64_data, mime.to_string());
-                        }
+                if let Some(file) = file_info {
+                    for path in &file.path {
+                        chat_data.add_file(path);
                     }
                 }
             });
@@ -2596,7 +3165,7 @@ fn chat_input_area(
     });
 
     // Stack: image previews on top, input bar below
-    stack((image_preview, input_bar))
+    stack((image_preview, file_preview, input_bar))
         .style(|s| s.flex_col().width_pct(100.0))
 }
 
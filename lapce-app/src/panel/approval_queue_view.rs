@@ -0,0 +1,180 @@
+use std::rc::Rc;
+
+use floem::{
+    View,
+    reactive::{SignalGet, SignalWith},
+    style::CursorStyle,
+    views::{Decorators, container, dyn_stack, label, rich_text, scroll, stack},
+};
+
+use super::{ai_chat_view::create_diff_text_layout, position::PanelPosition};
+use crate::{
+    ai_chat::{AiChatData, QueuedApproval},
+    config::color::LapceColor,
+    window_tab::WindowTabData,
+};
+
+/// Tool calls waiting for the user's approval, oldest first, each with its
+/// diff or command and Approve / Reject. The oldest one is also answered by
+/// the `forge_approve_next` / `forge_reject_next` keyboard shortcuts.
+pub fn approval_queue_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let chat_data = window_tab_data.ai_chat.clone();
+    let queue = chat_data.approval_queue;
+    let chat_data_all = chat_data.clone();
+
+    let toolbar = stack((
+        label(move || match queue.with(|q| q.len()) {
+            0 => "No tool calls waiting for approval".to_string(),
+            1 => "1 tool call waiting for approval".to_string(),
+            n => format!("{n} tool calls waiting for approval"),
+        })
+        .style(|s| s.flex_grow(1.0).min_width(0.0).text_ellipsis()),
+        label(|| "Approve All".to_string())
+            .on_click_stop(move |_| chat_data_all.approve_all_queued())
+            .style(move |s| {
+                let config = config.get();
+                s.padding_horiz(10.0)
+                    .padding_vert(2.0)
+                    .border(1.0)
+                    .border_radius(4.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    .cursor(CursorStyle::Pointer)
+                    .hover(|s| {
+                        s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+                    })
+                    .apply_if(queue.with(|q| q.len() < 2), |s| s.hide())
+            }),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.items_center()
+            .width_pct(100.0)
+            .padding_horiz(10.0)
+            .padding_vert(6.0)
+            .border_bottom(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .color(config.color(LapceColor::PANEL_FOREGROUND))
+    });
+
+    let list = scroll(
+        dyn_stack(
+            move || queue.get().into_iter().enumerate(),
+            // Rebuilt when a row becomes the next one, for its note
+            |(index, item)| (*index == 0, item.approval.tool_call_id.clone()),
+            move |(index, item)| approval_row(config, chat_data.clone(), item, index == 0),
+        )
+        .style(|s| s.flex_col().width_pct(100.0)),
+    )
+    .style(|s| s.flex_grow(1.0).min_height(0.0).width_pct(100.0));
+
+    stack((toolbar, list))
+        .style(|s| s.flex_col().size_pct(100.0, 100.0))
+        .debug_name("Approval Queue Panel")
+}
+
+fn approval_row(
+    config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
+    chat_data: AiChatData,
+    item: QueuedApproval,
+    is_next: bool,
+) -> impl View {
+    let approval = item.approval;
+    let id_approve = approval.tool_call_id.clone();
+    let id_reject = approval.tool_call_id.clone();
+    let chat_data_reject = chat_data.clone();
+    let kind = approval.kind.clone();
+    let summary = approval.summary.clone();
+    let note = match (item.timeout_note, is_next) {
+        (Some(note), true) => format!("{note} · next for the approve/reject shortcuts"),
+        (Some(note), false) => note,
+        (None, true) => "Next for the approve/reject shortcuts".to_string(),
+        (None, false) => String::new(),
+    };
+    let has_note = !note.is_empty();
+    let preview = approval.preview.clone();
+    let has_preview = !preview.is_empty();
+
+    let button = move |text: &'static str, primary: bool| {
+        label(move || text.to_string()).style(move |s| {
+            let config = config.get();
+            s.padding_horiz(12.0)
+                .padding_vert(2.0)
+                .margin_left(6.0)
+                .border_radius(4.0)
+                .cursor(CursorStyle::Pointer)
+                .apply_if(primary, |s| {
+                    s.font_bold()
+                        .color(config.color(LapceColor::PANEL_BACKGROUND))
+                        .background(config.color(LapceColor::LAPCE_ICON_ACTIVE))
+                })
+                .apply_if(!primary, |s| {
+                    s.border(1.0)
+                        .border_color(config.color(LapceColor::LAPCE_BORDER))
+                        .hover(|s| {
+                            s.background(
+                                config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            )
+                        })
+                })
+        })
+    };
+
+    stack((
+        stack((
+            label(move || kind.clone()).style(move |s| {
+                let config = config.get();
+                s.padding_horiz(6.0)
+                    .margin_right(8.0)
+                    .border(1.0)
+                    .border_radius(4.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    .color(config.color(LapceColor::EDITOR_DIM))
+            }),
+            label(move || summary.clone())
+                .style(|s| s.flex_grow(1.0).min_width(0.0).text_ellipsis()),
+            button("Approve", true)
+                .on_click_stop(move |_| chat_data.answer_approval(&id_approve, true)),
+            button("Reject", false).on_click_stop(move |_| {
+                chat_data_reject.answer_approval(&id_reject, false)
+            }),
+        ))
+        .style(|s| s.items_center().width_pct(100.0)),
+        label(move || note.clone()).style(move |s| {
+            let config = config.get();
+            s.font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                .margin_top(2.0)
+                .color(config.color(LapceColor::EDITOR_DIM))
+                .apply_if(!has_note, |s| s.hide())
+        }),
+        container(
+            scroll(
+                rich_text(move || create_diff_text_layout(&preview, &config.get()))
+                    .style(|s| s.selectable(true)),
+            )
+            .style(|s| s.width_pct(100.0).max_height(240.0)),
+        )
+        .style(move |s| {
+            let config = config.get();
+            s.width_pct(100.0)
+                .margin_top(4.0)
+                .padding(6.0)
+                .border_radius(4.0)
+                .background(config.color(LapceColor::EDITOR_BACKGROUND))
+                .apply_if(!has_preview, |s| s.hide())
+        }),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.flex_col()
+            .width_pct(100.0)
+            .padding_horiz(10.0)
+            .padding_vert(6.0)
+            .border_bottom(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .color(config.color(LapceColor::PANEL_FOREGROUND))
+    })
+}
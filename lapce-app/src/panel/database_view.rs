@@ -13,13 +13,17 @@ use floem::{
 };
 
 use crate::{
+    command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons},
     database::{ConnectionState, DatabaseViewData, DbViewMode},
+    editor::location::{EditorLocation, EditorPosition},
+    listener::Listener,
     main_split::Editors,
     window_tab::CommonData,
 };
 
 use lapce_rpc::db::{DbConnectionConfig, DbQueryResult, DbType};
+use lsp_types::Position;
 
 /// Main Database Manager view -- full TablePlus-like interface
 pub fn database_manager_view(
@@ -100,11 +104,12 @@ fn connection_sidebar(db_data: DatabaseViewData, common: Rc<CommonData>) -> impl
             scroll(
                 {
                     let db = db_data.clone();
+                    let internal_command = common.internal_command;
                     dyn_stack(
                         move || connections.get(),
                         move |conn: &ConnectionState| conn.config.id.clone(),
                         move |conn: ConnectionState| {
-                            connection_tree_item(conn, db.clone(), config)
+                            connection_tree_item(conn, db.clone(), config, internal_command)
                         },
                     )
                     .style(|s| s.flex_col().width_full())
@@ -130,6 +135,7 @@ fn connection_tree_item(
     conn: ConnectionState,
     db_data: DatabaseViewData,
     config: floem::reactive::ReadSignal<std::sync::Arc<crate::config::LapceConfig>>,
+    internal_command: Listener<InternalCommand>,
 ) -> impl View {
     let conn_id = conn.config.id.clone();
     let conn_name = conn.config.name.clone();
@@ -252,39 +258,72 @@ fn connection_tree_item(
                         let tname = table_info.name.clone();
                         let ttype = table_info.table_type.clone();
                         let row_count = table_info.row_count;
+                        let source = table_info.source.clone();
+                        let has_source = source.is_some();
 
-                        label(move || {
-                            let count_str = row_count
-                                .map(|c| format!(" ({})", c))
-                                .unwrap_or_default();
-                            format!("  {} {}{}", 
-                                if ttype == "collection" { "📁" } else { "📄" },
-                                tname, count_str)
-                        })
-                        .style(move |s| {
-                            let config = config.get();
-                            s.width_full()
-                                .padding_vert(3.0)
-                                .padding_left(32.0)
-                                .padding_right(8.0)
-                                .font_size(config.ui.font_size() as f32 * 0.9)
-                                .color(config.color(LapceColor::EDITOR_FOREGROUND))
-                                .cursor(CursorStyle::Pointer)
-                                .text_ellipsis()
-                                .hover(|s| {
-                                    s.background(
-                                        config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
-                                    )
+                        stack((
+                            label(move || {
+                                let count_str = row_count
+                                    .map(|c| format!(" ({})", c))
+                                    .unwrap_or_default();
+                                format!("  {} {}{}", 
+                                    if ttype == "collection" { "📁" } else { "📄" },
+                                    tname, count_str)
+                            })
+                            .style(move |s| {
+                                let config = config.get();
+                                s.flex_grow(1.0)
+                                    .min_width(0.0)
+                                    .padding_vert(3.0)
+                                    .padding_left(32.0)
+                                    .padding_right(8.0)
+                                    .font_size(config.ui.font_size() as f32 * 0.9)
+                                    .color(config.color(LapceColor::EDITOR_FOREGROUND))
+                                    .cursor(CursorStyle::Pointer)
+                                    .text_ellipsis()
+                                    .hover(|s| {
+                                        s.background(
+                                            config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                                        )
+                                    })
+                            })
+                            .on_click_stop({
+                                let db = db.clone();
+                                let cid = cid.clone();
+                                let tname = table_info.name.clone();
+                                move |_| {
+                                    db.load_table_data(cid.clone(), tname.clone());
+                                }
+                            }),
+                            // Jump to the migration that creates the table
+                            label(|| "↗")
+                                .on_click_stop(move |_| {
+                                    if let Some(source) = source.as_ref() {
+                                        internal_command.send(InternalCommand::JumpToLocation {
+                                            location: EditorLocation {
+                                                path: source.path.clone(),
+                                                position: Some(EditorPosition::Position(Position::new(
+                                                    source.line.saturating_sub(1),
+                                                    0,
+                                                ))),
+                                                scroll_offset: None,
+                                                ignore_unconfirmed: false,
+                                                same_editor_tab: false,
+                                            },
+                                        });
+                                    }
                                 })
-                        })
-                        .on_click_stop({
-                            let db = db.clone();
-                            let cid = cid.clone();
-                            let tname = table_info.name.clone();
-                            move |_| {
-                                db.load_table_data(cid.clone(), tname.clone());
-                            }
-                        })
+                                .style(move |s| {
+                                    let config = config.get();
+                                    s.padding_horiz(8.0)
+                                        .font_size(config.ui.font_size() as f32 * 0.9)
+                                        .color(config.color(LapceColor::EDITOR_DIM))
+                                        .cursor(CursorStyle::Pointer)
+                                        .apply_if(!has_source, |s| s.hide())
+                                        .hover(|s| s.color(config.color(LapceColor::EDITOR_FOREGROUND)))
+                                }),
+                        ))
+                        .style(|s| s.flex_row().width_full().items_center())
                     },
                 )
                 .style(|s| s.flex_col().width_full())
@@ -25,6 +25,9 @@ pub enum PanelKind {
     AiChat,
     ProjectMap,
     ProjectMapPage,
+    ApprovalQueue,
+    Usage,
+    WorkingNotes,
 }
 
 impl PanelKind {
@@ -47,6 +50,9 @@ impl PanelKind {
             PanelKind::AiChat => LapceIcons::AI_CHAT,
             PanelKind::ProjectMap => LapceIcons::SEARCH,
             PanelKind::ProjectMapPage => LapceIcons::SEARCH,
+            PanelKind::ApprovalQueue => LapceIcons::WARNING,
+            PanelKind::Usage => LapceIcons::HISTORY,
+            PanelKind::WorkingNotes => LapceIcons::LIGHTBULB,
         }
     }
 
@@ -79,6 +85,9 @@ impl PanelKind {
             PanelKind::AiChat => PanelPosition::RightTop,
             PanelKind::ProjectMap => PanelPosition::RightTop,
             PanelKind::ProjectMapPage => PanelPosition::LeftTop,
+            PanelKind::ApprovalQueue => PanelPosition::BottomLeft,
+            PanelKind::Usage => PanelPosition::BottomLeft,
+            PanelKind::WorkingNotes => PanelPosition::RightBottom,
         }
     }
 }
@@ -1,4 +1,5 @@
 pub mod ai_chat_view;
+pub mod approval_queue_view;
 pub mod call_hierarchy_view;
 pub mod data;
 pub mod database_view;
@@ -17,4 +18,6 @@ pub mod sdk_view;
 pub mod source_control_view;
 pub mod style;
 pub mod terminal_view;
+pub mod usage_view;
 pub mod view;
+pub mod working_notes_view;
@@ -22,9 +22,8 @@ pub fn project_map_panel(
     window_tab_data: Rc<WindowTabData>,
     _position: PanelPosition,
 ) -> impl View {
-    let workspace_id = window_tab_data.workspace.path.as_ref()
-        .and_then(|p| p.file_name())
-        .map(|n| n.to_string_lossy().to_string())
+    let workspace_id = window_tab_data.workspace.path.as_deref()
+        .map(forge_agent::forge_search::workspace_id)
         .unwrap_or_else(|| "default".to_string());
     
     let fs_client = forge_agent::forge_search::client();
@@ -0,0 +1,251 @@
+use std::rc::Rc;
+
+use chrono::{Local, TimeZone};
+use floem::{
+    View,
+    reactive::{ReadSignal, SignalGet, SignalWith},
+    style::CursorStyle,
+    views::{Decorators, container, dyn_stack, empty, label, scroll, stack},
+};
+use lapce_rpc::proxy::{UsageSummary, UsageTotals};
+
+use super::position::PanelPosition;
+use crate::{config::LapceConfig, config::color::LapceColor, window_tab::WindowTabData};
+
+type Config = ReadSignal<std::sync::Arc<LapceConfig>>;
+
+/// Token usage and cost from the proxy's ledger: today and this
+/// conversation against their budgets, the last 30 days by provider, and a
+/// cost per conversation.
+pub fn usage_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let chat_data = window_tab_data.ai_chat.clone();
+    let usage = chat_data.usage;
+
+    let toolbar = stack((
+        label(move || {
+            usage.with(|u| match u {
+                Some(u) if u.conversations.is_empty() => "No usage recorded yet".to_string(),
+                Some(u) => format!("Usage of {} conversations", u.conversations.len()),
+                None => "Loading usage…".to_string(),
+            })
+        })
+        .style(|s| s.flex_grow(1.0).min_width(0.0).text_ellipsis()),
+        label(|| "Refresh".to_string())
+            .on_click_stop(move |_| chat_data.refresh_usage())
+            .style(move |s| {
+                let config = config.get();
+                s.padding_horiz(10.0)
+                    .padding_vert(2.0)
+                    .border(1.0)
+                    .border_radius(4.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    .cursor(CursorStyle::Pointer)
+                    .hover(|s| {
+                        s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+                    })
+            }),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.items_center()
+            .width_pct(100.0)
+            .padding_horiz(10.0)
+            .padding_vert(6.0)
+            .border_bottom(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .color(config.color(LapceColor::PANEL_FOREGROUND))
+    });
+
+    let providers = dyn_stack(
+        move || {
+            usage.with(|u| u.as_ref().map(|u| u.providers.clone()).unwrap_or_default())
+        },
+        |p| (p.provider.clone(), p.totals.requests),
+        move |p| usage_row(config, p.provider, None, &p.totals),
+    )
+    .style(|s| s.flex_col().width_pct(100.0));
+
+    let conversations = dyn_stack(
+        move || {
+            usage.with(|u| u.as_ref().map(|u| u.conversations.clone()).unwrap_or_default())
+        },
+        |c| (c.conversation_id.clone(), c.totals.requests),
+        move |c| {
+            let title = c.title.unwrap_or_else(|| c.conversation_id.clone());
+            let last_used = Local
+                .timestamp_opt(c.last_used, 0)
+                .single()
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string());
+            usage_row(config, title, last_used, &c.totals)
+        },
+    )
+    .style(|s| s.flex_col().width_pct(100.0));
+
+    let body = stack((
+        budget_row(config, usage, "Today", |u| (&u.today, u.daily_budget_usd)),
+        budget_row(config, usage, "This conversation", |u| {
+            (&u.session, u.session_budget_usd)
+        }),
+        section_header(config, "By provider (last 30 days)"),
+        providers,
+        section_header(config, "Conversations"),
+        conversations,
+    ))
+    .style(|s| s.flex_col().width_pct(100.0));
+
+    stack((
+        toolbar,
+        scroll(body).style(|s| s.flex_grow(1.0).min_height(0.0).width_pct(100.0)),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0))
+    .debug_name("Usage Panel")
+}
+
+fn cost(totals: &UsageTotals) -> String {
+    let cost = format!("${:.2}", totals.cost_usd);
+    match totals.unpriced {
+        0 => cost,
+        n => format!("{cost} (+{n} unpriced)"),
+    }
+}
+
+fn tokens(totals: &UsageTotals) -> String {
+    let count = |t: u64| {
+        if t >= 1_000_000 {
+            format!("{:.1}M", t as f64 / 1_000_000.0)
+        } else if t >= 1000 {
+            format!("{:.1}k", t as f64 / 1000.0)
+        } else {
+            t.to_string()
+        }
+    };
+    format!(
+        "{} in · {} out",
+        count(totals.input_tokens),
+        count(totals.output_tokens)
+    )
+}
+
+/// Totals with a bar filling up to the budget, in the warning color once
+/// it's spent.
+fn budget_row(
+    config: Config,
+    usage: floem::reactive::RwSignal<Option<UsageSummary>>,
+    name: &'static str,
+    pick: fn(&UsageSummary) -> (&UsageTotals, Option<f64>),
+) -> impl View {
+    let spent = move || {
+        usage.with(|u| {
+            u.as_ref().map(|u| {
+                let (totals, budget) = pick(u);
+                (totals.clone(), budget)
+            })
+        })
+    };
+    let text = move || match spent() {
+        Some((totals, Some(budget))) => format!(
+            "{name}: {} of ${budget:.2} · {} · {} requests",
+            cost(&totals),
+            tokens(&totals),
+            totals.requests
+        ),
+        Some((totals, None)) => format!(
+            "{name}: {} · {} · {} requests",
+            cost(&totals),
+            tokens(&totals),
+            totals.requests
+        ),
+        None => format!("{name}: …"),
+    };
+    let fraction = move || match spent() {
+        Some((totals, Some(budget))) => Some(totals.cost_usd / budget),
+        _ => None,
+    };
+
+    stack((
+        label(text).style(|s| s.width_pct(100.0).text_ellipsis()),
+        container(empty().style(move |s| {
+            let config = config.get();
+            let fraction = fraction().unwrap_or(0.0);
+            let color = if fraction >= 1.0 {
+                LapceColor::LAPCE_WARN
+            } else {
+                LapceColor::LAPCE_ICON_ACTIVE
+            };
+            s.height_full()
+                .width_pct(fraction.clamp(0.0, 1.0) * 100.0)
+                .border_radius(2.0)
+                .background(config.color(color))
+        }))
+        .style(move |s| {
+            let config = config.get();
+            s.width_pct(100.0)
+                .height(4.0)
+                .margin_top(4.0)
+                .border_radius(2.0)
+                .background(config.color(LapceColor::EDITOR_BACKGROUND))
+                .apply_if(fraction().is_none(), |s| s.hide())
+        }),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.flex_col()
+            .width_pct(100.0)
+            .padding_horiz(10.0)
+            .padding_vert(6.0)
+            .border_bottom(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .color(config.color(LapceColor::PANEL_FOREGROUND))
+    })
+}
+
+fn section_header(config: Config, text: &'static str) -> impl View {
+    label(move || text.to_string()).style(move |s| {
+        let config = config.get();
+        s.width_pct(100.0)
+            .padding_horiz(10.0)
+            .padding_top(10.0)
+            .padding_bottom(4.0)
+            .font_bold()
+            .color(config.color(LapceColor::EDITOR_DIM))
+    })
+}
+
+fn usage_row(
+    config: Config,
+    name: String,
+    detail: Option<String>,
+    totals: &UsageTotals,
+) -> impl View {
+    let has_detail = detail.is_some();
+    let detail = detail.unwrap_or_default();
+    let tokens = tokens(totals);
+    let cost = cost(totals);
+    stack((
+        label(move || name.clone())
+            .style(|s| s.flex_grow(1.0).flex_basis(0.0).min_width(0.0).text_ellipsis()),
+        label(move || detail.clone()).style(move |s| {
+            s.margin_left(12.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+                .apply_if(!has_detail, |s| s.hide())
+        }),
+        label(move || tokens.clone()).style(move |s| {
+            s.margin_left(12.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+        }),
+        label(move || cost.clone()).style(|s| s.margin_left(12.0).min_width(70.0)),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.items_center()
+            .width_pct(100.0)
+            .padding_horiz(10.0)
+            .padding_vert(3.0)
+            .color(config.color(LapceColor::PANEL_FOREGROUND))
+            .hover(|s| s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND)))
+    })
+}
@@ -18,6 +18,7 @@ use floem::{
 
 use super::{
     ai_chat_view::ai_chat_panel,
+    approval_queue_view::approval_queue_panel,
     debug_view::debug_panel,
     git_log_view::git_log_panel,
     global_search_view::global_search_panel,
@@ -29,6 +30,8 @@ use super::{
     sdk_view::sdk_panel,
     source_control_view::source_control_panel,
     terminal_view::terminal_panel,
+    usage_view::usage_panel,
+    working_notes_view::working_notes_panel,
 };
 use crate::{
     app::{clickable_icon, clickable_icon_base},
@@ -617,6 +620,39 @@ fn panel_view(
                 PanelKind::ProjectMap => {
                     project_map_panel(window_tab_data.clone(), position).into_any()
                 }
+                PanelKind::ApprovalQueue => {
+                    if is_bottom {
+                        bottom_panel_with_header(
+                            window_tab_data.clone(),
+                            kind,
+                            approval_queue_panel(window_tab_data.clone(), position),
+                        ).into_any()
+                    } else {
+                        approval_queue_panel(window_tab_data.clone(), position).into_any()
+                    }
+                }
+                PanelKind::Usage => {
+                    if is_bottom {
+                        bottom_panel_with_header(
+                            window_tab_data.clone(),
+                            kind,
+                            usage_panel(window_tab_data.clone(), position),
+                        ).into_any()
+                    } else {
+                        usage_panel(window_tab_data.clone(), position).into_any()
+                    }
+                }
+                PanelKind::WorkingNotes => {
+                    if is_bottom {
+                        bottom_panel_with_header(
+                            window_tab_data.clone(),
+                            kind,
+                            working_notes_panel(window_tab_data.clone(), position),
+                        ).into_any()
+                    } else {
+                        working_notes_panel(window_tab_data.clone(), position).into_any()
+                    }
+                }
             };
             view.style(|s| s.size_pct(100.0, 100.0))
         },
@@ -662,6 +698,9 @@ fn bottom_panel_with_header(
         PanelKind::Implementation => "Implementation",
         PanelKind::AiChat => "Forge AI",
         PanelKind::ProjectMap => "Project Map",
+        PanelKind::ApprovalQueue => "Approvals",
+        PanelKind::Usage => "Usage & Cost",
+        PanelKind::WorkingNotes => "Working Notes",
         _ => "Panel",
     };
     let icon = kind.svg_name();
@@ -752,6 +791,9 @@ fn panel_icon_button(
         PanelKind::AiChat => "Forge AI",
         PanelKind::ProjectMap => "Project Map",
         PanelKind::ProjectMapPage => "Project Map Page",
+        PanelKind::ApprovalQueue => "Approvals",
+        PanelKind::Usage => "Usage & Cost",
+        PanelKind::WorkingNotes => "Working Notes",
     };
     let icon = p.svg_name();
     let is_active = {
@@ -0,0 +1,161 @@
+use std::rc::Rc;
+
+use chrono::{Local, TimeZone};
+use floem::{
+    View,
+    reactive::{ReadSignal, RwSignal, SignalGet, SignalUpdate, SignalWith, create_rw_signal},
+    style::CursorStyle,
+    views::{Decorators, dyn_stack, label, scroll, stack},
+};
+use lapce_rpc::core::AgentNote;
+
+use super::position::PanelPosition;
+use crate::{config::LapceConfig, config::color::LapceColor, window_tab::WindowTabData};
+
+type Config = ReadSignal<std::sync::Arc<LapceConfig>>;
+
+/// Sections of the panel: note kind, heading, collapsed at first.
+const SECTIONS: [(&str, &str, bool); 4] = [
+    ("decision", "Decisions", false),
+    ("assumption", "Assumptions", false),
+    ("question", "Open questions", false),
+    ("thought", "Thoughts", true),
+];
+
+/// The agent's working notes for this conversation (decisions, assumptions,
+/// open questions, thoughts), one collapsible section per kind.
+pub fn working_notes_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let chat_data = window_tab_data.ai_chat.clone();
+    let notes = chat_data.working_notes;
+
+    let toolbar = stack((
+        label(move || match notes.with(|n| n.len()) {
+            0 => "No working notes yet".to_string(),
+            1 => "1 note".to_string(),
+            n => format!("{n} notes"),
+        })
+        .style(|s| s.flex_grow(1.0).min_width(0.0).text_ellipsis()),
+        label(|| "Refresh".to_string())
+            .on_click_stop(move |_| chat_data.refresh_working_notes())
+            .style(move |s| {
+                let config = config.get();
+                s.padding_horiz(10.0)
+                    .padding_vert(2.0)
+                    .border(1.0)
+                    .border_radius(4.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    .cursor(CursorStyle::Pointer)
+                    .hover(|s| {
+                        s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+                    })
+            }),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.items_center()
+            .width_pct(100.0)
+            .padding_horiz(10.0)
+            .padding_vert(6.0)
+            .border_bottom(1.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .color(config.color(LapceColor::PANEL_FOREGROUND))
+    });
+
+    let sections = SECTIONS.map(|(kind, heading, collapsed)| {
+        section(config, notes, kind, heading, create_rw_signal(collapsed))
+    });
+    let [decisions, assumptions, questions, thoughts] = sections;
+
+    stack((
+        toolbar,
+        scroll(
+            stack((decisions, assumptions, questions, thoughts))
+                .style(|s| s.flex_col().width_pct(100.0)),
+        )
+        .style(|s| s.flex_grow(1.0).min_height(0.0).width_pct(100.0)),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0))
+    .debug_name("Working Notes Panel")
+}
+
+fn section(
+    config: Config,
+    notes: RwSignal<im::Vector<AgentNote>>,
+    kind: &'static str,
+    heading: &'static str,
+    collapsed: RwSignal<bool>,
+) -> impl View {
+    let count = move || notes.with(|n| n.iter().filter(|note| note.kind == kind).count());
+
+    stack((
+        label(move || {
+            let chevron = if collapsed.get() { "\u{25B6}" } else { "\u{25BC}" };
+            format!("{chevron} {heading} ({})", count())
+        })
+        .on_click_stop(move |_| collapsed.update(|c| *c = !*c))
+        .style(move |s| {
+            let config = config.get();
+            s.width_pct(100.0)
+                .padding_horiz(10.0)
+                .padding_vert(4.0)
+                .font_bold()
+                .cursor(CursorStyle::Pointer)
+                .color(config.color(LapceColor::EDITOR_DIM))
+                .hover(|s| s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND)))
+        }),
+        dyn_stack(
+            move || {
+                notes.with(|n| {
+                    n.iter()
+                        .filter(|note| note.kind == kind)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                })
+            },
+            |note| (note.at, note.text.clone()),
+            move |note| note_row(config, note),
+        )
+        .style(move |s| {
+            s.flex_col()
+                .width_pct(100.0)
+                .apply_if(collapsed.get(), |s| s.hide())
+        }),
+    ))
+    .style(move |s| {
+        s.flex_col()
+            .width_pct(100.0)
+            .apply_if(count() == 0, |s| s.hide())
+    })
+}
+
+fn note_row(config: Config, note: AgentNote) -> impl View {
+    let time = Local
+        .timestamp_opt(note.at, 0)
+        .single()
+        .map(|t| t.format("%H:%M").to_string())
+        .unwrap_or_default();
+    let text = note.text;
+    stack((
+        label(move || time.clone()).style(move |s| {
+            let config = config.get();
+            s.margin_right(8.0)
+                .font_size((config.ui.font_size() as f32 - 2.0).max(10.0))
+                .color(config.color(LapceColor::EDITOR_DIM))
+        }),
+        label(move || text.clone())
+            .style(|s| s.flex_grow(1.0).flex_basis(0.0).min_width(0.0).selectable(true)),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.items_start()
+            .width_pct(100.0)
+            .padding_left(24.0)
+            .padding_right(10.0)
+            .padding_vert(3.0)
+            .color(config.color(LapceColor::PANEL_FOREGROUND))
+    })
+}
@@ -1,11 +1,11 @@
 //! Run Configuration data management
 
-use std::rc::Rc;
+use std::{collections::HashMap, rc::Rc};
 
 use floem::reactive::{RwSignal, Scope, SignalGet, SignalUpdate};
 use lapce_rpc::{
     dap_types::RunDebugConfig,
-    proxy::{DetectedRunConfig, ProxyResponse},
+    proxy::{DetectedRunConfig, ProxyResponse, RunHistoryEntry},
 };
 
 use crate::window_tab::CommonData;
@@ -20,6 +20,8 @@ pub struct RunConfigData {
     pub detected_configs: RwSignal<Vec<DetectedRunConfig>>,
     /// User-defined configurations from .lapce/run.toml
     pub user_configs: RwSignal<Vec<RunDebugConfig>>,
+    /// Recently launched configurations, newest first
+    pub history: RwSignal<Vec<RunHistoryEntry>>,
     /// Whether configs are currently loading
     pub loading: RwSignal<bool>,
     /// Error message if any
@@ -35,6 +37,7 @@ impl RunConfigData {
             selected: scope.create_rw_signal(None),
             detected_configs: scope.create_rw_signal(Vec::new()),
             user_configs: scope.create_rw_signal(Vec::new()),
+            history: scope.create_rw_signal(Vec::new()),
             loading: scope.create_rw_signal(false),
             error: scope.create_rw_signal(None),
             dropdown_visible: scope.create_rw_signal(false),
@@ -54,6 +57,8 @@ impl RunConfigData {
                 command: config.command.clone(),
                 args: config.args.clone(),
                 cwd: config.cwd.clone(),
+                env: None,
+                env_file: None,
             });
         }
         
@@ -66,6 +71,8 @@ impl RunConfigData {
                 command: config.program.clone(),
                 args: config.args.clone().unwrap_or_default(),
                 cwd: config.cwd.clone(),
+                env: config.env.clone(),
+                env_file: config.env_file.clone(),
             });
         }
         
@@ -103,6 +110,8 @@ pub struct RunConfigItem {
     pub command: String,
     pub args: Vec<String>,
     pub cwd: Option<String>,
+    pub env: Option<HashMap<String, String>>,
+    pub env_file: Option<String>,
 }
 
 impl RunConfigItem {
@@ -114,8 +123,10 @@ impl RunConfigItem {
             program: self.command.clone(),
             args: Some(self.args.clone()),
             cwd: self.cwd.clone(),
-            env: None,
+            env: self.env.clone(),
+            env_file: self.env_file.clone(),
             prelaunch: None,
+            depends_on: None,
             debug_command: None,
             dap_id: Default::default(),
             tracing_output: false,
@@ -124,6 +135,27 @@ impl RunConfigItem {
     }
 }
 
+/// Format env vars for the editor's single-line field: `KEY=value; KEY2=value2`.
+pub fn format_env_field(env: Option<&HashMap<String, String>>) -> String {
+    let Some(env) = env else {
+        return String::new();
+    };
+    let mut pairs: Vec<String> = env.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    pairs.sort();
+    pairs.join("; ")
+}
+
+/// Parse the editor's env field back into a map (`None` when empty).
+pub fn parse_env_field(field: &str) -> Option<HashMap<String, String>> {
+    let env: HashMap<String, String> = field
+        .split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .filter(|(k, _)| !k.is_empty())
+        .collect();
+    if env.is_empty() { None } else { Some(env) }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ConfigSource {
     Detected,
@@ -175,4 +207,12 @@ pub fn fetch_run_configs(
     tracing::info!("fetch_run_configs: Calling proxy.get_run_configs");
     common.proxy.get_run_configs(send);
     tracing::info!("fetch_run_configs: RPC call initiated");
+    
+    let history = data.history;
+    let send = create_ext_action(scope, move |result: Result<ProxyResponse, _>| {
+        if let Ok(ProxyResponse::RunHistoryResponse { entries }) = result {
+            history.set(entries);
+        }
+    });
+    common.proxy.get_run_history(send);
 }
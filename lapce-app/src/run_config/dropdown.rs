@@ -6,16 +6,20 @@ use floem::{
     View,
     event::EventListener,
     peniko::kurbo::{Point, Size},
-    reactive::{ReadSignal, RwSignal, SignalGet, SignalUpdate, create_effect},
+    ext_event::create_ext_action,
+    reactive::{ReadSignal, RwSignal, SignalGet, SignalUpdate, SignalWith, create_effect},
     style::{CursorStyle, Display},
     views::{Decorators, container, dyn_stack, empty, label, scroll, stack, svg},
     action::{add_overlay, remove_overlay},
     ViewId,
 };
 
+use lapce_rpc::proxy::{ProxyResponse, RunHistoryEntry};
+
 use crate::{
     app::clickable_icon,
-    command::LapceWorkbenchCommand,
+    command::{InternalCommand, LapceWorkbenchCommand},
+    debug::RunDebugMode,
     config::{LapceConfig, color::LapceColor, icon::LapceIcons},
     listener::Listener,
     window_tab::WindowTabData,
@@ -29,6 +33,7 @@ fn run_config_dropdown_overlay(
     run_config_data: RunConfigData,
     workbench_command: Listener<LapceWorkbenchCommand>,
     on_run: Rc<dyn Fn(super::RunConfigItem)>,
+    on_rerun: Rc<dyn Fn(RunHistoryEntry)>,
 ) -> impl View {
     let selected = run_config_data.selected;
     let dropdown_visible = run_config_data.dropdown_visible;
@@ -131,6 +136,9 @@ fn run_config_dropdown_overlay(
             )
             .style(|s| s.width_full().max_height(300.0)),
             
+            // Recent runs (newest first)
+            recent_runs_section(config, run_config_data.clone(), on_rerun),
+            
             // Separator
             empty().style(move |s| {
                 let cfg = config.get();
@@ -184,6 +192,96 @@ fn run_config_dropdown_overlay(
     })
 }
 
+/// Max recent runs listed in the dropdown.
+const MAX_RECENT_RUNS: usize = 5;
+
+/// "Recent" section listing the last launched configs with their exit status
+fn recent_runs_section(
+    config: ReadSignal<Arc<LapceConfig>>,
+    run_config_data: RunConfigData,
+    on_rerun: Rc<dyn Fn(RunHistoryEntry)>,
+) -> impl View {
+    let history = run_config_data.history;
+    let dropdown_visible = run_config_data.dropdown_visible;
+    
+    stack((
+        label(|| "Recent".to_string())
+            .style(move |s| {
+                let cfg = config.get();
+                s.padding_horiz(12.0)
+                    .padding_top(8.0)
+                    .padding_bottom(4.0)
+                    .font_size((cfg.ui.font_size() - 1) as f32)
+                    .font_bold()
+                    .color(cfg.color(LapceColor::PANEL_FOREGROUND_DIM))
+            }),
+        dyn_stack(
+            move || {
+                history.get().into_iter().take(MAX_RECENT_RUNS).collect::<Vec<_>>()
+            },
+            |entry| (entry.config.name.clone(), entry.mode.clone(), entry.started_at),
+            move |entry| {
+                let on_rerun = on_rerun.clone();
+                let status = match entry.exit_code {
+                    None => String::new(),
+                    Some(0) => "ok".to_string(),
+                    Some(code) => format!("exit {}", code),
+                };
+                let failed = entry.exit_code.is_some_and(|c| c != 0);
+                let title = if entry.mode == "debug" {
+                    format!("Debug {}", entry.config.name)
+                } else {
+                    entry.config.name.clone()
+                };
+                container(
+                    stack((
+                        label(move || title.clone())
+                            .style(move |s| {
+                                let cfg = config.get();
+                                s.flex_grow(1.0)
+                                    .font_size(cfg.ui.font_size() as f32)
+                                    .color(cfg.color(LapceColor::PANEL_FOREGROUND))
+                                    .text_ellipsis()
+                            }),
+                        label(move || status.clone())
+                            .style(move |s| {
+                                let cfg = config.get();
+                                s.font_size((cfg.ui.font_size() - 2) as f32)
+                                    .margin_left(8.0)
+                                    .color(if failed {
+                                        cfg.color(LapceColor::LAPCE_ERROR)
+                                    } else {
+                                        cfg.color(LapceColor::PANEL_FOREGROUND_DIM)
+                                    })
+                            }),
+                    ))
+                    .style(|s| s.items_center().width_full()),
+                )
+                .on_click_stop(move |_| {
+                    dropdown_visible.set(false);
+                    on_rerun(entry.clone());
+                })
+                .style(move |s| {
+                    let cfg = config.get();
+                    s.padding_horiz(12.0)
+                        .padding_vert(6.0)
+                        .width_full()
+                        .hover(|s| {
+                            s.cursor(CursorStyle::Pointer)
+                                .background(cfg.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+                        })
+                })
+            },
+        )
+        .style(|s| s.flex_col().width_full()),
+    ))
+    .style(move |s| {
+        s.flex_col()
+            .width_full()
+            .display(if history.with(|h| h.is_empty()) { Display::None } else { Display::Flex })
+    })
+}
+
 /// Create the run configuration dropdown widget for the title bar
 pub fn run_config_dropdown(
     window_tab_data: Rc<WindowTabData>,
@@ -203,16 +301,30 @@ pub fn run_config_dropdown(
     let selected = run_config_data.selected;
     
     // Run action callback - opens a new terminal and runs the command
-    let common_for_run = common.clone();
+    let internal_command = common.internal_command;
     let on_run: Rc<dyn Fn(super::RunConfigItem)> = Rc::new(move |item: super::RunConfigItem| {
-        // Run command via proxy terminal
-        let cmd = if item.args.is_empty() {
-            item.command.clone()
-        } else {
-            format!("{} {}", item.command, item.args.join(" "))
-        };
-        tracing::info!("Running: {}", cmd);
-        // TODO: Create terminal with command - for now just log
+        tracing::info!("Running: {} {}", item.command, item.args.join(" "));
+        internal_command.send(InternalCommand::RunAndDebug {
+            mode: RunDebugMode::Run,
+            config: item.to_run_debug_config(),
+        });
+    });
+    
+    // Re-run a history entry with the exact config and mode it was launched with
+    let history = run_config_data.history;
+    let common_for_history = common.clone();
+    let on_rerun: Rc<dyn Fn(RunHistoryEntry)> = Rc::new(move |entry: RunHistoryEntry| {
+        internal_command.send(InternalCommand::RunAndDebug {
+            mode: RunDebugMode::from_history(&entry.mode),
+            config: entry.config,
+        });
+        // Refresh so the entry moves to the top
+        let send = create_ext_action(scope, move |result: Result<ProxyResponse, _>| {
+            if let Ok(ProxyResponse::RunHistoryResponse { entries }) = result {
+                history.set(entries);
+            }
+        });
+        common_for_history.proxy.get_run_history(send);
     });
     
     // Fetch configs on mount
@@ -221,6 +333,7 @@ pub fn run_config_dropdown(
     // Manage overlay visibility
     let run_config_data_for_overlay = run_config_data.clone();
     let on_run_for_overlay = on_run.clone();
+    let on_rerun_for_overlay = on_rerun.clone();
     create_effect(move |_| {
         if dropdown_visible.get() {
             let origin = button_origin.get();
@@ -229,12 +342,14 @@ pub fn run_config_dropdown(
             
             let data_clone = run_config_data_for_overlay.clone();
             let on_run_clone = on_run_for_overlay.clone();
+            let on_rerun_clone = on_rerun_for_overlay.clone();
             let id = add_overlay(point, move |_| {
                 run_config_dropdown_overlay(
                     config,
                     data_clone.clone(),
                     workbench_command,
                     on_run_clone.clone(),
+                    on_rerun_clone.clone(),
                 )
             });
             overlay_id.set(Some(id));
@@ -10,6 +10,9 @@ pub mod data;
 pub mod dropdown;
 pub mod view;
 
-pub use data::{RunConfigData, RunConfigItem, ConfigSource, fetch_run_configs};
+pub use data::{
+    RunConfigData, RunConfigItem, ConfigSource, fetch_run_configs, format_env_field,
+    parse_env_field,
+};
 pub use dropdown::run_config_dropdown;
 pub use view::run_config_editor_view;
@@ -27,7 +27,9 @@ use crate::{
     window_tab::CommonData,
 };
 
-use super::{RunConfigData, ConfigSource, fetch_run_configs};
+use super::{
+    RunConfigData, ConfigSource, fetch_run_configs, format_env_field, parse_env_field,
+};
 
 /// Create the run configuration editor view (opens as an editor tab)
 pub fn run_config_editor_view(
@@ -52,6 +54,8 @@ pub fn run_config_editor_view(
     let edit_command: RwSignal<String> = scope.create_rw_signal(String::new());
     let edit_args: RwSignal<String> = scope.create_rw_signal(String::new());
     let edit_cwd: RwSignal<String> = scope.create_rw_signal(String::new());
+    let edit_env: RwSignal<String> = scope.create_rw_signal(String::new());
+    let edit_env_file: RwSignal<String> = scope.create_rw_signal(String::new());
     let edit_type: RwSignal<String> = scope.create_rw_signal(String::new());
     let is_user_config: RwSignal<bool> = scope.create_rw_signal(false);
     let save_message: RwSignal<Option<String>> = scope.create_rw_signal(None);
@@ -92,6 +96,8 @@ pub fn run_config_editor_view(
                     edit_command,
                     edit_args,
                     edit_cwd,
+                    edit_env,
+                    edit_env_file,
                     edit_type,
                     is_user_config,
                     common.clone(),
@@ -113,6 +119,8 @@ pub fn run_config_editor_view(
                     edit_command,
                     edit_args,
                     edit_cwd,
+                    edit_env,
+                    edit_env_file,
                     edit_type,
                     is_user_config,
                     save_message,
@@ -141,6 +149,8 @@ fn left_panel(
     edit_command: RwSignal<String>,
     edit_args: RwSignal<String>,
     edit_cwd: RwSignal<String>,
+    edit_env: RwSignal<String>,
+    edit_env_file: RwSignal<String>,
     edit_type: RwSignal<String>,
     is_user_config: RwSignal<bool>,
     common: Rc<CommonData>,
@@ -164,6 +174,8 @@ fn left_panel(
                                 edit_command.set(String::new());
                                 edit_args.set(String::new());
                                 edit_cwd.set("${workspace}".to_string());
+                                edit_env.set(String::new());
+                                edit_env_file.set(String::new());
                                 edit_type.set("custom".to_string());
                                 is_user_config.set(true);
                             }
@@ -221,6 +233,8 @@ fn left_panel(
                         edit_command,
                         edit_args,
                         edit_cwd,
+                        edit_env,
+                        edit_env_file,
                         edit_type,
                         is_user_config,
                         true,
@@ -236,6 +250,8 @@ fn left_panel(
                         edit_command,
                         edit_args,
                         edit_cwd,
+                        edit_env,
+                        edit_env_file,
                         edit_type,
                         is_user_config,
                         false,
@@ -265,6 +281,8 @@ fn config_section(
     edit_command: RwSignal<String>,
     edit_args: RwSignal<String>,
     edit_cwd: RwSignal<String>,
+    edit_env: RwSignal<String>,
+    edit_env_file: RwSignal<String>,
     edit_type: RwSignal<String>,
     is_user_config: RwSignal<bool>,
     is_detected: bool,
@@ -333,6 +351,8 @@ fn config_section(
                         edit_command.set(item_for_click.command.clone());
                         edit_args.set(item_for_click.args.join(" "));
                         edit_cwd.set(item_for_click.cwd.clone().unwrap_or_default());
+                        edit_env.set(format_env_field(item_for_click.env.as_ref()));
+                        edit_env_file.set(item_for_click.env_file.clone().unwrap_or_default());
                         edit_type.set(item_for_click.config_type.clone());
                         is_user_config.set(item_for_click.source == ConfigSource::User);
                     }
@@ -369,6 +389,8 @@ fn right_panel(
     edit_command: RwSignal<String>,
     edit_args: RwSignal<String>,
     edit_cwd: RwSignal<String>,
+    edit_env: RwSignal<String>,
+    edit_env_file: RwSignal<String>,
     edit_type: RwSignal<String>,
     is_user_config: RwSignal<bool>,
     save_message: RwSignal<Option<String>>,
@@ -430,6 +452,7 @@ fn right_panel(
                                     .map(String::from)
                                     .collect();
                                 let cwd = edit_cwd.get();
+                                let env_file = edit_env_file.get();
                                 let config = lapce_rpc::dap_types::RunDebugConfig {
                                     ty: None,
                                     name: name.clone(),
@@ -443,8 +466,10 @@ fn right_panel(
                                         },
                                     ]),
                                     cwd: if cwd.is_empty() { None } else { Some(cwd) },
-                                    env: None,
+                                    env: parse_env_field(&edit_env.get()),
+                                    env_file: if env_file.is_empty() { None } else { Some(env_file) },
                                     prelaunch: None,
+                                    depends_on: None,
                                     debug_command: None,
                                     dap_id: lapce_rpc::dap_types::DapId::next(),
                                     tracing_output: false,
@@ -483,6 +508,12 @@ fn right_panel(
                         // Working Directory field
                         form_field(config, "Working Directory", edit_cwd, !is_user_config.get()),
                         
+                        // Environment variables (KEY=value; KEY2=value2)
+                        form_field(config, "Environment", edit_env, !is_user_config.get()),
+                        
+                        // Env file, relative to the working directory
+                        form_field(config, "Env File", edit_env_file, !is_user_config.get()),
+                        
                         // Save message
                         label(move || save_message.get().unwrap_or_default())
                             .style(move |s| {
@@ -513,8 +544,10 @@ fn right_panel(
                                         program: edit_command.get(),
                                         args: Some(edit_args.get().split_whitespace().map(String::from).collect()),
                                         cwd: Some(edit_cwd.get()),
-                                        env: None,
+                                        env: parse_env_field(&edit_env.get()),
+                                        env_file: Some(edit_env_file.get()).filter(|f| !f.is_empty()),
                                         prelaunch: None,
+                                        depends_on: None,
                                         debug_command: None,
                                         dap_id: Default::default(),
                                         tracing_output: false,
@@ -67,6 +67,10 @@ pub fn status(
     };
 
     let progresses = window_tab_data.progresses;
+    let agent_progress = window_tab_data.ai_chat.progress;
+    let chat_panel = window_tab_data.panel.clone();
+    let approval_queue = window_tab_data.ai_chat.approval_queue;
+    let window_tab_data_for_approvals = window_tab_data.clone();
     let window_tab_data_for_click = window_tab_data.clone();
     let mode = create_memo(move |_| window_tab_data.mode());
     let pointer_down = floem::reactive::create_rw_signal(false);
@@ -262,6 +266,12 @@ pub fn status(
         }),
         // Panel toggle icons moved to title bar
         progress_loader_view(config, progresses),
+        agent_progress_view(config, agent_progress).on_click_stop(move |_| {
+            chat_panel.show_panel(&PanelKind::AiChat);
+        }),
+        approval_queue_view(config, approval_queue).on_click_stop(move |_| {
+            window_tab_data_for_approvals.show_approval_queue();
+        }),
         stack({
             let palette_clone = palette.clone();
             let cursor_info = status_text(config, editor, move || {
@@ -400,6 +410,67 @@ fn progress_loader_view(
     })
 }
 
+/// The running agent request's status; clicking it opens the chat.
+fn agent_progress_view(
+    config: ReadSignal<Arc<LapceConfig>>,
+    progress: RwSignal<Option<lapce_rpc::core::AgentRunProgress>>,
+) -> impl View {
+    label(move || {
+        progress
+            .get()
+            .map(|p| format!("Forge: {}", crate::ai_chat::progress_line(&p)))
+            .unwrap_or_default()
+    })
+    .style(move |s| {
+        let config = config.get();
+        let display = if progress.with(|p| p.is_some()) {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        s.display(display)
+            .height_pct(100.0)
+            .max_width(420.0)
+            .items_center()
+            .padding_horiz(10.0)
+            .text_ellipsis()
+            .selectable(false)
+            .color(config.color(LapceColor::STATUS_FOREGROUND))
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer)
+                    .background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+            })
+    })
+}
+
+fn approval_queue_view(
+    config: ReadSignal<Arc<LapceConfig>>,
+    queue: RwSignal<im::Vector<crate::ai_chat::QueuedApproval>>,
+) -> impl View {
+    label(move || match queue.with(|q| q.len()) {
+        1 => "1 approval pending".to_string(),
+        n => format!("{n} approvals pending"),
+    })
+    .style(move |s| {
+        let config = config.get();
+        let display = if queue.with(|q| q.is_empty()) {
+            Display::None
+        } else {
+            Display::Flex
+        };
+        s.display(display)
+            .height_pct(100.0)
+            .items_center()
+            .padding_horiz(10.0)
+            .selectable(false)
+            .color(config.color(LapceColor::LAPCE_WARN))
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer)
+                    .background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+            })
+    })
+}
+
 fn status_text<S: std::fmt::Display + 'static>(
     config: ReadSignal<Arc<LapceConfig>>,
     editor: Memo<Option<EditorData>>,
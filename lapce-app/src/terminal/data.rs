@@ -20,7 +20,7 @@ use lapce_core::{
     register::Clipboard,
 };
 use lapce_rpc::{
-    dap_types::RunDebugConfig,
+    dap_types::{RunDebugConfig, RunVariables},
     terminal::{TermId, TerminalProfile},
 };
 use parking_lot::RwLock;
@@ -404,6 +404,10 @@ impl TerminalData {
             None
         });
 
+        if let Some(run_debug) = run_debug {
+            profile.run_config = Some(run_debug.config.name.clone());
+        }
+
         if let Some(run_debug) = exp_run_debug {
             if let Some(work_dir) = run_debug.work_dir {
                 profile.workdir = Some(work_dir);
@@ -773,6 +777,15 @@ impl ExpandedRunDebug {
         run_debug: &RunDebugConfig,
         is_prelaunch: bool,
     ) -> anyhow::Result<Self> {
+        // Placeholders are normally resolved at launch; this covers configs
+        // re-read from run.toml (e.g. on restart). ${env:*} is left to the proxy.
+        let mut run_debug = run_debug.clone();
+        run_debug.substitute_variables(&RunVariables {
+            workspace: workspace.path.clone(),
+            ..Default::default()
+        });
+        let run_debug = &run_debug;
+
         // Get the current working directory variable, which can container ${workspace}
         let work_dir = Self::expand_work_dir(workspace, run_debug);
 
@@ -780,10 +793,16 @@ impl ExpandedRunDebug {
             .then_some(run_debug.prelaunch.as_ref())
             .flatten();
 
-        let env = run_debug.env.clone();
+        // env_file is relative to the working directory, or the workspace
+        let env_base = work_dir
+            .as_ref()
+            .and_then(|u| u.to_file_path().ok())
+            .or_else(|| workspace.path.clone());
+        let env = run_debug
+            .resolved_env(env_base.as_deref())
+            .map_err(|e| anyhow!(e))?;
 
-        // TODO: replace some variables in the args
-        let (program, mut args) =
+        let (program, args) =
             if let Some(debug_command) = run_debug.debug_command.as_ref() {
                 let mut args = debug_command.to_owned();
                 let command = args.first().cloned().unwrap_or_default();
@@ -798,7 +817,7 @@ impl ExpandedRunDebug {
             } else {
                 (run_debug.program.clone(), run_debug.args.clone())
             };
-        let mut program = if program == "${lapce}" {
+        let program = if program == "${lapce}" {
             std::env::current_exe()
                 .map_err(|e| {
                     anyhow!(
@@ -812,24 +831,13 @@ impl ExpandedRunDebug {
             program
         };
 
-        if program.contains("${workspace}") {
-            if let Some(workspace) = workspace.path.as_ref().and_then(|x| x.to_str())
-            {
-                program = program.replace("${workspace}", workspace);
-            }
-        }
-
-        if let Some(args) = &mut args {
-            for arg in args {
-                // Replace all mentions of ${workspace} with the current workspace path
-                if arg.contains("${workspace}") {
-                    if let Some(workspace) =
-                        workspace.path.as_ref().and_then(|x| x.to_str())
-                    {
-                        *arg = arg.replace("${workspace}", workspace);
-                    }
-                }
-            }
+        if let Some(unresolved) = std::iter::once(&program)
+            .chain(args.iter().flatten())
+            .find(|s| s.contains("${input:"))
+        {
+            return Err(anyhow!(
+                "no value for input in '{unresolved}', add it under [[inputs]] in .lapce/run.toml"
+            ));
         }
 
         Ok(ExpandedRunDebug {
@@ -406,6 +406,14 @@ impl TerminalPanelData {
                         }
                     })
                     .unwrap();
+                if was_prelaunch == Some(false) {
+                    if let Some(name) = terminal
+                        .run_debug
+                        .with_untracked(|r| r.as_ref().map(|r| r.config.name.clone()))
+                    {
+                        self.common.proxy.run_exited(name, exit_code);
+                    }
+                }
                 let exit_code = exit_code.unwrap_or(0);
                 if was_prelaunch == Some(true) && exit_code == 0 {
                     let run_debug = terminal.run_debug.get_untracked();
@@ -473,7 +481,7 @@ impl TerminalPanelData {
             if let Some(new_config) =
                 self.get_run_config_by_name(&run_debug.config.name)
             {
-                run_debug.config = new_config;
+                run_debug.config = self.resolve_run_config(new_config);
             }
         }
         let mut is_debug = false;
@@ -517,23 +525,63 @@ impl TerminalPanelData {
     }
 
     fn get_run_config_by_name(&self, name: &str) -> Option<RunDebugConfig> {
-        if let Some(workspace) = self.common.workspace.path.as_deref() {
-            let run_toml = workspace.join(".lapce").join("run.toml");
-            let (doc, new_doc) = self.main_split.get_doc(run_toml.clone(), None);
-            if !new_doc {
-                let content = doc.buffer.with_untracked(|b| b.to_string());
-                match toml::from_str::<RunDebugConfigs>(&content) {
-                    Ok(configs) => {
-                        return configs.configs.into_iter().find(|x| x.name == name);
-                    }
-                    Err(err) => {
-                        // todo show message window
-                        tracing::error!("deser fail {:?}", err);
-                    }
-                }
+        self.run_configs()?.find(name).cloned()
+    }
+
+    /// The configs and compounds in `.lapce/run.toml`, preferring the open
+    /// (possibly unsaved) buffer over the file on disk.
+    pub fn run_configs(&self) -> Option<RunDebugConfigs> {
+        let workspace = self.common.workspace.path.as_deref()?;
+        let run_toml = workspace.join(".lapce").join("run.toml");
+        let (doc, new_doc) = self.main_split.get_doc(run_toml.clone(), None);
+        let content = if new_doc {
+            std::fs::read_to_string(&run_toml).ok()?
+        } else {
+            doc.buffer.with_untracked(|b| b.to_string())
+        };
+        match toml::from_str::<RunDebugConfigs>(&content) {
+            Ok(configs) => Some(configs),
+            Err(err) => {
+                // todo show message window
+                tracing::error!("deser fail {:?}", err);
+                None
             }
         }
-        None
+    }
+
+    /// Expand a config's `depends_on` into its prelaunch step.
+    pub fn resolve_run_config(&self, config: RunDebugConfig) -> RunDebugConfig {
+        if config.depends_on.as_ref().is_none_or(|d| d.is_empty()) {
+            return config;
+        }
+        let Some(configs) = self.run_configs() else {
+            return config;
+        };
+        match configs.resolve_depends_on(&config) {
+            Ok(resolved) => resolved,
+            Err(err) => {
+                tracing::error!("{err}");
+                config
+            }
+        }
+    }
+
+    /// Terminals currently running one of the named configs.
+    pub fn running_terminals_for(&self, names: &[String]) -> Vec<TermId> {
+        self.tab_info.with_untracked(|info| {
+            info.tabs
+                .iter()
+                .flat_map(|(_, tab)| tab.terminals.get_untracked())
+                .filter(|(_, terminal)| {
+                    terminal.run_debug.with_untracked(|run_debug| {
+                        run_debug.as_ref().is_some_and(|r| {
+                            !r.stopped && names.contains(&r.config.name)
+                        })
+                    })
+                })
+                .map(|(_, terminal)| terminal.term_id)
+                .collect()
+        })
     }
 
     pub fn focus_terminal(&self, term_id: TermId) {
@@ -7,7 +7,7 @@ use std::{
         Arc,
         mpsc::{Sender, channel},
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use alacritty_terminal::vte::ansi::Handler;
@@ -37,10 +37,10 @@ use lapce_core::{
 use lapce_rpc::{
     RpcError,
     core::CoreNotification,
-    dap_types::{ConfigSource, RunDebugConfig},
+    dap_types::{ConfigSource, RunDebugConfig, RunVariables},
     file::{Naming, PathObject},
     plugin::PluginId,
-    proxy::{ProxyResponse, ProxyRpcHandler, ProxyStatus},
+    proxy::{ProxyResponse, ProxyRpcHandler, ProxyStatus, RunHistoryEntry},
     source_control::{FileDiff, GitCheckoutStatus},
     terminal::TermId,
 };
@@ -104,6 +104,7 @@ pub enum Focus {
     Rename,
     AboutPopup,
     SearchPopup,
+    Canvas,
     Panel(PanelKind),
 }
 
@@ -1199,6 +1200,41 @@ impl WindowTabData {
                     self.terminal.stop_run_debug(term_id);
                 }
             }
+            RunAndDebugRerunLast => {
+                let internal_command = self.common.internal_command;
+                let send = create_ext_action(self.scope, move |result| {
+                    if let Ok(ProxyResponse::RunHistoryResponse { entries }) = result {
+                        if let Some(last) = entries.into_iter().next() {
+                            internal_command.send(InternalCommand::RunAndDebug {
+                                mode: RunDebugMode::from_history(&last.mode),
+                                config: last.config,
+                            });
+                        }
+                    }
+                });
+                self.common.proxy.get_run_history(send);
+            }
+            RunAndDebugPrevious => {
+                // The run and debug palette orders configs by when they were
+                // last executed, so seed that from the persisted history
+                let palette = self.palette.clone();
+                let send = create_ext_action(self.scope, move |result| {
+                    if let Ok(ProxyResponse::RunHistoryResponse { entries }) = result {
+                        let now = chrono::Utc::now().timestamp();
+                        let mut executed = palette.executed_run_configs.borrow_mut();
+                        for entry in entries {
+                            let age = Duration::from_secs((now - entry.started_at).max(0) as u64);
+                            if let Some(at) = Instant::now().checked_sub(age) {
+                                executed
+                                    .entry((RunDebugMode::from_history(&entry.mode), entry.config.name))
+                                    .or_insert(at);
+                            }
+                        }
+                    }
+                    palette.run(PaletteKind::RunAndDebug);
+                });
+                self.common.proxy.get_run_history(send);
+            }
 
             // ==== UI ====
             ZoomIn => {
@@ -1652,7 +1688,9 @@ impl WindowTabData {
                             args,
                             cwd: None,
                             env: None,
+                            env_file: None,
                             prelaunch: None,
+                            depends_on: None,
                             debug_command: None,
                             dap_id: Default::default(),
                             tracing_output: false,
@@ -1898,6 +1936,46 @@ impl WindowTabData {
             GitManageRemotes => {
                 // TODO: Show remotes management dialog
             }
+            ForgePushToTalk => {
+                // First press starts dictating into the chat input, the next
+                // one stops and transcribes.
+                if !self.ai_chat.recorder.is_recording() {
+                    self.panel.show_panel(&PanelKind::AiChat);
+                    self.common.focus.set(Focus::Panel(PanelKind::AiChat));
+                }
+                self.ai_chat.toggle_recording();
+            }
+            ForgeShowApprovalQueue => {
+                self.toggle_panel_visual_at_position(PanelKind::ApprovalQueue, PanelPosition::BottomLeft);
+            }
+            ForgeApproveNext => {
+                self.ai_chat.answer_next_approval(true);
+            }
+            ForgeRejectNext => {
+                self.ai_chat.answer_next_approval(false);
+            }
+            ForgeApproveAllQueued => {
+                self.ai_chat.approve_all_queued();
+            }
+            ForgeExplainRepository => {
+                self.ai_chat.explain_repository();
+            }
+            ForgeShowUsage => {
+                self.ai_chat.refresh_usage();
+                self.toggle_panel_visual_at_position(PanelKind::Usage, PanelPosition::BottomLeft);
+            }
+            ForgeShowWorkingNotes => {
+                self.ai_chat.refresh_working_notes();
+                self.toggle_panel_visual_at_position(PanelKind::WorkingNotes, PanelPosition::RightBottom);
+            }
+            ForgeRestoreDeletedFile => {
+                self.ai_chat.restore_deleted_file();
+            }
+            ForgeShowCanvas => {
+                if let Some(canvas) = self.ai_chat.canvas.canvas.get_untracked() {
+                    self.ai_chat.canvas.open(canvas);
+                }
+            }
 
         }
     }
@@ -2201,8 +2279,12 @@ impl WindowTabData {
                 self.terminal.split_exchange(term_id);
             }
             InternalCommand::RunAndDebug { mode, config } => {
+                let config = self.terminal.resolve_run_config(config);
                 self.run_and_debug(cx, &mode, &config);
             }
+            InternalCommand::RunCompound { name } => {
+                self.run_compound(cx, &name);
+            }
             InternalCommand::StartRename {
                 path,
                 placeholder,
@@ -2462,6 +2544,51 @@ impl WindowTabData {
         }
     }
 
+    /// After (re)connecting to the proxy, catch the chat panel up on the
+    /// agent turn it was following: replay the events it missed and stop
+    /// waiting if the turn ended or died with the old proxy.
+    fn resume_agent(&self) {
+        use crate::ai_chat::{ChatRole, new_message};
+        use lapce_rpc::proxy::{AgentTaskState, ProxyRequest};
+
+        let (conversation_id, last_seq) = match self.ai_chat.last_event.get_untracked() {
+            Some(last) => last,
+            None if self.ai_chat.is_loading.get_untracked() => {
+                (self.ai_chat.conversation_id.get_untracked(), 0)
+            }
+            None => return,
+        };
+        let window_tab = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            let Ok(ProxyResponse::AgentResumeResponse { status, events }) = result else {
+                return;
+            };
+            for event in &events {
+                window_tab.handle_core_notification(event);
+            }
+            let ai_chat = &window_tab.ai_chat;
+            match status.map(|s| s.state) {
+                Some(AgentTaskState::Running) => ai_chat.is_loading.set(true),
+                Some(AgentTaskState::Interrupted) if ai_chat.is_loading.get_untracked() => {
+                    ai_chat.streaming_text.set(String::new());
+                    ai_chat.has_first_token.set(false);
+                    ai_chat.is_loading.set(false);
+                    ai_chat.entries.update(|entries| {
+                        entries.push_back(new_message(
+                            ChatRole::System,
+                            "The agent stopped when the proxy restarted. Send a message to continue the task.".to_string(),
+                        ));
+                    });
+                }
+                _ => ai_chat.is_loading.set(false),
+            }
+        });
+        self.common.proxy.request_async(
+            ProxyRequest::AgentResume { conversation_id, last_seq },
+            send,
+        );
+    }
+
     fn handle_core_notification(&self, rpc: &CoreNotification) {
         let cx = self.scope;
         match rpc {
@@ -2483,6 +2610,33 @@ impl WindowTabData {
             }
             CoreNotification::ProxyStatus { status } => {
                 self.common.proxy_status.set(Some(status.to_owned()));
+                if matches!(status, ProxyStatus::Connected) {
+                    self.resume_agent();
+                }
+            }
+            CoreNotification::ProxyProtocol { protocol } => {
+                use lapce_rpc::version::{Compatibility, ProtocolVersion};
+                match protocol.compatibility() {
+                    Compatibility::Same => {}
+                    Compatibility::Older | Compatibility::Newer => {
+                        tracing::info!(
+                            "Proxy speaks {protocol}, app {}; features only one of them has won't work",
+                            ProtocolVersion::CURRENT
+                        );
+                    }
+                    Compatibility::Incompatible => {
+                        self.show_message(
+                            "Proxy version mismatch",
+                            &ShowMessageParams {
+                                typ: lsp_types::MessageType::ERROR,
+                                message: format!(
+                                    "The proxy speaks {protocol} and this app {}; reinstall the proxy that matches the app.",
+                                    ProtocolVersion::CURRENT
+                                ),
+                            },
+                        );
+                    }
+                }
             }
             CoreNotification::DiffInfo { diff } => {
                 self.source_control.branch.set(diff.head.clone());
@@ -2761,6 +2915,17 @@ impl WindowTabData {
             CoreNotification::WorkspaceFileChange => {
                 self.file_explorer.reload();
             }
+            CoreNotification::AgentEvent { conversation_id, seq, event } => {
+                let seen = self.ai_chat.last_event.with_untracked(|last| {
+                    last.as_ref().is_some_and(|(id, last)| id == conversation_id && seq <= last)
+                });
+                // Already shown before a resume replayed it
+                if seen {
+                    return;
+                }
+                self.ai_chat.last_event.set(Some((conversation_id.clone(), *seq)));
+                self.handle_core_notification(event);
+            }
             CoreNotification::AgentTextChunk { text, done } => {
                 use crate::ai_chat::{ChatRole, new_message};
                 if !text.is_empty() {
@@ -2841,6 +3006,7 @@ impl WindowTabData {
                     "awaiting_review" => ToolCallStatus::AwaitingReview,
                     "rejected" => ToolCallStatus::Rejected,
                     "accepted" => ToolCallStatus::Accepted,
+                    "conflict" => ToolCallStatus::Conflict,
                     _ => ToolCallStatus::Pending,
                 };
 
@@ -3004,6 +3170,64 @@ impl WindowTabData {
                 // All diffs for this turn have been sent
                 tracing::info!("All agent diffs received");
             }
+            CoreNotification::AgentTurnChanges { files } => {
+                tracing::info!("Agent turn changed {} file(s)", files.len());
+                self.ai_diffs.set_turn_changes(files.clone());
+            }
+            CoreNotification::AgentTaskSummary { title, body } => {
+                use crate::ai_chat::{ChatTaskSummary, new_task_summary};
+                self.ai_chat.entries.update(|entries| {
+                    entries.push_back(new_task_summary(ChatTaskSummary {
+                        title: title.clone(),
+                        body: body.clone(),
+                    }));
+                });
+                self.ai_chat.request_scroll_to_bottom();
+            }
+            CoreNotification::AgentWorkspaceChanges { files } => {
+                self.ai_chat.external_changes.set(files.clone());
+            }
+            CoreNotification::AgentOpenInEditor { path, line, column, action } => {
+                use lapce_rpc::core::AgentEditorAction;
+                match action {
+                    AgentEditorAction::Open => {
+                        let position = line.map(|line| {
+                            EditorPosition::Position(lsp_types::Position {
+                                line: line.saturating_sub(1),
+                                character: column.unwrap_or(1).saturating_sub(1),
+                            })
+                        });
+                        self.common.internal_command.send(InternalCommand::JumpToLocation {
+                            location: EditorLocation {
+                                path: path.clone(),
+                                position,
+                                scroll_offset: None,
+                                ignore_unconfirmed: false,
+                                same_editor_tab: false,
+                            },
+                        });
+                    }
+                    AgentEditorAction::Reveal => {
+                        self.show_panel(PanelKind::FileExplorer);
+                        self.panel.section_open(PanelSection::FileExplorer).set(true);
+                        self.file_explorer.reveal_in_file_tree(path.clone());
+                    }
+                    AgentEditorAction::Diff => {
+                        self.common
+                            .internal_command
+                            .send(InternalCommand::OpenFileChanges { path: path.clone() });
+                    }
+                }
+            }
+            CoreNotification::AgentBufferEdit { path, content } => {
+                let doc = self
+                    .main_split
+                    .docs
+                    .with_untracked(|docs| docs.get(path).cloned());
+                if let Some(doc) = doc {
+                    doc.apply_agent_edit(content);
+                }
+            }
             CoreNotification::AiInlineCompletionResponse {
                 request_id: _,
                 items: _,
@@ -3015,6 +3239,82 @@ impl WindowTabData {
                 // Update the AI chat index status and progress
                 self.ai_chat.index_status.set(status.clone());
                 self.ai_chat.index_progress.set(*progress);
+                if *progress < 0.0 {
+                    self.ai_chat.fetch_index_details();
+                }
+            }
+            CoreNotification::AgentFollowupQuestion { tool_call_id, question, options, default } => {
+                use crate::ai_chat::{ChatFollowup, ChatRole, new_followup, new_message};
+                // The question follows whatever the agent wrote before it
+                let pending_text = self.ai_chat.streaming_text.get_untracked();
+                self.ai_chat.entries.update(|entries| {
+                    if !pending_text.is_empty() {
+                        entries.push_back(new_message(ChatRole::Assistant, pending_text));
+                    }
+                    entries.push_back(new_followup(ChatFollowup {
+                        tool_call_id: tool_call_id.clone(),
+                        question: question.clone(),
+                        options: options.clone(),
+                        default: default.clone(),
+                        answer: None,
+                    }));
+                });
+                self.ai_chat.streaming_text.set(String::new());
+                self.ai_chat.has_first_token.set(true);
+                self.ai_chat.request_scroll_to_bottom();
+            }
+            CoreNotification::AgentProgress { progress } => {
+                self.ai_chat.progress.set(progress.clone());
+                // A run ended: its usage is in the ledger now
+                if progress.is_none() {
+                    self.ai_chat.refresh_usage();
+                }
+            }
+            CoreNotification::AgentWorkingNotes { conversation_id, notes } => {
+                if *conversation_id == self.ai_chat.conversation_id.get_untracked() {
+                    self.ai_chat.working_notes.set(notes.iter().cloned().collect());
+                }
+            }
+            CoreNotification::AgentShowCanvas { canvas, open } => {
+                if *open {
+                    self.ai_chat.canvas.open(canvas.clone());
+                } else {
+                    self.ai_chat.canvas.set(canvas.clone());
+                }
+            }
+            CoreNotification::AgentManagePlugin { action, volt } => {
+                use lapce_rpc::core::AgentPluginAction;
+                let id = volt.id();
+                match action {
+                    AgentPluginAction::Install => self.plugin.install_volt(volt.clone()),
+                    AgentPluginAction::Remove => {
+                        let meta = self.plugin.installed.with_untracked(|installed| {
+                            installed.get(&id).map(|v| v.meta.get_untracked())
+                        });
+                        if let Some(meta) = meta {
+                            self.plugin.uninstall_volt(meta);
+                        }
+                    }
+                    AgentPluginAction::Enable => {
+                        self.plugin.enable_volt(volt.clone());
+                        if self.plugin.workspace_disabled.with_untracked(|d| d.contains(&id)) {
+                            self.plugin.enable_volt_for_ws(volt.clone());
+                        }
+                    }
+                    AgentPluginAction::Disable => self.plugin.disable_volt(volt.clone()),
+                }
+            }
+            CoreNotification::AgentApprovalQueued { approval } => {
+                let queued = crate::ai_chat::QueuedApproval::new(approval.clone());
+                self.ai_chat.approval_queue.update(|queue| {
+                    queue.retain(|q| q.approval.tool_call_id != approval.tool_call_id);
+                    queue.push_back(queued);
+                });
+            }
+            CoreNotification::AgentApprovalResolved { tool_call_id } => {
+                self.ai_chat.approval_queue.update(|queue| {
+                    queue.retain(|q| &q.approval.tool_call_id != tool_call_id);
+                });
             }
             CoreNotification::AgentThinkingStep { step_type, message, detail } => {
                 // Add a thinking step to the thinking section
@@ -3095,7 +3395,7 @@ impl WindowTabData {
                     }
                 });
             }
-            CoreNotification::AgentRunProject { config_name, command, mode } => {
+            CoreNotification::AgentRunProject { config_name, command, mode, debug_config } => {
                 // Agent wants to run a project - execute through IDE's run system
                 use crate::debug::RunDebugMode;
                 use lapce_rpc::dap_types::{RunDebugConfig, DapId};
@@ -3107,24 +3407,43 @@ impl WindowTabData {
                     RunDebugMode::Run
                 };
                 
+                let run_configs = self.terminal.run_configs();
+                let compound = config_name.as_deref().and_then(|name| {
+                    run_configs.as_ref()?.find_compound(name).cloned()
+                });
+                let user_config = config_name.as_deref().and_then(|name| {
+                    run_configs.as_ref()?.find(name).cloned()
+                });
+                
                 // Create a config from either config_name or command
-                let config = if let Some(name) = config_name {
+                let config = if let Some(debug_config) = debug_config {
+                    // Auto-configured debug adapter launch from the proxy
+                    Some(debug_config)
+                } else if let Some(compound) = compound {
+                    // Compounds launch each member in its own terminal
+                    self.run_compound(cx, &compound.name);
+                    None
+                } else if let Some(user_config) = user_config {
+                    Some(self.terminal.resolve_run_config(user_config))
+                } else if let Some(name) = config_name {
                     // Try to parse the name as a command
                     // The name from detected configs should be something like "npm run dev" or "cargo run"
                     // We'll use sh -c to execute it as a shell command
-                    RunDebugConfig {
+                    Some(RunDebugConfig {
                         ty: None,
                         name: name.clone(),
                         program: "sh".to_string(),
                         args: Some(vec!["-c".to_string(), name.clone()]),
                         cwd: None,
                         env: None,
+                        env_file: None,
                         prelaunch: None,
+                        depends_on: None,
                         debug_command: None,
                         dap_id: DapId::next(),
                         tracing_output: false,
                         config_source: lapce_rpc::dap_types::ConfigSource::Palette,
-                    }
+                    })
                 } else if let Some(cmd) = command {
                     // Create a config from the command
                     let new_config = RunDebugConfig {
@@ -3134,7 +3453,9 @@ impl WindowTabData {
                         args: Some(vec!["-c".to_string(), cmd.clone()]),
                         cwd: None,
                         env: None,
+                        env_file: None,
                         prelaunch: None,
+                        depends_on: None,
                         debug_command: None,
                         dap_id: DapId::next(),
                         tracing_output: false,
@@ -3144,14 +3465,16 @@ impl WindowTabData {
                     // Save it to run.toml for future use
                     self.common.proxy.save_run_config(new_config.clone(), |_| {});
                     
-                    new_config
+                    Some(new_config)
                 } else {
                     // No config or command provided
                     return;
                 };
                 
                 // Execute through the IDE's run system
-                self.run_and_debug(cx, &run_mode, &config);
+                if let Some(config) = config {
+                    self.run_and_debug(cx, &run_mode, &config);
+                }
                 
                 // Always ensure Terminal is visible at the bottom panel
                 use crate::panel::position::PanelContainerPosition;
@@ -3179,12 +3502,26 @@ impl WindowTabData {
             }
             CoreNotification::AgentStopProject { config_name } => {
                 // Agent wants to stop a running project
-                // Find the most recent run/debug terminal and close it
-                if let Some(term_id) = self.terminal.debug.active_term.get_untracked() {
+                let Some(name) = config_name else {
+                    // No name: stop the most recent run/debug terminal
+                    if let Some(term_id) = self.terminal.debug.active_term.get_untracked() {
+                        self.common.proxy.terminal_close(term_id);
+                    }
+                    return;
+                };
+                // A compound stops all of its members as a unit
+                let names = self
+                    .terminal
+                    .run_configs()
+                    .and_then(|c| c.find_compound(&name).map(|g| g.configs.clone()))
+                    .unwrap_or_else(|| vec![name.clone()]);
+                let term_ids = self.terminal.running_terminals_for(&names);
+                if term_ids.is_empty() {
+                    tracing::warn!("AgentStopProject: nothing running for '{}'", name);
+                }
+                for term_id in term_ids {
                     self.common.proxy.terminal_close(term_id);
                 }
-                // If config_name was provided, we could search for specific terminal,
-                // but for now just stop the most recent one
             }
             _ => {}
         }
@@ -3205,6 +3542,7 @@ impl WindowTabData {
             }
             Focus::Rename => Some(keypress.key_down(event, &self.rename)),
             Focus::AboutPopup => Some(keypress.key_down(event, &self.about_data)),
+            Focus::Canvas => Some(keypress.key_down(event, &self.ai_chat.canvas)),
             Focus::Panel(PanelKind::Terminal) => {
                 self.terminal.key_down(event, &keypress)
             }
@@ -3560,6 +3898,13 @@ impl WindowTabData {
         }
     }
 
+    /// Show the approval queue at the bottom, leaving it open if it already is.
+    pub fn show_approval_queue(&self) {
+        if !self.panel.is_panel_visible(&PanelKind::ApprovalQueue) {
+            self.toggle_panel_visual_at_position(PanelKind::ApprovalQueue, PanelPosition::BottomLeft);
+        }
+    }
+
     /// Toggle a specific kind of panel.
     fn toggle_panel_focus(&self, kind: PanelKind) {
         let should_hide = match kind {
@@ -3575,7 +3920,10 @@ impl WindowTabData {
             | PanelKind::Implementation
             | PanelKind::AiChat
             | PanelKind::ProjectMap
-            | PanelKind::ProjectMapPage => {
+            | PanelKind::ProjectMapPage
+            | PanelKind::ApprovalQueue
+            | PanelKind::Usage
+            | PanelKind::WorkingNotes => {
                 // Some panels don't accept focus (yet). Fall back to visibility check
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
@@ -3676,7 +4024,22 @@ impl WindowTabData {
         mode: &RunDebugMode,
         config: &RunDebugConfig,
     ) {
+        let mut config = config.clone();
+        config.substitute_variables(&self.run_variables());
+        let config = &config;
         debug!("{:?}", config);
+
+        self.palette
+            .executed_run_configs
+            .borrow_mut()
+            .insert((*mode, config.name.clone()), Instant::now());
+        self.common.proxy.record_run(RunHistoryEntry {
+            config: config.clone(),
+            mode: mode.history_name().to_string(),
+            started_at: chrono::Utc::now().timestamp(),
+            exit_code: None,
+        });
+
         match mode {
             RunDebugMode::Run => {
                 self.run_in_terminal(cx, mode, config, false);
@@ -3697,6 +4060,58 @@ impl WindowTabData {
         }
     }
 
+    /// Placeholder values known to the UI: the workspace, the active file and
+    /// the `[[inputs]]` from run.toml. `${env:VAR}` is left for the proxy.
+    fn run_variables(&self) -> RunVariables {
+        let file = self.main_split.active_editor.get_untracked().and_then(|editor| {
+            match editor.doc().content.get_untracked() {
+                DocContent::File { path, .. } => Some(path),
+                _ => None,
+            }
+        });
+        let inputs = self
+            .terminal
+            .run_configs()
+            .map(|c| {
+                c.inputs
+                    .iter()
+                    .filter_map(|input| Some((input.id.clone(), input.value()?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        RunVariables {
+            workspace: self.workspace.path.clone(),
+            file,
+            inputs,
+            env: false,
+        }
+    }
+
+    /// Launch every member of a compound config in its own terminal.
+    fn run_compound(&self, cx: Scope, name: &str) {
+        let Some(configs) = self.terminal.run_configs() else {
+            return;
+        };
+        let Some(compound) = configs.find_compound(name) else {
+            tracing::warn!("No compound run config named '{}'", name);
+            return;
+        };
+        for member in &compound.configs {
+            let Some(config) = configs.find(member) else {
+                tracing::warn!("Compound '{}' references unknown config '{}'", name, member);
+                continue;
+            };
+            let config = match configs.resolve_depends_on(config) {
+                Ok(config) => config,
+                Err(err) => {
+                    tracing::error!("{err}");
+                    config.clone()
+                }
+            };
+            self.run_and_debug(cx, &RunDebugMode::Run, &config);
+        }
+    }
+
     fn run_in_terminal(
         &self,
         cx: Scope,
@@ -0,0 +1,133 @@
+//! Numbered, resumable agent chat events.
+//!
+//! Each agent turn sends its chat events through a [`CoreRpcHandler`] with
+//! an [`AgentStream`], which numbers them and keeps them in the
+//! conversation's [`EventLog`]. A chat panel that reconnects asks for the
+//! ones after the last number it saw (`AgentResume`) and for the turn's
+//! status (`AgentTaskStatus`); a turn the log says is running but this
+//! proxy isn't running died with a previous proxy, and is reported (and
+//! recorded) as interrupted.
+
+use std::{
+    collections::HashSet,
+    sync::Arc,
+};
+
+use forge_agent::event_log::{EventLog, TurnState, TurnStatus};
+use lapce_rpc::{
+    core::{AgentStream, CoreNotification, CoreRpcHandler},
+    proxy::{AgentTaskState, AgentTaskStatus},
+};
+use parking_lot::Mutex;
+
+/// Conversations with a turn running in this proxy.
+pub type RunningTurns = Arc<Mutex<HashSet<String>>>;
+
+/// Marks the turn done when dropped, however it ended.
+pub struct TurnGuard {
+    conversation_id: String,
+    running: RunningTurns,
+    log: Option<EventLog>,
+}
+
+impl Drop for TurnGuard {
+    fn drop(&mut self) {
+        self.running.lock().remove(&self.conversation_id);
+        if let Some(log) = &self.log {
+            if let Err(e) = log.finish(TurnState::Done) {
+                tracing::warn!("Recording the end of turn {}: {e}", self.conversation_id);
+            }
+        }
+    }
+}
+
+/// Start a turn of `conversation_id`: the handler its chat events should
+/// go through, and the guard that ends it.
+pub fn begin_turn(
+    core_rpc: &CoreRpcHandler,
+    running: &RunningTurns,
+    conversation_id: &str,
+    prompt: &str,
+) -> (CoreRpcHandler, TurnGuard) {
+    running.lock().insert(conversation_id.to_string());
+    let log = EventLog::open(conversation_id);
+    let first_seq = log.as_ref().map(|log| log.begin_turn(prompt)).transpose().unwrap_or_else(|e| {
+        tracing::warn!("Agent events of {conversation_id} won't be resumable: {e}");
+        None
+    });
+    let guard = TurnGuard {
+        conversation_id: conversation_id.to_string(),
+        running: running.clone(),
+        log: log.clone(),
+    };
+    let recorder = log.filter(|_| first_seq.is_some());
+    let stream = AgentStream::new(conversation_id.to_string(), first_seq.unwrap_or(1), move |seq, event| {
+        let Some(log) = &recorder else {
+            return;
+        };
+        let appended = serde_json::to_value(event)
+            .map_err(std::io::Error::other)
+            .and_then(|event| log.append(seq, &event));
+        if let Err(e) = appended {
+            tracing::warn!("Recording agent event {seq}: {e}");
+        }
+    });
+    (core_rpc.with_agent_stream(Arc::new(stream)), guard)
+}
+
+/// The latest turn of `conversation_id`, if it had one.
+pub fn task_status(
+    conversation_id: &str,
+    running: &RunningTurns,
+    pending_approvals: Vec<String>,
+) -> Option<AgentTaskStatus> {
+    let log = EventLog::open(conversation_id)?;
+    let status = log.status()?;
+    let is_running = running.lock().contains(conversation_id);
+    let state = match status.state {
+        TurnState::Running if is_running => AgentTaskState::Running,
+        TurnState::Running => {
+            let _ = log.finish(TurnState::Interrupted);
+            AgentTaskState::Interrupted
+        }
+        TurnState::Done => AgentTaskState::Done,
+        TurnState::Interrupted => AgentTaskState::Interrupted,
+    };
+    Some(to_rpc(conversation_id, status, state, if is_running { pending_approvals } else { Vec::new() }))
+}
+
+/// The chat events of `conversation_id` after `last_seq`, as sent.
+pub fn missed_events(conversation_id: &str, last_seq: u64) -> Vec<CoreNotification> {
+    let Some(log) = EventLog::open(conversation_id) else {
+        return Vec::new();
+    };
+    log.since(last_seq)
+        .into_iter()
+        .filter_map(|(seq, event)| {
+            let event = serde_json::from_value(event)
+                .map_err(|e| tracing::warn!("Skipping unreadable agent event {seq}: {e}"))
+                .ok()?;
+            Some(CoreNotification::AgentEvent {
+                conversation_id: conversation_id.to_string(),
+                seq,
+                event: Box::new(event),
+            })
+        })
+        .collect()
+}
+
+fn to_rpc(
+    conversation_id: &str,
+    status: TurnStatus,
+    state: AgentTaskState,
+    pending_approvals: Vec<String>,
+) -> AgentTaskStatus {
+    AgentTaskStatus {
+        conversation_id: conversation_id.to_string(),
+        state,
+        prompt: status.prompt,
+        started_at: status.started_at,
+        last_seq: status.last_seq,
+        pending_approvals,
+    }
+}
@@ -10,6 +10,12 @@
 //!
 //! This replaces the old approach of `Command::new("sh").stdout(Stdio::piped())`
 //! which was invisible to the user and didn't source shell profiles.
+//!
+//! `shell_session` keeps one interactive shell alive per session name, so
+//! `cd`, `source .venv/bin/activate` and `export` carry over between
+//! commands. Each command is bracketed by printf markers carrying a sequence
+//! number, the exit code and `$PWD`, which is how the end of its output is
+//! found.
 
 use std::borrow::Cow;
 use std::collections::{HashMap, VecDeque};
@@ -30,11 +36,21 @@ use lapce_rpc::{
     core::{CoreNotification, CoreRpcHandler},
     terminal::{TermId, TerminalProfile},
 };
+use forge_agent::tools::process_log::ProcessLog;
 use polling::PollMode;
 
 use crate::terminal::TerminalSender;
 
+type SharedLog = Arc<Mutex<ProcessLog>>;
+
 const READ_BUFFER_SIZE: usize = 0x10_0000;
+
+/// Sent to a new session shell: no echo or prompts, so the capture holds just
+/// command output and markers.
+const SESSION_INIT: &str =
+    "stty -echo 2>/dev/null; PS1=''; PS2=''; PROMPT=''; RPROMPT=''; PROMPT_COMMAND=''\n";
+const SESSION_BEGIN: &str = "__FORGE_BEGIN_";
+const SESSION_END: &str = "__FORGE_END_";
 /// Max bytes to capture for agent output.
 const MAX_CAPTURE_BYTES: usize = 200_000;
 
@@ -106,6 +122,97 @@ impl WriteState {
 pub struct AgentTerminalManager {
     /// Active agent terminals, keyed by PID (so existing tools can look up by PID).
     terminals: Mutex<HashMap<u32, AgentTermHandle>>,
+    /// Output of IDE run-config terminals (run_project), keyed by config
+    /// name. Only the latest run of each config is kept.
+    runs: Mutex<HashMap<String, Arc<RunCapture>>>,
+    /// Persistent shells for `shell_session`, keyed by session name.
+    sessions: Mutex<HashMap<String, ShellSession>>,
+}
+
+/// State of one `shell_session` shell.
+struct ShellSession {
+    /// PID of the shell (key into `terminals`).
+    pid: u32,
+    /// Sequence number of the last command sent.
+    seq: u64,
+    /// Sequence number of a command that hasn't finished yet.
+    pending: Option<u64>,
+    /// Working directory after the last finished command.
+    cwd: String,
+}
+
+/// Where a session's last command is at.
+enum SessionStatus {
+    /// No command pending; holds the working directory.
+    Idle(String),
+    /// Output so far.
+    Running(String),
+    Finished(SessionCommandEnd),
+    /// The shell died mid-command; output so far.
+    Exited(String),
+}
+
+impl SessionStatus {
+    fn into_result(self, session: &str) -> forge_agent::tools::ToolResult {
+        match self {
+            Self::Idle(cwd) => forge_agent::tools::ToolResult::ok(format!(
+                "Session '{session}' is idle.\nCwd: {cwd}"
+            )),
+            Self::Running(partial) => forge_agent::tools::ToolResult::ok(format!(
+                "Still running.\n{}",
+                truncate_output(&partial)
+            )),
+            Self::Finished(end) => {
+                let text = format!(
+                    "Session: {session}\nCwd: {}\nExit code: {}\n{}",
+                    end.cwd,
+                    end.exit_code,
+                    truncate_output(&end.output)
+                );
+                if end.exit_code == 0 {
+                    forge_agent::tools::ToolResult::ok(text)
+                } else {
+                    forge_agent::tools::ToolResult::err(text)
+                }
+            }
+            Self::Exited(partial) => forge_agent::tools::ToolResult::err(format!(
+                "The shell of session '{session}' exited.\n{}",
+                truncate_output(&partial)
+            )),
+        }
+    }
+}
+
+/// How a finished session command ended, parsed from its end marker.
+#[derive(Debug, PartialEq, Eq)]
+struct SessionCommandEnd {
+    output: String,
+    exit_code: i32,
+    cwd: String,
+}
+
+/// Output captured from an IDE terminal running a run config.
+pub struct RunCapture {
+    pub term_id: TermId,
+    output: Mutex<Vec<u8>>,
+    /// Set to Some when the process exits.
+    exit_code: Mutex<Option<Option<i32>>>,
+}
+
+impl RunCapture {
+    pub fn append(&self, data: &[u8]) {
+        let mut output = self.output.lock().unwrap();
+        output.extend_from_slice(data);
+        // Keep the tail: that's where crashes show up
+        if output.len() > MAX_CAPTURE_BYTES {
+            let excess = output.len() - MAX_CAPTURE_BYTES;
+            output.drain(..excess);
+        }
+    }
+
+    pub fn set_exited(&self, exit_code: Option<i32>) {
+        *self.exit_code.lock().unwrap() = Some(exit_code);
+    }
 }
 
 /// Handle to a running agent terminal.
@@ -123,8 +230,9 @@ struct AgentTermHandle {
     /// Condvar signaled when the process exits.
     exit_notify: Arc<Condvar>,
     exit_flag: Arc<Mutex<bool>>,
-    /// Sender to write input to the terminal (for future use).
-    #[allow(dead_code)]
+    /// Full output on disk for background commands.
+    pub log: Option<SharedLog>,
+    /// Sender to write input to the terminal (shell sessions).
     pub sender: TerminalSender,
 }
 
@@ -132,9 +240,69 @@ impl AgentTerminalManager {
     pub fn new() -> Self {
         Self {
             terminals: Mutex::new(HashMap::new()),
+            runs: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Start capturing a run-config terminal's output under `name`,
+    /// replacing the capture of any previous run of the same config.
+    pub fn track_run(&self, name: &str, term_id: TermId) -> Arc<RunCapture> {
+        let capture = Arc::new(RunCapture {
+            term_id,
+            output: Mutex::new(Vec::new()),
+            exit_code: Mutex::new(None),
+        });
+        self.runs
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), capture.clone());
+        capture
+    }
+
+    /// Read the recent output of a run config launched in the IDE.
+    ///
+    /// `name: None` reads the only tracked run, if there is exactly one.
+    /// Returns `Err` with the known config names when nothing matches.
+    pub fn read_run_output(
+        &self,
+        name: Option<&str>,
+        tail_lines: usize,
+    ) -> Result<forge_agent::tools::ToolResult, Vec<String>> {
+        let runs = self.runs.lock().unwrap();
+        let found = match name {
+            Some(name) => runs.get_key_value(name),
+            None if runs.len() == 1 => runs.iter().next(),
+            None => None,
+        };
+        let Some((name, capture)) = found else {
+            let mut names: Vec<String> = runs.keys().cloned().collect();
+            names.sort();
+            return Err(names);
+        };
+
+        let raw = capture.output.lock().unwrap();
+        let text = strip_ansi_escapes(&String::from_utf8_lossy(&raw));
+        let lines: Vec<&str> = text.lines().collect();
+        let tail = if tail_lines > 0 && lines.len() > tail_lines {
+            lines[lines.len() - tail_lines..].join("\n")
+        } else {
+            text.clone()
+        };
+        let status = match *capture.exit_code.lock().unwrap() {
+            None => "running".to_string(),
+            Some(Some(code)) => format!("exited with code {code}"),
+            Some(None) => "exited".to_string(),
+        };
+        Ok(forge_agent::tools::ToolResult::ok(format!(
+            "Run config: {name}\n\
+             Status: {status}\n\
+             --- Output (last {} lines) ---\n\
+             {tail}",
+            tail.lines().count()
+        )))
+    }
+
     /// Execute a command in a real IDE terminal (foreground, waits for completion).
     ///
     /// The terminal appears in the IDE's terminal panel so the user can see it.
@@ -149,7 +317,7 @@ impl AgentTerminalManager {
         tool_call_id: &str,
         tool_name: &str,
     ) -> forge_agent::tools::ToolResult {
-        let (handle, sender) = match self.spawn_terminal(command, workdir, core_rpc) {
+        let (handle, sender) = match self.spawn_terminal(command, workdir, core_rpc, false, None) {
             Ok(h) => h,
             Err(e) => return forge_agent::tools::ToolResult::err(format!("Failed to create terminal: {e}")),
         };
@@ -214,7 +382,7 @@ impl AgentTerminalManager {
         core_rpc: &CoreRpcHandler,
         ide_terminals: &Arc<std::sync::Mutex<HashMap<TermId, TerminalSender>>>,
     ) -> forge_agent::tools::ToolResult {
-        let (handle, sender) = match self.spawn_terminal(command, workdir, core_rpc) {
+        let (handle, sender) = match self.spawn_terminal(command, workdir, core_rpc, true, None) {
             Ok(h) => h,
             Err(e) => return forge_agent::tools::ToolResult::err(format!("Failed to create terminal: {e}")),
         };
@@ -250,12 +418,21 @@ impl AgentTerminalManager {
         let initial_output = self.get_output(pid);
         let clean_output = strip_ansi_escapes(&initial_output);
         let len = clean_output.len().min(20_000);
+        let log_line = self
+            .terminals
+            .lock()
+            .unwrap()
+            .get(&pid)
+            .and_then(|h| h.log.as_ref())
+            .map(|log| format!("Log: .forge/logs/{}.*.log\n", log.lock().unwrap().stem()))
+            .unwrap_or_default();
 
         forge_agent::tools::ToolResult::ok(format!(
             "Process started in background (visible in terminal panel).\n\
              PID: {pid}\n\
              Terminal: {term_id:?}\n\
              Running: {is_running}\n\
+             {log_line}\
              --- Initial output ---\n\
              {}", &clean_output[..len]
         ))
@@ -280,6 +457,213 @@ impl AgentTerminalManager {
         }
     }
 
+    /// Read a background agent terminal's on-disk log from byte offset
+    /// `since`. `None` if the PID has no log.
+    pub fn read_log(
+        &self,
+        pid: u32,
+        workdir: &Path,
+        since: u64,
+    ) -> Option<forge_agent::tools::ToolResult> {
+        let stem = {
+            let terminals = self.terminals.lock().unwrap();
+            let log = terminals.get(&pid)?.log.as_ref()?;
+            let stem = log.lock().unwrap().stem().to_string();
+            stem
+        };
+        let status = if self.is_running(pid) { "running" } else { "exited" };
+        let mut result = forge_agent::tools::log_result(workdir, &stem, since, status);
+        result.output = strip_ansi_escapes(&result.output);
+        Some(result)
+    }
+
+    /// Run `command` in the persistent shell `session`, starting the shell on
+    /// first use. A command still running after `timeout_secs` keeps the
+    /// session busy until [`Self::session_read`] sees it finish.
+    pub fn session_run(
+        &self,
+        session: &str,
+        command: &str,
+        workdir: &Path,
+        timeout_secs: u64,
+        core_rpc: &CoreRpcHandler,
+        ide_terminals: &Arc<std::sync::Mutex<HashMap<TermId, TerminalSender>>>,
+    ) -> forge_agent::tools::ToolResult {
+        if let Some(SessionStatus::Running(partial)) = self.session_status(session) {
+            return forge_agent::tools::ToolResult::err(format!(
+                "Session '{session}' is busy with a previous command. \
+                 Use action 'read' to check on it or 'interrupt' to stop it.\n{}",
+                truncate_output(&partial)
+            ));
+        }
+        let pid = match self.ensure_session(session, workdir, core_rpc, ide_terminals) {
+            Ok(pid) => pid,
+            Err(e) => {
+                return forge_agent::tools::ToolResult::err(format!(
+                    "Failed to start shell session '{session}': {e}"
+                ));
+            }
+        };
+
+        let seq = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let Some(state) = sessions.get_mut(session) else {
+                return forge_agent::tools::ToolResult::err(format!("Session '{session}' was closed"));
+            };
+            state.seq += 1;
+            state.pending = Some(state.seq);
+            state.seq
+        };
+        {
+            let terminals = self.terminals.lock().unwrap();
+            let Some(handle) = terminals.get(&pid) else {
+                return forge_agent::tools::ToolResult::err(format!("Session '{session}' has no terminal"));
+            };
+            // Only the current command's output is kept
+            handle.capture.lock().unwrap().clear();
+            handle.sender.send(Msg::Input(Cow::Owned(session_script(seq, command).into_bytes())));
+        }
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(timeout_secs);
+        loop {
+            std::thread::sleep(Duration::from_millis(100));
+            match self.session_status(session) {
+                Some(SessionStatus::Running(partial)) => {
+                    if std::time::Instant::now() >= deadline {
+                        return forge_agent::tools::ToolResult::err(format!(
+                            "Command still running after {timeout_secs}s in session '{session}'. \
+                             Use action 'read' to check on it or 'interrupt' to stop it.\n{}",
+                            truncate_output(&partial)
+                        ));
+                    }
+                }
+                Some(status) => return status.into_result(session),
+                None => {
+                    return forge_agent::tools::ToolResult::err(format!("Session '{session}' was closed"));
+                }
+            }
+        }
+    }
+
+    /// Check on the last command of `session`. `None` if there is no such
+    /// session.
+    pub fn session_read(&self, session: &str) -> Option<forge_agent::tools::ToolResult> {
+        self.session_status(session).map(|status| status.into_result(session))
+    }
+
+    fn session_status(&self, session: &str) -> Option<SessionStatus> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let state = sessions.get_mut(session)?;
+        let Some(seq) = state.pending else {
+            return Some(SessionStatus::Idle(state.cwd.clone()));
+        };
+        let text = strip_ansi_escapes(&self.get_output(state.pid));
+        match parse_session_output(&text, seq) {
+            Ok(end) => {
+                state.pending = None;
+                state.cwd = end.cwd.clone();
+                Some(SessionStatus::Finished(end))
+            }
+            Err(partial) if !self.is_running(state.pid) => {
+                let pid = state.pid;
+                sessions.remove(session);
+                self.terminals.lock().unwrap().remove(&pid);
+                Some(SessionStatus::Exited(partial))
+            }
+            Err(partial) => Some(SessionStatus::Running(partial)),
+        }
+    }
+
+    /// Send Ctrl-C to `session`'s running command.
+    pub fn session_interrupt(&self, session: &str) -> forge_agent::tools::ToolResult {
+        let pid = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let Some(state) = sessions.get_mut(session) else {
+                return forge_agent::tools::ToolResult::err(format!("No shell session '{session}'"));
+            };
+            // The tty drops queued input on SIGINT, end marker included
+            state.pending = None;
+            state.pid
+        };
+        let output = self.get_output(pid);
+        if let Some(handle) = self.terminals.lock().unwrap().get(&pid) {
+            handle.sender.send(Msg::Input(Cow::Borrowed(b"\x03")));
+        }
+        forge_agent::tools::ToolResult::ok(format!(
+            "Interrupted the command in session '{session}'.\n{}",
+            truncate_output(&strip_ansi_escapes(&output))
+        ))
+    }
+
+    /// Close `session`, hanging up its shell.
+    pub fn session_close(&self, session: &str) -> forge_agent::tools::ToolResult {
+        let Some(state) = self.sessions.lock().unwrap().remove(session) else {
+            return forge_agent::tools::ToolResult::err(format!("No shell session '{session}'"));
+        };
+        if let Some(handle) = self.terminals.lock().unwrap().remove(&state.pid) {
+            handle.sender.send(Msg::Shutdown);
+        }
+        forge_agent::tools::ToolResult::ok(format!("Closed shell session '{session}'."))
+    }
+
+    /// Hang up every agent terminal and shell session, e.g. at shutdown.
+    pub fn close_all(&self) {
+        self.sessions.lock().unwrap().clear();
+        for (_, handle) in self.terminals.lock().unwrap().drain() {
+            handle.sender.send(Msg::Shutdown);
+        }
+    }
+
+    /// Open shell sessions, one per line.
+    pub fn session_list(&self) -> forge_agent::tools::ToolResult {
+        let sessions = self.sessions.lock().unwrap();
+        if sessions.is_empty() {
+            return forge_agent::tools::ToolResult::ok("No shell sessions.");
+        }
+        let mut names: Vec<&String> = sessions.keys().collect();
+        names.sort();
+        let lines: Vec<String> = names
+            .into_iter()
+            .map(|name| {
+                let state = &sessions[name];
+                let status = if state.pending.is_some() { "busy" } else { "idle" };
+                format!("{name}: PID {} | {status} | cwd {}", state.pid, state.cwd)
+            })
+            .collect();
+        forge_agent::tools::ToolResult::ok(lines.join("\n"))
+    }
+
+    /// PID of the shell for `session`, starting it if needed.
+    fn ensure_session(
+        &self,
+        session: &str,
+        workdir: &Path,
+        core_rpc: &CoreRpcHandler,
+        ide_terminals: &Arc<std::sync::Mutex<HashMap<TermId, TerminalSender>>>,
+    ) -> Result<u32> {
+        if let Some(state) = self.sessions.lock().unwrap().get(session) {
+            if self.is_running(state.pid) {
+                return Ok(state.pid);
+            }
+        }
+        let label = format!("shell session {session}");
+        let (handle, sender) = self.spawn_terminal(&label, workdir, core_rpc, false, Some(session))?;
+        let pid = handle.pid;
+        handle.sender.send(Msg::Input(Cow::Borrowed(SESSION_INIT.as_bytes())));
+        ide_terminals.lock().unwrap().insert(handle.term_id, sender);
+        self.terminals.lock().unwrap().insert(pid, handle);
+        self.sessions.lock().unwrap().insert(
+            session.to_string(),
+            ShellSession {
+                pid,
+                seq: 0,
+                pending: None,
+                cwd: workdir.display().to_string(),
+            },
+        );
+        Ok(pid)
+    }
+
     /// Check if an agent terminal process is still running.
     pub fn is_running(&self, pid: u32) -> bool {
         let terminals = self.terminals.lock().unwrap();
@@ -372,26 +756,41 @@ impl AgentTerminalManager {
     }
 
     /// Spawn a new PTY terminal for an agent command.
+    ///
+    /// With `persist`, the output is also written to `.forge/logs`. With
+    /// `session`, an interactive login shell is started instead of running
+    /// `command`, and the capture keeps the newest output.
     fn spawn_terminal(
         &self,
         command: &str,
         workdir: &Path,
         core_rpc: &CoreRpcHandler,
+        persist: bool,
+        session: Option<&str>,
     ) -> Result<(AgentTermHandle, TerminalSender)> {
         let term_id = TermId::next();
 
         // Use user's login shell
         let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
+        let shell_args: Vec<String> = match session {
+            Some(_) => vec!["-l".into()],
+            None => vec!["-l".into(), "-c".into(), command.into()],
+        };
+        let name = match session {
+            Some(session) => format!("Agent shell: {session}"),
+            None => format!("Agent: {}", &command[..command.len().min(60)]),
+        };
 
         let profile = TerminalProfile {
-            name: format!("Agent: {}", &command[..command.len().min(60)]),
+            name,
             command: Some(shell.clone()),
-            arguments: Some(vec!["-l".into(), "-c".into(), command.into()]),
+            arguments: Some(shell_args.clone()),
             workdir: Some(
                 url::Url::from_file_path(workdir)
                     .map_err(|_| anyhow::anyhow!("Invalid workdir path"))?,
             ),
             environment: None,
+            run_config: None,
         };
 
         let mut env = profile.environment.clone().unwrap_or_default();
@@ -421,11 +820,12 @@ impl AgentTerminalManager {
             env.insert("PATH".to_string(), new_path);
         }
 
+        // Project-pinned toolchains (.prototools / .mise.toml / .tool-versions)
+        // take precedence over the user's defaults.
+        forge_agent::tools::sdk_env::apply_project_env(workdir, &mut env);
+
         let options = Options {
-            shell: Some(Shell::new(
-                shell,
-                vec!["-l".into(), "-c".into(), command.into()],
-            )),
+            shell: Some(Shell::new(shell, shell_args)),
             working_directory: Some(workdir.to_path_buf()),
             hold: false,
             env,
@@ -462,8 +862,21 @@ impl AgentTerminalManager {
 
         // Notify frontend about the new terminal
         core_rpc.terminal_process_id(term_id, Some(child_pid));
+        forge_agent::tools::ownership::register(
+            child_pid,
+            command,
+            forge_agent::tools::ownership::ProcessOrigin::Agent,
+        );
 
         let capture = Arc::new(Mutex::new(Vec::new()));
+        let log = if persist {
+            ProcessLog::create(workdir, child_pid, command)
+                .map_err(|e| tracing::warn!("Failed to create log for PID {child_pid}: {e}"))
+                .ok()
+                .map(|log| Arc::new(Mutex::new(log)))
+        } else {
+            None
+        };
         let exit_code = Arc::new(Mutex::new(None::<Option<i32>>));
         let exit_notify = Arc::new(Condvar::new());
         let exit_flag = Arc::new(Mutex::new(false));
@@ -481,6 +894,7 @@ impl AgentTerminalManager {
             exit_code: exit_code.clone(),
             exit_notify: exit_notify.clone(),
             exit_flag: exit_flag.clone(),
+            log: log.clone(),
             sender: handle_sender,
         };
 
@@ -490,9 +904,11 @@ impl AgentTerminalManager {
         let ec = exit_code;
         let en = exit_notify;
         let ef = exit_flag;
+        let keep_tail = session.is_some();
 
         std::thread::spawn(move || {
-            run_capturing_event_loop(term_id, pty, poller, rx, rpc, cap, ec, en, ef);
+            run_capturing_event_loop(term_id, pty, poller, rx, rpc, cap, log, keep_tail, ec, en, ef);
+            forge_agent::tools::ownership::unregister(child_pid);
         });
 
         Ok((handle, ide_sender))
@@ -508,6 +924,8 @@ fn run_capturing_event_loop(
     rx: Receiver<Msg>,
     core_rpc: CoreRpcHandler,
     capture: Arc<Mutex<Vec<u8>>>,
+    log: Option<SharedLog>,
+    keep_tail: bool,
     exit_code_holder: Arc<Mutex<Option<Option<i32>>>>,
     exit_notify: Arc<Condvar>,
     exit_flag: Arc<Mutex<bool>>,
@@ -555,7 +973,7 @@ fn run_capturing_event_loop(
                 PTY_CHILD_EVENT_TOKEN => {
                     if let Some(tty::ChildEvent::Exited(exited_code)) = pty.next_child_event() {
                         // Read any remaining output
-                        let _ = pty_read_capturing(&mut pty, &core_rpc, &capture, term_id, &mut buf);
+                        let _ = pty_read_capturing(&mut pty, &core_rpc, &capture, log.as_ref(), keep_tail, term_id, &mut buf);
                         final_exit_code = exited_code;
                         break 'event_loop;
                     }
@@ -565,7 +983,7 @@ fn run_capturing_event_loop(
                         continue;
                     }
                     if event.readable {
-                        if let Err(err) = pty_read_capturing(&mut pty, &core_rpc, &capture, term_id, &mut buf) {
+                        if let Err(err) = pty_read_capturing(&mut pty, &core_rpc, &capture, log.as_ref(), keep_tail, term_id, &mut buf) {
                             #[cfg(target_os = "linux")]
                             if err.raw_os_error() == Some(libc::EIO) {
                                 continue;
@@ -604,10 +1022,15 @@ fn run_capturing_event_loop(
 }
 
 /// Read from PTY: send to frontend (visible in terminal) AND capture for agent.
+///
+/// Once the capture is full, new output is dropped, or with `keep_tail` the
+/// oldest output is.
 fn pty_read_capturing(
     pty: &mut alacritty_terminal::tty::Pty,
     core_rpc: &CoreRpcHandler,
     capture: &Arc<Mutex<Vec<u8>>>,
+    log: Option<&SharedLog>,
+    keep_tail: bool,
     term_id: TermId,
     buf: &mut [u8],
 ) -> io::Result<()> {
@@ -618,9 +1041,16 @@ fn pty_read_capturing(
                 let data = buf[..n].to_vec();
                 // Send to frontend (appears in terminal panel)
                 core_rpc.update_terminal(term_id, data.clone());
+                if let Some(log) = log {
+                    log.lock().unwrap().append(&data);
+                }
                 // Capture for agent
                 let mut cap = capture.lock().unwrap();
-                if cap.len() < MAX_CAPTURE_BYTES {
+                if keep_tail {
+                    cap.extend_from_slice(&data);
+                    let excess = cap.len().saturating_sub(MAX_CAPTURE_BYTES);
+                    cap.drain(..excess);
+                } else if cap.len() < MAX_CAPTURE_BYTES {
                     let remaining = MAX_CAPTURE_BYTES - cap.len();
                     cap.extend_from_slice(&data[..data.len().min(remaining)]);
                 }
@@ -671,6 +1101,54 @@ fn pty_write(
     Ok(())
 }
 
+// ─── Shell session markers ───────────────────────────────────────────────────
+
+/// Input for running `command` as session command `seq`, bracketed by the
+/// begin marker and an end marker with the exit code and working directory.
+fn session_script(seq: u64, command: &str) -> String {
+    format!(
+        "printf '\\n{SESSION_BEGIN}%s__\\n' {seq}\n\
+         {command}\n\
+         printf '\\n{SESSION_END}%s_%s_%s__\\n' {seq} \"$?\" \"$PWD\"\n"
+    )
+}
+
+/// Split the (ANSI-stripped) output of session command `seq`. `Err` holds the
+/// output so far while the end marker hasn't arrived.
+fn parse_session_output(text: &str, seq: u64) -> Result<SessionCommandEnd, String> {
+    let begin = format!("{SESSION_BEGIN}{seq}__\n");
+    // The begin marker may have scrolled out of a very long capture
+    let body = match text.find(&begin) {
+        Some(i) => &text[i + begin.len()..],
+        None => text,
+    };
+    let end = format!("{SESSION_END}{seq}_");
+    let Some(i) = body.find(&end) else {
+        return Err(body.to_string());
+    };
+    let status = body[i + end.len()..].lines().next().unwrap_or_default();
+    let Some((code, cwd)) = status.strip_suffix("__").and_then(|s| s.split_once('_')) else {
+        return Err(body.to_string());
+    };
+    Ok(SessionCommandEnd {
+        output: body[..i].trim_end_matches('\n').to_string(),
+        exit_code: code.parse().unwrap_or(-1),
+        cwd: cwd.to_string(),
+    })
+}
+
+/// Cap agent-facing output the same way `execute_command` does.
+fn truncate_output(output: &str) -> String {
+    if output.len() <= 30_000 {
+        return output.to_string();
+    }
+    let mut cut = 30_000;
+    while !output.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    format!("{}...\n(output truncated at 30000 chars)", &output[..cut])
+}
+
 // ─── ANSI escape stripping ──────────────────────────────────────────────────
 
 /// Strip ANSI escape sequences from terminal output so the agent gets clean text.
@@ -723,3 +1201,50 @@ fn strip_ansi_escapes(input: &str) -> String {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_run_output() {
+        let mgr = AgentTerminalManager::new();
+        assert_eq!(mgr.read_run_output(None, 10).err(), Some(Vec::new()));
+
+        let capture = mgr.track_run("server", TermId::next());
+        capture.append(b"\x1b[32mstarting\x1b[0m\nlistening on 8080\npanic: boom\n");
+        capture.set_exited(Some(101));
+
+        let result = mgr.read_run_output(None, 2).unwrap();
+        assert!(result.output.contains("exited with code 101"));
+        assert!(result.output.contains("listening on 8080\npanic: boom"));
+        assert!(!result.output.contains("starting"));
+
+        assert_eq!(
+            mgr.read_run_output(Some("client"), 10).err(),
+            Some(vec!["server".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_session_output() {
+        let script = session_script(3, "cd src && ls");
+        assert!(script.starts_with("printf '\\n__FORGE_BEGIN_%s__\\n' 3\ncd src && ls\n"));
+
+        // Banner from shell rc files before the begin marker is dropped
+        let text = "Welcome!\n\n__FORGE_BEGIN_3__\nmain.rs\nlib.rs\n\n__FORGE_END_3_0_/home/me/my_app/src__\n";
+        assert_eq!(
+            parse_session_output(text, 3),
+            Ok(SessionCommandEnd {
+                output: "main.rs\nlib.rs".to_string(),
+                exit_code: 0,
+                cwd: "/home/me/my_app/src".to_string(),
+            })
+        );
+
+        let running = "\n__FORGE_BEGIN_4__\ncompiling\n";
+        assert_eq!(parse_session_output(running, 4), Err("compiling\n".to_string()));
+        // An older command's end marker doesn't finish this one
+        assert!(parse_session_output("\n__FORGE_END_3_1_/tmp__\n", 4).is_err());
+    }
+}
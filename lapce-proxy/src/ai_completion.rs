@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 
 const AI_KEYS_FILE: &str = "ai-keys.toml";
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Serialize, Deserialize, Default)]
 struct AiKeysConfig {
     #[serde(default)]
     keys: std::collections::HashMap<String, String>,
@@ -41,10 +41,25 @@ impl AiKeysConfig {
         let Some(path) = config_dir.map(|d| d.join(AI_KEYS_FILE)) else {
             return Self::default();
         };
-        match std::fs::read_to_string(&path) {
+        let mut config: Self = match std::fs::read_to_string(&path) {
             Ok(content) => toml::from_str(&content).unwrap_or_default(),
             Err(_) => Self::default(),
+        };
+        // Profile and workspace settings (.forge/config.toml) take precedence
+        let overrides = forge_agent::config::active();
+        if let Some(provider) = overrides.get("provider") {
+            config.defaults.provider = provider.to_string();
+        }
+        if let Some(model) = overrides.get("model") {
+            config.defaults.model = model.to_string();
         }
+        // Keys from the environment, config files or the keychain
+        for provider in ["gemini", "anthropic", "openai"] {
+            if let Some(key) = forge_agent::secrets::api_key(provider) {
+                config.keys.insert(provider.to_string(), key);
+            }
+        }
+        config
     }
 }
 
@@ -67,7 +82,7 @@ pub async fn generate_completion(
         .cloned()
         .unwrap_or_default();
 
-    if api_key.is_empty() {
+    if api_key.is_empty() || !forge_agent::egress::allows_llm(provider) {
         return vec![];
     }
 
@@ -78,6 +93,11 @@ pub async fn generate_completion(
 
     let lang_hint = detect_language(file_path);
 
+    // The code around the cursor leaves the machine; mask secrets in it.
+    let redactor = forge_agent::redaction::configured();
+    let (prefix, suffix) = (redactor.redact_str(prefix), redactor.redact_str(suffix));
+    let (prefix, suffix) = (prefix.as_ref(), suffix.as_ref());
+
     match provider.as_str() {
         "gemini" => {
             generate_gemini(&api_key, file_name, &lang_hint, prefix, suffix).await
@@ -169,7 +189,7 @@ async fn generate_gemini(
         }
     });
 
-    let client = reqwest::Client::builder()
+    let client = crate::http_client_builder()
         .timeout(std::time::Duration::from_secs(5))
         .build()
         .unwrap_or_default();
@@ -231,7 +251,7 @@ async fn generate_anthropic(
         }]
     });
 
-    let client = reqwest::Client::builder()
+    let client = crate::http_client_builder()
         .timeout(std::time::Duration::from_secs(5))
         .build()
         .unwrap_or_default();
@@ -298,7 +318,7 @@ async fn generate_openai(
         }]
     });
 
-    let client = reqwest::Client::builder()
+    let client = crate::http_client_builder()
         .timeout(std::time::Duration::from_secs(5))
         .build()
         .unwrap_or_default();
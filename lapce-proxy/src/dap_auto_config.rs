@@ -0,0 +1,272 @@
+//! Debug adapter auto-configuration for agent-launched debug sessions.
+//!
+//! When the agent runs a project in debug mode without a hand-written DAP
+//! config, the command (or detected config name) is mapped to a launch
+//! config for an adapter found on the machine:
+//! - `cargo run` / `go run` -> build with debug info, then an lldb adapter
+//!   (`lldb-dap`, `lldb-vscode` or CodeLLDB's `codelldb`)
+//! - `python script.py` -> `debugpy.adapter`
+//! - `node` / npm scripts -> run with `--inspect` (no stdio DAP adapter
+//!   exists for node, so this attaches via the inspector instead)
+//!
+//! Breakpoints are wired by the regular `dap_start` path in the UI.
+
+use std::path::{Path, PathBuf};
+
+use lapce_rpc::dap_types::{ConfigSource, DapId, RunDebugConfig, RunDebugProgram};
+
+/// Debugger types registered for auto-configured sessions. Kept distinct
+/// from plugin-provided types so plugin registrations aren't overwritten.
+const LLDB_TYPE: &str = "auto-lldb";
+const DEBUGPY_TYPE: &str = "auto-debugpy";
+
+/// A generated debug launch.
+#[derive(Debug, Clone)]
+pub struct AutoDebugConfig {
+    pub config: RunDebugConfig,
+    /// Debug adapter to register as `config.ty`: (program, args).
+    /// `None` means the config should be run in a terminal (inspector-based).
+    pub adapter: Option<(String, Vec<String>)>,
+    /// Human readable summary for the agent.
+    pub description: String,
+}
+
+/// Build a debug config for `command` (e.g. "cargo run --bin server -- -v").
+pub fn auto_debug_config(workspace: &Path, command: &str) -> Result<AutoDebugConfig, String> {
+    let words: Vec<String> = command.split_whitespace().map(String::from).collect();
+    let (first, rest) = words
+        .split_first()
+        .ok_or_else(|| "empty command".to_string())?;
+
+    match first.as_str() {
+        "cargo" if rest.first().map(String::as_str) == Some("run") => {
+            let target = parse_cargo_run(&rest[1..]);
+            let bin = target
+                .bin
+                .clone()
+                .or_else(|| cargo_package_name(workspace))
+                .ok_or_else(|| "could not determine the binary name; pass --bin".to_string())?;
+            let profile_dir = if target.release { "release" } else { "debug" };
+            let program = format!("${{workspace}}/target/{}/{}", profile_dir, bin);
+            let mut build = vec!["build".to_string()];
+            build.extend(target.build_args);
+            lldb_config(command, program, target.program_args, "cargo", build)
+        }
+        "go" if rest.first().map(String::as_str) == Some("run") => {
+            let (package, args) = match rest.get(1) {
+                Some(pkg) => (pkg.clone(), rest[2..].to_vec()),
+                None => (".".to_string(), Vec::new()),
+            };
+            // `go build -o` doesn't create missing parent directories
+            let _ = std::fs::create_dir_all(workspace.join(".lapce").join("debug"));
+            let program = "${workspace}/.lapce/debug/go-debug-bin".to_string();
+            let build = vec![
+                "build".to_string(),
+                "-gcflags=all=-N -l".to_string(),
+                "-o".to_string(),
+                program.clone(),
+                package,
+            ];
+            lldb_config(command, program, args, "go", build)
+        }
+        "python" | "python3" => {
+            if rest.first().map(String::as_str) == Some("-m") {
+                return Err(
+                    "debugging 'python -m' modules is not supported; run a script path".to_string(),
+                );
+            }
+            let (script, args) = rest
+                .split_first()
+                .ok_or_else(|| "no script given".to_string())?;
+            let python = find_program(&["python3", "python"])
+                .ok_or_else(|| "python not found on PATH".to_string())?;
+            if !python_has_debugpy(&python) {
+                return Err("debugpy is not installed (pip install debugpy)".to_string());
+            }
+            let mut config = base_config(command);
+            config.ty = Some(DEBUGPY_TYPE.to_string());
+            config.program = script.clone();
+            config.args = Some(args.to_vec());
+            Ok(AutoDebugConfig {
+                config,
+                adapter: Some((
+                    python,
+                    vec!["-m".to_string(), "debugpy.adapter".to_string()],
+                )),
+                description: format!("debugpy: {}", script),
+            })
+        }
+        "node" | "npm" | "npx" | "yarn" | "pnpm" => {
+            // No stdio DAP adapter for node; enable the inspector instead
+            let mut config = base_config(command);
+            config.program = first.clone();
+            config.args = Some(rest.to_vec());
+            config.env = Some(
+                [("NODE_OPTIONS".to_string(), "--inspect".to_string())]
+                    .into_iter()
+                    .collect(),
+            );
+            Ok(AutoDebugConfig {
+                config,
+                adapter: None,
+                description: "node --inspect (attach a debugger to port 9229)".to_string(),
+            })
+        }
+        _ => Err(format!(
+            "no debug adapter auto-configuration for '{}'",
+            first
+        )),
+    }
+}
+
+fn lldb_config(
+    command: &str,
+    program: String,
+    args: Vec<String>,
+    build_program: &str,
+    build_args: Vec<String>,
+) -> Result<AutoDebugConfig, String> {
+    let adapter = find_program(&["lldb-dap", "lldb-vscode", "codelldb"])
+        .ok_or_else(|| "no lldb debug adapter found (install lldb-dap or CodeLLDB)".to_string())?;
+    let mut config = base_config(command);
+    config.ty = Some(LLDB_TYPE.to_string());
+    config.program = program.clone();
+    config.args = Some(args);
+    config.prelaunch = Some(RunDebugProgram {
+        program: build_program.to_string(),
+        args: Some(build_args),
+    });
+    Ok(AutoDebugConfig {
+        description: format!("{} via {}", program, adapter),
+        config,
+        adapter: Some((adapter, Vec::new())),
+    })
+}
+
+fn base_config(command: &str) -> RunDebugConfig {
+    RunDebugConfig {
+        ty: None,
+        name: format!("Debug: {}", command),
+        program: String::new(),
+        args: None,
+        cwd: Some("${workspace}".to_string()),
+        env: None,
+        env_file: None,
+        prelaunch: None,
+        depends_on: None,
+        debug_command: None,
+        dap_id: DapId::next(),
+        tracing_output: true,
+        config_source: ConfigSource::Palette,
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct CargoRunTarget {
+    bin: Option<String>,
+    release: bool,
+    /// Arguments for `cargo build` (target selection and profile).
+    build_args: Vec<String>,
+    /// Arguments after `--`, passed to the program.
+    program_args: Vec<String>,
+}
+
+fn parse_cargo_run(args: &[String]) -> CargoRunTarget {
+    let mut target = CargoRunTarget::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--" => {
+                target.program_args = iter.cloned().collect();
+                break;
+            }
+            "--release" | "-r" => {
+                target.release = true;
+                target.build_args.push("--release".to_string());
+            }
+            "--bin" | "-p" | "--package" | "--example" | "--features" | "-F" => {
+                if let Some(value) = iter.next() {
+                    if arg == "--bin"
+                        || (target.bin.is_none() && (arg == "-p" || arg == "--package"))
+                    {
+                        target.bin = Some(value.clone());
+                    }
+                    target.build_args.push(arg.clone());
+                    target.build_args.push(value.clone());
+                }
+            }
+            other => target.build_args.push(other.to_string()),
+        }
+    }
+    target
+}
+
+fn cargo_package_name(workspace: &Path) -> Option<String> {
+    let manifest = std::fs::read_to_string(workspace.join("Cargo.toml")).ok()?;
+    let manifest: toml::Table = manifest.parse().ok()?;
+    manifest
+        .get("package")?
+        .get("name")?
+        .as_str()
+        .map(String::from)
+}
+
+fn python_has_debugpy(python: &str) -> bool {
+    std::process::Command::new(python)
+        .args(["-c", "import debugpy"])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// First of `names` found on PATH.
+fn find_program(names: &[&str]) -> Option<String> {
+    let path = std::env::var_os("PATH")?;
+    for name in names {
+        for dir in std::env::split_paths(&path) {
+            let candidate: PathBuf = dir.join(name);
+            if candidate.is_file() {
+                return Some(candidate.to_string_lossy().to_string());
+            }
+            #[cfg(windows)]
+            {
+                let exe = candidate.with_extension("exe");
+                if exe.is_file() {
+                    return Some(exe.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_cargo_run() {
+        let target = parse_cargo_run(&args("--release --bin server -- --port 8080"));
+        assert_eq!(target.bin.as_deref(), Some("server"));
+        assert!(target.release);
+        assert_eq!(target.build_args, args("--release --bin server"));
+        assert_eq!(target.program_args, args("--port 8080"));
+
+        let target = parse_cargo_run(&args("-p api"));
+        assert_eq!(target.bin.as_deref(), Some("api"));
+        assert!(!target.release);
+    }
+
+    #[test]
+    fn test_node_uses_inspector() {
+        let auto = auto_debug_config(Path::new("."), "npm run dev").unwrap();
+        assert!(auto.adapter.is_none());
+        assert_eq!(auto.config.program, "npm");
+        assert_eq!(auto.config.env.unwrap()["NODE_OPTIONS"], "--inspect");
+        assert!(auto_debug_config(Path::new("."), "make all").is_err());
+    }
+}
@@ -79,6 +79,7 @@ impl DatabaseEngine for MongoAdapter {
                     schema: None,
                     row_count: count,
                     table_type: "collection".to_string(),
+                    source: None,
                 });
             }
 
@@ -84,6 +84,7 @@ impl DatabaseEngine for PostgresAdapter {
                             "VIEW" => "view".to_string(),
                             other => other.to_lowercase(),
                         },
+                        source: None,
                     }
                 })
                 .collect();
@@ -28,7 +28,7 @@ use lapce_rpc::{
     file_line::FileLine,
     proxy::{
         ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse,
-        ProxyRpcHandler, SearchMatch,
+        ProxyRpcHandler, RequestClass, SearchMatch,
     },
     source_control::{DiffInfo, FileDiff},
     style::{LineStyle, SemanticStyles},
@@ -51,11 +51,59 @@ use crate::{
     proto_manager::ProtoManager,
     terminal::{Terminal, TerminalSender},
     watcher::{FileWatcher, Notify, WatchToken},
+    worker_pool::WorkerPools,
 };
 
 const OPEN_FILE_EVENT_TOKEN: WatchToken = WatchToken(1);
 const WORKSPACE_EVENT_TOKEN: WatchToken = WatchToken(2);
 
+/// Contents of `.lapce/run.toml`.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+struct RunConfigsFile {
+    #[serde(default)]
+    configs: Vec<lapce_rpc::dap_types::RunDebugConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    compounds: Vec<lapce_rpc::dap_types::CompoundRunConfig>,
+}
+
+fn load_run_configs(workspace: &Path) -> RunConfigsFile {
+    std::fs::read_to_string(workspace.join(".lapce").join("run.toml"))
+        .ok()
+        .and_then(|c| toml::from_str::<RunConfigsFile>(&c).ok())
+        .unwrap_or_default()
+}
+
+/// The command line to auto-configure a debugger for, or `None` when the
+/// named config in run.toml already declares a debugger type.
+fn debug_target_command(
+    workspace: &Path,
+    config_name: Option<&str>,
+    command: Option<&str>,
+) -> Option<String> {
+    if let Some(cmd) = command {
+        return Some(cmd.to_string());
+    }
+    let name = config_name?;
+    if let Some(config) = load_run_configs(workspace).configs.into_iter().find(|c| c.name == name) {
+        if config.ty.as_deref().is_some_and(|ty| ty != "shell") {
+            return None;
+        }
+        // `sh -c "<cmd>"` configs saved by the agent
+        let args = config.args.unwrap_or_default();
+        if config.program == "sh" && args.len() == 2 && args[0] == "-c" {
+            return Some(args[1].clone());
+        }
+        return Some(std::iter::once(config.program).chain(args).collect::<Vec<_>>().join(" "));
+    }
+    if let Some(detected) = crate::run_config_detector::detect_run_configs(workspace)
+        .into_iter()
+        .find(|c| c.name == name)
+    {
+        return Some(std::iter::once(detected.command).chain(detected.args).collect::<Vec<_>>().join(" "));
+    }
+    Some(name.to_string())
+}
+
 // Helper to save run config from proxy
 fn save_run_config_to_disk(workspace: &Path, config: lapce_rpc::dap_types::RunDebugConfig) -> Result<(), String> {
     let lapce_dir = workspace.join(".lapce");
@@ -64,20 +112,7 @@ fn save_run_config_to_disk(workspace: &Path, config: lapce_rpc::dap_types::RunDe
     }
     let run_toml = lapce_dir.join("run.toml");
     
-    #[derive(serde::Deserialize, serde::Serialize)]
-    struct RunConfigs {
-        configs: Vec<lapce_rpc::dap_types::RunDebugConfig>,
-    }
-    
-    let mut configs = if run_toml.exists() {
-        std::fs::read_to_string(&run_toml)
-            .ok()
-            .and_then(|c| toml::from_str::<RunConfigs>(&c).ok())
-            .map(|c| c.configs)
-            .unwrap_or_default()
-    } else {
-        Vec::new()
-    };
+    let RunConfigsFile { mut configs, compounds } = load_run_configs(workspace);
     
     if let Some(pos) = configs.iter().position(|c| c.name == config.name) {
         configs[pos] = config;
@@ -85,7 +120,7 @@ fn save_run_config_to_disk(workspace: &Path, config: lapce_rpc::dap_types::RunDe
         configs.push(config);
     }
     
-    match toml::to_string_pretty(&RunConfigs { configs }) {
+    match toml::to_string_pretty(&RunConfigsFile { configs, compounds }) {
         Ok(content) => {
             match std::fs::write(&run_toml, content) {
                 Ok(_) => Ok(()),
@@ -113,11 +148,42 @@ pub struct Dispatcher {
     /// Pending approval channels: tool_call_id -> oneshot sender (true=approved, false=rejected).
     /// The agent loop awaits on the receiver; the UI sends approve/reject via ProxyRequest.
     pending_approvals: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<bool>>>>,
+    /// Questions the agent is waiting on: tool_call_id -> oneshot sender for
+    /// the user's answer (sent by AgentAnswerFollowup).
+    pending_followups: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<String>>>>,
     /// When true, all future tool calls are auto-approved (except dangerous ones like delete_file).
     /// Set by AgentApproveAllFuture. Now defaults to true and persists.
     auto_approve_session: Arc<std::sync::atomic::AtomicBool>,
     /// Manages terminals created by the AI agent (visible in terminal panel).
     agent_terminal_mgr: Arc<AgentTerminalManager>,
+    /// Text of the open buffers, shared with the agent's file tools so they
+    /// see unsaved edits (see [`AgentOpenBuffers`]).
+    agent_buffers: Arc<Mutex<HashMap<PathBuf, Rope>>>,
+    /// Conversations with an agent turn running in this proxy.
+    running_turns: crate::agent_events::RunningTurns,
+    /// Where handlers that can't answer on this thread run, one pool per
+    /// [`RequestClass`] so agent work can't crowd out the editor's.
+    pools: WorkerPools,
+}
+
+/// Lets agent file tools read open buffers instead of the disk, and forwards
+/// their writes to the editor so they land as undoable edits.
+struct AgentOpenBuffers {
+    buffers: Arc<Mutex<HashMap<PathBuf, Rope>>>,
+    core_rpc: CoreRpcHandler,
+}
+
+impl forge_agent::tools::buffers::OpenBuffers for AgentOpenBuffers {
+    fn text(&self, path: &Path) -> Option<String> {
+        self.buffers.lock().get(path).map(|rope| rope.to_string())
+    }
+
+    fn written(&self, path: &Path, content: &str) {
+        if self.buffers.lock().contains_key(path) {
+            self.core_rpc
+                .agent_buffer_edit(path.to_path_buf(), content.to_string());
+        }
+    }
 }
 
 impl ProxyHandler for Dispatcher {
@@ -131,6 +197,7 @@ impl ProxyHandler for Dispatcher {
                 plugin_configurations,
                 window_id,
                 tab_id,
+                protocol,
             } => {
                 self.window_id = window_id;
                 self.tab_id = tab_id;
@@ -157,6 +224,20 @@ impl ProxyHandler for Dispatcher {
                     );
                     plugin_rpc.mainloop(&mut plugin);
                 });
+                // Before Connected, so the app knows what to expect when it resumes
+                use lapce_rpc::version::{Compatibility, ProtocolVersion};
+                match protocol.compatibility() {
+                    Compatibility::Same => {}
+                    Compatibility::Older | Compatibility::Newer => {
+                        tracing::info!("App speaks {protocol}, proxy {}", ProtocolVersion::CURRENT);
+                    }
+                    Compatibility::Incompatible => {
+                        tracing::warn!("App speaks {protocol}, which proxy {} can't serve", ProtocolVersion::CURRENT);
+                    }
+                }
+                self.core_rpc.notification(CoreNotification::ProxyProtocol {
+                    protocol: ProtocolVersion::CURRENT,
+                });
                 self.core_rpc.notification(CoreNotification::ProxyStatus {
                     status: lapce_rpc::proxy::ProxyStatus::Connected,
                 });
@@ -195,6 +276,7 @@ impl ProxyHandler for Dispatcher {
                     }
                 } else {
                     self.buffers.remove(&path);
+                    self.agent_buffers.lock().remove(&path);
                     self.core_rpc.open_file_changed(path, FileChanged::Delete);
                 }
             }
@@ -219,6 +301,7 @@ impl ProxyHandler for Dispatcher {
                 for (_, sender) in self.terminals.lock().unwrap().iter() {
                     sender.send(Msg::Shutdown);
                 }
+                forge_agent::tools::resources::shutdown(forge_agent::tools::resources::GRACE);
                 self.proxy_rpc.shutdown();
             }
             Update { path, delta, rev } => {
@@ -228,6 +311,9 @@ impl ProxyHandler for Dispatcher {
                 };
                 let old_text = buffer.rope.clone();
                 buffer.update(&delta, rev);
+                self.agent_buffers
+                    .lock()
+                    .insert(path.clone(), buffer.rope.clone());
                 self.catalog_rpc.did_change_text_document(
                     &path,
                     rev,
@@ -242,6 +328,7 @@ impl ProxyHandler for Dispatcher {
                 }
             }
             NewTerminal { term_id, profile } => {
+                let run_config = profile.run_config.clone();
                 let mut terminal = match Terminal::new(term_id, profile, 50, 10) {
                     Ok(terminal) => terminal,
                     Err(e) => {
@@ -263,6 +350,18 @@ impl ProxyHandler for Dispatcher {
                 }
 
                 self.core_rpc.terminal_process_id(term_id, child_id);
+                if let Some(name) = run_config {
+                    terminal.capture =
+                        Some(self.agent_terminal_mgr.track_run(&name, term_id));
+                    // Run configs are IDE-owned; the agent may stop them
+                    if let Some(pid) = child_id {
+                        forge_agent::tools::ownership::register(
+                            pid,
+                            &name,
+                            forge_agent::tools::ownership::ProcessOrigin::Ide,
+                        );
+                    }
+                }
                 let tx = terminal.tx.clone();
                 let poller = terminal.poller.clone();
                 let sender = TerminalSender::new(tx, poller);
@@ -270,6 +369,9 @@ impl ProxyHandler for Dispatcher {
                 let rpc = self.core_rpc.clone();
                 thread::spawn(move || {
                     terminal.run(rpc);
+                    if let Some(pid) = child_id {
+                        forge_agent::tools::ownership::unregister(pid);
+                    }
                 });
             }
             TerminalWrite { term_id, content } => {
@@ -298,6 +400,16 @@ impl ProxyHandler for Dispatcher {
                     tx.send(Msg::Shutdown);
                 }
             }
+            RecordRun { entry } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    crate::run_history::record(workspace, entry);
+                }
+            }
+            RunExited { name, exit_code } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    crate::run_history::set_exit_code(workspace, &name, exit_code);
+                }
+            }
             DapStart {
                 config,
                 breakpoints,
@@ -556,6 +668,9 @@ impl ProxyHandler for Dispatcher {
                     false,
                 );
             }
+            Unknown => {
+                tracing::debug!("Ignoring a notification from a newer app");
+            }
         }
     }
 
@@ -574,6 +689,9 @@ impl ProxyHandler for Dispatcher {
                     content.clone(),
                 );
                 self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
+                self.agent_buffers
+                    .lock()
+                    .insert(path.clone(), buffer.rope.clone());
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(
                     id,
@@ -621,7 +739,7 @@ impl ProxyHandler for Dispatcher {
                 let proxy_rpc = self.proxy_rpc.clone();
 
                 // Perform the search on another thread to avoid blocking the proxy thread
-                thread::spawn(move || {
+                self.pools.spawn(RequestClass::Interactive, move || {
                     proxy_rpc.handle_response(
                         id,
                         search_in_path(
@@ -1339,6 +1457,15 @@ impl ProxyHandler for Dispatcher {
                         proxy_rpc.handle_response(id, result);
                     });
             }
+            GetSyntaxSymbols { path } => {
+                let content = match self.buffers.get(&path) {
+                    Some(buffer) => buffer.rope.to_string(),
+                    None => std::fs::read_to_string(&path).unwrap_or_default(),
+                };
+                let resp = syntax_symbols(&path, &content);
+                self.proxy_rpc
+                    .handle_response(id, Ok(ProxyResponse::GetDocumentSymbols { resp }));
+            }
             GetWorkspaceSymbols { query } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc
@@ -1391,7 +1518,7 @@ impl ProxyHandler for Dispatcher {
             GetFiles { .. } => {
                 let workspace = self.workspace.clone();
                 let proxy_rpc = self.proxy_rpc.clone();
-                thread::spawn(move || {
+                self.pools.spawn(RequestClass::Interactive, move || {
                     let result = if let Some(workspace) = workspace {
                         let git_folder =
                             ignore::overrides::OverrideBuilder::new(&workspace)
@@ -1448,7 +1575,7 @@ impl ProxyHandler for Dispatcher {
             }
             ReadDir { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
-                thread::spawn(move || {
+                self.pools.spawn(RequestClass::Interactive, move || {
                     let result = fs::read_dir(path)
                         .map(|entries| {
                             let mut items = entries
@@ -1499,7 +1626,13 @@ impl ProxyHandler for Dispatcher {
                         
                         // ── Incremental Re-index on Save ──
                         // Fire-and-forget: update the cloud index for this file
-                        if forge_agent::forge_search::is_indexable_file(
+                        // (never for untrusted workspaces, which aren't uploaded).
+                        // The file watcher picks up saves when index_watch is on.
+                        let trusted = workspace.as_ref().is_some_and(|w| forge_agent::trust::is_trusted(w));
+                        if trusted
+                            && !forge_agent::index_watch::enabled()
+                            && !forge_agent::egress::local_only()
+                            && forge_agent::forge_search::is_indexable_file(
                             &path_clone.file_name()
                                 .map(|n| n.to_string_lossy().to_string())
                                 .unwrap_or_default()
@@ -1510,10 +1643,9 @@ impl ProxyHandler for Dispatcher {
                             
                             tokio::spawn(async move {
                                 let workspace_id = ws
-                                    .as_ref()
-                                    .and_then(|p| p.file_name())
-                                    .and_then(|n| n.to_str())
-                                    .unwrap_or("default");
+                                    .as_deref()
+                                    .map(forge_agent::forge_search::workspace_id)
+                                    .unwrap_or_else(|| "default".to_string());
                                 
                                 let rel_path = ws.as_ref()
                                     .and_then(|ws| file_path.strip_prefix(ws).ok())
@@ -1522,7 +1654,7 @@ impl ProxyHandler for Dispatcher {
                                 
                                 let client = forge_agent::forge_search::client();
                                 if let Err(e) = client.index_file(
-                                    workspace_id,
+                                    &workspace_id,
                                     &rel_path,
                                     &file_content
                                 ).await {
@@ -1558,6 +1690,9 @@ impl ProxyHandler for Dispatcher {
                         code: 0,
                         message: e.to_string(),
                     });
+                self.agent_buffers
+                    .lock()
+                    .insert(path.clone(), buffer.rope.clone());
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(id, result);
             }
@@ -1686,6 +1821,11 @@ impl ProxyHandler for Dispatcher {
                                     let new_path = to.join(suffix);
                                     buffer.path = new_path;
 
+                                    let mut agent_buffers = self.agent_buffers.lock();
+                                    agent_buffers.remove(&path);
+                                    agent_buffers
+                                        .insert(buffer.path.clone(), buffer.rope.clone());
+                                    drop(agent_buffers);
                                     self.buffers.insert(buffer.path.clone(), buffer);
                                 }
                             }
@@ -1696,6 +1836,10 @@ impl ProxyHandler for Dispatcher {
 
                             if let Some(mut buffer) = buffer {
                                 buffer.path.clone_from(&to);
+                                let mut agent_buffers = self.agent_buffers.lock();
+                                agent_buffers.remove(&from);
+                                agent_buffers.insert(to.clone(), buffer.rope.clone());
+                                drop(agent_buffers);
                                 self.buffers.insert(to.clone(), buffer);
                             }
                         }
@@ -1995,17 +2139,7 @@ impl ProxyHandler for Dispatcher {
                     let run_toml = workspace.join(".lapce").join("run.toml");
                     if run_toml.exists() {
                         tracing::info!("GetRunConfigs: Loading user configs from {:?}", run_toml);
-                        if let Ok(content) = std::fs::read_to_string(&run_toml) {
-                            #[derive(serde::Deserialize)]
-                            struct RunConfigs {
-                                configs: Vec<lapce_rpc::dap_types::RunDebugConfig>,
-                            }
-                            toml::from_str::<RunConfigs>(&content)
-                                .map(|c| c.configs)
-                                .unwrap_or_default()
-                        } else {
-                            Vec::new()
-                        }
+                        load_run_configs(workspace).configs
                     } else {
                         tracing::info!("GetRunConfigs: No run.toml file");
                         Vec::new()
@@ -2018,6 +2152,15 @@ impl ProxyHandler for Dispatcher {
                 self.respond_rpc(id, Ok(ProxyResponse::RunConfigsResponse { detected, user }));
             }
             
+            GetRunHistory {} => {
+                let entries = self
+                    .workspace
+                    .as_ref()
+                    .map(|workspace| crate::run_history::load(workspace))
+                    .unwrap_or_default();
+                self.respond_rpc(id, Ok(ProxyResponse::RunHistoryResponse { entries }));
+            }
+            
             SaveRunConfig { config } => {
                 let result = if let Some(workspace) = self.workspace.as_ref() {
                     match save_run_config_to_disk(workspace, config) {
@@ -2194,7 +2337,8 @@ impl ProxyHandler for Dispatcher {
             }
             DbConnect { connection_id } => {
                 match self.db_manager.connect(&connection_id) {
-                    Ok(schema) => {
+                    Ok(mut schema) => {
+                        link_table_sources(&mut schema, self.workspace.as_deref());
                         self.respond_rpc(
                             id,
                             Ok(ProxyResponse::DbConnectResponse {
@@ -2228,7 +2372,8 @@ impl ProxyHandler for Dispatcher {
             }
             DbGetSchema { connection_id } => {
                 match self.db_manager.get_schema(&connection_id) {
-                    Ok(schema) => {
+                    Ok(mut schema) => {
+                        link_table_sources(&mut schema, self.workspace.as_deref());
                         self.respond_rpc(
                             id,
                             Ok(ProxyResponse::DbSchemaResponse { schema }),
@@ -2315,20 +2460,29 @@ impl ProxyHandler for Dispatcher {
             }
 
             // ── AI Agent ─────────────────────────────────────────
-            AgentPrompt { prompt, provider, model, api_key, conversation_id: conv_id, attached_images } => {
+            AgentPrompt { prompt, provider, model, api_key, conversation_id: conv_id, attached_images, attachments, turn: prompt_turn, rewind } => {
                 tracing::info!("Agent prompt received, conv_id={conv_id}, provider={provider}, model={model}");
                 let proxy_rpc = self.proxy_rpc.clone();
-                let core_rpc = self.core_rpc.clone();
+                // Chat events go out numbered, so a reconnecting panel can resume
+                let (core_rpc, turn_guard) = crate::agent_events::begin_turn(&self.core_rpc, &self.running_turns, &conv_id, &prompt);
                 let workspace = self.workspace.clone();
                 let diff_snapshots = self.pending_diff_snapshots.clone();
                 let pending_approvals = self.pending_approvals.clone();
+                let pending_followups = self.pending_followups.clone();
                 let auto_approve_session = self.auto_approve_session.clone();
                 let agent_term_mgr = self.agent_terminal_mgr.clone();
                 let ide_terminals = self.terminals.clone();
                 let catalog_rpc = self.catalog_rpc.clone();
-                let _ = (provider, model, api_key); // Unused — all LLM calls go through forge-search
+                // Untrusted workspaces run in plan mode without commands or uploads
+                let restricted = self
+                    .workspace
+                    .as_ref()
+                    .is_some_and(|w| !forge_agent::trust::is_trusted(w));
+                // provider, model and api_key are only used by the local backend;
+                // the cloud agent picks its own model
 
                 thread::spawn(move || {
+                    let _turn = turn_guard;
                     let rt = match tokio::runtime::Runtime::new() {
                         Ok(rt) => rt,
                         Err(e) => {
@@ -2342,20 +2496,64 @@ impl ProxyHandler for Dispatcher {
                         }
                     };
 
-                    rt.block_on(async move {
+                    // Files this message edits stay locked to it until it's done
+                    let lock_owner = forge_agent::tools::locks::Owner {
+                        id: conv_id.clone(),
+                        task: format!("the conversation \"{}\"", prompt.trim().chars().take(60).collect::<String>()),
+                    };
+                    let _file_locks = forge_agent::tools::locks::TurnLocks::new(&lock_owner);
+                    rt.block_on(forge_agent::tools::locks::scope(lock_owner, async move {
                         let workspace_path = workspace
                             .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+                        // Re-read global/profile/workspace settings every turn
+                        forge_agent::config::activate(&workspace_path);
+                        forge_agent::auth::refresh_all().await;
 
                         // ══════════════════════════════════════════════════════
-                        // All LLM calls go through forge-search cloud.
-                        // Uses the /chat/stream endpoint (SSE) with real-time event streaming.
+                        // LLM calls go through forge-search cloud (/chat/stream,
+                        // SSE), or straight to the user's provider with the local
+                        // backend (always in local-only mode). Both stream the
+                        // same events and the tools run here either way.
                         // ══════════════════════════════════════════════════════
-                        let workspace_name = workspace_path
-                            .file_name()
-                            .map(|n| n.to_string_lossy().to_string())
-                            .unwrap_or_else(|| "default".to_string());
-
-                        let fs_client = forge_agent::forge_search::client();
+                        let local_agent = forge_agent::api::direct::selected();
+                        // A scripted model (provider = "mock") wins over both,
+                        // so the dispatcher tests can run a whole turn offline
+                        let mocked = forge_agent::config::active().get("provider") == Some("mock");
+                        let chat_api = if local_agent && !mocked {
+                            forge_agent::api::direct::DirectProvider::new(&provider, &model, Some(api_key))
+                                .map(|direct| std::sync::Arc::new(direct) as std::sync::Arc<dyn forge_agent::api::ChatApi>)
+                        } else {
+                            forge_agent::api::chat_api()
+                        };
+                        let chat_api = match chat_api {
+                            Ok(chat_api) => chat_api,
+                            Err(e) => {
+                                let error = e.to_string();
+                                core_rpc.agent_error(error.clone());
+                                proxy_rpc.handle_response(id, Ok(ProxyResponse::AgentError { error }));
+                                return;
+                            }
+                        };
+                        let cloud = !forge_agent::egress::local_only();
+
+                        let workspace_name = forge_agent::forge_search::workspace_id(&workspace_path);
+
+                        // Turn boundary: note what changed outside the agent since
+                        // the previous message (badge in the chat header), and the
+                        // refreshed workspace summary for the prompt
+                        let turn_changes = forge_agent::tools::workspace_diff::begin_turn(&workspace_path);
+                        let change_feed = forge_agent::tools::workspace_diff::change_feed(&turn_changes);
+                        let workspace_summary = forge_agent::tools::workspace_diff::summary(&workspace_path);
+                        // Files the user @-mentions rank higher in codebase_search
+                        forge_agent::tools::working_set::record_mentions(&workspace_path, &prompt);
+                        let external_changes: Vec<String> = turn_changes
+                            .into_iter()
+                            .filter(|c| c.origin == forge_agent::tools::workspace_diff::ChangeOrigin::External)
+                            .map(|c| c.path)
+                            .collect();
+                        core_rpc.notification(CoreNotification::AgentWorkspaceChanges {
+                            files: external_changes.clone(),
+                        });
                         
                         // Auto-Index
                         core_rpc.notification(CoreNotification::AgentToolCallUpdate {
@@ -2366,10 +2564,28 @@ impl ProxyHandler for Dispatcher {
                             output: None,
                         });
                         
-                        let (was_indexed, symbol_count) = 
-                            forge_agent::tools::ensure_indexed(&workspace_path).await;
+                        // Indexing uploads the code, so not for untrusted workspaces
+                        // or in local-only mode
+                        let (was_indexed, symbol_count) = if restricted || !cloud {
+                            (false, 0)
+                        } else {
+                            forge_agent::tools::ensure_indexed(&workspace_path).await
+                        };
+                        // Extra roots are indexed as workspaces of their own
+                        let roots = forge_agent::workspace_roots::WorkspaceRoots::discover(&workspace_path);
+                        for root in roots.iter().skip(1).filter(|_| !restricted && cloud) {
+                            forge_agent::tools::ensure_indexed(&root.path).await;
+                        }
+                        // Snapshot them too, so the prompt can say what language each is in
+                        for root in roots.iter().skip(1) {
+                            forge_agent::tools::workspace_diff::begin_turn(&root.path);
+                        }
                         
-                        let index_msg = if was_indexed {
+                        let index_msg = if restricted {
+                            "Restricted mode: workspace not trusted, indexing skipped".to_string()
+                        } else if !cloud {
+                            "Local-only mode: indexing skipped".to_string()
+                        } else if was_indexed {
                             format!("Workspace ready ({} symbols indexed)", symbol_count)
                         } else if symbol_count > 0 {
                             format!("Indexed {} symbols", symbol_count)
@@ -2385,24 +2601,107 @@ impl ProxyHandler for Dispatcher {
                             output: None,
                         });
                         
-                        let attached_files = collect_relevant_files(&workspace_path);
+                        let mut attached_files = if restricted { Vec::new() } else { collect_relevant_files(&workspace_path) };
+                        for root in roots.iter().skip(1).filter(|_| !restricted) {
+                            // One key file (the manifest) per extra root
+                            for mut file in collect_relevant_files(&root.path).into_iter().take(1) {
+                                let path = format!("{}/{}", root.name, file["path"].as_str().unwrap_or_default());
+                                file["path"] = serde_json::Value::String(path);
+                                attached_files.push(file);
+                            }
+                        }
+                        // Dependencies mentioned in the prompt, with the versions the
+                        // workspace actually uses, so server-side doc prefetching can
+                        // fetch version-matched docs and skip libraries we don't use.
+                        let workspace_deps = forge_agent::manifest::workspace_dependencies(&workspace_path);
+                        let prompt_deps = forge_agent::manifest::relevant_dependencies(&prompt, &workspace_deps);
                         let conversation_id = format!("{}-{}", workspace_name, conv_id);
                         let mut tool_results: Vec<serde_json::Value> = Vec::new();
                         let mut is_first_turn = true;
+                        // Pre-edit file copies for "retry from here", keyed by the chat's conversation id
+                        let checkpoints = forge_agent::checkpoint::Checkpoints::for_conversation(&conv_id);
+                        // Kept file edits of this message, as (call id, edit), for the review panel
+                        let mut turn_edits: Vec<(String, forge_agent::tools::FileEditMeta)> = Vec::new();
+                        // Completion, commits and test runs of this message, for its summary
+                        let mut task_log = forge_agent::changelog::TaskLog::start(&workspace_path);
                         let mut turn = 0;
+                        let edit_format = forge_agent::edit_format::EditFormat::configured();
+                        let mut self_correction = forge_agent::self_correction::SelfCorrection::from_config();
+                        // Follow-up for text edits that failed to apply
+                        let mut repair_question: Option<String> = None;
+                        let mut repair_rounds = 0;
+                        // Tools offered to the model (per-model limits, disabled categories)
+                        let tool_selection = forge_agent::tools::selection::ToolSelection::configured();
+                        let mut active_tools = tool_selection.select(forge_agent::tools::definitions(restricted));
+                        if restricted {
+                            active_tools.retain(|d| d["name"].as_str().is_some_and(forge_agent::trust::allowed_when_restricted));
+                        }
+                        
+                        // Status row in the chat panel and status bar while this runs
+                        let progress = Arc::new(Mutex::new(forge_agent::progress::Progress::new()));
+                        let _progress_ticker = ProgressTicker::start(progress.clone(), core_rpc.clone());
                         
                         loop {
                             turn += 1;
+                            progress.lock().next_step();
                             let mut chat_req = serde_json::json!({
                                 "workspace_id": workspace_name,
                                 "conversation_id": conversation_id,
                             });
+                            if let Some(question) = repair_question.take() {
+                                chat_req["question"] = serde_json::Value::String(question);
+                            }
+                            forge_agent::tools::selection::apply(&mut chat_req, &tool_selection, &active_tools);
+                            if restricted {
+                                chat_req["allowed_tools"] = serde_json::json!(forge_agent::tools::selection::names(&active_tools));
+                            }
                             
                             if is_first_turn {
-                                chat_req["question"] = serde_json::Value::String(prompt.clone());
+                                // Question plus workspace context, from the (user-overridable) turn template
+                                let mut vars = std::collections::HashMap::new();
+                                vars.insert("question", prompt.clone());
+                                vars.insert("attachments", forge_agent::attachments::prompt_section(
+                                    attachments.iter().map(|a| (a.filename.as_str(), a.data.as_str())),
+                                ));
+                                vars.insert("mode", if restricted { "plan" } else { "agent" }.to_string());
+                                if restricted {
+                                    vars.insert("restricted", forge_agent::trust::RESTRICTED_PROMPT.to_string());
+                                }
+                                vars.insert("roots", roots.prompt_section().trim_end().to_string());
+                                vars.insert("workspace", workspace_summary.as_ref().map(|s| s.to_string()).unwrap_or_default());
+                                vars.insert("changes", change_feed.clone().unwrap_or_default());
+                                if !external_changes.is_empty() {
+                                    let shown: Vec<&str> = external_changes.iter().take(20).map(String::as_str).collect();
+                                    let more = external_changes.len().saturating_sub(shown.len());
+                                    vars.insert("external_changes", format!(
+                                        "{}{}",
+                                        shown.join(", "),
+                                        if more > 0 { format!(" and {more} more") } else { String::new() },
+                                    ));
+                                }
+                                let memory = [
+                                    forge_agent::project_memory::load_global(),
+                                    forge_agent::project_memory::load_workspace(&workspace_path),
+                                ];
+                                vars.insert("memory", memory.iter().filter(|m| !m.is_empty()).cloned().collect::<Vec<_>>().join("\n\n"));
+                                vars.insert("rules", forge_agent::prompt_template::workspace_fragments(&workspace_path));
+                                vars.insert("respond_in", forge_agent::i18n::response_language().unwrap_or_default());
+                                vars.insert("edit_format", edit_format.instructions().to_string());
+                                vars.insert("tools", forge_agent::tools::selection::prompt_section(&tool_selection, &active_tools));
+                                let template = forge_agent::prompt_template::template(forge_agent::prompt_template::TURN_TEMPLATE);
+                                let question = forge_agent::prompt_template::render(&template, &vars);
+                                chat_req["question"] = serde_json::Value::String(question);
                                 if !attached_files.is_empty() {
                                     chat_req["attached_files"] = serde_json::json!(attached_files);
                                 }
+                                if rewind {
+                                    // Retrying from an earlier message: drop the server's history from there
+                                    chat_req["rewind_to_turn"] = serde_json::json!(prompt_turn);
+                                }
+                                // (doc prefetching is a cloud service)
+                                if !prompt_deps.is_empty() && cloud {
+                                    chat_req["dependencies"] = serde_json::json!(prompt_deps);
+                                }
                                 // Include pasted/attached images (base64)
                                 if !attached_images.is_empty() {
                                     let images_json: Vec<serde_json::Value> = attached_images.iter().map(|img| {
@@ -2421,12 +2720,24 @@ impl ProxyHandler for Dispatcher {
                                 chat_req["tool_results"] = serde_json::Value::Array(tool_results.clone());
                                 tool_results.clear();
                             }
+                            // Strong model for the user's question, the executor
+                            // model for turns that only return tool results
+                            let role = forge_agent::model_routing::ModelRole::for_turn(
+                                chat_req.get("question").is_some(),
+                                false,
+                            );
+                            forge_agent::model_routing::apply(&mut chat_req, role);
 
-                            tracing::info!("Cloud chat turn {} for {}", turn, conversation_id);
+                            tracing::info!(
+                                "{} chat turn {} for {}",
+                                if local_agent { "Local" } else { "Cloud" },
+                                turn,
+                                conversation_id
+                            );
 
-                            // ── SSE Streaming request to /chat/stream ──
+                            // ── Streaming chat turn ──
                             use futures_util::StreamExt;
-                            match fs_client.chat_stream(&chat_req).await {
+                            match chat_api.chat_stream(&chat_req).await {
                                 Ok(mut stream) => {
                                     // Process SSE events as they arrive
                                     let mut final_answer = String::new();
@@ -2435,6 +2746,8 @@ impl ProxyHandler for Dispatcher {
                                     // Track whether any text arrived via text_delta so we can
                                     // detect silent loss and fall back to done.answer.
                                     let mut streamed_any_text = false;
+                                    // Reported (input, output) tokens of this model call
+                                    let mut turn_usage = None;
                                     
                                     while let Some(event) = stream.next().await {
                                         use forge_agent::forge_search::SseEvent;
@@ -2443,6 +2756,7 @@ impl ProxyHandler for Dispatcher {
                                             // Agent reasoning/thinking
                                             SseEvent::Thinking { step_type, message, detail } => {
                                                 tracing::debug!("[SSE] Thinking: {}: {}", step_type, &message[..message.len().min(50)]);
+                                                progress.lock().set_activity(message.clone());
                                                 core_rpc.agent_thinking_step(step_type, message, detail);
                                             }
                                             
@@ -2461,12 +2775,18 @@ impl ProxyHandler for Dispatcher {
                                                     }
                                                     other => other.to_string(),
                                                 };
+                                                progress.lock().tool_started(&tool_name, &forge_agent::agent_loop::describe(&tool_name, &arguments));
+                                                if let Some((kind, text)) = forge_agent::session::note_from_tool(&tool_name, &arguments) {
+                                                    record_working_note(&core_rpc, &conv_id, kind, &text);
+                                                }
+                                                task_log.record_call(&tool_name, &arguments);
                                                 core_rpc.agent_server_tool_start(tool_call_id, tool_name, args_str);
                                             }
                                             
                                             // Server tool execution completed
                                             SseEvent::ToolEnd { tool_call_id, tool_name, result_summary, success } => {
                                                 tracing::info!("[SSE] Server tool completed: {} (id={}, success={})", tool_name, tool_call_id, success);
+                                                progress.lock().tool_finished();
                                                 core_rpc.agent_server_tool_end(tool_call_id, tool_name, result_summary, success);
                                             }
                                             
@@ -2475,6 +2795,7 @@ impl ProxyHandler for Dispatcher {
                                                 if !text.is_empty() {
                                                     tracing::debug!("[SSE] Text delta: {} chars", text.len());
                                                     core_rpc.agent_text_chunk(text.clone(), false);
+                                                    progress.lock().text(&text);
                                                     final_answer.push_str(&text);
                                                     streamed_any_text = true;
                                                 }
@@ -2516,6 +2837,12 @@ impl ProxyHandler for Dispatcher {
                                                 }).collect();
                                             }
                                             
+                                            // Token usage of this model call
+                                            SseEvent::Usage { input_tokens, output_tokens } => {
+                                                progress.lock().usage(input_tokens, output_tokens);
+                                                turn_usage = Some((input_tokens, output_tokens));
+                                            }
+                                            
                                             // Stream complete
                                             SseEvent::Done { answer } => {
                                                 if let Some(ans) = answer {
@@ -2537,6 +2864,23 @@ impl ProxyHandler for Dispatcher {
                                         }
                                     }
                                     
+                                    if let Some((input_tokens, output_tokens)) = turn_usage {
+                                        // The cloud agent bills through forge-search, with its own model
+                                        let (usage_provider, default_model) = if local_agent {
+                                            (provider.as_str(), model.as_str())
+                                        } else {
+                                            ("forge-search", "cloud")
+                                        };
+                                        let turn_model = chat_req.get("model").and_then(|m| m.as_str()).unwrap_or(default_model);
+                                        let mut record = forge_agent::usage::UsageRecord::new(
+                                            &conv_id, usage_provider, turn_model, input_tokens, output_tokens,
+                                        );
+                                        if chat_req.get("question").is_some() {
+                                            record = record.with_title(&prompt);
+                                        }
+                                        forge_agent::usage::record(&record);
+                                    }
+
                                     // Fallback: if no text_delta events arrived but the done event
                                     // carried an answer (e.g. all chunks were split at boundaries and
                                     // the buffer fix wasn't enough, or the server sent the answer
@@ -2546,6 +2890,25 @@ impl ProxyHandler for Dispatcher {
                                         tracing::warn!("[SSE] No text_delta events received — using done.answer as fallback ({} chars)", final_answer.len());
                                         core_rpc.agent_text_chunk(final_answer.clone(), false);
                                     }
+
+                                    // Edits written in the answer (text edit formats) run as the
+                                    // equivalent edit tool calls, through the same review flow
+                                    let mut text_edits = false;
+                                    if final_status != "requires_action" && edit_format.is_text() {
+                                        let calls = forge_agent::edit_format::tool_calls(
+                                            edit_format,
+                                            &final_answer,
+                                            &format!("text-edit-{turn}"),
+                                        );
+                                        if !calls.is_empty() {
+                                            tracing::info!("Applying {} {} edits from the answer", calls.len(), edit_format.as_str());
+                                            final_status = "requires_action".to_string();
+                                            text_edits = true;
+                                            ide_tool_calls = calls.iter().map(|tc| {
+                                                serde_json::json!({ "id": tc.id, "name": tc.name, "args": tc.args })
+                                            }).collect();
+                                        }
+                                    }
                                     
                                     // Now handle IDE tool calls if needed (same logic as before)
                                     if final_status == "requires_action" && !ide_tool_calls.is_empty() {
@@ -2557,6 +2920,8 @@ impl ProxyHandler for Dispatcher {
                                             
                                             let mut safe_calls = Vec::new();
                                             let mut risky_calls = Vec::new();
+                                            // Questions for the user, asked after the other calls ran
+                                            let mut questions = Vec::new();
                                             
                                             for tc_val in &ide_tool_calls {
                                                 let tc_id = tc_val.get("id").and_then(|i| i.as_str()).unwrap_or("").to_string();
@@ -2565,6 +2930,48 @@ impl ProxyHandler for Dispatcher {
                                                 
                                                 if tc_name.is_empty() { continue; }
                                                 has_tool_calls = true;
+
+                                                // Untrusted workspace: nothing that edits, runs, or fetches
+                                                if restricted && !forge_agent::trust::allowed_when_restricted(&tc_name) {
+                                                    let output = forge_agent::trust::refusal(&tc_name);
+                                                    core_rpc.notification(CoreNotification::AgentToolCallUpdate {
+                                                        tool_call_id: tc_id.clone(),
+                                                        tool_name: tc_name.clone(),
+                                                        arguments: serde_json::to_string(&tc_args).unwrap_or_default(),
+                                                        status: "failed".to_string(),
+                                                        output: Some(output.clone()),
+                                                    });
+                                                    tool_results.push(serde_json::json!({
+                                                        "call_id": tc_id,
+                                                        "output": output,
+                                                        "success": false,
+                                                    }));
+                                                    continue;
+                                                }
+                                                
+                                                if tc_name == "ask_followup_question" {
+                                                    questions.push((tc_id, tc_args));
+                                                    continue;
+                                                }
+                                                if tc_name == "focus_chain" {
+                                                    let result = update_focus_chain(&core_rpc, &conv_id, &tc_args);
+                                                    core_rpc.notification(CoreNotification::AgentToolCallUpdate {
+                                                        tool_call_id: tc_id.clone(),
+                                                        tool_name: tc_name.clone(),
+                                                        arguments: serde_json::to_string(&tc_args).unwrap_or_default(),
+                                                        status: if result.success { "completed" } else { "failed" }.to_string(),
+                                                        output: Some(result.output.clone()),
+                                                    });
+                                                    tool_results.push(serde_json::json!({
+                                                        "call_id": tc_id,
+                                                        "output": result.output,
+                                                        "success": result.success,
+                                                    }));
+                                                    continue;
+                                                }
+                                                if let Some((kind, text)) = forge_agent::session::note_from_tool(&tc_name, &tc_args) {
+                                                    record_working_note(&core_rpc, &conv_id, kind, &text);
+                                                }
                                                 
                                                 let cmd_str = tc_args.get("command").and_then(|c| c.as_str()).unwrap_or("");
                                                 let is_run_tool = matches!(tc_name.as_str(),
@@ -2576,11 +2983,16 @@ impl ProxyHandler for Dispatcher {
                                                 };
                                                 
                                                 let is_file_edit = matches!(tc_name.as_str(), 
-                                                    "write_file" | "edit_file" | "apply_patch" | "delete_file"
+                                                    "write_file" | "write_file_range" | "edit_file" | "apply_patch" | "delete_file"
                                                     | "write_to_file" | "replace_in_file"); // legacy aliases
                                                 
-                                                let is_risky_command = is_run_tool
-                                                    && !is_safe_command;
+                                                // Plugin tools run arbitrary plugin code
+                                                let is_risky_command = (is_run_tool
+                                                    && !is_safe_command)
+                                                    || tc_name == "generate_tests"
+                                                    || (tc_name == "plugins" && !matches!(tc_args["action"].as_str(), Some("search" | "list")))
+                                                    || (tc_name == "modify_settings" && tc_args["preview"].as_bool() != Some(true))
+                                                    || forge_agent::tools::plugin::is_registered(&tc_name);
                                                 
                                                 // lsp rename (new and legacy) is risky
                                                 let is_risky_lsp = matches!(tc_name.as_str(), "lsp" | "lsp_rename")
@@ -2598,6 +3010,10 @@ impl ProxyHandler for Dispatcher {
                                             
                                             // 1. Execute safe calls in parallel
                                             if !safe_calls.is_empty() {
+                                                match safe_calls.as_slice() {
+                                                    [(_, name, args)] => progress.lock().tool_started(name, &forge_agent::agent_loop::describe(name, args)),
+                                                    calls => progress.lock().set_activity(format!("Running {} tools", calls.len())),
+                                                }
                                                 let mut futures = Vec::new();
                                                 for (tc_id, tc_name, tc_args) in safe_calls {
                                                     let args_json = serde_json::to_string(&tc_args).unwrap_or_default();
@@ -2636,6 +3052,7 @@ impl ProxyHandler for Dispatcher {
                                                 }
                                                 
                                                 let results = futures_util::future::join_all(futures).await;
+                                                progress.lock().tool_finished();
                                                 for (tc_id, tc_name, result) in results {
                                                     core_rpc.notification(CoreNotification::AgentToolCallUpdate {
                                                         tool_call_id: tc_id.clone(),
@@ -2647,7 +3064,7 @@ impl ProxyHandler for Dispatcher {
                                                     
                                                     tool_results.push(serde_json::json!({
                                                         "call_id": tc_id,
-                                                        "output": result.output,
+                                                        "output": forge_agent::output_masking::postprocess(&tc_name, &result.output, &workspace_path).await,
                                                         "success": result.success,
                                                     }));
                                                 }
@@ -2656,6 +3073,9 @@ impl ProxyHandler for Dispatcher {
                                             // Tools that always require explicit approval even when auto-approve is on
                                             const ALWAYS_ASK: &[&str] = &["delete_file"];
 
+                                            // Files edited (and kept) this turn, as (call id, path)
+                                            let mut edited: Vec<(String, String)> = Vec::new();
+
                                             // 2. Execute risky calls sequentially
                                             for (tc_id, tc_name, tc_args, is_file_edit) in risky_calls {
                                                 let args_json = serde_json::to_string(&tc_args).unwrap_or_default();
@@ -2664,9 +3084,17 @@ impl ProxyHandler for Dispatcher {
                                                     // ═══ FILE EDIT FLOW: Execute First, Ask After ═══
                                                     
                                                     // 1. Save snapshot before modifying
+                                                    if let Some(checkpoints) = &checkpoints {
+                                                        for path in forge_agent::checkpoint::paths_touched(&tc_name, &tc_args) {
+                                                            if let Err(e) = checkpoints.record(prompt_turn, &workspace_path, &path) {
+                                                                tracing::warn!("Checkpoint for {path} failed: {e}");
+                                                            }
+                                                        }
+                                                    }
                                                     if let Some(path) = tc_args.get("path").and_then(|p| p.as_str()) {
+                                                        self_correction.before_edit(path, &workspace_path).await;
                                                         let full_path = workspace_path.join(path);
-                                                        if let Ok(old_content) = std::fs::read_to_string(&full_path) {
+                                                        if let Ok(old_content) = forge_agent::tools::buffers::read_to_string(&full_path) {
                                                             diff_snapshots.lock().insert(
                                                                 tc_id.clone(),
                                                                 (path.to_string(), old_content)
@@ -2689,6 +3117,7 @@ impl ProxyHandler for Dispatcher {
                                                         name: tc_name.clone(),
                                                         args: tc_args.clone(),
                                                     };
+                                                    progress.lock().tool_started(&tc_name, &forge_agent::agent_loop::describe(&tc_name, &tc_args));
                                                     let result = execute_ide_tool(
                                                         &tc_info,
                                                         &workspace_path,
@@ -2697,20 +3126,27 @@ impl ProxyHandler for Dispatcher {
                                                         &ide_terminals,
                                                         &catalog_rpc,
                                                     ).await;
+                                                    progress.lock().tool_finished();
 
                                                     if !result.success {
-                                                        // Tool failed, no need for approval
+                                                        // Tool failed, no need for approval. A conflict (the user
+                                                        // edited the file since the agent read it) gets its own status.
+                                                        let status = if forge_agent::tools::buffers::is_conflict(&result.output) {
+                                                            "conflict"
+                                                        } else {
+                                                            "failed"
+                                                        };
                                                         core_rpc.notification(CoreNotification::AgentToolCallUpdate {
                                                             tool_call_id: tc_id.clone(),
                                                             tool_name: tc_name.clone(),
                                                             arguments: String::new(),
-                                                            status: "failed".to_string(),
+                                                            status: status.to_string(),
                                                             output: Some(result.output.clone()),
                                                         });
                                                         diff_snapshots.lock().remove(&tc_id); // Remove snapshot
                                                         tool_results.push(serde_json::json!({
                                                             "call_id": tc_id,
-                                                            "output": result.output,
+                                                            "output": forge_agent::output_masking::postprocess(&tc_name, &result.output, &workspace_path).await,
                                                             "success": false,
                                                         }));
                                                         continue;
@@ -2720,7 +3156,7 @@ impl ProxyHandler for Dispatcher {
                                                     let path = tc_args.get("path").and_then(|p| p.as_str()).unwrap_or("?");
                                                     let summary = match tc_name.as_str() {
                                                         "write_file" | "write_to_file" => format!("Created/wrote: {}", path),
-                                                        "edit_file" | "replace_in_file" => format!("Edited: {}", path),
+                                                        "edit_file" | "write_file_range" | "replace_in_file" => format!("Edited: {}", path),
                                                         "apply_patch" => "Applied multi-file patch".to_string(),
                                                         "delete_file" => format!("Deleted: {}", path),
                                                         _ => format!("Modified: {}", path),
@@ -2738,6 +3174,7 @@ impl ProxyHandler for Dispatcher {
                                                         status: "awaiting_review".to_string(),
                                                         output: Some(format!("{} — Accept to keep, Reject to revert", summary)),
                                                     });
+                                                    progress.lock().set_activity(format!("Waiting for review: {summary}"));
                                                     
                                                     // Wait for user review (skip if auto-approve is on)
                                                     // delete_file is always dangerous — always ask
@@ -2755,31 +3192,27 @@ impl ProxyHandler for Dispatcher {
                                                         });
                                                         true
                                                     } else {
-                                                        let (tx, rx) = tokio::sync::oneshot::channel::<bool>();
-                                                        pending_approvals.lock().insert(tc_id.clone(), tx);
-                                                        // 5-minute timeout: if user doesn't respond, auto-reject and
-                                                        // report to forge-search so it isn't left waiting forever.
-                                                        match tokio::time::timeout(
-                                                            std::time::Duration::from_secs(300),
-                                                            rx,
-                                                        ).await {
-                                                            Ok(Ok(v)) => v,
-                                                            Ok(Err(_)) | Err(_) => {
-                                                                tracing::warn!(
-                                                                    "[APPROVAL] Timed out waiting for user review of {} ({})",
-                                                                    tc_name, tc_id
-                                                                );
-                                                                pending_approvals.lock().remove(&tc_id);
-                                                                false
-                                                            }
-                                                        }
+                                                        use forge_agent::approvals::ApprovalKind;
+                                                        let preview = diff_snapshots.lock().get(&tc_id).map(|(rel_path, old_content)| {
+                                                            let new_content = forge_agent::tools::buffers::read_to_string(&workspace_path.join(rel_path)).unwrap_or_default();
+                                                            forge_agent::approvals::edit_preview(rel_path, old_content, &new_content)
+                                                        });
+                                                        await_approval(
+                                                            &pending_approvals,
+                                                            &core_rpc,
+                                                            &tc_id,
+                                                            &tc_name,
+                                                            ApprovalKind::of(&tc_name, true),
+                                                            &summary,
+                                                            preview.unwrap_or_default(),
+                                                        ).await
                                                     };
 
                                                     if !approved {
                                                         // User rejected or timed out — revert from snapshot
                                                         if let Some((rel_path, old_content)) = diff_snapshots.lock().remove(&tc_id) {
                                                             let full_path = workspace_path.join(&rel_path);
-                                                            if let Err(e) = std::fs::write(&full_path, &old_content) {
+                                                            if let Err(e) = forge_agent::tools::buffers::write(&full_path, &old_content) {
                                                                 tracing::error!("Failed to revert {}: {}", rel_path, e);
                                                             } else {
                                                                 tracing::info!("Reverted {} (rejected by user)", rel_path);
@@ -2804,17 +3237,23 @@ impl ProxyHandler for Dispatcher {
                                                     
                                                     // Approved — clear snapshot and report success
                                                     diff_snapshots.lock().remove(&tc_id);
+                                                    if tc_name != "delete_file" && path != "?" {
+                                                        edited.push((tc_id.clone(), path.to_string()));
+                                                    }
+                                                    if let Some(meta) = &result.file_edit {
+                                                        turn_edits.push((tc_id.clone(), meta.clone()));
+                                                    }
                                                     core_rpc.notification(CoreNotification::AgentToolCallUpdate {
                                                         tool_call_id: tc_id.clone(),
                                                         tool_name: tc_name.clone(),
                                                         arguments: String::new(),
                                                         status: "accepted".to_string(),
-                                                        output: Some("Changes accepted".to_string()),
+                                                        output: Some(forge_agent::i18n::tr("approval.accepted", &[])),
                                                     });
                                                     
                                                     tool_results.push(serde_json::json!({
                                                         "call_id": tc_id,
-                                                        "output": result.output,
+                                                        "output": forge_agent::output_masking::postprocess(&tc_name, &result.output, &workspace_path).await,
                                                         "success": true,
                                                     }));
                                                     
@@ -2823,11 +3262,13 @@ impl ProxyHandler for Dispatcher {
                                                     // (execute_command, execute_background, lsp_rename)
                                                     
                                                     let cmd_str = tc_args.get("command").and_then(|v| v.as_str()).unwrap_or("?");
+                                                    use forge_agent::i18n::tr;
                                                     let summary = match tc_name.as_str() {
-                                                        "run" | "execute_command" => format!("Run command: {}", cmd_str),
-                                                        "execute_background" => format!("Start background process: {}", cmd_str),
-                                                        "lsp" | "lsp_rename" => format!("Rename symbol to: {}", tc_args.get("new_name").and_then(|v| v.as_str()).unwrap_or("?")),
-                                                        _ => format!("Execute risky tool: {}", tc_name),
+                                                        "run" | "execute_command" => tr("approval.run_command", &[cmd_str]),
+                                                        "execute_background" => tr("approval.start_background", &[cmd_str]),
+                                                        "lsp" | "lsp_rename" => tr("approval.rename", &[tc_args.get("new_name").and_then(|v| v.as_str()).unwrap_or("?")]),
+                                                        "generate_tests" => tr("approval.generate_tests", &[tc_args.get("symbol").and_then(|v| v.as_str()).unwrap_or("?")]),
+                                                        _ => tr("approval.risky_tool", &[&tc_name]),
                                                     };
                                                     
                                                     // Request approval (skip if auto-approve is on; delete_file always asks)
@@ -2840,7 +3281,7 @@ impl ProxyHandler for Dispatcher {
                                                             tool_name: tc_name.clone(),
                                                             arguments: args_json.clone(),
                                                             status: "running".to_string(),
-                                                            output: Some("Executing command...".to_string()),
+                                                            output: Some(tr("status.executing", &[])),
                                                         });
                                                         true
                                                     } else {
@@ -2849,26 +3290,23 @@ impl ProxyHandler for Dispatcher {
                                                             tool_name: tc_name.clone(),
                                                             arguments: args_json.clone(),
                                                             status: "waiting_approval".to_string(),
-                                                            output: Some(format!("{} — Accept to run, Reject to skip", summary)),
+                                                            output: Some(tr("approval.prompt", &[&summary])),
                                                         });
-                                                        let (tx, rx) = tokio::sync::oneshot::channel::<bool>();
-                                                        pending_approvals.lock().insert(tc_id.clone(), tx);
-                                                        // 5-minute timeout: if user doesn't respond, auto-reject and
-                                                        // report to forge-search so it isn't left waiting forever.
-                                                        match tokio::time::timeout(
-                                                            std::time::Duration::from_secs(300),
-                                                            rx,
-                                                        ).await {
-                                                            Ok(Ok(v)) => v,
-                                                            Ok(Err(_)) | Err(_) => {
-                                                                tracing::warn!(
-                                                                    "[APPROVAL] Timed out waiting for user approval of {} ({})",
-                                                                    tc_name, tc_id
-                                                                );
-                                                                pending_approvals.lock().remove(&tc_id);
-                                                                false
-                                                            }
-                                                        }
+                                                        progress.lock().set_activity(format!("Waiting for approval: {summary}"));
+                                                        use forge_agent::approvals::ApprovalKind;
+                                                        let preview = match tc_args.get("command").and_then(|v| v.as_str()) {
+                                                            Some(command) => format!("$ {command}"),
+                                                            None => serde_json::to_string_pretty(&tc_args).unwrap_or_default(),
+                                                        };
+                                                        await_approval(
+                                                            &pending_approvals,
+                                                            &core_rpc,
+                                                            &tc_id,
+                                                            &tc_name,
+                                                            ApprovalKind::of(&tc_name, false),
+                                                            &summary,
+                                                            preview,
+                                                        ).await
                                                     };
 
                                                     if !approved {
@@ -2877,7 +3315,7 @@ impl ProxyHandler for Dispatcher {
                                                             tool_name: tc_name.clone(),
                                                             arguments: String::new(),
                                                             status: "rejected".to_string(),
-                                                            output: Some("Command rejected by user".to_string()),
+                                                            output: Some(tr("approval.rejected", &[])),
                                                         });
                                                         
                                                         tool_results.push(serde_json::json!({
@@ -2894,9 +3332,10 @@ impl ProxyHandler for Dispatcher {
                                                         tool_name: tc_name.clone(),
                                                         arguments: args_json,
                                                         status: "running".to_string(),
-                                                        output: Some("Executing command...".to_string()),
+                                                        output: Some(tr("status.executing", &[])),
                                                     });
                                                     
+                                                    progress.lock().tool_started(&tc_name, &forge_agent::agent_loop::describe(&tc_name, &tc_args));
                                                     let tc_info = forge_agent::ToolCallInfo {
                                                         id: tc_id.clone(),
                                                         name: tc_name.clone(),
@@ -2910,6 +3349,20 @@ impl ProxyHandler for Dispatcher {
                                                         &ide_terminals,
                                                         &catalog_rpc,
                                                     ).await;
+                                                    progress.lock().tool_finished();
+
+                                                    // Files the command changed: reviewable and revertable
+                                                    // with the turn, like file edits
+                                                    for change in &result.fs_changes {
+                                                        if let (Some(checkpoints), Some(before)) = (&checkpoints, change.before()) {
+                                                            if let Err(e) = checkpoints.record_content(prompt_turn, &change.path, before) {
+                                                                tracing::warn!("Checkpoint for {} failed: {e}", change.path);
+                                                            }
+                                                        }
+                                                        if let Some(meta) = change.edit_meta(&workspace_path) {
+                                                            turn_edits.push((tc_id.clone(), meta));
+                                                        }
+                                                    }
 
                                                     core_rpc.notification(CoreNotification::AgentToolCallUpdate {
                                                         tool_call_id: tc_id.clone(),
@@ -2921,19 +3374,117 @@ impl ProxyHandler for Dispatcher {
                                                     
                                                     tool_results.push(serde_json::json!({
                                                         "call_id": tc_id,
-                                                        "output": result.output,
+                                                        "output": forge_agent::output_masking::postprocess(&tc_name, &result.output, &workspace_path).await,
                                                         "success": result.success,
                                                     }));
                                                 }
                                             }
                                             
-                                            if has_tool_calls {
+                                            // 3. Ask the user, waiting for a clicked or typed answer
+                                            for (tc_id, tc_args) in questions {
+                                                use forge_agent::tools::display;
+                                                let result = match forge_agent::tools::args::parse::<forge_agent::tools::args::AskFollowupQuestionArgs>("ask_followup_question", &tc_args) {
+                                                    Ok(args) => {
+                                                        let args = display::followup(args);
+                                                        let (tx, rx) = tokio::sync::oneshot::channel::<String>();
+                                                        pending_followups.lock().insert(tc_id.clone(), tx);
+                                                        progress.lock().set_activity(format!("Waiting for your answer: {}", args.question));
+                                                        core_rpc.agent_followup_question(tc_id.clone(), args.question, args.options, args.default);
+                                                        let answer = tokio::time::timeout(FOLLOWUP_TIMEOUT, rx).await;
+                                                        pending_followups.lock().remove(&tc_id);
+                                                        display::followup_answer(answer.ok().and_then(Result::ok).as_deref())
+                                                    }
+                                                    Err(invalid) => invalid,
+                                                };
+                                                tool_results.push(serde_json::json!({
+                                                    "call_id": tc_id,
+                                                    "output": result.output,
+                                                    "success": result.success,
+                                                }));
+                                            }
+                                            
+                                            for result in &tool_results {
+                                                if let Some(call) = ide_tool_calls.iter().find(|c| c["id"] == result["call_id"]) {
+                                                    let name = call["name"].as_str().unwrap_or_default();
+                                                    task_log.record_result(name, &call["args"], result["success"] == true);
+                                                }
+                                            }
+                                            
+                                            // Lint what was edited; new errors go back to the model
+                                            let edited_paths: Vec<String> = edited.iter().map(|(_, p)| p.clone()).collect();
+                                            let mut correction = None;
+                                            match self_correction.after_edits(&edited_paths, &workspace_path).await {
+                                                forge_agent::self_correction::Check::Clean => {}
+                                                forge_agent::self_correction::Check::Retry(message) => correction = Some(message),
+                                                forge_agent::self_correction::Check::GiveUp(message) => {
+                                                    core_rpc.agent_text_chunk(format!("\n\n{message}"), false);
+                                                }
+                                            }
+                                            if let (Some(message), false) = (&correction, text_edits) {
+                                                let last_edit = edited.last().map(|(id, _)| id.as_str());
+                                                if let Some(result) = tool_results.iter_mut().find(|r| r["call_id"].as_str() == last_edit) {
+                                                    let output = result["output"].as_str().unwrap_or_default().to_string();
+                                                    result["output"] = serde_json::Value::String(format!("{output}\n\n{message}"));
+                                                }
+                                            }
+
+                                            if text_edits {
+                                                // The server did not ask for these calls: report
+                                                // failures as a new message instead of tool results
+                                                // (rejected edits are the user's call, not failures)
+                                                let failures: Vec<(String, String)> = tool_results.drain(..)
+                                                    .filter(|r| r["success"] == false)
+                                                    .filter_map(|r| {
+                                                        let output = r["output"].as_str().unwrap_or_default();
+                                                        if output.starts_with("User rejected") {
+                                                            return None;
+                                                        }
+                                                        let call = ide_tool_calls.iter().find(|c| c["id"] == r["call_id"])?;
+                                                        let path = call["args"]["path"].as_str().unwrap_or("?").to_string();
+                                                        Some((path, output.to_string()))
+                                                    })
+                                                    .collect();
+                                                let mut follow_up: Vec<String> = correction.into_iter().collect();
+                                                if !failures.is_empty() && repair_rounds < forge_agent::edit_format::MAX_REPAIR_ROUNDS {
+                                                    repair_rounds += 1;
+                                                    follow_up.insert(0, forge_agent::edit_format::repair_prompt(edit_format, &failures));
+                                                }
+                                                if !follow_up.is_empty() {
+                                                    repair_question = Some(follow_up.join("\n\n"));
+                                                    continue;
+                                                }
+                                            } else if has_tool_calls {
                                                 continue; // Loop back to send results to server
                                             }
                                     }
                                     
+                                    // Formatter pass over the completed task's files, so it lands in the same changes
+                                    if task_log.completed() && forge_agent::formatting::enabled() && !turn_edits.is_empty() {
+                                        let paths: Vec<String> = turn_edits.iter().map(|(_, meta)| meta.path.clone()).collect();
+                                        let workspace = workspace_path.clone();
+                                        let _ = tokio::task::spawn_blocking(move || forge_agent::formatting::format_files(&workspace, &paths)).await;
+                                    }
+
+                                    // Net changes of the whole turn, reviewable per file
+                                    let changes = turn_file_changes(&conv_id, prompt_turn, &turn_edits, &workspace_path);
+                                    let changed_files: Vec<_> = changes
+                                        .iter()
+                                        .map(|c| forge_agent::changelog::FileChange::new(&c.path, &c.old_content, &c.new_content))
+                                        .collect();
+                                    if !changes.is_empty() {
+                                        let mut snapshots = diff_snapshots.lock();
+                                        for change in &changes {
+                                            snapshots.insert(change.diff_id.clone(), (change.path.clone(), change.old_content.clone()));
+                                        }
+                                        drop(snapshots);
+                                        core_rpc.agent_turn_changes(changes);
+                                    }
+
                                     // Done
                                     core_rpc.agent_text_chunk(String::new(), true);
+                                    if let Some(summary) = task_log.summary(&workspace_path, changed_files) {
+                                        core_rpc.agent_task_summary(summary.title(), summary.markdown());
+                                    }
                                     proxy_rpc.handle_response(id, Ok(ProxyResponse::AgentDone {
                                         message: final_answer.clone(),
                                     }));
@@ -2948,9 +3499,21 @@ impl ProxyHandler for Dispatcher {
                                 }
                             }
                         }
-                    });
+                    }));
                 });
             }
+            AgentTaskStatus { conversation_id } => {
+                let approvals = self.pending_approvals.lock().keys().cloned().collect();
+                let status = crate::agent_events::task_status(&conversation_id, &self.running_turns, approvals);
+                self.respond_rpc(id, Ok(ProxyResponse::AgentTaskStatusResponse { status }));
+            }
+            AgentResume { conversation_id, last_seq } => {
+                let approvals = self.pending_approvals.lock().keys().cloned().collect();
+                let status = crate::agent_events::task_status(&conversation_id, &self.running_turns, approvals);
+                let events = crate::agent_events::missed_events(&conversation_id, last_seq);
+                tracing::info!("Resuming {conversation_id} after event {last_seq}: {} missed", events.len());
+                self.respond_rpc(id, Ok(ProxyResponse::AgentResumeResponse { status, events }));
+            }
             AgentCancel {} => {
                 // TODO: Cancel running agent task
                 tracing::info!("Agent cancel requested");
@@ -2962,77 +3525,26 @@ impl ProxyHandler for Dispatcher {
                 tracing::info!("Audio transcription requested ({} bytes)", audio_data.len());
                 let proxy_rpc = self.proxy_rpc.clone();
                 
-                // Run in a thread — uses blocking reqwest (no async runtime needed)
-                thread::spawn(move || {
-                    const GROQ_WHISPER_URL: &str = "https://api.groq.com/openai/v1/audio/transcriptions";
-                        
-                        // Try environment variable first, then ai-keys.toml
-                        let groq_key = std::env::var("GROQ_API_KEY")
-                            .ok()
-                            .filter(|k| !k.is_empty())
-                            .or_else(|| {
-                                // Load from ai-keys.toml
-                                use lapce_core::directory::Directory;
-                                let path = Directory::config_directory()?.join("ai-keys.toml");
-                                let content = std::fs::read_to_string(&path).ok()?;
-                                let config: toml::Value = toml::from_str(&content).ok()?;
-                                config.get("keys")?.get("groq")?.as_str().map(String::from)
-                            });
-                        
-                        let Some(groq_key) = groq_key else {
-                            proxy_rpc.handle_response(id, Ok(ProxyResponse::AgentError {
-                                error: "Groq API key not found. Add 'groq' key to ai-keys.toml or set GROQ_API_KEY env var.".to_string(),
-                            }));
-                            return;
-                        };
-                    
-                    let client = reqwest::blocking::Client::new();
-                    
-                    let audio_part = reqwest::blocking::multipart::Part::bytes(audio_data)
-                        .file_name("audio.wav")
-                        .mime_str("audio/wav")
-                        .unwrap();
-                    
-                    let form = reqwest::blocking::multipart::Form::new()
-                        .text("model", "whisper-large-v3-turbo")
-                        .text("temperature", "0")
-                        .text("response_format", "verbose_json")
-                        .part("file", audio_part);
-                    
-                    match client.post(GROQ_WHISPER_URL)
-                        .header("Authorization", format!("Bearer {}", groq_key))
-                        .multipart(form)
-                        .send()
-                    {
-                        Ok(resp) => {
-                            if resp.status().is_success() {
-                                match resp.json::<serde_json::Value>() {
-                                    Ok(json) => {
-                                        let text = json.get("text")
-                                            .and_then(|t| t.as_str())
-                                            .unwrap_or("")
-                                            .to_string();
-                                        tracing::info!("Transcription: {} chars", text.len());
-                                        proxy_rpc.handle_response(id, Ok(ProxyResponse::AgentTranscription { text }));
-                                    }
-                                    Err(e) => {
-                                        proxy_rpc.handle_response(id, Ok(ProxyResponse::AgentError {
-                                            error: format!("Failed to parse Whisper response: {e}"),
-                                        }));
-                                    }
-                                }
-                            } else {
-                                let status = resp.status();
-                                let body = resp.text().unwrap_or_default();
-                                proxy_rpc.handle_response(id, Ok(ProxyResponse::AgentError {
-                                    error: format!("Whisper API error {status}: {body}"),
-                                }));
-                            }
+                // Run in a thread — whisper.cpp and the blocking reqwest client need no async runtime
+                self.pools.spawn(RequestClass::Interactive, move || {
+                    use forge_agent::speech::Stt;
+                    let result = match Stt::configured() {
+                        Ok(Stt::Local { binary, model }) => {
+                            forge_agent::speech::transcribe_local(&binary, &model, &audio_data)
+                                .map_err(|e| format!("{e:#}"))
                         }
-                        Err(e) => {
-                            proxy_rpc.handle_response(id, Ok(ProxyResponse::AgentError {
-                                error: format!("Whisper request failed: {e}"),
-                            }));
+                        Ok(Stt::Provider { provider, url, model }) => {
+                            transcribe_with_provider(&provider, url, &model, audio_data)
+                        }
+                        Err(e) => Err(e.to_string()),
+                    };
+                    match result {
+                        Ok(text) => {
+                            tracing::info!("Transcription: {} chars", text.len());
+                            proxy_rpc.handle_response(id, Ok(ProxyResponse::AgentTranscription { text }));
+                        }
+                        Err(error) => {
+                            proxy_rpc.handle_response(id, Ok(ProxyResponse::AgentError { error }));
                         }
                     }
                 });
@@ -3046,6 +3558,45 @@ impl ProxyHandler for Dispatcher {
                     message: format!("Approved: {tool_call_id}"),
                 }));
             }
+            AgentRestoreCheckpoint { conversation_id, turn } => {
+                tracing::info!("Restoring checkpoint: conv_id={conversation_id}, turn={turn}");
+                let workspace = self.workspace.clone().unwrap_or_default();
+                let Some(checkpoints) = forge_agent::checkpoint::Checkpoints::for_conversation(&conversation_id) else {
+                    self.respond_rpc(id, Ok(ProxyResponse::AgentRestoreCheckpointResponse { restored: Vec::new() }));
+                    return;
+                };
+                // Pending review diffs belong to the turns being undone
+                self.pending_diff_snapshots.lock().clear();
+                match checkpoints.restore(turn, &workspace) {
+                    Ok(restored) => {
+                        self.respond_rpc(id, Ok(ProxyResponse::AgentRestoreCheckpointResponse { restored }));
+                    }
+                    Err(e) => {
+                        self.respond_rpc(id, Err(RpcError {
+                            code: 0,
+                            message: format!("Failed to restore checkpoint: {e}"),
+                        }));
+                    }
+                }
+            }
+            AgentWorkspaceTrust { set } => {
+                let Some(workspace) = self.workspace.clone() else {
+                    // No folder open: nothing to restrict
+                    self.respond_rpc(id, Ok(ProxyResponse::AgentWorkspaceTrustResponse { trusted: Some(true) }));
+                    return;
+                };
+                if let Some(trusted) = set {
+                    if let Err(e) = forge_agent::trust::set_trusted(&workspace, trusted) {
+                        self.respond_rpc(id, Err(RpcError {
+                            code: 0,
+                            message: format!("Failed to save workspace trust: {e}"),
+                        }));
+                        return;
+                    }
+                }
+                let trusted = forge_agent::trust::decision(&workspace);
+                self.respond_rpc(id, Ok(ProxyResponse::AgentWorkspaceTrustResponse { trusted }));
+            }
             AgentApproveAllFuture {} => {
                 tracing::info!("Auto-approve all future tool calls enabled for this session");
                 self.auto_approve_session.store(true, std::sync::atomic::Ordering::Relaxed);
@@ -3058,6 +3609,15 @@ impl ProxyHandler for Dispatcher {
                     message: "Auto-approve enabled for this session".to_string(),
                 }));
             }
+            AgentAnswerFollowup { tool_call_id, answer } => {
+                tracing::info!("Agent follow-up answered: {tool_call_id}");
+                if let Some(sender) = self.pending_followups.lock().remove(&tool_call_id) {
+                    let _ = sender.send(answer);
+                }
+                self.respond_rpc(id, Ok(ProxyResponse::AgentDone {
+                    message: format!("Answered: {tool_call_id}"),
+                }));
+            }
             AgentRejectToolCall { tool_call_id } => {
                 tracing::info!("Agent tool call rejected: {tool_call_id}");
                 if let Some(sender) = self.pending_approvals.lock().remove(&tool_call_id) {
@@ -3071,6 +3631,7 @@ impl ProxyHandler for Dispatcher {
             // ── AI Diff Accept/Reject ─────────────────────────────
             AgentDiffAccept { diff_id, accepted_hunks } => {
                 tracing::info!("Agent diff accepted: {diff_id}, hunks: {:?}", accepted_hunks);
+                self.pending_diff_snapshots.lock().remove(&diff_id);
                 // The diff has already been applied to disk by the tool.
                 // This acknowledges that the user wants to keep the changes.
                 // In a future iteration, we could revert-then-selectively-apply hunks.
@@ -3087,7 +3648,7 @@ impl ProxyHandler for Dispatcher {
                 if let Some(snapshot) = self.pending_diff_snapshots.lock().remove(&diff_id) {
                     let workspace = self.workspace.clone().unwrap_or_default();
                     let full_path = workspace.join(&snapshot.0);
-                    match std::fs::write(&full_path, &snapshot.1) {
+                    match forge_agent::tools::buffers::write(&full_path, &snapshot.1) {
                         Ok(_) => {
                             tracing::info!("Reverted {} to pre-edit state", snapshot.0);
                             self.respond_rpc(id, Ok(ProxyResponse::AgentDiffRejectResponse { diff_id }));
@@ -3122,7 +3683,7 @@ impl ProxyHandler for Dispatcher {
                 let workspace = self.workspace.clone().unwrap_or_default();
                 for (rel_path, old_content) in &snapshots {
                     let full_path = workspace.join(rel_path);
-                    if let Err(e) = std::fs::write(&full_path, old_content) {
+                    if let Err(e) = forge_agent::tools::buffers::write(&full_path, old_content) {
                         tracing::error!("Failed to revert {}: {e}", rel_path);
                     }
                 }
@@ -3140,7 +3701,7 @@ impl ProxyHandler for Dispatcher {
                 let suffix = suffix.clone();
                 let req_id = request_id;
 
-                thread::spawn(move || {
+                self.pools.spawn(RequestClass::Interactive, move || {
                     let rt = match tokio::runtime::Builder::new_current_thread()
                         .enable_all()
                         .build()
@@ -3181,7 +3742,7 @@ impl ProxyHandler for Dispatcher {
                 let core_rpc = self.core_rpc.clone();
                 let proxy_rpc = self.proxy_rpc.clone();
 
-                thread::spawn(move || {
+                self.pools.spawn(RequestClass::Agent, move || {
                     let rt = match tokio::runtime::Runtime::new() {
                         Ok(rt) => rt,
                         Err(e) => {
@@ -3206,11 +3767,24 @@ impl ProxyHandler for Dispatcher {
                                 return;
                             }
                         };
+                        forge_agent::config::activate(&workspace_path);
+                        let blocked = if !forge_agent::trust::is_trusted(&workspace_path) {
+                            Some("Workspace not trusted")
+                        } else if forge_agent::egress::local_only() {
+                            Some("Local-only mode")
+                        } else {
+                            None
+                        };
+                        if let Some(status) = blocked {
+                            core_rpc.notification(CoreNotification::IndexProgress {
+                                status: status.to_string(),
+                                progress: -1.0,
+                            });
+                            proxy_rpc.handle_response(id, Ok(ProxyResponse::IndexStarted {}));
+                            return;
+                        }
 
-                        let workspace_id = workspace_path
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("default");
+                        let workspace_id = &forge_agent::forge_search::workspace_id(&workspace_path);
 
                         core_rpc.notification(CoreNotification::IndexProgress {
                             status: "Scanning files...".to_string(),
@@ -3261,46 +3835,198 @@ impl ProxyHandler for Dispatcher {
                 let workspace = self.workspace.clone();
                 let proxy_rpc = self.proxy_rpc.clone();
 
-                thread::spawn(move || {
+                self.pools.spawn(RequestClass::Agent, move || {
                     let rt = match tokio::runtime::Runtime::new() {
                         Ok(rt) => rt,
                         Err(_) => {
-                            proxy_rpc.handle_response(
-                                id,
-                                Ok(ProxyResponse::IndexStatusResponse {
-                                    is_indexed: false,
-                                    symbol_count: 0,
-                                }),
-                            );
+                            proxy_rpc.handle_response(id, Ok(index_status_response("default", None)));
                             return;
                         }
                     };
 
                     rt.block_on(async move {
-                        let workspace_id = workspace
-                            .as_ref()
-                            .and_then(|p| p.file_name())
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("default");
+                        let workspace_id = &workspace
+                            .as_deref()
+                            .map(forge_agent::forge_search::workspace_id)
+                            .unwrap_or_else(|| "default".to_string());
 
                         let client = forge_agent::forge_search::client();
+                        let symbols = client.check_index_status(workspace_id).await.ok().map(|(_, count)| count);
 
-                        let (is_indexed, symbol_count) = client
-                            .check_index_status(workspace_id)
-                            .await
-                            .unwrap_or((false, 0));
+                        proxy_rpc.handle_response(id, Ok(index_status_response(workspace_id, symbols)));
+                    });
+                });
+            }
 
-                        proxy_rpc.handle_response(
-                            id,
-                            Ok(ProxyResponse::IndexStatusResponse {
-                                is_indexed,
-                                symbol_count,
-                            }),
-                        );
+            IndexPath { path } => {
+                let Some(workspace_path) = self.workspace.clone() else {
+                    self.respond_rpc(id, Err(RpcError { code: 0, message: "No workspace open".to_string() }));
+                    return;
+                };
+                let proxy_rpc = self.proxy_rpc.clone();
+
+                self.pools.spawn(RequestClass::Agent, move || {
+                    let Ok(rt) = tokio::runtime::Runtime::new() else {
+                        proxy_rpc.handle_response(id, Err(RpcError { code: 0, message: "Failed to start runtime".to_string() }));
+                        return;
+                    };
+                    rt.block_on(async move {
+                        forge_agent::config::activate(&workspace_path);
+                        if !forge_agent::trust::is_trusted(&workspace_path) {
+                            proxy_rpc.handle_response(id, Err(RpcError { code: 0, message: "Workspace not trusted".to_string() }));
+                            return;
+                        }
+                        let workspace_id = &forge_agent::forge_search::workspace_id(&workspace_path);
+                        let client = forge_agent::forge_search::client();
+                        match client.reindex_path(workspace_id, &workspace_path, &path).await {
+                            Ok(_) => {
+                                let symbols = client.check_index_status(workspace_id).await.ok().map(|(_, count)| count);
+                                proxy_rpc.handle_response(id, Ok(index_status_response(workspace_id, symbols)));
+                            }
+                            Err(e) => {
+                                proxy_rpc.handle_response(id, Err(RpcError { code: 0, message: format!("Re-index failed: {e}") }));
+                            }
+                        }
+                    });
+                });
+            }
+
+            IndexClear { workspace_id } => {
+                let workspace_id = match (workspace_id, &self.workspace) {
+                    (Some(workspace_id), _) => workspace_id,
+                    (None, Some(workspace_path)) => forge_agent::forge_search::workspace_id(workspace_path),
+                    (None, None) => {
+                        self.respond_rpc(id, Err(RpcError { code: 0, message: "No workspace open".to_string() }));
+                        return;
+                    }
+                };
+                let proxy_rpc = self.proxy_rpc.clone();
+
+                self.pools.spawn(RequestClass::Agent, move || {
+                    let Ok(rt) = tokio::runtime::Runtime::new() else {
+                        proxy_rpc.handle_response(id, Err(RpcError { code: 0, message: "Failed to start runtime".to_string() }));
+                        return;
+                    };
+                    rt.block_on(async move {
+                        match forge_agent::forge_search::client().clear_index(&workspace_id).await {
+                            Ok(_) => proxy_rpc.handle_response(id, Ok(index_status_response(&workspace_id, Some(0)))),
+                            Err(e) => {
+                                proxy_rpc.handle_response(id, Err(RpcError { code: 0, message: format!("Clearing the index failed: {e}") }));
+                            }
+                        }
                     });
                 });
             }
 
+            IndexWorkspaces {} => {
+                let workspaces = forge_agent::index_state::list()
+                    .into_iter()
+                    .map(|(workspace_id, state)| lapce_rpc::proxy::IndexedWorkspace {
+                        workspace_id,
+                        path: state.path,
+                        files_indexed: state.files_indexed,
+                        size_bytes: state.size_bytes,
+                        last_used: state.last_used,
+                    })
+                    .collect();
+                self.respond_rpc(id, Ok(ProxyResponse::IndexWorkspacesResponse { workspaces }));
+            }
+
+            AgentUsageSummary { conversation_id } => {
+                let summary = forge_agent::usage::summarize(
+                    &forge_agent::usage::load(),
+                    chrono::Utc::now().timestamp(),
+                    conversation_id.as_deref(),
+                );
+                let totals = |t: forge_agent::usage::Totals| lapce_rpc::proxy::UsageTotals {
+                    requests: t.requests,
+                    input_tokens: t.input_tokens,
+                    output_tokens: t.output_tokens,
+                    cost_usd: t.cost_usd,
+                    unpriced: t.unpriced,
+                };
+                let summary = lapce_rpc::proxy::UsageSummary {
+                    today: totals(summary.today),
+                    session: totals(summary.session),
+                    providers: summary
+                        .providers
+                        .into_iter()
+                        .map(|(provider, t)| lapce_rpc::proxy::ProviderUsage { provider, totals: totals(t) })
+                        .collect(),
+                    conversations: summary
+                        .conversations
+                        .into_iter()
+                        .map(|c| lapce_rpc::proxy::ConversationUsage {
+                            conversation_id: c.conversation_id,
+                            title: c.title,
+                            last_used: c.last_used,
+                            totals: totals(c.totals),
+                        })
+                        .collect(),
+                    daily_budget_usd: summary.daily_budget_usd,
+                    session_budget_usd: summary.session_budget_usd,
+                };
+                self.respond_rpc(id, Ok(ProxyResponse::AgentUsageSummaryResponse { summary }));
+            }
+
+            AgentWorkingNotes { conversation_id } => {
+                let session = forge_agent::session::Session::load(&conversation_id);
+                self.respond_rpc(id, Ok(ProxyResponse::AgentWorkingNotesResponse {
+                    notes: working_notes(&session),
+                }));
+            }
+
+            AgentOnboardingReport {} => {
+                let Some(workspace) = self.workspace.clone() else {
+                    self.respond_rpc(id, Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    }));
+                    return;
+                };
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.pools.spawn(RequestClass::Agent, move || {
+                    let run_commands = crate::run_config_detector::detect_run_configs(&workspace)
+                        .into_iter()
+                        .map(|c| forge_agent::onboarding::RunCommand {
+                            command: std::iter::once(c.command).chain(c.args).collect::<Vec<_>>().join(" "),
+                            name: c.name,
+                            source: c.source,
+                        })
+                        .collect();
+                    let report = forge_agent::onboarding::build(&workspace, run_commands);
+                    let path = forge_agent::onboarding::report_path(&workspace);
+                    let written = path
+                        .parent()
+                        .map_or(Ok(()), std::fs::create_dir_all)
+                        .and_then(|()| std::fs::write(&path, report.markdown()));
+                    let response = match written {
+                        Ok(()) => Ok(ProxyResponse::AgentOnboardingReportResponse { path }),
+                        Err(e) => Err(RpcError {
+                            code: 0,
+                            message: format!("Writing {} failed: {e}", path.display()),
+                        }),
+                    };
+                    proxy_rpc.handle_response(id, response);
+                });
+            }
+            AgentRestoreDeletedFile {} => {
+                let Some(workspace) = self.workspace.clone() else {
+                    self.respond_rpc(id, Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    }));
+                    return;
+                };
+                let response = forge_agent::tools::trash::restore(&workspace, None)
+                    .map(|entry| ProxyResponse::AgentRestoreDeletedFileResponse {
+                        path: workspace.join(&entry.path),
+                        is_dir: entry.is_dir,
+                    })
+                    .map_err(|message| RpcError { code: 0, message });
+                self.respond_rpc(id, response);
+            }
+
             // ── LSP Tools for AI Agent ────────────────────────────
             LspGotoDefinition { path, position } => {
                 let proxy_rpc = self.proxy_rpc.clone();
@@ -3430,6 +4156,18 @@ impl ProxyHandler for Dispatcher {
                     },
                 );
             }
+            Unknown => {
+                self.respond_rpc(
+                    id,
+                    Err(RpcError {
+                        code: lapce_rpc::version::UNSUPPORTED,
+                        message: format!(
+                            "This proxy ({}) doesn't support the request; update it to match the app",
+                            lapce_rpc::version::ProtocolVersion::CURRENT
+                        ),
+                    }),
+                );
+            }
         }
     }
 }
@@ -3576,6 +4314,75 @@ fn get_offset(content: &str, position: lsp_types::Position) -> usize {
     offset
 }
 
+/// Point each table at the workspace SQL migration that creates it.
+fn link_table_sources(schema: &mut lapce_rpc::db::DbSchema, workspace: Option<&Path>) {
+    let Some(workspace) = workspace else {
+        return;
+    };
+    let locations = forge_agent::sql::table_locations(workspace);
+    for table in &mut schema.tables {
+        if let Some((path, line)) = locations.get(&table.name.to_lowercase()) {
+            table.source = Some(lapce_rpc::db::DbTableSource { path: path.clone(), line: *line as u32 });
+        }
+    }
+}
+
+/// The tree-sitter outline of `path` as LSP document symbols.
+fn syntax_symbols(path: &Path, content: &str) -> lsp_types::DocumentSymbolResponse {
+    use forge_agent::tools::treesitter::{self, OutlineItem, SymbolKind};
+
+    let lines: Vec<&str> = content.lines().collect();
+    // Line lengths in UTF-16, as LSP positions count
+    let width = |line: usize| lines.get(line).map_or(0, |l| l.encode_utf16().count() as u32);
+    #[allow(deprecated)]
+    fn convert(item: OutlineItem, width: &dyn Fn(usize) -> u32, lines: &[&str]) -> lsp_types::DocumentSymbol {
+        let symbol = item.symbol;
+        let start = symbol.start_line.saturating_sub(1);
+        let end = symbol.end_line.saturating_sub(1).max(start);
+        let range = Range { start: Position::new(start as u32, 0), end: Position::new(end as u32, width(end)) };
+        // The name on its first line, else the whole line
+        let selection_range = lines
+            .get(start)
+            .and_then(|line| {
+                let short = symbol.name.rsplit(['.', ':', '/']).next().unwrap_or(&symbol.name);
+                let byte = line.find(short)?;
+                let col = line[..byte].encode_utf16().count() as u32;
+                Some(Range {
+                    start: Position::new(start as u32, col),
+                    end: Position::new(start as u32, col + short.encode_utf16().count() as u32),
+                })
+            })
+            .unwrap_or(Range { start: range.start, end: Position::new(start as u32, width(start)) });
+        let kind = match symbol.kind {
+            SymbolKind::Function => lsp_types::SymbolKind::FUNCTION,
+            SymbolKind::Class => lsp_types::SymbolKind::CLASS,
+            SymbolKind::Struct => lsp_types::SymbolKind::STRUCT,
+            SymbolKind::Enum => lsp_types::SymbolKind::ENUM,
+            SymbolKind::Interface => lsp_types::SymbolKind::INTERFACE,
+            SymbolKind::Type => lsp_types::SymbolKind::TYPE_PARAMETER,
+            SymbolKind::Constant => lsp_types::SymbolKind::CONSTANT,
+            SymbolKind::Variable => lsp_types::SymbolKind::VARIABLE,
+            SymbolKind::Method => lsp_types::SymbolKind::METHOD,
+            SymbolKind::Module => lsp_types::SymbolKind::MODULE,
+        };
+        let children: Vec<_> = item.children.into_iter().map(|c| convert(c, width, lines)).collect();
+        lsp_types::DocumentSymbol {
+            name: symbol.name,
+            detail: symbol.doc,
+            kind,
+            tags: None,
+            deprecated: None,
+            range,
+            selection_range,
+            children: (!children.is_empty()).then_some(children),
+        }
+    }
+
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let symbols = treesitter::outline(content, ext).into_iter().map(|item| convert(item, &width, &lines)).collect();
+    lsp_types::DocumentSymbolResponse::Nested(symbols)
+}
+
 fn make_lsp_position(line: u64, col: u64) -> lsp_types::Position {
     lsp_types::Position::new(line as u32, col as u32)
 }
@@ -3607,12 +4414,155 @@ fn format_lsp_locations(
     output
 }
 
+/// The index status of `workspace_id`: what this process recorded, with the
+/// server's symbol count when it answered.
+fn index_status_response(workspace_id: &str, symbols: Option<i64>) -> ProxyResponse {
+    let state = forge_agent::index_state::get(workspace_id);
+    let symbol_count = symbols.unwrap_or(state.symbols);
+    ProxyResponse::IndexStatusResponse {
+        is_indexed: symbol_count > 0,
+        symbol_count,
+        files_indexed: state.files_indexed,
+        pending: state.pending,
+        size_bytes: state.size_bytes,
+        last_refresh: state.last_refresh,
+        last_error: state.last_error,
+    }
+}
+
+/// How long the agent waits for the answer to a follow-up question.
+const FOLLOWUP_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Sends an agent request's progress to the UI once a second while it runs,
+/// and clears it when dropped (the request finished or failed).
+struct ProgressTicker {
+    task: tokio::task::JoinHandle<()>,
+    core_rpc: CoreRpcHandler,
+}
+
+impl ProgressTicker {
+    fn start(progress: Arc<Mutex<forge_agent::progress::Progress>>, core_rpc: CoreRpcHandler) -> Self {
+        let rpc = core_rpc.clone();
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                interval.tick().await;
+                let snapshot = {
+                    let p = progress.lock();
+                    lapce_rpc::core::AgentRunProgress {
+                        step: p.step(),
+                        activity: p.activity().to_string(),
+                        tool: p.tool().map(str::to_string),
+                        elapsed_ms: p.elapsed().as_millis() as u64,
+                        tokens: p.tokens(),
+                    }
+                };
+                rpc.agent_progress(Some(snapshot));
+            }
+        });
+        Self { task, core_rpc }
+    }
+}
+
+impl Drop for ProgressTicker {
+    fn drop(&mut self) {
+        self.task.abort();
+        self.core_rpc.agent_progress(None);
+    }
+}
+
+/// Fold one message's file edits into a net change per file: the content
+/// before its first edit against what's on disk now. Files that ended up
+/// unchanged are left out.
+fn turn_file_changes(
+    conversation_id: &str,
+    turn: u32,
+    edits: &[(String, forge_agent::tools::FileEditMeta)],
+    workspace_path: &Path,
+) -> Vec<lapce_rpc::core::AgentFileEdit> {
+    let mut changes: Vec<lapce_rpc::core::AgentFileEdit> = Vec::new();
+    for (_, meta) in edits {
+        match changes.iter_mut().find(|c| c.path == meta.path) {
+            Some(change) => change.new_content = meta.new_content.clone(),
+            None => changes.push(lapce_rpc::core::AgentFileEdit {
+                diff_id: format!("{conversation_id}:turn-{turn}:{}", meta.path),
+                path: meta.path.clone(),
+                old_content: meta.old_content.clone(),
+                new_content: meta.new_content.clone(),
+            }),
+        }
+    }
+    for change in &mut changes {
+        if let Ok(current) = std::fs::read_to_string(workspace_path.join(&change.path)) {
+            change.new_content = current;
+        }
+    }
+    changes.retain(|c| c.old_content != c.new_content);
+    changes
+}
+
 /// Execute an IDE tool locally (the "Hands" executing what the "Brain" requested).
 /// This handles tool calls that need to run in the IDE context.
 ///
 /// `execute_command`, `execute_background`, and the new `run` tool are routed
 /// through the IDE's real terminal (PTY) so the user can see the output and
 /// the shell profile is loaded.
+/// Run a foreground command for the agent: in a visible IDE terminal the
+/// user can watch and type into, or hidden, per `TerminalPolicy`. Output
+/// is returned to the model either way.
+async fn run_foreground_command(
+    tc: &forge_agent::ToolCallInfo,
+    command: &str,
+    workspace_path: &std::path::Path,
+    core_rpc: &CoreRpcHandler,
+    agent_term_mgr: &Arc<AgentTerminalManager>,
+    ide_terminals: &Arc<std::sync::Mutex<HashMap<TermId, TerminalSender>>>,
+) -> forge_agent::tools::ToolResult {
+    if forge_agent::tools::TerminalPolicy::for_call(&tc.args) == forge_agent::tools::TerminalPolicy::Hidden {
+        let mut args = tc.args.clone();
+        if let Some(obj) = args.as_object_mut() {
+            obj.insert("command".to_string(), serde_json::Value::String(command.to_string()));
+            obj.insert("background".to_string(), serde_json::Value::Bool(false));
+        }
+        let tool_call_obj = forge_agent::tools::ToolCall {
+            name: "run".to_string(),
+            arguments: args,
+            thought_signature: None,
+        };
+        return forge_agent::tools::execute(&tool_call_obj, workspace_path, false).await;
+    }
+
+    let timeout_secs = tc.args.get("timeout_secs")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(120)
+        .min(600);
+    // execute_command uses a blocking condvar wait internally, so we must
+    // run it on the blocking thread pool to avoid starving the Tokio executor.
+    // Starvation was causing concurrent SSE streams to time out while a
+    // long-running command held the executor thread.
+    let atm = agent_term_mgr.clone();
+    let wp = workspace_path.to_path_buf();
+    let cmd = command.to_string();
+    let cr = core_rpc.clone();
+    let it = ide_terminals.clone();
+    let tc_id = tc.id.clone();
+    let tc_name = tc.name.clone();
+    let run = async move {
+        tokio::task::spawn_blocking(move || {
+            atm.execute_command(&cmd, &wp, timeout_secs, &cr, &it, &tc_id, &tc_name)
+        })
+        .await
+        .unwrap_or_else(|e| forge_agent::tools::ToolResult::err(
+            format!("spawn_blocking panicked: {e}")
+        ))
+    };
+    if is_read_only_command(command) {
+        run.await
+    } else {
+        forge_agent::tools::fs_changes::tracked(workspace_path, run).await
+    }
+}
+
 async fn execute_ide_tool(
     tc: &forge_agent::ToolCallInfo,
     workspace_path: &std::path::Path,
@@ -3640,28 +4590,7 @@ async fn execute_ide_tool(
                     command, workspace_path, 5_000, core_rpc, ide_terminals,
                 )
             } else {
-                let timeout_secs = tc.args.get("timeout_secs")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(120)
-                    .min(600);
-                // execute_command uses a blocking condvar wait internally, so we must
-                // run it on the blocking thread pool to avoid starving the Tokio executor.
-                // Starvation was causing concurrent SSE streams to time out while a
-                // long-running command held the executor thread.
-                let atm = agent_term_mgr.clone();
-                let wp = workspace_path.to_path_buf();
-                let cmd = command.to_string();
-                let cr = core_rpc.clone();
-                let it = ide_terminals.clone();
-                let tc_id = tc.id.clone();
-                let tc_name = tc.name.clone();
-                tokio::task::spawn_blocking(move || {
-                    atm.execute_command(&cmd, &wp, timeout_secs, &cr, &it, &tc_id, &tc_name)
-                })
-                .await
-                .unwrap_or_else(|e| forge_agent::tools::ToolResult::err(
-                    format!("spawn_blocking panicked: {e}")
-                ))
+                run_foreground_command(tc, command, workspace_path, core_rpc, agent_term_mgr, ide_terminals).await
             }
         }
         // ── New `process` tool (output / status / kill) ──────────
@@ -3670,6 +4599,12 @@ async fn execute_ide_tool(
             match action {
                 "output" => {
                     let pid = tc.args.get("pid").and_then(|v| v.as_u64()).map(|p| p as u32);
+                    let since = tc.args.get("since").and_then(|v| v.as_u64());
+                    if let (Some(pid), Some(since)) = (pid, since) {
+                        if let Some(result) = agent_term_mgr.read_log(pid, workspace_path, since) {
+                            return result;
+                        }
+                    }
                     if let Some(pid) = pid {
                         let tail_lines = tc.args.get("lines")
                             .and_then(|v| v.as_u64())
@@ -3733,15 +4668,7 @@ async fn execute_ide_tool(
             if command.is_empty() {
                 return forge_agent::tools::ToolResult::err("Missing 'command' parameter");
             }
-            let timeout_secs = tc.args.get("timeout_secs")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(120)
-                .min(600);
-
-            agent_term_mgr.execute_command(
-                command, workspace_path, timeout_secs, core_rpc, ide_terminals,
-                &tc.id, &tc.name,
-            )
+            run_foreground_command(tc, command, workspace_path, core_rpc, agent_term_mgr, ide_terminals).await
         }
         "execute_background" => {
             let command = tc.args.get("command")
@@ -3760,6 +4687,12 @@ async fn execute_ide_tool(
         }
         "read_process_output" => {
             let pid = tc.args.get("pid").and_then(|v| v.as_u64()).map(|p| p as u32);
+            let since = tc.args.get("since").and_then(|v| v.as_u64());
+            if let (Some(pid), Some(since)) = (pid, since) {
+                if let Some(result) = agent_term_mgr.read_log(pid, workspace_path, since) {
+                    return result;
+                }
+            }
             if let Some(pid) = pid {
                 // Check if this PID belongs to an agent terminal
                 if agent_term_mgr.has_terminal(pid) {
@@ -3919,8 +4852,16 @@ async fn execute_ide_tool(
                                 forge_agent::tools::ToolResult::ok(format!("Successfully renamed in: {}", results.join(", ")))
                             }
                         }
-                        Ok(Ok(Err(e))) => forge_agent::tools::ToolResult::err(format!("LSP rename failed: {:?}", e)),
-                        _ => forge_agent::tools::ToolResult::err("LSP rename request failed or timed out".to_string()),
+                        // No language server answered: rename by syntax tree
+                        Ok(Ok(Err(e))) => {
+                            let result = forge_agent::tools::rename::rename(workspace_path, path_str, line, col, new_name);
+                            if result.success {
+                                result
+                            } else {
+                                forge_agent::tools::ToolResult::err(format!("LSP rename failed: {:?}; {}", e, result.output))
+                            }
+                        }
+                        _ => forge_agent::tools::rename::rename(workspace_path, path_str, line, col, new_name),
                     }
                 }
                 other => forge_agent::tools::ToolResult::err(format!(
@@ -3953,7 +4894,16 @@ async fn execute_ide_tool(
         "list_run_configs" => {
             // Use the proxy's existing handler to get configs
             let workspace = workspace_path.to_path_buf();
-            let detected = crate::run_config_detector::detect_run_configs(&workspace);
+            let mut detected = crate::run_config_detector::detect_run_configs(&workspace);
+            // Other workspace roots: their configs run from their own directory
+            let roots = forge_agent::workspace_roots::WorkspaceRoots::discover(&workspace);
+            for root in roots.iter().skip(1) {
+                for mut config in crate::run_config_detector::detect_run_configs(&root.path) {
+                    config.name = format!("{}: {}", root.name, config.name);
+                    config.cwd.get_or_insert_with(|| root.path.display().to_string());
+                    detected.push(config);
+                }
+            }
             
             // Format the output nicely for the agent
             let mut output = String::from("=== Available Run Configurations ===\n\n");
@@ -3963,9 +4913,10 @@ async fn execute_ide_tool(
                 output.push_str("\nYou can create a custom configuration in .lapce/run.toml\n");
             } else {
                 output.push_str("Detected configurations:\n\n");
+                let limit = 10 * roots.iter().count();
                 for (i, config) in detected.iter().enumerate() {
-                    if i >= 10 {
-                        output.push_str(&format!("... and {} more\n", detected.len() - 10));
+                    if i >= limit {
+                        output.push_str(&format!("... and {} more\n", detected.len() - limit));
                         break;
                     }
                     output.push_str(&format!("{}. {}\n", i + 1, config.name));
@@ -3980,6 +4931,30 @@ async fn execute_ide_tool(
                 }
             }
             
+            // User configs and compounds from .lapce/run.toml
+            let user = load_run_configs(&workspace);
+            if !user.configs.is_empty() {
+                output.push_str("User configurations (.lapce/run.toml):\n\n");
+                for config in &user.configs {
+                    output.push_str(&format!("- {}\n", config.name));
+                    output.push_str(&format!(
+                        "   Command: {} {}\n",
+                        config.program,
+                        config.args.clone().unwrap_or_default().join(" ")
+                    ));
+                    if let Some(deps) = config.depends_on.as_ref().filter(|d| !d.is_empty()) {
+                        output.push_str(&format!("   Depends on: {}\n", deps.join(", ")));
+                    }
+                }
+                output.push_str("\n");
+            }
+            if !user.compounds.is_empty() {
+                output.push_str("Compound configurations (launch/stop as a group):\n\n");
+                for compound in &user.compounds {
+                    output.push_str(&format!("- {}: {}\n", compound.name, compound.configs.join(" + ")));
+                }
+            }
+            
             forge_agent::tools::ToolResult::ok(output)
         }
         "run_project" => {
@@ -4016,7 +4991,9 @@ async fn execute_ide_tool(
                         args: Some(vec!["-c".to_string(), cmd.clone()]),
                         cwd: None,
                         env: None,
+                        env_file: None,
                         prelaunch: None,
+                        depends_on: None,
                         debug_command: None,
                         dap_id: lapce_rpc::dap_types::DapId::next(),
                         tracing_output: false,
@@ -4032,11 +5009,38 @@ async fn execute_ide_tool(
                 );
             }
             
+            // Debug mode without a hand-written DAP config: generate one
+            let mut launch_mode = mode.clone();
+            let mut debug_config = None;
+            let mut debug_note = None;
+            if mode == "debug" {
+                if let Some(cmd) = debug_target_command(workspace_path, config_name.as_deref(), command.as_deref()) {
+                    let auto = match crate::dap_auto_config::auto_debug_config(workspace_path, &cmd) {
+                        Ok(auto) => auto,
+                        Err(e) => {
+                            return forge_agent::tools::ToolResult::err(format!(
+                                "Cannot debug '{}': {}", cmd, e
+                            ));
+                        }
+                    };
+                    match (&auto.adapter, &auto.config.ty) {
+                        (Some((program, args)), Some(ty)) => {
+                            catalog_rpc.register_debugger_type(ty.clone(), program.clone(), Some(args.clone()));
+                        }
+                        // Inspector-based: runs in a plain terminal
+                        _ => launch_mode = "run".to_string(),
+                    }
+                    debug_note = Some(auto.description);
+                    debug_config = Some(auto.config);
+                }
+            }
+
             // Otherwise, send notification to IDE to run the project normally (returns immediately)
             core_rpc.notification(CoreNotification::AgentRunProject {
                 config_name: config_name.clone(),
                 command: command.clone(),
-                mode: mode.clone(),
+                mode: launch_mode,
+                debug_config,
             });
             
             let desc = if let Some(name) = config_name {
@@ -4047,9 +5051,206 @@ async fn execute_ide_tool(
                 "No config or command specified".to_string()
             };
             
-            forge_agent::tools::ToolResult::ok(format!(
-                "✓ {}. Opening terminal...", desc
-            ))
+            match debug_note {
+                Some(note) => forge_agent::tools::ToolResult::ok(format!(
+                    "✓ {} (auto-configured: {}). Breakpoints set in the editor apply.", desc, note
+                )),
+                None => forge_agent::tools::ToolResult::ok(format!(
+                    "✓ {}. Opening terminal...", desc
+                )),
+            }
+        }
+        "read_run_output" => {
+            let config_name = tc.args.get("config_name").and_then(|v| v.as_str());
+            let tail_lines = tc.args.get("tail_lines")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(100) as usize;
+            match agent_term_mgr.read_run_output(config_name, tail_lines) {
+                Ok(result) => result,
+                Err(names) if names.is_empty() => forge_agent::tools::ToolResult::err(
+                    "No run output captured yet. Start the project with run_project first."
+                ),
+                Err(names) => forge_agent::tools::ToolResult::err(format!(
+                    "No run output for '{}'. Runs with output: {}",
+                    config_name.unwrap_or_default(),
+                    names.join(", ")
+                )),
+            }
+        }
+        // ── Persistent shell sessions ──────────────────────────────
+        "shell_session" => {
+            let session = tc.args.get("session")
+                .and_then(|v| v.as_str())
+                .unwrap_or("default")
+                .to_string();
+            let action = tc.args.get("action").and_then(|v| v.as_str()).unwrap_or("run");
+            match action {
+                "read" => agent_term_mgr.session_read(&session).unwrap_or_else(|| {
+                    forge_agent::tools::ToolResult::err(format!("No shell session '{session}'"))
+                }),
+                "interrupt" => agent_term_mgr.session_interrupt(&session),
+                "close" => agent_term_mgr.session_close(&session),
+                "list" => agent_term_mgr.session_list(),
+                "run" => {
+                    let command = tc.args.get("command")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string();
+                    if command.is_empty() {
+                        return forge_agent::tools::ToolResult::err("Missing 'command' parameter");
+                    }
+                    let timeout_secs = tc.args.get("timeout_secs")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(120)
+                        .min(600);
+                    // Waits block; keep them off the executor (see run_foreground_command)
+                    let atm = agent_term_mgr.clone();
+                    let wp = workspace_path.to_path_buf();
+                    let cr = core_rpc.clone();
+                    let it = ide_terminals.clone();
+                    tokio::task::spawn_blocking(move || {
+                        atm.session_run(&session, &command, &wp, timeout_secs, &cr, &it)
+                    })
+                    .await
+                    .unwrap_or_else(|e| forge_agent::tools::ToolResult::err(
+                        format!("spawn_blocking panicked: {e}")
+                    ))
+                }
+                other => forge_agent::tools::ToolResult::err(format!(
+                    "Unknown action '{other}'. Valid: run, read, interrupt, close, list"
+                )),
+            }
+        }
+        // ── Point the user at a file in the editor ─────────────
+        "open_in_editor" => {
+            use forge_agent::tools::args::{EditorAction, OpenInEditorArgs};
+            let args: OpenInEditorArgs = match forge_agent::tools::args::parse(&tc.name, &tc.args) {
+                Ok(args) => args,
+                Err(invalid) => return invalid,
+            };
+            let path = match forge_agent::tools::display::editor_target(&args, workspace_path) {
+                Ok(path) => path,
+                Err(invalid) => return invalid,
+            };
+            let action = match args.action {
+                EditorAction::Open => lapce_rpc::core::AgentEditorAction::Open,
+                EditorAction::Reveal => lapce_rpc::core::AgentEditorAction::Reveal,
+                EditorAction::Diff => lapce_rpc::core::AgentEditorAction::Diff,
+            };
+            core_rpc.agent_open_in_editor(path, args.line, args.column, action);
+            forge_agent::tools::ToolResult::ok(forge_agent::tools::display::editor_summary(&args))
+        }
+        // ── Plugins: the registry and installed volts here, changes in the editor ──
+        "plugins" => {
+            use forge_agent::tools::args::{PluginAction, PluginsArgs};
+            use forge_agent::tools::volts;
+            let args: PluginsArgs = match forge_agent::tools::args::parse(&tc.name, &tc.args) {
+                Ok(args) => args,
+                Err(invalid) => return invalid,
+            };
+            let search = |query: String| async move {
+                tokio::task::spawn_blocking(move || -> Result<volts::SearchResults> {
+                    Ok(crate::get_url(volts::search_url(&query), None)?.error_for_status()?.json()?)
+                })
+                .await
+                .map_err(|e| anyhow!("spawn_blocking panicked: {e}"))
+                .and_then(|r| r)
+            };
+            let installed = || -> Vec<lapce_rpc::plugin::VoltInfo> {
+                crate::plugin::wasi::find_all_volts(&[]).iter().map(|meta| meta.info()).collect()
+            };
+            let name = args.name.clone().unwrap_or_default();
+            let query = args.query.clone().unwrap_or_default();
+            match args.action {
+                PluginAction::Search => match search(query).await {
+                    Ok(results) => forge_agent::tools::ToolResult::ok(volts::format_volts(&results.plugins, Some(results.total))),
+                    Err(e) => forge_agent::tools::ToolResult::err(format!("Searching the plugin registry failed: {e:#}")),
+                },
+                PluginAction::List => forge_agent::tools::ToolResult::ok(volts::format_volts(&installed(), None)),
+                PluginAction::Install => {
+                    let results = match search(name.clone()).await {
+                        Ok(results) => results,
+                        Err(e) => return forge_agent::tools::ToolResult::err(format!("Searching the plugin registry failed: {e:#}")),
+                    };
+                    let volt = match volts::find(&name, &results.plugins) {
+                        Ok(volt) => volt.clone(),
+                        Err(e) => return forge_agent::tools::ToolResult::err(e),
+                    };
+                    if installed().iter().any(|v| v.id() == volt.id()) {
+                        return forge_agent::tools::ToolResult::ok(format!("{} is already installed", volts::id(&volt)));
+                    }
+                    let summary = format!("Installing {} {}; it starts once downloaded", volts::id(&volt), volt.version);
+                    core_rpc.agent_manage_plugin(lapce_rpc::core::AgentPluginAction::Install, volt);
+                    forge_agent::tools::ToolResult::ok(summary)
+                }
+                PluginAction::Remove | PluginAction::Enable | PluginAction::Disable => {
+                    let installed = installed();
+                    let volt = match volts::find(&name, &installed) {
+                        Ok(volt) => volt.clone(),
+                        Err(e) => return forge_agent::tools::ToolResult::err(format!("{e} (among installed plugins)")),
+                    };
+                    let (action, done) = match args.action {
+                        PluginAction::Remove => (lapce_rpc::core::AgentPluginAction::Remove, "Removed"),
+                        PluginAction::Enable => (lapce_rpc::core::AgentPluginAction::Enable, "Enabled"),
+                        _ => (lapce_rpc::core::AgentPluginAction::Disable, "Disabled"),
+                    };
+                    let summary = format!("{done} {}", volts::id(&volt));
+                    core_rpc.agent_manage_plugin(action, volt);
+                    forge_agent::tools::ToolResult::ok(summary)
+                }
+            }
+        }
+        // ── Settings: the editor's config files, reloaded when they change ──
+        "modify_settings" => {
+            use forge_agent::tools::settings::{self, SettingsFiles, Themes};
+            use lapce_core::directory::Directory;
+            let args: forge_agent::tools::args::ModifySettingsArgs = match forge_agent::tools::args::parse(&tc.name, &tc.args) {
+                Ok(args) => args,
+                Err(invalid) => return invalid,
+            };
+            let Some(config) = Directory::config_directory() else {
+                return forge_agent::tools::ToolResult::err("The IDE has no config directory");
+            };
+            let files = SettingsFiles { settings: config.join("settings.toml"), keymaps: config.join("keymaps.toml") };
+            let mut theme_files: Vec<PathBuf> = Directory::themes_directory()
+                .and_then(|dir| std::fs::read_dir(dir).ok())
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .collect();
+            for meta in crate::plugin::wasi::find_all_volts(&[]) {
+                let Some(dir) = meta.dir.as_ref() else { continue };
+                let themes = meta.color_themes.iter().chain(meta.icon_themes.iter()).flatten();
+                theme_files.extend(themes.map(|theme| dir.join(theme)));
+            }
+            settings::modify(&args, &files, &Themes::load(theme_files))
+        }
+        // ── Diagrams and code blocks: also offered to the canvas ──
+        "show_diagram" | "show_code" => {
+            let tool_call_obj = forge_agent::tools::ToolCall {
+                name: tc.name.clone(),
+                arguments: tc.args.clone(),
+                thought_signature: None,
+            };
+            let result = forge_agent::tools::execute(&tool_call_obj, workspace_path, false).await;
+            let arg = |key: &str| tc.args.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+            let (kind, content) = if tc.name == "show_diagram" {
+                (lapce_rpc::core::AgentCanvasKind::Diagram, arg("diagram_code"))
+            } else {
+                (lapce_rpc::core::AgentCanvasKind::Code, arg("code"))
+            };
+            if let (true, Some(content)) = (result.success, content) {
+                let open = forge_agent::tools::display::opens_canvas(&content);
+                let canvas = lapce_rpc::core::AgentCanvas {
+                    kind,
+                    title: arg("title"),
+                    content,
+                    language: arg("language"),
+                };
+                core_rpc.agent_show_canvas(canvas, open);
+            }
+            result
         }
         "stop_project" => {
             let config_name = tc.args.get("config_name")
@@ -4090,6 +5291,58 @@ async fn execute_ide_tool(
             };
             forge_agent::tools::execute(&tool_call_obj, workspace_path, false).await
         }
+        // ── Diagnostics: lint locally, then push results to the problems panel ──
+        "diagnostics" => {
+            use forge_agent::tools::{deadline, lint};
+            if let Err(message) = forge_agent::tools::schema::validate_call(&tc.name, &tc.args) {
+                return forge_agent::tools::ToolResult::err(message);
+            }
+            let limit = deadline::Timeouts::configured().for_tool(&tc.name, forge_agent::tools::Tool::Diagnostics);
+            deadline::limit(&tc.name, limit, async {
+                match lint::run_diagnostics(&tc.args, workspace_path).await {
+                    Ok((target, result)) => {
+                        publish_lint_diagnostics(&target, &result, core_rpc);
+                        lint::format_diagnostics(&tc.args, &result)
+                    }
+                    Err(e) => e,
+                }
+            })
+            .await
+        }
+        // ── Review: run locally, then surface findings as editor diagnostics ──
+        "review" => {
+            use forge_agent::tools::review;
+            let target = review::ReviewTarget::from_args(&tc.args);
+            let path = tc.args.get("path").and_then(|v| v.as_str());
+            match review::collect_findings(workspace_path, &target, path).await {
+                Ok((hunks, findings)) => {
+                    publish_review_diagnostics(&findings, workspace_path, core_rpc);
+                    forge_agent::tools::ToolResult::ok(
+                        review::format_findings(&target, hunks, &findings)
+                    )
+                }
+                Err(e) => forge_agent::tools::ToolResult::err(e),
+            }
+        }
+        // ── Plugin-contributed tools: run in the owning plugin ──────────
+        name if forge_agent::tools::plugin::is_registered(name) => {
+            if let Err(message) = forge_agent::tools::schema::validate_call(name, &tc.args) {
+                return forge_agent::tools::ToolResult::err(message);
+            }
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            let tx = Arc::new(Mutex::new(Some(tx)));
+            catalog_rpc.call_agent_tool(name, tc.args.clone(), move |result| {
+                if let Some(tx) = tx.lock().take() {
+                    let _ = tx.send(result);
+                }
+            });
+            match tokio::time::timeout(std::time::Duration::from_secs(120), rx).await {
+                Ok(Ok(Ok(result))) if result.success => forge_agent::tools::ToolResult::ok(result.output),
+                Ok(Ok(Ok(result))) => forge_agent::tools::ToolResult::err(result.output),
+                Ok(Ok(Err(e))) => forge_agent::tools::ToolResult::err(format!("{name} failed: {}", e.message)),
+                _ => forge_agent::tools::ToolResult::err(format!("{name} timed out")),
+            }
+        }
         // ── All other tools: use standard execution ──────────────
         _ => {
             let tool_call_obj = forge_agent::tools::ToolCall {
@@ -4102,6 +5355,236 @@ async fn execute_ide_tool(
     }
 }
 
+/// Save a working note to the conversation's session and send the IDE the
+/// updated notes.
+fn record_working_note(
+    core_rpc: &CoreRpcHandler,
+    conversation_id: &str,
+    kind: forge_agent::session::NoteKind,
+    text: &str,
+) {
+    let mut session = forge_agent::session::Session::load(conversation_id);
+    if session.add_note(kind, text) {
+        session.save();
+        core_rpc.agent_working_notes(conversation_id.to_string(), working_notes(&session));
+    }
+}
+
+/// Apply a `focus_chain` call to the conversation's task list, save it and
+/// show it in the IDE's plan view.
+fn update_focus_chain(
+    core_rpc: &CoreRpcHandler,
+    conversation_id: &str,
+    args: &serde_json::Value,
+) -> forge_agent::tools::ToolResult {
+    use forge_agent::session::TaskStatus;
+    use lapce_rpc::core::{AgentPlanStep, AgentPlanStepStatus};
+
+    let mut session = forge_agent::session::Session::load(conversation_id);
+    let result = forge_agent::tools::focus_chain::apply(&mut session, args);
+    if result.success {
+        session.save();
+        core_rpc.agent_plan(
+            session
+                .tasks
+                .iter()
+                .map(|task| AgentPlanStep {
+                    number: task.id,
+                    description: task.description.clone(),
+                    status: match task.status {
+                        TaskStatus::Pending => AgentPlanStepStatus::Pending,
+                        TaskStatus::InProgress => AgentPlanStepStatus::InProgress,
+                        TaskStatus::Done => AgentPlanStepStatus::Done,
+                    },
+                })
+                .collect(),
+        );
+    }
+    result
+}
+
+fn working_notes(session: &forge_agent::session::Session) -> Vec<lapce_rpc::core::AgentNote> {
+    session
+        .notes
+        .iter()
+        .map(|note| lapce_rpc::core::AgentNote {
+            at: note.at,
+            kind: note.kind.as_str().to_string(),
+            text: note.text.clone(),
+        })
+        .collect()
+}
+
+/// Publish review findings as diagnostics (source "forge-review") so they show
+/// up inline in the editor and in the problems panel. Only files with findings
+/// are published; the language server's next publish for a file replaces them.
+fn publish_review_diagnostics(
+    findings: &[forge_agent::tools::review::ReviewFinding],
+    workspace_path: &Path,
+    core_rpc: &CoreRpcHandler,
+) {
+    let mut by_file: IndexMap<&str, Vec<lsp_types::Diagnostic>> = IndexMap::new();
+    for f in findings {
+        let severity = match f.severity {
+            forge_agent::tools::LintSeverity::Error => lsp_types::DiagnosticSeverity::ERROR,
+            forge_agent::tools::LintSeverity::Warning => lsp_types::DiagnosticSeverity::WARNING,
+            forge_agent::tools::LintSeverity::Info => lsp_types::DiagnosticSeverity::INFORMATION,
+        };
+        let line = f.line.saturating_sub(1) as u32;
+        let message = match &f.suggestion {
+            Some(s) => format!("{}\nSuggestion: {}", f.message, s),
+            None => f.message.clone(),
+        };
+        by_file.entry(f.file.as_str()).or_default().push(lsp_types::Diagnostic {
+            range: Range::new(Position::new(line, 0), Position::new(line, u32::MAX)),
+            severity: Some(severity),
+            source: Some("forge-review".to_string()),
+            message,
+            ..Default::default()
+        });
+    }
+
+    for (file, diagnostics) in by_file {
+        let Ok(uri) = Url::from_file_path(workspace_path.join(file)) else {
+            continue;
+        };
+        core_rpc.publish_diagnostics(lsp_types::PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        });
+    }
+}
+
+/// Queue a tool call for the user's approval and wait for the answer. When
+/// the configured timeout for its kind runs out first, the call is approved
+/// or rejected per that policy, so the agent (and forge-search) isn't left
+/// waiting forever.
+async fn await_approval(
+    pending_approvals: &Mutex<HashMap<String, tokio::sync::oneshot::Sender<bool>>>,
+    core_rpc: &CoreRpcHandler,
+    tool_call_id: &str,
+    tool_name: &str,
+    kind: forge_agent::approvals::ApprovalKind,
+    summary: &str,
+    preview: String,
+) -> bool {
+    let policy = forge_agent::approvals::ApprovalPolicy::configured(kind);
+    let (tx, rx) = tokio::sync::oneshot::channel::<bool>();
+    pending_approvals.lock().insert(tool_call_id.to_string(), tx);
+    core_rpc.agent_approval_queued(lapce_rpc::core::AgentPendingApproval {
+        tool_call_id: tool_call_id.to_string(),
+        tool_name: tool_name.to_string(),
+        kind: kind.as_str().to_string(),
+        summary: summary.to_string(),
+        preview,
+        timeout_secs: policy.timeout.map(|t| t.as_secs()),
+        approve_on_timeout: policy.approve_on_timeout,
+    });
+    let answer = match policy.timeout {
+        Some(timeout) => tokio::time::timeout(timeout, rx).await.ok(),
+        None => Some(rx.await),
+    };
+    let approved = match answer {
+        Some(Ok(approved)) => approved,
+        // The request was dropped, e.g. by a new conversation
+        Some(Err(_)) => false,
+        None => {
+            tracing::warn!(
+                "[APPROVAL] Timed out waiting for user approval of {tool_name} ({tool_call_id}), {}",
+                if policy.approve_on_timeout { "approving" } else { "rejecting" }
+            );
+            policy.approve_on_timeout
+        }
+    };
+    pending_approvals.lock().remove(tool_call_id);
+    core_rpc.agent_approval_resolved(tool_call_id.to_string());
+    approved
+}
+
+/// Publish a diagnostics tool run (source "forge-lint") so its findings show
+/// up as squiggles and in the problems panel. A linted file that came back
+/// clean is published empty, clearing what an earlier run left behind.
+fn publish_lint_diagnostics(
+    target: &Path,
+    result: &forge_agent::tools::LintResult,
+    core_rpc: &CoreRpcHandler,
+) {
+    use forge_agent::bridge::DiagnosticSeverity;
+    for (file, found) in forge_agent::tools::lint::editor_diagnostics(target, result) {
+        let Ok(uri) = Url::from_file_path(&file) else {
+            continue;
+        };
+        let diagnostics = found
+            .into_iter()
+            .map(|d| {
+                let line = d.line.saturating_sub(1);
+                lsp_types::Diagnostic {
+                    range: Range::new(Position::new(line, d.column.saturating_sub(1)), Position::new(line, u32::MAX)),
+                    severity: Some(match d.severity {
+                        DiagnosticSeverity::Error => lsp_types::DiagnosticSeverity::ERROR,
+                        DiagnosticSeverity::Warning => lsp_types::DiagnosticSeverity::WARNING,
+                        DiagnosticSeverity::Info => lsp_types::DiagnosticSeverity::INFORMATION,
+                        DiagnosticSeverity::Hint => lsp_types::DiagnosticSeverity::HINT,
+                    }),
+                    source: d.source,
+                    message: d.message,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        core_rpc.publish_diagnostics(lsp_types::PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        });
+    }
+}
+
+/// Transcribe a WAV recording with `provider`'s OpenAI-compatible
+/// transcription endpoint.
+fn transcribe_with_provider(provider: &str, url: &str, model: &str, audio_data: Vec<u8>) -> Result<String, String> {
+    // Environment, .forge/config.toml or the keychain, then ai-keys.toml
+    let key = forge_agent::secrets::api_key(provider)
+        .or_else(|| {
+            use lapce_core::directory::Directory;
+            let path = Directory::config_directory()?.join("ai-keys.toml");
+            let content = std::fs::read_to_string(&path).ok()?;
+            let config: toml::Value = toml::from_str(&content).ok()?;
+            config.get("keys")?.get(provider)?.as_str().map(String::from)
+        })
+        .ok_or_else(|| format!(
+            "{provider} API key not found. Add a '{provider}' key to ai-keys.toml, or set stt = \"local\" to use whisper.cpp."
+        ))?;
+
+    let client = crate::blocking_http_client_builder().build().unwrap_or_default();
+    let audio_part = reqwest::blocking::multipart::Part::bytes(audio_data)
+        .file_name("audio.wav")
+        .mime_str("audio/wav")
+        .map_err(|e| e.to_string())?;
+    let form = reqwest::blocking::multipart::Form::new()
+        .text("model", model.to_string())
+        .text("temperature", "0")
+        .text("response_format", "json")
+        .part("file", audio_part);
+
+    let resp = client
+        .post(url)
+        .header("Authorization", format!("Bearer {key}"))
+        .multipart(form)
+        .send()
+        .map_err(|e| format!("Transcription request failed: {e}"))?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().unwrap_or_default();
+        return Err(format!("Transcription API error {status}: {body}"));
+    }
+    let json = resp
+        .json::<serde_json::Value>()
+        .map_err(|e| format!("Failed to parse transcription response: {e}"))?;
+    Ok(json.get("text").and_then(|t| t.as_str()).unwrap_or("").trim().to_string())
+}
+
 /// Collect relevant files from the workspace to attach to the AI prompt.
 /// This gives the cloud "Brain" live context about what the user is working on.
 fn collect_relevant_files(workspace_path: &Path) -> Vec<serde_json::Value> {
@@ -4159,6 +5642,37 @@ impl Dispatcher {
 
         let file_watcher = FileWatcher::new();
 
+        let agent_buffers = Arc::new(Mutex::new(HashMap::new()));
+        forge_agent::tools::buffers::set_overlay(Arc::new(AgentOpenBuffers {
+            buffers: agent_buffers.clone(),
+            core_rpc: core_rpc.clone(),
+        }));
+
+        // Left by a proxy that crashed
+        let orphans = forge_agent::tools::resources::sweep_orphans();
+        if orphans > 0 {
+            tracing::info!("Removed {orphans} orphaned agent temp files");
+        }
+        let pending_approvals: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<bool>>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let pending_followups: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<String>>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let agent_terminal_mgr = Arc::new(AgentTerminalManager::new());
+        let approvals = pending_approvals.clone();
+        forge_agent::tools::resources::on_shutdown("reject pending approvals", move || {
+            for (_, sender) in approvals.lock().drain() {
+                let _ = sender.send(false);
+            }
+        });
+        let followups = pending_followups.clone();
+        forge_agent::tools::resources::on_shutdown("drop pending questions", move || {
+            followups.lock().clear();
+        });
+        let terminals = agent_terminal_mgr.clone();
+        forge_agent::tools::resources::on_shutdown("close agent terminals", move || {
+            terminals.close_all();
+        });
+
         Self {
             workspace: None,
             proxy_rpc,
@@ -4171,9 +5685,13 @@ impl Dispatcher {
             tab_id: 1,
             db_manager: crate::database::connection_manager::ConnectionManager::new(),
             pending_diff_snapshots: Arc::new(Mutex::new(HashMap::new())),
-            pending_approvals: Arc::new(Mutex::new(HashMap::new())),
+            pending_approvals,
+            pending_followups,
             auto_approve_session: Arc::new(std::sync::atomic::AtomicBool::new(true)),
-            agent_terminal_mgr: Arc::new(AgentTerminalManager::new()),
+            agent_terminal_mgr,
+            agent_buffers,
+            running_turns: Default::default(),
+            pools: WorkerPools::new(),
         }
     }
 
@@ -4282,6 +5800,11 @@ impl FileWatchNotifier {
             _ => return,
         };
 
+        // Keep the semantic index current for changes made outside the editor too
+        if let Some(workspace) = self.workspace.as_deref() {
+            forge_agent::index_watch::files_changed(workspace, event.paths.iter().cloned());
+        }
+
         let mut handler = self.workspace_fs_change_handler.lock();
         if let Some(sender) = handler.as_mut() {
             if explorer_change {
@@ -1,17 +1,21 @@
 #![allow(clippy::manual_clamp)]
 
+pub mod agent_events;
 pub mod agent_terminal;
 pub mod ai_completion;
 pub mod buffer;
 pub mod cli;
+pub mod dap_auto_config;
 pub mod database;
 pub mod dispatch;
 pub mod gix_utils;
 pub mod plugin;
 pub mod proto_manager;
 pub mod run_config_detector;
+pub mod run_history;
 pub mod terminal;
 pub mod watcher;
+pub mod worker_pool;
 
 use std::{
     io::{BufReader, stdin, stdout},
@@ -192,19 +196,56 @@ fn listen_local_socket(proxy_rpc: ProxyRpcHandler) -> Result<()> {
     Ok(())
 }
 
+/// Proxy and CA certificates from the forge config (`http_proxy`,
+/// `no_proxy`, `ca_bundle`), for the reqwest 0.11 clients of the IDE.
+fn network_proxy_and_certs() -> (Option<reqwest::Proxy>, Vec<reqwest::Certificate>) {
+    let settings = forge_agent::http::NetworkSettings::from_config();
+    let proxy = settings.proxy.as_deref().and_then(|url| {
+        let no_proxy = settings
+            .no_proxy
+            .as_deref()
+            .and_then(reqwest::NoProxy::from_string);
+        reqwest::Proxy::all(url).ok().map(|p| p.no_proxy(no_proxy))
+    });
+    let certs = settings
+        .ca_pem()
+        .and_then(|pem| reqwest::Certificate::from_pem_bundle(&pem).ok())
+        .unwrap_or_default();
+    (proxy, certs)
+}
+
+/// Async client builder with the configured proxy and CA certificates.
+pub fn http_client_builder() -> reqwest::ClientBuilder {
+    let (proxy, certs) = network_proxy_and_certs();
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(proxy);
+    }
+    for cert in certs {
+        builder = builder.add_root_certificate(cert);
+    }
+    builder
+}
+
+/// Blocking client builder with the configured proxy and CA certificates.
+pub fn blocking_http_client_builder() -> reqwest::blocking::ClientBuilder {
+    let (proxy, certs) = network_proxy_and_certs();
+    let mut builder = reqwest::blocking::Client::builder();
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(proxy);
+    }
+    for cert in certs {
+        builder = builder.add_root_certificate(cert);
+    }
+    builder
+}
+
 pub fn get_url<T: reqwest::IntoUrl + Clone>(
     url: T,
     user_agent: Option<&str>,
 ) -> Result<reqwest::blocking::Response> {
-    let mut builder = if let Ok(proxy) = std::env::var("https_proxy") {
-        let proxy = reqwest::Proxy::all(proxy)?;
-        reqwest::blocking::Client::builder()
-            .proxy(proxy)
-            .timeout(std::time::Duration::from_secs(10))
-    } else {
-        reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(10))
-    };
+    let mut builder =
+        blocking_http_client_builder().timeout(std::time::Duration::from_secs(10));
     if let Some(user_agent) = user_agent {
         builder = builder.user_agent(user_agent);
     }
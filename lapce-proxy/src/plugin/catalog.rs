@@ -671,6 +671,7 @@ impl PluginCatalog {
                 for id in ids {
                     if self.plugins.get(&id).unwrap().volt_id == volt_id {
                         let plugin = self.plugins.remove(&id).unwrap();
+                        forge_agent::tools::plugin::unregister_owner(id.0);
                         plugin.shutdown();
                     }
                 }
@@ -685,6 +686,7 @@ impl PluginCatalog {
                 for id in ids {
                     if self.plugins.get(&id).unwrap().volt_id == volt_id {
                         let plugin = self.plugins.remove(&id).unwrap();
+                        forge_agent::tools::plugin::unregister_owner(id.0);
                         plugin.shutdown();
                     }
                 }
@@ -1,7 +1,7 @@
 use std::{
     collections::HashMap,
     io::{BufReader, BufWriter, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{Child, Command, Stdio},
     sync::{
         Arc,
@@ -19,7 +19,7 @@ use lapce_rpc::{
         DapEvent, DapId, DapPayload, DapRequest, DapResponse, DapServer,
         DebuggerCapabilities, Disconnect, Initialize, Launch, Next, NextArguments,
         Pause, PauseArguments, Request, RunDebugConfig, RunInTerminal,
-        RunInTerminalArguments, RunInTerminalResponse, Scope, Scopes,
+        RunInTerminalArguments, RunInTerminalResponse, RunVariables, Scope, Scopes,
         ScopesArguments, ScopesResponse, SetBreakpoints, SetBreakpointsArguments,
         SetBreakpointsResponse, Source, SourceBreakpoint, StackTrace,
         StackTraceArguments, StackTraceResponse, StepIn, StepInArguments, StepOut,
@@ -620,12 +620,18 @@ impl DapRpcHandler {
     }
 
     pub fn launch(&self, config: &RunDebugConfig) -> Result<()> {
+        let mut config = config.clone();
+        config.substitute_variables(&RunVariables::env_only());
+        let config = &config;
+        let env = config
+            .resolved_env(config.cwd.as_deref().map(Path::new))
+            .map_err(|e| anyhow!(e))?;
         let params = serde_json::json!({
             "program": config.program,
             "args": config.args,
             "cwd": config.cwd,
             "runInTerminal": true,
-            "env": config.env
+            "env": env
         });
         let _resp = self
             .request::<Launch>(params)
@@ -1545,6 +1545,81 @@ impl PluginCatalogRpcHandler {
             tracing::error!("{:?}", err);
         }
     }
+
+    /// Add an agent tool contributed by `plugin_id`; it's dropped again when
+    /// the plugin stops or reloads.
+    pub fn register_agent_tool(
+        &self,
+        plugin_id: PluginId,
+        name: String,
+        description: String,
+        parameters: Value,
+    ) -> Result<(), String> {
+        forge_agent::tools::plugin::register(forge_agent::tools::plugin::PluginTool {
+            name,
+            description,
+            parameters,
+            owner: plugin_id.0,
+        })
+    }
+
+    /// Run a plugin-contributed agent tool in the plugin that registered it.
+    pub fn call_agent_tool(
+        &self,
+        name: &str,
+        arguments: Value,
+        f: impl FnOnce(Result<AgentToolResult, RpcError>) + Clone + Send + 'static,
+    ) {
+        let Some(tool) = forge_agent::tools::plugin::get(name) else {
+            f(Err(RpcError {
+                code: 0,
+                message: format!("no plugin provides tool {name}"),
+            }));
+            return;
+        };
+        self.send_request(
+            Some(PluginId(tool.owner)),
+            None,
+            EXECUTE_AGENT_TOOL,
+            serde_json::json!({ "name": name, "arguments": arguments }),
+            None,
+            None,
+            false,
+            move |_, result| {
+                f(result.and_then(|value| {
+                    serde_json::from_value(value).map_err(|e| RpcError {
+                        code: 0,
+                        message: format!("invalid tool result: {e}"),
+                    })
+                }))
+            },
+        );
+    }
+}
+
+/// Plugin → host request contributing an agent tool.
+pub const REGISTER_AGENT_TOOL: &str = "forge/registerAgentTool";
+/// Host → plugin request running one of its agent tools.
+pub const EXECUTE_AGENT_TOOL: &str = "forge/executeAgentTool";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RegisterAgentToolParams {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// JSON schema of the tool's arguments.
+    pub parameters: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentToolResult {
+    pub output: String,
+    #[serde(default = "default_success")]
+    pub success: bool,
+}
+
+fn default_success() -> bool {
+    true
 }
 
 #[derive(Serialize, Deserialize, Debug)]
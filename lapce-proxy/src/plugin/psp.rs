@@ -996,6 +996,19 @@ impl PluginHostHandler {
                 );
                 resp.send_null();
             }
+            super::REGISTER_AGENT_TOOL => {
+                let params: super::RegisterAgentToolParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                match self.catalog_rpc.register_agent_tool(
+                    self.server_rpc.plugin_id,
+                    params.name,
+                    params.description,
+                    params.parameters,
+                ) {
+                    Ok(()) => resp.send_null(),
+                    Err(message) => resp.send_err(0, message),
+                }
+            }
             StartLspServer::METHOD => {
                 let params: StartLspServerParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
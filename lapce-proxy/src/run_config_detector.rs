@@ -10,6 +10,11 @@
 //! 7. Makefile - Make targets
 //! 8. pyproject.toml - Python projects
 //! 9. go.mod - Go projects
+//! 10. build.gradle(.kts) / pom.xml - Gradle tasks and Maven goals
+//! 11. WORKSPACE / MODULE.bazel - Bazel targets
+//! 12. deno.json(c) - Deno tasks
+//! 13. pubspec.yaml - Flutter run variants
+//! 14. CMakeLists.txt - CMake configure/build and ctest
 
 use std::path::Path;
 use std::fs;
@@ -50,6 +55,22 @@ pub fn detect_run_configs(workspace: &Path) -> Vec<DetectedRunConfig> {
     // Priority 9: Go projects
     configs.extend(detect_go_commands(workspace));
     
+    // Priority 10: JVM projects
+    configs.extend(detect_gradle_tasks(workspace));
+    configs.extend(detect_maven_goals(workspace));
+    
+    // Priority 11: Bazel targets
+    configs.extend(detect_bazel_targets(workspace));
+    
+    // Priority 12: Deno tasks
+    configs.extend(detect_deno_tasks(workspace));
+    
+    // Priority 13: Flutter
+    configs.extend(detect_flutter_commands(workspace));
+    
+    // Priority 14: CMake / ctest
+    configs.extend(detect_cmake_commands(workspace));
+    
     configs
 }
 
@@ -708,6 +729,366 @@ fn detect_go_commands(workspace: &Path) -> Vec<DetectedRunConfig> {
     configs
 }
 
+// ============================================================================
+// Gradle (build.gradle / build.gradle.kts)
+// ============================================================================
+
+fn detect_gradle_tasks(workspace: &Path) -> Vec<DetectedRunConfig> {
+    let mut configs = Vec::new();
+    
+    let build_file = ["build.gradle.kts", "build.gradle", "settings.gradle.kts", "settings.gradle"]
+        .iter()
+        .map(|f| workspace.join(f))
+        .find(|p| p.exists());
+    let Some(build_file) = build_file else {
+        return configs;
+    };
+    
+    tracing::info!("Detecting Gradle tasks from {:?}", build_file);
+    
+    // Prefer the project's wrapper so the pinned Gradle version is used
+    let gradle = if workspace.join("gradlew").exists() {
+        "./gradlew"
+    } else {
+        "gradle"
+    };
+    
+    // Plugins may be applied in the root or in module build files (app/build.gradle)
+    let mut build_scripts = String::new();
+    for dir in [workspace.to_path_buf(), workspace.join("app")] {
+        for name in ["build.gradle.kts", "build.gradle"] {
+            if let Ok(content) = fs::read_to_string(dir.join(name)) {
+                build_scripts.push_str(&content);
+            }
+        }
+    }
+    
+    let mut tasks = vec!["build", "test", "clean"];
+    if build_scripts.contains("com.android.application") {
+        tasks.extend(["assembleDebug", "installDebug"]);
+    }
+    if build_scripts.contains("org.springframework.boot") {
+        tasks.push("bootRun");
+    } else if build_scripts.contains("application") || build_scripts.contains("mainClass") {
+        tasks.push("run");
+    }
+    
+    for task in tasks {
+        configs.push(DetectedRunConfig {
+            name: format!("gradle {}", task),
+            config_type: "gradle".to_string(),
+            command: gradle.to_string(),
+            args: vec![task.to_string()],
+            cwd: Some(workspace.to_string_lossy().to_string()),
+            source: build_file
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        });
+    }
+    
+    configs
+}
+
+// ============================================================================
+// Maven (pom.xml)
+// ============================================================================
+
+fn detect_maven_goals(workspace: &Path) -> Vec<DetectedRunConfig> {
+    let mut configs = Vec::new();
+    let pom = workspace.join("pom.xml");
+    
+    let Ok(content) = fs::read_to_string(&pom) else {
+        return configs;
+    };
+    
+    tracing::info!("Detecting Maven goals from {:?}", pom);
+    
+    let mvn = if workspace.join("mvnw").exists() {
+        "./mvnw"
+    } else {
+        "mvn"
+    };
+    
+    let mut goals: Vec<Vec<&str>> = vec![
+        vec!["package"],
+        vec!["test"],
+        vec!["clean", "install"],
+    ];
+    if content.contains("spring-boot-maven-plugin") {
+        goals.push(vec!["spring-boot:run"]);
+    }
+    if content.contains("exec-maven-plugin") {
+        goals.push(vec!["exec:java"]);
+    }
+    if content.contains("quarkus-maven-plugin") {
+        goals.push(vec!["quarkus:dev"]);
+    }
+    
+    for goal in goals {
+        configs.push(DetectedRunConfig {
+            name: format!("mvn {}", goal.join(" ")),
+            config_type: "maven".to_string(),
+            command: mvn.to_string(),
+            args: goal.into_iter().map(String::from).collect(),
+            cwd: Some(workspace.to_string_lossy().to_string()),
+            source: "pom.xml".to_string(),
+        });
+    }
+    
+    configs
+}
+
+// ============================================================================
+// Bazel (WORKSPACE / MODULE.bazel)
+// ============================================================================
+
+/// Max directory depth scanned for BUILD files.
+const BAZEL_MAX_DEPTH: usize = 4;
+/// Max `bazel run` targets reported.
+const BAZEL_MAX_TARGETS: usize = 20;
+
+fn detect_bazel_targets(workspace: &Path) -> Vec<DetectedRunConfig> {
+    let mut configs = Vec::new();
+    
+    let marker = ["MODULE.bazel", "WORKSPACE.bazel", "WORKSPACE"]
+        .iter()
+        .find(|f| workspace.join(f).exists());
+    let Some(marker) = marker else {
+        return configs;
+    };
+    
+    tracing::info!("Detecting Bazel targets from {}", marker);
+    
+    // bazelisk respects .bazelversion; fall back to plain bazel
+    let bazel = if workspace.join(".bazelversion").exists() && which_exists("bazelisk") {
+        "bazelisk"
+    } else {
+        "bazel"
+    };
+    
+    for (verb, pattern) in [("build", "//..."), ("test", "//...")] {
+        configs.push(DetectedRunConfig {
+            name: format!("bazel {} {}", verb, pattern),
+            config_type: "bazel".to_string(),
+            command: bazel.to_string(),
+            args: vec![verb.to_string(), pattern.to_string()],
+            cwd: Some(workspace.to_string_lossy().to_string()),
+            source: marker.to_string(),
+        });
+    }
+    
+    // Runnable targets: *_binary(name = "...") rules in BUILD files
+    let Ok(rule_re) = regex::Regex::new(r#"(?s)\b(\w+_binary)\s*\(\s*.*?\bname\s*=\s*"([^"]+)""#) else {
+        return configs;
+    };
+    let build_files = walkdir::WalkDir::new(workspace)
+        .max_depth(BAZEL_MAX_DEPTH)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            !(name.starts_with('.') || name.starts_with("bazel-") || name == "node_modules")
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| matches!(e.file_name().to_str(), Some("BUILD") | Some("BUILD.bazel")));
+    
+    'files: for entry in build_files {
+        let Ok(content) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let package = entry
+            .path()
+            .parent()
+            .and_then(|p| p.strip_prefix(workspace).ok())
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        for cap in rule_re.captures_iter(&content) {
+            if configs.len() >= BAZEL_MAX_TARGETS + 2 {
+                break 'files;
+            }
+            let label = format!("//{}:{}", package, &cap[2]);
+            configs.push(DetectedRunConfig {
+                name: format!("bazel run {}", label),
+                config_type: "bazel".to_string(),
+                command: bazel.to_string(),
+                args: vec!["run".to_string(), label],
+                cwd: Some(workspace.to_string_lossy().to_string()),
+                source: "BUILD".to_string(),
+            });
+        }
+    }
+    
+    configs
+}
+
+fn which_exists(program: &str) -> bool {
+    Command::new(program)
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+// ============================================================================
+// Deno (deno.json / deno.jsonc)
+// ============================================================================
+
+fn detect_deno_tasks(workspace: &Path) -> Vec<DetectedRunConfig> {
+    let mut configs = Vec::new();
+    
+    let config_file = ["deno.json", "deno.jsonc"]
+        .iter()
+        .find(|f| workspace.join(f).exists());
+    let Some(config_file) = config_file else {
+        return configs;
+    };
+    
+    tracing::info!("Detecting deno tasks from {}", config_file);
+    
+    let Ok(content) = fs::read_to_string(workspace.join(config_file)) else {
+        return configs;
+    };
+    let content = strip_json_comments(&content);
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) else {
+        tracing::warn!("Failed to parse {}", config_file);
+        return configs;
+    };
+    
+    if let Some(tasks) = json.get("tasks").and_then(|t| t.as_object()) {
+        for name in tasks.keys() {
+            configs.push(DetectedRunConfig {
+                name: format!("deno task {}", name),
+                config_type: "deno".to_string(),
+                command: "deno".to_string(),
+                args: vec!["task".to_string(), name.clone()],
+                cwd: Some(workspace.to_string_lossy().to_string()),
+                source: config_file.to_string(),
+            });
+        }
+    }
+    
+    // Entry point without tasks: offer a plain run
+    if configs.is_empty() {
+        if let Some(entry) = ["main.ts", "mod.ts", "main.js"].iter().find(|f| workspace.join(f).exists()) {
+            configs.push(DetectedRunConfig {
+                name: format!("deno run {}", entry),
+                config_type: "deno".to_string(),
+                command: "deno".to_string(),
+                args: vec!["run".to_string(), "-A".to_string(), entry.to_string()],
+                cwd: Some(workspace.to_string_lossy().to_string()),
+                source: config_file.to_string(),
+            });
+        }
+    }
+    
+    configs
+}
+
+// ============================================================================
+// Flutter (pubspec.yaml)
+// ============================================================================
+
+fn detect_flutter_commands(workspace: &Path) -> Vec<DetectedRunConfig> {
+    let mut configs = Vec::new();
+    let pubspec = workspace.join("pubspec.yaml");
+    
+    let Ok(content) = fs::read_to_string(&pubspec) else {
+        return configs;
+    };
+    // Plain Dart packages don't depend on the flutter SDK
+    if !content.contains("sdk: flutter") {
+        return configs;
+    }
+    
+    tracing::info!("Detecting Flutter commands from {:?}", pubspec);
+    
+    let mut variants: Vec<(String, Vec<String>)> = vec![
+        ("flutter run".to_string(), vec!["run".to_string()]),
+        ("flutter run --profile".to_string(), vec!["run".to_string(), "--profile".to_string()]),
+        ("flutter run --release".to_string(), vec!["run".to_string(), "--release".to_string()]),
+        ("flutter test".to_string(), vec!["test".to_string()]),
+    ];
+    
+    // Additional entry points (lib/main_dev.dart, lib/main_prod.dart, ...)
+    if let Ok(entries) = fs::read_dir(workspace.join("lib")) {
+        let mut mains: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().to_str().map(String::from))
+            .filter(|n| n.starts_with("main_") && n.ends_with(".dart"))
+            .collect();
+        mains.sort();
+        for main in mains {
+            let target = format!("lib/{}", main);
+            variants.push((
+                format!("flutter run -t {}", target),
+                vec!["run".to_string(), "-t".to_string(), target],
+            ));
+        }
+    }
+    
+    for (name, args) in variants {
+        configs.push(DetectedRunConfig {
+            name,
+            config_type: "flutter".to_string(),
+            command: "flutter".to_string(),
+            args,
+            cwd: Some(workspace.to_string_lossy().to_string()),
+            source: "pubspec.yaml".to_string(),
+        });
+    }
+    
+    configs
+}
+
+// ============================================================================
+// CMake (CMakeLists.txt)
+// ============================================================================
+
+/// Build directories checked for an existing CMake cache, in order.
+const CMAKE_BUILD_DIRS: &[&str] = &["build", "cmake-build-debug", "cmake-build-release", "out/build"];
+
+fn detect_cmake_commands(workspace: &Path) -> Vec<DetectedRunConfig> {
+    let mut configs = Vec::new();
+    let Ok(content) = fs::read_to_string(workspace.join("CMakeLists.txt")) else {
+        return configs;
+    };
+    
+    tracing::info!("Detecting CMake commands from CMakeLists.txt");
+    
+    // Reuse a configured build directory, else the conventional one
+    let build_dir = CMAKE_BUILD_DIRS
+        .iter()
+        .find(|d| workspace.join(d).join("CMakeCache.txt").exists())
+        .copied()
+        .unwrap_or("build");
+    
+    let mut commands = vec![
+        ("cmake configure", "cmake", vec!["-S", ".", "-B", build_dir, "-DCMAKE_EXPORT_COMPILE_COMMANDS=ON"]),
+        ("cmake build", "cmake", vec!["--build", build_dir]),
+    ];
+    // ctest only finds tests once enable_testing() or CTest is included
+    let lower = content.to_ascii_lowercase();
+    let has_tests = lower.contains("enable_testing(")
+        || lower.contains("include(ctest)")
+        || workspace.join(build_dir).join("CTestTestfile.cmake").exists();
+    if has_tests {
+        commands.push(("ctest", "ctest", vec!["--test-dir", build_dir, "--output-on-failure"]));
+    }
+    
+    for (name, command, args) in commands {
+        configs.push(DetectedRunConfig {
+            name: name.to_string(),
+            config_type: "cmake".to_string(),
+            command: command.to_string(),
+            args: args.into_iter().map(String::from).collect(),
+            cwd: Some(workspace.to_string_lossy().to_string()),
+            source: "CMakeLists.txt".to_string(),
+        });
+    }
+    
+    configs
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
@@ -791,4 +1172,80 @@ mod tests {
         assert!(!result.contains("/*"));
         assert!(result.contains("\"key\""));
     }
+    
+    fn temp_workspace(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "lapce-run-config-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    #[test]
+    fn test_detect_gradle_and_maven() {
+        let dir = temp_workspace("jvm");
+        fs::write(dir.join("build.gradle.kts"), "plugins { id(\"org.springframework.boot\") }").unwrap();
+        fs::write(dir.join("gradlew"), "").unwrap();
+        fs::write(dir.join("pom.xml"), "<plugin><artifactId>exec-maven-plugin</artifactId></plugin>").unwrap();
+        
+        let gradle = detect_gradle_tasks(&dir);
+        assert!(gradle.iter().all(|c| c.command == "./gradlew"));
+        assert!(gradle.iter().any(|c| c.args == ["bootRun"]));
+        
+        let maven = detect_maven_goals(&dir);
+        assert_eq!(maven[0].command, "mvn");
+        assert!(maven.iter().any(|c| c.args == ["exec:java"]));
+        assert!(!maven.iter().any(|c| c.args == ["spring-boot:run"]));
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_detect_bazel_deno_flutter() {
+        let dir = temp_workspace("misc");
+        fs::write(dir.join("MODULE.bazel"), "").unwrap();
+        fs::create_dir_all(dir.join("server")).unwrap();
+        fs::write(
+            dir.join("server/BUILD.bazel"),
+            "go_binary(\n    name = \"api\",\n    srcs = [\"main.go\"],\n)\ngo_library(name = \"lib\")\n",
+        )
+        .unwrap();
+        let bazel = detect_bazel_targets(&dir);
+        assert!(bazel.iter().any(|c| c.args == ["run", "//server:api"]));
+        assert!(!bazel.iter().any(|c| c.name.contains(":lib")));
+        
+        fs::write(dir.join("deno.jsonc"), "{ // comment\n \"tasks\": { \"dev\": \"deno run main.ts\" } }").unwrap();
+        let deno = detect_deno_tasks(&dir);
+        assert_eq!(deno.len(), 1);
+        assert_eq!(deno[0].args, ["task", "dev"]);
+        
+        fs::write(dir.join("pubspec.yaml"), "dependencies:\n  flutter:\n    sdk: flutter\n").unwrap();
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::write(dir.join("lib/main_dev.dart"), "").unwrap();
+        let flutter = detect_flutter_commands(&dir);
+        assert!(flutter.iter().any(|c| c.args == ["run", "-t", "lib/main_dev.dart"]));
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_detect_cmake() {
+        let dir = temp_workspace("cmake");
+        fs::write(dir.join("CMakeLists.txt"), "project(app)\nadd_executable(app main.cpp)\n").unwrap();
+        let cmake = detect_cmake_commands(&dir);
+        assert_eq!(cmake.len(), 2);
+        assert!(cmake.iter().any(|c| c.args == ["--build", "build"]));
+        
+        fs::write(dir.join("CMakeLists.txt"), "project(app)\ninclude(CTest)\nadd_test(NAME unit COMMAND tests)\n").unwrap();
+        fs::create_dir_all(dir.join("cmake-build-debug")).unwrap();
+        fs::write(dir.join("cmake-build-debug/CMakeCache.txt"), "").unwrap();
+        let cmake = detect_cmake_commands(&dir);
+        let ctest = cmake.iter().find(|c| c.command == "ctest").unwrap();
+        assert_eq!(ctest.args, ["--test-dir", "cmake-build-debug", "--output-on-failure"]);
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,132 @@
+//! Per-workspace history of launched run configurations.
+//!
+//! Stored under the local data directory (not in the workspace) as
+//! `run_history/<workspace hash>.json`, newest entry first. Each config
+//! appears once per mode, so the history doubles as a "recently run" list.
+
+use std::path::{Path, PathBuf};
+
+use lapce_core::directory::Directory;
+use lapce_rpc::proxy::RunHistoryEntry;
+
+/// Max entries kept per workspace.
+const MAX_HISTORY: usize = 50;
+
+fn history_file(workspace: &Path) -> Option<PathBuf> {
+    let dir = Directory::data_local_directory()?.join("run_history");
+    if let Err(err) = std::fs::create_dir_all(&dir) {
+        tracing::error!("{:?}", err);
+        return None;
+    }
+    let key = workspace.to_string_lossy();
+    Some(dir.join(format!("{:016x}.json", fnv1a(key.as_bytes()))))
+}
+
+pub fn load(workspace: &Path) -> Vec<RunHistoryEntry> {
+    history_file(workspace)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(workspace: &Path, entries: &[RunHistoryEntry]) {
+    let Some(path) = history_file(workspace) else {
+        return;
+    };
+    match serde_json::to_string_pretty(entries) {
+        Ok(content) => {
+            if let Err(err) = std::fs::write(path, content) {
+                tracing::error!("Failed to save run history: {:?}", err);
+            }
+        }
+        Err(err) => tracing::error!("{:?}", err),
+    }
+}
+
+/// Record a launch.
+pub fn record(workspace: &Path, entry: RunHistoryEntry) {
+    let mut entries = load(workspace);
+    push_entry(&mut entries, entry);
+    save(workspace, &entries);
+}
+
+/// Set the exit code of the most recent launch of `name`.
+pub fn set_exit_code(workspace: &Path, name: &str, exit_code: Option<i32>) {
+    let mut entries = load(workspace);
+    if mark_exited(&mut entries, name, exit_code) {
+        save(workspace, &entries);
+    }
+}
+
+fn push_entry(entries: &mut Vec<RunHistoryEntry>, entry: RunHistoryEntry) {
+    entries.retain(|e| !(e.config.name == entry.config.name && e.mode == entry.mode));
+    entries.insert(0, entry);
+    entries.truncate(MAX_HISTORY);
+}
+
+fn mark_exited(
+    entries: &mut [RunHistoryEntry],
+    name: &str,
+    exit_code: Option<i32>,
+) -> bool {
+    match entries.iter_mut().find(|e| e.config.name == name) {
+        Some(entry) => {
+            entry.exit_code = exit_code;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Stable 64-bit FNV-1a hash, used for history file names.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_rpc::dap_types::RunDebugConfig;
+
+    use super::*;
+
+    fn entry(name: &str, mode: &str, started_at: i64) -> RunHistoryEntry {
+        let config: RunDebugConfig = toml::from_str(&format!(
+            "name = \"{name}\"\nprogram = \"cargo\""
+        ))
+        .unwrap();
+        RunHistoryEntry {
+            config,
+            mode: mode.to_string(),
+            started_at,
+            exit_code: None,
+        }
+    }
+
+    #[test]
+    fn test_push_and_mark_exited() {
+        let mut entries = Vec::new();
+        push_entry(&mut entries, entry("build", "run", 1));
+        push_entry(&mut entries, entry("server", "run", 2));
+        push_entry(&mut entries, entry("build", "debug", 3));
+        push_entry(&mut entries, entry("build", "run", 4));
+
+        let order: Vec<_> = entries
+            .iter()
+            .map(|e| (e.config.name.as_str(), e.mode.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![("build", "run"), ("build", "debug"), ("server", "run")]
+        );
+
+        assert!(mark_exited(&mut entries, "build", Some(1)));
+        assert_eq!(entries[0].exit_code, Some(1));
+        assert_eq!(entries[1].exit_code, None);
+        assert!(!mark_exited(&mut entries, "missing", Some(0)));
+    }
+}
@@ -18,10 +18,13 @@ use crossbeam_channel::{Receiver, Sender};
 use directories::BaseDirs;
 use lapce_rpc::{
     core::CoreRpcHandler,
+    dap_types::RunVariables,
     terminal::{TermId, TerminalProfile},
 };
 use polling::PollMode;
 
+use crate::agent_terminal::RunCapture;
+
 const READ_BUFFER_SIZE: usize = 0x10_0000;
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
@@ -60,17 +63,31 @@ pub struct Terminal {
     pub(crate) pty: alacritty_terminal::tty::Pty,
     rx: Receiver<Msg>,
     pub tx: Sender<Msg>,
+    /// Output capture for run-config terminals, read by the agent.
+    pub capture: Option<Arc<RunCapture>>,
 }
 
 impl Terminal {
     pub fn new(
         term_id: TermId,
-        profile: TerminalProfile,
+        mut profile: TerminalProfile,
         width: usize,
         height: usize,
     ) -> Result<Terminal> {
         let poll = polling::Poller::new()?.into();
 
+        // Run configs may reference ${env:VAR}, which must come from the
+        // machine the process runs on
+        let vars = RunVariables::env_only();
+        let sub = |s: &mut String| *s = vars.substitute(s);
+        profile.command.iter_mut().for_each(sub);
+        profile.arguments.iter_mut().flatten().for_each(sub);
+        profile
+            .environment
+            .iter_mut()
+            .flat_map(|e| e.values_mut())
+            .for_each(sub);
+
         let options = Options {
             shell: Terminal::program(&profile),
             working_directory: Terminal::workdir(&profile),
@@ -99,6 +116,7 @@ impl Terminal {
             pty,
             tx,
             rx,
+            capture: None,
         })
     }
 
@@ -200,6 +218,9 @@ impl Terminal {
                     .unwrap();
             }
         }
+        if let Some(capture) = &self.capture {
+            capture.set_exited(exit_code);
+        }
         core_rpc.terminal_process_stopped(self.term_id, exit_code);
         if let Err(err) = self.pty.deregister(&self.poller) {
             tracing::error!("{:?}", err);
@@ -231,6 +252,9 @@ impl Terminal {
             match self.pty.reader().read(buf) {
                 Ok(0) => break,
                 Ok(n) => {
+                    if let Some(capture) = &self.capture {
+                        capture.append(&buf[..n]);
+                    }
                     core_rpc.update_terminal(self.term_id, buf[..n].to_vec());
                 }
                 Err(err) => match err.kind() {
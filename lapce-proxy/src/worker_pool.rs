@@ -0,0 +1,180 @@
+//! Worker threads for request handlers that can't answer on the dispatcher
+//! thread.
+//!
+//! Each [`RequestClass`] has its own pool with a fixed number of workers, so
+//! indexing or an agent's LSP lookups can use at most their share and never
+//! hold up a file search or directory listing the user is waiting on.
+//! `FORGE_PROXY_WORKERS` sets the sizes, e.g. `interactive=4,agent=2`.
+
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use crossbeam_channel::Sender;
+use lapce_rpc::proxy::RequestClass;
+
+const DEFAULT_INTERACTIVE: usize = 4;
+const DEFAULT_AGENT: usize = 2;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct Pool {
+    name: &'static str,
+    tx: Sender<Job>,
+    /// Jobs queued or running.
+    pending: Arc<AtomicUsize>,
+}
+
+impl Pool {
+    fn new(name: &'static str, workers: usize) -> Self {
+        let (tx, rx) = crossbeam_channel::unbounded::<Job>();
+        let pending = Arc::new(AtomicUsize::new(0));
+        for n in 0..workers.max(1) {
+            let rx = rx.clone();
+            let pending = pending.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("proxy-{name}-{n}"))
+                .spawn(move || {
+                    for job in rx {
+                        if let Err(e) = std::panic::catch_unwind(AssertUnwindSafe(job)) {
+                            tracing::error!("{name} worker job panicked: {e:?}");
+                        }
+                        pending.fetch_sub(1, Ordering::Relaxed);
+                    }
+                });
+            if let Err(e) = spawned {
+                tracing::error!("can't start {name} worker: {e}");
+            }
+        }
+        Self { name, tx, pending }
+    }
+
+    fn spawn(&self, job: Job) {
+        let queued = self.pending.fetch_add(1, Ordering::Relaxed);
+        if queued > 0 {
+            tracing::debug!("{} pool: {queued} jobs ahead", self.name);
+        }
+        if let Err(e) = self.tx.send(job) {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+            tracing::error!("{} pool is gone: {e}", self.name);
+        }
+    }
+}
+
+/// One pool per [`RequestClass`].
+pub struct WorkerPools {
+    interactive: Pool,
+    agent: Pool,
+}
+
+impl WorkerPools {
+    pub fn new() -> Self {
+        let (interactive, agent) = configured_sizes(
+            forge_agent::config::var("FORGE_PROXY_WORKERS").as_deref(),
+        );
+        Self {
+            interactive: Pool::new("interactive", interactive),
+            agent: Pool::new("agent", agent),
+        }
+    }
+
+    /// Run `job` on `class`'s pool once one of its workers is free.
+    pub fn spawn(&self, class: RequestClass, job: impl FnOnce() + Send + 'static) {
+        self.pool(class).spawn(Box::new(job));
+    }
+
+    /// Jobs of `class` queued or running.
+    pub fn pending(&self, class: RequestClass) -> usize {
+        self.pool(class).pending.load(Ordering::Relaxed)
+    }
+
+    fn pool(&self, class: RequestClass) -> &Pool {
+        match class {
+            RequestClass::Interactive => &self.interactive,
+            RequestClass::Agent => &self.agent,
+        }
+    }
+}
+
+impl Default for WorkerPools {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Workers per class from `FORGE_PROXY_WORKERS`-style `sizes`.
+fn configured_sizes(sizes: Option<&str>) -> (usize, usize) {
+    let (mut interactive, mut agent) = (DEFAULT_INTERACTIVE, DEFAULT_AGENT);
+    for (class, n) in sizes
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|s| s.split_once('='))
+    {
+        let Ok(n) = n.trim().parse::<usize>() else {
+            tracing::warn!("FORGE_PROXY_WORKERS: can't read '{n}' for {class}");
+            continue;
+        };
+        match class.trim() {
+            "interactive" => interactive = n.max(1),
+            "agent" => agent = n.max(1),
+            other => {
+                tracing::warn!("FORGE_PROXY_WORKERS: unknown class '{other}'")
+            }
+        }
+    }
+    (interactive, agent)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Barrier},
+        time::Duration,
+    };
+
+    use lapce_rpc::proxy::{ProxyRequest, ProxyRpc, RequestQueue};
+
+    use super::*;
+
+    #[test]
+    fn test_agent_work_does_not_block_interactive() {
+        let pools = WorkerPools {
+            interactive: Pool::new("interactive", 1),
+            agent: Pool::new("agent", 1),
+        };
+        // Hold the only agent worker, and queue more agent work behind it
+        let release = Arc::new(Barrier::new(2));
+        let held = release.clone();
+        pools.spawn(RequestClass::Agent, move || {
+            held.wait();
+        });
+        pools.spawn(RequestClass::Agent, || {});
+        assert_eq!(pools.pending(RequestClass::Agent), 2);
+
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        pools.spawn(RequestClass::Interactive, move || tx.send(()).unwrap());
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+        release.wait();
+
+        // Interactive messages are taken ahead of queued agent requests,
+        // and agent requests past the backlog are handed back
+        let mut queue = RequestQueue::new(1);
+        let push = |queue: &mut RequestQueue, id, request| {
+            queue.push(ProxyRpc::Request(id, request)).map_err(|(id, _)| id)
+        };
+        assert_eq!(push(&mut queue, 1, ProxyRequest::IndexWorkspace {}), Ok(()));
+        assert_eq!(push(&mut queue, 2, ProxyRequest::IndexStatus {}), Err(2));
+        assert_eq!(push(&mut queue, 3, ProxyRequest::GetOpenFilesContent {}), Ok(()));
+        assert!(matches!(queue.pop(), Some(ProxyRpc::Request(3, _))));
+        assert!(matches!(queue.pop(), Some(ProxyRpc::Request(1, _))));
+        assert!(queue.pop().is_none());
+
+        assert_eq!(configured_sizes(Some("agent=3, interactive=0,x=1")), (1, 3));
+        assert_eq!(configured_sizes(None), (DEFAULT_INTERACTIVE, DEFAULT_AGENT));
+    }
+}
@@ -0,0 +1,143 @@
+//! A [`Dispatcher`] on its own threads, driven the way the IDE drives it:
+//! requests and notifications go in through a [`ProxyRpcHandler`], and a
+//! stand-in for the core collects what comes back.
+//!
+//! Everything the proxy keeps under the home directory (settings, sessions,
+//! saved DB connections) lands in a temp home shared by the whole test
+//! binary, whose global config scripts the model with the mock provider
+//! (`tests/fixtures/agent.jsonl`) and keeps the agent off the network.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::Receiver;
+use lapce_proxy::dispatch::Dispatcher;
+use lapce_rpc::{
+    RpcError,
+    core::{CoreNotification, CoreRpc, CoreRpcHandler},
+    proxy::{ProxyRequest, ProxyResponse, ProxyRpcHandler},
+};
+use tempfile::TempDir;
+
+/// Long enough for a whole agent turn on a slow CI machine.
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Point the home directory at a temp dir, once per test binary.
+fn home() -> &'static Path {
+    static HOME: OnceLock<TempDir> = OnceLock::new();
+    HOME.get_or_init(|| {
+        let home = tempfile::tempdir().unwrap();
+        // SAFETY: set before any dispatcher (or its threads) exists, and
+        // only once
+        unsafe {
+            std::env::set_var("HOME", home.path());
+            std::env::set_var("XDG_CONFIG_HOME", home.path().join(".config"));
+            std::env::set_var("XDG_DATA_HOME", home.path().join(".local/share"));
+        }
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/agent.jsonl");
+        std::fs::create_dir_all(home.path().join(".forge")).unwrap();
+        std::fs::write(
+            home.path().join(".forge/config.toml"),
+            format!("provider = \"mock\"\nmock_fixture = {:?}\nlocal_only = true\n", fixture.display().to_string()),
+        )
+        .unwrap();
+        home
+    })
+    .path()
+}
+
+pub struct Proxy {
+    proxy_rpc: ProxyRpcHandler,
+    notifications: Receiver<CoreNotification>,
+    workspace: TempDir,
+}
+
+impl Proxy {
+    /// A dispatcher for a fresh, trusted workspace, initialized.
+    pub fn start() -> Self {
+        home();
+        let workspace = tempfile::tempdir().unwrap();
+        {
+            // The trust store is rewritten whole, one test at a time
+            static TRUST: Mutex<()> = Mutex::new(());
+            let _lock = TRUST.lock().unwrap();
+            forge_agent::trust::set_trusted(workspace.path(), true).unwrap();
+        }
+
+        let core_rpc = CoreRpcHandler::new();
+        let proxy_rpc = ProxyRpcHandler::new();
+        let (tx, notifications) = crossbeam_channel::unbounded();
+        {
+            let core_rpc = core_rpc.clone();
+            thread::spawn(move || {
+                for msg in core_rpc.rx() {
+                    match msg {
+                        CoreRpc::Notification(notification) => {
+                            let _ = tx.send(*notification);
+                        }
+                        // Nothing in the IDE to ask
+                        CoreRpc::Request(id, _) => core_rpc.handle_response(
+                            id,
+                            Err(RpcError { code: 0, message: "no core in tests".to_string() }),
+                        ),
+                        CoreRpc::Shutdown => return,
+                    }
+                }
+            });
+        }
+        {
+            // Never shut down: that ends the agent's processes and
+            // approvals for every dispatcher in the binary
+            let (core_rpc, proxy_rpc) = (core_rpc.clone(), proxy_rpc.clone());
+            thread::spawn(move || {
+                let mut dispatcher = Dispatcher::new(core_rpc, proxy_rpc.clone());
+                proxy_rpc.mainloop(&mut dispatcher);
+            });
+        }
+        proxy_rpc.initialize(Some(workspace.path().to_path_buf()), Vec::new(), Vec::new(), HashMap::new(), 1, 1);
+
+        let proxy = Self { proxy_rpc, notifications, workspace };
+        proxy.wait_for(|n| matches!(n, CoreNotification::ProxyStatus { .. }).then_some(()));
+        proxy
+    }
+
+    pub fn workspace(&self) -> &Path {
+        self.workspace.path()
+    }
+
+    /// Send `request` and wait for its response.
+    pub fn request(&self, request: ProxyRequest) -> Result<ProxyResponse, RpcError> {
+        self.send(request).recv_timeout(TIMEOUT).expect("no response")
+    }
+
+    /// Send `request`; its response arrives on the returned channel.
+    pub fn send(&self, request: ProxyRequest) -> Receiver<Result<ProxyResponse, RpcError>> {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.proxy_rpc.request_async(request, move |result| {
+            let _ = tx.send(result);
+        });
+        rx
+    }
+
+    /// Skip notifications until `f` picks one. Agent chat events are
+    /// unwrapped from their numbered envelope.
+    pub fn wait_for<T>(&self, mut f: impl FnMut(&CoreNotification) -> Option<T>) -> T {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let notification = self.notifications.recv_timeout(remaining).expect("notification never came");
+            let notification = match notification {
+                CoreNotification::AgentEvent { event, .. } => *event,
+                other => other,
+            };
+            if let Some(found) = f(&notification) {
+                return found;
+            }
+        }
+    }
+}
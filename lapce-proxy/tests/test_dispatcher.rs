@@ -0,0 +1,169 @@
+//! Black-box tests of the dispatcher: requests in, responses and core
+//! notifications out, with the model scripted by the mock provider.
+
+mod harness;
+
+use harness::{Proxy, TIMEOUT};
+use lapce_rpc::{
+    core::CoreNotification,
+    db::{DbConnectionConfig, DbType},
+    proxy::{AgentTaskState, ProxyRequest, ProxyResponse},
+};
+
+fn prompt(conversation_id: &str) -> ProxyRequest {
+    ProxyRequest::AgentPrompt {
+        prompt: "Write out.txt and remove old.txt".to_string(),
+        provider: "mock".to_string(),
+        model: "mock".to_string(),
+        api_key: String::new(),
+        conversation_id: conversation_id.to_string(),
+        attached_images: Vec::new(),
+        attachments: Vec::new(),
+        turn: 0,
+        rewind: false,
+    }
+}
+
+#[test]
+fn test_agent_prompt_approvals_and_diff_reject() {
+    let proxy = Proxy::start();
+    let workspace = proxy.workspace().to_path_buf();
+    std::fs::write(workspace.join("old.txt"), "keep me\n").unwrap();
+
+    let done = proxy.send(prompt("conv-e2e"));
+    // The write is auto-approved; deleting always asks, after the fact
+    let approval = proxy.wait_for(|n| match n {
+        CoreNotification::AgentApprovalQueued { approval } => Some(approval.clone()),
+        _ => None,
+    });
+    assert_eq!((approval.tool_call_id.as_str(), approval.kind.as_str()), ("call-2", "delete"));
+    assert_eq!(std::fs::read_to_string(workspace.join("out.txt")).unwrap(), "written\n");
+    assert!(!workspace.join("old.txt").exists());
+
+    let Ok(ProxyResponse::AgentTaskStatusResponse { status: Some(status) }) =
+        proxy.request(ProxyRequest::AgentTaskStatus { conversation_id: "conv-e2e".to_string() })
+    else {
+        panic!("no task status");
+    };
+    assert_eq!(status.state, AgentTaskState::Running);
+    assert_eq!(status.pending_approvals, vec!["call-2".to_string()]);
+
+    // Rejecting reverts the delete from its snapshot
+    proxy.request(ProxyRequest::AgentRejectToolCall { tool_call_id: "call-2".to_string() }).unwrap();
+    let changes = proxy.wait_for(|n| match n {
+        CoreNotification::AgentTurnChanges { files } => Some(files.clone()),
+        _ => None,
+    });
+    let Ok(ProxyResponse::AgentDone { message }) = done.recv_timeout(TIMEOUT).unwrap() else {
+        panic!("the turn didn't finish");
+    };
+    assert_eq!(message, "Finished.");
+    assert_eq!(std::fs::read_to_string(workspace.join("old.txt")).unwrap(), "keep me\n");
+
+    // The kept write is still reviewable, and rejecting it reverts it
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].path, "out.txt");
+    let diff_id = changes[0].diff_id.clone();
+    let reverted = proxy.request(ProxyRequest::AgentDiffReject { diff_id: diff_id.clone() });
+    assert!(matches!(reverted, Ok(ProxyResponse::AgentDiffRejectResponse { diff_id: id }) if id == diff_id));
+    assert_eq!(std::fs::read_to_string(workspace.join("out.txt")).unwrap(), "");
+
+    // A panel that reconnects gets the whole turn back, once the turn's
+    // thread has wound down
+    let (status, events) = loop {
+        let Ok(ProxyResponse::AgentResumeResponse { status: Some(status), events }) =
+            proxy.request(ProxyRequest::AgentResume { conversation_id: "conv-e2e".to_string(), last_seq: 0 })
+        else {
+            panic!("nothing to resume");
+        };
+        if status.state != AgentTaskState::Running {
+            break (status, events);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    };
+    assert_eq!(status.state, AgentTaskState::Done);
+    assert!(status.pending_approvals.is_empty());
+    assert_eq!(events.len() as u64, status.last_seq);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        CoreNotification::AgentEvent { event, .. } if matches!(**event, CoreNotification::AgentApprovalQueued { .. })
+    )));
+}
+
+#[test]
+fn test_run_config_requests() {
+    let proxy = Proxy::start();
+    std::fs::write(
+        proxy.workspace().join("Cargo.toml"),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    let config = serde_json::from_value(serde_json::json!({
+        "name": "serve",
+        "program": "cargo",
+        "args": ["run", "--", "serve"],
+    }))
+    .unwrap();
+
+    let saved = proxy.request(ProxyRequest::SaveRunConfig { config }).unwrap();
+    assert!(matches!(saved, ProxyResponse::RunConfigSaveResponse { success: true, .. }));
+    assert!(proxy.workspace().join(".lapce/run.toml").exists());
+
+    let Ok(ProxyResponse::RunConfigsResponse { detected, user }) = proxy.request(ProxyRequest::GetRunConfigs {}) else {
+        panic!("no run configs");
+    };
+    assert_eq!(user.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["serve"]);
+    assert!(detected.iter().any(|c| c.command == "cargo"));
+
+    let Ok(ProxyResponse::AgentListRunConfigsResponse { configs }) = proxy.request(ProxyRequest::AgentListRunConfigs {}) else {
+        panic!("no agent run configs");
+    };
+    assert_eq!(configs.len(), detected.len());
+
+    let deleted = proxy.request(ProxyRequest::DeleteRunConfig { name: "serve".to_string() }).unwrap();
+    assert!(matches!(deleted, ProxyResponse::RunConfigSaveResponse { success: true, .. }));
+    let Ok(ProxyResponse::RunConfigsResponse { user, .. }) = proxy.request(ProxyRequest::GetRunConfigs {}) else {
+        panic!("no run configs");
+    };
+    assert!(user.is_empty());
+}
+
+#[test]
+fn test_db_connection_requests() {
+    let proxy = Proxy::start();
+    // Nothing listens on port 1, so connecting fails fast
+    let config = DbConnectionConfig {
+        id: "db-1".to_string(),
+        name: "local".to_string(),
+        db_type: DbType::Postgres,
+        host: "127.0.0.1".to_string(),
+        port: 1,
+        user: "forge".to_string(),
+        password: "secret".to_string(),
+        database: "app".to_string(),
+        color: None,
+    };
+
+    let Ok(ProxyResponse::DbConnectionsListResponse { connections }) =
+        proxy.request(ProxyRequest::DbSaveConnection { config: config.clone() })
+    else {
+        panic!("not saved");
+    };
+    assert_eq!(connections.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), vec!["db-1"]);
+    let Ok(ProxyResponse::DbConnectionsListResponse { connections }) = proxy.request(ProxyRequest::DbListConnections {}) else {
+        panic!("no connections");
+    };
+    assert_eq!(connections.len(), 1);
+
+    let tested = proxy.request(ProxyRequest::DbTestConnection { config }).unwrap();
+    assert!(matches!(tested, ProxyResponse::DbTestConnectionResponse { success: false, .. }));
+    let connected = proxy.request(ProxyRequest::DbConnect { connection_id: "db-1".to_string() }).unwrap();
+    assert!(matches!(connected, ProxyResponse::DbConnectResponse { success: false, schema: None, .. }));
+
+    let Ok(ProxyResponse::DbConnectionsListResponse { connections }) =
+        proxy.request(ProxyRequest::DbDeleteConnection { id: "db-1".to_string() })
+    else {
+        panic!("not deleted");
+    };
+    assert!(connections.is_empty());
+}
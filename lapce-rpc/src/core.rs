@@ -25,6 +25,7 @@ use crate::{
     proxy::ProxyStatus,
     source_control::DiffInfo,
     terminal::TermId,
+    version::ProtocolVersion,
 };
 
 pub enum CoreRpc {
@@ -47,6 +48,10 @@ pub enum CoreNotification {
     ProxyStatus {
         status: ProxyStatus,
     },
+    /// The proxy's answer to the app's protocol version in `Initialize`.
+    ProxyProtocol {
+        protocol: ProtocolVersion,
+    },
     OpenFileChanged {
         path: PathBuf,
         content: FileChanged,
@@ -182,6 +187,15 @@ pub enum CoreNotification {
         /// Optional detail (e.g., plan items as JSON, search results count)
         detail: Option<String>,
     },
+    /// The agent asks the user a question (`ask_followup_question`) and
+    /// waits; answer with `AgentAnswerFollowup` via ProxyRequest. `options`
+    /// are suggested answers to show as buttons.
+    AgentFollowupQuestion {
+        tool_call_id: String,
+        question: String,
+        options: Vec<String>,
+        default: Option<String>,
+    },
     /// Agent's task plan - breakdown of steps it will execute.
     AgentPlan {
         /// List of plan steps
@@ -226,6 +240,29 @@ pub enum CoreNotification {
     },
     /// All pending diffs for the current agent turn have been sent.
     AgentDiffsDone {},
+    /// Every file the agent changed while answering one message, sent when
+    /// the turn ends, for the "Changes from AI" review panel.
+    AgentTurnChanges {
+        files: Vec<AgentFileEdit>,
+    },
+    /// A summary of the task the agent just completed (`attempt_completion`),
+    /// shown under its answer and exportable as a PR description.
+    AgentTaskSummary {
+        title: String,
+        /// Markdown: summary, files changed, commits, tests.
+        body: String,
+    },
+    /// Files changed outside the agent since the user's previous message.
+    /// Sent at the start of every agent turn; an empty list clears the badge.
+    AgentWorkspaceChanges {
+        files: Vec<String>,
+    },
+    /// An agent tool wrote a file that is open in the editor; apply the new
+    /// content to the buffer as an edit (the disk already has it).
+    AgentBufferEdit {
+        path: PathBuf,
+        content: String,
+    },
     
     // ── Agent Run Configuration ──────────────────────────
     /// Agent wants to run a project configuration - trigger terminal execution.
@@ -233,11 +270,63 @@ pub enum CoreNotification {
         config_name: Option<String>,
         command: Option<String>,
         mode: String,  // "run" or "debug"
+        /// Generated launch config for debug mode when the project has no
+        /// hand-written DAP config; takes precedence over `command`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        debug_config: Option<RunDebugConfig>,
     },
     /// Agent wants to stop a running project.
     AgentStopProject {
         config_name: Option<String>,
     },
+    /// Agent points the user at a file: open it (at a 1-based line and
+    /// column), select it in the explorer, or show its changes.
+    AgentOpenInEditor {
+        path: PathBuf,
+        line: Option<u32>,
+        column: Option<u32>,
+        action: AgentEditorAction,
+    },
+
+    /// Agent installs, removes, enables or disables a plugin (approved by
+    /// the user), done like the plugin panel would.
+    AgentManagePlugin {
+        action: AgentPluginAction,
+        volt: VoltInfo,
+    },
+
+    /// Status of the running agent request, re-sent about once a second
+    /// while it runs; `None` when it finished.
+    AgentProgress {
+        progress: Option<AgentRunProgress>,
+    },
+    /// A tool call is waiting for the user's approval.
+    AgentApprovalQueued {
+        approval: AgentPendingApproval,
+    },
+    /// A queued approval was answered, timed out or dropped.
+    AgentApprovalResolved {
+        tool_call_id: String,
+    },
+    /// The working notes of a conversation after the agent added one.
+    AgentWorkingNotes {
+        conversation_id: String,
+        notes: Vec<AgentNote>,
+    },
+    /// A diagram or code block the agent showed, for the canvas; `open`
+    /// shows the canvas (for large ones), otherwise it's only kept as the
+    /// latest one.
+    AgentShowCanvas {
+        canvas: AgentCanvas,
+        open: bool,
+    },
+    /// A chat event of a conversation's running turn, numbered so a panel
+    /// that reconnects can ask for the ones it missed (`AgentResume`).
+    AgentEvent {
+        conversation_id: String,
+        seq: u64,
+        event: Box<CoreNotification>,
+    },
 
     // ── AI Inline Completion (ghost text) ────────────────
     /// Response to an AI inline completion request.
@@ -261,6 +350,151 @@ pub enum CoreNotification {
         version: String,
         progress: f64,
     },
+    /// A notification this app doesn't know, from a newer proxy.
+    #[serde(other)]
+    Unknown,
+}
+
+impl CoreNotification {
+    /// Whether this shows in the chat panel and can be sent again on
+    /// resume. Editor actions (buffer edits, opening files, runs) and the
+    /// once-a-second progress aren't.
+    pub fn is_agent_chat_event(&self) -> bool {
+        use CoreNotification::*;
+        matches!(
+            self,
+            AgentTextChunk { .. }
+                | AgentToolCallUpdate { .. }
+                | AgentToolCallApprovalRequest { .. }
+                | AgentError { .. }
+                | AgentThinkingStep { .. }
+                | AgentFollowupQuestion { .. }
+                | AgentPlan { .. }
+                | AgentServerToolStart { .. }
+                | AgentServerToolEnd { .. }
+                | AgentDiffPreview { .. }
+                | AgentDiffsDone { .. }
+                | AgentTurnChanges { .. }
+                | AgentTaskSummary { .. }
+                | AgentWorkspaceChanges { .. }
+                | AgentApprovalQueued { .. }
+                | AgentApprovalResolved { .. }
+                | AgentWorkingNotes { .. }
+                | AgentShowCanvas { .. }
+        )
+    }
+}
+
+/// Numbers the chat events of one conversation's turn (see
+/// [`CoreRpcHandler::with_agent_stream`]) and hands each to `record` to be
+/// kept for resuming.
+pub struct AgentStream {
+    pub conversation_id: String,
+    next_seq: AtomicU64,
+    record: Box<dyn Fn(u64, &CoreNotification) + Send + Sync>,
+}
+
+impl AgentStream {
+    pub fn new(
+        conversation_id: String,
+        first_seq: u64,
+        record: impl Fn(u64, &CoreNotification) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            conversation_id,
+            next_seq: AtomicU64::new(first_seq),
+            record: Box::new(record),
+        }
+    }
+
+    fn wrap(&self, event: CoreNotification) -> CoreNotification {
+        let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+        (self.record)(seq, &event);
+        CoreNotification::AgentEvent {
+            conversation_id: self.conversation_id.clone(),
+            seq,
+            event: Box::new(event),
+        }
+    }
+}
+
+/// What [`CoreNotification::AgentOpenInEditor`] does with the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentEditorAction {
+    Open,
+    Reveal,
+    Diff,
+}
+
+/// What [`CoreNotification::AgentManagePlugin`] does with the volt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentPluginAction {
+    Install,
+    Remove,
+    Enable,
+    Disable,
+}
+
+/// A decision, assumption, open question or thought the agent noted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentNote {
+    /// Unix seconds.
+    pub at: i64,
+    /// "decision", "assumption", "question" or "thought".
+    pub kind: String,
+    pub text: String,
+}
+
+/// A diagram or code block shown by `show_diagram` / `show_code`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentCanvas {
+    pub kind: AgentCanvasKind,
+    pub title: Option<String>,
+    /// Mermaid source or code.
+    pub content: String,
+    /// The code's language; `None` for diagrams.
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentCanvasKind {
+    Diagram,
+    Code,
+}
+
+/// A tool call waiting in the approval queue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentPendingApproval {
+    pub tool_call_id: String,
+    pub tool_name: String,
+    /// "edit" (made, kept or reverted), "command" (runs once approved) or
+    /// "delete".
+    pub kind: String,
+    pub summary: String,
+    /// Unified diff of an edit, or the command to run.
+    pub preview: String,
+    /// Seconds until it times out; `None` waits until answered.
+    pub timeout_secs: Option<u64>,
+    /// Whether it's approved when it times out.
+    pub approve_on_timeout: bool,
+}
+
+/// Where a running agent request is, for the chat panel's status row and
+/// the status bar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentRunProgress {
+    /// Model call number within the request (1-based).
+    pub step: u32,
+    /// What it's doing, e.g. "Waiting for the model" or a tool summary.
+    pub activity: String,
+    /// Tool being run, if any.
+    pub tool: Option<String>,
+    pub elapsed_ms: u64,
+    /// Tokens used so far (estimated until the backend reports usage).
+    pub tokens: u64,
 }
 
 /// A single step in the agent's task plan.
@@ -283,6 +517,20 @@ pub enum AgentPlanStepStatus {
     Done,
 }
 
+/// One file's net change over an agent turn (the proxy side of
+/// `forge_agent::tools::FileEditMeta`, plus the id to accept/reject it by).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentFileEdit {
+    /// Id for `AgentDiffAccept` / `AgentDiffReject`.
+    pub diff_id: String,
+    /// Relative path within the workspace.
+    pub path: String,
+    /// Content before the turn's first edit (empty for new files).
+    pub old_content: String,
+    /// Content after the turn's last edit.
+    pub new_content: String,
+}
+
 /// A single diff hunk in an AI-proposed edit.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AiDiffHunk {
@@ -329,6 +577,8 @@ pub struct CoreRpcHandler {
     id: Arc<AtomicU64>,
     #[allow(clippy::type_complexity)]
     pending: Arc<Mutex<HashMap<u64, Sender<Result<CoreResponse, RpcError>>>>>,
+    /// Set on the handler an agent turn sends through.
+    agent_stream: Option<Arc<AgentStream>>,
 }
 
 impl CoreRpcHandler {
@@ -339,6 +589,15 @@ impl CoreRpcHandler {
             rx,
             id: Arc::new(AtomicU64::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            agent_stream: None,
+        }
+    }
+
+    /// A handler whose chat events go out numbered by `stream`.
+    pub fn with_agent_stream(&self, stream: Arc<AgentStream>) -> Self {
+        Self {
+            agent_stream: Some(stream),
+            ..self.clone()
         }
     }
 
@@ -403,6 +662,12 @@ impl CoreRpcHandler {
     }
 
     pub fn notification(&self, notification: CoreNotification) {
+        let notification = match &self.agent_stream {
+            Some(stream) if notification.is_agent_chat_event() => {
+                stream.wrap(notification)
+            }
+            _ => notification,
+        };
         if let Err(err) = self.tx.send(CoreRpc::Notification(Box::new(notification)))
         {
             tracing::error!("{:?}", err);
@@ -585,8 +850,59 @@ impl CoreRpcHandler {
         self.notification(CoreNotification::AgentDiffsDone {});
     }
 
+    pub fn agent_turn_changes(&self, files: Vec<AgentFileEdit>) {
+        self.notification(CoreNotification::AgentTurnChanges { files });
+    }
+
+    pub fn agent_task_summary(&self, title: String, body: String) {
+        self.notification(CoreNotification::AgentTaskSummary { title, body });
+    }
+
+    pub fn agent_buffer_edit(&self, path: PathBuf, content: String) {
+        self.notification(CoreNotification::AgentBufferEdit { path, content });
+    }
+
+    pub fn agent_open_in_editor(
+        &self,
+        path: PathBuf,
+        line: Option<u32>,
+        column: Option<u32>,
+        action: AgentEditorAction,
+    ) {
+        self.notification(CoreNotification::AgentOpenInEditor {
+            path,
+            line,
+            column,
+            action,
+        });
+    }
+
+    pub fn agent_manage_plugin(&self, action: AgentPluginAction, volt: VoltInfo) {
+        self.notification(CoreNotification::AgentManagePlugin { action, volt });
+    }
+
     // ── Agent Thinking/Streaming helpers ─────────────────────
 
+    pub fn agent_progress(&self, progress: Option<AgentRunProgress>) {
+        self.notification(CoreNotification::AgentProgress { progress });
+    }
+
+    pub fn agent_approval_queued(&self, approval: AgentPendingApproval) {
+        self.notification(CoreNotification::AgentApprovalQueued { approval });
+    }
+
+    pub fn agent_approval_resolved(&self, tool_call_id: String) {
+        self.notification(CoreNotification::AgentApprovalResolved { tool_call_id });
+    }
+
+    pub fn agent_working_notes(&self, conversation_id: String, notes: Vec<AgentNote>) {
+        self.notification(CoreNotification::AgentWorkingNotes { conversation_id, notes });
+    }
+
+    pub fn agent_show_canvas(&self, canvas: AgentCanvas, open: bool) {
+        self.notification(CoreNotification::AgentShowCanvas { canvas, open });
+    }
+
     pub fn agent_thinking_step(
         &self,
         step_type: String,
@@ -600,6 +916,21 @@ impl CoreRpcHandler {
         });
     }
 
+    pub fn agent_followup_question(
+        &self,
+        tool_call_id: String,
+        question: String,
+        options: Vec<String>,
+        default: Option<String>,
+    ) {
+        self.notification(CoreNotification::AgentFollowupQuestion {
+            tool_call_id,
+            question,
+            options,
+            default,
+        });
+    }
+
     pub fn agent_plan(&self, steps: Vec<AgentPlanStep>) {
         self.notification(CoreNotification::AgentPlan { steps });
     }
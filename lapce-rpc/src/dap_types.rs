@@ -6,7 +6,10 @@
  * Much of the code in this file is modified from [helix](https://github.com/helix-editor/helix)'s implementation of their syntax highlighting, which is under the MPL.
  */
 
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::Value;
@@ -56,7 +59,15 @@ pub struct RunDebugConfig {
     pub args: Option<Vec<String>>,
     pub cwd: Option<String>,
     pub env: Option<HashMap<String, String>>,
+    /// Path to a dotenv-style file (`KEY=value` per line), relative to the
+    /// working directory. Values in `env` take precedence over the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<String>,
     pub prelaunch: Option<RunDebugProgram>,
+    /// Names of other configs that must run to completion first, in order
+    /// (e.g. a build task). They run before `prelaunch`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<Vec<String>>,
     #[serde(skip)]
     #[ts(skip)]
     pub debug_command: Option<Vec<String>>,
@@ -69,6 +80,209 @@ pub struct RunDebugConfig {
     pub config_source: ConfigSource,
 }
 
+/// A named group of run configs launched together, each in its own
+/// terminal, and stopped as a unit (e.g. frontend + backend).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export, export_to = "../../webview-ui/src/types/proxy.ts")]
+pub struct CompoundRunConfig {
+    pub name: String,
+    /// Names of the member configs.
+    pub configs: Vec<String>,
+}
+
+impl RunDebugConfig {
+    /// Resolve `${...}` placeholders in the program, arguments, working
+    /// directory, environment and prelaunch step.
+    pub fn substitute_variables(&mut self, vars: &RunVariables) {
+        let sub = |s: &mut String| *s = vars.substitute(s);
+        sub(&mut self.program);
+        self.args.iter_mut().flatten().for_each(sub);
+        self.cwd.iter_mut().for_each(sub);
+        self.env_file.iter_mut().for_each(sub);
+        self.env.iter_mut().flat_map(|e| e.values_mut()).for_each(sub);
+        if let Some(prelaunch) = &mut self.prelaunch {
+            sub(&mut prelaunch.program);
+            prelaunch.args.iter_mut().flatten().for_each(sub);
+        }
+    }
+
+    /// The environment to launch with: variables from `env_file` (resolved
+    /// against `base_dir` when relative) overlaid with the explicit `env`.
+    ///
+    /// Returns `Ok(None)` when neither is set, so the inherited environment
+    /// is left untouched.
+    pub fn resolved_env(
+        &self,
+        base_dir: Option<&Path>,
+    ) -> Result<Option<HashMap<String, String>>, String> {
+        let Some(env_file) = self.env_file.as_deref().filter(|f| !f.is_empty())
+        else {
+            return Ok(self.env.clone());
+        };
+
+        let mut path = PathBuf::from(env_file);
+        if path.is_relative() {
+            if let Some(base_dir) = base_dir {
+                path = base_dir.join(path);
+            }
+        }
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            format!("Failed to read env file {}: {e}", path.display())
+        })?;
+
+        let mut env = parse_env_file(&content);
+        if let Some(overrides) = &self.env {
+            env.extend(overrides.clone());
+        }
+        Ok(Some(env))
+    }
+}
+
+/// A value referenced as `${input:<id>}` in run configs, declared under
+/// `[[inputs]]` in run.toml so machine-specific values stay out of the
+/// configs themselves.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export, export_to = "../../webview-ui/src/types/proxy.ts")]
+pub struct RunInput {
+    pub id: String,
+    pub description: Option<String>,
+    pub default: Option<String>,
+    /// Allowed values; the first one is used when there is no default.
+    pub options: Option<Vec<String>>,
+}
+
+impl RunInput {
+    pub fn value(&self) -> Option<String> {
+        self.default
+            .clone()
+            .or_else(|| self.options.as_ref()?.first().cloned())
+    }
+}
+
+/// Values for the `${...}` placeholders in run configs.
+///
+/// Placeholders whose value isn't known here are left untouched, so they can
+/// be resolved by a later stage (e.g. `${env:VAR}` by the proxy, which runs on
+/// the machine the process is launched on).
+#[derive(Debug, Clone, Default)]
+pub struct RunVariables {
+    pub workspace: Option<PathBuf>,
+    /// The file open in the active editor.
+    pub file: Option<PathBuf>,
+    pub inputs: HashMap<String, String>,
+    /// Resolve `${env:VAR}` from this process's environment.
+    pub env: bool,
+}
+
+impl RunVariables {
+    /// Only `${env:VAR}`, from this process's environment.
+    pub fn env_only() -> Self {
+        Self {
+            env: true,
+            ..Default::default()
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<String> {
+        let path_str = |p: &Path| p.to_string_lossy().to_string();
+        if let Some(var) = name.strip_prefix("env:") {
+            return self.env.then(|| std::env::var(var).unwrap_or_default());
+        }
+        if let Some(id) = name.strip_prefix("input:") {
+            return self.inputs.get(id).cloned();
+        }
+        let workspace = self.workspace.as_deref();
+        let file = self.file.as_deref();
+        match name {
+            "workspace" | "workspaceFolder" => workspace.map(path_str),
+            "workspaceFolderBasename" => {
+                workspace?.file_name().map(|n| n.to_string_lossy().to_string())
+            }
+            "file" => file.map(path_str),
+            "fileBasename" => {
+                file?.file_name().map(|n| n.to_string_lossy().to_string())
+            }
+            "fileBasenameNoExtension" => {
+                file?.file_stem().map(|n| n.to_string_lossy().to_string())
+            }
+            "fileExtname" => file?
+                .extension()
+                .map(|e| format!(".{}", e.to_string_lossy())),
+            "fileDirname" => file?.parent().map(path_str),
+            "relativeFile" => file
+                .map(|f| workspace.and_then(|w| f.strip_prefix(w).ok()).unwrap_or(f))
+                .map(path_str),
+            "pathSeparator" => Some(std::path::MAIN_SEPARATOR.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Replace the known `${...}` placeholders in `input`.
+    pub fn substitute(&self, input: &str) -> String {
+        let mut out = String::with_capacity(input.len());
+        let mut rest = input;
+        while let Some(start) = rest.find("${") {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let Some(end) = after.find('}') else {
+                out.push_str(&rest[start..]);
+                return out;
+            };
+            match self.lookup(&after[..end]) {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[start..start + 2 + end + 1]),
+            }
+            rest = &after[end + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Parse a dotenv-style file.
+///
+/// Supports `KEY=value`, `export KEY=value`, `#` comments, and single or
+/// double quoted values (`\n` is unescaped inside double quotes).
+pub fn parse_env_file(content: &str) -> HashMap<String, String> {
+    let mut env = HashMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            continue;
+        }
+        let value = value.trim();
+        let value = if let Some(v) =
+            value.strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+        {
+            v.replace("\\n", "\n").replace("\\\"", "\"")
+        } else if let Some(v) =
+            value.strip_prefix('\'').and_then(|v| v.strip_suffix('\''))
+        {
+            v.to_string()
+        } else {
+            // Unquoted values may carry a trailing comment
+            value
+                .split_once(" #")
+                .map(|(v, _)| v)
+                .unwrap_or(value)
+                .trim()
+                .to_string()
+        };
+        env.insert(key.to_string(), value);
+    }
+    env
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq, TS)]
 #[ts(export, export_to = "../../webview-ui/src/types/proxy.ts")]
 pub enum ConfigSource {
@@ -884,3 +1098,50 @@ impl Request for StepOut {
     type Result = ();
     const COMMAND: &'static str = "stepOut";
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{RunVariables, parse_env_file};
+
+    #[test]
+    fn test_substitute_variables() {
+        let mut vars = RunVariables {
+            workspace: Some(PathBuf::from("/home/me/app")),
+            file: Some(PathBuf::from("/home/me/app/src/main.rs")),
+            ..Default::default()
+        };
+        vars.inputs.insert("port".to_string(), "8080".to_string());
+
+        assert_eq!(
+            vars.substitute("${workspaceFolder}/target --port=${input:port}"),
+            "/home/me/app/target --port=8080"
+        );
+        assert_eq!(
+            vars.substitute("${relativeFile} ${fileBasenameNoExtension}${fileExtname}"),
+            "src/main.rs main.rs"
+        );
+        // Unknown values are left for a later stage
+        assert_eq!(
+            vars.substitute("${env:HOME} ${input:missing} ${lapce} ${oops"),
+            "${env:HOME} ${input:missing} ${lapce} ${oops"
+        );
+
+        let env = RunVariables::env_only();
+        assert_eq!(env.substitute("${env:LAPCE_TEST_UNSET_VAR}x"), "x");
+        assert_eq!(env.substitute("${workspace}"), "${workspace}");
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let env = parse_env_file(
+            "# database\nDATABASE_URL=postgres://localhost/dev\nexport PORT=3000 # web\nGREETING=\"hello\\nworld\"\nRAW='a # b'\nnot a pair\n",
+        );
+        assert_eq!(env["DATABASE_URL"], "postgres://localhost/dev");
+        assert_eq!(env["PORT"], "3000");
+        assert_eq!(env["GREETING"], "hello\nworld");
+        assert_eq!(env["RAW"], "a # b");
+        assert_eq!(env.len(), 4);
+    }
+}
@@ -89,6 +89,18 @@ pub struct DbTableInfo {
     pub row_count: Option<u64>,
     /// Type: "table", "view", "materialized_view", "collection"
     pub table_type: String,
+    /// The workspace migration that creates it, if one does
+    #[serde(default)]
+    pub source: Option<DbTableSource>,
+}
+
+/// Where a table is created in the workspace's SQL files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbTableSource {
+    /// Absolute path of the SQL file
+    pub path: std::path::PathBuf,
+    /// 1-based line of the CREATE statement
+    pub line: u32,
 }
 
 /// Information about a column in a table
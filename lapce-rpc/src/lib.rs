@@ -14,6 +14,7 @@ pub mod source_control;
 pub mod stdio;
 pub mod style;
 pub mod terminal;
+pub mod version;
 
 pub use parse::{Call, RequestId, RpcObject};
 use serde::{Deserialize, Serialize};
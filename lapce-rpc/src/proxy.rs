@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     path::PathBuf,
     sync::{
         Arc,
@@ -33,6 +33,7 @@ use crate::{
     source_control::FileDiff,
     style::SemanticStyles,
     terminal::{TermId, TerminalProfile},
+    version::ProtocolVersion,
 };
 
 /// Image data attached to a chat message (pasted screenshot, etc.).
@@ -44,6 +45,94 @@ pub struct AttachedImageData {
     pub mime_type: String,  // "image/png", "image/jpeg"
 }
 
+/// A non-image file attached to a chat message (log, CSV, PDF, spec, ...).
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../webview-ui/src/types/proxy.ts")]
+pub struct AttachedFileData {
+    pub filename: String,
+    pub data: String,       // base64-encoded
+    pub mime_type: String,
+}
+
+/// A workspace with a semantic index, as recorded on this machine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedWorkspace {
+    pub workspace_id: String,
+    pub path: Option<PathBuf>,
+    pub files_indexed: usize,
+    pub size_bytes: u64,
+    /// Unix seconds.
+    pub last_used: Option<u64>,
+}
+
+/// Token and cost totals of some model calls.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    /// Calls to models without a known price (not in `cost_usd`).
+    pub unpriced: u64,
+}
+
+/// One provider's usage over the summary's window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderUsage {
+    pub provider: String,
+    pub totals: UsageTotals,
+}
+
+/// One conversation's usage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationUsage {
+    pub conversation_id: String,
+    /// Its first question.
+    pub title: Option<String>,
+    /// Unix seconds.
+    pub last_used: i64,
+    pub totals: UsageTotals,
+}
+
+/// The usage dashboard's data.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub today: UsageTotals,
+    /// The conversation the summary was asked for.
+    pub session: UsageTotals,
+    /// Last 30 days, costliest first.
+    pub providers: Vec<ProviderUsage>,
+    /// Most recently used first.
+    pub conversations: Vec<ConversationUsage>,
+    pub daily_budget_usd: Option<f64>,
+    pub session_budget_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../webview-ui/src/types/proxy.ts")]
+pub enum AgentTaskState {
+    Running,
+    Done,
+    /// The proxy that ran it exited or crashed before it finished.
+    Interrupted,
+}
+
+/// The latest agent turn of a conversation.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../webview-ui/src/types/proxy.ts")]
+pub struct AgentTaskStatus {
+    pub conversation_id: String,
+    pub state: AgentTaskState,
+    /// The user's message, shortened.
+    pub prompt: String,
+    pub started_at: i64,
+    /// Sequence number of the turn's latest `AgentEvent`.
+    pub last_seq: u64,
+    /// Tool calls of the turn waiting for approval.
+    pub pending_approvals: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[serde(rename_all = "snake_case")]
 #[ts(export, export_to = "../../webview-ui/src/types/proxy.ts")]
@@ -76,6 +165,8 @@ pub struct SearchMatch {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "method", content = "params")]
 pub enum ProxyRequest {
     NewBuffer {
         buffer_id: BufferId,
@@ -319,6 +410,11 @@ pub enum ProxyRequest {
     GetDocumentSymbols {
         path: PathBuf,
     },
+    /// Document symbols from the tree-sitter definitions, for files no
+    /// language server gives them for. Answered with `GetDocumentSymbols`.
+    GetSyntaxSymbols {
+        path: PathBuf,
+    },
     GetWorkspaceSymbols {
         /// The search query
         query: String,
@@ -420,6 +516,7 @@ pub enum ProxyRequest {
         config_name: Option<String>,
     },
     GetRunConfigs {},
+    GetRunHistory {},
 
     // Database Manager
     DbListConnections {},
@@ -471,6 +568,19 @@ pub enum ProxyRequest {
         /// Images pasted/attached by the user (base64-encoded)
         #[serde(default)]
         attached_images: Vec<AttachedImageData>,
+        /// Other files dropped or pasted into the chat; their text goes
+        /// into the prompt.
+        #[serde(default)]
+        attachments: Vec<AttachedFileData>,
+        /// Index of this user message in the conversation (0-based); edits
+        /// made while answering it are checkpointed under this turn.
+        #[serde(default)]
+        turn: u32,
+        /// The conversation was rewound to `turn` (see
+        /// `AgentRestoreCheckpoint`): the server forgets everything from
+        /// that turn on before answering.
+        #[serde(default)]
+        rewind: bool,
     },
     /// Transcribe audio to text using Groq Whisper.
     AgentTranscribeAudio {
@@ -489,6 +599,22 @@ pub enum ProxyRequest {
     },
     /// Auto-approve all future tool calls this session (except dangerous ones like delete_file).
     AgentApproveAllFuture {},
+    /// The user's answer to an `AgentFollowupQuestion`.
+    AgentAnswerFollowup {
+        tool_call_id: String,
+        answer: String,
+    },
+    /// Restore the files the agent edited from `turn` on to their state
+    /// before that turn ("retry from here").
+    AgentRestoreCheckpoint {
+        conversation_id: String,
+        turn: u32,
+    },
+    /// The user's trust decision for the open workspace; `set` records a
+    /// new one first. Untrusted workspaces run the agent restricted.
+    AgentWorkspaceTrust {
+        set: Option<bool>,
+    },
 
     // ── AI Diff Accept/Reject ────────────────────────────
     /// Accept an AI-proposed diff (write the new content to disk).
@@ -527,6 +653,56 @@ pub enum ProxyRequest {
     /// Check if the workspace is indexed and get status.
     IndexStatus {},
 
+    /// Re-index one file or directory of the workspace. Answers with the
+    /// updated `IndexStatusResponse`.
+    IndexPath {
+        path: PathBuf,
+    },
+
+    /// Delete an index: the open workspace's, or the one with
+    /// `workspace_id` (see `IndexWorkspaces`). Answers with the (empty)
+    /// `IndexStatusResponse`.
+    IndexClear {
+        #[serde(default)]
+        workspace_id: Option<String>,
+    },
+
+    /// The workspaces indexed from this machine, most recently used first.
+    IndexWorkspaces {},
+
+    /// Token usage and cost from the usage ledger, with `conversation_id`
+    /// as the current session.
+    AgentUsageSummary {
+        #[serde(default)]
+        conversation_id: Option<String>,
+    },
+
+    /// The working notes saved in a conversation's session.
+    AgentWorkingNotes {
+        conversation_id: String,
+    },
+
+    /// Write the workspace's onboarding report ("explain this repository")
+    /// to `.forge/onboarding.md`.
+    AgentOnboardingReport {},
+
+    /// Restore the path the agent most recently deleted from the
+    /// workspace's `.forge/trash`.
+    AgentRestoreDeletedFile {},
+
+    /// Where a conversation's latest agent turn is: running, done, or
+    /// interrupted by a proxy restart.
+    AgentTaskStatus {
+        conversation_id: String,
+    },
+
+    /// After reconnecting: the conversation's chat events after `last_seq`
+    /// (the last `AgentEvent` the panel saw), and its task status.
+    AgentResume {
+        conversation_id: String,
+        last_seq: u64,
+    },
+
     // ── LSP Tools for AI Agent ────────────────────────────
     /// Get definition location for symbol at position.
     /// Used by AI agent to understand code structure.
@@ -569,6 +745,48 @@ pub enum ProxyRequest {
         position: Position,
         new_name: String,
     },
+    /// A request from a newer app that this proxy doesn't know; answered
+    /// with [`crate::version::UNSUPPORTED`].
+    #[serde(other)]
+    Unknown,
+}
+
+/// Which worker pool serves a request, so heavy agent work can't starve
+/// the editor's completion, hover and the like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestClass {
+    /// Sent by the user's editing; answered first.
+    Interactive,
+    /// Started by or for the AI agent: prompts, indexing, its LSP tools.
+    Agent,
+}
+
+impl ProxyRequest {
+    pub fn class(&self) -> RequestClass {
+        use ProxyRequest::*;
+        match self {
+            AgentPrompt { .. }
+            | AgentListRunConfigs { .. }
+            | AgentRunProject { .. }
+            | AgentStopProject { .. }
+            | AgentUsageSummary { .. }
+            | AgentWorkingNotes { .. }
+            | AgentOnboardingReport { .. }
+            | IndexWorkspace { .. }
+            | IndexStatus { .. }
+            | IndexPath { .. }
+            | IndexClear { .. }
+            | IndexWorkspaces { .. }
+            | LspGotoDefinition { .. }
+            | LspFindReferences { .. }
+            | LspHover { .. }
+            | LspGetDiagnostics { .. }
+            | LspPrepareRename { .. }
+            | LspRename { .. } => RequestClass::Agent,
+            // Approvals, cancel and diff review are the user's clicks
+            _ => RequestClass::Interactive,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -583,6 +801,9 @@ pub enum ProxyNotification {
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
         window_id: usize,
         tab_id: usize,
+        /// The app's; the proxy answers with `CoreNotification::ProxyProtocol`.
+        #[serde(default)]
+        protocol: ProtocolVersion,
     },
     OpenFileChanged {
         path: PathBuf,
@@ -660,6 +881,13 @@ pub enum ProxyNotification {
     TerminalClose {
         term_id: TermId,
     },
+    RecordRun {
+        entry: RunHistoryEntry,
+    },
+    RunExited {
+        name: String,
+        exit_code: Option<i32>,
+    },
     DapStart {
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
@@ -709,6 +937,9 @@ pub enum ProxyNotification {
         version: String,
         progress: f64,
     },
+    /// A notification from a newer app that this proxy doesn't know.
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -941,6 +1172,9 @@ pub enum ProxyResponse {
         success: bool,
         message: String,
     },
+    RunHistoryResponse {
+        entries: Vec<RunHistoryEntry>,
+    },
     
     // Agent Run Configuration Responses
     AgentListRunConfigsResponse {
@@ -1024,6 +1258,14 @@ pub enum ProxyResponse {
     AgentDiffRejectResponse {
         diff_id: String,
     },
+    AgentRestoreCheckpointResponse {
+        /// Workspace-relative paths that were restored.
+        restored: Vec<String>,
+    },
+    AgentWorkspaceTrustResponse {
+        /// `None` until the user decided.
+        trusted: Option<bool>,
+    },
 
     // ── AI Inline Completion ─────────────────────────────
     AiInlineCompletionResponse {
@@ -1038,6 +1280,52 @@ pub enum ProxyResponse {
     IndexStatusResponse {
         is_indexed: bool,
         symbol_count: i64,
+        /// Files indexed from this machine since the index was last cleared.
+        #[serde(default)]
+        files_indexed: usize,
+        /// Files waiting to be sent by a scan in progress.
+        #[serde(default)]
+        pending: usize,
+        /// Bytes of source uploaded.
+        #[serde(default)]
+        size_bytes: u64,
+        /// Unix seconds of the last finished scan.
+        #[serde(default)]
+        last_refresh: Option<u64>,
+        #[serde(default)]
+        last_error: Option<String>,
+    },
+
+    IndexWorkspacesResponse {
+        workspaces: Vec<IndexedWorkspace>,
+    },
+
+    AgentUsageSummaryResponse {
+        summary: UsageSummary,
+    },
+
+    AgentWorkingNotesResponse {
+        notes: Vec<crate::core::AgentNote>,
+    },
+
+    AgentOnboardingReportResponse {
+        path: PathBuf,
+    },
+
+    AgentRestoreDeletedFileResponse {
+        path: PathBuf,
+        is_dir: bool,
+    },
+
+    AgentTaskStatusResponse {
+        /// `None` if the conversation never ran a turn.
+        status: Option<AgentTaskStatus>,
+    },
+
+    AgentResumeResponse {
+        status: Option<AgentTaskStatus>,
+        /// `CoreNotification::AgentEvent`s, oldest first.
+        events: Vec<crate::core::CoreNotification>,
     },
 
     // ── LSP Tool Responses ────────────────────────────────
@@ -1080,6 +1368,9 @@ pub enum ProxyResponse {
         /// The workspace edit to apply, or None if rename failed.
         edit: Option<WorkspaceEdit>,
     },
+    /// A response this app doesn't know, from a newer proxy.
+    #[serde(other)]
+    Unknown,
 }
 
 /// Information about an installed proto tool
@@ -1091,6 +1382,19 @@ pub struct ProtoToolInfo {
     pub is_default: bool,
 }
 
+/// A launched run configuration, kept in the per-workspace run history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunHistoryEntry {
+    pub config: RunDebugConfig,
+    /// "run" or "debug"
+    pub mode: String,
+    /// Unix timestamp (seconds) of the launch
+    pub started_at: i64,
+    /// Exit code once the process has finished
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+}
+
 /// A run configuration detected from project files
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DetectedRunConfig {
@@ -1137,6 +1441,66 @@ impl ResponseHandler {
     }
 }
 
+/// Error code of a request refused because the agent backlog is full.
+pub const BUSY: i64 = -32001;
+
+const DEFAULT_AGENT_BACKLOG: usize = 64;
+
+/// How many agent requests may wait for the dispatcher
+/// (`FORGE_PROXY_AGENT_BACKLOG`, default 64).
+pub fn agent_backlog() -> usize {
+    std::env::var("FORGE_PROXY_AGENT_BACKLOG")
+        .ok()
+        .and_then(|n| n.trim().parse().ok())
+        .unwrap_or(DEFAULT_AGENT_BACKLOG)
+}
+
+fn panic_message(panic_info: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = panic_info.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = panic_info.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Messages waiting for the dispatcher: notifications and interactive
+/// requests in arrival order, then agent requests.
+pub struct RequestQueue {
+    interactive: VecDeque<ProxyRpc>,
+    agent: VecDeque<ProxyRpc>,
+    max_agent: usize,
+}
+
+impl RequestQueue {
+    pub fn new(max_agent: usize) -> Self {
+        Self { interactive: VecDeque::new(), agent: VecDeque::new(), max_agent }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.interactive.is_empty() && self.agent.is_empty()
+    }
+
+    /// Queue `msg`, or give back an agent request the backlog has no room for.
+    pub fn push(&mut self, msg: ProxyRpc) -> Result<(), (RequestId, ProxyRequest)> {
+        match msg {
+            ProxyRpc::Request(id, request) if request.class() == RequestClass::Agent => {
+                if self.agent.len() >= self.max_agent {
+                    return Err((id, request));
+                }
+                self.agent.push_back(ProxyRpc::Request(id, request));
+            }
+            msg => self.interactive.push_back(msg),
+        }
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<ProxyRpc> {
+        self.interactive.pop_front().or_else(|| self.agent.pop_front())
+    }
+}
+
 pub trait ProxyHandler {
     fn handle_notification(&mut self, rpc: ProxyNotification);
     fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest);
@@ -1174,57 +1538,79 @@ impl ProxyRpcHandler {
         &self.rx
     }
 
+    /// Handle messages until shutdown. Whatever is waiting is taken off the
+    /// channel first, so interactive requests and notifications go ahead of
+    /// queued agent requests; past [`agent_backlog`] queued agent requests,
+    /// new ones are refused with [`BUSY`] instead of piling up.
     pub fn mainloop<H>(&self, handler: &mut H)
     where
         H: ProxyHandler,
     {
-        use std::panic::AssertUnwindSafe;
-        use ProxyRpc::*;
         tracing::info!("[RPC] mainloop started, instance_id={}, channel_len={}", self.instance_id, self.rx.len());
+        let mut queue = RequestQueue::new(agent_backlog());
         let mut msg_count: u64 = 0;
-        for msg in &self.rx {
-            msg_count += 1;
-            match msg {
-                Request(id, request) => {
-                    let req_name = format!("{:?}", &request).chars().take(80).collect::<String>();
-                    tracing::info!("[RPC] mainloop msg#{}: Request id={}, type={}", msg_count, id, req_name);
-                    // Wrap in catch_unwind so a panic in one handler doesn't kill the mainloop
-                    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
-                        handler.handle_request(id, request);
-                    }));
-                    if let Err(panic_info) = result {
-                        let panic_msg = if let Some(s) = panic_info.downcast_ref::<&str>() {
-                            s.to_string()
-                        } else if let Some(s) = panic_info.downcast_ref::<String>() {
-                            s.clone()
-                        } else {
-                            "unknown panic".to_string()
-                        };
-                        tracing::error!("[RPC] PANIC in handle_request for id={}: {}", id, panic_msg);
-                    }
+        loop {
+            if queue.is_empty() {
+                match self.rx.recv() {
+                    Ok(msg) => self.enqueue(&mut queue, msg),
+                    Err(_) => break,
                 }
-                Notification(notification) => {
-                    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
-                        handler.handle_notification(notification);
-                    }));
-                    if let Err(panic_info) = result {
-                        let panic_msg = if let Some(s) = panic_info.downcast_ref::<&str>() {
-                            s.to_string()
-                        } else if let Some(s) = panic_info.downcast_ref::<String>() {
-                            s.clone()
-                        } else {
-                            "unknown panic".to_string()
-                        };
-                        tracing::error!("[RPC] PANIC in handle_notification: {}", panic_msg);
-                    }
+            }
+            while let Ok(msg) = self.rx.try_recv() {
+                self.enqueue(&mut queue, msg);
+            }
+            let Some(msg) = queue.pop() else {
+                continue;
+            };
+            msg_count += 1;
+            if !Self::dispatch(handler, msg, msg_count) {
+                tracing::info!("[RPC] mainloop received Shutdown signal after {} msgs", msg_count);
+                return;
+            }
+        }
+        tracing::warn!("[RPC] mainloop channel closed after {} msgs", msg_count);
+    }
+
+    fn enqueue(&self, queue: &mut RequestQueue, msg: ProxyRpc) {
+        if let Err((id, request)) = queue.push(msg) {
+            tracing::warn!("[RPC] agent backlog full ({}), refusing {:?}", queue.agent.len(), request.class());
+            self.handle_response(
+                id,
+                Err(RpcError {
+                    code: BUSY,
+                    message: format!("The proxy is busy with {} queued agent requests; try again shortly.", queue.agent.len()),
+                }),
+            );
+        }
+    }
+
+    /// Handle one message; false on shutdown.
+    fn dispatch<H: ProxyHandler>(handler: &mut H, msg: ProxyRpc, msg_count: u64) -> bool {
+        use std::panic::AssertUnwindSafe;
+        use ProxyRpc::*;
+        match msg {
+            Request(id, request) => {
+                let req_name = format!("{:?}", &request).chars().take(80).collect::<String>();
+                tracing::info!("[RPC] mainloop msg#{}: Request id={}, type={}", msg_count, id, req_name);
+                // Wrap in catch_unwind so a panic in one handler doesn't kill the mainloop
+                let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    handler.handle_request(id, request);
+                }));
+                if let Err(panic_info) = result {
+                    tracing::error!("[RPC] PANIC in handle_request for id={}: {}", id, panic_message(&*panic_info));
                 }
-                Shutdown => {
-                    tracing::info!("[RPC] mainloop received Shutdown signal after {} msgs", msg_count);
-                    return;
+            }
+            Notification(notification) => {
+                let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    handler.handle_notification(notification);
+                }));
+                if let Err(panic_info) = result {
+                    tracing::error!("[RPC] PANIC in handle_notification: {}", panic_message(&*panic_info));
                 }
             }
+            Shutdown => return false,
         }
-        tracing::warn!("[RPC] mainloop channel closed after {} msgs", msg_count);
+        true
     }
 
     fn request_common(&self, request: ProxyRequest, rh: ResponseHandler) {
@@ -1355,6 +1741,7 @@ impl ProxyRpcHandler {
             plugin_configurations,
             window_id,
             tab_id,
+            protocol: ProtocolVersion::CURRENT,
         });
     }
 
@@ -1730,6 +2117,14 @@ impl ProxyRpcHandler {
         self.request_async(ProxyRequest::GetDocumentSymbols { path }, f);
     }
 
+    pub fn get_syntax_symbols(
+        &self,
+        path: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetSyntaxSymbols { path }, f);
+    }
+
     pub fn get_workspace_symbols(
         &self,
         query: String,
@@ -2315,6 +2710,18 @@ impl ProxyRpcHandler {
     pub fn delete_run_config(&self, name: String, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::DeleteRunConfig { name }, f);
     }
+    
+    pub fn get_run_history(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GetRunHistory {}, f);
+    }
+    
+    pub fn record_run(&self, entry: RunHistoryEntry) {
+        self.notification(ProxyNotification::RecordRun { entry });
+    }
+    
+    pub fn run_exited(&self, name: String, exit_code: Option<i32>) {
+        self.notification(ProxyNotification::RunExited { name, exit_code });
+    }
 
     // Database Manager methods
 
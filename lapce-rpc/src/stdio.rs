@@ -5,7 +5,7 @@ use std::{
 
 use anyhow::Result;
 use crossbeam_channel::{Receiver, Sender};
-use serde::{Serialize, de::DeserializeOwned};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Value, json};
 
 use crate::{RpcError, RpcMessage, RpcObject};
@@ -118,7 +118,7 @@ where
             .map_err(|_| io::ErrorKind::NotFound)?;
         match resp {
             Ok(value) => {
-                let resp: Resp = serde_json::from_value(value)?;
+                let resp: Resp = from_value_or_unknown(&value)?;
                 RpcMessage::Response(id, resp)
             }
             Err(value) => {
@@ -129,14 +129,27 @@ where
     } else {
         match object.get_id() {
             Some(id) => {
-                let req: Req = serde_json::from_value(object.0)?;
+                let req: Req = from_value_or_unknown(&object.0)?;
                 RpcMessage::Request(id, req)
             }
             None => {
-                let notif: Notif = serde_json::from_value(object.0)?;
+                let notif: Notif = from_value_or_unknown(&object.0)?;
                 RpcMessage::Notification(notif)
             }
         }
     };
     Ok(msg)
 }
+
+/// `value` as `T`, or as its `#[serde(other)]` variant if the method is one
+/// this build doesn't know (see [`crate::version`]). Unknown params can't be
+/// read, so they're left out of the second try.
+fn from_value_or_unknown<T: DeserializeOwned>(value: &Value) -> serde_json::Result<T> {
+    T::deserialize(value).or_else(|e| {
+        let Some(method) = value.get("method") else {
+            return Err(e);
+        };
+        // A known method with unreadable params still fails, with its error
+        T::deserialize(&json!({ "method": method })).map_err(|_| e)
+    })
+}
@@ -23,6 +23,10 @@ pub struct TerminalProfile {
     pub arguments: Option<Vec<String>>,
     pub workdir: Option<url::Url>,
     pub environment: Option<HashMap<String, String>>,
+    /// Name of the run config this terminal runs, if any. The proxy keeps
+    /// the output of these so the agent can read it back.
+    #[serde(default)]
+    pub run_config: Option<String>,
 }
 
 impl TerminalProfile {}
@@ -0,0 +1,73 @@
+//! Version of the protocol between the app and the proxy.
+//!
+//! A remote proxy can be older or newer than the app it talks to. The app
+//! sends its [`ProtocolVersion`] in `Initialize` and the proxy answers with
+//! its own (`CoreNotification::ProxyProtocol`), so each side knows what to
+//! expect. Messages a side doesn't know come out as the `Unknown` variant of
+//! their enum instead of failing to parse, a request the proxy doesn't know
+//! is answered with [`UNSUPPORTED`], and fields added to existing messages
+//! are `#[serde(default)]`, so peers within each other's range keep working
+//! on everything they have in common.
+
+use serde::{Deserialize, Serialize};
+
+/// Bump when a message changes in a way an older peer can't read. Adding
+/// messages, or fields with a default, doesn't need a bump.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The oldest peer this build still works with.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// JSON-RPC "method not found": the peer doesn't know the request.
+pub const UNSUPPORTED: i64 = -32601;
+
+/// A peer's protocol version and the oldest one it works with. Peers from
+/// before the handshake send none, which reads as version 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ProtocolVersion {
+    pub version: u32,
+    pub min_compatible: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    Same,
+    /// The peer is older; it ignores what it doesn't know.
+    Older,
+    /// The peer is newer; we ignore what we don't know.
+    Newer,
+    Incompatible,
+}
+
+impl ProtocolVersion {
+    /// This build's.
+    pub const CURRENT: Self = Self {
+        version: PROTOCOL_VERSION,
+        min_compatible: MIN_PROTOCOL_VERSION,
+    };
+
+    /// How well this build can talk to a peer speaking `self`.
+    pub fn compatibility(&self) -> Compatibility {
+        let current = Self::CURRENT;
+        if self.version == current.version {
+            Compatibility::Same
+        } else if self.version < current.min_compatible
+            || current.version < self.min_compatible
+        {
+            Compatibility::Incompatible
+        } else if self.version < current.version {
+            Compatibility::Older
+        } else {
+            Compatibility::Newer
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.version {
+            0 => write!(f, "an unversioned protocol"),
+            version => write!(f, "protocol v{version}"),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Wire compatibility between app and proxy builds of different protocol
+//! versions, from recorded messages in `tests/fixtures/protocol`:
+//! `v1_*` is what this version sends and must read back unchanged, `v0_*`
+//! is from before the handshake and `v2_*` from a newer peer, with methods
+//! and fields this build doesn't know.
+
+use std::io::BufReader;
+
+use lapce_rpc::{
+    RpcMessage,
+    core::{CoreNotification, CoreRequest, CoreResponse},
+    proxy::{ProxyNotification, ProxyRequest, ProxyResponse},
+    stdio::{read_msg, write_msg},
+    version::{Compatibility, ProtocolVersion},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+/// What the proxy reads.
+type FromApp = RpcMessage<ProxyRequest, ProxyNotification, CoreResponse>;
+/// What the app reads.
+type FromProxy = RpcMessage<CoreRequest, CoreNotification, ProxyResponse>;
+
+fn fixture(name: &str) -> Vec<String> {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/protocol").join(name);
+    std::fs::read_to_string(path).unwrap().lines().map(String::from).collect()
+}
+
+fn read<Req, Notif, Resp>(line: &str) -> RpcMessage<Req, Notif, Resp>
+where
+    Req: DeserializeOwned,
+    Notif: DeserializeOwned,
+    Resp: DeserializeOwned,
+{
+    let bytes = format!("{line}\n");
+    let mut reader = BufReader::new(bytes.as_bytes());
+    read_msg(&mut reader).unwrap().unwrap_or_else(|| panic!("can't read {line}"))
+}
+
+/// `line` read and written again, as JSON.
+fn round_trip<Req, Notif, Resp>(line: &str) -> Value
+where
+    Req: Serialize + DeserializeOwned,
+    Notif: Serialize + DeserializeOwned,
+    Resp: Serialize + DeserializeOwned,
+{
+    let mut out = Vec::new();
+    write_msg(&mut out, read::<Req, Notif, Resp>(line)).unwrap();
+    serde_json::from_slice(&out).unwrap()
+}
+
+#[test]
+fn test_current_messages_keep_their_shape() {
+    for line in fixture("v1_app.jsonl") {
+        let expected: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(round_trip::<ProxyRequest, ProxyNotification, CoreResponse>(&line), expected);
+    }
+    for line in fixture("v1_proxy.jsonl") {
+        let expected: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(round_trip::<CoreRequest, CoreNotification, ProxyResponse>(&line), expected);
+    }
+
+    let FromApp::Notification(ProxyNotification::Initialize { protocol, .. }) = read(&fixture("v1_app.jsonl")[0]) else {
+        panic!("not an initialize");
+    };
+    assert_eq!(protocol, ProtocolVersion::CURRENT);
+}
+
+#[test]
+fn test_older_and_newer_peers() {
+    // An app from before the handshake says nothing, and can't be served
+    let FromApp::Notification(ProxyNotification::Initialize { protocol, .. }) = read(&fixture("v0_app.jsonl")[0]) else {
+        panic!("not an initialize");
+    };
+    assert_eq!(protocol.version, 0);
+    assert_eq!(protocol.compatibility(), Compatibility::Incompatible);
+
+    // A newer app: extra fields are ignored, unknown methods are Unknown
+    let app: Vec<FromApp> = fixture("v2_app.jsonl").iter().map(|line| read(line)).collect();
+    assert!(matches!(
+        &app[0],
+        RpcMessage::Notification(ProxyNotification::Initialize { protocol, .. }) if protocol.compatibility() == Compatibility::Newer
+    ));
+    assert!(matches!(&app[1], RpcMessage::Request(1, ProxyRequest::AgentTaskStatus { conversation_id }) if conversation_id == "conv-1"));
+    assert!(matches!(app[2], RpcMessage::Request(2, ProxyRequest::Unknown)));
+    assert!(matches!(app[3], RpcMessage::Notification(ProxyNotification::Unknown)));
+
+    // A proxy too new for this app
+    let proxy: Vec<FromProxy> = fixture("v2_proxy.jsonl").iter().map(|line| read(line)).collect();
+    assert!(matches!(
+        &proxy[0],
+        RpcMessage::Notification(CoreNotification::ProxyProtocol { protocol }) if protocol.compatibility() == Compatibility::Incompatible
+    ));
+    assert!(matches!(&proxy[1], RpcMessage::Notification(CoreNotification::AgentApprovalResolved { tool_call_id }) if tool_call_id == "call-2"));
+    assert!(matches!(proxy[2], RpcMessage::Notification(CoreNotification::Unknown)));
+    assert!(matches!(proxy[3], RpcMessage::Response(2, ProxyResponse::Unknown)));
+
+    // Params that don't fit a known method are still an error
+    let mut reader = BufReader::new(&b"{\"method\":\"agent_task_status\",\"params\":{\"conversation_id\":7},\"id\":3}\n"[..]);
+    assert!(read_msg::<_, ProxyRequest, ProxyNotification, CoreResponse>(&mut reader).unwrap().is_none());
+}